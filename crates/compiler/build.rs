@@ -0,0 +1,49 @@
+//! Hashes the sources of the compiler and of the crates whose types it
+//! caches on disk into `LUMO_COMPILER_HASH`, so a cache entry written by one
+//! build of the compiler is never read back by another.
+
+use std::path::{Path, PathBuf};
+
+const SOURCES: &[&str] = &[
+    "src",
+    "../span/src",
+    "../types/src",
+    "../lexer/src",
+    "../lst/src",
+    "../hir/src",
+    "../lir/src",
+];
+
+fn main() {
+    let mut files = Vec::new();
+    for dir in SOURCES {
+        println!("cargo:rerun-if-changed={dir}");
+        collect(Path::new(dir), &mut files);
+    }
+    files.sort();
+
+    let mut state = 0xcbf29ce484222325_u64;
+    for file in &files {
+        let name = file.to_string_lossy();
+        let contents = std::fs::read(file).unwrap_or_default();
+        for b in name.as_bytes().iter().chain([0].iter()).chain(&contents) {
+            state ^= *b as u64;
+            state = state.wrapping_mul(0x100000001b3);
+        }
+    }
+    println!("cargo:rustc-env=LUMO_COMPILER_HASH={state:016x}");
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
@@ -3,6 +3,8 @@ use crate::lir;
 pub mod rs;
 pub mod ts;
 
+pub use simple_ts_ast::OptLevel;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BackendKind {
     TypeScript,
@@ -25,6 +27,19 @@ pub enum BackendError {
     EmitFailed(String),
 }
 
+/// Settings for the TypeScript / JavaScript backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TsOptions {
+    /// Level of the CPS optimization passes run after lowering.
+    pub opt_level: OptLevel,
+    /// Keep runtime checks the typechecker makes redundant, such as the
+    /// `__lumo_match_error` fallback of an exhaustive match.
+    pub debug_assertions: bool,
+    /// Drop indentation and blank lines, putting each top-level statement
+    /// on one line. Names are left alone.
+    pub minify: bool,
+}
+
 pub trait Backend: Send + Sync {
     fn kind(&self) -> BackendKind;
     fn supports(&self, target: CodegenTarget) -> bool;
@@ -45,6 +60,25 @@ impl BackendRegistry {
         }
     }
 
+    /// Default backends, with the TypeScript backend running the CPS
+    /// optimization passes at `opt_level`.
+    pub fn with_opt_level(opt_level: OptLevel) -> Self {
+        Self::with_ts_options(TsOptions {
+            opt_level,
+            ..TsOptions::default()
+        })
+    }
+
+    /// Default backends, with the TypeScript backend configured by `options`.
+    pub fn with_ts_options(options: TsOptions) -> Self {
+        Self {
+            backends: vec![
+                Box::new(ts::TypeScriptBackend::with_options(options)),
+                Box::new(rs::RustBackend::new()),
+            ],
+        }
+    }
+
     pub fn register<B>(&mut self, backend: B)
     where
         B: Backend + 'static,
@@ -71,3 +105,19 @@ impl Default for BackendRegistry {
 pub fn emit(file: &lir::File, target: CodegenTarget) -> Result<String, BackendError> {
     BackendRegistry::with_defaults().emit(file, target)
 }
+
+pub fn emit_with_opt_level(
+    file: &lir::File,
+    target: CodegenTarget,
+    opt_level: OptLevel,
+) -> Result<String, BackendError> {
+    BackendRegistry::with_opt_level(opt_level).emit(file, target)
+}
+
+pub fn emit_with_ts_options(
+    file: &lir::File,
+    target: CodegenTarget,
+    options: TsOptions,
+) -> Result<String, BackendError> {
+    BackendRegistry::with_ts_options(options).emit(file, target)
+}
@@ -22,6 +22,8 @@ impl Backend for RustBackend {
         matches!(target, CodegenTarget::Rust)
     }
 
+    /// Expects `file` lowered for [`BackendKind::Rust`]: performs resolved
+    /// by LTO and generics monomorphized (see `QueryEngine::set_backend`).
     fn emit(&self, file: &lir::File, _target: CodegenTarget) -> Result<String, BackendError> {
         emit_file(file)
     }
@@ -82,7 +84,7 @@ fn emit_file(file: &lir::File) -> Result<String, BackendError> {
         }
     }
     for ext in deduped_extern_types.values() {
-        if let Some(s) = emit_extern_type(ext) {
+        if let Some(s) = emit_extern_type(ext)? {
             out.push_str(&s);
             out.push('\n');
         }
@@ -143,6 +145,8 @@ struct LoweringContext {
     data_types: std::collections::HashMap<String, Vec<(String, usize)>>,
     /// Known extern function names
     extern_fns: std::collections::HashSet<String>,
+    /// Known user function names
+    user_fns: std::collections::HashSet<String>,
     /// Maps data type name -> set of (variant_index, field_index) for recursive fields
     recursive_fields: std::collections::HashMap<String, std::collections::HashSet<(usize, usize)>>,
 }
@@ -151,6 +155,7 @@ impl LoweringContext {
     fn from_file(file: &lir::File) -> Self {
         let mut data_types = std::collections::HashMap::new();
         let mut extern_fns = std::collections::HashSet::new();
+        let mut user_fns = std::collections::HashSet::new();
         let mut recursive_fields = std::collections::HashMap::new();
         for item in &file.items {
             match item {
@@ -169,12 +174,16 @@ impl LoweringContext {
                 lir::Item::ExternFn(func) => {
                     extern_fns.insert(func.name.clone());
                 }
+                lir::Item::Fn(func) => {
+                    user_fns.insert(func.name.clone());
+                }
                 _ => {}
             }
         }
         Self {
             data_types,
             extern_fns,
+            user_fns,
             recursive_fields,
         }
     }
@@ -184,27 +193,55 @@ impl LoweringContext {
 // Extern types
 // ---------------------------------------------------------------------------
 
-fn emit_extern_type(ext: &lir::ExternTypeDecl) -> Option<String> {
+fn emit_extern_type(ext: &lir::ExternTypeDecl) -> Result<Option<String>, BackendError> {
     let Some(extern_name) = ext.extern_name.as_deref() else {
-        return None; // bare extern type (no annotation) — skip, platform file provides it
+        return Ok(None); // bare extern type (no annotation) — skip, platform file provides it
     };
     match extern_name {
-        "string" | "number" => None, // built-in, no alias needed
-        _ => Some(format!(
-            "type {} = {};\n",
-            ext.name,
-            rust_type_for_extern(extern_name)
-        )),
+        "string" | "number" => Ok(None), // built-in, no alias needed
+        _ => {
+            let Some(rust_ty) = rust_type_for_extern(extern_name, &ext.generics) else {
+                return Err(BackendError::EmitFailed(format!(
+                    "extern type `{}` names `{extern_name}`, which has no Rust type",
+                    ext.name
+                )));
+            };
+            Ok(Some(format!(
+                "type {}{} = {rust_ty};\n",
+                ext.name,
+                rust_generics(&ext.generics, ""),
+            )))
+        }
     }
 }
 
-fn rust_type_for_extern(extern_name: &str) -> &str {
-    match extern_name {
-        "string" => "String",
-        "number" => "f64",
-        "bool" => "bool",
-        _ => "() /* unknown extern type */",
+fn rust_type_for_extern(extern_name: &str, generics: &[String]) -> Option<String> {
+    let ty = match (extern_name, generics) {
+        ("string", _) => "String".to_owned(),
+        ("number", _) => "f64".to_owned(),
+        ("bigint", _) => "i128".to_owned(),
+        ("bool", _) => "bool".to_owned(),
+        // Shared by reference, like the JS array and Map they stand in for.
+        ("Vec", [a]) => format!("std::rc::Rc<std::cell::RefCell<Vec<{a}>>>"),
+        ("HashMap", [k, v]) => format!(
+            "std::rc::Rc<std::cell::RefCell<std::collections::HashMap<i128, (Array<{k}>, Array<{v}>)>>>"
+        ),
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// `<A, B>` with `bound` on each param, or nothing for a non-generic item.
+fn rust_generics(generics: &[String], bound: &str) -> String {
+    if generics.is_empty() {
+        return String::new();
     }
+    let params = generics
+        .iter()
+        .map(|g| format!("{g}{bound}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("<{params}>")
 }
 
 // ---------------------------------------------------------------------------
@@ -233,7 +270,8 @@ fn type_expr_to_rust(ty: &TypeExpr) -> String {
     match ty {
         TypeExpr::Named(name) => match name.as_str() {
             "String" => "String".to_string(),
-            "Number" => "f64".to_string(),
+            "Number" | "Float" => "f64".to_string(),
+            "Int" => "i128".to_string(),
             "Unit" => "()".to_string(),
             _ => name.clone(),
         },
@@ -242,6 +280,7 @@ fn type_expr_to_rust(ty: &TypeExpr) -> String {
             format!("{}<{}>", head, args_str.join(", "))
         }
         TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => type_expr_to_rust(inner),
+        TypeExpr::Comp { ty, .. } => type_expr_to_rust(ty),
         TypeExpr::Cap { name, .. } => name.clone(),
         TypeExpr::Fn { params, ret, .. } => {
             let ps = params.iter().map(type_expr_to_rust).collect::<Vec<_>>().join(", ");
@@ -300,9 +339,36 @@ fn emit_data_decl(data: &lir::DataDecl) -> String {
     }
 
     out.push_str("}\n");
+
+    // `Step` (in any specialization) drives native loops; see `emit_loop`.
+    if is_step_decl(data) {
+        let generics = if data.generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", data.generics.join(", "))
+        };
+        let state = type_expr_to_rust(&data.variants[0].payload[0].value);
+        out.push_str(&format!(
+            "impl{} {}{} {{\n    fn into_next(self) -> Option<{}> {{\n        match self {{\n            Self::Next(s) => Some(s),\n            Self::Done => None,\n        }}\n    }}\n}}\n",
+            rust_generics(&data.generics, ": Clone + std::fmt::Debug"),
+            data.name,
+            generics,
+            state,
+        ));
+    }
     out
 }
 
+/// Whether a data type has the shape of libcore's `Step[S]`: `.next(S)`
+/// followed by `.done`.
+fn is_step_decl(data: &lir::DataDecl) -> bool {
+    matches!(
+        data.variants.as_slice(),
+        [next, done] if next.name == "next" && next.payload.len() == 1
+            && done.name == "done" && done.payload.is_empty()
+    )
+}
+
 // ---------------------------------------------------------------------------
 // Extern functions
 // ---------------------------------------------------------------------------
@@ -355,14 +421,17 @@ fn emit_extern_fn(func: &lir::ExternFnDecl) -> String {
             }
         }
 
-        // String operations
-        "globalThis.String.prototype.length" => format!("({}.len() as f64)", p(0)),
-        "globalThis.String.prototype.charAt()" => format!(
-            "{}.chars().nth({} as usize).map(|c| c.to_string()).unwrap_or_default()",
+        // String operations. Indices and lengths count UTF-16 code units,
+        // as JS strings do, so both backends agree on them.
+        "globalThis.String.prototype.length" | "str.len" => {
+            format!("({}.encode_utf16().count() as f64)", p(0))
+        }
+        "globalThis.String.prototype.charAt()" | "str.char_at" => format!(
+            "String::from_utf16_lossy(&{}.encode_utf16().skip({} as usize).take(1).collect::<Vec<u16>>())",
             p(0), p(1)
         ),
-        "globalThis.String.prototype.slice()" => format!(
-            "{}.chars().skip({} as usize).take(({} as usize) - ({} as usize)).collect::<String>()",
+        "globalThis.String.prototype.slice()" | "str.slice" => format!(
+            "String::from_utf16_lossy(&{}.encode_utf16().skip({} as usize).take(({} as usize).saturating_sub({} as usize)).collect::<Vec<u16>>())",
             p(0), p(1), p(2), p(1)
         ),
         "globalThis.String.prototype.startsWith()" => format!(
@@ -373,13 +442,13 @@ fn emit_extern_fn(func: &lir::ExternFnDecl) -> String {
             "if {}.contains({}.as_str()) {{ Bool::True }} else {{ Bool::False }}",
             p(0), p(1)
         ),
-        "globalThis.String.prototype.indexOf()" => format!(
-            "{}.find({}.as_str()).map(|i| i as f64).unwrap_or(-1.0)",
+        "globalThis.String.prototype.indexOf()" | "str.index_of" => format!(
+            "{0}.find({1}.as_str()).map(|i| {0}[..i].encode_utf16().count() as f64).unwrap_or(-1.0)",
             p(0), p(1)
         ),
         "globalThis.String.prototype.trim()" => format!("{}.trim().to_string()", p(0)),
-        "globalThis.String.prototype.charCodeAt()" => format!(
-            "{}.chars().nth({} as usize).map(|c| c as u32 as f64).unwrap_or(-1.0)",
+        "globalThis.String.prototype.charCodeAt()" | "str.char_code_at" => format!(
+            "{}.encode_utf16().nth({} as usize).map(|c| c as f64).unwrap_or(f64::NAN)",
             p(0), p(1)
         ),
         "globalThis.String.fromCharCode()" => format!(
@@ -392,6 +461,12 @@ fn emit_extern_fn(func: &lir::ExternFnDecl) -> String {
         ),
         "globalThis.Number.prototype.toString()" => format!("{}.to_string()", p(0)),
         "Math.floor()" => format!("{}.floor()", p(0)),
+        "Math.trunc()" => format!("{}.trunc()", p(0)),
+
+        // Int and Float conversions
+        "globalThis.String()" => format!("{}.to_string()", p(0)),
+        "globalThis.Number()" => format!("({} as f64)", p(0)),
+        "globalThis.BigInt()" => format!("({} as i128)", p(0)),
 
         // File I/O (Node fs imports)
         "__node_fs.readFileSync()" => format!(
@@ -411,10 +486,53 @@ fn emit_extern_fn(func: &lir::ExternFnDecl) -> String {
         "globalThis.process.argv.length" => "std::env::args().count() as f64".to_string(),
         "globalThis.process.exit()" => format!("std::process::exit({} as i32)", p(0)),
 
+        // Array and Map (`Rc<RefCell<Vec>>`, `Rc<RefCell<HashMap>>`)
+        "vec.new" | "hash_map.new" => {
+            "std::rc::Rc::new(std::cell::RefCell::new(Default::default()))".to_string()
+        }
+        "vec.len" => format!("({}.borrow().len() as f64)", p(0)),
+        // The JS side's `Object.hasOwn`: only whole numbers below the length.
+        "vec.has" => format!(
+            "if {i} >= 0.0 && {i}.fract() == 0.0 && ({i} as usize) < {}.borrow().len() {{ Bool::True }} else {{ Bool::False }}",
+            p(0),
+            i = p(1)
+        ),
+        "vec.at" => format!("{}.borrow()[{} as usize].clone()", p(0), p(1)),
+        "vec.set" => format!("{}.borrow_mut()[{} as usize] = {};", p(0), p(1), p(2)),
+        "vec.push" => format!("{}.borrow_mut().push({});", p(0), p(1)),
+        "vec.pop" => format!("{}.borrow_mut().pop().expect(\"pop from an empty Array\")", p(0)),
+        "hash_map.keys_at" | "hash_map.values_at" => format!(
+            "{}.borrow().get(&{}).map(|b| b.{}.clone()).unwrap_or_default()",
+            p(0),
+            p(1),
+            if extern_name == "hash_map.keys_at" { 0 } else { 1 }
+        ),
+        "hash_map.set_bucket" => format!(
+            "{}.borrow_mut().insert({}, ({}, {}));",
+            p(0), p(1), p(2), p(3)
+        ),
+        "hash_map.hashes" => format!(
+            "std::rc::Rc::new(std::cell::RefCell::new({}.borrow().keys().copied().collect()))",
+            p(0)
+        ),
+
         _ => format!("todo!(\"extern: {}\")", extern_name),
     };
 
-    format!("fn {}({}) -> {} {{\n    {}\n}}\n", func.name, params, ret, body)
+    let generics = func
+        .generics
+        .iter()
+        .filter(|g| !g.is_cap_row())
+        .map(|g| g.name().to_owned())
+        .collect::<Vec<_>>();
+    format!(
+        "fn {}{}({}) -> {} {{\n    {}\n}}\n",
+        func.name,
+        rust_generics(&generics, ": Clone + std::fmt::Debug"),
+        params,
+        ret,
+        body
+    )
 }
 
 // ---------------------------------------------------------------------------
@@ -582,11 +700,14 @@ fn emit_expr(expr: &lir::Expr, ctx: &LoweringContext) -> String {
         lir::Expr::String { value, .. } => format!("\"{}\".to_string()", escape_str(value)),
 
         lir::Expr::Number { value, .. } => {
-            // Ensure it's a valid Rust float literal
-            if value.contains('.') {
-                format!("{}f64", value)
+            let digits = value.trim_end_matches(['i', 'f']);
+            if lumo_lexer::NumberKind::of(value) == lumo_lexer::NumberKind::Int {
+                format!("{}i128", digits)
+            } else if digits.contains('.') {
+                // Ensure it's a valid Rust float literal
+                format!("{}f64", digits)
             } else {
-                format!("{}.0f64", value)
+                format!("{}.0f64", digits)
             }
         }
 
@@ -600,7 +721,7 @@ fn emit_expr(expr: &lir::Expr, ctx: &LoweringContext) -> String {
         lir::Expr::Force { expr, .. } => {
             // If forcing a known function name, just reference it
             if let lir::Expr::Ident { name, .. } = expr.as_ref() {
-                if ctx.extern_fns.contains(name) {
+                if ctx.extern_fns.contains(name) || ctx.user_fns.contains(name) {
                     return name.clone();
                 }
             }
@@ -635,6 +756,10 @@ fn emit_expr(expr: &lir::Expr, ctx: &LoweringContext) -> String {
             scrutinee, arms, ..
         } => emit_match(scrutinee, arms, ctx),
 
+        lir::Expr::Loop {
+            var, init, body, ..
+        } => emit_loop(var, init, body, ctx),
+
         lir::Expr::Ctor {
             name, args, called, ..
         } => emit_ctor(name, args, *called, ctx),
@@ -660,6 +785,20 @@ fn emit_expr(expr: &lir::Expr, ctx: &LoweringContext) -> String {
     }
 }
 
+/// A loop steps its body until it yields `.done`:
+///
+/// ```text
+/// { let mut state = init; while let Some(s) = { let var = state; body }.into_next() { state = s; } }
+/// ```
+fn emit_loop(var: &str, init: &lir::Expr, body: &lir::Expr, ctx: &LoweringContext) -> String {
+    let init_str = emit_expr(init, ctx);
+    let body_str = emit_expr(body, ctx);
+    format!(
+        "{{ let mut __state = {}; while let Some(__next) = {{ let {} = __state; {} }}.into_next() {{ __state = __next; }} }}",
+        init_str, var, body_str
+    )
+}
+
 /// Collect a chain of Apply nodes into (root_callee, [arg1, arg2, ...])
 fn collect_apply_chain(expr: &lir::Expr) -> (&lir::Expr, Vec<&lir::Expr>) {
     let mut args = Vec::new();
@@ -685,13 +824,44 @@ fn emit_match(
     let mut out = format!("match {} {{\n", scrut);
     for arm in arms {
         let pat = emit_pattern(&arm.pattern, ctx);
-        let body = emit_expr(&arm.body, ctx);
+        let mut body = emit_expr(&arm.body, ctx);
+        // Recursive payloads are boxed; unbox their bindings so the arm body
+        // sees the declared type.
+        for name in boxed_bindings(&arm.pattern, ctx).iter().rev() {
+            body = format!("{{ let {name} = *{name}; {body} }}");
+        }
         out.push_str(&format!("        {} => {},\n", pat, body));
     }
     out.push_str("    }");
     out
 }
 
+/// Names bound directly to a boxed (recursive) field by a qualified
+/// `Type.variant(...)` pattern.
+fn boxed_bindings(pattern: &Pattern, ctx: &LoweringContext) -> Vec<String> {
+    let Pattern::Ctor { name, args } = pattern else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    let boxed = name.split_once('.').and_then(|(type_name, variant_name)| {
+        let vi = ctx
+            .data_types
+            .get(type_name)?
+            .iter()
+            .position(|(vn, _)| vn == variant_name)?;
+        Some((ctx.recursive_fields.get(type_name)?, vi))
+    });
+    for (fi, arg) in args.iter().enumerate() {
+        match arg {
+            Pattern::Bind(n) if boxed.is_some_and(|(rec, vi)| rec.contains(&(vi, fi))) => {
+                out.push(n.clone());
+            }
+            _ => out.extend(boxed_bindings(arg, ctx)),
+        }
+    }
+    out
+}
+
 fn emit_pattern(pattern: &Pattern, _ctx: &LoweringContext) -> String {
     match pattern {
         Pattern::Wildcard => "_".to_string(),
@@ -790,9 +960,18 @@ fn to_pascal_case(name: &str) -> String {
 }
 
 fn escape_str(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
 }
@@ -1,14 +1,19 @@
 use crate::{
-    backend::{Backend, BackendError, BackendKind, CodegenTarget},
+    backend::{Backend, BackendError, BackendKind, CodegenTarget, TsOptions},
     lir::{self, AsRawValue},
-    types::{CapEntry, CapRef, Pattern, TypeExpr, cap_ref_is_effectful, cap_ref_mangled_params},
+    types::{
+        CapEntry, CapRef, Pattern, TypeExpr, cap_ref_is_effectful, cap_ref_mangled_params,
+        mangle_type_repr,
+    },
 };
 use simple_ts_ast as tsast;
 use std::cell::Cell;
 use std::collections::HashMap;
 
 #[derive(Debug, Default)]
-pub struct TypeScriptBackend;
+pub struct TypeScriptBackend {
+    options: TsOptions,
+}
 
 struct LoweringContext {
     direct_callable_arities: HashMap<String, usize>,
@@ -35,8 +40,13 @@ struct LoweringContext {
     /// carry the owner type).
     ctor_as_raw: HashMap<(String, String), AsRawValue>,
     variant_as_raw: HashMap<String, AsRawValue>,
+    /// Test the last arm of an exhaustive match too, so a value the
+    /// typechecker missed reaches `__lumo_match_error` instead of that arm.
+    debug_assertions: bool,
     match_counter: Cell<usize>,
     k_counter: Cell<usize>,
+    cps_value_counter: Cell<usize>,
+    loop_counter: Cell<usize>,
 }
 
 impl LoweringContext {
@@ -51,11 +61,34 @@ impl LoweringContext {
         self.k_counter.set(n + 1);
         format!("__k_{n}")
     }
+
+    fn next_cps_value_name(&self) -> String {
+        let n = self.cps_value_counter.get();
+        self.cps_value_counter.set(n + 1);
+        format!("__cps_v_{n}")
+    }
+
+    fn next_loop_name(&self) -> String {
+        let n = self.loop_counter.get();
+        self.loop_counter.set(n + 1);
+        format!("__loop_{n}")
+    }
 }
 
 impl TypeScriptBackend {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_opt_level(opt_level: tsast::OptLevel) -> Self {
+        Self::with_options(TsOptions {
+            opt_level,
+            ..TsOptions::default()
+        })
+    }
+
+    pub fn with_options(options: TsOptions) -> Self {
+        Self { options }
     }
 
     fn lower_file(&self, file: &lir::File) -> Result<tsast::Program, BackendError> {
@@ -81,8 +114,11 @@ impl TypeScriptBackend {
             cap_names,
             ctor_as_raw,
             variant_as_raw,
+            debug_assertions: self.options.debug_assertions,
             match_counter: Cell::new(0),
             k_counter: Cell::new(0),
+            cps_value_counter: Cell::new(0),
+            loop_counter: Cell::new(0),
         };
 
         // Deduplicate extern types: prefer annotated over bare
@@ -105,7 +141,7 @@ impl TypeScriptBackend {
             body.push(tsast::Stmt::TypeAlias(tsast::TypeAlias {
                 export: true,
                 name: ext.name.clone(),
-                type_params: Vec::new(),
+                type_params: ext.generics.clone(),
                 ty: ts_type_from_extern_name(ext),
             }));
         }
@@ -196,6 +232,8 @@ impl TypeScriptBackend {
         tsast::inline_literal_consts(&mut program);
         tsast::simplify_bool_comparisons(&mut program);
         tsast::inline_trivial_consts(&mut program);
+        tsast::optimize_cps(&mut program, self.options.opt_level);
+        tsast::loop_self_tail_calls(&mut program);
         validate_program_has_no_any_or_unknown(&program)?;
         Ok(program)
     }
@@ -340,7 +378,7 @@ fn collect_expr_required_caps(
             }
             collect_expr_required_caps(expr, fn_caps, out);
         }
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             collect_expr_required_caps(value, fn_caps, out);
             collect_expr_required_caps(body, fn_caps, out);
         }
@@ -460,7 +498,8 @@ fn collect_fn_caps(file: &lir::File) -> HashMap<String, Vec<String>> {
 }
 
 /// Build the runtime JS variable name for a cap with optional type args.
-/// e.g. ("Add", ["Number"]) → "__cap_Add_Number", ("IO", []) → "__cap_IO"
+/// e.g. ("Add", ["Number"]) → "__cap_Add_Number", ("IO", []) → "__cap_IO",
+/// ("Eq", ["List[Number]"]) → "__cap_Eq_List_Number"
 ///
 /// Used as the key in `handled_caps` tracking. For code *emission*, use
 /// `cap_bundle_access_expr` — caps are accessed through the `__caps` bundle
@@ -469,10 +508,18 @@ fn cap_runtime_name(cap: &str, type_args: &[String]) -> String {
     if type_args.is_empty() {
         format!("__cap_{cap}")
     } else {
-        format!("__cap_{}_{}", cap, type_args.join("_"))
+        format!("__cap_{}_{}", cap, mangle_type_args(type_args))
     }
 }
 
+fn mangle_type_args(type_args: &[String]) -> String {
+    type_args
+        .iter()
+        .map(|t| mangle_type_repr(t))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
 /// Bundle property key for a cap: e.g. ("Add", ["Number"]) → "Add_Number".
 /// Caps are grouped into a single `__caps` record per effectful function;
 /// this helper computes the property name within that record.
@@ -480,7 +527,7 @@ fn cap_bundle_key(cap: &str, type_args: &[String]) -> String {
     if type_args.is_empty() {
         cap.to_owned()
     } else {
-        format!("{}_{}", cap, type_args.join("_"))
+        format!("{}_{}", cap, mangle_type_args(type_args))
     }
 }
 
@@ -489,10 +536,21 @@ fn cap_bundle_key_from_runtime(runtime_name: &str) -> &str {
     runtime_name.strip_prefix("__cap_").unwrap_or(runtime_name)
 }
 
-/// Parse a mangled runtime cap name back into `(cap_name, type_args)`.
-/// Assumes cap/type names contain no underscores — all underscore-separated
-/// segments after `__cap_` are split with the first being the cap.
-fn cap_runtime_to_pair(runtime_name: &str) -> Option<(String, Vec<String>)> {
+/// Recover `(cap_name, type_args)` from a mangled runtime cap name. A
+/// default impl whose key mangles to it wins, since mangling flattens
+/// `List[Number]` to `List_Number`; otherwise this assumes cap/type names
+/// contain no underscores — all underscore-separated segments after
+/// `__cap_` are split with the first being the cap.
+fn cap_runtime_to_pair(
+    runtime_name: &str,
+    default_impls: &HashMap<(String, Vec<String>), String>,
+) -> Option<(String, Vec<String>)> {
+    if let Some(pair) = default_impls
+        .keys()
+        .find(|(cap, type_args)| cap_runtime_name(cap, type_args) == runtime_name)
+    {
+        return Some(pair.clone());
+    }
     let rest = runtime_name.strip_prefix("__cap_")?;
     let mut parts = rest.split('_');
     let cap = parts.next()?.to_owned();
@@ -659,6 +717,7 @@ fn type_refs_self(ty: &TypeExpr) -> bool {
         TypeExpr::Named(n) => n == "Self",
         TypeExpr::App { head, args } => head == "Self" || args.iter().any(type_refs_self),
         TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => type_refs_self(inner),
+        TypeExpr::Comp { ty, .. } => type_refs_self(ty),
         TypeExpr::Cap { type_args, .. } => type_args.iter().any(type_refs_self),
         TypeExpr::Fn { params, ret, .. } => {
             params.iter().any(type_refs_self) || type_refs_self(ret)
@@ -804,13 +863,19 @@ fn lower_fn_decl(
             .as_ref()
             .map(|s| &s.value)
             .unwrap_or(&unit_ty);
+        let mut body = lower_expr(lowered_body, ctx);
+        // A `handle` in a pure fn extends the caller's `__caps`, but a pure
+        // fn has none; start it from an empty bundle.
+        if tsast::expr_references_name(&body, CAPS_PARAM) {
+            body = iife(CAPS_PARAM, body, tsast::Expr::Object(Vec::new()));
+        }
         Ok(tsast::FunctionDecl {
             export: true,
             name: func.name.clone(),
             type_params: func.generics.iter().filter(|g| !g.is_cap_row()).map(|g| g.name().to_owned()).collect(),
             params: user_params,
             return_type: Some(lower_type_expr_to_ts_type(return_ty)),
-            body: tsast::FunctionBody::Expr(Box::new(lower_expr(lowered_body, ctx))),
+            body: tsast::FunctionBody::Expr(Box::new(body)),
             inline_always: func.inline,
         })
     }
@@ -889,7 +954,7 @@ fn emit_main_entry_wrapper(
                 continue;
             }
             for runtime in method_caps {
-                if let Some((cap_n, cap_a)) = cap_runtime_to_pair(runtime) {
+                if let Some((cap_n, cap_a)) = cap_runtime_to_pair(runtime, &ctx.default_impls) {
                     if !required.iter().any(|p| p == &(cap_n.clone(), cap_a.clone())) {
                         required.push((cap_n.clone(), cap_a.clone()));
                         pending.push((cap_n, cap_a));
@@ -983,6 +1048,7 @@ fn lower_cap_impl_const(
             &method.params,
             lowered_body,
             handled_caps,
+            CAPS_PARAM,
             ctx,
         ));
     }
@@ -1133,10 +1199,23 @@ impl Backend for TypeScriptBackend {
             _ => return Err(BackendError::UnsupportedTarget(target)),
         };
 
-        let emitted = tsast::Emitter::default().emit_program(&program, target);
+        let emitter = if self.options.minify {
+            tsast::Emitter::compact()
+        } else {
+            tsast::Emitter::default()
+        };
+        let emitted = emitter.emit_program(&program, target);
         let imports = format_imports(file, target);
         let prelude = runtime_prelude(target, &emitted);
-        Ok(format!("{prelude}{imports}{emitted}"))
+        let out = format!("{prelude}{imports}{emitted}");
+        if !self.options.minify {
+            return Ok(out);
+        }
+        Ok(out
+            .lines()
+            .filter(|line| !line.is_empty())
+            .flat_map(|line| [line, "\n"])
+            .collect())
     }
 }
 
@@ -1195,6 +1274,7 @@ fn runtime_prelude(target: tsast::EmitTarget, emitted: &str) -> String {
     let needs_match_error = emitted.contains("__lumo_match_error(");
     let needs_error = emitted.contains("__lumo_error(");
     let needs_identity = emitted.contains("__identity");
+    let needs_loop = emitted.contains("__lumo_loop(");
     let ts = matches!(target, tsast::EmitTarget::TypeScript);
 
     let mut out = String::new();
@@ -1216,6 +1296,13 @@ fn runtime_prelude(target: tsast::EmitTarget, emitted: &str) -> String {
             out.push_str("const __lumo_error = () => { throw new Error(\"lumo runtime error\"); };\n");
         }
     }
+    if needs_loop {
+        if ts {
+            out.push_str("const __lumo_loop = <S>(state: S, body: (state: S) => __LumoRuntime): void => { for (;;) { const step = body(state) as { [LUMO_TAG]: string; args?: __LumoRuntime[] }; if (step[LUMO_TAG] === \"done\") return; state = step.args![0] as S; } };\n");
+        } else {
+            out.push_str("const __lumo_loop = (state, body) => { for (;;) { const step = body(state); if (step[LUMO_TAG] === \"done\") return; state = step.args[0]; } };\n");
+        }
+    }
     if needs_trampoline {
         if ts {
             // Note: __thunk and __trampoline are declared *after* the CPS
@@ -1283,6 +1370,9 @@ fn lower_type_expr_to_ts_type(ty: &TypeExpr) -> tsast::TsType {
             tsast::TsType::TypeRef("__CpsValue".to_owned())
         }
         TypeExpr::Named(name) => tsast::TsType::TypeRef(name.clone()),
+        TypeExpr::App { head, .. } if head == "Bundle" => {
+            tsast::TsType::TypeRef(type_expr_to_ts_text(ty))
+        }
         TypeExpr::App { head, args } => tsast::TsType::TypeRef(format!(
             "{head}<{}>",
             args.iter()
@@ -1294,6 +1384,7 @@ fn lower_type_expr_to_ts_type(ty: &TypeExpr) -> tsast::TsType {
         TypeExpr::Produce(inner) => lower_type_expr_to_ts_type(inner),
         // `thunk T` → `() => T` in TS
         TypeExpr::Thunk(inner) => lower_type_expr_to_ts_type(inner),
+        TypeExpr::Comp { ty, .. } => lower_type_expr_to_ts_type(ty),
         TypeExpr::Cap { name, .. } => tsast::TsType::TypeRef(name.clone()),
         TypeExpr::Fn { params, ret, .. } => {
             let ps = params.iter().map(type_expr_to_ts_text).collect::<Vec<_>>().join(", ");
@@ -1393,6 +1484,7 @@ fn is_js_ident(text: &str) -> bool {
 /// - If path ends with `()`, it's a function call; otherwise a value/property access.
 /// - If path contains `.prototype.`, the first param is the receiver (`this`) and
 ///   the rest are passed as args. Otherwise the path is used directly.
+/// - A `new ` prefix (`new globalThis.Map()`) constructs the class instead of calling it.
 /// - If the return type is `Bool`, the call result is auto-wrapped in the Lumo Bool ADT.
 fn extern_body_expr(
     extern_path: &str,
@@ -1404,6 +1496,10 @@ fn extern_body_expr(
         return maybe_bool_wrap(expr, return_type, bool_is_native);
     }
 
+    let (extern_path, is_new) = match extern_path.strip_prefix("new ") {
+        Some(rest) => (rest, true),
+        None => (extern_path, false),
+    };
     let (base, is_call) = match extern_path.strip_suffix("()") {
         Some(base) => (base, true),
         None => (extern_path, false),
@@ -1440,6 +1536,14 @@ fn extern_body_expr(
             path_expr
         }
     };
+    let expr = if is_new {
+        tsast::Expr::Unary {
+            op: tsast::UnaryOp::New,
+            expr: Box::new(expr),
+        }
+    } else {
+        expr
+    };
 
     maybe_bool_wrap(expr, return_type, bool_is_native)
 }
@@ -1563,6 +1667,7 @@ fn map_binary_operator(op: &str) -> Option<tsast::BinaryOp> {
         "-" => Some(tsast::BinaryOp::Sub),
         "*" => Some(tsast::BinaryOp::Mul),
         "/" => Some(tsast::BinaryOp::Div),
+        "%" => Some(tsast::BinaryOp::Mod),
         "^" | "**" => Some(tsast::BinaryOp::Exp),
         "==" | "===" => Some(tsast::BinaryOp::EqEqEq),
         "!=" | "!==" => Some(tsast::BinaryOp::NotEqEq),
@@ -1691,6 +1796,10 @@ fn type_expr_to_ts_text(ty: &TypeExpr) -> String {
         TypeExpr::Named(name) if name == "Unit" => "void".to_owned(),
         TypeExpr::Named(name) if name == "<missing>" => "__CpsValue".to_owned(),
         TypeExpr::Named(name) => name.clone(),
+        // A `Bundle[Cap]` value is the handler factory `handle` installs.
+        TypeExpr::App { head, .. } if head == "Bundle" => {
+            "((__k_handle: __Kont) => __Caps[string])".to_owned()
+        }
         TypeExpr::App { head, args } => format!(
             "{head}<{}>",
             args.iter()
@@ -1700,6 +1809,7 @@ fn type_expr_to_ts_text(ty: &TypeExpr) -> String {
         ),
         TypeExpr::Produce(inner) => type_expr_to_ts_text(inner),
         TypeExpr::Thunk(inner) => type_expr_to_ts_text(inner),
+        TypeExpr::Comp { ty, .. } => type_expr_to_ts_text(ty),
         TypeExpr::Cap { name, .. } => name.clone(),
         TypeExpr::Fn { params, ret, .. } => {
             let ps = params.iter().map(type_expr_to_ts_text).collect::<Vec<_>>().join(", ");
@@ -1754,6 +1864,14 @@ fn validate_stmt_has_no_any_or_unknown(stmt: &tsast::Stmt) -> Result<(), Backend
             }
             Ok(())
         }
+        tsast::Stmt::While { cond, body } => {
+            validate_expr_has_no_any_or_unknown(cond)?;
+            for stmt in &body.stmts {
+                validate_stmt_has_no_any_or_unknown(stmt)?;
+            }
+            Ok(())
+        }
+        tsast::Stmt::Continue => Ok(()),
         tsast::Stmt::Function(decl) => {
             for param in &decl.params {
                 if let Some(ty) = &param.type_ann {
@@ -1867,6 +1985,7 @@ fn validate_expr_has_no_any_or_unknown(expr: &tsast::Expr) -> Result<(), Backend
         tsast::Expr::Ident(_)
         | tsast::Expr::String(_)
         | tsast::Expr::Number(_)
+        | tsast::Expr::BigInt(_)
         | tsast::Expr::Bool(_)
         | tsast::Expr::Null
         | tsast::Expr::Undefined => Ok(()),
@@ -1933,7 +2052,11 @@ fn lower_expr(expr: &lir::Expr, ctx: &LoweringContext) -> tsast::Expr {
         lir::Expr::Ident { name, .. } => tsast::Expr::Ident(name.clone()),
         lir::Expr::String { value, .. } => tsast::Expr::String(value.clone()),
         lir::Expr::Number { value, .. } => {
-            tsast::Expr::Number(value.parse::<f64>().unwrap_or(0.0))
+            let digits = value.trim_end_matches(['i', 'f']);
+            match lumo_lexer::NumberKind::of(value) {
+                lumo_lexer::NumberKind::Int => tsast::Expr::BigInt(digits.to_owned()),
+                _ => tsast::Expr::Number(digits.parse::<f64>().unwrap_or(0.0)),
+            }
         }
         lir::Expr::Produce { expr, .. } => lower_expr(expr, ctx),
         lir::Expr::Thunk { expr, .. } => tsast::Expr::Arrow {
@@ -1956,6 +2079,20 @@ fn lower_expr(expr: &lir::Expr, ctx: &LoweringContext) -> tsast::Expr {
         lir::Expr::Match {
             scrutinee, arms, ..
         } => lower_match_expr(scrutinee, arms, ctx),
+        // `__lumo_loop` steps the body in a native loop until it is done.
+        lir::Expr::Loop {
+            var, init, body, ..
+        } => runtime_call(
+            "__lumo_loop",
+            vec![
+                lower_expr(init, ctx),
+                tsast::Expr::Arrow {
+                    params: vec![tsast::Param::new(var)],
+                    return_type: None,
+                    body: Box::new(tsast::FunctionBody::Expr(Box::new(lower_expr(body, ctx)))),
+                },
+            ],
+        ),
         lir::Expr::Ctor { name, args, .. } => {
             if let Some((owner, variant)) = name.split_once('.') {
                 // `#[as__raw]` variants: skip the bundle access and emit the
@@ -2005,7 +2142,7 @@ fn lower_expr(expr: &lir::Expr, ctx: &LoweringContext) -> tsast::Expr {
             // All bundles are cap handlers — use CPS entries (with __k).
             // A bare Bundle outside a `handle` has no specific handled cap; the
             // inner-body CPS lowering treats handled_caps as empty.
-            lower_handler_with_resume(expr, &[], ctx)
+            lower_handler_with_resume(expr, &[], CAPS_PARAM, ctx)
         }
         lir::Expr::Member {
             object, field, ..
@@ -2146,7 +2283,7 @@ fn lower_match_expr(
         .collect::<Vec<_>>();
     let decision = build_match_decision(vec![scrutinee_expr.clone()], rows);
     let lowered =
-        lower_match_decision(&scrutinee_expr, decision, &ctx.variant_as_raw, &|body, bindings| {
+        lower_match_decision(&scrutinee_expr, decision, ctx, &|body, bindings| {
             wrap_bindings(lower_expr(body, ctx), bindings)
         });
 
@@ -2371,7 +2508,7 @@ fn default_specialize_occurrences(occurrences: &[tsast::Expr], column: usize) ->
 fn lower_match_decision(
     error_value: &tsast::Expr,
     decision: MatchDecision,
-    variant_as_raw: &HashMap<String, AsRawValue>,
+    ctx: &LoweringContext,
     lower_body: &dyn Fn(&lir::Expr, Vec<(String, tsast::Expr)>) -> tsast::Expr,
 ) -> tsast::Expr {
     match decision {
@@ -2384,16 +2521,18 @@ fn lower_match_decision(
         } => {
             // When default is Fail (exhaustive match), use the last case as the unconditional
             // else branch — TypeScript's type system guarantees no other value is possible.
-            let mut folded = if matches!(*default, MatchDecision::Fail) && !cases.is_empty() {
+            // With debug assertions the last case is tested like the others.
+            let exhaustive = matches!(*default, MatchDecision::Fail) && !cases.is_empty();
+            let mut folded = if exhaustive && !ctx.debug_assertions {
                 let last = cases.pop().unwrap();
-                lower_match_decision(error_value, last.subtree, variant_as_raw, lower_body)
+                lower_match_decision(error_value, last.subtree, ctx, lower_body)
             } else {
-                lower_match_decision(error_value, *default, variant_as_raw, lower_body)
+                lower_match_decision(error_value, *default, ctx, lower_body)
             };
             for case in cases.into_iter().rev() {
                 // `#[as__raw]` variant: compare with the raw JS literal
                 // (`=== true` / `=== false`) instead of the tagged form.
-                let cond = if let Some(raw) = variant_as_raw.get(&case.ctor_name) {
+                let cond = if let Some(raw) = ctx.variant_as_raw.get(&case.ctor_name) {
                     tsast::Expr::Binary {
                         left: Box::new(occurrence.clone()),
                         op: tsast::BinaryOp::EqEqEq,
@@ -2416,7 +2555,7 @@ fn lower_match_decision(
                     then_expr: Box::new(lower_match_decision(
                         error_value,
                         case.subtree,
-                        variant_as_raw,
+                        ctx,
                         lower_body,
                     )),
                     else_expr: Box::new(folded),
@@ -2513,7 +2652,8 @@ fn lower_cps_handle(
     // body's CPS continuation and the factory's `__k_handle` are the shared
     // `__identity` const (see runtime prelude).
     let cps_body = lower_cps_expr(body, identity_k_expr(), &handled, ctx);
-    let handler_factory = lower_handler_with_resume(handler, &handled, ctx);
+    // A direct-style fn has no `__caps` of its own to hand the ops.
+    let handler_factory = lower_handler_with_resume(handler, &handled, CAPS_PARAM, ctx);
     let handler_instance = tsast::Expr::Call {
         callee: Box::new(handler_factory),
         args: vec![identity_k_expr()],
@@ -2615,19 +2755,23 @@ fn is_effectful_expr(
                     .iter()
                     .any(|a| is_effectful_expr(&a.body, handled_caps, ctx))
         }
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             is_effectful_expr(value, handled_caps, ctx)
                 || is_effectful_expr(body, handled_caps, ctx)
         }
         lir::Expr::Produce { expr, .. } => is_effectful_expr(expr, handled_caps, ctx),
         lir::Expr::Member { object, .. } => is_effectful_expr(object, handled_caps, ctx),
         lir::Expr::Handle { .. } => true,
+        // A bundle value whose ops perform a handled cap closes over this
+        // scope's `__caps`, so it has to be built in the CPS context.
+        lir::Expr::Bundle { entries, .. } => entries
+            .iter()
+            .any(|e| is_effectful_expr(&e.body, handled_caps, ctx)),
         // Leaf values are never effectful
         lir::Expr::Ident { .. }
         | lir::Expr::String { .. }
         | lir::Expr::Number { .. }
         | lir::Expr::Perform { .. } // bare Perform (no member/apply) is just a value ref
-        | lir::Expr::Bundle { .. }
         | lir::Expr::Lambda { .. }
         | lir::Expr::Error { .. } => false,
         // Wrappers: recurse into inner expression
@@ -2653,12 +2797,7 @@ fn lower_cps_value(
     then: impl FnOnce(tsast::Expr) -> tsast::Expr,
 ) -> tsast::Expr {
     if is_effectful_expr(expr, handled_caps, ctx) {
-        static COUNTER: std::sync::atomic::AtomicUsize =
-            std::sync::atomic::AtomicUsize::new(0);
-        let tmp = format!(
-            "__cps_v_{}",
-            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-        );
+        let tmp = ctx.next_cps_value_name();
         let body = then(tsast::Expr::Ident(tmp.clone()));
         let inner_k = tsast::Expr::Arrow {
             params: vec![tsast::Param::new(&tmp)],
@@ -2754,6 +2893,11 @@ fn lower_cps_expr_inner(
         lir::Expr::Match {
             scrutinee, arms, ..
         } => lower_cps_match_expr(scrutinee, arms, k, handled_caps, ctx),
+        lir::Expr::Loop {
+            var, init, body, ..
+        } if is_effectful_expr(body, handled_caps, ctx) => {
+            lower_cps_loop(var, init, body, k, handled_caps, ctx)
+        }
         // Ctor with effectful args: CPS-sequence each arg, then construct
         lir::Expr::Ctor { name, args, .. } => {
             let arg_refs: Vec<&lir::Expr> = args.iter().collect();
@@ -2797,6 +2941,12 @@ fn lower_cps_expr_inner(
                 }
             })
         }
+        // A bundle value built here: its ops perform through this scope's
+        // `__caps`, not the perform site's.
+        lir::Expr::Bundle { .. } => tsast::Expr::Call {
+            callee: Box::new(k),
+            args: vec![lower_handler_with_resume(expr, handled_caps, "__perform_caps", ctx)],
+        },
         // Transparent wrappers: recurse into inner expression for CPS
         lir::Expr::Unroll { expr, .. }
         | lir::Expr::Roll { expr, .. }
@@ -2813,7 +2963,8 @@ fn lower_cps_expr_inner(
             let mut extended_caps: Vec<String> =
                 handled_caps.iter().cloned().collect();
             extended_caps.push(runtime_name.clone());
-            let handler_factory = lower_handler_with_resume(handler, &extended_caps, ctx);
+            let handler_factory =
+                lower_handler_with_resume(handler, &extended_caps, "__perform_caps", ctx);
             let handler_instance = tsast::Expr::Call {
                 callee: Box::new(handler_factory),
                 args: vec![k.clone()],
@@ -2829,8 +2980,9 @@ fn lower_cps_expr_inner(
                 // the perform-site continuation. Two emission modes:
                 //
                 // - Tail (`k` is `__k_handle`, i.e. resume is the body's
-                //   final expression): emit as `__k_handle(__k_perform(v))`.
-                //   `__k_perform(v)` returns a thunk; the outer trampoline
+                //   final expression): emit as `__k_perform(v)`, whose
+                //   handled computation goes on into the `handle`'s own
+                //   continuation. It returns a thunk; the outer trampoline
                 //   unwinds it. Stack-safe through any perform depth.
                 //
                 // - Non-tail (e.g. `let _ = resume(a); rest`): drive
@@ -2851,19 +3003,17 @@ fn lower_cps_expr_inner(
                                 callee: Box::new(tsast::Expr::Ident("__k_perform".to_owned())),
                                 args: ts_args,
                             };
-                            let resume_value = if is_tail {
-                                kperform_call
-                            } else {
-                                tsast::Expr::Call {
+                            if is_tail {
+                                return kperform_call;
+                            }
+                            tsast::Expr::Call {
+                                callee: Box::new(k.clone()),
+                                args: vec![tsast::Expr::Call {
                                     callee: Box::new(tsast::Expr::Ident(
                                         "__trampoline".to_owned(),
                                     )),
                                     args: vec![kperform_call],
-                                }
-                            };
-                            tsast::Expr::Call {
-                                callee: Box::new(k.clone()),
-                                args: vec![resume_value],
+                                }],
                             }
                         },
                     );
@@ -2972,7 +3122,10 @@ fn lower_cps_match_expr(
     ctx: &LoweringContext,
 ) -> tsast::Expr {
     let k_name = ctx.next_k_name();
-    let k_ident = tsast::Expr::Ident(k_name.clone());
+    // A named `k` goes to the arms as is: binding it to a join point would
+    // hide a `__k_handle` from the tail-`resume` check in the arms.
+    let k_is_ident = matches!(k, tsast::Expr::Ident(_));
+    let k_ident = if k_is_ident { k.clone() } else { tsast::Expr::Ident(k_name.clone()) };
 
     let scrutinee_name = ctx.next_match_name();
     let scrutinee_expr = tsast::Expr::Ident(scrutinee_name.clone());
@@ -2987,7 +3140,7 @@ fn lower_cps_match_expr(
         .collect::<Vec<_>>();
     let decision = build_match_decision(vec![scrutinee_expr.clone()], rows);
     let lowered =
-        lower_match_decision(&scrutinee_expr, decision, &ctx.variant_as_raw, &|body, bindings| {
+        lower_match_decision(&scrutinee_expr, decision, ctx, &|body, bindings| {
             wrap_bindings(
                 lower_cps_expr(body, k_ident.clone(), handled_caps, ctx),
                 bindings,
@@ -2996,15 +3149,93 @@ fn lower_cps_match_expr(
 
     lower_cps_value(scrutinee, handled_caps, ctx, |lowered_scrutinee| {
         let inner = iife(&scrutinee_name, lowered, lowered_scrutinee);
+        if k_is_ident {
+            return inner;
+        }
         iife(&k_name, inner, k.clone())
     })
 }
 
+/// CPS-transform a loop whose body has effects. Each turn is a call of a
+/// local fn on the next state, bounced through `__thunk` so the trampoline
+/// runs the loop in constant stack:
+///
+/// ```text
+/// (() => { const __loop_0 = (s) => __thunk(() => CPS[body]((r) =>
+///   r[LUMO_TAG] === "done" ? k(void 0) : __loop_0(r.args[0])));
+///   return CPS[init](__loop_0); })()
+/// ```
+fn lower_cps_loop(
+    var: &str,
+    init: &lir::Expr,
+    body: &lir::Expr,
+    k: tsast::Expr,
+    handled_caps: &[String],
+    ctx: &LoweringContext,
+) -> tsast::Expr {
+    let loop_name = ctx.next_loop_name();
+    let step_name = ctx.next_cps_value_name();
+    let step = tsast::Expr::Ident(step_name.clone());
+    let is_done = tsast::Expr::Binary {
+        left: Box::new(tsast::Expr::Index {
+            object: Box::new(step.clone()),
+            index: Box::new(tsast::Expr::Ident("LUMO_TAG".to_owned())),
+        }),
+        op: tsast::BinaryOp::EqEqEq,
+        right: Box::new(tsast::Expr::String("done".to_owned())),
+    };
+    let next_k = tsast::Expr::Arrow {
+        params: vec![tsast::Param::new(&step_name)],
+        return_type: None,
+        body: Box::new(tsast::FunctionBody::Expr(Box::new(tsast::Expr::IfElse {
+            cond: Box::new(is_done),
+            then_expr: Box::new(tsast::Expr::Call {
+                callee: Box::new(k),
+                args: vec![tsast::Expr::Void(Box::new(tsast::Expr::Number(0.0)))],
+            }),
+            else_expr: Box::new(tsast::Expr::Call {
+                callee: Box::new(tsast::Expr::Ident(loop_name.clone())),
+                args: vec![payload_access_expr(&step, 0)],
+            }),
+        }))),
+    };
+    let turn = tsast::Expr::Arrow {
+        params: vec![tsast::Param::new(var)],
+        return_type: None,
+        body: Box::new(tsast::FunctionBody::Expr(Box::new(thunk_wrap(lower_cps_expr(
+            body,
+            next_k,
+            handled_caps,
+            ctx,
+        ))))),
+    };
+    let start = lower_cps_expr(init, tsast::Expr::Ident(loop_name.clone()), handled_caps, ctx);
+    let block = tsast::Block {
+        stmts: vec![
+            tsast::Stmt::Const(tsast::ConstDecl {
+                export: false,
+                name: loop_name,
+                type_ann: None,
+                init: turn,
+            }),
+            tsast::Stmt::Return(Some(start)),
+        ],
+    };
+    tsast::Expr::Call {
+        callee: Box::new(tsast::Expr::Arrow {
+            params: Vec::new(),
+            return_type: None,
+            body: Box::new(tsast::FunctionBody::Block(block)),
+        }),
+        args: Vec::new(),
+    }
+}
+
 /// Emit a single handler method property:
 /// `op: (__caps, user_args..., __k_perform) => __thunk(() => body)` where
 /// `body` is CPS-lowered with `__k_handle` as the continuation and, if the
-/// source references `resume`, wrapped in `((resume) => body)(() => (v) =>
-/// __trampoline(__k_perform(v)))`.
+/// source passes `resume` around as a value, wrapped in `((resume) =>
+/// body)((v) => __trampoline(__k_handle(__k_perform(v))))`.
 ///
 /// Shared between `lower_handler_with_resume` (for user `handle` blocks)
 /// and `lower_impl_const` (for `impl Cap` / `impl T: Cap` default bundles
@@ -3014,13 +3245,14 @@ fn emit_handler_method_prop(
     user_params: &[lir::Param],
     body: &lir::Expr,
     handled_caps: &[String],
+    caps_param: &str,
     ctx: &LoweringContext,
 ) -> tsast::ObjectProp {
     let k_handle = tsast::Expr::Ident("__k_handle".to_owned());
     let body_lowered = lower_cps_expr(body, k_handle, handled_caps, ctx);
 
     let mut params: Vec<tsast::Param> =
-        vec![tsast::Param::new(CAPS_PARAM).with_type(caps_type())];
+        vec![tsast::Param::new(caps_param).with_type(caps_type())];
     params.extend(user_params.iter().map(|p| {
         tsast::Param::new(&p.name).with_type(lower_type_expr_to_ts_type(&p.ty.value))
     }));
@@ -3031,7 +3263,8 @@ fn emit_handler_method_prop(
     // continuation:
     //
     // - Tail (`resume(v)` is the body's last expression, CPS k = __k_handle):
-    //   emit as `__k_handle(__k_perform(v))`. The thunk returned by
+    //   emit as `__k_perform(v)`. The handled computation already continues
+    //   into the `handle`'s own continuation, so the thunk returned by
     //   `__k_perform(v)` bubbles up to the outer `__trampoline` for
     //   iterative unwinding — stack-safe through any perform depth.
     //
@@ -3043,7 +3276,27 @@ fn emit_handler_method_prop(
     //   non-deterministic handlers.
     //
     // See `lower_cps_expr_inner` for the detection + emission logic.
-    let raw_body = body_lowered;
+    //
+    // A `resume` that escapes as a value (`__promise_then(p, resume)`) gets
+    // a real binding: a function that drives the rest of the handled
+    // computation to completion whenever the host calls it.
+    let raw_body = if resume_escapes(body) {
+        let v = tsast::Expr::Ident("__v".to_owned());
+        let resume_fn = tsast::Expr::Arrow {
+            params: vec![tsast::Param::new("__v")],
+            return_type: None,
+            body: Box::new(tsast::FunctionBody::Expr(Box::new(tsast::Expr::Call {
+                callee: Box::new(tsast::Expr::Ident("__trampoline".to_owned())),
+                args: vec![tsast::Expr::Call {
+                    callee: Box::new(tsast::Expr::Ident("__k_perform".to_owned())),
+                    args: vec![v],
+                }],
+            }))),
+        };
+        iife("resume", body_lowered, resume_fn)
+    } else {
+        body_lowered
+    };
 
     let inner_body = thunk_wrap(raw_body);
     tsast::ObjectProp {
@@ -3056,6 +3309,41 @@ fn emit_handler_method_prop(
     }
 }
 
+/// Whether `expr` references `resume` other than as the callee of a call.
+fn resume_escapes(expr: &lir::Expr) -> bool {
+    match expr {
+        lir::Expr::Ident { name, .. } => name == "resume",
+        lir::Expr::Apply { callee, arg, .. } => {
+            let is_resume_call = matches!(
+                callee.as_ref(),
+                lir::Expr::Force { expr, .. }
+                    if matches!(expr.as_ref(), lir::Expr::Ident { name, .. } if name == "resume")
+            );
+            (!is_resume_call && resume_escapes(callee)) || resume_escapes(arg)
+        }
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Ann { expr, .. } => resume_escapes(expr),
+        lir::Expr::Lambda { body, .. } => resume_escapes(body),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => resume_escapes(value) || resume_escapes(body),
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            resume_escapes(scrutinee) || arms.iter().any(|arm| resume_escapes(&arm.body))
+        }
+        // A nested handler's own `resume` is a different binding.
+        lir::Expr::Handle { body, .. } => resume_escapes(body),
+        lir::Expr::Bundle { .. } => false,
+        lir::Expr::Ctor { args, .. } => args.iter().any(resume_escapes),
+        lir::Expr::Member { object, .. } => resume_escapes(object),
+        lir::Expr::Perform { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Error { .. } => false,
+    }
+}
+
 /// Wrap a list of handler method properties in the `(__k_handle) => { ... }`
 /// factory. Calling `factory(__k_handle)` installs the bundle into a
 /// specific handle-expression's context.
@@ -3071,9 +3359,14 @@ fn emit_handler_factory(methods: Vec<tsast::ObjectProp>) -> tsast::Expr {
 
 /// Build a handler factory for `handle Cap with bundle { ... } in body`.
 /// Delegates per-method emission to `emit_handler_method_prop`.
+///
+/// The ops get the perform site's `__caps` as `caps_param`. Where that is
+/// not `__caps`, their bodies read the `__caps` of the `handle` instead, so
+/// an op that performs its own cap reaches the handler around this one.
 fn lower_handler_with_resume(
     handler: &lir::Expr,
     handled_caps: &[String],
+    caps_param: &str,
     ctx: &LoweringContext,
 ) -> tsast::Expr {
     let lir::Expr::Bundle { entries, .. } = handler else {
@@ -3087,6 +3380,7 @@ fn lower_handler_with_resume(
                 &entry.params,
                 &entry.body,
                 handled_caps,
+                caps_param,
                 ctx,
             )
         })
@@ -3105,12 +3399,13 @@ enum MatchPattern {
 }
 
 /// Convert from shared `Pattern` type to backend-local `MatchPattern`.
+/// A qualified `List.cons` pattern tests the same tag as `.cons`.
 fn pattern_to_match_pattern(pattern: &Pattern) -> MatchPattern {
     match pattern {
         Pattern::Wildcard => MatchPattern::Wildcard,
         Pattern::Bind(name) => MatchPattern::Bind(name.clone()),
         Pattern::Ctor { name, args } => MatchPattern::Ctor {
-            name: name.clone(),
+            name: name.rsplit_once('.').map_or(name.as_str(), |(_, variant)| variant).to_owned(),
             args: args.iter().map(pattern_to_match_pattern).collect(),
         },
     }
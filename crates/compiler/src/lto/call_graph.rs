@@ -23,6 +23,9 @@ pub struct CallSite {
 pub enum CallTarget {
     Fn(String),
     ImplMethod { impl_const: String, method: String },
+    /// `cap.method(...)` on a cap with no default impl for its type args:
+    /// dispatched at run time through the handler in scope.
+    Perform(String),
     Indirect,
 }
 
@@ -40,8 +43,8 @@ fn collect_apply_chain<'a>(expr: &'a lir::Expr) -> Option<(&'a lir::Expr, Vec<&'
     Some((cur, args))
 }
 
-/// Classifies whether `head` is a known fn / impl-method call or an
-/// indirect/opaque callee. `Force(Ident("resume"))` returns `None` —
+/// Classifies whether `head` is a known fn / impl-method call, an
+/// unresolved perform or an indirect/opaque callee. `Force(Ident("resume"))` returns `None` —
 /// `resume` is a CPS plumbing construct (identity in the dep-free path)
 /// and should not generate a call-graph edge that blocks dep-free analysis.
 fn classify_callee(
@@ -78,7 +81,7 @@ fn classify_callee(
                         });
                     }
                 }
-                Some(CallTarget::Indirect)
+                Some(CallTarget::Perform(cap.clone()))
             } else {
                 Some(CallTarget::Indirect)
             }
@@ -122,14 +125,28 @@ fn walk_expr(
             walk_expr(callee, file, fn_names, resolution, out);
             walk_expr(arg, file, fn_names, resolution, out);
         }
+        // `Force(Ident)` is a call head, already classified above.
+        lir::Expr::Force { expr, .. } if matches!(expr.as_ref(), lir::Expr::Ident { .. }) => {}
         lir::Expr::Force { expr, .. }
         | lir::Expr::Thunk { expr, .. }
         | lir::Expr::Produce { expr, .. }
         | lir::Expr::Roll { expr, .. }
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => walk_expr(expr, file, fn_names, resolution, out),
+        // `resume` passed as a value (e.g. to a promise's `then`) escapes the
+        // handler, so the op body can't be inlined with `resume` stripped.
+        lir::Expr::Ident { name, id } if name == "resume" => out.push(CallSite {
+            callee: CallTarget::Indirect,
+            span: file.span_of(*id),
+        }),
+        // A fn passed as a value, like a promise's rejection callback, may be
+        // called from anywhere: keep an edge to it so DCE doesn't sweep it.
+        lir::Expr::Ident { name, id } if fn_names.contains(name) => out.push(CallSite {
+            callee: CallTarget::Fn(name.clone()),
+            span: file.span_of(*id),
+        }),
         lir::Expr::Lambda { body, .. } => walk_expr(body, file, fn_names, resolution, out),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             walk_expr(value, file, fn_names, resolution, out);
             walk_expr(body, file, fn_names, resolution, out);
         }
@@ -279,4 +296,20 @@ mod tests {
             "expected Indirect call site, got {:?}", edges
         );
     }
+
+    #[test]
+    fn fn_passed_as_value_creates_edge() {
+        let src = r#"
+            extern fn js_then(k: Number): Number;
+            fn on_done(x: Number): Number { x }
+            fn caller(): Number { js_then(on_done) }
+        "#;
+        let file = lower(src);
+        let cg = build_call_graph(&file);
+        let edges = cg.edges.get("caller").expect("caller has edges");
+        assert!(
+            edges.iter().any(|cs| cs.callee == CallTarget::Fn("on_done".to_owned())),
+            "expected Fn(\"on_done\") in caller's edges, got {:?}", edges
+        );
+    }
 }
@@ -2,7 +2,10 @@ use std::collections::HashSet;
 
 use lumo_lir as lir;
 
-pub fn sweep(file: &mut lir::File) {
+/// Drop fns unreachable from `main` (or from every fn, in library mode) and
+/// from the impl methods, which are always kept.
+/// Returns the swept fn names, sorted.
+pub fn sweep(file: &mut lir::File) -> Vec<String> {
     use super::call_graph::{build_call_graph, CallTarget};
 
     let cg = build_call_graph(file);
@@ -22,6 +25,26 @@ pub fn sweep(file: &mut lir::File) {
         }
     }
 
+    // Impls are all kept (see below), so the fns their methods call are too.
+    let fn_names: HashSet<&str> = file
+        .items
+        .iter()
+        .filter_map(|i| match i {
+            lir::Item::Fn(f) => Some(f.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    for (caller, sites) in &cg.edges {
+        if fn_names.contains(caller.as_str()) {
+            continue;
+        }
+        for cs in sites {
+            if let CallTarget::Fn(callee) = &cs.callee {
+                work.push(callee.clone());
+            }
+        }
+    }
+
     while let Some(name) = work.pop() {
         if !reachable.insert(name.clone()) {
             continue;
@@ -43,7 +66,7 @@ pub fn sweep(file: &mut lir::File) {
                             }
                         }
                     }
-                    CallTarget::Indirect => {}
+                    CallTarget::Perform(_) | CallTarget::Indirect => {}
                 }
             }
         }
@@ -51,10 +74,16 @@ pub fn sweep(file: &mut lir::File) {
 
     // Drop fns that are not reachable. Keep all non-fn items (data, cap, impl,
     // extern) — DCE for those is out of scope.
+    let mut swept = Vec::new();
     file.items.retain(|item| match item {
-        lir::Item::Fn(f) => reachable.contains(&f.name),
+        lir::Item::Fn(f) if !reachable.contains(&f.name) => {
+            swept.push(f.name.clone());
+            false
+        }
         _ => true,
     });
+    swept.sort();
+    swept
 }
 
 #[cfg(test)]
@@ -75,7 +104,7 @@ mod tests {
             fn main(): Number { 2 }
         "#;
         let mut file = lower(src);
-        sweep(&mut file);
+        assert_eq!(sweep(&mut file), vec!["dead".to_owned()]);
         assert!(!file.items.iter().any(|i| matches!(i, lir::Item::Fn(f) if f.name == "dead")));
         assert!(file.items.iter().any(|i| matches!(i, lir::Item::Fn(f) if f.name == "main")));
     }
@@ -101,4 +130,16 @@ mod tests {
         sweep(&mut file);
         assert!(file.items.iter().any(|i| matches!(i, lir::Item::Fn(f) if f.name == "helper")));
     }
+
+    #[test]
+    fn fn_called_from_impl_is_kept() {
+        let src = r#"
+            cap Eq { fn eq(a: Self, b: Self): Number }
+            fn num_eq(a: Number, b: Number): Number { 1 }
+            impl Number: Eq { fn eq(self, other: Self): Number = resume(num_eq(self, other)) }
+            fn main(): Number { 0 }
+        "#;
+        let mut file = lower(src);
+        assert!(sweep(&mut file).is_empty());
+    }
 }
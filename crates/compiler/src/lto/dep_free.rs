@@ -36,6 +36,19 @@ pub enum DepFreeStatus {
     Blocked,
 }
 
+/// Why a target ended up `Blocked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockReason {
+    /// Calls through a lambda, fn-typed param or other opaque callee.
+    IndirectCall,
+    /// Calls a fn or impl method that has no body in this file.
+    UnknownCallee(String),
+    /// Performs the named cap without a resolvable default impl.
+    UnresolvedPerform(String),
+    /// Calls (or shares an SCC with) the named target, which is blocked.
+    BlockedCallee(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ResolvedRef {
     pub impl_const: String,
@@ -45,6 +58,8 @@ pub struct ResolvedRef {
 #[derive(Debug, Clone, Default)]
 pub struct DepFreeAnalysis {
     pub status: HashMap<DepFreeKey, DepFreeStatus>,
+    /// The first reason found for every `Blocked` target.
+    pub reasons: HashMap<DepFreeKey, BlockReason>,
     pub perform_resolution: HashMap<ExprId, ResolvedRef>,
 }
 
@@ -54,14 +69,15 @@ struct NodeInfo {
     /// and unknown-but-safe terminals are NOT recorded here (they don't
     /// participate in SCC cycles).
     outgoing: Vec<DepFreeKey>,
-    /// True if this node has an indirect call site (lambda / fn-typed param /
+    /// Set if this node has an indirect call site (lambda / fn-typed param /
     /// unknown direct callee / impl method we don't have a body for).
-    has_indirect: bool,
-    /// True if this node has a Perform that can't be resolved: either bare
+    indirect: Option<BlockReason>,
+    /// The cap of the first Perform that can't be resolved and isn't
+    /// discharged by an enclosing `handle` of its cap: either bare
     /// `Perform` (no enclosing Member), or `Member(Perform, m)` where the
     /// resolution map lacks an entry for `(cap, type_args)`, or resolution
     /// points to an impl method body we don't have.
-    has_unresolved: bool,
+    unresolved: Option<String>,
     /// Tentative `(ExprId, ResolvedRef)` entries to commit to
     /// `perform_resolution` iff this node's SCC ends up DepFree.
     tentative_resolutions: Vec<(ExprId, ResolvedRef)>,
@@ -108,13 +124,17 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
     let mut nodes: HashMap<DepFreeKey, NodeInfo> = HashMap::new();
     for (key, body) in &targets {
         let mut outgoing: Vec<DepFreeKey> = Vec::new();
-        let mut has_indirect = false;
+        let mut indirect: Option<BlockReason> = None;
 
         // Direct call edges via cg.
         if let Some(sites) = cg.edges.get(&key.0) {
             for site in sites {
                 match &site.callee {
-                    CallTarget::Indirect => has_indirect = true,
+                    CallTarget::Indirect => {
+                        indirect.get_or_insert(BlockReason::IndirectCall);
+                    }
+                    // Recorded as `unresolved` by the perform-site walk below.
+                    CallTarget::Perform(_) => {}
                     CallTarget::Fn(name) => {
                         let dep = (name.clone(), Vec::new());
                         if target_keys.contains(&dep) {
@@ -124,7 +144,7 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
                         } else {
                             // Unknown direct callee (no body, not extern) —
                             // conservatively treat as indirect.
-                            has_indirect = true;
+                            indirect.get_or_insert(BlockReason::UnknownCallee(name.clone()));
                         }
                     }
                     CallTarget::ImplMethod { impl_const, method } => {
@@ -133,7 +153,7 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
                             outgoing.push(dep);
                         } else {
                             // Impl method body unknown — conservatively block.
-                            has_indirect = true;
+                            indirect.get_or_insert(BlockReason::UnknownCallee(dep.0));
                         }
                     }
                 }
@@ -141,23 +161,24 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
         }
 
         // Perform-site walk: collect Member(Perform, method) patterns.
-        let mut has_unresolved = false;
+        let mut unresolved: Option<String> = None;
         let mut tentative_resolutions: Vec<(ExprId, ResolvedRef)> = Vec::new();
         walk_for_perform_sites(
             body,
             resolution,
             &target_keys,
             &mut outgoing,
-            &mut has_unresolved,
+            &mut unresolved,
             &mut tentative_resolutions,
+            &mut Vec::new(),
         );
 
         nodes.insert(
             key.clone(),
             NodeInfo {
                 outgoing,
-                has_indirect,
-                has_unresolved,
+                indirect,
+                unresolved,
                 tentative_resolutions,
             },
         );
@@ -171,14 +192,20 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
     // outgoing edge leads outside the SCC to a Blocked target, the whole SCC
     // is Blocked; otherwise DepFree.
     let mut perform_resolution: HashMap<ExprId, ResolvedRef> = HashMap::new();
+    let mut reasons: HashMap<DepFreeKey, BlockReason> = HashMap::new();
     for scc in &sccs {
         let scc_set: HashSet<&DepFreeKey> = scc.iter().collect();
-        let mut blocked = false;
+        // The member that blocks the SCC, and why.
+        let mut blocked: Option<(&DepFreeKey, BlockReason)> = None;
 
         for key in scc {
             let info = nodes.get(key).expect("node info present");
-            if info.has_indirect || info.has_unresolved {
-                blocked = true;
+            if let Some(reason) = &info.indirect {
+                blocked = Some((key, reason.clone()));
+                break;
+            }
+            if let Some(cap) = &info.unresolved {
+                blocked = Some((key, BlockReason::UnresolvedPerform(cap.clone())));
                 break;
             }
             for out in &info.outgoing {
@@ -192,19 +219,25 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
                         // By reverse-topo order the target must already be
                         // classified (DepFree or Blocked); extern leaves were
                         // seeded DepFree at the start of this phase.
-                        blocked = true;
+                        blocked = Some((key, BlockReason::BlockedCallee(out.0.clone())));
                         break;
                     }
                 }
             }
-            if blocked {
+            if blocked.is_some() {
                 break;
             }
         }
 
-        if blocked {
+        if let Some((culprit, reason)) = blocked {
             for key in scc {
                 status.insert(key.clone(), DepFreeStatus::Blocked);
+                let why = if key == culprit {
+                    reason.clone()
+                } else {
+                    BlockReason::BlockedCallee(culprit.0.clone())
+                };
+                reasons.insert(key.clone(), why);
             }
         } else {
             for key in scc {
@@ -225,6 +258,7 @@ pub fn run(file: &lir::File, resolution: &ResolutionMap, cg: &CallGraph) -> DepF
 
     DepFreeAnalysis {
         status,
+        reasons,
         perform_resolution,
     }
 }
@@ -235,16 +269,23 @@ fn walk_for_perform_sites(
     resolution: &ResolutionMap,
     target_keys: &HashSet<DepFreeKey>,
     outgoing: &mut Vec<DepFreeKey>,
-    has_unresolved: &mut bool,
+    unresolved: &mut Option<String>,
     tentative_resolutions: &mut Vec<(ExprId, ResolvedRef)>,
+    handled: &mut Vec<String>,
 ) {
     match expr {
+        // Discharged by an enclosing `handle` in this body, so it depends on
+        // nothing outside it.
+        lir::Expr::Perform { cap, .. } if handled.contains(cap) => {}
         // Bare Perform (no enclosing Member): cannot pick a method — block.
-        lir::Expr::Perform { .. } => {
-            *has_unresolved = true;
+        lir::Expr::Perform { cap, .. } => {
+            unresolved.get_or_insert_with(|| cap.clone());
         }
         lir::Expr::Member { object, field, id } => {
             if let lir::Expr::Perform { cap, type_args, .. } = object.as_ref() {
+                if handled.contains(cap) {
+                    return;
+                }
                 let key = (cap.clone(), type_args.clone());
                 if let Some(res) = resolution.get(&key) {
                     let method_key = (format!("{}.{}", res.impl_const, field), Vec::new());
@@ -260,12 +301,12 @@ fn walk_for_perform_sites(
                     } else {
                         // Resolution points to an impl method we don't have a
                         // body for (e.g. extern-only). Block.
-                        *has_unresolved = true;
+                        unresolved.get_or_insert_with(|| cap.clone());
                     }
                 } else {
                     // No resolution for (cap, type_args) — typeclass binding
                     // ambiguous or unbound. Block.
-                    *has_unresolved = true;
+                    unresolved.get_or_insert_with(|| cap.clone());
                 }
                 return;
             }
@@ -275,8 +316,9 @@ fn walk_for_perform_sites(
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
         }
         lir::Expr::Apply { callee, arg, .. } => {
@@ -285,16 +327,18 @@ fn walk_for_perform_sites(
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
             walk_for_perform_sites(
                 arg,
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
         }
         lir::Expr::Force { expr, .. }
@@ -307,33 +351,37 @@ fn walk_for_perform_sites(
             resolution,
             target_keys,
             outgoing,
-            has_unresolved,
+            unresolved,
             tentative_resolutions,
+            handled,
         ),
         lir::Expr::Lambda { body, .. } => walk_for_perform_sites(
             body,
             resolution,
             target_keys,
             outgoing,
-            has_unresolved,
+            unresolved,
             tentative_resolutions,
+            handled,
         ),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             walk_for_perform_sites(
                 value,
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
             walk_for_perform_sites(
                 body,
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
         }
         lir::Expr::Match { scrutinee, arms, .. } => {
@@ -342,8 +390,9 @@ fn walk_for_perform_sites(
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
             for arm in arms {
                 walk_for_perform_sites(
@@ -351,28 +400,35 @@ fn walk_for_perform_sites(
                     resolution,
                     target_keys,
                     outgoing,
-                    has_unresolved,
+                    unresolved,
                     tentative_resolutions,
+                    handled,
                 );
             }
         }
-        lir::Expr::Handle { handler, body, .. } => {
+        lir::Expr::Handle {
+            cap, handler, body, ..
+        } => {
             walk_for_perform_sites(
                 handler,
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
+            handled.push(cap.clone());
             walk_for_perform_sites(
                 body,
                 resolution,
                 target_keys,
                 outgoing,
-                has_unresolved,
+                unresolved,
                 tentative_resolutions,
+                handled,
             );
+            handled.pop();
         }
         lir::Expr::Bundle { entries, .. } => {
             for e in entries {
@@ -381,8 +437,9 @@ fn walk_for_perform_sites(
                     resolution,
                     target_keys,
                     outgoing,
-                    has_unresolved,
+                    unresolved,
                     tentative_resolutions,
+                    handled,
                 );
             }
         }
@@ -393,8 +450,9 @@ fn walk_for_perform_sites(
                     resolution,
                     target_keys,
                     outgoing,
-                    has_unresolved,
+                    unresolved,
                     tentative_resolutions,
+                    handled,
                 );
             }
         }
@@ -565,6 +623,24 @@ mod tests {
         let an = run(&file, &res, &cg);
         let key = ("apply".to_owned(), Vec::<String>::new());
         assert_eq!(an.status.get(&key), Some(&DepFreeStatus::Blocked));
+        assert_eq!(an.reasons.get(&key), Some(&BlockReason::IndirectCall));
+    }
+
+    #[test]
+    fn callers_of_blocked_fns_name_the_blocked_callee() {
+        let src = r#"
+            fn apply(f: thunk Number, x: Number): Number { f(x) }
+            fn outer(x: Number): Number { apply(x, x) }
+        "#;
+        let file = lower(src);
+        let res = lto::resolution::build_resolution_map(&file);
+        let cg = lto::call_graph::build_call_graph(&file);
+        let an = run(&file, &res, &cg);
+        let key = ("outer".to_owned(), Vec::<String>::new());
+        assert_eq!(
+            an.reasons.get(&key),
+            Some(&BlockReason::BlockedCallee("apply".to_owned()))
+        );
     }
 
     #[test]
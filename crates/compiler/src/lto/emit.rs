@@ -17,25 +17,37 @@ use super::resolution::ResolutionMap;
 // the body's internal Let/Lambda bindings.
 //
 // The counter is per `transform` invocation (not a process-global AtomicU64)
-// so output is deterministic across repeated compilations.
+// so output is deterministic across repeated compilations. Passes that run
+// before `transform` mint names under their own prefix so the two never
+// collide.
 
 #[derive(Default)]
-struct AlphaCtx {
+pub(super) struct AlphaCtx {
+    /// Replaces `__lto` in minted names.
+    prefix: Option<&'static str>,
     counter: u64,
+    /// Inherent methods currently being inlined, innermost last. A
+    /// (mutually) recursive method is left as a call instead of being
+    /// unfolded forever.
+    inherent_stack: Vec<(String, String)>,
 }
 
 impl AlphaCtx {
-    fn fresh(&mut self, base: &str) -> String {
+    pub(super) fn with_prefix(prefix: &'static str) -> Self {
+        AlphaCtx { prefix: Some(prefix), ..AlphaCtx::default() }
+    }
+
+    pub(super) fn fresh(&mut self, base: &str) -> String {
         let n = self.counter;
         self.counter += 1;
-        format!("__lto_{base}_{n}")
+        format!("{}_{base}_{n}", self.prefix.unwrap_or("__lto"))
     }
 }
 
 /// Rename every binding (`Let.name`, `Lambda.param`, pattern bindings) inside
 /// `expr` to a fresh unique name, and rewire in-scope references accordingly.
 /// References to names introduced OUTSIDE `expr` (free vars) are untouched.
-fn alpha_rename_bindings(expr: &mut lir::Expr, ctx: &mut AlphaCtx) {
+pub(super) fn alpha_rename_bindings(expr: &mut lir::Expr, ctx: &mut AlphaCtx) {
     fn walk_pattern(
         pattern: &mut Pattern,
         map: &mut Vec<(String, String)>,
@@ -66,7 +78,8 @@ fn alpha_rename_bindings(expr: &mut lir::Expr, ctx: &mut AlphaCtx) {
                     *name = new.clone();
                 }
             }
-            lir::Expr::Let { name, value, body, .. } => {
+            lir::Expr::Let { name, value, body, .. }
+            | lir::Expr::Loop { var: name, init: value, body, .. } => {
                 walk(value, map, ctx);
                 let fresh = ctx.fresh(name);
                 let old = std::mem::replace(name, fresh.clone());
@@ -130,7 +143,7 @@ fn alpha_rename_bindings(expr: &mut lir::Expr, ctx: &mut AlphaCtx) {
 /// is "free" if it is NOT shadowed by a Let/Lambda/pattern binding above it
 /// within `expr`. Used to rename param references in a method body AFTER the
 /// outer `Lambda(p, ...)` wrappers have been stripped.
-fn rename_free_idents(expr: &mut lir::Expr, map: &[(String, String)]) {
+pub(super) fn rename_free_idents(expr: &mut lir::Expr, map: &[(String, String)]) {
     fn walk_pattern(pattern: &Pattern, shadowed: &mut Vec<String>) -> usize {
         match pattern {
             Pattern::Wildcard => 0,
@@ -158,7 +171,8 @@ fn rename_free_idents(expr: &mut lir::Expr, map: &[(String, String)]) {
                     *name = new.clone();
                 }
             }
-            lir::Expr::Let { name, value, body, .. } => {
+            lir::Expr::Let { name, value, body, .. }
+            | lir::Expr::Loop { var: name, init: value, body, .. } => {
                 walk(value, map, shadowed);
                 shadowed.push(name.clone());
                 walk(body, map, shadowed);
@@ -406,7 +420,7 @@ fn apply_inlines(
     });
 }
 
-fn strip_thunk_lambdas(mut expr: lir::Expr, n_params: usize) -> lir::Expr {
+pub(super) fn strip_thunk_lambdas(mut expr: lir::Expr, n_params: usize) -> lir::Expr {
     if let lir::Expr::Thunk { expr: inner, .. } = expr {
         expr = *inner;
     }
@@ -479,7 +493,7 @@ fn inline_calls(
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => inline_calls(expr, bodies, spans, ctx),
         lir::Expr::Lambda { body, .. } => inline_calls(body, bodies, spans, ctx),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             inline_calls(value, bodies, spans, ctx);
             inline_calls(body, bodies, spans, ctx);
         }
@@ -508,7 +522,7 @@ fn inline_calls(
     }
 }
 
-fn match_call_chain(expr: &lir::Expr) -> Option<(String, Vec<lir::Expr>)> {
+pub(super) fn match_call_chain(expr: &lir::Expr) -> Option<(String, Vec<lir::Expr>)> {
     let mut args = Vec::new();
     let mut cur = expr;
     while let lir::Expr::Apply { callee, arg, .. } = cur {
@@ -537,7 +551,7 @@ fn body_size(expr: &lir::Expr) -> usize {
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => count += body_size(expr),
         lir::Expr::Lambda { body, .. } => count += body_size(body),
-        lir::Expr::Let { value, body, .. } => count += body_size(value) + body_size(body),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => count += body_size(value) + body_size(body),
         lir::Expr::Match { scrutinee, arms, .. } => {
             count += body_size(scrutinee);
             for a in arms {
@@ -603,6 +617,16 @@ fn rewrite_walk(
         rewrite_walk(expr, resolutions, resolution, spans, ctx);
         return;
     }
+    // Inherent method calls (`"s".len()` → `String.len("s")`) stay
+    // effectful in their own impl, so a dep-free body that kept calling them
+    // would call a CPS function in direct style. Inline them like resolved
+    // performs; the recursion then rewrites the performs they contain.
+    if let Some(key) = try_inline_inherent_call(expr, resolution, spans, ctx) {
+        ctx.inherent_stack.push(key);
+        rewrite_walk(expr, resolutions, resolution, spans, ctx);
+        ctx.inherent_stack.pop();
+        return;
+    }
     match expr {
         lir::Expr::Member { object, .. } => rewrite_walk(object, resolutions, resolution, spans, ctx),
         lir::Expr::Apply { callee, arg, .. } => {
@@ -616,7 +640,7 @@ fn rewrite_walk(
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => rewrite_walk(expr, resolutions, resolution, spans, ctx),
         lir::Expr::Lambda { body, .. } => rewrite_walk(body, resolutions, resolution, spans, ctx),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             rewrite_walk(value, resolutions, resolution, spans, ctx);
             rewrite_walk(body, resolutions, resolution, spans, ctx);
         }
@@ -740,21 +764,103 @@ fn try_inline_perform_call(
     true
 }
 
+/// If `expr` is an Apply chain terminating in `Member(Ident(impl_const),
+/// method)` for an inherent impl method, replace it with the method's
+/// inlined body (params bound via a Let-chain) and return the method key.
+///
+/// Only called on bodies the dep-free analysis accepted, so every inherent
+/// method reached here is itself dep-free and its perform sites carry
+/// resolutions.
+fn try_inline_inherent_call(
+    expr: &mut lir::Expr,
+    resolution: &ResolutionMap,
+    spans: &mut Vec<lumo_span::Span>,
+    ctx: &mut AlphaCtx,
+) -> Option<(String, String)> {
+    let mut args: Vec<lir::Expr> = Vec::new();
+    let mut cur: &lir::Expr = expr;
+    while let lir::Expr::Apply { callee, arg, .. } = cur {
+        args.push((**arg).clone());
+        cur = callee;
+    }
+    args.reverse();
+    if let lir::Expr::Force { expr: inner, .. } = cur {
+        cur = inner;
+    }
+
+    let lir::Expr::Member { id, object, field } = cur else {
+        return None;
+    };
+    let lir::Expr::Ident { name: impl_const, .. } = object.as_ref() else {
+        return None;
+    };
+    let key = (impl_const.clone(), field.clone());
+    if ctx.inherent_stack.contains(&key) {
+        return None;
+    }
+    let method_info = resolution.inherent_method(impl_const, field)?;
+    if method_info.params.len() != args.len() {
+        return None;
+    }
+    let span = spans[id.0 as usize];
+
+    let mut inlined = strip_thunk_lambdas(method_info.body.clone(), method_info.params.len());
+    let fresh_params: Vec<String> = method_info
+        .params
+        .iter()
+        .map(|p| ctx.fresh(&p.name))
+        .collect();
+    let param_renames: Vec<(String, String)> = method_info
+        .params
+        .iter()
+        .zip(&fresh_params)
+        .map(|(p, fresh)| (p.name.clone(), fresh.clone()))
+        .collect();
+    rename_free_idents(&mut inlined, &param_renames);
+    alpha_rename_bindings(&mut inlined, ctx);
+
+    for (fresh, a) in fresh_params.iter().zip(args).rev() {
+        let let_id = alloc_id(spans, span);
+        inlined = lir::Expr::Let {
+            id: let_id,
+            name: fresh.clone(),
+            value: Box::new(a),
+            body: Box::new(inlined),
+        };
+    }
+
+    *expr = inlined;
+    Some(key)
+}
+
 /// Replace `Apply(Force(Ident("resume")), x)` with `x` throughout `expr`.
 ///
 /// In inlined impl bodies under LTO's statically-dispatched path, there is
 /// no surrounding handler: the method body runs directly, so `resume(v)`
 /// reduces to `v` (the direct return of the method's result to its caller).
-fn strip_resume(expr: &mut lir::Expr) {
-    if let lir::Expr::Apply { callee, arg, .. } = expr {
+/// A value `v` — as in `resume(self)` — becomes `produce v`.
+pub(super) fn strip_resume(expr: &mut lir::Expr) {
+    if let lir::Expr::Apply { id, callee, arg } = expr {
         if let lir::Expr::Force { expr: inner, .. } = callee.as_ref() {
             if let lir::Expr::Ident { name, .. } = inner.as_ref() {
                 if name == "resume" {
+                    let id = *id;
                     let arg_inner = std::mem::replace(
                         arg.as_mut(),
                         lir::Expr::Error { id: ExprId(0) },
                     );
-                    *expr = arg_inner;
+                    *expr = match arg_inner {
+                        lir::Expr::Ident { .. }
+                        | lir::Expr::String { .. }
+                        | lir::Expr::Number { .. }
+                        | lir::Expr::Ctor { .. }
+                        | lir::Expr::Thunk { .. }
+                        | lir::Expr::Roll { .. } => lir::Expr::Produce {
+                            id,
+                            expr: Box::new(arg_inner),
+                        },
+                        computation => computation,
+                    };
                     strip_resume(expr);
                     return;
                 }
@@ -773,7 +879,7 @@ fn strip_resume(expr: &mut lir::Expr) {
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => strip_resume(expr),
         lir::Expr::Lambda { body, .. } => strip_resume(body),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             strip_resume(value);
             strip_resume(body);
         }
@@ -829,7 +935,7 @@ fn redirect_calls(expr: &mut lir::Expr, clone_names: &HashMap<String, String>) {
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => redirect_calls(expr, clone_names),
         lir::Expr::Lambda { body, .. } => redirect_calls(body, clone_names),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             redirect_calls(value, clone_names);
             redirect_calls(body, clone_names);
         }
@@ -858,7 +964,7 @@ fn redirect_calls(expr: &mut lir::Expr, clone_names: &HashMap<String, String>) {
     }
 }
 
-fn alloc_id(spans: &mut Vec<lumo_span::Span>, span: lumo_span::Span) -> ExprId {
+pub(super) fn alloc_id(spans: &mut Vec<lumo_span::Span>, span: lumo_span::Span) -> ExprId {
     let id = ExprId(spans.len() as u32);
     spans.push(span);
     id
@@ -924,7 +1030,7 @@ fn collect_applied_effects(
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => collect_applied_effects(expr, resolutions, out),
         lir::Expr::Lambda { body, .. } => collect_applied_effects(body, resolutions, out),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             collect_applied_effects(value, resolutions, out);
             collect_applied_effects(body, resolutions, out);
         }
@@ -972,7 +1078,7 @@ fn body_has_resolution(expr: &lir::Expr, resolutions: &HashMap<ExprId, ResolvedR
         | lir::Expr::Unroll { expr, .. }
         | lir::Expr::Ann { expr, .. } => body_has_resolution(expr, resolutions),
         lir::Expr::Lambda { body, .. } => body_has_resolution(body, resolutions),
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             body_has_resolution(value, resolutions) || body_has_resolution(body, resolutions)
         }
         lir::Expr::Match { scrutinee, arms, .. } => {
@@ -0,0 +1,1119 @@
+//! Handler runners and their lowering.
+//!
+//! A runner is a fn that forces a `thunk` param under a `handle`, like
+//! libstd's `run_state(init, thunk body)`. Caps a thunk performs count
+//! against the fn that builds it, so `run_state(0, thunk count(4))` would
+//! leave the caller needing `State` even though the runner handles it.
+//! [`inline_runners`] runs in every build, before typechecking: it inlines
+//! each call that passes a literal `thunk`, so the `handle` ends up around
+//! the thunk's body in the caller. A `thunk` let-bound to a literal and
+//! used once counts as one, as does a runner's own `thunk` param handed on
+//! to another runner; the typechecker rejects any other call. A fn that
+//! calls a `fn` param is inlined the same way wherever it is passed a fn
+//! literal, which has no run-time form of its own.
+//!
+//! Under LTO, [`lower_local_handlers`] then removes a `handle` whose
+//! handler is a literal bundle of tail-resumptive ops. Each op performed in
+//! the body, or in a fn the body calls that needs the cap, is replaced by
+//! the op's body with `resume(v)` reduced to `v`. Those fns are cloned per
+//! handler and take the locals the ops capture as leading params. A
+//! `run_state` cell then becomes a local array read and written in place,
+//! and the clones no longer need CPS. An op may perform the cap itself, as
+//! libstd's `map` re-yields: that perform belongs to the handler around the
+//! `handle` and stays in the clone, so a stack of such handles fuses from
+//! the innermost out.
+
+use std::collections::{HashMap, HashSet};
+
+use lumo_lir as lir;
+use lumo_span::Span;
+use lumo_types::{CapEntry, Pattern, Spanned, TypeExpr};
+
+use super::emit::{
+    alloc_id, alpha_rename_bindings, match_call_chain, rename_free_idents, strip_resume,
+    strip_thunk_lambdas, AlphaCtx,
+};
+use super::mono::fresh_ids;
+
+/// A runner's params and its body with the param lambdas stripped.
+struct Runner {
+    params: Vec<lir::Param>,
+    body: lir::Expr,
+}
+
+/// Inline every call to a runner whose `thunk` and `fn` arguments are all
+/// literals. Returns the names of the runners inlined, sorted.
+pub fn inline_runners(file: &mut lir::File) -> Vec<String> {
+    let runners = find_runners(file);
+    if runners.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ctx = AlphaCtx::with_prefix("__run");
+    let mut inlined = HashSet::new();
+    let mut items = std::mem::take(&mut file.items);
+    for item in &mut items {
+        match item {
+            lir::Item::Fn(f) => {
+                inline_runner_calls(&mut f.value, &runners, file, &mut ctx, &mut inlined)
+            }
+            lir::Item::Impl(impl_decl) => {
+                for m in &mut impl_decl.methods {
+                    inline_runner_calls(&mut m.value, &runners, file, &mut ctx, &mut inlined);
+                }
+            }
+            _ => {}
+        }
+    }
+    file.items = items;
+
+    let mut inlined: Vec<String> = inlined.into_iter().collect();
+    inlined.sort();
+    inlined
+}
+
+/// The params of each runner in `file`. A call left after
+/// [`inline_runners`] passed one of them something other than a literal.
+pub fn runner_params(file: &lir::File) -> HashMap<String, Vec<lir::Param>> {
+    find_runners(file).into_iter().map(|(name, r)| (name, r.params)).collect()
+}
+
+fn find_runners(file: &lir::File) -> HashMap<String, Runner> {
+    // A fn that only hands its literals on to another runner is one too.
+    let mut runners: HashMap<String, Runner> = HashMap::new();
+    loop {
+        let found: Vec<(String, Runner)> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                lir::Item::Fn(f) if !runners.contains_key(&f.name) => {
+                    runner(f, &runners).map(|r| (f.name.clone(), r))
+                }
+                _ => None,
+            })
+            .collect();
+        if found.is_empty() {
+            break;
+        }
+        runners.extend(found);
+    }
+    runners
+}
+
+fn is_literal_param(p: &lir::Param) -> bool {
+    matches!(p.ty.value, TypeExpr::Thunk(_) | TypeExpr::Fn { .. })
+}
+
+/// `f` as a runner: it has a `thunk` or `fn` param, each `thunk` param is
+/// used exactly once, either forced or handed on to one of `runners`, its
+/// body contains a `handle`, calls one of `runners` or calls a `fn` param,
+/// and it does not call itself.
+fn runner(f: &lir::FnDecl, runners: &HashMap<String, Runner>) -> Option<Runner> {
+    if !f.params.iter().any(is_literal_param) {
+        return None;
+    }
+    let body = strip_thunk_lambdas(f.value.clone(), f.params.len());
+    if count_idents(&body, &f.name) > 0 {
+        return None;
+    }
+    let mut heads = Vec::new();
+    collect_call_heads(&body, &mut heads);
+    let calls_fn_param = f
+        .params
+        .iter()
+        .any(|p| matches!(p.ty.value, TypeExpr::Fn { .. }) && heads.contains(&p.name));
+    let calls_runner = heads.iter().any(|h| runners.contains_key(h));
+    if !contains_handle(&body) && !calls_fn_param && !calls_runner {
+        return None;
+    }
+    for p in f.params.iter().filter(|p| matches!(p.ty.value, TypeExpr::Thunk(_))) {
+        if count_idents(&body, &p.name) != 1
+            || (count_forced(&body, &p.name) != 1 && !passed_to_runner(&body, &p.name, runners))
+        {
+            return None;
+        }
+    }
+    Some(Runner { params: f.params.clone(), body })
+}
+
+fn inline_runner_calls(
+    expr: &mut lir::Expr,
+    runners: &HashMap<String, Runner>,
+    file: &mut lir::File,
+    ctx: &mut AlphaCtx,
+    inlined: &mut HashSet<String>,
+) {
+    // `let t = thunk ..; run(t)`: put the literal back where the runner
+    // can take it.
+    if let lir::Expr::Let { id, name, value, body } = expr {
+        if matches!(value.as_ref(), lir::Expr::Thunk { .. })
+            && count_idents(body, name) == 1
+            && passed_to_runner(body, name, runners)
+        {
+            let mut bound = Vec::new();
+            binders(body, &mut bound);
+            let mut free = Vec::new();
+            collect_idents(value, &mut free);
+            if !free.iter().any(|f| bound.contains(f)) {
+                let mut body = std::mem::replace(body.as_mut(), lir::Expr::Error { id: *id });
+                replace_ident(&mut body, name, *value.clone());
+                *expr = body;
+                inline_runner_calls(expr, runners, file, ctx, inlined);
+                return;
+            }
+        }
+    }
+    if let Some((name, args)) = match_call_chain(expr) {
+        if let Some(runner) = runners.get(&name) {
+            if let Some(new_expr) = instantiate(runner, args, expr.id(), file, ctx) {
+                *expr = new_expr;
+                inlined.insert(name);
+                // The literals' bodies may call runners in turn.
+                inline_runner_calls(expr, runners, file, ctx, inlined);
+                return;
+            }
+        }
+    }
+    for child in children_mut(expr) {
+        inline_runner_calls(child, runners, file, ctx, inlined);
+    }
+}
+
+/// The runner's body at one call site: value params bound by `let`, each
+/// `force p` of a thunk param replaced by the argument thunk's body, a
+/// thunk param handed on to another runner by the argument thunk, each
+/// call of a fn param by the argument fn's body, and the runner's own
+/// bindings renamed so they can't capture the caller's.
+fn instantiate(
+    runner: &Runner,
+    args: Vec<lir::Expr>,
+    call_id: lumo_types::ExprId,
+    file: &mut lir::File,
+    ctx: &mut AlphaCtx,
+) -> Option<lir::Expr> {
+    if args.len() != runner.params.len() {
+        return None;
+    }
+    // Every param gets a fresh name, so the literals' bodies, spliced in
+    // below, can't capture or be mistaken for the runner's own names.
+    let mut renames: Vec<(String, String)> = Vec::new();
+    let mut forced: Vec<(String, lir::Expr)> = Vec::new();
+    let mut called: Vec<(String, lir::Expr)> = Vec::new();
+    let mut bound: Vec<(String, lir::Expr)> = Vec::new();
+    for (p, a) in runner.params.iter().zip(args) {
+        let fresh = ctx.fresh(&p.name);
+        renames.push((p.name.clone(), fresh.clone()));
+        match &p.ty.value {
+            TypeExpr::Thunk(_) => {
+                if !matches!(a, lir::Expr::Thunk { .. }) {
+                    return None;
+                }
+                forced.push((fresh, a));
+            }
+            TypeExpr::Fn { .. } => {
+                if !matches!(&a, lir::Expr::Thunk { expr, .. } if matches!(expr.as_ref(), lir::Expr::Lambda { .. }))
+                {
+                    return None;
+                }
+                called.push((fresh, a));
+            }
+            _ => bound.push((fresh, a)),
+        }
+    }
+
+    let mut body = runner.body.clone();
+    fresh_ids(&mut body, &mut file.spans);
+    rename_free_idents(&mut body, &renames);
+    alpha_rename_bindings(&mut body, ctx);
+    for (name, thunk) in forced {
+        if count_forced(&body, &name) == 1 {
+            let lir::Expr::Thunk { expr, .. } = thunk else { unreachable!("checked above") };
+            replace_force(&mut body, &name, *expr);
+        } else {
+            replace_ident(&mut body, &name, thunk);
+        }
+    }
+    for (name, lambda) in called {
+        replace_calls(&mut body, &name, &lambda, &mut file.spans, ctx);
+    }
+
+    let span = file.spans[call_id.0 as usize];
+    for (fresh, a) in bound.into_iter().rev() {
+        body = lir::Expr::Let {
+            id: alloc_id(&mut file.spans, span),
+            name: fresh,
+            value: Box::new(a),
+            body: Box::new(body),
+        };
+    }
+    Some(body)
+}
+
+/// Replace the `force name` in `expr` with `with`.
+fn replace_force(expr: &mut lir::Expr, name: &str, with: lir::Expr) {
+    fn walk(expr: &mut lir::Expr, name: &str, with: &mut Option<lir::Expr>) {
+        if let lir::Expr::Force { expr: inner, .. } = expr {
+            if matches!(inner.as_ref(), lir::Expr::Ident { name: n, .. } if n == name) {
+                if let Some(with) = with.take() {
+                    *expr = with;
+                }
+                return;
+            }
+        }
+        for child in children_mut(expr) {
+            walk(child, name, with);
+        }
+    }
+    walk(expr, name, &mut Some(with));
+}
+
+/// Replace the one use of the identifier `name` in `expr` with `with`.
+fn replace_ident(expr: &mut lir::Expr, name: &str, with: lir::Expr) {
+    fn walk(expr: &mut lir::Expr, name: &str, with: &mut Option<lir::Expr>) {
+        if matches!(expr, lir::Expr::Ident { name: n, .. } if n == name) {
+            if let Some(with) = with.take() {
+                *expr = with;
+            }
+            return;
+        }
+        for child in children_mut(expr) {
+            walk(child, name, with);
+        }
+    }
+    walk(expr, name, &mut Some(with));
+}
+
+/// Replace each call `name(a, ..)` in `expr` with a fresh copy of the fn
+/// literal `lambda`'s body, its params bound by `let` to the args, and any
+/// other use of `name` with the literal itself, for a runner to take in turn.
+fn replace_calls(
+    expr: &mut lir::Expr,
+    name: &str,
+    lambda: &lir::Expr,
+    spans: &mut Vec<Span>,
+    ctx: &mut AlphaCtx,
+) {
+    let is_name = |e: &lir::Expr| matches!(e, lir::Expr::Ident { name: n, .. } if n == name);
+    let mut args: Vec<lir::Expr> = Vec::new();
+    let mut cur = &*expr;
+    while let lir::Expr::Apply { callee, arg, .. } = cur {
+        args.push((**arg).clone());
+        cur = callee;
+    }
+    if let lir::Expr::Force { expr: head, .. } = cur {
+        if is_name(head) && !args.is_empty() {
+            args.reverse();
+            for a in &mut args {
+                replace_calls(a, name, lambda, spans, ctx);
+            }
+            let span = spans[expr.id().0 as usize];
+            let lir::Expr::Thunk { expr: chain, .. } = lambda else {
+                unreachable!("fn literals are thunked lambdas")
+            };
+            let mut chain = (**chain).clone();
+            fresh_ids(&mut chain, spans);
+            let mut params = Vec::new();
+            while let lir::Expr::Lambda { param, body, .. } = chain {
+                if params.len() == args.len() {
+                    chain = lir::Expr::Lambda { id: alloc_id(spans, span), param, body };
+                    break;
+                }
+                params.push(param);
+                chain = *body;
+            }
+            let mut out = chain;
+            for (param, arg) in params.into_iter().zip(args).rev() {
+                out = lir::Expr::Let {
+                    id: alloc_id(spans, span),
+                    name: param,
+                    value: Box::new(arg),
+                    body: Box::new(out),
+                };
+            }
+            alpha_rename_bindings(&mut out, ctx);
+            *expr = out;
+            return;
+        }
+    }
+    if is_name(expr) {
+        let mut copy = lambda.clone();
+        fresh_ids(&mut copy, spans);
+        *expr = copy;
+        return;
+    }
+    for child in children_mut(expr) {
+        replace_calls(child, name, lambda, spans, ctx);
+    }
+}
+
+/// Lower every `handle` whose performs are all visible to LTO: see the
+/// module docs. Returns the names of the fn clones made, sorted.
+pub fn lower_local_handlers(file: &mut lir::File) -> Vec<String> {
+    let fns: HashMap<String, lir::FnDecl> = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            lir::Item::Fn(f) => Some((f.name.clone(), f.clone())),
+            _ => None,
+        })
+        .collect();
+    let mut value_refs = HashSet::new();
+    let mut method_caps = HashSet::new();
+    for item in &file.items {
+        match item {
+            lir::Item::Fn(f) => collect_value_refs(&f.value, &fns, false, &mut value_refs),
+            lir::Item::Impl(impl_decl) => {
+                for m in &impl_decl.methods {
+                    collect_value_refs(&m.value, &fns, false, &mut value_refs);
+                    if impl_decl.capability.is_none() {
+                        collect_performed_caps(&m.value, &mut method_caps);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut items = std::mem::take(&mut file.items);
+    let mut lowering = Lowering {
+        fns,
+        value_refs,
+        method_caps,
+        spans: &mut file.spans,
+        ctx: AlphaCtx::with_prefix("__hdl"),
+        clones: Vec::new(),
+        counter: 0,
+    };
+    for item in &mut items {
+        if let lir::Item::Fn(f) = item {
+            let mut scope: Vec<String> = f.params.iter().map(|p| p.name.clone()).collect();
+            lowering.walk(&mut f.value, &mut scope);
+        }
+    }
+    // Clones can hold handles of other caps in turn.
+    let mut next = 0;
+    while next < lowering.clones.len() {
+        let mut clone = lowering.clones[next].clone();
+        let mut scope: Vec<String> = clone.params.iter().map(|p| p.name.clone()).collect();
+        lowering.walk(&mut clone.value, &mut scope);
+        lowering.clones[next] = clone;
+        next += 1;
+    }
+
+    let mut names: Vec<String> = lowering.clones.iter().map(|f| f.name.clone()).collect();
+    names.sort();
+    items.extend(lowering.clones.into_iter().map(lir::Item::Fn));
+    file.items = items;
+    names
+}
+
+struct Lowering<'a> {
+    /// Every fn as it was before lowering.
+    fns: HashMap<String, lir::FnDecl>,
+    /// Fns named other than as a call's callee.
+    value_refs: HashSet<String>,
+    /// Caps inherent impl methods perform.
+    method_caps: HashSet<String>,
+    spans: &'a mut Vec<Span>,
+    ctx: AlphaCtx,
+    clones: Vec<lir::FnDecl>,
+    counter: usize,
+}
+
+/// One handle being lowered: its cap, its ops, the locals they capture and
+/// the clone each fn that needs the cap is redirected to.
+struct Plan<'e> {
+    cap: String,
+    type_args: Vec<String>,
+    entries: &'e [lir::BundleEntry],
+    captures: Vec<String>,
+    redirects: HashMap<String, String>,
+}
+
+impl Lowering<'_> {
+    /// Lower the handles in `expr`, innermost first, so an outer handle of
+    /// the same cap no longer sees one in its body. `scope` holds the
+    /// locals bound around `expr`.
+    fn walk(&mut self, expr: &mut lir::Expr, scope: &mut Vec<String>) {
+        match expr {
+            lir::Expr::Let { value, body, name, .. }
+            | lir::Expr::Loop { init: value, body, var: name, .. } => {
+                self.walk(value, scope);
+                scope.push(name.clone());
+                self.walk(body, scope);
+                scope.pop();
+            }
+            lir::Expr::Lambda { param, body, .. } => {
+                scope.push(param.clone());
+                self.walk(body, scope);
+                scope.pop();
+            }
+            lir::Expr::Match { scrutinee, arms, .. } => {
+                self.walk(scrutinee, scope);
+                for arm in arms {
+                    let depth = scope.len();
+                    pattern_binders(&arm.pattern, scope);
+                    self.walk(&mut arm.body, scope);
+                    scope.truncate(depth);
+                }
+            }
+            lir::Expr::Bundle { entries, .. } => {
+                for e in entries {
+                    let depth = scope.len();
+                    scope.extend(e.params.iter().map(|p| p.name.clone()));
+                    self.walk(&mut e.body, scope);
+                    scope.truncate(depth);
+                }
+            }
+            _ => {
+                for child in children_mut(expr) {
+                    self.walk(child, scope);
+                }
+            }
+        }
+        if let lir::Expr::Handle { cap, type_args, handler, body, .. } = expr {
+            if let Some(lowered) = self.lower(cap, type_args, handler, body, scope) {
+                *expr = lowered;
+            }
+        }
+    }
+
+    /// `body` with the handler's ops inlined, or `None` if some perform of
+    /// the cap might escape the rewrite.
+    fn lower(
+        &mut self,
+        cap: &str,
+        type_args: &[String],
+        handler: &lir::Expr,
+        body: &lir::Expr,
+        scope: &[String],
+    ) -> Option<lir::Expr> {
+        let lir::Expr::Bundle { entries, .. } = handler else {
+            return None;
+        };
+        // A forced local, such as a `thunk` param, may perform the cap out
+        // of the rewrite's sight.
+        if self.method_caps.contains(cap)
+            || contains_handle_of(body, cap)
+            || forces_any(body, scope)
+        {
+            return None;
+        }
+        let key = TypeExpr::Cap {
+            name: cap.to_owned(),
+            type_args: type_args.iter().map(|a| TypeExpr::Named(a.clone())).collect(),
+        }
+        .cap_mangled_param();
+
+        let mut captures: Vec<String> = Vec::new();
+        // What the ops need in turn, which the clones take on in place of
+        // the cap.
+        let mut op_caps: Vec<CapEntry> = Vec::new();
+        for e in entries {
+            if !tail_resumes(&e.body) {
+                return None;
+            }
+            collect_needed_caps(&e.body, &self.fns, &mut op_caps);
+            let mut bound: Vec<String> = e.params.iter().map(|p| p.name.clone()).collect();
+            binders(&e.body, &mut bound);
+            let mut idents = Vec::new();
+            collect_idents(&e.body, &mut idents);
+            for name in idents {
+                if scope.contains(&name) && !bound.contains(&name) && !captures.contains(&name) {
+                    captures.push(name);
+                }
+            }
+        }
+        let shadows = |expr: &lir::Expr| {
+            let mut bound = Vec::new();
+            binders(expr, &mut bound);
+            bound.iter().any(|b| captures.contains(b))
+        };
+        if shadows(body) {
+            return None;
+        }
+
+        // Fns reached from `body` that need the cap, through each other.
+        let mut reached: Vec<String> = Vec::new();
+        let mut queue = vec![body];
+        while let Some(expr) = queue.pop() {
+            let mut heads = Vec::new();
+            collect_call_heads(expr, &mut heads);
+            for name in heads {
+                let Some(f) = self.fns.get(&name) else { continue };
+                if reached.contains(&name) || !needs(f, &key) {
+                    continue;
+                }
+                let params: Vec<String> = f.params.iter().map(|p| p.name.clone()).collect();
+                if self.value_refs.contains(&name)
+                    || contains_handle_of(&f.value, cap)
+                    || shadows(&f.value)
+                    || forces_any(&f.value, &params)
+                {
+                    return None;
+                }
+                reached.push(name);
+                queue.push(&f.value);
+            }
+        }
+        reached.sort();
+
+        let mut redirects = HashMap::new();
+        for name in &reached {
+            redirects.insert(name.clone(), format!("{name}__{cap}_{}", self.counter));
+            self.counter += 1;
+        }
+        let plan = Plan {
+            cap: cap.to_owned(),
+            type_args: type_args.to_vec(),
+            entries,
+            captures,
+            redirects,
+        };
+
+        for name in &reached {
+            let f = self.fns[name].clone();
+            let mut value = f.value.clone();
+            fresh_ids(&mut value, self.spans);
+            self.rewrite(&mut value, &plan);
+            if let lir::Expr::Thunk { expr, .. } = &mut value {
+                for c in plan.captures.iter().rev() {
+                    let id = alloc_id(self.spans, f.span);
+                    let inner = std::mem::replace(expr.as_mut(), lir::Expr::Error { id });
+                    **expr = lir::Expr::Lambda { id, param: c.clone(), body: Box::new(inner) };
+                }
+            }
+            let mut params: Vec<lir::Param> = plan
+                .captures
+                .iter()
+                .map(|c| lir::Param {
+                    name: c.clone(),
+                    // The capture's type isn't tracked in LIR; `_` matches any.
+                    ty: Spanned { value: TypeExpr::Named("_".to_owned()), span: f.span },
+                    span: f.span,
+                })
+                .collect();
+            params.extend(f.params.iter().cloned());
+            let cap_row = f.cap.as_ref().map(|row| {
+                let mut row: Vec<CapEntry> = row
+                    .iter()
+                    .filter(|e| e.cap_mangled_param().as_deref() != Some(key.as_str()))
+                    .cloned()
+                    .collect();
+                for c in &op_caps {
+                    if !row.iter().any(|e| e.cap_mangled_param() == c.cap_mangled_param()) {
+                        row.push(c.clone());
+                    }
+                }
+                row
+            });
+            let clone = lir::FnDecl {
+                name: plan.redirects[name].clone(),
+                params,
+                cap: cap_row,
+                value,
+                ..f
+            };
+            // A handle around this one reaches the clone in place of `f`.
+            self.fns.insert(clone.name.clone(), clone.clone());
+            self.clones.push(clone);
+        }
+
+        let mut lowered = body.clone();
+        self.rewrite(&mut lowered, &plan);
+        Some(lowered)
+    }
+
+    /// Inline the plan's ops at their performs in `expr` and redirect calls
+    /// to the fns it clones.
+    fn rewrite(&mut self, expr: &mut lir::Expr, plan: &Plan) {
+        if let Some(inlined) = self.inline_op(expr, plan) {
+            *expr = inlined;
+            return;
+        }
+        if let lir::Expr::Force { id, expr: inner } = expr {
+            if let lir::Expr::Ident { name, .. } = inner.as_mut() {
+                if let Some(clone) = plan.redirects.get(name) {
+                    *name = clone.clone();
+                    let id = *id;
+                    let span = self.spans[id.0 as usize];
+                    for c in &plan.captures {
+                        let callee = std::mem::replace(expr, lir::Expr::Error { id });
+                        *expr = lir::Expr::Apply {
+                            id: alloc_id(self.spans, span),
+                            callee: Box::new(callee),
+                            arg: Box::new(lir::Expr::Ident {
+                                id: alloc_id(self.spans, span),
+                                name: c.clone(),
+                            }),
+                        };
+                    }
+                    return;
+                }
+            }
+        }
+        for child in children_mut(expr) {
+            self.rewrite(child, plan);
+        }
+    }
+
+    /// The op body for a perform of the plan's cap, its params bound by
+    /// `let` to the rewritten args and `resume(v)` reduced to `v`. The body
+    /// itself is left alone: its performs of the cap, and its calls to fns
+    /// that need it, go to the handler around the `handle`, as they did.
+    fn inline_op(&mut self, expr: &lir::Expr, plan: &Plan) -> Option<lir::Expr> {
+        let mut args: Vec<lir::Expr> = Vec::new();
+        let mut cur = expr;
+        while let lir::Expr::Apply { callee, arg, .. } = cur {
+            args.push((**arg).clone());
+            cur = callee;
+        }
+        args.reverse();
+        if let lir::Expr::Force { expr, .. } = cur {
+            cur = expr;
+        }
+        let lir::Expr::Member { id, object, field } = cur else {
+            return None;
+        };
+        let lir::Expr::Perform { cap, type_args, .. } = object.as_ref() else {
+            return None;
+        };
+        if *cap != plan.cap || *type_args != plan.type_args {
+            return None;
+        }
+        let entry = plan.entries.iter().find(|e| e.name == *field)?;
+        if entry.params.len() != args.len() {
+            return None;
+        }
+
+        let span = self.spans[id.0 as usize];
+        let mut inlined = entry.body.clone();
+        fresh_ids(&mut inlined, self.spans);
+        let fresh: Vec<String> = entry.params.iter().map(|p| self.ctx.fresh(&p.name)).collect();
+        let renames: Vec<(String, String)> = entry
+            .params
+            .iter()
+            .zip(&fresh)
+            .map(|(p, f)| (p.name.clone(), f.clone()))
+            .collect();
+        rename_free_idents(&mut inlined, &renames);
+        strip_resume(&mut inlined);
+        alpha_rename_bindings(&mut inlined, &mut self.ctx);
+        for (name, mut a) in fresh.into_iter().zip(args).rev() {
+            self.rewrite(&mut a, plan);
+            inlined = lir::Expr::Let {
+                id: alloc_id(self.spans, span),
+                name,
+                value: Box::new(a),
+                body: Box::new(inlined),
+            };
+        }
+        Some(inlined)
+    }
+}
+
+/// Whether `f`'s cap row holds the cap with runtime key `key`.
+fn needs(f: &lir::FnDecl, key: &str) -> bool {
+    f.cap
+        .iter()
+        .flatten()
+        .any(|e| matches!(e, CapEntry::Cap(_)) && e.cap_mangled_param().as_deref() == Some(key))
+}
+
+/// `resume` is only called last, once per path, and nowhere else.
+fn tail_resumes(expr: &lir::Expr) -> bool {
+    match expr {
+        lir::Expr::Apply { callee, arg, .. } if is_resume(callee) => count_idents(arg, "resume") == 0,
+        lir::Expr::Let { value, body, .. } => count_idents(value, "resume") == 0 && tail_resumes(body),
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            count_idents(scrutinee, "resume") == 0 && arms.iter().all(|a| tail_resumes(&a.body))
+        }
+        lir::Expr::Ann { expr, .. } => tail_resumes(expr),
+        _ => false,
+    }
+}
+
+fn is_resume(callee: &lir::Expr) -> bool {
+    matches!(callee, lir::Expr::Force { expr, .. }
+        if matches!(expr.as_ref(), lir::Expr::Ident { name, .. } if name == "resume"))
+}
+
+/// The caps `expr` performs, or needs through the fns it calls.
+fn collect_needed_caps(expr: &lir::Expr, fns: &HashMap<String, lir::FnDecl>, out: &mut Vec<CapEntry>) {
+    let mut found: Vec<CapEntry> = Vec::new();
+    collect_performs(expr, &mut found);
+    let mut heads = Vec::new();
+    collect_call_heads(expr, &mut heads);
+    for h in heads {
+        if let Some(row) = fns.get(&h).and_then(|f| f.cap.as_ref()) {
+            found.extend(row.iter().filter(|e| matches!(e, CapEntry::Cap(_))).cloned());
+        }
+    }
+    for c in found {
+        if !out.iter().any(|e| e.cap_mangled_param() == c.cap_mangled_param()) {
+            out.push(c);
+        }
+    }
+}
+
+fn collect_performs(expr: &lir::Expr, out: &mut Vec<CapEntry>) {
+    if let lir::Expr::Perform { cap, type_args, .. } = expr {
+        out.push(CapEntry::Cap(TypeExpr::Cap {
+            name: cap.clone(),
+            type_args: type_args.iter().map(|a| TypeExpr::Named(a.clone())).collect(),
+        }));
+    }
+    for child in children(expr) {
+        collect_performs(child, out);
+    }
+}
+
+fn contains_handle_of(expr: &lir::Expr, cap: &str) -> bool {
+    matches!(expr, lir::Expr::Handle { cap: c, .. } if c == cap)
+        || children(expr).into_iter().any(|c| contains_handle_of(c, cap))
+}
+
+fn collect_performed_caps(expr: &lir::Expr, out: &mut HashSet<String>) {
+    if let lir::Expr::Perform { cap, .. } = expr {
+        out.insert(cap.clone());
+    }
+    for child in children(expr) {
+        collect_performed_caps(child, out);
+    }
+}
+
+/// Fns named in callee position: `f` in `force f`.
+fn collect_call_heads(expr: &lir::Expr, out: &mut Vec<String>) {
+    if let lir::Expr::Force { expr: inner, .. } = expr {
+        if let lir::Expr::Ident { name, .. } = inner.as_ref() {
+            out.push(name.clone());
+        }
+    }
+    for child in children(expr) {
+        collect_call_heads(child, out);
+    }
+}
+
+/// Fns in `fns` named anywhere but in callee position.
+fn collect_value_refs(
+    expr: &lir::Expr,
+    fns: &HashMap<String, lir::FnDecl>,
+    forced: bool,
+    out: &mut HashSet<String>,
+) {
+    if let lir::Expr::Ident { name, .. } = expr {
+        if !forced && fns.contains_key(name) {
+            out.insert(name.clone());
+        }
+    }
+    let forced = matches!(expr, lir::Expr::Force { .. });
+    for child in children(expr) {
+        collect_value_refs(child, fns, forced, out);
+    }
+}
+
+fn collect_idents(expr: &lir::Expr, out: &mut Vec<String>) {
+    if let lir::Expr::Ident { name, .. } = expr {
+        out.push(name.clone());
+    }
+    for child in children(expr) {
+        collect_idents(child, out);
+    }
+}
+
+/// Every name `expr` binds.
+fn binders(expr: &lir::Expr, out: &mut Vec<String>) {
+    match expr {
+        lir::Expr::Let { name, .. } | lir::Expr::Loop { var: name, .. } => out.push(name.clone()),
+        lir::Expr::Lambda { param, .. } => out.push(param.clone()),
+        lir::Expr::Match { arms, .. } => {
+            for arm in arms {
+                pattern_binders(&arm.pattern, out);
+            }
+        }
+        lir::Expr::Bundle { entries, .. } => {
+            for e in entries {
+                out.extend(e.params.iter().map(|p| p.name.clone()));
+            }
+        }
+        _ => {}
+    }
+    for child in children(expr) {
+        binders(child, out);
+    }
+}
+
+fn pattern_binders(pattern: &Pattern, out: &mut Vec<String>) {
+    match pattern {
+        Pattern::Wildcard => {}
+        Pattern::Bind(name) => out.push(name.clone()),
+        Pattern::Ctor { args, .. } => {
+            for a in args {
+                pattern_binders(a, out);
+            }
+        }
+    }
+}
+
+fn contains_handle(expr: &lir::Expr) -> bool {
+    matches!(expr, lir::Expr::Handle { .. }) || children(expr).into_iter().any(contains_handle)
+}
+
+/// Occurrences of the identifier `name` in `expr`.
+fn count_idents(expr: &lir::Expr, name: &str) -> usize {
+    let own = usize::from(matches!(expr, lir::Expr::Ident { name: n, .. } if n == name));
+    own + children(expr).into_iter().map(|c| count_idents(c, name)).sum::<usize>()
+}
+
+/// Occurrences of `force name` in `expr`.
+fn count_forced(expr: &lir::Expr, name: &str) -> usize {
+    if let lir::Expr::Force { expr: inner, .. } = expr {
+        if matches!(inner.as_ref(), lir::Expr::Ident { name: n, .. } if n == name) {
+            return 1;
+        }
+    }
+    children(expr).into_iter().map(|c| count_forced(c, name)).sum()
+}
+
+/// Whether `expr` passes the identifier `name` as a `thunk` argument to
+/// one of `runners`.
+fn passed_to_runner(expr: &lir::Expr, name: &str, runners: &HashMap<String, Runner>) -> bool {
+    if let Some((head, args)) = match_call_chain(expr) {
+        if let Some(r) = runners.get(&head) {
+            let hit = r.params.iter().zip(&args).any(|(p, a)| {
+                matches!(p.ty.value, TypeExpr::Thunk(_))
+                    && matches!(a, lir::Expr::Ident { name: n, .. } if n == name)
+            });
+            if hit {
+                return true;
+            }
+        }
+    }
+    children(expr).into_iter().any(|c| passed_to_runner(c, name, runners))
+}
+
+/// Whether `expr` forces any of `names`.
+fn forces_any(expr: &lir::Expr, names: &[String]) -> bool {
+    if let lir::Expr::Force { expr: inner, .. } = expr {
+        if matches!(inner.as_ref(), lir::Expr::Ident { name, .. } if names.contains(name)) {
+            return true;
+        }
+    }
+    children(expr).into_iter().any(|c| forces_any(c, names))
+}
+
+pub(super) fn children(expr: &lir::Expr) -> Vec<&lir::Expr> {
+    match expr {
+        lir::Expr::Apply { callee, arg, .. } => vec![callee, arg],
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Ann { expr, .. } => vec![expr],
+        lir::Expr::Lambda { body, .. } => vec![body],
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            vec![value, body]
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            std::iter::once(scrutinee.as_ref()).chain(arms.iter().map(|a| &a.body)).collect()
+        }
+        lir::Expr::Handle { handler, body, .. } => vec![handler, body],
+        lir::Expr::Bundle { entries, .. } => entries.iter().map(|e| &e.body).collect(),
+        lir::Expr::Ctor { args, .. } => args.iter().collect(),
+        lir::Expr::Member { object, .. } => vec![object],
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Perform { .. }
+        | lir::Expr::Error { .. } => Vec::new(),
+    }
+}
+
+fn children_mut(expr: &mut lir::Expr) -> Vec<&mut lir::Expr> {
+    match expr {
+        lir::Expr::Apply { callee, arg, .. } => vec![callee, arg],
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Ann { expr, .. } => vec![expr],
+        lir::Expr::Lambda { body, .. } => vec![body],
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            vec![value, body]
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => std::iter::once(scrutinee.as_mut())
+            .chain(arms.iter_mut().map(|a| &mut a.body))
+            .collect(),
+        lir::Expr::Handle { handler, body, .. } => vec![handler, body],
+        lir::Expr::Bundle { entries, .. } => entries.iter_mut().map(|e| &mut e.body).collect(),
+        lir::Expr::Ctor { args, .. } => args.iter_mut().collect(),
+        lir::Expr::Member { object, .. } => vec![object],
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Perform { .. }
+        | lir::Expr::Error { .. } => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hir, lexer::lex, parser::parse};
+
+    fn lower(src: &str) -> lir::File {
+        let lexed = lex(src);
+        let parsed = parse(&lexed.tokens, &lexed.errors);
+        lir::lower(&hir::lower(&parsed.file))
+    }
+
+    fn fn_value<'a>(file: &'a lir::File, name: &str) -> &'a lir::Expr {
+        file.items
+            .iter()
+            .find_map(|item| match item {
+                lir::Item::Fn(f) if f.name == name => Some(&f.value),
+                _ => None,
+            })
+            .expect("fn exists")
+    }
+
+    const RUNNER_SRC: &str = r#"
+        extern type Number;
+        cap Reader { fn ask(): Self }
+        fn run_reader[R, A](env: R, body: thunk A): A =
+          handle Reader with bundle { fn ask() = resume(env) } in force body
+    "#;
+
+    #[test]
+    fn inlines_runner_calls_with_literal_thunks() {
+        let src = format!("{RUNNER_SRC}\nfn main(): Number = run_reader(1, thunk Reader.ask())");
+        let mut file = lower(&src);
+        assert_eq!(inline_runners(&mut file), ["run_reader"]);
+        let main = fn_value(&file, "main");
+        assert!(contains_handle_of(main, "Reader"));
+        assert!(!collect_heads(main).contains(&"run_reader".to_owned()));
+    }
+
+    #[test]
+    fn inlines_runners_that_hand_on_their_thunk_param() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn go(t: thunk Number): Number = run_reader(1, t)\n\
+             fn main(): Number = go(thunk Reader.ask())"
+        );
+        let mut file = lower(&src);
+        assert_eq!(inline_runners(&mut file), ["go", "run_reader"]);
+        let main = fn_value(&file, "main");
+        assert!(contains_handle_of(main, "Reader"));
+        let heads = collect_heads(main);
+        assert!(!heads.contains(&"go".to_owned()) && !heads.contains(&"run_reader".to_owned()), "{heads:?}");
+    }
+
+    #[test]
+    fn inlines_runner_calls_with_let_bound_thunks() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn main(): Number = {{ let t = thunk Reader.ask(); run_reader(1, t) }}"
+        );
+        let mut file = lower(&src);
+        assert_eq!(inline_runners(&mut file), ["run_reader"]);
+        assert!(contains_handle_of(fn_value(&file, "main"), "Reader"));
+    }
+
+    #[test]
+    fn leaves_runner_calls_with_shared_thunks_alone() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn main(): Number = {{ let t = thunk Reader.ask(); let a = run_reader(1, t); run_reader(a, t) }}"
+        );
+        let mut file = lower(&src);
+        assert!(inline_runners(&mut file).is_empty());
+        assert!(collect_heads(fn_value(&file, "main")).contains(&"run_reader".to_owned()));
+    }
+
+    #[test]
+    fn inlines_runner_calls_with_literal_fns() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn twice(f: fn(Number): Number): Number = run_reader(1, thunk f(f(Reader.ask())))\n\
+             fn main(): Number = twice(fn(x) {{ x }})"
+        );
+        let mut file = lower(&src);
+        let inlined = inline_runners(&mut file);
+        assert!(inlined.contains(&"twice".to_owned()), "{inlined:?}");
+        let main = fn_value(&file, "main");
+        assert!(contains_handle_of(main, "Reader"));
+        let heads = collect_heads(main);
+        assert!(!heads.contains(&"twice".to_owned()) && !heads.contains(&"f".to_owned()), "{heads:?}");
+    }
+
+    #[test]
+    fn inlines_calls_passing_fn_literals_without_a_handle() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn apply(f: fn(Number): Number, x: Number): Number = f(x)\n\
+             fn main(): Number = apply(fn(x) {{ x }}, 1)"
+        );
+        let mut file = lower(&src);
+        assert_eq!(inline_runners(&mut file), ["apply"]);
+        let heads = collect_heads(fn_value(&file, "main"));
+        assert!(!heads.contains(&"apply".to_owned()), "{heads:?}");
+    }
+
+    #[test]
+    fn lowers_tail_resumptive_handle_in_place() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn main(): Number = handle Reader with bundle {{ fn ask() = resume(7) }} in Reader.ask()"
+        );
+        let mut file = lower(&src);
+        lower_local_handlers(&mut file);
+        let main = fn_value(&file, "main");
+        assert!(!contains_handle(main));
+        let mut caps = HashSet::new();
+        collect_performed_caps(main, &mut caps);
+        assert!(caps.is_empty());
+    }
+
+    #[test]
+    fn keeps_handle_whose_op_does_not_tail_resume() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn main(): Number = handle Reader with bundle {{ fn ask() = 7 }} in Reader.ask()"
+        );
+        let mut file = lower(&src);
+        assert!(lower_local_handlers(&mut file).is_empty());
+        assert!(contains_handle_of(fn_value(&file, "main"), "Reader"));
+    }
+
+    #[test]
+    fn lowers_a_handle_whose_op_performs_its_own_cap() {
+        let src = "extern type Number;\n\
+            cap Emit { fn emit(v: Number) }\n\
+            fn count(n: Number): Unit / { Emit } = Emit.emit(n)\n\
+            fn main(): Unit =\n\
+              handle Emit with bundle { fn emit(v) = resume(Unit) } in\n\
+              handle Emit with bundle { fn emit(v) = resume(Emit.emit(v)) } in count(1)";
+        let mut file = lower(src);
+        // The inner op re-emits to the outer handle, whose clone of the
+        // inner clone of `count` takes it.
+        assert_eq!(lower_local_handlers(&mut file).len(), 2);
+        let main = fn_value(&file, "main");
+        assert!(!contains_handle(main));
+        let clone = collect_heads(main).pop().expect("main calls a clone");
+        let mut caps = HashSet::new();
+        collect_performed_caps(fn_value(&file, &clone), &mut caps);
+        assert!(caps.is_empty(), "{clone} still performs {caps:?}");
+    }
+
+    #[test]
+    fn keeps_handle_around_a_thunk_param() {
+        let src = format!(
+            "{RUNNER_SRC}\nfn go(t: thunk Number): Number = handle Reader with bundle {{ fn ask() = resume(7) }} in force t"
+        );
+        let mut file = lower(&src);
+        lower_local_handlers(&mut file);
+        assert!(contains_handle_of(fn_value(&file, "go"), "Reader"));
+    }
+
+    fn collect_heads(expr: &lir::Expr) -> Vec<String> {
+        let mut heads = Vec::new();
+        collect_call_heads(expr, &mut heads);
+        heads
+    }
+}
@@ -20,18 +20,36 @@ pub use emit::*;
 
 pub mod dce;
 
+pub mod handlers;
+
+pub mod mono;
+
+pub mod report;
+
+pub mod selective_cps;
+
 /// Run LTO optimizations and return any validation diagnostics.
 ///
 /// An empty `Vec` means success. A non-empty `Vec` means at least one
 /// hard error was found (e.g. `#[inline(always)]` on an unresolvable fn)
 /// and the caller should abort compilation.
 pub fn optimize(file: &mut lir::File) -> Vec<Diagnostic> {
+    optimize_with_report(file).0
+}
+
+/// Like [`optimize`], but also returns a [`report::LtoReport`] describing
+/// the call graph, dep-free statuses, cap dispatch sites and swept fns.
+pub fn optimize_with_report(file: &mut lir::File) -> (Vec<Diagnostic>, report::LtoReport) {
+    // Handles LTO can see every perform of go first, so the clones they
+    // leave behind are resolved like any other fn.
+    handlers::lower_local_handlers(file);
     let resolution = resolution::build_resolution_map(file);
     // Pass the resolution map so that Member(Perform(cap, type_args), method)
     // chains with a matching default impl resolve to `ImplMethod` edges
-    // instead of being opaquely classified as Indirect.
+    // instead of runtime-dispatched `Perform` edges.
     let cg = call_graph::build_call_graph_with_resolution(file, Some(&resolution));
     let analysis = dep_free::run(file, &resolution, &cg);
+    let mut report = report::LtoReport::new(file, &cg, &analysis);
 
     // Validate: every fn marked #[inline(always)] must be proven dep-free.
     let mut errors: Vec<Diagnostic> = Vec::new();
@@ -56,8 +74,8 @@ pub fn optimize(file: &mut lir::File) -> Vec<Diagnostic> {
 
     if errors.is_empty() {
         emit::transform(file, &analysis, &resolution);
-        dce::sweep(file);
+        report.swept = dce::sweep(file);
     }
 
-    errors
+    (errors, report)
 }
@@ -0,0 +1,1914 @@
+//! Generic monomorphization: specialize generic fns and data types per
+//! concrete instantiation.
+//!
+//! Starting from every non-generic fn and impl method, the pass walks
+//! bodies with a small local type inference (literals, ctor payloads,
+//! declared signatures, `let` and `match` bindings) and, at each call to a
+//! generic fn whose type arguments are all known, redirects the call to a
+//! specialized clone. Specialized bodies are walked in turn, so
+//! `list_reverse[A]` reached at `A = Number` also specializes its
+//! `list_reverse_acc` call. Concrete applications of generic data types
+//! (`List[Number]`) become fresh non-generic decls, and ctor and pattern
+//! names are qualified with the instance they build or match.
+//!
+//! Names are deterministic: `<name>__<arg>__<arg>`, where a nested
+//! application mangles as `<head>_<arg>_<arg>`, e.g. `List[Pair[A, B]]` at
+//! `A = Number, B = String` becomes `List__Pair_Number_String`.
+//!
+//! Inference is best effort. Anything it cannot pin down (a type argument
+//! only fixed by a lambda, a cap op returning `Self`) keeps referring to the
+//! generic original, which then stays in the file. Originals are dropped
+//! once nothing refers to them.
+//!
+//! Methods of generic impls specialize the same way, into free fns named
+//! after the impl and method: `Map.get` at `K = String, V = Number` becomes
+//! `Map_get__String__Number`, and calls to it are redirected there.
+//!
+//! A generic cap impl (`impl[A: PartialEq] List[A]: PartialEq`) is looked
+//! up by its target type, so both passes clone it for each concrete target
+//! a `perform` is seen at: `PartialEq.eq` on a `List[Number]` mints
+//! `__impl_List__Number_PartialEq`. The generic impl itself is dropped.
+//!
+//! The JS backend shares one runtime representation between every
+//! instantiation, so only the Rust backend runs the full pass. Every backend
+//! runs [`specialize_bounded`], which only clones callables whose generics
+//! carry bounds: a cap like `Hash[K]` is looked up by type at runtime, so
+//! its body needs `K` fixed.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use lumo_lir as lir;
+use lumo_span::Span;
+use lumo_types::{CapEntry, Pattern, Spanned, TypeExpr};
+
+/// Type nesting beyond which an instantiation is left generic. Guards
+/// against polymorphic recursion (`data Nested[A] { .n(Nested[List[A]]) }`)
+/// minting instances forever.
+const MAX_TYPE_DEPTH: usize = 8;
+
+/// An unknown type, or an unknown argument inside a partially known one.
+fn hole() -> TypeExpr {
+    TypeExpr::Var("_".to_owned())
+}
+
+fn is_hole(ty: &TypeExpr) -> bool {
+    matches!(ty, TypeExpr::Var(v) if v == "_")
+}
+
+/// Signature of a callable, with the generics it is polymorphic over.
+#[derive(Clone)]
+struct Sig {
+    generics: Vec<String>,
+    params: Vec<TypeExpr>,
+    ret: TypeExpr,
+}
+
+/// Specialize generic fns and data types reachable at concrete types.
+/// Returns the names of the created instances, sorted.
+pub fn monomorphize(file: &mut lir::File) -> Vec<String> {
+    run(file, false)
+}
+
+/// Specialize only the generic fns and impl methods with bounded generics
+/// (`fn f[K: Hash](k: K)`), leaving data types and unbounded generics
+/// shared. Returns the names of the created instances, sorted.
+pub fn specialize_bounded(file: &mut lir::File) -> Vec<String> {
+    run(file, true)
+}
+
+fn run(file: &mut lir::File, bounded: bool) -> Vec<String> {
+    let mut mono = Mono::new(file, bounded);
+
+    let mut roots: Vec<lir::FnDecl> = Vec::new();
+    for item in &file.items {
+        if let lir::Item::Fn(f) = item {
+            if !specializable(&f.generics, bounded) {
+                roots.push(f.clone());
+            }
+        }
+    }
+    for f in &mut roots {
+        mono.visit_fn(f);
+    }
+    let mut root_iter = roots.into_iter();
+    for item in &mut file.items {
+        match item {
+            lir::Item::Fn(f) if !specializable(&f.generics, bounded) => {
+                *f = root_iter.next().expect("one visited clone per root");
+            }
+            lir::Item::Impl(impl_decl)
+                if !specializable(&impl_decl.generics, bounded) && !is_generic_cap_impl(impl_decl) =>
+            {
+                for m in &mut impl_decl.methods {
+                    mono.visit_method(m);
+                }
+            }
+            _ => {}
+        }
+    }
+    mono.drain();
+
+    // Signatures may mention concrete applications no body walked through
+    // (a param that is never used, a ctor-free data decl).
+    for item in &mut file.items {
+        match item {
+            lir::Item::Data(d) if d.generics.is_empty() => {
+                for v in &mut d.variants {
+                    for p in &mut v.payload {
+                        p.value = mono.mono_type(&p.value);
+                    }
+                }
+            }
+            lir::Item::ExternFn(e) => {
+                for p in &mut e.params {
+                    p.ty.value = mono.mono_type(&p.ty.value);
+                }
+                if let Some(r) = &mut e.return_type {
+                    r.value = mono.mono_type(&r.value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Mono {
+        fn_instances,
+        data_instances,
+        impl_instances,
+        spans,
+        ..
+    } = mono;
+    file.spans = spans;
+    let created: Vec<String> = fn_instances
+        .iter()
+        .map(|f| f.name.clone())
+        .chain(data_instances.iter().map(|d| d.name.clone()))
+        .chain(impl_instances.iter().filter_map(|i| i.name.clone()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let specialized: HashSet<String> = fn_instances
+        .origins
+        .iter()
+        .chain(data_instances.origins.iter())
+        .cloned()
+        .collect();
+
+    // Place each instance right after its generic original, sorted by name.
+    let mut fn_by_origin: HashMap<String, Vec<lir::FnDecl>> = HashMap::new();
+    for (origin, f) in fn_instances.origins.into_iter().zip(fn_instances.decls) {
+        fn_by_origin.entry(origin).or_default().push(f);
+    }
+    let mut data_by_origin: HashMap<String, Vec<lir::DataDecl>> = HashMap::new();
+    for (origin, d) in data_instances.origins.into_iter().zip(data_instances.decls) {
+        data_by_origin.entry(origin).or_default().push(d);
+    }
+    let mut impl_by_origin: HashMap<String, Vec<lir::ImplDecl>> = HashMap::new();
+    for (origin, i) in impl_instances.origins.into_iter().zip(impl_instances.decls) {
+        impl_by_origin.entry(origin).or_default().push(i);
+    }
+    let mut items = Vec::with_capacity(file.items.len() + created.len());
+    for item in std::mem::take(&mut file.items) {
+        if let lir::Item::Impl(impl_decl) = &item {
+            if is_generic_cap_impl(impl_decl) {
+                let mut impls = impl_by_origin
+                    .remove(&generic_impl_origin(impl_decl))
+                    .unwrap_or_default();
+                impls.sort_by(|a, b| a.name.cmp(&b.name));
+                items.extend(impls.into_iter().map(lir::Item::Impl));
+                continue;
+            }
+        }
+        let (fns, datas) = match &item {
+            lir::Item::Fn(f) => (fn_by_origin.remove(&f.name), None),
+            lir::Item::Data(d) => (None, data_by_origin.remove(&d.name)),
+            lir::Item::Impl(impl_decl) => {
+                let impl_const = impl_const_name(impl_decl);
+                let fns: Vec<lir::FnDecl> = impl_decl
+                    .methods
+                    .iter()
+                    .filter_map(|m| fn_by_origin.remove(&format!("{impl_const}.{}", m.name)))
+                    .flatten()
+                    .collect();
+                ((!fns.is_empty()).then_some(fns), None)
+            }
+            _ => (None, None),
+        };
+        items.push(item);
+        if let Some(mut fns) = fns {
+            fns.sort_by(|a, b| a.name.cmp(&b.name));
+            items.extend(fns.into_iter().map(lir::Item::Fn));
+        }
+        if let Some(mut datas) = datas {
+            datas.sort_by(|a, b| a.name.cmp(&b.name));
+            items.extend(datas.into_iter().map(lir::Item::Data));
+        }
+    }
+    file.items = items;
+
+    drop_unused_originals(file, &specialized);
+    created
+}
+
+/// Instances created so far, in creation order, with the generic item each
+/// one was cloned from.
+struct Instances<T> {
+    origins: Vec<String>,
+    decls: Vec<T>,
+}
+
+impl<T> Instances<T> {
+    fn new() -> Self {
+        Instances {
+            origins: Vec::new(),
+            decls: Vec::new(),
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.decls.iter()
+    }
+}
+
+struct Mono {
+    /// Only specialize bounded generics, and no data types.
+    bounded: bool,
+    /// Specializable fns by name, and generic impl methods as fns named
+    /// `<impl const>.<method>`.
+    generic_fns: HashMap<String, lir::FnDecl>,
+    generic_data: HashMap<String, lir::DataDecl>,
+    /// Non-generic data: name → variants' payloads. Instances are
+    /// registered with their payloads before `mono_type`, so inference
+    /// only ever sees structural types like `List[Number]`.
+    data: HashMap<String, Vec<(String, Vec<TypeExpr>)>>,
+    /// Signatures of every callable by name: fns, externs, instances and
+    /// inherent impl methods (`<impl const>.<method>`).
+    sigs: HashMap<String, Sig>,
+    /// Impl method return types, keyed by (impl const, method).
+    methods: HashMap<(String, String), TypeExpr>,
+    /// Cap op signatures, keyed by (cap, op).
+    ops: HashMap<(String, String), Sig>,
+    /// Generic cap impls, keyed by (cap, target head).
+    generic_impls: HashMap<(String, String), lir::ImplDecl>,
+    fn_instances: Instances<lir::FnDecl>,
+    data_instances: Instances<lir::DataDecl>,
+    impl_instances: Instances<lir::ImplDecl>,
+    names: InstanceNames,
+    /// Fn instances whose bodies still need walking.
+    pending: VecDeque<lir::FnDecl>,
+    /// Impl instances whose methods still need walking.
+    pending_impls: VecDeque<lir::ImplDecl>,
+    /// The file's span table, grown as instances get fresh expr ids.
+    spans: Vec<Span>,
+}
+
+impl Mono {
+    fn new(file: &lir::File, bounded: bool) -> Self {
+        let mut mono = Mono {
+            bounded,
+            generic_fns: HashMap::new(),
+            generic_data: HashMap::new(),
+            data: HashMap::new(),
+            sigs: HashMap::new(),
+            methods: HashMap::new(),
+            ops: HashMap::new(),
+            generic_impls: HashMap::new(),
+            fn_instances: Instances::new(),
+            data_instances: Instances::new(),
+            impl_instances: Instances::new(),
+            names: InstanceNames::new(file),
+            pending: VecDeque::new(),
+            pending_impls: VecDeque::new(),
+            spans: file.spans.clone(),
+        };
+        for item in &file.items {
+            match item {
+                lir::Item::Fn(f) => {
+                    let generics = type_generics(&f.generics);
+                    mono.sigs.insert(
+                        f.name.clone(),
+                        Sig {
+                            generics: generics.clone(),
+                            params: f.params.iter().map(|p| p.ty.value.clone()).collect(),
+                            ret: ret_type(&f.return_type),
+                        },
+                    );
+                    if specializable(&f.generics, bounded) {
+                        mono.generic_fns.insert(f.name.clone(), f.clone());
+                    }
+                }
+                lir::Item::ExternFn(e) => {
+                    mono.sigs.insert(
+                        e.name.clone(),
+                        Sig {
+                            generics: type_generics(&e.generics),
+                            params: e.params.iter().map(|p| p.ty.value.clone()).collect(),
+                            ret: ret_type(&e.return_type),
+                        },
+                    );
+                }
+                lir::Item::Data(d) if d.generics.is_empty() => {
+                    mono.data.insert(d.name.clone(), variant_payloads(d));
+                }
+                lir::Item::Data(d) => {
+                    mono.generic_data.insert(d.name.clone(), d.clone());
+                }
+                lir::Item::Impl(impl_decl) => {
+                    let target = impl_decl.target_type.value.display();
+                    let impl_const = impl_decl.name.clone().unwrap_or(target);
+                    for m in &impl_decl.methods {
+                        mono.methods.insert(
+                            (impl_const.clone(), m.name.clone()),
+                            ret_type(&m.return_type),
+                        );
+                    }
+                    if is_generic_cap_impl(impl_decl) {
+                        mono.generic_impls.insert(generic_impl_key(impl_decl), impl_decl.clone());
+                    }
+                    if impl_decl.capability.is_some() {
+                        continue;
+                    }
+                    for m in &impl_decl.methods {
+                        let key = format!("{impl_const}.{}", m.name);
+                        mono.sigs.insert(
+                            key.clone(),
+                            Sig {
+                                generics: type_generics(&impl_decl.generics),
+                                params: m.params.iter().map(|p| p.ty.value.clone()).collect(),
+                                ret: ret_type(&m.return_type),
+                            },
+                        );
+                        if specializable(&impl_decl.generics, bounded) {
+                            let f = lir::FnDecl {
+                                name: key.clone(),
+                                generics: impl_decl.generics.clone(),
+                                params: m.params.clone(),
+                                return_type: m.return_type.clone(),
+                                cap: None,
+                                value: m.value.clone(),
+                                inline: false,
+                                span: m.span,
+                            };
+                            mono.generic_fns.insert(key, f);
+                        }
+                    }
+                }
+                lir::Item::Cap(c) => {
+                    for op in &c.operations {
+                        mono.ops.insert(
+                            (c.name.clone(), op.name.clone()),
+                            Sig {
+                                generics: type_generics(&op.generics),
+                                params: op.params.iter().map(|p| p.ty.value.clone()).collect(),
+                                ret: ret_type(&op.return_type),
+                            },
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        mono
+    }
+
+    /// Walk fn and impl instances until no new ones appear.
+    fn drain(&mut self) {
+        loop {
+            if let Some(mut f) = self.pending.pop_front() {
+                self.visit_fn(&mut f);
+                let slot = self
+                    .fn_instances
+                    .decls
+                    .iter_mut()
+                    .find(|d| d.name == f.name)
+                    .expect("pending fn is a registered instance");
+                *slot = f;
+            } else if let Some(mut i) = self.pending_impls.pop_front() {
+                for m in &mut i.methods {
+                    self.visit_method(m);
+                }
+                let slot = self
+                    .impl_instances
+                    .decls
+                    .iter_mut()
+                    .find(|d| d.name == i.name)
+                    .expect("pending impl is a registered instance");
+                *slot = i;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn visit_fn(&mut self, f: &mut lir::FnDecl) {
+        let param_tys: Vec<TypeExpr> = f.params.iter().map(|p| p.ty.value.clone()).collect();
+        let ret = ret_type(&f.return_type);
+        let mut env = Env::default();
+        self.visit_callable(&mut f.value, &param_tys, &ret, &mut env);
+        for p in &mut f.params {
+            p.ty.value = self.mono_type(&p.ty.value);
+        }
+        if let Some(r) = &mut f.return_type {
+            r.value = self.mono_type(&r.value);
+        }
+    }
+
+    fn visit_method(&mut self, m: &mut lir::ImplMethodDecl) {
+        let param_tys: Vec<TypeExpr> = m.params.iter().map(|p| p.ty.value.clone()).collect();
+        let ret = ret_type(&m.return_type);
+        let mut env = Env::default();
+        self.visit_callable(&mut m.value, &param_tys, &ret, &mut env);
+        for p in &mut m.params {
+            p.ty.value = self.mono_type(&p.ty.value);
+        }
+        if let Some(r) = &mut m.return_type {
+            r.value = self.mono_type(&r.value);
+        }
+    }
+
+    /// Bind the `thunk lambda p1. lambda p2. body` chain of a fn value to
+    /// its declared param types, then walk the body.
+    fn visit_callable(
+        &mut self,
+        value: &mut lir::Expr,
+        params: &[TypeExpr],
+        ret: &TypeExpr,
+        env: &mut Env,
+    ) {
+        let mut cur = value;
+        if let lir::Expr::Thunk { expr, .. } = cur {
+            cur = expr;
+        }
+        let mut bound = 0;
+        for ty in params {
+            let lir::Expr::Lambda { param, body, .. } = cur else { break };
+            env.push(param.clone(), ty.clone());
+            bound += 1;
+            cur = body;
+        }
+        self.visit(cur, ret, env);
+        env.truncate(env.len() - bound);
+    }
+
+    /// Walk `expr`, redirecting generic calls, ctors and patterns to their
+    /// instances where the types are known. `expected` is the type the
+    /// context wants (a hole if none). Returns the type of `expr`.
+    fn visit(&mut self, expr: &mut lir::Expr, expected: &TypeExpr, env: &mut Env) -> TypeExpr {
+        match expr {
+            lir::Expr::Ident { name, .. } if name == "Unit" => unit(),
+            lir::Expr::Ident { name, .. } => env.refine(name, expected),
+            lir::Expr::String { .. } => TypeExpr::Named("String".to_owned()),
+            lir::Expr::Number { value, .. } => {
+                TypeExpr::Named(lumo_lexer::NumberKind::of(value).type_name().to_owned())
+            }
+            lir::Expr::Ctor { .. } => self.visit_ctor(expr, expected, env),
+            lir::Expr::Apply { .. } | lir::Expr::Force { .. } if call_head(expr).is_some() => {
+                self.visit_call(expr, expected, env)
+            }
+            lir::Expr::Force { expr, .. } => self.visit(expr, expected, env),
+            lir::Expr::Thunk { expr, .. }
+            | lir::Expr::Produce { expr, .. }
+            | lir::Expr::Roll { expr, .. }
+            | lir::Expr::Unroll { expr, .. } => self.visit(expr, expected, env),
+            lir::Expr::Ann { expr, ty, .. } => {
+                let declared = ty.clone();
+                self.visit(expr, &declared, env);
+                *ty = self.mono_type(&declared);
+                declared
+            }
+            lir::Expr::Lambda { param, body, .. } => {
+                env.push(param.clone(), hole());
+                self.visit(body, &hole(), env);
+                env.truncate(env.len() - 1);
+                hole()
+            }
+            lir::Expr::Apply { .. } if perform_head(expr).is_some() => {
+                self.visit_perform(expr, env);
+                hole()
+            }
+            lir::Expr::Apply { callee, arg, .. } => {
+                self.visit(callee, &hole(), env);
+                self.visit(arg, &hole(), env);
+                hole()
+            }
+            lir::Expr::Let {
+                name, value, body, ..
+            } => {
+                let value_ty = self.visit(value, &hole(), env);
+                env.push(name.clone(), value_ty.clone());
+                let body_ty = self.visit(body, expected, env);
+                let (_, used_as) = env.pop();
+                // `let xs = List.nil; f(xs)`: the use fixed what the value
+                // alone could not. Walk the value again at that type.
+                if !is_concrete(&value_ty) && is_concrete(&used_as) {
+                    self.visit(value, &used_as, env);
+                }
+                body_ty
+            }
+            lir::Expr::Loop {
+                var, init, body, ..
+            } => {
+                let state = self.visit(init, &hole(), env);
+                env.push(var.clone(), state.clone());
+                let step = TypeExpr::App {
+                    head: "Step".to_owned(),
+                    args: vec![state.clone()],
+                };
+                self.visit(body, &step, env);
+                let (_, used_as) = env.pop();
+                if !is_concrete(&state) && is_concrete(&used_as) {
+                    self.visit(init, &used_as, env);
+                }
+                unit()
+            }
+            lir::Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                let scrutinee_ty = self.visit(scrutinee, &hole(), env);
+                let mut result = expected.clone();
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    self.qualify_pattern(&mut arm.pattern, &scrutinee_ty, &mut bindings);
+                    let pushed = bindings.len();
+                    for (n, t) in bindings {
+                        env.push(n, t);
+                    }
+                    let arm_ty = self.visit(&mut arm.body, &result, env);
+                    env.truncate(env.len() - pushed);
+                    result = merge(&result, &arm_ty);
+                }
+                result
+            }
+            lir::Expr::Handle { handler, body, .. } => {
+                self.visit(handler, &hole(), env);
+                self.visit(body, expected, env)
+            }
+            lir::Expr::Bundle { entries, .. } => {
+                for e in entries {
+                    let pushed = e.params.len();
+                    for p in &e.params {
+                        env.push(p.name.clone(), p.ty.value.clone());
+                    }
+                    self.visit(&mut e.body, &hole(), env);
+                    env.truncate(env.len() - pushed);
+                }
+                hole()
+            }
+            lir::Expr::Member { object, .. } => {
+                self.visit(object, &hole(), env);
+                hole()
+            }
+            lir::Expr::Perform { .. } | lir::Expr::Error { .. } => hole(),
+        }
+    }
+
+    fn visit_ctor(&mut self, expr: &mut lir::Expr, expected: &TypeExpr, env: &mut Env) -> TypeExpr {
+        let ty = {
+            let inferred = self.infer(expr, env);
+            merge(&inferred, expected)
+        };
+        let lir::Expr::Ctor { name, args, .. } = expr else {
+            unreachable!("visit_ctor called on a ctor")
+        };
+        let Some((data_name, variant)) = name.split_once('.') else {
+            for a in args {
+                self.visit(a, &hole(), env);
+            }
+            return hole();
+        };
+        let (data_name, variant) = (data_name.to_owned(), variant.to_owned());
+        let instance = match &ty {
+            TypeExpr::App { head, .. } if *head == data_name && is_concrete(&ty) => {
+                self.data_instance(&ty)
+            }
+            _ => None,
+        };
+        let owner = instance.clone().unwrap_or(data_name.clone());
+        let payload = self
+            .data
+            .get(&owner)
+            .and_then(|vs| vs.iter().find(|(v, _)| *v == variant))
+            .map(|(_, p)| p.clone());
+        match payload {
+            Some(payload) => {
+                for (a, t) in args.iter_mut().zip(payload.iter()) {
+                    self.visit(a, t, env);
+                }
+            }
+            None => {
+                for a in args.iter_mut() {
+                    self.visit(a, &hole(), env);
+                }
+            }
+        }
+        if let Some(instance) = instance {
+            *name = format!("{instance}.{variant}");
+            return ty;
+        }
+        if self.data.contains_key(&data_name) {
+            return TypeExpr::Named(data_name);
+        }
+        ty
+    }
+
+    fn visit_call(&mut self, expr: &mut lir::Expr, expected: &TypeExpr, env: &mut Env) -> TypeExpr {
+        let head = call_head(expr).expect("visit_call on a call");
+        let Some(sig) = self.sigs.get(&head).cloned() else {
+            let mut args = call_args_mut(expr);
+            for a in args.iter_mut() {
+                self.visit(a, &hole(), env);
+            }
+            return hole();
+        };
+        let arg_tys: Vec<TypeExpr> = call_args(expr).iter().map(|a| self.infer(a, env)).collect();
+        let mut subst: HashMap<String, TypeExpr> = HashMap::new();
+        for (p, a) in sig.params.iter().zip(arg_tys.iter()) {
+            unify(p, a, &sig.generics, &mut subst);
+        }
+        unify(&sig.ret, expected, &sig.generics, &mut subst);
+        for g in &sig.generics {
+            subst.entry(g.clone()).or_insert_with(hole);
+        }
+
+        let type_args: Vec<TypeExpr> = sig
+            .generics
+            .iter()
+            .map(|g| subst.get(g).cloned().unwrap_or_else(hole))
+            .collect();
+        let specialized = self.generic_fns.contains_key(&head)
+            && type_args.iter().all(|t| is_concrete(t) && depth(t) <= MAX_TYPE_DEPTH);
+        let instance = if specialized {
+            Some(self.fn_instance(&head, &type_args))
+        } else {
+            None
+        };
+
+        let expected_args: Vec<TypeExpr> = sig
+            .params
+            .iter()
+            .map(|p| substitute(p, &subst))
+            .collect();
+        let mut args = call_args_mut(expr);
+        for (i, a) in args.iter_mut().enumerate() {
+            let t = expected_args.get(i).cloned().unwrap_or_else(hole);
+            self.visit(a, &t, env);
+        }
+        if let Some(instance) = instance {
+            rename_call_head(expr, &instance);
+        }
+        substitute(&sig.ret, &subst)
+    }
+
+    /// Walk the args of a cap op call, instantiating the generic impl of
+    /// the cap for the type `Self` is fixed to, if there is one.
+    fn visit_perform(&mut self, expr: &mut lir::Expr, env: &mut Env) {
+        let (cap, op) = perform_head(expr).expect("visit_perform on a perform");
+        if let Some(sig) = self.ops.get(&(cap.clone(), op)) {
+            // `cmp(Tree.leaf, t)`: the first `Self` arg may leave a hole
+            // that a later one fills.
+            let self_ty = sig
+                .params
+                .iter()
+                .zip(call_args(expr))
+                .filter(|(p, _)| matches!(p, TypeExpr::Named(n) if n == "Self"))
+                .map(|(_, a)| self.infer(a, env))
+                .find(is_concrete);
+            if let Some(ty @ TypeExpr::App { head, .. }) = &self_ty {
+                if is_concrete(ty)
+                    && depth(ty) <= MAX_TYPE_DEPTH
+                    && self.generic_impls.contains_key(&(cap.clone(), head.clone()))
+                {
+                    self.impl_instance(&cap, ty);
+                }
+            }
+        }
+        for a in call_args_mut(expr) {
+            self.visit(a, &hole(), env);
+        }
+    }
+
+    /// Type of `expr` without rewriting it.
+    fn infer(&self, expr: &lir::Expr, env: &Env) -> TypeExpr {
+        match expr {
+            lir::Expr::Ident { name, .. } if name == "Unit" => unit(),
+            lir::Expr::Loop { .. } => unit(),
+            lir::Expr::Ident { name, .. } => env.get(name).cloned().unwrap_or_else(hole),
+            lir::Expr::String { .. } => TypeExpr::Named("String".to_owned()),
+            lir::Expr::Number { value, .. } => {
+                TypeExpr::Named(lumo_lexer::NumberKind::of(value).type_name().to_owned())
+            }
+            lir::Expr::Ctor { name, args, .. } => {
+                let Some((data_name, variant)) = name.split_once('.') else {
+                    return hole();
+                };
+                if self.data.contains_key(data_name) {
+                    return TypeExpr::Named(data_name.to_owned());
+                }
+                let Some(decl) = self.generic_data.get(data_name) else {
+                    return hole();
+                };
+                let Some(v) = decl.variants.iter().find(|v| v.name == variant) else {
+                    return hole();
+                };
+                let mut subst = HashMap::new();
+                for (p, a) in v.payload.iter().zip(args.iter()) {
+                    unify(&p.value, &self.infer(a, env), &decl.generics, &mut subst);
+                }
+                TypeExpr::App {
+                    head: data_name.to_owned(),
+                    args: decl
+                        .generics
+                        .iter()
+                        .map(|g| subst.get(g).cloned().unwrap_or_else(hole))
+                        .collect(),
+                }
+            }
+            lir::Expr::Apply { .. } | lir::Expr::Force { .. } if call_head(expr).is_some() => {
+                let head = call_head(expr).expect("checked above");
+                let Some(sig) = self.sigs.get(&head) else {
+                    return hole();
+                };
+                self.infer_ret(sig, &call_args(expr), env)
+            }
+            lir::Expr::Apply { .. } => {
+                let mut cur = expr;
+                while let lir::Expr::Apply { callee, .. } = cur {
+                    cur = callee;
+                }
+                let lir::Expr::Member { object, field, .. } = cur else {
+                    return hole();
+                };
+                let ret = match object.as_ref() {
+                    lir::Expr::Ident { name, .. } => {
+                        self.methods.get(&(name.clone(), field.clone())).cloned()
+                    }
+                    lir::Expr::Perform { cap, .. } => self
+                        .ops
+                        .get(&(cap.clone(), field.clone()))
+                        .map(|sig| self.infer_ret(sig, &call_args(expr), env)),
+                    _ => None,
+                };
+                ret.filter(is_concrete).unwrap_or_else(hole)
+            }
+            lir::Expr::Force { expr, .. }
+            | lir::Expr::Thunk { expr, .. }
+            | lir::Expr::Produce { expr, .. }
+            | lir::Expr::Roll { expr, .. }
+            | lir::Expr::Unroll { expr, .. } => self.infer(expr, env),
+            lir::Expr::Ann { ty, .. } => ty.clone(),
+            lir::Expr::Let {
+                name, value, body, ..
+            } => {
+                let mut env = env.clone();
+                env.push(name.clone(), self.infer(value, &env));
+                self.infer(body, &env)
+            }
+            lir::Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                let scrutinee_ty = self.infer(scrutinee, env);
+                let mut result = hole();
+                for arm in arms {
+                    let mut env = env.clone();
+                    for (n, t) in self.pattern_bindings(&arm.pattern, &scrutinee_ty) {
+                        env.push(n, t);
+                    }
+                    result = merge(&result, &self.infer(&arm.body, &env));
+                }
+                result
+            }
+            lir::Expr::Handle { body, .. } => self.infer(body, env),
+            lir::Expr::Lambda { .. }
+            | lir::Expr::Bundle { .. }
+            | lir::Expr::Member { .. }
+            | lir::Expr::Perform { .. }
+            | lir::Expr::Error { .. } => hole(),
+        }
+    }
+
+    /// Return type of a call to `sig`, with generics fixed by the argument
+    /// types and the rest left as holes.
+    fn infer_ret(&self, sig: &Sig, args: &[&lir::Expr], env: &Env) -> TypeExpr {
+        let mut subst = HashMap::new();
+        for (p, a) in sig.params.iter().zip(args) {
+            unify(p, &self.infer(a, env), &sig.generics, &mut subst);
+        }
+        for g in &sig.generics {
+            subst.entry(g.clone()).or_insert_with(hole);
+        }
+        substitute(&sig.ret, &subst)
+    }
+
+    /// Payload types for matching `variant` against a value of type `ty`.
+    fn variant_payload(&self, ty: &TypeExpr, variant: &str) -> Option<Vec<TypeExpr>> {
+        let variant = variant.rsplit('.').next().unwrap_or(variant);
+        match ty {
+            TypeExpr::Named(n) => self
+                .data
+                .get(n)
+                .and_then(|vs| vs.iter().find(|(v, _)| v == variant))
+                .map(|(_, p)| p.clone()),
+            TypeExpr::App { head, args } => {
+                let decl = self.generic_data.get(head)?;
+                let v = decl.variants.iter().find(|v| v.name == variant)?;
+                let subst: HashMap<String, TypeExpr> =
+                    decl.generics.iter().cloned().zip(args.iter().cloned()).collect();
+                Some(v.payload.iter().map(|p| substitute(&p.value, &subst)).collect())
+            }
+            _ => None,
+        }
+    }
+
+    fn pattern_bindings(&self, pattern: &Pattern, ty: &TypeExpr) -> Vec<(String, TypeExpr)> {
+        let mut out = Vec::new();
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Bind(n) => out.push((n.clone(), ty.clone())),
+            Pattern::Ctor { name, args } => {
+                let payload = self.variant_payload(ty, name).unwrap_or_default();
+                for (i, a) in args.iter().enumerate() {
+                    let t = payload.get(i).cloned().unwrap_or_else(hole);
+                    out.extend(self.pattern_bindings(a, &t));
+                }
+            }
+        }
+        out
+    }
+
+    /// Qualify ctor patterns matching a data instance with the instance
+    /// name, collecting the bindings they introduce.
+    fn qualify_pattern(
+        &mut self,
+        pattern: &mut Pattern,
+        ty: &TypeExpr,
+        bindings: &mut Vec<(String, TypeExpr)>,
+    ) {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Bind(n) => bindings.push((n.clone(), ty.clone())),
+            Pattern::Ctor { name, args } => {
+                let payload = self.variant_payload(ty, name).unwrap_or_default();
+                if is_concrete(ty) {
+                    if let Some(instance) = self.data_instance(ty) {
+                        let variant = name.rsplit('.').next().unwrap_or(name).to_owned();
+                        *name = format!("{instance}.{variant}");
+                    }
+                }
+                for (i, a) in args.iter_mut().enumerate() {
+                    let t = payload.get(i).cloned().unwrap_or_else(hole);
+                    self.qualify_pattern(a, &t, bindings);
+                }
+            }
+        }
+    }
+
+    /// Replace concrete applications of generic data types with their
+    /// instances, creating them as needed.
+    fn mono_type(&mut self, ty: &TypeExpr) -> TypeExpr {
+        match ty {
+            TypeExpr::App { head, args } => {
+                if self.generic_data.contains_key(head) && is_concrete(ty) {
+                    if let Some(instance) = self.data_instance(ty) {
+                        return TypeExpr::Named(instance);
+                    }
+                }
+                TypeExpr::App {
+                    head: head.clone(),
+                    args: args.iter().map(|a| self.mono_type(a)).collect(),
+                }
+            }
+            TypeExpr::Produce(inner) => TypeExpr::Produce(Box::new(self.mono_type(inner))),
+            TypeExpr::Thunk(inner) => TypeExpr::Thunk(Box::new(self.mono_type(inner))),
+            TypeExpr::Comp { ty, cap } => TypeExpr::Comp {
+                ty: Box::new(self.mono_type(ty)),
+                cap: cap
+                    .iter()
+                    .map(|e| match e {
+                        CapEntry::Cap(TypeExpr::Cap { name, type_args }) => CapEntry::Cap(TypeExpr::Cap {
+                            name: name.clone(),
+                            type_args: type_args.iter().map(|a| self.mono_type(a)).collect(),
+                        }),
+                        other => other.clone(),
+                    })
+                    .collect(),
+            },
+            TypeExpr::Fn { params, ret, cap } => TypeExpr::Fn {
+                params: params.iter().map(|p| self.mono_type(p)).collect(),
+                ret: Box::new(self.mono_type(ret)),
+                cap: cap.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    /// Instance of the generic data type applied in `ty`, created on first
+    /// use. `None` if `ty` is nested too deeply to specialize.
+    fn data_instance(&mut self, ty: &TypeExpr) -> Option<String> {
+        let TypeExpr::App { head, args } = ty else {
+            return None;
+        };
+        if self.bounded || depth(ty) > MAX_TYPE_DEPTH {
+            return None;
+        }
+        let decl = self.generic_data.get(head)?.clone();
+        let (name, new) = self.names.claim(instance_name(head, args));
+        if new {
+            let subst: HashMap<String, TypeExpr> =
+                decl.generics.iter().cloned().zip(args.iter().cloned()).collect();
+            // Register before walking payloads: `List[Number]` refers to
+            // itself through `.cons`.
+            let mut instance = lir::DataDecl {
+                name: name.clone(),
+                generics: Vec::new(),
+                variants: decl.variants.clone(),
+                span: decl.span,
+            };
+            for v in &mut instance.variants {
+                for p in &mut v.payload {
+                    p.value = substitute(&p.value, &subst);
+                }
+            }
+            self.data.insert(name.clone(), variant_payloads(&instance));
+            for v in &mut instance.variants {
+                for p in &mut v.payload {
+                    p.value = self.mono_type(&p.value);
+                }
+            }
+            self.data_instances.origins.push(head.clone());
+            self.data_instances.decls.push(instance);
+        }
+        Some(name)
+    }
+
+    /// Instance of the generic impl of `cap` for the concrete application
+    /// `ty`, created (and queued for walking) on first use.
+    fn impl_instance(&mut self, cap: &str, ty: &TypeExpr) -> String {
+        let TypeExpr::App { head, args } = ty else {
+            unreachable!("impl_instance is only called with an application")
+        };
+        let key = format!("__impl_{}_{}", instance_name(head, args), cap.replace('_', "__"));
+        let (name, new) = self.names.claim(key);
+        if new {
+            let mut decl = self.generic_impls[&(cap.to_owned(), head.clone())].clone();
+            let generics = type_generics(&decl.generics);
+            let mut subst = HashMap::new();
+            unify(&decl.target_type.value, ty, &generics, &mut subst);
+            for g in &generics {
+                subst.entry(g.clone()).or_insert_with(hole);
+            }
+            let origin = generic_impl_origin(&decl);
+            decl.name = Some(name.clone());
+            decl.generics.retain(|g| g.is_cap_row());
+            decl.target_type.value = ty.clone();
+            for m in &mut decl.methods {
+                for p in &mut m.params {
+                    p.ty.value = substitute(&p.ty.value, &subst);
+                }
+                if let Some(r) = &mut m.return_type {
+                    r.value = substitute(&r.value, &subst);
+                }
+                substitute_in_expr(&mut m.value, &subst);
+                fresh_ids(&mut m.value, &mut self.spans);
+            }
+            self.impl_instances.origins.push(origin);
+            self.impl_instances.decls.push(decl.clone());
+            self.pending_impls.push_back(decl);
+        }
+        name
+    }
+
+    /// Instance of generic fn `name` at `type_args`, created (and queued
+    /// for walking) on first use.
+    fn fn_instance(&mut self, name: &str, type_args: &[TypeExpr]) -> String {
+        let (instance_name, new) = self.names.claim(instance_name(name, type_args));
+        if new {
+            let decl = self.generic_fns[name].clone();
+            let generics = type_generics(&decl.generics);
+            let subst: HashMap<String, TypeExpr> =
+                generics.iter().cloned().zip(type_args.iter().cloned()).collect();
+            let mut f = decl;
+            f.name = instance_name.clone();
+            // A bound `K: PartialEq` made its cap available in the body; once
+            // `K` is gone, a closed cap list has to name it instead.
+            if let Some(cap) = &mut f.cap {
+                for g in &f.generics {
+                    let lir::GenericParam::Type(name, bounds) = g else {
+                        continue;
+                    };
+                    for bound in bounds {
+                        let entry = CapEntry::Cap(TypeExpr::Cap {
+                            name: bound.clone(),
+                            type_args: vec![subst[name].clone()],
+                        });
+                        if !cap.contains(&entry) {
+                            cap.push(entry);
+                        }
+                    }
+                }
+            }
+            f.generics.retain(|g| g.is_cap_row());
+            for p in &mut f.params {
+                p.ty.value = substitute(&p.ty.value, &subst);
+            }
+            if let Some(r) = &mut f.return_type {
+                r.value = substitute(&r.value, &subst);
+            }
+            if let Some(cap) = &mut f.cap {
+                for entry in cap.iter_mut() {
+                    if let CapEntry::Cap(ty) = entry {
+                        *ty = substitute(ty, &subst);
+                    }
+                }
+            }
+            substitute_in_expr(&mut f.value, &subst);
+            fresh_ids(&mut f.value, &mut self.spans);
+            self.sigs.insert(
+                instance_name.clone(),
+                Sig {
+                    generics: Vec::new(),
+                    params: f.params.iter().map(|p| p.ty.value.clone()).collect(),
+                    ret: ret_type(&f.return_type),
+                },
+            );
+            self.fn_instances.origins.push(name.to_owned());
+            self.fn_instances.decls.push(f.clone());
+            self.pending.push_back(f);
+        }
+        instance_name
+    }
+}
+
+/// Lexically scoped variable types. A stack, so shadowing and scope exit
+/// are a push and a truncate.
+#[derive(Default, Clone)]
+struct Env(Vec<(String, TypeExpr)>);
+
+impl Env {
+    fn push(&mut self, name: String, ty: TypeExpr) {
+        self.0.push((name, ty));
+    }
+
+    fn pop(&mut self) -> (String, TypeExpr) {
+        self.0.pop().expect("pop matches a push")
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    fn get(&self, name: &str) -> Option<&TypeExpr> {
+        self.0.iter().rev().find(|(n, _)| n == name).map(|(_, t)| t)
+    }
+
+    /// Fill holes in `name`'s type from what a use site expects, and return
+    /// the refined type.
+    fn refine(&mut self, name: &str, expected: &TypeExpr) -> TypeExpr {
+        match self.0.iter_mut().rev().find(|(n, _)| n == name) {
+            Some((_, ty)) => {
+                *ty = merge(ty, expected);
+                ty.clone()
+            }
+            None => expected.clone(),
+        }
+    }
+}
+
+/// Whether a callable with `generics` gets cloned per instantiation.
+fn specializable(generics: &[lir::GenericParam], bounded: bool) -> bool {
+    if bounded {
+        generics.iter().any(|g| !g.bounds().is_empty())
+    } else {
+        !type_generics(generics).is_empty()
+    }
+}
+
+/// A cap impl over a generic target, like `impl[A: PartialEq] List[A]: PartialEq`.
+fn is_generic_cap_impl(impl_decl: &lir::ImplDecl) -> bool {
+    impl_decl.capability.is_some() && !type_generics(&impl_decl.generics).is_empty()
+}
+
+/// What a generic cap impl is found by: `(cap, target head)`.
+fn generic_impl_key(impl_decl: &lir::ImplDecl) -> (String, String) {
+    let cap = impl_decl
+        .capability
+        .as_ref()
+        .map(|c| c.value.display())
+        .unwrap_or_default();
+    (cap, impl_decl.target_type.value.head_name().to_owned())
+}
+
+/// The origin recorded for the instances of a generic cap impl.
+fn generic_impl_origin(impl_decl: &lir::ImplDecl) -> String {
+    let (cap, head) = generic_impl_key(impl_decl);
+    format!("{head}: {cap}")
+}
+
+/// The const an impl is referred to by: its name, or its target type.
+fn impl_const_name(impl_decl: &lir::ImplDecl) -> String {
+    impl_decl
+        .name
+        .clone()
+        .unwrap_or_else(|| impl_decl.target_type.value.display())
+}
+
+fn type_generics(generics: &[lir::GenericParam]) -> Vec<String> {
+    generics
+        .iter()
+        .filter(|g| !g.is_cap_row())
+        .map(|g| g.name().to_owned())
+        .collect()
+}
+
+fn ret_type(ret: &Option<Spanned<TypeExpr>>) -> TypeExpr {
+    ret.as_ref()
+        .map(|r| r.value.clone())
+        .unwrap_or_else(unit)
+}
+
+fn unit() -> TypeExpr {
+    TypeExpr::Named("Unit".to_owned())
+}
+
+fn variant_payloads(d: &lir::DataDecl) -> Vec<(String, Vec<TypeExpr>)> {
+    d.variants
+        .iter()
+        .map(|v| (v.name.clone(), v.payload.iter().map(|p| p.value.clone()).collect()))
+        .collect()
+}
+
+/// The key an instance of `name` at `type_args` is named by. Each `_` in a
+/// source name is doubled and `.` becomes `_D`, so a lone `_` only ever
+/// starts a structural marker: `_A` .. `_Z` bracket type args (split by
+/// `_C`), `_P`/`_T` wrap produce/thunk types, `_V` marks a type variable,
+/// `_K` a cap and `_F`/`_M` fn and recursive types. Distinct instances
+/// therefore never share a key: `Box[Number]` is `Box_ANumber_Z` and
+/// `Box_Number` stays `Box__Number`.
+pub(crate) fn instance_name(name: &str, type_args: &[TypeExpr]) -> String {
+    fn escape(name: &str) -> String {
+        name.replace('_', "__").replace('.', "_D")
+    }
+    fn args(tys: &[TypeExpr]) -> String {
+        let inner: Vec<String> = tys.iter().map(mangle).collect();
+        format!("_A{}_Z", inner.join("_C"))
+    }
+    fn mangle(ty: &TypeExpr) -> String {
+        match ty {
+            TypeExpr::Named(n) => escape(n),
+            TypeExpr::Var(n) => format!("_V{}", escape(n)),
+            TypeExpr::App { head, args: a } => format!("{}{}", escape(head), args(a)),
+            TypeExpr::Produce(inner) => format!("_P{}", mangle(inner)),
+            TypeExpr::Thunk(inner) => format!("_T{}", mangle(inner)),
+            TypeExpr::Comp { ty, .. } => mangle(ty),
+            TypeExpr::Cap { name, type_args } => format!("_K{}{}", escape(name), args(type_args)),
+            TypeExpr::Fn { params, ret, .. } => {
+                format!("_F{}{}", args(params), args(std::slice::from_ref(ret)))
+            }
+            TypeExpr::Mu { var, body } => {
+                format!("_M{}{}", escape(var), args(std::slice::from_ref(body)))
+            }
+        }
+    }
+    format!("{}{}", escape(name), args(type_args))
+}
+
+/// Instance names given out so far, kept apart from the file's own items.
+struct InstanceNames {
+    /// Every top-level name in the file, and every instance name given out.
+    taken: HashSet<String>,
+    /// Instance names by their [`instance_name`] key.
+    assigned: HashMap<String, String>,
+}
+
+impl InstanceNames {
+    fn new(file: &lir::File) -> Self {
+        let mut taken = HashSet::new();
+        for item in &file.items {
+            match item {
+                lir::Item::ExternType(t) => taken.insert(t.name.clone()),
+                lir::Item::ExternFn(e) => taken.insert(e.name.clone()),
+                lir::Item::Data(d) => taken.insert(d.name.clone()),
+                lir::Item::Cap(c) => taken.insert(c.name.clone()),
+                lir::Item::Fn(f) => taken.insert(f.name.clone()),
+                lir::Item::Impl(impl_decl) => {
+                    if let (None, Some(cap)) = (&impl_decl.name, &impl_decl.capability) {
+                        let target = impl_decl.target_type.value.display();
+                        taken.insert(format!("__impl_{target}_{}", cap.value.display()));
+                    }
+                    taken.insert(impl_const_name(impl_decl))
+                }
+                lir::Item::Use(_) => false,
+            };
+        }
+        InstanceNames {
+            taken,
+            assigned: HashMap::new(),
+        }
+    }
+
+    /// The name of the instance keyed `key`, and whether this is its first
+    /// use. A key some item of the file already uses as its name gets a
+    /// `_<n>` suffix, which no key ends in.
+    fn claim(&mut self, key: String) -> (String, bool) {
+        if let Some(name) = self.assigned.get(&key) {
+            return (name.clone(), false);
+        }
+        let mut name = key.clone();
+        let mut n = 1;
+        while self.taken.contains(&name) {
+            name = format!("{key}_{n}");
+            n += 1;
+        }
+        self.taken.insert(name.clone());
+        self.assigned.insert(key, name.clone());
+        (name, true)
+    }
+}
+
+fn is_concrete(ty: &TypeExpr) -> bool {
+    match ty {
+        TypeExpr::Var(_) => false,
+        TypeExpr::Named(_) => true,
+        TypeExpr::App { args, .. } => args.iter().all(is_concrete),
+        TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => is_concrete(inner),
+        TypeExpr::Comp { ty, .. } => is_concrete(ty),
+        TypeExpr::Cap { type_args, .. } => type_args.iter().all(is_concrete),
+        TypeExpr::Fn { params, ret, .. } => params.iter().all(is_concrete) && is_concrete(ret),
+        TypeExpr::Mu { .. } => false,
+    }
+}
+
+fn depth(ty: &TypeExpr) -> usize {
+    match ty {
+        TypeExpr::App { args, .. } => 1 + args.iter().map(depth).max().unwrap_or(0),
+        TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => depth(inner),
+        TypeExpr::Fn { params, ret, .. } => {
+            1 + params.iter().chain(std::iter::once(ret.as_ref())).map(depth).max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// Fill the holes of `a` from `b`.
+fn merge(a: &TypeExpr, b: &TypeExpr) -> TypeExpr {
+    match (a, b) {
+        (a, _) if is_hole(a) => b.clone(),
+        (
+            TypeExpr::App { head, args },
+            TypeExpr::App {
+                head: head_b,
+                args: args_b,
+            },
+        ) if head == head_b && args.len() == args_b.len() => TypeExpr::App {
+            head: head.clone(),
+            args: args.iter().zip(args_b).map(|(x, y)| merge(x, y)).collect(),
+        },
+        _ => a.clone(),
+    }
+}
+
+/// Bind the `generics` occurring in `pattern` from the matching parts of
+/// `actual`. Holes in `actual` bind nothing.
+fn unify(
+    pattern: &TypeExpr,
+    actual: &TypeExpr,
+    generics: &[String],
+    subst: &mut HashMap<String, TypeExpr>,
+) {
+    if is_hole(actual) {
+        return;
+    }
+    match (pattern, actual) {
+        (TypeExpr::Named(g), _) if generics.contains(g) => {
+            let merged = match subst.get(g) {
+                Some(existing) => merge(existing, actual),
+                None => actual.clone(),
+            };
+            subst.insert(g.clone(), merged);
+        }
+        (
+            TypeExpr::App { head, args },
+            TypeExpr::App {
+                head: head_b,
+                args: args_b,
+            },
+        ) if head == head_b => {
+            for (p, a) in args.iter().zip(args_b) {
+                unify(p, a, generics, subst);
+            }
+        }
+        (TypeExpr::Produce(p) | TypeExpr::Thunk(p), _) => unify(p, actual, generics, subst),
+        (_, TypeExpr::Produce(a) | TypeExpr::Thunk(a)) => unify(pattern, a, generics, subst),
+        (
+            TypeExpr::Fn { params, ret, .. },
+            TypeExpr::Fn {
+                params: params_b,
+                ret: ret_b,
+                ..
+            },
+        ) => {
+            for (p, a) in params.iter().zip(params_b) {
+                unify(p, a, generics, subst);
+            }
+            unify(ret, ret_b, generics, subst);
+        }
+        _ => {}
+    }
+}
+
+/// Replace the generic names bound in `subst`.
+fn substitute(ty: &TypeExpr, subst: &HashMap<String, TypeExpr>) -> TypeExpr {
+    match ty {
+        TypeExpr::Named(n) => subst.get(n).cloned().unwrap_or_else(|| ty.clone()),
+        TypeExpr::App { head, args } => TypeExpr::App {
+            head: head.clone(),
+            args: args.iter().map(|a| substitute(a, subst)).collect(),
+        },
+        TypeExpr::Produce(inner) => TypeExpr::Produce(Box::new(substitute(inner, subst))),
+        TypeExpr::Thunk(inner) => TypeExpr::Thunk(Box::new(substitute(inner, subst))),
+        // The row is only checked against the thunk's caps, which the
+        // specialized body performs at the substituted types.
+        TypeExpr::Comp { ty, cap } => TypeExpr::Comp {
+            ty: Box::new(substitute(ty, subst)),
+            cap: cap
+                .iter()
+                .map(|e| match e {
+                    CapEntry::Cap(c) => CapEntry::Cap(substitute(c, subst)),
+                    other => other.clone(),
+                })
+                .collect(),
+        },
+        TypeExpr::Cap { name, type_args } => TypeExpr::Cap {
+            name: name.clone(),
+            type_args: type_args.iter().map(|a| substitute(a, subst)).collect(),
+        },
+        TypeExpr::Fn { params, ret, cap } => TypeExpr::Fn {
+            params: params.iter().map(|p| substitute(p, subst)).collect(),
+            ret: Box::new(substitute(ret, subst)),
+            cap: cap.clone(),
+        },
+        TypeExpr::Mu { .. } | TypeExpr::Var(_) => ty.clone(),
+    }
+}
+
+/// Substitute generics inside a cloned body: annotations and the type
+/// arguments of performs and handles (`Add[A]` → `Add[Number]`).
+fn substitute_in_expr(expr: &mut lir::Expr, subst: &HashMap<String, TypeExpr>) {
+    let sub_args = |type_args: &mut Vec<String>| {
+        for t in type_args.iter_mut() {
+            if let Some(ty) = subst.get(t.as_str()) {
+                *t = ty.display();
+            }
+        }
+    };
+    match expr {
+        lir::Expr::Ann { expr, ty, .. } => {
+            *ty = substitute(ty, subst);
+            substitute_in_expr(expr, subst);
+        }
+        lir::Expr::Perform { type_args, .. } => sub_args(type_args),
+        lir::Expr::Handle {
+            type_args,
+            handler,
+            body,
+            ..
+        } => {
+            sub_args(type_args);
+            substitute_in_expr(handler, subst);
+            substitute_in_expr(body, subst);
+        }
+        lir::Expr::Apply { callee, arg, .. } => {
+            substitute_in_expr(callee, subst);
+            substitute_in_expr(arg, subst);
+        }
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. } => substitute_in_expr(expr, subst),
+        lir::Expr::Lambda { body, .. } => substitute_in_expr(body, subst),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            substitute_in_expr(value, subst);
+            substitute_in_expr(body, subst);
+        }
+        lir::Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            substitute_in_expr(scrutinee, subst);
+            for arm in arms {
+                substitute_in_expr(&mut arm.body, subst);
+            }
+        }
+        lir::Expr::Bundle { entries, .. } => {
+            for e in entries {
+                for p in &mut e.params {
+                    p.ty.value = substitute(&p.ty.value, subst);
+                }
+                substitute_in_expr(&mut e.body, subst);
+            }
+        }
+        lir::Expr::Ctor { args, .. } => {
+            for a in args {
+                substitute_in_expr(a, subst);
+            }
+        }
+        lir::Expr::Member { object, .. } => substitute_in_expr(object, subst),
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Error { .. } => {}
+    }
+}
+
+/// Give a cloned body ids of its own, so what the typechecker records per
+/// id (a perform's `Self`) is not shared with the original.
+pub(super) fn fresh_ids(expr: &mut lir::Expr, spans: &mut Vec<Span>) {
+    let id = expr.id_mut();
+    let span = spans[id.0 as usize];
+    *id = lumo_types::ExprId(spans.len() as u32);
+    spans.push(span);
+    match expr {
+        lir::Expr::Apply { callee, arg, .. } => {
+            fresh_ids(callee, spans);
+            fresh_ids(arg, spans);
+        }
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Ann { expr, .. } => fresh_ids(expr, spans),
+        lir::Expr::Lambda { body, .. } => fresh_ids(body, spans),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            fresh_ids(value, spans);
+            fresh_ids(body, spans);
+        }
+        lir::Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            fresh_ids(scrutinee, spans);
+            for arm in arms {
+                fresh_ids(&mut arm.body, spans);
+            }
+        }
+        lir::Expr::Handle { handler, body, .. } => {
+            fresh_ids(handler, spans);
+            fresh_ids(body, spans);
+        }
+        lir::Expr::Bundle { entries, .. } => {
+            for e in entries {
+                fresh_ids(&mut e.body, spans);
+            }
+        }
+        lir::Expr::Ctor { args, .. } => {
+            for a in args {
+                fresh_ids(a, spans);
+            }
+        }
+        lir::Expr::Member { object, .. } => fresh_ids(object, spans),
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Perform { .. }
+        | lir::Expr::Error { .. } => {}
+    }
+}
+
+/// The cap op a call performs: `Cap.op(args)` is
+/// `Apply*(Force?(Member(Perform Cap, op)), args)`.
+fn perform_head(expr: &lir::Expr) -> Option<(String, String)> {
+    let mut cur = expr;
+    while let lir::Expr::Apply { callee, .. } = cur {
+        cur = callee;
+    }
+    if let lir::Expr::Force { expr, .. } = cur {
+        cur = expr;
+    }
+    match cur {
+        lir::Expr::Member { object, field, .. } => match object.as_ref() {
+            lir::Expr::Perform { cap, .. } => Some((cap.clone(), field.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The fn a call targets: `f(args)` is `Apply*(Force(Ident f), args)` and
+/// `f()` is `Force(Ident f)`. A method call `Impl.m(args)` targets
+/// `Impl.m`.
+fn call_head(expr: &lir::Expr) -> Option<String> {
+    let mut cur = expr;
+    while let lir::Expr::Apply { callee, .. } = cur {
+        cur = callee;
+    }
+    if let lir::Expr::Force { expr, .. } = cur {
+        if let lir::Expr::Ident { name, .. } = expr.as_ref() {
+            return Some(name.clone());
+        }
+        cur = expr;
+    }
+    match cur {
+        lir::Expr::Member { object, field, .. } => match object.as_ref() {
+            lir::Expr::Ident { name, .. } => Some(format!("{name}.{field}")),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn call_args(expr: &lir::Expr) -> Vec<&lir::Expr> {
+    let mut args = Vec::new();
+    let mut cur = expr;
+    while let lir::Expr::Apply { callee, arg, .. } = cur {
+        args.push(arg.as_ref());
+        cur = callee;
+    }
+    args.reverse();
+    args
+}
+
+fn call_args_mut(expr: &mut lir::Expr) -> Vec<&mut lir::Expr> {
+    let mut args = Vec::new();
+    let mut cur = expr;
+    while let lir::Expr::Apply { callee, arg, .. } = cur {
+        args.push(arg.as_mut());
+        cur = callee;
+    }
+    args.reverse();
+    args
+}
+
+fn rename_call_head(expr: &mut lir::Expr, to: &str) {
+    let mut cur = expr;
+    while let lir::Expr::Apply { callee, .. } = cur {
+        cur = callee;
+    }
+    let forced = matches!(cur, lir::Expr::Force { .. });
+    if let lir::Expr::Force { expr, .. } = cur {
+        cur = expr;
+    }
+    match cur {
+        lir::Expr::Ident { name, .. } => *name = to.to_owned(),
+        // A method call becomes a call of the free instance fn.
+        lir::Expr::Member { id, object, .. } => {
+            let ident = lir::Expr::Ident {
+                id: object.id(),
+                name: to.to_owned(),
+            };
+            *cur = if forced {
+                ident
+            } else {
+                lir::Expr::Force {
+                    id: *id,
+                    expr: Box::new(ident),
+                }
+            };
+        }
+        _ => {}
+    }
+}
+
+/// Remove generic originals that were specialized and are no longer
+/// referenced by any remaining item.
+fn drop_unused_originals(file: &mut lir::File, specialized: &HashSet<String>) {
+    if specialized.is_empty() {
+        return;
+    }
+
+    // A removed original can keep others alive, so iterate to a fixpoint.
+    loop {
+        let mut used: HashSet<String> = HashSet::new();
+        for item in &file.items {
+            let own = match item {
+                lir::Item::Fn(f) => Some(f.name.as_str()),
+                lir::Item::Data(d) => Some(d.name.as_str()),
+                _ => None,
+            };
+            let mut refs = HashSet::new();
+            item_references(item, &mut refs);
+            if let Some(own) = own {
+                refs.remove(own);
+            }
+            used.extend(refs);
+        }
+        let before = file.items.len();
+        file.items.retain(|item| match item {
+            lir::Item::Fn(f) => !specialized.contains(&f.name) || used.contains(&f.name),
+            lir::Item::Data(d) => !specialized.contains(&d.name) || used.contains(&d.name),
+            _ => true,
+        });
+        if file.items.len() == before {
+            break;
+        }
+    }
+}
+
+fn item_references(item: &lir::Item, out: &mut HashSet<String>) {
+    fn ty_refs(ty: &TypeExpr, out: &mut HashSet<String>) {
+        match ty {
+            TypeExpr::Named(n) => {
+                out.insert(n.clone());
+            }
+            TypeExpr::App { head, args } => {
+                out.insert(head.clone());
+                for a in args {
+                    ty_refs(a, out);
+                }
+            }
+            TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => ty_refs(inner, out),
+            TypeExpr::Comp { ty, .. } => ty_refs(ty, out),
+            TypeExpr::Cap { type_args, .. } => {
+                for a in type_args {
+                    ty_refs(a, out);
+                }
+            }
+            TypeExpr::Fn { params, ret, .. } => {
+                for p in params {
+                    ty_refs(p, out);
+                }
+                ty_refs(ret, out);
+            }
+            TypeExpr::Mu { body, .. } => ty_refs(body, out),
+            TypeExpr::Var(_) => {}
+        }
+    }
+    fn pattern_refs(p: &Pattern, out: &mut HashSet<String>) {
+        if let Pattern::Ctor { name, args } = p {
+            if let Some((data, _)) = name.split_once('.') {
+                out.insert(data.to_owned());
+            }
+            for a in args {
+                pattern_refs(a, out);
+            }
+        }
+    }
+    fn expr_refs(e: &lir::Expr, out: &mut HashSet<String>) {
+        match e {
+            lir::Expr::Ident { name, .. } => {
+                out.insert(name.clone());
+            }
+            lir::Expr::Ctor { name, args, .. } => {
+                if let Some((data, _)) = name.split_once('.') {
+                    out.insert(data.to_owned());
+                }
+                for a in args {
+                    expr_refs(a, out);
+                }
+            }
+            lir::Expr::Ann { expr, ty, .. } => {
+                ty_refs(ty, out);
+                expr_refs(expr, out);
+            }
+            lir::Expr::Apply { callee, arg, .. } => {
+                expr_refs(callee, out);
+                expr_refs(arg, out);
+            }
+            lir::Expr::Force { expr, .. }
+            | lir::Expr::Thunk { expr, .. }
+            | lir::Expr::Produce { expr, .. }
+            | lir::Expr::Roll { expr, .. }
+            | lir::Expr::Unroll { expr, .. } => expr_refs(expr, out),
+            lir::Expr::Lambda { body, .. } => expr_refs(body, out),
+            lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+                expr_refs(value, out);
+                expr_refs(body, out);
+            }
+            lir::Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                expr_refs(scrutinee, out);
+                for arm in arms {
+                    pattern_refs(&arm.pattern, out);
+                    expr_refs(&arm.body, out);
+                }
+            }
+            lir::Expr::Handle { handler, body, .. } => {
+                expr_refs(handler, out);
+                expr_refs(body, out);
+            }
+            lir::Expr::Bundle { entries, .. } => {
+                for entry in entries {
+                    for p in &entry.params {
+                        ty_refs(&p.ty.value, out);
+                    }
+                    expr_refs(&entry.body, out);
+                }
+            }
+            lir::Expr::Member { object, .. } => expr_refs(object, out),
+            lir::Expr::String { .. }
+            | lir::Expr::Number { .. }
+            | lir::Expr::Perform { .. }
+            | lir::Expr::Error { .. } => {}
+        }
+    }
+    let sig_refs = |params: &[lir::Param], ret: &Option<Spanned<TypeExpr>>, out: &mut HashSet<String>| {
+        for p in params {
+            ty_refs(&p.ty.value, out);
+        }
+        if let Some(r) = ret {
+            ty_refs(&r.value, out);
+        }
+    };
+    match item {
+        lir::Item::Fn(f) => {
+            sig_refs(&f.params, &f.return_type, out);
+            expr_refs(&f.value, out);
+        }
+        lir::Item::ExternFn(e) => sig_refs(&e.params, &e.return_type, out),
+        lir::Item::Data(d) => {
+            for v in &d.variants {
+                for p in &v.payload {
+                    ty_refs(&p.value, out);
+                }
+            }
+        }
+        lir::Item::Impl(impl_decl) => {
+            ty_refs(&impl_decl.target_type.value, out);
+            for m in &impl_decl.methods {
+                sig_refs(&m.params, &m.return_type, out);
+                expr_refs(&m.value, out);
+            }
+        }
+        lir::Item::Cap(c) => {
+            for op in &c.operations {
+                sig_refs(&op.params, &op.return_type, out);
+            }
+        }
+        lir::Item::ExternType(_) | lir::Item::Use(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hir, lexer::lex, parser::parse};
+
+    fn lower(src: &str) -> lir::File {
+        let lexed = lex(src);
+        let parsed = parse(&lexed.tokens, &lexed.errors);
+        lir::lower(&hir::lower(&parsed.file))
+    }
+
+    fn item_names(file: &lir::File) -> Vec<String> {
+        file.items
+            .iter()
+            .filter_map(|item| match item {
+                lir::Item::Fn(f) => Some(f.name.clone()),
+                lir::Item::Data(d) => Some(d.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    const LIST_SRC: &str = r#"
+        data List[A] { .nil, .cons(A, List[A]) }
+        fn rev_acc[A](xs: List[A], acc: List[A]): List[A] =
+          match xs { .nil => acc, .cons(h, t) => rev_acc(t, List.cons(h, acc)) }
+        fn rev[A](xs: List[A]): List[A] = rev_acc(xs, List.nil)
+    "#;
+
+    #[test]
+    fn specializes_transitively_reached_generic_fns() {
+        let src = format!(
+            "{LIST_SRC}\nfn main(): List[Number] = rev(List.cons(1, List.nil))"
+        );
+        let mut file = lower(&src);
+        let created = monomorphize(&mut file);
+        assert_eq!(created, ["List_ANumber_Z", "rev_ANumber_Z", "rev__acc_ANumber_Z"]);
+        // Fully specialized originals are gone.
+        assert_eq!(
+            item_names(&file),
+            ["List_ANumber_Z", "rev__acc_ANumber_Z", "rev_ANumber_Z", "main"]
+        );
+    }
+
+    #[test]
+    fn one_instance_per_distinct_type_argument() {
+        let src = format!(
+            "{LIST_SRC}\nfn main() = {{ let a = rev(List.cons(1, List.nil)); let b = rev(List.cons(\"x\", List.nil)); rev(List.cons(2, List.nil)) }}"
+        );
+        let mut file = lower(&src);
+        let created = monomorphize(&mut file);
+        assert_eq!(
+            created,
+            [
+                "List_ANumber_Z",
+                "List_AString_Z",
+                "rev_ANumber_Z",
+                "rev_AString_Z",
+                "rev__acc_ANumber_Z",
+                "rev__acc_AString_Z",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_applications_mangle_head_then_args() {
+        let src = format!(
+            "{LIST_SRC}\nfn main() = rev(List.cons(List.cons(1, List.nil), List.nil))"
+        );
+        let mut file = lower(&src);
+        let created = monomorphize(&mut file);
+        assert!(created.contains(&"rev_AList_ANumber_Z_Z".to_owned()), "{created:?}");
+        assert!(created.contains(&"List_AList_ANumber_Z_Z".to_owned()), "{created:?}");
+    }
+
+    #[test]
+    fn instance_names_never_collide_with_items_or_each_other() {
+        let src = r#"
+            data Box[A] { .box(A) }
+            data Box_Number { .plain }
+            data Box_ANumber_Z { .taken }
+            fn a(): Box[Number] = Box.box(1)
+            fn b(): Box[Box_Number] = Box.box(Box_Number.plain)
+            fn c(): Box[Box[Number]] = Box.box(Box.box(2))
+            fn main(): Box_ANumber_Z = Box_ANumber_Z.taken
+        "#;
+        let mut file = lower(src);
+        let created = monomorphize(&mut file);
+        assert_eq!(
+            created,
+            ["Box_ABox_ANumber_Z_Z", "Box_ABox__Number_Z", "Box_ANumber_Z_1"]
+        );
+        let names = item_names(&file);
+        assert!(names.contains(&"Box_ANumber_Z".to_owned()), "{names:?}");
+    }
+
+    #[test]
+    fn let_bound_ctor_takes_its_type_from_the_use_site() {
+        let src = format!(
+            "{LIST_SRC}\nfn main() = {{ let e = List.nil; rev(List.cons(1, e)) }}"
+        );
+        let mut file = lower(&src);
+        monomorphize(&mut file);
+        // No generic `List` left: `List.nil` was retargeted to the instance.
+        assert!(!item_names(&file).contains(&"List".to_owned()));
+    }
+
+    #[test]
+    fn unresolved_instantiation_keeps_generic_original() {
+        let src = format!("{LIST_SRC}\nfn main() = rev(List.nil)");
+        let mut file = lower(&src);
+        let created = monomorphize(&mut file);
+        assert!(created.is_empty(), "{created:?}");
+        assert!(item_names(&file).contains(&"rev".to_owned()));
+    }
+
+    const BOUNDED_SRC: &str = r#"
+        cap Hash { fn hash(a: Self): Number }
+        data Box[A] { .box(A) }
+        extern type Bag[A];
+        extern fn bag_of[A](a: A): Bag[A];
+        fn key[K: Hash](k: K): Number = Hash.hash(k)
+        fn wrap[A](a: A): Box[A] = Box.box(a)
+        impl[K: Hash] Bag {
+          fn key(self: Bag[K], k: K): Number = key(k)
+        }
+    "#;
+
+    #[test]
+    fn specialize_bounded_only_clones_bounded_callables() {
+        let src = format!(
+            "{BOUNDED_SRC}\nfn main() = {{ let b = wrap(\"x\"); let n = key(1); Bag.key(bag_of(\"x\"), \"y\") }}"
+        );
+        let mut file = lower(&src);
+        let created = specialize_bounded(&mut file);
+        assert_eq!(created, ["Bag_Dkey_AString_Z", "key_ANumber_Z", "key_AString_Z"]);
+        // Unbounded generics stay shared, data types included.
+        let names = item_names(&file);
+        assert!(names.contains(&"wrap".to_owned()), "{names:?}");
+        assert!(names.contains(&"Box".to_owned()), "{names:?}");
+    }
+
+    #[test]
+    fn generic_cap_impl_is_cloned_per_target() {
+        let src = r#"
+            cap Eq { fn eq(a: Self, b: Self): Number }
+            impl Number: Eq { fn eq(self, other: Self): Number = resume(1) }
+            impl String: Eq { fn eq(self, other: Self): Number = resume(1) }
+            data Box[A] { .box(A) }
+            fn box_eq[A: Eq](a: Box[A], b: Box[A]): Number = match a { .box(x) => match b { .box(y) => Eq.eq(x, y) } }
+            impl[A: Eq] Box[A]: Eq { fn eq(self, other: Self): Number = resume(box_eq(self, other)) }
+            fn main() = { let n = Eq.eq(Box.box(1), Box.box(2)); Eq.eq(Box.box("a"), Box.box("b")) }
+        "#;
+        let mut file = lower(src);
+        let created = monomorphize(&mut file);
+        assert!(created.contains(&"__impl_Box_ANumber_Z_Eq".to_owned()), "{created:?}");
+        assert!(created.contains(&"__impl_Box_AString_Z_Eq".to_owned()), "{created:?}");
+        // The generic impl is replaced by its instances, each with its own ids.
+        let impls: Vec<&lir::ImplDecl> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                lir::Item::Impl(i) if i.target_type.value.display().starts_with("Box") => Some(i),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(impls.len(), 2);
+        assert_ne!(impls[0].methods[0].value.id(), impls[1].methods[0].value.id());
+    }
+}
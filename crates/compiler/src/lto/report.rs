@@ -0,0 +1,398 @@
+//! Human- and machine-readable summary of what LTO decided.
+//!
+//! The report is a snapshot of the analysis `optimize` ran on: the call
+//! graph and dep-free statuses are taken before `emit::transform` rewrites
+//! anything, the swept list after `dce::sweep`. `lbs build --lto-report`
+//! prints [`LtoReport::to_text`]; `--lto-report=json` prints
+//! [`LtoReport::to_json`].
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use lumo_lir as lir;
+use lumo_types::ExprId;
+
+use super::call_graph::{CallGraph, CallTarget};
+use super::dep_free::{BlockReason, DepFreeAnalysis, DepFreeStatus, ResolvedRef};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LtoReport {
+    /// Caller → distinct callees, sorted by caller.
+    pub call_graph: Vec<(String, Vec<CallTarget>)>,
+    /// Dep-free status of every fn and impl method, sorted by name.
+    pub targets: Vec<TargetReport>,
+    /// Every `Member(Perform(cap), method)` site, in body order.
+    pub dispatch: Vec<DispatchSite>,
+    /// Fns removed by `dce::sweep`, sorted.
+    pub swept: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetReport {
+    pub name: String,
+    pub status: DepFreeStatus,
+    /// Why the target is not dep-free; `None` when it is.
+    pub reason: Option<BlockReason>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchSite {
+    pub caller: String,
+    pub cap: String,
+    pub method: String,
+    /// The impl method the site was rewritten to call directly, or `None`
+    /// if it still dispatches through the runtime cap bundle.
+    pub resolved: Option<ResolvedRef>,
+}
+
+impl LtoReport {
+    pub fn new(file: &lir::File, cg: &CallGraph, analysis: &DepFreeAnalysis) -> Self {
+        let mut call_graph: Vec<(String, Vec<CallTarget>)> = cg
+            .edges
+            .iter()
+            .map(|(caller, sites)| {
+                let mut callees: Vec<CallTarget> = Vec::new();
+                for site in sites {
+                    if !callees.contains(&site.callee) {
+                        callees.push(site.callee.clone());
+                    }
+                }
+                (caller.clone(), callees)
+            })
+            .collect();
+        call_graph.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut targets: Vec<TargetReport> = analysis
+            .status
+            .iter()
+            .filter(|(key, _)| cg.edges.contains_key(&key.0))
+            .map(|(key, status)| TargetReport {
+                name: key.0.clone(),
+                status: status.clone(),
+                reason: analysis.reasons.get(key).cloned(),
+            })
+            .collect();
+        targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut dispatch = Vec::new();
+        for (caller, body) in bodies(file) {
+            collect_dispatch(body, &caller, &analysis.perform_resolution, &mut dispatch);
+        }
+
+        LtoReport {
+            call_graph,
+            targets,
+            dispatch,
+            swept: Vec::new(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("call graph:\n");
+        for (caller, callees) in &self.call_graph {
+            let callees: Vec<String> = callees.iter().map(target_name).collect();
+            if callees.is_empty() {
+                let _ = writeln!(out, "  {caller}");
+            } else {
+                let _ = writeln!(out, "  {caller} -> {}", callees.join(", "));
+            }
+        }
+        out.push_str("dep-free:\n");
+        for t in &self.targets {
+            match &t.reason {
+                Some(reason) => {
+                    let _ = writeln!(out, "  {}: blocked, {}", t.name, describe(reason));
+                }
+                None => {
+                    let _ = writeln!(out, "  {}: {}", t.name, status_name(&t.status));
+                }
+            }
+        }
+        let resolved = self.dispatch.iter().filter(|d| d.resolved.is_some()).count();
+        let _ = writeln!(
+            out,
+            "cap dispatch: {resolved} resolved, {} indirect",
+            self.dispatch.len() - resolved
+        );
+        // Runs of identical sites (e.g. every `+` in one fn) share a line.
+        let mut i = 0;
+        while i < self.dispatch.len() {
+            let d = &self.dispatch[i];
+            let run = self.dispatch[i..].iter().take_while(|e| *e == d).count();
+            let target = match &d.resolved {
+                Some(r) => format!("{}.{}", r.impl_const, r.method),
+                None => "<indirect>".to_owned(),
+            };
+            let _ = write!(out, "  {}: {}.{} -> {target}", d.caller, d.cap, d.method);
+            if run > 1 {
+                let _ = write!(out, " (x{run})");
+            }
+            out.push('\n');
+            i += run;
+        }
+        out.push_str("swept:\n");
+        for name in &self.swept {
+            let _ = writeln!(out, "  {name}");
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let call_graph: Vec<String> = self
+            .call_graph
+            .iter()
+            .map(|(caller, callees)| {
+                let callees: Vec<String> =
+                    callees.iter().map(|c| json_string(&target_name(c))).collect();
+                format!("{}:[{}]", json_string(caller), callees.join(","))
+            })
+            .collect();
+        let targets: Vec<String> = self
+            .targets
+            .iter()
+            .map(|t| {
+                let reason = match &t.reason {
+                    Some(r) => json_reason(r),
+                    None => "null".to_owned(),
+                };
+                format!(
+                    "{{\"name\":{},\"status\":{},\"reason\":{reason}}}",
+                    json_string(&t.name),
+                    json_string(status_name(&t.status)),
+                )
+            })
+            .collect();
+        let dispatch: Vec<String> = self
+            .dispatch
+            .iter()
+            .map(|d| {
+                let resolved = match &d.resolved {
+                    Some(r) => json_string(&format!("{}.{}", r.impl_const, r.method)),
+                    None => "null".to_owned(),
+                };
+                format!(
+                    "{{\"caller\":{},\"cap\":{},\"method\":{},\"resolved\":{resolved}}}",
+                    json_string(&d.caller),
+                    json_string(&d.cap),
+                    json_string(&d.method),
+                )
+            })
+            .collect();
+        let swept: Vec<String> = self.swept.iter().map(|s| json_string(s)).collect();
+        format!(
+            "{{\"call_graph\":{{{}}},\"targets\":[{}],\"dispatch\":[{}],\"swept\":[{}]}}\n",
+            call_graph.join(","),
+            targets.join(","),
+            dispatch.join(","),
+            swept.join(","),
+        )
+    }
+}
+
+/// Fn and impl-method bodies, keyed the way the call graph keys them.
+fn bodies(file: &lir::File) -> Vec<(String, &lir::Expr)> {
+    let mut out = Vec::new();
+    for item in &file.items {
+        match item {
+            lir::Item::Fn(f) => out.push((f.name.clone(), &f.value)),
+            lir::Item::Impl(impl_decl) => {
+                let target = impl_decl.target_type.value.display();
+                let const_name = impl_decl.name.clone().unwrap_or_else(|| {
+                    match &impl_decl.capability {
+                        Some(cap) => format!("__impl_{target}_{}", cap.value.display()),
+                        None => target.clone(),
+                    }
+                });
+                for m in &impl_decl.methods {
+                    out.push((format!("{const_name}.{}", m.name), &m.value));
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn collect_dispatch(
+    expr: &lir::Expr,
+    caller: &str,
+    resolved: &HashMap<ExprId, ResolvedRef>,
+    out: &mut Vec<DispatchSite>,
+) {
+    match expr {
+        lir::Expr::Member { object, field, id } => {
+            if let lir::Expr::Perform { cap, .. } = object.as_ref() {
+                out.push(DispatchSite {
+                    caller: caller.to_owned(),
+                    cap: cap.clone(),
+                    method: field.clone(),
+                    resolved: resolved.get(id).cloned(),
+                });
+            } else {
+                collect_dispatch(object, caller, resolved, out);
+            }
+        }
+        lir::Expr::Apply { callee, arg, .. } => {
+            collect_dispatch(callee, caller, resolved, out);
+            collect_dispatch(arg, caller, resolved, out);
+        }
+        lir::Expr::Force { expr, .. }
+        | lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Ann { expr, .. } => collect_dispatch(expr, caller, resolved, out),
+        lir::Expr::Lambda { body, .. } => collect_dispatch(body, caller, resolved, out),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            collect_dispatch(value, caller, resolved, out);
+            collect_dispatch(body, caller, resolved, out);
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            collect_dispatch(scrutinee, caller, resolved, out);
+            for arm in arms {
+                collect_dispatch(&arm.body, caller, resolved, out);
+            }
+        }
+        lir::Expr::Handle { handler, body, .. } => {
+            collect_dispatch(handler, caller, resolved, out);
+            collect_dispatch(body, caller, resolved, out);
+        }
+        lir::Expr::Bundle { entries, .. } => {
+            for e in entries {
+                collect_dispatch(&e.body, caller, resolved, out);
+            }
+        }
+        lir::Expr::Ctor { args, .. } => {
+            for a in args {
+                collect_dispatch(a, caller, resolved, out);
+            }
+        }
+        lir::Expr::Perform { .. }
+        | lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Error { .. } => {}
+    }
+}
+
+fn target_name(target: &CallTarget) -> String {
+    match target {
+        CallTarget::Fn(name) => name.clone(),
+        CallTarget::ImplMethod { impl_const, method } => format!("{impl_const}.{method}"),
+        CallTarget::Perform(cap) => format!("<perform {cap}>"),
+        CallTarget::Indirect => "<indirect>".to_owned(),
+    }
+}
+
+fn status_name(status: &DepFreeStatus) -> &'static str {
+    match status {
+        DepFreeStatus::Pending => "pending",
+        DepFreeStatus::DepFree => "dep-free",
+        DepFreeStatus::Blocked => "blocked",
+    }
+}
+
+fn describe(reason: &BlockReason) -> String {
+    match reason {
+        BlockReason::IndirectCall => "indirect call".to_owned(),
+        BlockReason::UnknownCallee(name) => format!("calls `{name}`, which has no body"),
+        BlockReason::UnresolvedPerform(cap) => format!("performs `{cap}` with no default impl"),
+        BlockReason::BlockedCallee(name) => format!("depends on blocked `{name}`"),
+    }
+}
+
+fn json_reason(reason: &BlockReason) -> String {
+    let (kind, subject) = match reason {
+        BlockReason::IndirectCall => return "{\"kind\":\"indirect-call\"}".to_owned(),
+        BlockReason::UnknownCallee(name) => ("unknown-callee", name),
+        BlockReason::UnresolvedPerform(cap) => ("unresolved-perform", cap),
+        BlockReason::BlockedCallee(name) => ("blocked-callee", name),
+    };
+    format!("{{\"kind\":\"{kind}\",\"target\":{}}}", json_string(subject))
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hir, lexer::lex, lto, parser::parse};
+
+    fn lower(src: &str) -> lir::File {
+        let lexed = lex(src);
+        let parsed = parse(&lexed.tokens, &lexed.errors);
+        lir::lower(&hir::lower(&parsed.file))
+    }
+
+    #[test]
+    fn report_explains_blocked_fns_and_lists_swept_ones() {
+        let src = r#"
+            fn apply(f: thunk Number, x: Number): Number { f(x) }
+            fn dead(): Number { 1 }
+            fn main(): Number { apply(2, 3) }
+        "#;
+        let mut file = lower(src);
+        let (errors, report) = lto::optimize_with_report(&mut file);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(report.swept, vec!["dead".to_owned()]);
+        let text = report.to_text();
+        assert!(text.contains("  main -> apply\n"), "{text}");
+        assert!(text.contains("  apply: blocked, indirect call\n"), "{text}");
+        assert!(text.contains("  main: blocked, depends on blocked `apply`\n"), "{text}");
+        assert!(text.contains("  dead: dep-free\n"), "{text}");
+    }
+
+    #[test]
+    fn report_ignores_performs_discharged_by_a_local_handle() {
+        let src = r#"
+            cap Log { fn log(msg: String): Number }
+            fn quiet(h: Bundle[Log]): Number { handle Log with h in Log.log("x") }
+            fn noisy(): Number { Log.log("y") }
+            fn main(h: Bundle[Log]): Number { quiet(h) }
+        "#;
+        let mut file = lower(src);
+        let (errors, report) = lto::optimize_with_report(&mut file);
+        assert!(errors.is_empty(), "{errors:?}");
+        let text = report.to_text();
+        assert!(text.contains("  quiet: dep-free\n"), "{text}");
+        assert!(text.contains("  main: dep-free\n"), "{text}");
+        assert!(text.contains("  noisy: blocked, performs `Log` with no default impl\n"), "{text}");
+    }
+
+    #[test]
+    fn json_escapes_names_and_encodes_reasons() {
+        let report = LtoReport {
+            call_graph: vec![("main".to_owned(), vec![CallTarget::Indirect])],
+            targets: vec![TargetReport {
+                name: "main".to_owned(),
+                status: DepFreeStatus::Blocked,
+                reason: Some(BlockReason::UnresolvedPerform("Lo\"g".to_owned())),
+            }],
+            dispatch: Vec::new(),
+            swept: Vec::new(),
+        };
+        assert_eq!(
+            report.to_json(),
+            "{\"call_graph\":{\"main\":[\"<indirect>\"]},\"targets\":[{\"name\":\"main\",\
+             \"status\":\"blocked\",\"reason\":{\"kind\":\"unresolved-perform\",\
+             \"target\":\"Lo\\\"g\"}}],\"dispatch\":[],\"swept\":[]}\n"
+        );
+    }
+}
@@ -6,6 +6,10 @@ use lumo_lir as lir;
 pub struct ResolutionMap {
     impls: HashMap<(String, Vec<String>), ImplResolution>,
     ambiguous: HashSet<(String, Vec<String>)>,
+    /// Inherent `impl T { ... }` methods keyed by `(impl_const, method)`.
+    /// Not part of cap dispatch, but LTO inlines them into dep-free bodies
+    /// the same way it inlines resolved perform sites.
+    inherent: HashMap<(String, String), MethodInfo>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +35,11 @@ impl ResolutionMap {
     pub fn keys(&self) -> impl Iterator<Item = &(String, Vec<String>)> {
         self.impls.keys()
     }
+
+    pub fn inherent_method(&self, impl_const: &str, method: &str) -> Option<&MethodInfo> {
+        self.inherent
+            .get(&(impl_const.to_owned(), method.to_owned()))
+    }
 }
 
 pub fn build_resolution_map(file: &lir::File) -> ResolutionMap {
@@ -45,6 +54,7 @@ pub fn build_resolution_map(file: &lir::File) -> ResolutionMap {
 
     let mut impls: HashMap<(String, Vec<String>), ImplResolution> = HashMap::new();
     let mut ambiguous: HashSet<(String, Vec<String>)> = HashSet::new();
+    let mut inherent: HashMap<(String, String), MethodInfo> = HashMap::new();
 
     for item in &file.items {
         let lir::Item::Impl(impl_decl) = item else {
@@ -67,7 +77,17 @@ pub fn build_resolution_map(file: &lir::File) -> ResolutionMap {
                     .unwrap_or_else(|| format!("__impl_{target}_{cap}"));
                 ((cap, vec![target]), const_name)
             } else {
-                // Inherent impl `impl T { ... }` where T is not a cap — skip.
+                // Inherent impl `impl T { ... }` where T is not a cap.
+                let const_name = impl_decl.name.clone().unwrap_or(target);
+                for m in &impl_decl.methods {
+                    inherent.insert(
+                        (const_name.clone(), m.name.clone()),
+                        MethodInfo {
+                            params: m.params.clone(),
+                            body: m.value.clone(),
+                        },
+                    );
+                }
                 continue;
             };
 
@@ -98,7 +118,44 @@ pub fn build_resolution_map(file: &lir::File) -> ResolutionMap {
         }
     }
 
-    ResolutionMap { impls, ambiguous }
+    // A `handle` left after `lower_local_handlers` installs its bundle at
+    // run time, so a perform of its cap can't be pinned to the default impl.
+    let mut handled: Vec<(String, Vec<String>)> = Vec::new();
+    for item in &file.items {
+        match item {
+            lir::Item::Fn(f) => collect_handled(&f.value, &mut handled),
+            lir::Item::Impl(i) => {
+                for m in &i.methods {
+                    collect_handled(&m.value, &mut handled);
+                }
+            }
+            _ => {}
+        }
+    }
+    for key in impls.keys() {
+        let shadowed = handled
+            .iter()
+            .any(|(cap, args)| *cap == key.0 && (args.is_empty() || *args == key.1));
+        if shadowed {
+            ambiguous.insert(key.clone());
+        }
+    }
+
+    ResolutionMap {
+        impls,
+        ambiguous,
+        inherent,
+    }
+}
+
+/// The `(cap, type_args)` of every `handle` in `expr`.
+fn collect_handled(expr: &lir::Expr, out: &mut Vec<(String, Vec<String>)>) {
+    if let lir::Expr::Handle { cap, type_args, .. } = expr {
+        out.push((cap.clone(), type_args.clone()));
+    }
+    for child in super::handlers::children(expr) {
+        collect_handled(child, out);
+    }
 }
 
 #[cfg(test)]
@@ -141,6 +198,32 @@ mod tests {
         assert_eq!(res.impl_const, "Logger");
     }
 
+    #[test]
+    fn inherent_impl_methods_are_indexed_separately() {
+        let src = r#"
+            cap Logger { fn log(msg: String): Number }
+            impl String { fn shout(self): Number { Logger.log(self) } }
+        "#;
+        let file = lower(src);
+        let map = build_resolution_map(&file);
+        assert!(map.keys().next().is_none(), "inherent impls are not cap dispatch");
+        let method = map.inherent_method("String", "shout").expect("indexed");
+        assert_eq!(method.params.len(), 1);
+    }
+
+    #[test]
+    fn caps_handled_at_run_time_are_excluded() {
+        let src = r#"
+            cap Logger { fn log(msg: String): Number }
+            impl Logger { fn log(msg: String): Number { 0 } }
+            fn quiet(h: Bundle[Logger]): Number { handle Logger with h in Logger.log("x") }
+        "#;
+        let file = lower(src);
+        let map = build_resolution_map(&file);
+        let key = ("Logger".to_owned(), vec!["Logger".to_owned()]);
+        assert!(map.get(&key).is_none(), "a `handle` may install another bundle");
+    }
+
     #[test]
     fn ambiguous_impls_are_excluded() {
         let src = r#"
@@ -0,0 +1,97 @@
+//! Selective CPS: compile functions that never perform in direct style.
+//!
+//! Cap inference only ever *adds* caps to a fn (see
+//! `typecheck::apply_inferred_caps`), so after LTO a fn can still carry the
+//! caps it needed before its callees were replaced by dep-free clones. The
+//! backends take `cap` at face value and lower such a fn to CPS — `__caps`
+//! bundle, continuation, `__thunk` — even though nothing in its body
+//! performs any more.
+//!
+//! This pass re-runs `typecheck::infer_caps_for_file` after LTO with those
+//! stale rows cleared, so each fn gets the caps its body needs now rather
+//! than the union of everything it ever needed. A fn left with none is
+//! emitted as an ordinary function and every caller calls it directly;
+//! inference already treats a pure recursive group as pure.
+//!
+//! Only fns whose cap row was inferred (no annotation, or an open one) are
+//! cleared: a closed annotation is part of the fn's declared interface, and
+//! a `..row` spread means the effects come from a caller-supplied function.
+//! Fns referenced as values keep their row too, since their calling
+//! convention is fixed by whoever receives them.
+
+use std::collections::HashSet;
+
+use lumo_lir as lir;
+use lumo_types::{CapEntry, cap_ref_is_effectful, cap_ref_is_open};
+
+use super::handlers::children;
+use crate::typecheck;
+
+/// Re-infer every inferred cap row from scratch. Returns the fns whose
+/// row had caps and now has none, sorted.
+pub fn demote_direct_style(file: &mut lir::File) -> Vec<String> {
+    let fn_names: HashSet<String> = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            lir::Item::Fn(f) => Some(f.name.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut escaping = HashSet::new();
+    for item in &file.items {
+        match item {
+            lir::Item::Fn(f) => collect_escaping(&f.value, &fn_names, &mut escaping),
+            lir::Item::Impl(i) => {
+                for m in &i.methods {
+                    collect_escaping(&m.value, &fn_names, &mut escaping);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut cleared = Vec::new();
+    for item in &mut file.items {
+        let lir::Item::Fn(f) = item else { continue };
+        let Some(cap) = f.cap.as_deref() else { continue };
+        let spread = cap.iter().any(|e| matches!(e, CapEntry::Spread(_)));
+        if cap_ref_is_effectful(cap) && cap_ref_is_open(cap) && !spread && !escaping.contains(&f.name) {
+            f.cap = Some(vec![CapEntry::Infer]);
+            cleared.push(f.name.clone());
+        }
+    }
+    let (inferred, _) = typecheck::infer_caps_for_file(file);
+    typecheck::apply_inferred_caps(file, &inferred);
+
+    let still_effectful: HashSet<&str> = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            lir::Item::Fn(f) if f.cap.as_deref().is_some_and(cap_ref_is_effectful) => Some(f.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut demoted: Vec<String> = cleared
+        .into_iter()
+        .filter(|name| !still_effectful.contains(name.as_str()))
+        .collect();
+    demoted.sort();
+    demoted
+}
+
+/// Fns named in `expr` other than as a callee: passed, stored or returned.
+fn collect_escaping(expr: &lir::Expr, fn_names: &HashSet<String>, out: &mut HashSet<String>) {
+    match expr {
+        // `f(...)` and `f()` both reach the callee through `Force(Ident(f))`.
+        lir::Expr::Force { expr: inner, .. } if matches!(inner.as_ref(), lir::Expr::Ident { .. }) => {}
+        lir::Expr::Ident { name, .. } if fn_names.contains(name) => {
+            out.insert(name.clone());
+        }
+        _ => {
+            for child in children(expr) {
+                collect_escaping(child, fn_names, out);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Persistent cache behind a [`QueryEngine`](super::QueryEngine).
+//!
+//! Entries live under `<dir>/<kind>/<key>`, where `key` is a hex FNV hash:
+//!
+//! - `hir/` — a file's lowered HIR, keyed by its source hash.
+//! - `module/` — the LIR `lower_module` produced for a group of files,
+//!   keyed by every file's name and source hash.
+//! - `typecheck/` — the type errors for a LIR file, keyed by its encoding.
+//!
+//! Writes go through a temporary file and a rename, so a reader never sees
+//! a half-written entry. Any I/O or decode failure is a miss: the cache only
+//! ever saves work, it never changes what the compiler produces.
+
+use std::path::{Path, PathBuf};
+
+use super::codec::{self, Decode, Encode};
+
+/// Prefixes every entry with a hash of the compiler's sources (see
+/// `build.rs`), so entries written by a different build are never read back.
+const FORMAT: &str = concat!("lumo-cache ", env!("LUMO_COMPILER_HASH"), " ");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CacheKind {
+    Hir,
+    Module,
+    Typecheck,
+}
+
+impl CacheKind {
+    fn dir_name(self) -> &'static str {
+        match self {
+            CacheKind::Hir => "hir",
+            CacheKind::Module => "module",
+            CacheKind::Typecheck => "typecheck",
+        }
+    }
+}
+
+/// Hits and misses for one kind of cached query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) fn load<T: Decode>(&self, kind: CacheKind, key: u64) -> Option<T> {
+        let bytes = std::fs::read(self.path(kind, key)).ok()?;
+        let body = bytes.strip_prefix(FORMAT.as_bytes())?;
+        codec::decode(body)
+    }
+
+    pub(crate) fn store<T: Encode>(&self, kind: CacheKind, key: u64, value: &T) {
+        let path = self.path(kind, key);
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        if std::fs::create_dir_all(dir).is_err() {
+            return;
+        }
+        let mut bytes = FORMAT.as_bytes().to_vec();
+        bytes.extend(codec::encode(value));
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        if std::fs::write(&tmp, &bytes).is_ok() && std::fs::rename(&tmp, &path).is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+
+    fn path(&self, kind: CacheKind, key: u64) -> PathBuf {
+        self.dir.join(kind.dir_name()).join(format!("{key:016x}"))
+    }
+}
@@ -0,0 +1,1292 @@
+//! Binary encoding of HIR, LIR and type errors for the on-disk cache.
+//!
+//! The text printers in `hir::print`/`lir::print` are for reading IR, not
+//! storing it: they drop spans and attributes, and post-LTO LIR prints forms
+//! the parser does not accept. This codec writes every field, so a decoded
+//! value compares equal to the one that was encoded.
+//!
+//! Integers are LEB128 varints, strings and sequences are length-prefixed,
+//! and enums are a tag byte followed by the variant's fields in declaration
+//! order. Decoding returns `None` on any malformed input; the cache treats
+//! that as a miss.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    hir,
+    lexer::Span,
+    lir,
+    typecheck::TypeError,
+    types::{CapEntry, ContentHash, ExprId, Pattern, Spanned, TypeExpr},
+};
+
+pub(crate) struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes.push(tag);
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn tag(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.tag()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+}
+
+pub(crate) trait Encode {
+    fn encode(&self, w: &mut Writer);
+}
+
+pub(crate) trait Decode: Sized {
+    fn decode(r: &mut Reader) -> Option<Self>;
+}
+
+pub(crate) fn encode<T: Encode>(value: &T) -> Vec<u8> {
+    let mut w = Writer::new();
+    value.encode(&mut w);
+    w.finish()
+}
+
+/// Decode a whole buffer; trailing bytes count as malformed.
+pub(crate) fn decode<T: Decode>(bytes: &[u8]) -> Option<T> {
+    let mut r = Reader::new(bytes);
+    let value = T::decode(&mut r)?;
+    r.is_at_end().then_some(value)
+}
+
+// ---------------------------------------------------------------------------
+// Primitives and containers
+// ---------------------------------------------------------------------------
+
+impl Encode for u64 {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(*self);
+    }
+}
+
+impl Decode for u64 {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        r.varint()
+    }
+}
+
+impl Encode for u32 {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(u64::from(*self));
+    }
+}
+
+impl Decode for u32 {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        u32::try_from(r.varint()?).ok()
+    }
+}
+
+impl Encode for usize {
+    fn encode(&self, w: &mut Writer) {
+        w.varint(*self as u64);
+    }
+}
+
+impl Decode for usize {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        usize::try_from(r.varint()?).ok()
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, w: &mut Writer) {
+        w.tag(u8::from(*self));
+    }
+}
+
+impl Decode for bool {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        match r.tag()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, w: &mut Writer) {
+        self.len().encode(w);
+        w.bytes.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        let len = usize::decode(r)?;
+        let bytes = r.take(len)?;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, w: &mut Writer) {
+        self.len().encode(w);
+        for item in self {
+            item.encode(w);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        let len = usize::decode(r)?;
+        // Every element takes at least one byte, so a length past the end
+        // of the buffer is malformed rather than a reason to allocate.
+        if len > r.bytes.len() - r.pos {
+            return None;
+        }
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(T::decode(r)?);
+        }
+        Some(items)
+    }
+}
+
+impl<T: Encode> Encode for BTreeSet<T> {
+    fn encode(&self, w: &mut Writer) {
+        self.len().encode(w);
+        for item in self {
+            item.encode(w);
+        }
+    }
+}
+
+impl<T: Decode + Ord> Decode for BTreeSet<T> {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(Vec::decode(r)?.into_iter().collect())
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            None => w.tag(0),
+            Some(value) => {
+                w.tag(1);
+                value.encode(w);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        match r.tag()? {
+            0 => Some(None),
+            1 => Some(Some(T::decode(r)?)),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Encode> Encode for Box<T> {
+    fn encode(&self, w: &mut Writer) {
+        (**self).encode(w);
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        T::decode(r).map(Box::new)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, w: &mut Writer) {
+        self.0.encode(w);
+        self.1.encode(w);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+/// Implements `Encode`/`Decode` for a struct by its fields, in the order
+/// listed. Every field must be listed.
+macro_rules! record {
+    ($ty:path { $($field:ident),* $(,)? }) => {
+        impl Encode for $ty {
+            fn encode(&self, w: &mut Writer) {
+                $(self.$field.encode(w);)*
+            }
+        }
+
+        impl Decode for $ty {
+            fn decode(r: &mut Reader) -> Option<Self> {
+                Some(Self { $($field: Decode::decode(r)?),* })
+            }
+        }
+    };
+}
+
+// ---------------------------------------------------------------------------
+// Shared types
+// ---------------------------------------------------------------------------
+
+record!(Span { start, end });
+
+impl Encode for ContentHash {
+    fn encode(&self, w: &mut Writer) {
+        self.0.encode(w);
+    }
+}
+
+impl Decode for ContentHash {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        u64::decode(r).map(ContentHash)
+    }
+}
+
+impl Encode for ExprId {
+    fn encode(&self, w: &mut Writer) {
+        self.0.encode(w);
+    }
+}
+
+impl Decode for ExprId {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        u32::decode(r).map(ExprId)
+    }
+}
+
+impl<T: Encode> Encode for Spanned<T> {
+    fn encode(&self, w: &mut Writer) {
+        self.value.encode(w);
+        self.span.encode(w);
+    }
+}
+
+impl<T: Decode> Decode for Spanned<T> {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(Spanned {
+            value: T::decode(r)?,
+            span: Span::decode(r)?,
+        })
+    }
+}
+
+impl Encode for TypeExpr {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            TypeExpr::Named(name) => {
+                w.tag(0);
+                name.encode(w);
+            }
+            TypeExpr::App { head, args } => {
+                w.tag(1);
+                head.encode(w);
+                args.encode(w);
+            }
+            TypeExpr::Produce(inner) => {
+                w.tag(2);
+                inner.encode(w);
+            }
+            TypeExpr::Thunk(inner) => {
+                w.tag(3);
+                inner.encode(w);
+            }
+            TypeExpr::Cap { name, type_args } => {
+                w.tag(4);
+                name.encode(w);
+                type_args.encode(w);
+            }
+            TypeExpr::Fn { params, ret, cap } => {
+                w.tag(5);
+                params.encode(w);
+                ret.encode(w);
+                cap.encode(w);
+            }
+            TypeExpr::Mu { var, body } => {
+                w.tag(6);
+                var.encode(w);
+                body.encode(w);
+            }
+            TypeExpr::Var(name) => {
+                w.tag(7);
+                name.encode(w);
+            }
+            TypeExpr::Comp { ty, cap } => {
+                w.tag(8);
+                ty.encode(w);
+                cap.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for TypeExpr {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => TypeExpr::Named(Decode::decode(r)?),
+            1 => TypeExpr::App {
+                head: Decode::decode(r)?,
+                args: Decode::decode(r)?,
+            },
+            2 => TypeExpr::Produce(Decode::decode(r)?),
+            3 => TypeExpr::Thunk(Decode::decode(r)?),
+            4 => TypeExpr::Cap {
+                name: Decode::decode(r)?,
+                type_args: Decode::decode(r)?,
+            },
+            5 => TypeExpr::Fn {
+                params: Decode::decode(r)?,
+                ret: Decode::decode(r)?,
+                cap: Decode::decode(r)?,
+            },
+            6 => TypeExpr::Mu {
+                var: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+            },
+            7 => TypeExpr::Var(Decode::decode(r)?),
+            8 => TypeExpr::Comp {
+                ty: Decode::decode(r)?,
+                cap: Decode::decode(r)?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl Encode for CapEntry {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            CapEntry::Cap(ty) => {
+                w.tag(0);
+                ty.encode(w);
+            }
+            CapEntry::Spread(name) => {
+                w.tag(1);
+                name.encode(w);
+            }
+            CapEntry::Infer => w.tag(2),
+        }
+    }
+}
+
+impl Decode for CapEntry {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => CapEntry::Cap(Decode::decode(r)?),
+            1 => CapEntry::Spread(Decode::decode(r)?),
+            2 => CapEntry::Infer,
+            _ => return None,
+        })
+    }
+}
+
+impl Encode for Pattern {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            Pattern::Wildcard => w.tag(0),
+            Pattern::Bind(name) => {
+                w.tag(1);
+                name.encode(w);
+            }
+            Pattern::Ctor { name, args } => {
+                w.tag(2);
+                name.encode(w);
+                args.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for Pattern {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => Pattern::Wildcard,
+            1 => Pattern::Bind(Decode::decode(r)?),
+            2 => Pattern::Ctor {
+                name: Decode::decode(r)?,
+                args: Decode::decode(r)?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+impl Encode for hir::GenericParam {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            hir::GenericParam::Type(name, bounds) => {
+                w.tag(0);
+                name.encode(w);
+                bounds.encode(w);
+            }
+            hir::GenericParam::CapRow(name) => {
+                w.tag(1);
+                name.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for hir::GenericParam {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => hir::GenericParam::Type(Decode::decode(r)?, Decode::decode(r)?),
+            1 => hir::GenericParam::CapRow(Decode::decode(r)?),
+            _ => return None,
+        })
+    }
+}
+
+record!(TypeError {
+    node_id,
+    span,
+    message,
+    fn_name,
+});
+
+// ---------------------------------------------------------------------------
+// HIR
+// ---------------------------------------------------------------------------
+
+record!(hir::File {
+    items,
+    content_hash,
+    errors,
+});
+record!(hir::HirError { span, message });
+record!(hir::Cfg {
+    target,
+    features,
+    declared,
+});
+record!(hir::ExternTypeDecl {
+    name,
+    generics,
+    extern_name,
+    span,
+});
+record!(hir::ExternFnDecl {
+    name,
+    extern_name,
+    link_module,
+    inline,
+    generics,
+    params,
+    return_type,
+    cap,
+    span,
+});
+record!(hir::DataDecl {
+    name,
+    generics,
+    derives,
+    variants,
+    span,
+});
+record!(hir::VariantDecl {
+    name,
+    payload,
+    as_raw,
+    span,
+});
+record!(hir::CapDecl {
+    name,
+    operations,
+    span,
+});
+record!(hir::OperationDecl {
+    name,
+    generics,
+    params,
+    return_type,
+    span,
+});
+record!(hir::FnDecl {
+    name,
+    generics,
+    params,
+    return_type,
+    cap,
+    body,
+    inline,
+    span,
+});
+record!(hir::UseDecl { path, names, span });
+record!(hir::ImplDecl {
+    name,
+    generics,
+    target_type,
+    capability,
+    methods,
+    span,
+});
+record!(hir::ImplMethodDecl {
+    name,
+    params,
+    return_type,
+    body,
+    span,
+});
+record!(hir::Param { name, ty, span });
+record!(hir::MatchArm {
+    pattern,
+    body,
+    span,
+});
+record!(hir::BundleEntry {
+    name,
+    params,
+    body,
+    span,
+});
+
+impl Encode for hir::Item {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            hir::Item::ExternType(decl) => {
+                w.tag(0);
+                decl.encode(w);
+            }
+            hir::Item::ExternFn(decl) => {
+                w.tag(1);
+                decl.encode(w);
+            }
+            hir::Item::Data(decl) => {
+                w.tag(2);
+                decl.encode(w);
+            }
+            hir::Item::Cap(decl) => {
+                w.tag(3);
+                decl.encode(w);
+            }
+            hir::Item::Fn(decl) => {
+                w.tag(4);
+                decl.encode(w);
+            }
+            hir::Item::Use(decl) => {
+                w.tag(5);
+                decl.encode(w);
+            }
+            hir::Item::Impl(decl) => {
+                w.tag(6);
+                decl.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for hir::Item {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => hir::Item::ExternType(Decode::decode(r)?),
+            1 => hir::Item::ExternFn(Decode::decode(r)?),
+            2 => hir::Item::Data(Decode::decode(r)?),
+            3 => hir::Item::Cap(Decode::decode(r)?),
+            4 => hir::Item::Fn(Decode::decode(r)?),
+            5 => hir::Item::Use(Decode::decode(r)?),
+            6 => hir::Item::Impl(Decode::decode(r)?),
+            _ => return None,
+        })
+    }
+}
+
+impl Encode for hir::AsRawValue {
+    fn encode(&self, w: &mut Writer) {
+        w.tag(match self {
+            hir::AsRawValue::True => 0,
+            hir::AsRawValue::False => 1,
+        });
+    }
+}
+
+impl Decode for hir::AsRawValue {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        match r.tag()? {
+            0 => Some(hir::AsRawValue::True),
+            1 => Some(hir::AsRawValue::False),
+            _ => None,
+        }
+    }
+}
+
+impl Encode for hir::Expr {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            hir::Expr::Ident { name, span } => {
+                w.tag(0);
+                name.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::String { value, span } => {
+                w.tag(1);
+                value.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Number { value, span } => {
+                w.tag(2);
+                value.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Call { callee, args, span } => {
+                w.tag(3);
+                callee.encode(w);
+                args.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Member {
+                object,
+                member,
+                span,
+            } => {
+                w.tag(4);
+                object.encode(w);
+                member.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Produce { expr, span } => {
+                w.tag(5);
+                expr.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Thunk { expr, span } => {
+                w.tag(6);
+                expr.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Lambda { params, body, span } => {
+                w.tag(7);
+                params.encode(w);
+                body.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Force { expr, span } => {
+                w.tag(8);
+                expr.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Let {
+                name,
+                value,
+                body,
+                span,
+            } => {
+                w.tag(9);
+                name.encode(w);
+                value.encode(w);
+                body.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Match {
+                scrutinee,
+                arms,
+                span,
+            } => {
+                w.tag(10);
+                scrutinee.encode(w);
+                arms.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Perform { cap, span } => {
+                w.tag(11);
+                cap.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Handle {
+                cap,
+                type_args,
+                handler,
+                body,
+                span,
+            } => {
+                w.tag(12);
+                cap.encode(w);
+                type_args.encode(w);
+                handler.encode(w);
+                body.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Bundle { entries, span } => {
+                w.tag(13);
+                entries.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Ann { expr, ty, span } => {
+                w.tag(14);
+                expr.encode(w);
+                ty.encode(w);
+                span.encode(w);
+            }
+            hir::Expr::Error { span } => {
+                w.tag(15);
+                span.encode(w);
+            }
+            hir::Expr::Loop {
+                var,
+                init,
+                body,
+                span,
+            } => {
+                w.tag(16);
+                var.encode(w);
+                init.encode(w);
+                body.encode(w);
+                span.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for hir::Expr {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => hir::Expr::Ident {
+                name: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            1 => hir::Expr::String {
+                value: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            2 => hir::Expr::Number {
+                value: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            3 => hir::Expr::Call {
+                callee: Decode::decode(r)?,
+                args: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            4 => hir::Expr::Member {
+                object: Decode::decode(r)?,
+                member: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            5 => hir::Expr::Produce {
+                expr: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            6 => hir::Expr::Thunk {
+                expr: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            7 => hir::Expr::Lambda {
+                params: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            8 => hir::Expr::Force {
+                expr: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            9 => hir::Expr::Let {
+                name: Decode::decode(r)?,
+                value: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            10 => hir::Expr::Match {
+                scrutinee: Decode::decode(r)?,
+                arms: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            11 => hir::Expr::Perform {
+                cap: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            12 => hir::Expr::Handle {
+                cap: Decode::decode(r)?,
+                type_args: Decode::decode(r)?,
+                handler: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            13 => hir::Expr::Bundle {
+                entries: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            14 => hir::Expr::Ann {
+                expr: Decode::decode(r)?,
+                ty: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            15 => hir::Expr::Error {
+                span: Decode::decode(r)?,
+            },
+            16 => hir::Expr::Loop {
+                var: Decode::decode(r)?,
+                init: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+                span: Decode::decode(r)?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// LIR
+// ---------------------------------------------------------------------------
+
+record!(lir::File {
+    items,
+    content_hash,
+    spans,
+});
+record!(lir::ExternTypeDecl {
+    name,
+    generics,
+    extern_name,
+    span,
+});
+record!(lir::ExternFnDecl {
+    name,
+    extern_name,
+    link_module,
+    inline,
+    generics,
+    params,
+    return_type,
+    cap,
+    span,
+});
+record!(lir::DataDecl {
+    name,
+    generics,
+    variants,
+    span,
+});
+record!(lir::VariantDecl {
+    name,
+    payload,
+    as_raw,
+    span,
+});
+record!(lir::CapDecl {
+    name,
+    operations,
+    span,
+});
+record!(lir::OperationDecl {
+    name,
+    generics,
+    params,
+    return_type,
+    span,
+});
+record!(lir::FnDecl {
+    name,
+    generics,
+    params,
+    return_type,
+    cap,
+    value,
+    inline,
+    span,
+});
+record!(lir::UseDecl { path, names, span });
+record!(lir::ImplDecl {
+    name,
+    generics,
+    target_type,
+    capability,
+    methods,
+    span,
+});
+record!(lir::ImplMethodDecl {
+    name,
+    params,
+    return_type,
+    value,
+    span,
+});
+record!(lir::Param { name, ty, span });
+record!(lir::MatchArm {
+    pattern,
+    body,
+    span,
+});
+record!(lir::BundleEntry {
+    name,
+    params,
+    body,
+    span,
+});
+
+impl Encode for lir::Item {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            lir::Item::ExternType(decl) => {
+                w.tag(0);
+                decl.encode(w);
+            }
+            lir::Item::ExternFn(decl) => {
+                w.tag(1);
+                decl.encode(w);
+            }
+            lir::Item::Data(decl) => {
+                w.tag(2);
+                decl.encode(w);
+            }
+            lir::Item::Cap(decl) => {
+                w.tag(3);
+                decl.encode(w);
+            }
+            lir::Item::Fn(decl) => {
+                w.tag(4);
+                decl.encode(w);
+            }
+            lir::Item::Use(decl) => {
+                w.tag(5);
+                decl.encode(w);
+            }
+            lir::Item::Impl(decl) => {
+                w.tag(6);
+                decl.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for lir::Item {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => lir::Item::ExternType(Decode::decode(r)?),
+            1 => lir::Item::ExternFn(Decode::decode(r)?),
+            2 => lir::Item::Data(Decode::decode(r)?),
+            3 => lir::Item::Cap(Decode::decode(r)?),
+            4 => lir::Item::Fn(Decode::decode(r)?),
+            5 => lir::Item::Use(Decode::decode(r)?),
+            6 => lir::Item::Impl(Decode::decode(r)?),
+            _ => return None,
+        })
+    }
+}
+
+impl Encode for lir::AsRawValue {
+    fn encode(&self, w: &mut Writer) {
+        w.tag(match self {
+            lir::AsRawValue::True => 0,
+            lir::AsRawValue::False => 1,
+        });
+    }
+}
+
+impl Decode for lir::AsRawValue {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        match r.tag()? {
+            0 => Some(lir::AsRawValue::True),
+            1 => Some(lir::AsRawValue::False),
+            _ => None,
+        }
+    }
+}
+
+impl Encode for lir::Expr {
+    fn encode(&self, w: &mut Writer) {
+        match self {
+            lir::Expr::Ident { id, name } => {
+                w.tag(0);
+                id.encode(w);
+                name.encode(w);
+            }
+            lir::Expr::String { id, value } => {
+                w.tag(1);
+                id.encode(w);
+                value.encode(w);
+            }
+            lir::Expr::Number { id, value } => {
+                w.tag(2);
+                id.encode(w);
+                value.encode(w);
+            }
+            lir::Expr::Ctor {
+                id,
+                name,
+                called,
+                args,
+            } => {
+                w.tag(3);
+                id.encode(w);
+                name.encode(w);
+                called.encode(w);
+                args.encode(w);
+            }
+            lir::Expr::Thunk { id, expr } => {
+                w.tag(4);
+                id.encode(w);
+                expr.encode(w);
+            }
+            lir::Expr::Roll { id, expr } => {
+                w.tag(5);
+                id.encode(w);
+                expr.encode(w);
+            }
+            lir::Expr::Bundle { id, entries } => {
+                w.tag(6);
+                id.encode(w);
+                entries.encode(w);
+            }
+            lir::Expr::Produce { id, expr } => {
+                w.tag(7);
+                id.encode(w);
+                expr.encode(w);
+            }
+            lir::Expr::Force { id, expr } => {
+                w.tag(8);
+                id.encode(w);
+                expr.encode(w);
+            }
+            lir::Expr::Lambda { id, param, body } => {
+                w.tag(9);
+                id.encode(w);
+                param.encode(w);
+                body.encode(w);
+            }
+            lir::Expr::Apply { id, callee, arg } => {
+                w.tag(10);
+                id.encode(w);
+                callee.encode(w);
+                arg.encode(w);
+            }
+            lir::Expr::Let {
+                id,
+                name,
+                value,
+                body,
+            } => {
+                w.tag(11);
+                id.encode(w);
+                name.encode(w);
+                value.encode(w);
+                body.encode(w);
+            }
+            lir::Expr::Match {
+                id,
+                scrutinee,
+                arms,
+            } => {
+                w.tag(12);
+                id.encode(w);
+                scrutinee.encode(w);
+                arms.encode(w);
+            }
+            lir::Expr::Unroll { id, expr } => {
+                w.tag(13);
+                id.encode(w);
+                expr.encode(w);
+            }
+            lir::Expr::Perform { id, cap, type_args } => {
+                w.tag(14);
+                id.encode(w);
+                cap.encode(w);
+                type_args.encode(w);
+            }
+            lir::Expr::Handle {
+                id,
+                cap,
+                type_args,
+                handler,
+                body,
+            } => {
+                w.tag(15);
+                id.encode(w);
+                cap.encode(w);
+                type_args.encode(w);
+                handler.encode(w);
+                body.encode(w);
+            }
+            lir::Expr::Member { id, object, field } => {
+                w.tag(16);
+                id.encode(w);
+                object.encode(w);
+                field.encode(w);
+            }
+            lir::Expr::Ann { id, expr, ty } => {
+                w.tag(17);
+                id.encode(w);
+                expr.encode(w);
+                ty.encode(w);
+            }
+            lir::Expr::Error { id } => {
+                w.tag(18);
+                id.encode(w);
+            }
+            lir::Expr::Loop {
+                id,
+                var,
+                init,
+                body,
+            } => {
+                w.tag(19);
+                id.encode(w);
+                var.encode(w);
+                init.encode(w);
+                body.encode(w);
+            }
+        }
+    }
+}
+
+impl Decode for lir::Expr {
+    fn decode(r: &mut Reader) -> Option<Self> {
+        Some(match r.tag()? {
+            0 => lir::Expr::Ident {
+                id: Decode::decode(r)?,
+                name: Decode::decode(r)?,
+            },
+            1 => lir::Expr::String {
+                id: Decode::decode(r)?,
+                value: Decode::decode(r)?,
+            },
+            2 => lir::Expr::Number {
+                id: Decode::decode(r)?,
+                value: Decode::decode(r)?,
+            },
+            3 => lir::Expr::Ctor {
+                id: Decode::decode(r)?,
+                name: Decode::decode(r)?,
+                called: Decode::decode(r)?,
+                args: Decode::decode(r)?,
+            },
+            4 => lir::Expr::Thunk {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+            },
+            5 => lir::Expr::Roll {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+            },
+            6 => lir::Expr::Bundle {
+                id: Decode::decode(r)?,
+                entries: Decode::decode(r)?,
+            },
+            7 => lir::Expr::Produce {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+            },
+            8 => lir::Expr::Force {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+            },
+            9 => lir::Expr::Lambda {
+                id: Decode::decode(r)?,
+                param: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+            },
+            10 => lir::Expr::Apply {
+                id: Decode::decode(r)?,
+                callee: Decode::decode(r)?,
+                arg: Decode::decode(r)?,
+            },
+            11 => lir::Expr::Let {
+                id: Decode::decode(r)?,
+                name: Decode::decode(r)?,
+                value: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+            },
+            12 => lir::Expr::Match {
+                id: Decode::decode(r)?,
+                scrutinee: Decode::decode(r)?,
+                arms: Decode::decode(r)?,
+            },
+            13 => lir::Expr::Unroll {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+            },
+            14 => lir::Expr::Perform {
+                id: Decode::decode(r)?,
+                cap: Decode::decode(r)?,
+                type_args: Decode::decode(r)?,
+            },
+            15 => lir::Expr::Handle {
+                id: Decode::decode(r)?,
+                cap: Decode::decode(r)?,
+                type_args: Decode::decode(r)?,
+                handler: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+            },
+            16 => lir::Expr::Member {
+                id: Decode::decode(r)?,
+                object: Decode::decode(r)?,
+                field: Decode::decode(r)?,
+            },
+            17 => lir::Expr::Ann {
+                id: Decode::decode(r)?,
+                expr: Decode::decode(r)?,
+                ty: Decode::decode(r)?,
+            },
+            18 => lir::Expr::Error {
+                id: Decode::decode(r)?,
+            },
+            19 => lir::Expr::Loop {
+                id: Decode::decode(r)?,
+                var: Decode::decode(r)?,
+                init: Decode::decode(r)?,
+                body: Decode::decode(r)?,
+            },
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! Picking one default `impl` per cap key when several packages give one.
+//!
+//! The `handle` around `main` installs exactly one bundle per key: `IO` for
+//! `impl IO { ... }`, `Number: Add` for `impl Number: Add { ... }`. The
+//! root package's own impl overrides any dep's; between deps, the
+//! `[impls]` choice in the manifest decides, and without one it is an
+//! error naming every package involved. A choice that names no cap or no
+//! package of the build, or that settles no conflict, is an error too.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::hir;
+
+/// A key more than one dep gives a default impl for, with no usable
+/// `[impls]` choice between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImplConflict {
+    /// `IO`, or `Number: Add` for a typeclass impl.
+    pub key: String,
+    /// The packages with an impl for `key`, sorted.
+    pub packages: Vec<String>,
+    /// What `[impls]` picked, if it names none of `packages`.
+    pub chosen: Option<String>,
+}
+
+impl fmt::Display for ImplConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.packages.iter().map(|p| format!("`{p}`")).collect();
+        let (last, rest) = names.split_last().expect("a conflict has packages");
+        write!(f, "conflicting default `impl {}` in packages {} and {last}", self.key, rest.join(", "))?;
+        match &self.chosen {
+            Some(chosen) => write!(f, "; `[impls]` picks `{chosen}`, which has none"),
+            None => write!(f, "; pick one with `{} = \"<package>\"` under `[impls]`", quote_key(&self.key)),
+        }
+    }
+}
+
+/// An `[impls]` entry that cannot settle anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BadImplChoice {
+    /// The key names no cap, or a typeclass impl of no cap.
+    UnknownCap { key: String },
+    /// The chosen package is not one the build uses.
+    UnknownPackage { key: String, package: String },
+    /// Fewer than two deps give an impl for the key, or the root package
+    /// gives its own, so there is nothing to choose between.
+    Unused { key: String, package: String },
+}
+
+impl fmt::Display for BadImplChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BadImplChoice::UnknownCap { key } => {
+                write!(f, "`[impls]` names `{key}`, which is not a cap")
+            }
+            BadImplChoice::UnknownPackage { key, package } => write!(
+                f,
+                "`[impls]` picks `{package}` for `{key}`, but the build uses no package `{package}`"
+            ),
+            BadImplChoice::Unused { key, package } => write!(
+                f,
+                "unused `[impls]` choice `{} = \"{package}\"`: no two deps give a default `impl {key}` to pick between",
+                quote_key(key)
+            ),
+        }
+    }
+}
+
+/// `key` as a TOML key: bare if it can be, quoted otherwise.
+fn quote_key(key: &str) -> String {
+    if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_owned()
+    } else {
+        format!("\"{key}\"")
+    }
+}
+
+/// Drop every default impl that loses to another package's for the same
+/// key. `files` pairs each file with its package, `None` for the root's.
+/// Returns the keys no choice settles, whose impls are all kept, and the
+/// choices that are themselves wrong.
+pub(super) fn select_default_impls(
+    files: &mut [(Option<String>, hir::File)],
+    choices: &HashMap<String, String>,
+) -> (Vec<ImplConflict>, Vec<BadImplChoice>) {
+    let caps: HashSet<String> = files
+        .iter()
+        .flat_map(|(_, file)| &file.items)
+        .filter_map(|item| match item {
+            hir::Item::Cap(c) => Some(c.name.clone()),
+            _ => None,
+        })
+        .collect();
+
+    // Key → the packages with an impl for it; `None` is the root.
+    let mut providers: BTreeMap<String, BTreeSet<Option<String>>> = BTreeMap::new();
+    for (package, file) in files.iter() {
+        for item in &file.items {
+            if let Some(key) = default_impl_key(item, &caps) {
+                providers.entry(key).or_default().insert(package.clone());
+            }
+        }
+    }
+
+    // Choices that name no cap or no package are reported once, on their
+    // own, and then play no part in settling conflicts.
+    let packages: HashSet<&String> = files.iter().filter_map(|(package, _)| package.as_ref()).collect();
+    let mut sorted: Vec<(&String, &String)> = choices.iter().collect();
+    sorted.sort();
+    let mut bad = Vec::new();
+    let mut usable: HashMap<&String, &String> = HashMap::new();
+    for (key, package) in sorted {
+        let cap = key.rsplit_once(':').map_or(key.as_str(), |(_, cap)| cap);
+        if !caps.contains(cap.split('[').next().unwrap_or(cap).trim()) {
+            bad.push(BadImplChoice::UnknownCap { key: key.clone() });
+        } else if !packages.contains(package) {
+            bad.push(BadImplChoice::UnknownPackage {
+                key: key.clone(),
+                package: package.clone(),
+            });
+        } else if providers
+            .get(key)
+            .is_none_or(|p| p.len() < 2 || p.contains(&None))
+        {
+            bad.push(BadImplChoice::Unused {
+                key: key.clone(),
+                package: package.clone(),
+            });
+        } else {
+            usable.insert(key, package);
+        }
+    }
+
+    let mut winners: HashMap<String, Option<String>> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (key, packages) in providers {
+        if packages.len() < 2 {
+            continue;
+        }
+        if packages.contains(&None) {
+            winners.insert(key, None);
+            continue;
+        }
+        let chosen = usable.get(&key).copied();
+        match chosen {
+            Some(chosen) if packages.contains(&Some(chosen.clone())) => {
+                winners.insert(key, Some(chosen.clone()));
+            }
+            _ => conflicts.push(ImplConflict {
+                packages: packages.into_iter().flatten().collect(),
+                chosen: chosen.cloned(),
+                key,
+            }),
+        }
+    }
+
+    for (package, file) in files.iter_mut() {
+        file.items.retain(|item| {
+            default_impl_key(item, &caps)
+                .and_then(|key| winners.get(&key))
+                .is_none_or(|winner| winner == package)
+        });
+    }
+    (conflicts, bad)
+}
+
+/// The key `item` is the default impl for, if it is one. Mirrors what the
+/// backend installs around `main`.
+fn default_impl_key(item: &hir::Item, caps: &HashSet<String>) -> Option<String> {
+    let hir::Item::Impl(decl) = item else {
+        return None;
+    };
+    let target = decl.target_type.value.display();
+    match &decl.capability {
+        None => caps.contains(&target).then_some(target),
+        Some(cap) => {
+            let cap = cap.value.display();
+            caps.contains(&cap).then(|| format!("{target}: {cap}"))
+        }
+    }
+}
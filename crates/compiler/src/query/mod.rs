@@ -1,6 +1,16 @@
+mod cache;
+mod codec;
+mod impls;
+
 use std::collections::{HashMap, HashSet, VecDeque};
 
+pub use cache::{CacheStats, DiskCache};
+pub use impls::{BadImplChoice, ImplConflict};
+
+use cache::CacheKind;
+
 use crate::{
+    backend::BackendKind,
     diagnostics::Diagnostic,
     hir,
     lexer::Span,
@@ -22,6 +32,10 @@ pub struct QueryStats {
     pub parse_computes: usize,
     pub lower_computes: usize,
     pub diagnostics_computes: usize,
+    /// Disk cache traffic; all zero unless a [`DiskCache`] is set.
+    pub hir_cache: CacheStats,
+    pub module_cache: CacheStats,
+    pub typecheck_cache: CacheStats,
 }
 
 impl QueryStats {
@@ -30,6 +44,9 @@ impl QueryStats {
             parse_computes: 0,
             lower_computes: 0,
             diagnostics_computes: 0,
+            hir_cache: CacheStats::default(),
+            module_cache: CacheStats::default(),
+            typecheck_cache: CacheStats::default(),
         }
     }
 }
@@ -95,6 +112,18 @@ impl FileEntry {
 pub struct QueryEngine {
     files: HashMap<String, FileEntry>,
     stats: QueryStats,
+    lto_report: Option<crate::lto::report::LtoReport>,
+    disk_cache: Option<DiskCache>,
+    cfg: Option<hir::Cfg>,
+    package_cfgs: HashMap<String, hir::Cfg>,
+    lto: bool,
+    backend: BackendKind,
+    /// The package each file `compile_with_deps` resolved belongs to.
+    /// Files missing here are the root package's.
+    file_packages: HashMap<String, String>,
+    impl_choices: HashMap<String, String>,
+    impl_conflicts: Vec<ImplConflict>,
+    bad_impl_choices: Vec<BadImplChoice>,
 }
 
 impl QueryEngine {
@@ -102,9 +131,54 @@ impl QueryEngine {
         Self {
             files: HashMap::new(),
             stats: QueryStats::new(),
+            lto_report: None,
+            disk_cache: None,
+            cfg: None,
+            package_cfgs: HashMap::new(),
+            lto: true,
+            backend: BackendKind::TypeScript,
+            file_packages: HashMap::new(),
+            impl_choices: HashMap::new(),
+            impl_conflicts: Vec::new(),
+            bad_impl_choices: Vec::new(),
         }
     }
 
+    /// Back `lower_hir`, `lower_module` and `typecheck` with a cache that
+    /// outlives this engine, so another process can reuse their results.
+    pub fn set_disk_cache(&mut self, cache: Option<DiskCache>) {
+        self.disk_cache = cache;
+    }
+
+    /// Evaluate `#[cfg]` attributes when lowering: files named
+    /// `<package>/...` against `packages[package]`, every other file (the
+    /// root package's own) against `cfg`. With no `cfg`, those files keep
+    /// every item.
+    pub fn set_cfg(&mut self, cfg: Option<hir::Cfg>, packages: HashMap<String, hir::Cfg>) {
+        self.cfg = cfg;
+        self.package_cfgs = packages;
+    }
+
+    /// Run LTO in `lower_module` (the default). Without it, cap-resolved
+    /// fns stay generic and nothing is inlined or swept, so the output keeps
+    /// one function per source fn.
+    pub fn set_lto(&mut self, lto: bool) {
+        self.lto = lto;
+    }
+
+    /// Lower for `backend` (TypeScript by default). The Rust backend has
+    /// no runtime cap dispatch and no generic instantiation, so for it
+    /// `lower_module` runs LTO whatever `set_lto` says, then monomorphizes.
+    pub fn set_backend(&mut self, backend: BackendKind) {
+        self.backend = backend;
+    }
+
+    /// Which package's default impl `lower_module` keeps for a key (`IO`,
+    /// `Number: Add`) that more than one dep gives an impl for.
+    pub fn set_impl_choices(&mut self, choices: HashMap<String, String>) {
+        self.impl_choices = choices;
+    }
+
     pub fn set_file(&mut self, file: impl Into<String>, source: impl Into<String>) {
         let file = file.into();
         let source = source.into();
@@ -145,24 +219,38 @@ impl QueryEngine {
     }
 
     pub fn lower_hir(&mut self, file: &str) -> Option<hir::File> {
-        let source_hash = self.files.get(file)?.source_hash;
-        if self.files.get(file)?.lowered_hir_at_hash == Some(source_hash) {
+        let key = self.hir_key(file)?;
+        if self.files.get(file)?.lowered_hir_at_hash == Some(key) {
             return self.files.get(file)?.lowered_hir.clone();
         }
 
-        let parsed = self.parse(file)?;
-        let lowered = hir::lower(&parsed.file);
+        let cached = self.disk_cache.as_ref().and_then(|cache| {
+            let hit = cache.load::<hir::File>(CacheKind::Hir, key);
+            count(&mut self.stats.hir_cache, hit.is_some());
+            hit
+        });
+        let lowered = match cached {
+            Some(lowered) => lowered,
+            None => {
+                let parsed = self.parse(file)?;
+                let lowered = hir::lower_with_cfg(&parsed.file, self.cfg_for(file));
+                if let Some(cache) = &self.disk_cache {
+                    cache.store(CacheKind::Hir, key, &lowered);
+                }
+                lowered
+            }
+        };
 
         let entry = self.files.get_mut(file)?;
-        entry.lowered_hir_at_hash = Some(entry.source_hash);
+        entry.lowered_hir_at_hash = Some(key);
         entry.lowered_hir = Some(lowered.clone());
 
         Some(lowered)
     }
 
     pub fn lower(&mut self, file: &str) -> Option<lir::File> {
-        let source_hash = self.files.get(file)?.source_hash;
-        if self.files.get(file)?.lowered_at_hash == Some(source_hash) {
+        let key = self.hir_key(file)?;
+        if self.files.get(file)?.lowered_at_hash == Some(key) {
             return self.files.get(file)?.lowered.clone();
         }
 
@@ -170,7 +258,7 @@ impl QueryEngine {
         let lowered = lir::lower(&lowered_hir);
 
         let entry = self.files.get_mut(file)?;
-        entry.lowered_at_hash = Some(entry.source_hash);
+        entry.lowered_at_hash = Some(key);
         entry.lowered = Some(lowered.clone());
         self.stats.lower_computes += 1;
 
@@ -182,26 +270,95 @@ impl QueryEngine {
     /// then lowers the merged HIR to a single LIR with inferred caps applied.
     ///
     /// The pipeline:
-    /// 1. Merge HIR → lower to LIR
+    /// 0. Keep one default impl per key across packages; fail with
+    ///    `impl_conflicts` set where no choice settles one
+    /// 1. Merge HIR → lower to LIR, rewrite method calls and specialize
+    ///    bounded generics per instantiation
     /// 2. Typecheck to get per-Perform type_args resolutions
     /// 3. Patch Perform nodes with resolved type_args (e.g. Add[] → Add[Number])
     /// 4. Resolve default cap impls (Perform → Ident for caps with matching impls)
     /// 5. Re-run cap inference on patched LIR
+    /// 6. LTO (unless turned off with `set_lto`), then demote fns that no
+    ///    longer perform to direct style
+    /// 7. For the Rust backend, specialize generic fns and data types
+    ///
+    /// With a disk cache, a group whose sources, `#[cfg]` configurations,
+    /// impl choices and LTO setting all match an earlier run skips the pipeline;
+    /// `lto_report` is then `None`, as it is without LTO.
     pub fn lower_module(&mut self, files: &[&str]) -> Option<lir::File> {
+        let mut key = Vec::new();
+        for file in files {
+            key.extend_from_slice(file.as_bytes());
+            key.push(0);
+            key.extend_from_slice(&self.hir_key(file)?.to_le_bytes());
+        }
+        let lto = self.lto || self.backend == BackendKind::Rust;
+        if !lto {
+            key.extend_from_slice(b"no-lto");
+        }
+        if self.backend == BackendKind::Rust {
+            key.extend_from_slice(b"rust");
+        }
+        let mut choices: Vec<_> = self.impl_choices.iter().collect();
+        choices.sort();
+        for (cap, package) in choices {
+            key.extend_from_slice(format!("impl {cap}={package}").as_bytes());
+            key.push(0);
+        }
+        let key = hash_bytes(&key);
+        if let Some(cache) = &self.disk_cache {
+            let hit = cache.load::<lir::File>(CacheKind::Module, key);
+            count(&mut self.stats.module_cache, hit.is_some());
+            if let Some(lowered) = hit {
+                self.lto_report = None;
+                self.impl_conflicts.clear();
+                self.bad_impl_choices.clear();
+                return Some(lowered);
+            }
+        }
+
         let mut hir_files = Vec::new();
         for file in files {
-            hir_files.push(self.lower_hir(file)?);
+            hir_files.push((self.file_packages.get(*file).cloned(), self.lower_hir(file)?));
         }
+        (self.impl_conflicts, self.bad_impl_choices) =
+            impls::select_default_impls(&mut hir_files, &self.impl_choices);
+        if !self.impl_conflicts.is_empty() || !self.bad_impl_choices.is_empty() {
+            self.lto_report = None;
+            return None;
+        }
+        let hir_files: Vec<hir::File> = hir_files.into_iter().map(|(_, file)| file).collect();
         let merged = hir::merge_files(&hir_files);
         let mut lowered = lir::lower(&merged);
 
         // Phase 0: Rewrite value method calls (e.g. "asdf".len() → String.len("asdf"))
         rewrite_value_method_calls(&mut lowered);
+        // Phase 0.5: Specialize bounded generics (`fn f[K: Hash]`), whose
+        // caps are looked up by type
+        crate::lto::mono::specialize_bounded(&mut lowered);
+        // Phase 0.6: Inline handler runners (`run_state(init, thunk e)`) so
+        // the thunk's caps are handled where it is built
+        crate::lto::handlers::inline_runners(&mut lowered);
+
+        // Phase 0.75: Performs take the type args their fn's cap row or an
+        // enclosing `handle ... for T` gives their cap
+        patch_scoped_perform_type_args(&mut lowered);
 
         // Phase 1: Typecheck to get perform_for_types
-        let (_, perform_for_types) = typecheck::infer_caps_for_file(&lowered);
+        let (fn_caps, perform_for_types) = typecheck::infer_caps_for_file(&lowered);
         // Phase 2: Patch Perform nodes with resolved type_args
         patch_perform_type_args(&mut lowered, &perform_for_types);
+        // Phase 2.25: A bare `handle` of a Self-using cap takes its type from
+        // its body, or else from what its handler resumes with; retypecheck
+        // so its handler's performs, and the body's still unpinned ones,
+        // resolve against it. Those performs can pin an outer `handle` in
+        // turn, so repeat until none is left to pin.
+        let mut perform_for_types = perform_for_types;
+        while patch_handle_type_args(&mut lowered, &fn_caps, &perform_for_types) {
+            patch_scoped_perform_type_args(&mut lowered);
+            perform_for_types = typecheck::infer_caps_for_file(&lowered).1;
+            patch_perform_type_args(&mut lowered, &perform_for_types);
+        }
         // Phase 2.5: Fill default type_args for Perform/Handle with empty type_args
         fill_default_type_args(&mut lowered);
         // Phase 3: Re-run cap inference on patched LIR
@@ -209,15 +366,28 @@ impl QueryEngine {
         typecheck::apply_inferred_caps(&mut lowered, &inferred);
 
         // Phase 4: LTO — monomorphize cap-resolved fns
-        let lto_errors = crate::lto::optimize(&mut lowered);
-        if !lto_errors.is_empty() {
-            // Hard errors (e.g. #[inline(always)] on unresolvable fn) — abort.
-            return None;
+        self.lto_report = None;
+        if lto {
+            let (lto_errors, lto_report) = crate::lto::optimize_with_report(&mut lowered);
+            self.lto_report = Some(lto_report);
+            if !lto_errors.is_empty() {
+                // Hard errors (e.g. #[inline(always)] on unresolvable fn) — abort.
+                return None;
+            }
+            // Phase 4': Re-infer caps from scratch (clones changed cap
+            // requirements). Selective CPS: fns that no longer perform drop
+            // their stale cap rows and get compiled in direct style.
+            crate::lto::selective_cps::demote_direct_style(&mut lowered);
+        }
+        // Phase 5: Rust output has a concrete enum per `List[Number]` and a
+        // concrete fn per instantiation
+        if self.backend == BackendKind::Rust {
+            crate::lto::mono::monomorphize(&mut lowered);
         }
-        // Phase 4': Re-typecheck (clones changed cap requirements)
-        let (inferred, _) = typecheck::infer_caps_for_file(&lowered);
-        typecheck::apply_inferred_caps(&mut lowered, &inferred);
 
+        if let Some(cache) = &self.disk_cache {
+            cache.store(CacheKind::Module, key, &lowered);
+        }
         Some(lowered)
     }
 
@@ -242,9 +412,10 @@ impl QueryEngine {
         let mut pending: VecDeque<String> = ordered_files.iter().cloned().collect();
 
         while let Some(file) = pending.pop_front() {
-            let parsed = self.parse(&file)?;
-            for use_path in collect_use_paths(&parsed.file) {
+            let lowered = self.lower_hir(&file)?;
+            for use_path in collect_use_paths(&lowered) {
                 if let Some((filename, source)) = resolve(&use_path) {
+                    self.file_packages.insert(filename.clone(), use_path[0].clone());
                     if seen.insert(filename.clone()) {
                         self.set_file(&filename, source.clone());
                         ordered_files.push(filename.clone());
@@ -265,8 +436,8 @@ impl QueryEngine {
     }
 
     pub fn diagnostics(&mut self, file: &str) -> Option<Vec<Diagnostic>> {
-        let source_hash = self.files.get(file)?.source_hash;
-        if self.files.get(file)?.diagnostics_at_hash == Some(source_hash) {
+        let key = self.hir_key(file)?;
+        if self.files.get(file)?.diagnostics_at_hash == Some(key) {
             return self.files.get(file)?.diagnostics.clone();
         }
 
@@ -330,16 +501,68 @@ impl QueryEngine {
         }));
 
         let entry = self.files.get_mut(file)?;
-        entry.diagnostics_at_hash = Some(entry.source_hash);
+        entry.diagnostics_at_hash = Some(key);
         entry.diagnostics = Some(diags.clone());
         self.stats.diagnostics_computes += 1;
 
         Some(diags)
     }
 
+    /// Type errors in a lowered module, cached on disk by the module's
+    /// encoded LIR when a disk cache is set.
+    pub fn typecheck(&mut self, file: &lir::File) -> Vec<typecheck::TypeError> {
+        let Some(cache) = &self.disk_cache else {
+            return typecheck::typecheck_file(file);
+        };
+        let key = hash_bytes(&codec::encode(file));
+        let hit = cache.load::<Vec<typecheck::TypeError>>(CacheKind::Typecheck, key);
+        count(&mut self.stats.typecheck_cache, hit.is_some());
+        hit.unwrap_or_else(|| {
+            let errors = typecheck::typecheck_file(file);
+            cache.store(CacheKind::Typecheck, key, &errors);
+            errors
+        })
+    }
+
     pub fn stats(&self) -> QueryStats {
         self.stats.clone()
     }
+
+    /// The default impls the last `lower_module` run could not choose
+    /// between; it returned `None` if there were any.
+    pub fn impl_conflicts(&self) -> &[ImplConflict] {
+        &self.impl_conflicts
+    }
+
+    /// The `[impls]` choices the last `lower_module` run rejected; it
+    /// returned `None` if there were any.
+    pub fn bad_impl_choices(&self) -> &[BadImplChoice] {
+        &self.bad_impl_choices
+    }
+
+    /// What LTO decided during the last `lower_module` run.
+    pub fn lto_report(&self) -> Option<&crate::lto::report::LtoReport> {
+        self.lto_report.as_ref()
+    }
+
+    fn cfg_for(&self, file: &str) -> Option<&hir::Cfg> {
+        let package = file.split('/').next().unwrap_or(file);
+        self.package_cfgs.get(package).or(self.cfg.as_ref())
+    }
+
+    /// What a file's lowered forms depend on: its source and, once `#[cfg]`
+    /// is evaluated, the configuration it is evaluated against.
+    fn hir_key(&self, file: &str) -> Option<u64> {
+        let source_hash = self.files.get(file)?.source_hash;
+        Some(match self.cfg_for(file) {
+            None => source_hash,
+            Some(cfg) => {
+                let mut bytes = source_hash.to_le_bytes().to_vec();
+                bytes.extend(codec::encode(cfg));
+                hash_bytes(&bytes)
+            }
+        })
+    }
 }
 
 fn build_lir_span_map(file: &lir::File) -> HashMap<u64, Span> {
@@ -352,19 +575,31 @@ fn build_lir_span_map(file: &lir::File) -> HashMap<u64, Span> {
 }
 
 fn hash_text(text: &str) -> u64 {
+    hash_bytes(text.as_bytes())
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
     let mut state = 0xcbf29ce484222325_u64;
-    for b in text.as_bytes() {
+    for b in bytes {
         state ^= *b as u64;
         state = state.wrapping_mul(0x100000001b3);
     }
     state
 }
 
-fn collect_use_paths(file: &crate::lst::File) -> Vec<Vec<String>> {
+fn count(stats: &mut CacheStats, hit: bool) {
+    if hit {
+        stats.hits += 1;
+    } else {
+        stats.misses += 1;
+    }
+}
+
+fn collect_use_paths(file: &hir::File) -> Vec<Vec<String>> {
     file.items
         .iter()
         .filter_map(|item| {
-            if let lst::Item::Use(u) = item {
+            if let hir::Item::Use(u) = item {
                 Some(u.path.clone())
             } else {
                 None
@@ -406,6 +641,7 @@ struct RewriteCtx {
 fn simple_type_name(ty: &TypeExpr) -> Option<String> {
     match ty {
         TypeExpr::Named(n) => Some(n.clone()),
+        TypeExpr::App { .. } => Some(ty.display()),
         _ => None,
     }
 }
@@ -552,12 +788,17 @@ fn determine_expr_type(
 ) -> Option<String> {
     match expr {
         lir::Expr::String { .. } => Some("String".to_string()),
-        lir::Expr::Number { .. } => Some("Number".to_string()),
+        lir::Expr::Number { value, .. } => {
+            Some(lumo_lexer::NumberKind::of(value).type_name().to_string())
+        }
         lir::Expr::Ident { name, .. } => scope.get(name).cloned(),
         lir::Expr::Produce { expr, .. } | lir::Expr::Ann { expr, .. } => {
             determine_expr_type(expr, scope, ctx)
         }
-        lir::Expr::Apply { .. } => determine_apply_return_type(expr, ctx),
+        // A zero-arg call (`ArrayOps.new()`) has no Apply layer
+        lir::Expr::Apply { .. } | lir::Expr::Member { .. } | lir::Expr::Force { .. } => {
+            determine_apply_return_type(expr, ctx)
+        }
         _ => None,
     }
 }
@@ -583,15 +824,13 @@ fn determine_apply_return_type(expr: &lir::Expr, ctx: &RewriteCtx) -> Option<Str
 
     match current {
         lir::Expr::Ident { name, .. } => ctx.fn_return_types.get(name).cloned(),
-        lir::Expr::Member { object, field, .. } => {
-            if let lir::Expr::Ident { name, .. } = object.as_ref() {
-                ctx.method_return_types
-                    .get(&(name.clone(), field.clone()))
-                    .cloned()
-            } else {
-                None
-            }
-        }
+        lir::Expr::Member { object, field, .. } => match object.as_ref() {
+            lir::Expr::Ident { name, .. } | lir::Expr::Perform { cap: name, .. } => ctx
+                .method_return_types
+                .get(&(name.clone(), field.clone()))
+                .cloned(),
+            _ => None,
+        },
         _ => None,
     }
 }
@@ -683,6 +922,14 @@ fn rewrite_method_calls_in_expr(
             }
             rewrite_method_calls_in_expr(body, ctx, &inner_scope, spans);
         }
+        lir::Expr::Loop { var, init, body, .. } => {
+            rewrite_method_calls_in_expr(init, ctx, scope, spans);
+            let mut inner_scope = scope.clone();
+            if let Some(ty) = determine_expr_type(init, scope, ctx) {
+                inner_scope.insert(var.clone(), ty);
+            }
+            rewrite_method_calls_in_expr(body, ctx, &inner_scope, spans);
+        }
         lir::Expr::Match { scrutinee, arms, .. } => {
             rewrite_method_calls_in_expr(scrutinee, ctx, scope, spans);
             for arm in arms {
@@ -763,7 +1010,7 @@ fn patch_expr_type_args(expr: &mut lir::Expr, perform_for_types: &HashMap<u64, V
             patch_expr_type_args(callee, perform_for_types);
             patch_expr_type_args(arg, perform_for_types);
         }
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             patch_expr_type_args(value, perform_for_types);
             patch_expr_type_args(body, perform_for_types);
         }
@@ -798,6 +1045,215 @@ fn patch_expr_type_args(expr: &mut lir::Expr, perform_for_types: &HashMap<u64, V
     }
 }
 
+/// Give a perform the type args its context names for its cap: the
+/// enclosing fn's cap row (`/ { State[Number] }`) or an enclosing
+/// `handle State for Number`. Ops like `State.get()` have no argument to
+/// infer `Self` from.
+fn patch_scoped_perform_type_args(file: &mut lir::File) {
+    for item in &mut file.items {
+        match item {
+            lir::Item::Fn(f) => {
+                let mut scope = HashMap::new();
+                for entry in f.cap.iter().flatten() {
+                    if let CapEntry::Cap(TypeExpr::Cap { name, type_args }) = entry {
+                        if !type_args.is_empty() {
+                            scope.insert(name.clone(), type_args.iter().map(|t| t.display()).collect());
+                        }
+                    }
+                }
+                patch_scoped_expr_type_args(&mut f.value, &scope);
+            }
+            lir::Item::Impl(impl_decl) => {
+                for m in &mut impl_decl.methods {
+                    patch_scoped_expr_type_args(&mut m.value, &HashMap::new());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn patch_scoped_expr_type_args(expr: &mut lir::Expr, scope: &HashMap<String, Vec<String>>) {
+    match expr {
+        lir::Expr::Perform { cap, type_args, .. } => {
+            if type_args.is_empty() {
+                if let Some(found) = scope.get(cap.as_str()) {
+                    *type_args = found.clone();
+                }
+            }
+        }
+        lir::Expr::Handle { cap, type_args, handler, body, .. } => {
+            patch_scoped_expr_type_args(handler, scope);
+            if type_args.is_empty() || type_args == std::slice::from_ref(cap) {
+                patch_scoped_expr_type_args(body, scope);
+            } else {
+                let mut inner = scope.clone();
+                inner.insert(cap.clone(), type_args.clone());
+                patch_scoped_expr_type_args(body, &inner);
+            }
+        }
+        lir::Expr::Apply { callee, arg, .. } => {
+            patch_scoped_expr_type_args(callee, scope);
+            patch_scoped_expr_type_args(arg, scope);
+        }
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            patch_scoped_expr_type_args(value, scope);
+            patch_scoped_expr_type_args(body, scope);
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            patch_scoped_expr_type_args(scrutinee, scope);
+            for arm in arms {
+                patch_scoped_expr_type_args(&mut arm.body, scope);
+            }
+        }
+        lir::Expr::Lambda { body, .. } => patch_scoped_expr_type_args(body, scope),
+        lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Force { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Ann { expr, .. }
+        | lir::Expr::Member { object: expr, .. } => patch_scoped_expr_type_args(expr, scope),
+        lir::Expr::Bundle { entries, .. } => {
+            for e in entries {
+                patch_scoped_expr_type_args(&mut e.body, scope);
+            }
+        }
+        lir::Expr::Ctor { args, .. } => {
+            for a in args {
+                patch_scoped_expr_type_args(a, scope);
+            }
+        }
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Error { .. } => {}
+    }
+}
+
+/// Give a `handle` of a Self-using cap without `for T` (as `try`/`catch`
+/// writes it) the type its body performs the cap at: `Throw[String]` from a
+/// direct `Throw.throw("..")` or from a called fn's inferred caps. Returns
+/// whether any handle was patched.
+fn patch_handle_type_args(
+    file: &mut lir::File,
+    fn_caps: &HashMap<String, Vec<CapEntry>>,
+    resumed: &HashMap<u64, Vec<String>>,
+) -> bool {
+    let mut patched = false;
+    for item in &mut file.items {
+        match item {
+            lir::Item::Fn(f) => patched |= patch_handle_expr_type_args(&mut f.value, fn_caps, resumed),
+            lir::Item::Impl(impl_decl) => {
+                for m in &mut impl_decl.methods {
+                    patched |= patch_handle_expr_type_args(&mut m.value, fn_caps, resumed);
+                }
+            }
+            _ => {}
+        }
+    }
+    patched
+}
+
+fn patch_handle_expr_type_args(
+    expr: &mut lir::Expr,
+    fn_caps: &HashMap<String, Vec<CapEntry>>,
+    resumed: &HashMap<u64, Vec<String>>,
+) -> bool {
+    let mut patched = false;
+    if let lir::Expr::Handle { id, cap, type_args, body, .. } = expr {
+        if type_args.is_empty() {
+            let found = resumed
+                .get(&(id.0 as u64))
+                .cloned()
+                .or_else(|| performed_type_args(body, cap, fn_caps));
+            if let Some(found) = found {
+                *type_args = found;
+                patched = true;
+            }
+        }
+    }
+    let mut children: Vec<&mut lir::Expr> = Vec::new();
+    match expr {
+        lir::Expr::Handle { handler, body, .. } => children.extend([handler.as_mut(), body.as_mut()]),
+        lir::Expr::Apply { callee, arg, .. } => children.extend([callee.as_mut(), arg.as_mut()]),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            children.extend([value.as_mut(), body.as_mut()])
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            children.push(scrutinee.as_mut());
+            children.extend(arms.iter_mut().map(|arm| &mut arm.body));
+        }
+        lir::Expr::Lambda { body, .. } => children.push(body.as_mut()),
+        lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Force { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Ann { expr, .. }
+        | lir::Expr::Member { object: expr, .. } => children.push(expr.as_mut()),
+        lir::Expr::Bundle { entries, .. } => children.extend(entries.iter_mut().map(|e| &mut e.body)),
+        lir::Expr::Ctor { args, .. } => children.extend(args.iter_mut()),
+        lir::Expr::Ident { .. }
+        | lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Perform { .. }
+        | lir::Expr::Error { .. } => {}
+    }
+    for child in children {
+        patched |= patch_handle_expr_type_args(child, fn_caps, resumed);
+    }
+    patched
+}
+
+/// The type_args `expr` performs `cap` at, if any. A nested handle of the
+/// same cap hides its body.
+fn performed_type_args(
+    expr: &lir::Expr,
+    cap: &str,
+    fn_caps: &HashMap<String, Vec<CapEntry>>,
+) -> Option<Vec<String>> {
+    let go = |e: &lir::Expr| performed_type_args(e, cap, fn_caps);
+    // A perform still typed `Self` or `_` waits for a later round to pin it.
+    let concrete = |args: &[String]| {
+        (!args.is_empty() && args != [cap] && !args.iter().any(|a| a == "Self" || a == "_"))
+            .then(|| args.to_vec())
+    };
+    match expr {
+        lir::Expr::Perform { cap: c, type_args, .. } if c == cap => concrete(type_args),
+        lir::Expr::Ident { name, .. } => fn_caps.get(name)?.iter().find_map(|entry| match entry {
+            CapEntry::Cap(TypeExpr::Cap { name, type_args }) if name == cap => {
+                concrete(&type_args.iter().map(|t| t.display()).collect::<Vec<_>>())
+            }
+            _ => None,
+        }),
+        lir::Expr::Handle { cap: c, handler, body, .. } => {
+            go(handler).or_else(|| if c == cap { None } else { go(body) })
+        }
+        lir::Expr::Apply { callee, arg, .. } => go(callee).or_else(|| go(arg)),
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
+            go(value).or_else(|| go(body))
+        }
+        lir::Expr::Match { scrutinee, arms, .. } => {
+            go(scrutinee).or_else(|| arms.iter().find_map(|arm| go(&arm.body)))
+        }
+        lir::Expr::Lambda { body, .. } => go(body),
+        lir::Expr::Thunk { expr, .. }
+        | lir::Expr::Produce { expr, .. }
+        | lir::Expr::Force { expr, .. }
+        | lir::Expr::Unroll { expr, .. }
+        | lir::Expr::Roll { expr, .. }
+        | lir::Expr::Ann { expr, .. }
+        | lir::Expr::Member { object: expr, .. } => go(expr),
+        lir::Expr::Bundle { entries, .. } => entries.iter().find_map(|e| go(&e.body)),
+        lir::Expr::Ctor { args, .. } => args.iter().find_map(go),
+        lir::Expr::String { .. }
+        | lir::Expr::Number { .. }
+        | lir::Expr::Perform { .. }
+        | lir::Expr::Error { .. } => None,
+    }
+}
+
 /// Fill default type_args for Perform/Handle nodes and FnDecl/ExternFn cap annotations
 /// with empty type_args. Sets type_args = [cap_name] so all caps consistently have self
 /// as first type_arg.
@@ -854,7 +1310,7 @@ fn fill_expr_default_type_args(expr: &mut lir::Expr) {
             fill_expr_default_type_args(callee);
             fill_expr_default_type_args(arg);
         }
-        lir::Expr::Let { value, body, .. } => {
+        lir::Expr::Let { value, body, .. } | lir::Expr::Loop { init: value, body, .. } => {
             fill_expr_default_type_args(value);
             fill_expr_default_type_args(body);
         }
@@ -75,7 +75,7 @@ fn v_types_match(a: &ValueType, b: &ValueType) -> bool {
     match (a, b) {
         // `Self` and `_` (inferred placeholder) are wildcards that match any type
         (ValueType::Named(n), _) | (_, ValueType::Named(n)) if n == "Self" || n == "_" => true,
-        (ValueType::Named(a), ValueType::Named(b)) => a == b,
+        (ValueType::Named(a), ValueType::Named(b)) => a == b || nominal_args_match(a, b),
         (ValueType::Thunk(a), ValueType::Thunk(b)) => c_types_match(a, b),
         (
             ValueType::Func { params: pa, ret: ra },
@@ -89,6 +89,20 @@ fn v_types_match(a: &ValueType, b: &ValueType) -> bool {
     }
 }
 
+/// `List[Number]` matches `List[_]`: type arguments compare one by one, so
+/// wildcards nest.
+fn nominal_args_match(a: &str, b: &str) -> bool {
+    let (head_a, args_a) = split_nominal_type_args(a);
+    let (head_b, args_b) = split_nominal_type_args(b);
+    !args_a.is_empty()
+        && head_a == head_b
+        && args_a.len() == args_b.len()
+        && args_a.iter().zip(&args_b).all(|(x, y)| match (parse_v_type(x), parse_v_type(y)) {
+            (Some(x), Some(y)) => v_types_match(&x, &y),
+            _ => false,
+        })
+}
+
 /// Compare two computation types, treating `Self` as a wildcard.
 fn c_types_match(a: &CompType, b: &CompType) -> bool {
     match (a, b) {
@@ -217,8 +231,12 @@ pub fn typecheck_and_bindings(file: &lir::File) -> (Vec<CheckedBinding>, Vec<Typ
         value_type_methods: HashMap::new(),
         impl_registry: HashMap::new(),
         fn_generics: HashMap::new(),
+        member_generics: HashMap::new(),
         current_generic_bounds: HashMap::new(),
         current_generic_names: HashSet::new(),
+        runners: HashMap::new(),
+        thunk_rows: HashMap::new(),
+        performed: Vec::new(),
     };
     tc.check_file(file);
     (tc.bindings, tc.errors)
@@ -243,8 +261,12 @@ pub fn infer_caps_for_file(
         value_type_methods: HashMap::new(),
         impl_registry: HashMap::new(),
         fn_generics: HashMap::new(),
+        member_generics: HashMap::new(),
         current_generic_bounds: HashMap::new(),
         current_generic_names: HashSet::new(),
+        runners: HashMap::new(),
+        thunk_rows: HashMap::new(),
+        performed: Vec::new(),
     };
     tc.check_file(file);
     let mut result = HashMap::new();
@@ -362,10 +384,22 @@ struct TypeChecker {
     impl_registry: HashMap<String, HashSet<String>>,
     /// Generics for each named function: fn_name → Vec<GenericParam>.
     fn_generics: HashMap<String, Vec<lir::GenericParam>>,
+    /// Generics of cap operations and generic impl methods:
+    /// (cap or impl const, name) → Vec<GenericParam>.
+    member_generics: HashMap<(String, String), Vec<lir::GenericParam>>,
     /// Current function's generic type variable bounds: var_name → bound names.
     current_generic_bounds: HashMap<String, Vec<String>>,
     /// Current function's generic type variable names (for unification).
     current_generic_names: HashSet<String>,
+    /// Runners' params: a call that still reaches one was not inlined, so
+    /// its handler never sees what a non-literal `thunk` argument performs.
+    runners: HashMap<String, Vec<lir::Param>>,
+    /// The caps each fn's `thunk T / { .. }` params declare, by position;
+    /// empty for the other params.
+    thunk_rows: HashMap<String, Vec<Vec<TypeExpr>>>,
+    /// What each literal `thunk` argument checked against such a row
+    /// performs, as cap name and type args; innermost argument last.
+    performed: Vec<Vec<(String, Vec<ValueType>)>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -383,6 +417,17 @@ struct CapDef {
     uses_self: bool,
 }
 
+/// What a bundle literal's entries are checked against: the cap it
+/// handles and the answer of the `handle` or `Bundle` value it serves.
+struct BundleCheck<'a> {
+    def: &'a CapDef,
+    cap_name: &'a str,
+    /// The answer type, which `resume` returns; `None` when unknown.
+    handle_result_type: Option<&'a CompType>,
+    /// Every exit of an op body produces the answer, not only `resume`.
+    aborts_to_answer: bool,
+}
+
 enum BundleExprInferResult {
     NotBundleExpr,
     Typed(CompType),
@@ -391,6 +436,7 @@ enum BundleExprInferResult {
 
 impl TypeChecker {
     fn check_file(&mut self, file: &lir::File) {
+        self.runners = crate::lto::handlers::runner_params(file);
         for item in &file.items {
             if let lir::Item::Data(d) = item {
                 for v in &d.variants {
@@ -484,6 +530,10 @@ impl TypeChecker {
                         }
                     };
                     operations.insert(op.name.clone(), op_ty);
+                    if !op.generics.is_empty() {
+                        self.member_generics
+                            .insert((e.name.clone(), op.name.clone()), op.generics.clone());
+                    }
                 }
                 let uses_self = operations.values().any(|op_ty| c_type_references_self(op_ty));
                 self.cap_defs
@@ -512,7 +562,19 @@ impl TypeChecker {
                     self.impl_consts.insert(target.clone(), target.clone());
                     self.register_impl_methods(impl_decl, &target);
                 } else if impl_decl.capability.is_none() {
-                    // Non-cap inherent impl: register const and methods
+                    // Non-cap inherent impl: register const and methods.
+                    // With no op to take them from, its params need types.
+                    for m in &impl_decl.methods {
+                        for p in &m.params {
+                            if matches!(&p.ty.value, TypeExpr::Named(n) if n == "<missing>") {
+                                self.errors.push(TypeError::with_span(
+                                    0,
+                                    p.ty.span,
+                                    format!("param `{}` of `{target}.{}` needs a type", p.name, m.name),
+                                ));
+                            }
+                        }
+                    }
                     self.impl_consts.insert(target.clone(), target.clone());
                     self.register_impl_methods(impl_decl, &target);
                 } else if let Some(cap_ty) = &impl_decl.capability {
@@ -649,10 +711,22 @@ impl TypeChecker {
         if !f.generics.is_empty() {
             self.fn_generics.insert(f.name.clone(), f.generics.clone());
         }
+        let rows: Vec<Vec<TypeExpr>> = f.params.iter().map(|p| thunk_row(&p.ty.value)).collect();
+        if rows.iter().any(|row| !row.is_empty()) {
+            self.thunk_rows.insert(f.name.clone(), rows);
+        }
     }
 
     /// Register impl methods in `value_type_methods` for value method dispatch.
     fn register_impl_methods(&mut self, impl_decl: &lir::ImplDecl, target: &str) {
+        if !impl_decl.generics.is_empty() {
+            for m in &impl_decl.methods {
+                self.member_generics.insert(
+                    (target.to_owned(), m.name.clone()),
+                    impl_decl.generics.clone(),
+                );
+            }
+        }
         let methods = self.value_type_methods.entry(target.to_owned()).or_default();
         for m in &impl_decl.methods {
             let param_types = m
@@ -687,6 +761,9 @@ impl TypeChecker {
             f.return_type.as_ref().map(|t| &t.value),
             f.cap.as_ref(),
         );
+        if !f.generics.is_empty() {
+            self.fn_generics.insert(f.name.clone(), f.generics.clone());
+        }
     }
 
     fn check_fn(&mut self, f: &lir::FnDecl) {
@@ -774,6 +851,13 @@ impl TypeChecker {
                 self.current_generic_names.insert(name.clone());
                 if !bounds.is_empty() {
                     self.current_generic_bounds.insert(name.clone(), bounds.clone());
+                    // A bound `K: PartialEq` makes its cap available in the
+                    // body, as if listed after `/`
+                    for bound in bounds {
+                        if let Some(handler_ty) = self.cap_handler_type(bound) {
+                            env.entry(format!("__cap_{bound}")).or_insert(handler_ty);
+                        }
+                    }
                     // Inject bound methods into value_type_methods for this type var
                     let methods = self.synthesize_bound_methods(name, bounds);
                     if !methods.is_empty() {
@@ -868,10 +952,22 @@ impl TypeChecker {
         ) = (expr, expected)
         {
             if let Some(def) = self.cap_defs.get(cap_name).cloned() {
-                self.check_bundle_against_cap(entries, &def, cap_name, id.0 as u64, env, None);
+                let check = BundleCheck {
+                    def: &def,
+                    cap_name,
+                    handle_result_type: None,
+                    aborts_to_answer: false,
+                };
+                self.check_bundle_against_cap(entries, &check, id.0 as u64, env);
                 return;
             }
         }
+        if let (Expr::Bundle { entries, id, .. }, Some((cap, type_args, answer))) =
+            (expr, bundle_type_cap(expected))
+        {
+            self.check_bundle_value(entries, &cap, &type_args, answer.as_ref(), id.0 as u64, env);
+            return;
+        }
         // For constructor expressions, pass expected type to resolve generics
         if matches!(expr, Expr::Ctor { .. } | Expr::Roll { .. }) {
             let expected_ct = CompType::Produce(Box::new(expected.clone()));
@@ -916,15 +1012,199 @@ impl TypeChecker {
         }
     }
 
+    /// The cap a `handle` installs: `handle Throw for String` handles
+    /// `Throw[String]`, so its ops take `String` where they take `Self`.
+    fn handled_cap_def(&self, cap: &str, type_args: &[String]) -> Option<CapDef> {
+        let mut def = self.cap_defs.get(cap)?.clone();
+        let for_type = type_args
+            .first()
+            .filter(|t| def.uses_self && *t != cap)
+            .and_then(|t| parse_v_type(t));
+        if let Some(concrete) = for_type {
+            for op_ty in def.operations.values_mut() {
+                *op_ty = subst_self_c(op_ty, &concrete);
+            }
+        }
+        Some(def)
+    }
+
+    /// A bare `handle` of a `Self` cap whose handler resumes an op that
+    /// returns `Self` with a value of known type, as `fn ask() =
+    /// resume(env)` does, handles the cap at that type. Recorded under the
+    /// handle's id for its type args to be patched like a perform's.
+    fn record_resumed_self_type(
+        &mut self,
+        handle_id: u64,
+        cap: &str,
+        type_args: &[String],
+        handler: &Expr,
+        env: &HashMap<String, ValueType>,
+    ) {
+        let Some(def) = self.cap_defs.get(cap).cloned() else { return };
+        let Expr::Bundle { entries, .. } = handler else { return };
+        if !type_args.is_empty() || !def.uses_self {
+            return;
+        }
+        for entry in entries {
+            let returns_self = def
+                .operations
+                .get(&entry.name)
+                .and_then(comp_type_return_value)
+                .is_some_and(|v| matches!(v, ValueType::Named(n) if n == "Self"));
+            let Expr::Apply { callee, arg, .. } = &entry.body else { continue };
+            let resumes = matches!(callee.as_ref(), Expr::Force { expr, .. }
+                if matches!(expr.as_ref(), Expr::Ident { name, .. } if name == "resume"));
+            if !returns_self || !resumes {
+                continue;
+            }
+            let ty = match arg.as_ref() {
+                Expr::Ident { name, .. } => env.get(name).cloned(),
+                Expr::String { .. } | Expr::Number { .. } => self.infer_v_expr(arg, env),
+                _ => None,
+            };
+            let concrete = ty.filter(|t| match t {
+                ValueType::Named(n) => n != "Self" && n != "_" && !self.current_generic_names.contains(n),
+                _ => false,
+            });
+            if let Some(concrete) = concrete {
+                self.perform_for_types.insert(handle_id, vec![render_v_type(&concrete)]);
+                return;
+            }
+        }
+    }
+
+    /// A bundle literal used as a `Bundle[cap]` value rather than directly
+    /// in a `handle`. Each op body is checked against the bundle's answer
+    /// type, as is `resume`'s result. `Bundle[cap, R]` names it, so an op
+    /// may abort with an `R`; plain `Bundle[cap]` leaves it to whichever
+    /// `handle` installs the value, so the answer is abstract and only
+    /// `resume` (or a call that never returns) can produce one.
+    fn check_bundle_value(
+        &mut self,
+        entries: &[lir::BundleEntry],
+        cap: &str,
+        type_args: &[String],
+        answer: Option<&ValueType>,
+        node_id: u64,
+        env: &HashMap<String, ValueType>,
+    ) {
+        let Some(def) = self.handled_cap_def(cap, type_args) else {
+            self.errors.push(TypeError::new(node_id, format!("unknown cap `{cap}`")));
+            return;
+        };
+        let answer = answer.cloned().unwrap_or_else(|| ValueType::Named(ABSTRACT_ANSWER.to_owned()));
+        let answer = CompType::Produce(Box::new(answer));
+        let check = BundleCheck {
+            def: &def,
+            cap_name: cap,
+            handle_result_type: Some(&answer),
+            aborts_to_answer: true,
+        };
+        self.check_bundle_against_cap(entries, &check, node_id, env);
+        for entry in entries {
+            let Some(op_ty) = def.operations.get(&entry.name) else { continue };
+            // Unlike a `handle`'s literal, whose params may name a runner's
+            // generics, a value's params must be the op's own.
+            if let CompType::Fn { params, .. } = op_ty {
+                for (p, expected_ty) in entry.params.iter().zip(params) {
+                    let annotated =
+                        !matches!(&p.ty.value, TypeExpr::Named(n) if n.is_empty() || n == "<missing>");
+                    let Some(declared) = v_type_from_type_expr(&p.ty.value).filter(|_| annotated) else {
+                        continue;
+                    };
+                    if !v_types_match(&declared, expected_ty) {
+                        self.errors.push(TypeError::new(
+                            node_id,
+                            format!(
+                                "bundle entry `{}` param `{}`: expected {}, got {}",
+                                entry.name,
+                                p.name,
+                                render_v_type(expected_ty),
+                                render_v_type(&declared)
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    /// A bundle literal passed where a `Bundle[cap]` is expected has no
+    /// type of its own to infer: check it against that type and take it.
+    fn infer_bundle_arg(
+        &mut self,
+        arg: &Expr,
+        expected: &ValueType,
+        env: &HashMap<String, ValueType>,
+    ) -> Option<ValueType> {
+        let Expr::Bundle { entries, id, .. } = arg else { return None };
+        let (cap, type_args, answer) = bundle_type_cap(expected)?;
+        self.check_bundle_value(entries, &cap, &type_args, answer.as_ref(), id.0 as u64, env);
+        Some(expected.clone())
+    }
+
+    /// A `handle` whose handler is a value, not a literal: it must be a
+    /// `Bundle` for the handled cap. A bare `handle` of a `Self` cap takes
+    /// its type args from the bundle's type. A bundle naming its answer
+    /// type may abort with it, so the `handle` must produce that type.
+    fn check_handler_value(
+        &mut self,
+        handle_id: u64,
+        cap: &str,
+        type_args: &[String],
+        handler: &Expr,
+        handle_result: Option<&CompType>,
+        env: &HashMap<String, ValueType>,
+    ) {
+        let Some(actual) = self.infer_v_expr(handler, env) else { return };
+        match bundle_type_cap(&actual) {
+            Some((bundle_cap, bundle_args, answer))
+                if bundle_cap == cap
+                    && (type_args.is_empty() || bundle_args.is_empty() || bundle_args == type_args) =>
+            {
+                if type_args.is_empty() && !bundle_args.is_empty() {
+                    self.perform_for_types.insert(handle_id, bundle_args);
+                }
+                let result = handle_result.and_then(comp_type_return_value);
+                if let (Some(answer), Some(result)) = (answer, result) {
+                    if !v_types_match(&answer, result) {
+                        self.errors.push(TypeError::new(
+                            handle_id,
+                            format!(
+                                "type mismatch: a `{}` handler answers {}, but the `handle` produces {}",
+                                render_v_type(&actual),
+                                render_v_type(&answer),
+                                render_v_type(result)
+                            ),
+                        ));
+                    }
+                }
+            }
+            // The cap's own name stands for its handlers too.
+            _ if matches!(&actual, ValueType::Named(n) if n == cap) => {}
+            _ => self.errors.push(TypeError::new(
+                expr_node_id(handler),
+                format!(
+                    "type mismatch: handler for `{cap}` must be a `Bundle[{cap}]`, got `{}`",
+                    render_v_type(&actual)
+                ),
+            )),
+        }
+    }
+
     fn check_bundle_against_cap(
         &mut self,
         entries: &[lir::BundleEntry],
-        def: &CapDef,
-        cap_name: &str,
+        check: &BundleCheck,
         node_id: u64,
         env: &HashMap<String, ValueType>,
-        handle_result_type: Option<&CompType>,
     ) {
+        let BundleCheck {
+            def,
+            cap_name,
+            handle_result_type,
+            aborts_to_answer,
+        } = *check;
         // Check for missing operations
         for op_name in def.operations.keys() {
             if !entries.iter().any(|e| e.name == *op_name) {
@@ -939,6 +1219,18 @@ impl TypeChecker {
         // Check each entry
         for entry in entries {
             if let Some(op_ty) = def.operations.get(&entry.name) {
+                // A handler serves every instantiation of a generic op, so
+                // the op's own type params are left open in its entry.
+                let op_ty = &match self.member_generics.get(&(cap_name.to_owned(), entry.name.clone())) {
+                    Some(generics) => {
+                        let open: HashMap<String, ValueType> = generics
+                            .iter()
+                            .map(|g| (g.name().to_owned(), ValueType::Named("_".to_owned())))
+                            .collect();
+                        subst_c_type(op_ty, &open)
+                    }
+                    None => op_ty.clone(),
+                };
                 let mut entry_env = env.clone();
                 let uses_resume = lir::expr_references_name(&entry.body, "resume");
 
@@ -975,8 +1267,9 @@ impl TypeChecker {
                             entry_env.insert(p.name.clone(), expected_ty.clone());
                         }
                         // If entry uses resume, check body against handle result type;
-                        // otherwise check against op return type (tail-resumptive)
-                        let check_against = if uses_resume {
+                        // otherwise check against op return type (tail-resumptive),
+                        // unless the caller pins every exit to the answer type.
+                        let check_against = if uses_resume || aborts_to_answer {
                             handle_result_type.unwrap_or(ret.as_ref())
                         } else {
                             ret.as_ref()
@@ -995,7 +1288,7 @@ impl TypeChecker {
                             ));
                             continue;
                         }
-                        let check_against = if uses_resume {
+                        let check_against = if uses_resume || aborts_to_answer {
                             handle_result_type.unwrap_or(op_ty)
                         } else {
                             op_ty
@@ -1071,12 +1364,16 @@ impl TypeChecker {
 
         let generic_set = def.generics.iter().cloned().collect::<HashSet<_>>();
         let mut subst = HashMap::new();
+        let mut open_payload = false;
 
         if called {
             for (arg, payload_ty) in args.iter().zip(payload_types.iter()) {
-                let Some(actual_ty) = self.infer_v_expr(arg, env) else {
+                let actual_ty = self.infer_bundle_arg(arg, payload_ty, env)
+                    .or_else(|| self.infer_v_expr(arg, env));
+                let Some(actual_ty) = actual_ty else {
                     return BundleExprInferResult::Error;
                 };
+                open_payload |= matches!(&actual_ty, ValueType::Named(n) if n == "_");
                 if !self.unify_ctor_payload_type(
                     payload_ty,
                     &actual_ty,
@@ -1123,9 +1420,10 @@ impl TypeChecker {
             }
         }
         // For zero-payload constructors (e.g. List.nil), unresolved generics
-        // are allowed — the type parameter can only be inferred from context.
-        // Use the generic name as-is for the result type.
-        if !unresolved.is_empty() && !payload_types.is_empty() {
+        // are allowed — the type parameter can only be inferred from context,
+        // so the result leaves it open as `_`. The same goes for a payload
+        // that is itself open (`Option.some(ArrayOps.at(xs, 0))`).
+        if !unresolved.is_empty() && !payload_types.is_empty() && !open_payload {
             self.errors.push(TypeError::new(
                 node_id,
                 format!(
@@ -1146,7 +1444,7 @@ impl TypeChecker {
                     subst
                         .get(generic)
                         .map(render_v_type)
-                        .unwrap_or_else(|| generic.clone())
+                        .unwrap_or_else(|| "_".to_owned())
                 })
                 .collect::<Vec<_>>();
             ValueType::Named(format!("{owner}[{}]", resolved_args.join(", ")))
@@ -1242,6 +1540,39 @@ impl TypeChecker {
         Some(*inner)
     }
 
+    /// `Step[S]` for a loop over `state`. Once mono has run, `Step` exists
+    /// only as its specialized copies: pick the one whose `next` carries `S`.
+    fn step_type(&self, state: &ValueType) -> ValueType {
+        let rendered = render_v_type(state);
+        if !self.data_defs.contains_key("Step") {
+            let specialized = self.data_defs.iter().find(|(name, def)| {
+                name.starts_with("Step_")
+                    && def.variants.get("next").is_some_and(|payload| {
+                        matches!(payload.as_slice(), [ty] if render_v_type(ty) == rendered)
+                    })
+            });
+            if let Some((name, _)) = specialized {
+                return ValueType::Named(name.clone());
+            }
+        }
+        ValueType::Named(format!("Step[{rendered}]"))
+    }
+
+    /// A cap op's signature is not specialized by mono, so `ArrayOps.at`
+    /// still produces `Option[Number]` where everything else has moved on to
+    /// `Option_ANumber_Z`: name the instance instead, if there is one.
+    fn specialized_ret(&self, ret: CompType) -> CompType {
+        let CompType::Produce(inner) = &ret else { return ret };
+        let ValueType::Named(name) = inner.as_ref() else { return ret };
+        let Some(TypeExpr::App { head, args }) = TypeExpr::parse(name) else { return ret };
+        let instance = crate::lto::mono::instance_name(&head, &args);
+        if self.data_defs.contains_key(&instance) {
+            CompType::Produce(Box::new(ValueType::Named(instance)))
+        } else {
+            ret
+        }
+    }
+
     fn check_c_expr(&mut self, expr: &Expr, expected: &CompType, env: &HashMap<String, ValueType>) {
         match self.infer_bundle_expr_as_comp(expr, env, Some(expected)) {
             BundleExprInferResult::Typed(_) | BundleExprInferResult::Error => return,
@@ -1275,6 +1606,21 @@ impl TypeChecker {
                 child.insert(name.clone(), inner);
                 self.check_c_expr(body, expected, &child);
             }
+            Expr::Loop { id, .. } => {
+                let Some(actual) = self.infer_c_expr(expr, env) else {
+                    return;
+                };
+                if actual != *expected {
+                    self.errors.push(TypeError::new(
+                        id.0 as u64,
+                        format!(
+                            "type mismatch: expected {}, got {}",
+                            render_c_type(expected),
+                            render_c_type(&actual)
+                        ),
+                    ));
+                }
+            }
             Expr::Match {
                 scrutinee,
                 arms,
@@ -1288,10 +1634,10 @@ impl TypeChecker {
             }
             Expr::Handle {
                 cap,
+                type_args,
                 handler,
                 body,
                 id,
-                ..
             } => {
                 let base = cap.as_str();
                 if self.cap_defs.get(base).is_none() {
@@ -1309,27 +1655,29 @@ impl TypeChecker {
                 if let Some(ref ty) = handler_value_type {
                     body_env.insert(format!("__cap_{cap}"), ty.clone());
                 }
+                self.performed.push(Vec::new());
                 self.check_c_expr(body, expected, &body_env);
+                self.record_resumed_self_type(id.0 as u64, base, type_args, handler, env);
+                let type_args = &self.handled_type_args(id.0 as u64, base, type_args);
                 // Check handler with handle result type for resume
-                if let Some(ref expected_handler_ty) = handler_value_type {
+                if handler_value_type.is_some() {
                     if let Expr::Bundle {
                         entries,
                         id: bundle_id,
                         ..
                     } = handler.as_ref()
                     {
-                        if let Some(def) = self.cap_defs.get(base).cloned() {
-                            self.check_bundle_against_cap(
-                                entries,
-                                &def,
-                                base,
-                                bundle_id.0 as u64,
-                                env,
-                                Some(expected),
-                            );
+                        if let Some(def) = self.handled_cap_def(base, type_args) {
+                            let check = BundleCheck {
+                                def: &def,
+                                cap_name: base,
+                                handle_result_type: Some(expected),
+                                aborts_to_answer: false,
+                            };
+                            self.check_bundle_against_cap(entries, &check, bundle_id.0 as u64, env);
                         }
                     } else {
-                        self.check_v_expr(handler, expected_handler_ty, env);
+                        self.check_handler_value(id.0 as u64, base, type_args, handler, Some(expected), env);
                     }
                 } else {
                     let _ = self.infer_c_expr(handler, env);
@@ -1371,6 +1719,8 @@ impl TypeChecker {
             Expr::Ident { name, id, .. } => {
                 if let Some(ty) = env.get(name) {
                     Some(ty.clone())
+                } else if name == "Unit" {
+                    Some(ValueType::Named("Unit".to_owned()))
                 } else if let Some(cap_name) = self.impl_consts.get(name) {
                     // Resolved impl const — type as its cap name for member access
                     Some(ValueType::Named(cap_name.clone()))
@@ -1383,7 +1733,9 @@ impl TypeChecker {
                 }
             }
             Expr::String { .. } => Some(ValueType::Named("String".to_owned())),
-            Expr::Number { .. } => Some(ValueType::Named("Number".to_owned())),
+            Expr::Number { value, .. } => Some(ValueType::Named(
+                lumo_lexer::NumberKind::of(value).type_name().to_owned(),
+            )),
             Expr::Thunk { expr, .. } => {
                 let inner = self.infer_c_expr(expr, env)?;
                 Some(ValueType::Thunk(Box::new(inner)))
@@ -1553,7 +1905,13 @@ impl TypeChecker {
                                         }
                                     }
                                 }
-                                return Some(*ret.clone());
+                                let generics = self.fn_generics.get(name).cloned().unwrap_or_default();
+                                let open: HashMap<String, ValueType> = generics
+                                    .iter()
+                                    .map(|g| (g.name().to_owned(), ValueType::Named("_".to_owned())))
+                                    .collect();
+                                self.record_performed(callee_caps, &open);
+                                return Some(open_generics(ret, &generics));
                             }
                         }
                         return Some(ty.clone());
@@ -1614,13 +1972,11 @@ impl TypeChecker {
                     ));
                     return None;
                 }
+                self.check_runner_args(callee, &args, node_id);
 
                 // --- Generic unification ---
                 // Collect generic params for this callee (if named function)
-                let callee_generics = extract_callee_name(callee)
-                    .and_then(|name| self.fn_generics.get(name))
-                    .cloned()
-                    .unwrap_or_default();
+                let callee_generics = self.callee_generics(callee);
                 let generic_names: HashSet<String> = callee_generics.iter()
                     .filter(|g| !g.is_cap_row())
                     .map(|g| g.name().to_owned())
@@ -1629,8 +1985,19 @@ impl TypeChecker {
                 // Pass 1: infer arg types and build substitution
                 let mut subst: HashMap<String, ValueType> = HashMap::new();
                 let mut arg_tys: Vec<Option<ValueType>> = Vec::new();
-                for (arg, param_ty) in args.iter().zip(params.iter()) {
-                    let arg_ty = self.infer_v_expr(arg, env);
+                let callee_name = extract_callee_name(callee).unwrap_or_default();
+                let rows = self.thunk_rows.get(callee_name).cloned().unwrap_or_default();
+                for (i, (arg, param_ty)) in args.iter().zip(params.iter()).enumerate() {
+                    let row = rows.get(i).filter(|row| !row.is_empty() && matches!(arg, Expr::Thunk { .. }));
+                    if row.is_some() {
+                        self.performed.push(Vec::new());
+                    }
+                    let arg_ty = self.infer_bundle_arg(arg, param_ty, env)
+                        .or_else(|| self.infer_v_expr(arg, env));
+                    if let Some(row) = row {
+                        let performed = self.performed.pop().unwrap_or_default();
+                        self.unify_thunk_row(callee_name, row, &performed, &generic_names, &mut subst, expr_node_id(arg));
+                    }
                     if let Some(ref at) = arg_ty {
                         if !self.unify_type_var(param_ty, at, &generic_names, &mut subst) {
                             self.errors.push(TypeError::new(
@@ -1665,15 +2032,43 @@ impl TypeChecker {
                     }
                 }
 
+                // A generic no argument pins down is left open: `List.nil`
+                // passed as `List[A]` leaves `A` as `_`, which matches anything.
+                for name in &generic_names {
+                    subst
+                        .entry(name.clone())
+                        .or_insert_with(|| ValueType::Named("_".to_owned()));
+                }
+
                 // Pass 2: check arg types against substituted param types
                 let mut self_concrete: Option<ValueType> = None;
-                for (arg_ty, (arg, param_ty)) in arg_tys.iter().zip(args.iter().zip(params.iter())) {
+                for (i, (arg_ty, (arg, param_ty))) in arg_tys.iter().zip(args.iter().zip(params.iter())).enumerate() {
                     let effective_param = Self::apply_subst_v(param_ty, &subst);
-                    if matches!(&effective_param, ValueType::Named(n) if n == "Self") {
-                        if self_concrete.is_none() {
-                            self_concrete = arg_ty.clone();
+                    // Only a declared `Self` param pins the cap's type: a generic
+                    // `A` bound to a handler's open `Self` doesn't.
+                    if matches!(param_ty, ValueType::Named(n) if n == "Self") {
+                        match (&self_concrete, arg_ty) {
+                            (None, _) => self_concrete = arg_ty.clone(),
+                            // `cmp(Tree.leaf, t)`: a later arg pins down the
+                            // `_` left open by `Tree.leaf`.
+                            (Some(expected), Some(at))
+                                if is_open_type(expected) && !is_open_type(at) && expected == at =>
+                            {
+                                self_concrete = Some(at.clone());
+                            }
+                            // Every `Self` is the same type: `1i + 1.0f` is an error.
+                            (Some(expected), Some(at)) if expected != at => {
+                                self.errors.push(TypeError::new(
+                                    expr_node_id(arg),
+                                    format!(
+                                        "type mismatch: expected `{}`, got `{}`",
+                                        render_v_type(expected),
+                                        render_v_type(at),
+                                    ),
+                                ));
+                            }
+                            _ => {}
                         }
-                        // Self check handled below
                     } else if generic_names.contains(match param_ty { ValueType::Named(n) => n.as_str(), _ => "" }) {
                         // Generic param: check inferred arg type matches effective param
                         if let Some(at) = arg_ty {
@@ -1688,6 +2083,11 @@ impl TypeChecker {
                                 ));
                             }
                         }
+                    } else if rows.get(i).is_some_and(|row| !row.is_empty()) {
+                        // Its performs were matched against the row in pass 1.
+                        self.performed.push(Vec::new());
+                        self.check_v_expr(arg, &effective_param, env);
+                        self.performed.pop();
                     } else {
                         self.check_v_expr(arg, &effective_param, env);
                     }
@@ -1706,6 +2106,12 @@ impl TypeChecker {
                         }
                     }
                 }
+                if let (Some(cap_name), Some(concrete)) = (extract_cap_from_callee(callee), &self_concrete) {
+                    if let Some(frame) = self.performed.last_mut() {
+                        frame.push((cap_name.to_owned(), vec![concrete.clone()]));
+                    }
+                }
+                self.record_performed(&callee_caps, &subst);
                 // Check that required caps are available at call site
                 for cap_entry in &callee_caps {
                     if let CapEntry::Cap(cap_ty) = cap_entry {
@@ -1730,6 +2136,9 @@ impl TypeChecker {
                 } else {
                     subst_ret
                 };
+                if extract_cap_from_callee(callee).is_some() {
+                    return Some(self.specialized_ret(resolved_ret));
+                }
                 Some(resolved_ret)
             }
             Expr::Let {
@@ -1740,6 +2149,27 @@ impl TypeChecker {
                 child.insert(name.clone(), inner);
                 self.infer_c_expr(body, &child)
             }
+            Expr::Loop {
+                id, var, init, body,
+            } => {
+                // The body produces the next state, as `Step[S]`; the loop
+                // itself produces `Unit` once it is done.
+                let state = self.infer_let_value_type(init, env)?;
+                if let Some(data) = for_loop_data(body) {
+                    if nominal_head_name(&state).as_deref() != Some(data) {
+                        self.errors.push(TypeError::new(
+                            id.0 as u64,
+                            format!("`for` iterates over a `{data}`, got {}", render_v_type(&state)),
+                        ));
+                        return Some(CompType::Produce(Box::new(ValueType::Named("Unit".to_owned()))));
+                    }
+                }
+                let step = self.step_type(&state);
+                let mut child = env.clone();
+                child.insert(var.clone(), state);
+                self.check_c_expr(body, &CompType::Produce(Box::new(step)), &child);
+                Some(CompType::Produce(Box::new(ValueType::Named("Unit".to_owned()))))
+            }
             Expr::Match {
                 scrutinee,
                 arms,
@@ -1793,10 +2223,10 @@ impl TypeChecker {
             }
             Expr::Handle {
                 cap,
+                type_args,
                 handler,
                 body,
                 id,
-                ..
             } => {
                 let base = cap.as_str();
                 if self.cap_defs.get(base).is_none() {
@@ -1813,27 +2243,36 @@ impl TypeChecker {
                 if let Some(ref ty) = handler_value_type {
                     body_env.insert(format!("__cap_{cap}"), ty.clone());
                 }
+                self.performed.push(Vec::new());
                 let body_comp_type = self.infer_c_expr(body, &body_env);
+                self.record_resumed_self_type(id.0 as u64, base, type_args, handler, env);
+                let type_args = &self.handled_type_args(id.0 as u64, base, type_args);
                 // Check handler — if it's a bundle literal, pass handle_result_type for resume
-                if let Some(ref expected_handler_ty) = handler_value_type {
+                if handler_value_type.is_some() {
                     if let Expr::Bundle {
                         entries,
                         id: bundle_id,
                         ..
                     } = handler.as_ref()
                     {
-                        if let Some(def) = self.cap_defs.get(base).cloned() {
-                            self.check_bundle_against_cap(
-                                entries,
-                                &def,
-                                base,
-                                bundle_id.0 as u64,
-                                env,
-                                body_comp_type.as_ref(),
-                            );
+                        if let Some(def) = self.handled_cap_def(base, type_args) {
+                            let check = BundleCheck {
+                                def: &def,
+                                cap_name: base,
+                                handle_result_type: body_comp_type.as_ref(),
+                                aborts_to_answer: false,
+                            };
+                            self.check_bundle_against_cap(entries, &check, bundle_id.0 as u64, env);
                         }
                     } else {
-                        self.check_v_expr(handler, expected_handler_ty, env);
+                        self.check_handler_value(
+                            id.0 as u64,
+                            base,
+                            type_args,
+                            handler,
+                            body_comp_type.as_ref(),
+                            env,
+                        );
                     }
                 } else {
                     let _ = self.infer_c_expr(handler, env);
@@ -1865,15 +2304,30 @@ impl TypeChecker {
                 };
 
                 if let ValueType::Named(ref name) = obj_ty {
-                    if let Some(def) = self.cap_defs.get(name).cloned() {
+                    // `perform State[Number]` types its ops with `Self` = `Number`
+                    let def = match object.as_ref() {
+                        Expr::Perform { cap, type_args, .. } => self.handled_cap_def(cap, type_args),
+                        _ => self.cap_defs.get(name).cloned(),
+                    };
+                    if let Some(def) = def {
                         if let Some(op_ty) = def.operations.get(field) {
-                            return Some(op_ty.clone());
+                            if let CompType::Fn { .. } = op_ty {
+                                return Some(op_ty.clone());
+                            }
+                            let generics = self
+                                .member_generics
+                                .get(&(name.clone(), field.clone()))
+                                .cloned()
+                                .unwrap_or_default();
+                            return Some(open_generics(op_ty, &generics));
                         }
                         // Fall through to value_type_methods check
                     }
 
-                    // Check value type methods (from inherent + typeclass impls)
-                    if let Some(methods) = self.value_type_methods.get(name) {
+                    // Check value type methods (from inherent + typeclass impls);
+                    // `Array[Number]` finds the methods of `impl[A] Array`.
+                    let head = name.split_once('[').map_or(name.as_str(), |(head, _)| head);
+                    if let Some(methods) = self.value_type_methods.get(head) {
                         if let Some(method_ty) = methods.get(field) {
                             return Some(method_ty.clone());
                         }
@@ -1952,7 +2406,17 @@ impl TypeChecker {
                     ));
                     return;
                 };
-                if let Some(payload_types) = data_def.variants.get(name) {
+                if let Some((qualifier, _)) = name.rsplit_once('.') {
+                    if qualifier != data_name {
+                        self.errors.push(TypeError::with_span(
+                            0,
+                            span,
+                            format!("pattern `{name}` does not match data type `{data_name}`"),
+                        ));
+                        return;
+                    }
+                }
+                if let Some(payload_types) = data_def.variants.get(pattern_variant(name)) {
                     if payload_types.len() != args.len() {
                         self.errors.push(TypeError::with_span(
                             0,
@@ -2048,6 +2512,9 @@ impl TypeChecker {
         subst: &mut HashMap<String, ValueType>,
         node_id: u64,
     ) -> bool {
+        if matches!(actual, ValueType::Named(n) if n == "_") {
+            return true;
+        }
         match template {
             ValueType::Named(name) => {
                 if generics.contains(name) {
@@ -2282,6 +2749,140 @@ impl TypeChecker {
         self.impl_registry
             .get(type_name)
             .map_or(false, |bs| bs.contains(bound))
+            || self
+                .current_generic_bounds
+                .get(type_name)
+                .is_some_and(|bs| bs.iter().any(|b| b == bound))
+    }
+
+    /// Generics of the fn, cap operation or impl method `callee` names.
+    /// A runner only handles the caps of a `thunk` literal spliced in at the
+    /// call; one held in a variable would perform them unhandled. A runner
+    /// handing its own `thunk` param on is inlined with it.
+    fn check_runner_args(&mut self, callee: &Expr, args: &[&Expr], node_id: u64) {
+        let Some(name) = extract_callee_name(callee) else { return };
+        let Some(params) = self.runners.get(name) else { return };
+        let forwarding = self.runners.get(&self.current_fn);
+        for (p, arg) in params.iter().zip(args) {
+            if !matches!(p.ty.value, TypeExpr::Thunk(_)) || matches!(arg, Expr::Thunk { .. }) {
+                continue;
+            }
+            if let Expr::Ident { name: held, .. } = arg {
+                if forwarding.is_some_and(|ps| ps.iter().any(|q| &q.name == held)) {
+                    continue;
+                }
+            }
+            let message = format!(
+                "`{name}` handles caps only in a literal `thunk` argument, got `{}`",
+                render_expr_head(arg)
+            );
+            self.errors.push(TypeError::new(node_id, message));
+        }
+    }
+
+    /// Bind the generics in a `thunk T / { .. }` param's caps from what the
+    /// literal `thunk` passed for it performs.
+    fn unify_thunk_row(
+        &mut self,
+        callee: &str,
+        row: &[TypeExpr],
+        performed: &[(String, Vec<ValueType>)],
+        generic_names: &HashSet<String>,
+        subst: &mut HashMap<String, ValueType>,
+        node_id: u64,
+    ) {
+        for declared in row {
+            let declared_args: Vec<ValueType> =
+                declared.cap_type_args().iter().filter_map(v_type_from_type_expr).collect();
+            for (cap, args) in performed {
+                if cap != declared.cap_name() || args.len() != declared_args.len() {
+                    continue;
+                }
+                let fits = declared_args.iter().zip(args).all(|(d, a)| {
+                    self.unify_type_var(d, a, generic_names, subst)
+                        && (is_open_type(a) || &Self::apply_subst_v(d, subst) == a)
+                });
+                if !fits {
+                    let render = |tys: &[ValueType]| tys.iter().map(render_v_type).collect::<Vec<_>>().join(", ");
+                    let expected: Vec<ValueType> =
+                        declared_args.iter().map(|d| Self::apply_subst_v(d, subst)).collect();
+                    self.errors.push(TypeError::new(
+                        node_id,
+                        format!(
+                            "`{callee}` expects a `thunk` performing `{cap}[{}]`, got one performing `{cap}[{}]`",
+                            render(&expected),
+                            render(args),
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Note a call's caps, at the types `subst` gives its generics, as
+    /// performed by the `thunk` argument or `handle` body being checked.
+    fn record_performed(&mut self, caps: &[CapEntry], subst: &HashMap<String, ValueType>) {
+        let Some(frame) = self.performed.last_mut() else { return };
+        for entry in caps {
+            if let CapEntry::Cap(cap_ty) = entry {
+                let args = cap_ty
+                    .cap_type_args()
+                    .iter()
+                    .filter_map(v_type_from_type_expr)
+                    .map(|t| Self::apply_subst_v(&t, subst))
+                    .collect();
+                frame.push((cap_ty.cap_name().to_owned(), args));
+            }
+        }
+    }
+
+    /// Pop the frame the body of a `handle cap` was checked in: what it
+    /// performed of `cap` is handled there, the rest passes on to the
+    /// enclosing frame. A bare `handle` whose body performs `cap` at one
+    /// concrete type handles it at that type, so its ops take and resume
+    /// values of it; recorded under the handle's id for its type args to be
+    /// patched like a perform's. Gives the type args to check the ops at.
+    fn handled_type_args(&mut self, handle_id: u64, cap: &str, type_args: &[String]) -> Vec<String> {
+        let frame = self.performed.pop().unwrap_or_default();
+        let (handled, rest): (Vec<_>, Vec<_>) = frame.into_iter().partition(|(name, _)| name == cap);
+        if let Some(outer) = self.performed.last_mut() {
+            outer.extend(rest);
+        }
+        if !type_args.is_empty() {
+            return type_args.to_vec();
+        }
+        let mut types = handled.into_iter().map(|(_, args)| args.into_iter().next());
+        let Some(Some(first)) = types.next() else { return Vec::new() };
+        let concrete = !is_open_type(&first)
+            && !matches!(&first, ValueType::Named(n) if n == "Self" || self.current_generic_names.contains(n));
+        if !concrete || !types.all(|t| t.as_ref() == Some(&first)) {
+            return Vec::new();
+        }
+        let found = vec![render_v_type(&first)];
+        self.perform_for_types.entry(handle_id).or_insert_with(|| found.clone());
+        found
+    }
+
+    fn callee_generics(&self, callee: &Expr) -> Vec<lir::GenericParam> {
+        if let Some(name) = extract_callee_name(callee) {
+            return self.fn_generics.get(name).cloned().unwrap_or_default();
+        }
+        let member = match callee {
+            Expr::Force { expr, .. } => expr.as_ref(),
+            other => other,
+        };
+        let Expr::Member { object, field, .. } = member else {
+            return Vec::new();
+        };
+        let owner = match object.as_ref() {
+            Expr::Ident { name, .. } => name,
+            Expr::Perform { cap, .. } => cap,
+            _ => return Vec::new(),
+        };
+        self.member_generics
+            .get(&(owner.clone(), field.clone()))
+            .cloned()
+            .unwrap_or_default()
     }
 
     fn validate_cap_entries(&mut self, entries: &[TypeExpr], span: Span) {
@@ -2319,6 +2920,9 @@ impl TypeChecker {
         generic_names: &HashSet<String>,
         subst: &mut HashMap<String, ValueType>,
     ) -> bool {
+        if matches!(concrete, ValueType::Named(n) if n == "_") {
+            return true;
+        }
         match pattern {
             ValueType::Named(n) if generic_names.contains(n) => {
                 if let Some(existing) = subst.get(n) {
@@ -2328,6 +2932,21 @@ impl TypeChecker {
                     true
                 }
             }
+            // `List[A]` against `List[Number]` binds `A` from the arguments.
+            ValueType::Named(n) => {
+                let ValueType::Named(c) = concrete else {
+                    return true;
+                };
+                let (head, args) = split_nominal_type_args(n);
+                let (concrete_head, concrete_args) = split_nominal_type_args(c);
+                if args.is_empty() || head != concrete_head || args.len() != concrete_args.len() {
+                    return true;
+                }
+                args.iter().zip(&concrete_args).all(|(p, c)| match (parse_v_type(p), parse_v_type(c)) {
+                    (Some(p), Some(c)) => self.unify_type_var(&p, &c, generic_names, subst),
+                    _ => true,
+                })
+            }
             _ => true, // non-generic: checked separately
         }
     }
@@ -2335,7 +2954,7 @@ impl TypeChecker {
     /// Apply a type variable substitution to a ValueType.
     fn apply_subst_v(ty: &ValueType, subst: &HashMap<String, ValueType>) -> ValueType {
         match ty {
-            ValueType::Named(n) => subst.get(n).cloned().unwrap_or_else(|| ty.clone()),
+            ValueType::Named(_) => subst_v_type(ty, subst),
             ValueType::Thunk(inner) => {
                 ValueType::Thunk(Box::new(Self::apply_subst_c(inner, subst)))
             }
@@ -2425,7 +3044,7 @@ impl TypeChecker {
     ) -> bool {
         self.is_useful_matrix(
             matrix,
-            &[pattern.clone()],
+            std::slice::from_ref(pattern),
             std::slice::from_ref(scrutinee_ty),
         )
     }
@@ -2450,7 +3069,9 @@ impl TypeChecker {
         if let Some(constructors) = self.constructors_for_type(first_ty) {
             match &vector[0] {
                 Pattern::Ctor { name, args } => {
-                    let Some(ctor) = constructors.iter().find(|ctor| ctor.name == *name) else {
+                    let Some(ctor) =
+                        constructors.iter().find(|ctor| ctor.name == pattern_variant(name))
+                    else {
                         return false;
                     };
                     let matrix =
@@ -2583,7 +3204,10 @@ impl TypeChecker {
                 let Some(constructors) = self.constructors_for_type(ty) else {
                     return false;
                 };
-                let Some(ctor) = constructors.into_iter().find(|ctor| ctor.name == *name) else {
+                let Some(ctor) = constructors
+                    .into_iter()
+                    .find(|ctor| ctor.name == pattern_variant(name))
+                else {
                     return false;
                 };
                 if ctor.payload_types.len() != args.len() {
@@ -2622,7 +3246,9 @@ impl TypeChecker {
     ) -> Option<Vec<Pattern>> {
         let (head, tail) = row.split_first()?;
         match head {
-            Pattern::Ctor { name, args } if name == ctor_name && args.len() == arity => {
+            Pattern::Ctor { name, args }
+                if pattern_variant(name) == ctor_name && args.len() == arity =>
+            {
                 let mut out = args.clone();
                 out.extend_from_slice(tail);
                 Some(out)
@@ -2740,7 +3366,28 @@ impl TypeChecker {
             })
             .collect();
 
-        if needs_inference.is_empty() {
+        // Inherent impl methods (`impl[A] Array { fn get(..) }`) carry no cap
+        // annotation; infer them too, keyed `Array.get`, so callers pick up
+        // the caps their bodies perform.
+        let inherent_methods: Vec<(String, &Expr)> = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                lir::Item::Impl(impl_decl) if impl_decl.capability.is_none() => {
+                    let target = impl_decl.target_type.value.display();
+                    (!cap_names.contains(&target)).then_some((target, impl_decl))
+                }
+                _ => None,
+            })
+            .flat_map(|(target, impl_decl)| {
+                impl_decl
+                    .methods
+                    .iter()
+                    .map(move |m| (format!("{target}.{}", m.name), &m.value))
+            })
+            .collect();
+
+        if needs_inference.is_empty() && inherent_methods.is_empty() {
             return;
         }
 
@@ -2780,6 +3427,14 @@ impl TypeChecker {
                     }
                 }
             }
+            for (key, value) in &inherent_methods {
+                let inferred = collect_caps_from_expr(value, &HashSet::new(), &fn_caps, &cap_names);
+                let old = fn_caps.get(key).cloned().unwrap_or_default();
+                if !caps_equal(&inferred, &old) {
+                    fn_caps.insert(key.clone(), inferred);
+                    changed = true;
+                }
+            }
             if !changed {
                 break;
             }
@@ -2840,6 +3495,21 @@ fn collect_caps_inner(
         return;
     }
 
+    // Decompose inherent method call: Apply*(Member(Ident(impl_const), method), args)
+    if let Some((key, args)) = decompose_method_call_for_inference(expr) {
+        if let Some(callee_caps) = fn_caps.get(&key) {
+            for c in callee_caps {
+                if !handled.contains(c.cap_name()) {
+                    add_cap(out, c.clone());
+                }
+            }
+            for arg in args {
+                collect_caps_inner(arg, handled, fn_caps, cap_defs, out);
+            }
+            return;
+        }
+    }
+
     // Decompose function call: Apply*(Force(Ident(name)), args)
     if let Some((fn_name, args)) = decompose_fn_call_for_inference(expr) {
         if let Some(callee_caps) = fn_caps.get(fn_name) {
@@ -2896,7 +3566,7 @@ fn collect_caps_inner(
             }
             collect_caps_inner(expr, handled, fn_caps, cap_defs, out);
         }
-        Expr::Let { value, body, .. } => {
+        Expr::Let { value, body, .. } | Expr::Loop { init: value, body, .. } => {
             collect_caps_inner(value, handled, fn_caps, cap_defs, out);
             collect_caps_inner(body, handled, fn_caps, cap_defs, out);
         }
@@ -2954,6 +3624,23 @@ fn decompose_perform_call_for_inference(expr: &Expr) -> Option<(&str, &[String],
     None
 }
 
+/// Decompose: Apply*(Member(Ident(impl_const), method), args) →
+/// Some(("impl_const.method", args))
+fn decompose_method_call_for_inference(expr: &Expr) -> Option<(String, Vec<&Expr>)> {
+    let (root, args) = unwrap_apply_chain_ref(expr);
+    let root = if let Expr::Force { expr, .. } = root {
+        expr.as_ref()
+    } else {
+        root
+    };
+    if let Expr::Member { object, field, .. } = root {
+        if let Expr::Ident { name, .. } = object.as_ref() {
+            return Some((format!("{name}.{field}"), args));
+        }
+    }
+    None
+}
+
 /// Decompose: Apply*(Force(Ident(name)), args) → Some((name, args))
 fn decompose_fn_call_for_inference(expr: &Expr) -> Option<(&str, Vec<&Expr>)> {
     let (root, args) = unwrap_apply_chain_ref(expr);
@@ -3044,6 +3731,8 @@ fn v_type_from_type_expr(te: &TypeExpr) -> Option<ValueType> {
             Some(ValueType::Thunk(Box::new(ct)))
         }
         TypeExpr::Produce(_) => None, // `produce T` is not a value type
+        // Only a literal `thunk` argument is checked against the caps.
+        TypeExpr::Comp { ty, .. } => v_type_from_type_expr(ty),
         TypeExpr::Cap { name, .. } => Some(ValueType::Named(name.clone())),
         TypeExpr::Fn { params, ret, cap } => {
             let param_types: Vec<ValueType> = params.iter().filter_map(v_type_from_type_expr).collect();
@@ -3099,6 +3788,34 @@ fn canonicalize_named_type_text(text: &str) -> String {
     text.chars().filter(|ch| !ch.is_whitespace()).collect()
 }
 
+/// Whether a `_` left open by inference appears in `ty`, as in `List[_]`.
+fn is_open_type(ty: &ValueType) -> bool {
+    fn open(text: &str) -> bool {
+        let (head, args) = split_nominal_type_args(text);
+        head == "_" || args.iter().any(|a| open(a))
+    }
+    matches!(ty, ValueType::Named(n) if open(n))
+}
+
+/// The answer type of a `Bundle[Cap]` value: the `handle` installing it
+/// picks the real one, so it matches nothing but itself.
+const ABSTRACT_ANSWER: &str = "<answer>";
+
+/// The cap a `Bundle[IO]` is a handler for, with its type args and the
+/// answer type if named: `Bundle[State[Number], String]` gives
+/// `("State", ["Number"], Some(String))`.
+fn bundle_type_cap(ty: &ValueType) -> Option<(String, Vec<String>, Option<ValueType>)> {
+    let ValueType::Named(text) = ty else { return None };
+    match split_nominal_type_args(text) {
+        (head, args) if head == "Bundle" && (1..=2).contains(&args.len()) => {
+            let (cap, cap_args) = split_nominal_type_args(&args[0]);
+            let answer = args.get(1).map(|r| parse_v_type(r).unwrap_or_else(|| ValueType::Named(r.clone())));
+            Some((cap, cap_args, answer))
+        }
+        _ => None,
+    }
+}
+
 fn split_nominal_type_args(text: &str) -> (String, Vec<String>) {
     let text = text.trim();
     let Some(start) = text.find('[') else {
@@ -3130,6 +3847,20 @@ fn split_nominal_type_args(text: &str) -> (String, Vec<String>) {
     (head, out)
 }
 
+/// A zero-arg call has no arguments to pin its generics down, so they are
+/// left open: `ArrayOps.new()` produces an `Array[_]`.
+fn open_generics(ty: &CompType, generics: &[lir::GenericParam]) -> CompType {
+    let subst: HashMap<String, ValueType> = generics
+        .iter()
+        .filter(|g| !g.is_cap_row())
+        .map(|g| (g.name().to_owned(), ValueType::Named("_".to_owned())))
+        .collect();
+    if subst.is_empty() {
+        return ty.clone();
+    }
+    subst_c_type(ty, &subst)
+}
+
 fn subst_v_type(ty: &ValueType, subst: &HashMap<String, ValueType>) -> ValueType {
     match ty {
         ValueType::Named(name) => {
@@ -3197,6 +3928,24 @@ fn render_pattern(pattern: &Pattern) -> String {
     }
 }
 
+/// The variant a ctor pattern names: `wrap` for both `.wrap(..)` and the
+/// qualified `Wrap.wrap(..)` (or `Wrap__Number.wrap(..)` after mono).
+fn pattern_variant(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, variant)| variant)
+}
+
+/// The data type a `for` loop walks: its desugaring matches the state
+/// against qualified `List.nil`/`List.cons` arms.
+fn for_loop_data(body: &Expr) -> Option<&str> {
+    let Expr::Match { arms, .. } = body else {
+        return None;
+    };
+    arms.iter().find_map(|arm| match &arm.pattern {
+        Pattern::Ctor { name, .. } => name.rsplit_once('.').map(|(data, _)| data),
+        _ => None,
+    })
+}
+
 fn nominal_head_name(ty: &ValueType) -> Option<String> {
     match ty {
         ValueType::Named(n) => Some(split_nominal_type_args(n).0),
@@ -3231,6 +3980,7 @@ fn is_syntactic_computation_expr(expr: &Expr) -> bool {
             | Expr::Handle { .. }
             | Expr::Member { .. }
             | Expr::Ann { .. }
+            | Expr::Loop { .. }
             | Expr::Error { .. }
     )
 }
@@ -3343,6 +4093,18 @@ fn extract_callee_name(callee: &Expr) -> Option<&str> {
     }
 }
 
+/// The caps a `thunk T / { .. }` param type declares.
+fn thunk_row(ty: &TypeExpr) -> Vec<TypeExpr> {
+    let TypeExpr::Thunk(inner) = ty else { return Vec::new() };
+    let TypeExpr::Comp { cap, .. } = inner.as_ref() else { return Vec::new() };
+    cap.iter()
+        .filter_map(|e| match e {
+            CapEntry::Cap(c) => Some(c.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
 fn extract_cap_from_callee(callee: &Expr) -> Option<&str> {
     if let Expr::Force { expr, .. } = callee {
         if let Expr::Member { object, .. } = expr.as_ref() {
@@ -1,9 +1,10 @@
 use lumo_compiler::{
-    backend::{self, CodegenTarget},
+    backend::{self, BackendKind, CodegenTarget},
     hir,
     lexer::lex,
     lir,
     parser::parse,
+    query::QueryEngine,
 };
 
 fn lower_typed(src: &str) -> lir::File {
@@ -18,6 +19,16 @@ fn emit_rust(src: &str) -> String {
     backend::emit(&file, CodegenTarget::Rust).expect("rust emit")
 }
 
+/// Emit `src` after the pipeline's lowering for the Rust backend, which
+/// monomorphizes.
+fn emit_rust_pipeline(src: &str) -> String {
+    let mut q = QueryEngine::new();
+    q.set_backend(BackendKind::Rust);
+    q.set_file("main.lumo", src);
+    let file = q.compile_with_deps(&["main.lumo"], |_| None).expect("compile");
+    backend::emit(&file, CodegenTarget::Rust).expect("rust emit")
+}
+
 #[test]
 fn rs_backend_emits_pure_function() {
     let rs = emit_rust("fn id(x: String): String { x }");
@@ -95,6 +106,19 @@ fn rs_backend_skips_builtin_extern_types() {
     assert!(!rs.contains("type String"), "{rs}");
 }
 
+#[test]
+fn rs_backend_maps_bigint_extern_type_to_i128() {
+    let rs = emit_rust("#[extern = \"bigint\"] extern type Int;");
+    assert!(rs.contains("type Int = i128;"), "{rs}");
+}
+
+#[test]
+fn rs_backend_rejects_unknown_extern_type() {
+    let file = lower_typed("#[extern = \"globalThis.Symbol\"] extern type Sym;");
+    let err = format!("{:?}", backend::emit(&file, CodegenTarget::Rust).unwrap_err());
+    assert!(err.contains("`Sym`") && err.contains("no Rust type"), "{err}");
+}
+
 #[test]
 fn rs_backend_emits_unit_return() {
     let rs = emit_rust("fn noop() { Unit }");
@@ -185,3 +209,41 @@ fn rs_backend_emits_named_cap_impl() {
         "named impl method should be name__method: {rs}"
     );
 }
+
+#[test]
+fn rs_backend_monomorphizes_generic_data_and_fns() {
+    let rs = emit_rust_pipeline(
+        "data List[A] { .nil, .cons(A, List[A]) } fn len[A](xs: List[A]): Number { match xs { .nil => 0, .cons(_, t) => len(t) } } fn main(): Number { len(List.cons(\"a\", List.nil)) }",
+    );
+    assert!(
+        rs.contains("enum List_AString_Z {"),
+        "List[String] should become a concrete enum: {rs}"
+    );
+    assert!(
+        !rs.contains("enum List<"),
+        "fully specialized generic enum should be dropped: {rs}"
+    );
+    assert!(
+        rs.contains("fn len_AString_Z(xs: List_AString_Z) -> f64"),
+        "generic fn should be specialized: {rs}"
+    );
+    assert!(
+        rs.contains("List_AString_Z::Cons(_, t) => { let t = *t;"),
+        "patterns should be qualified and boxed fields unboxed: {rs}"
+    );
+}
+
+#[test]
+fn rs_backend_emits_for_loop_as_native_loop() {
+    let rs = emit_rust_pipeline(
+        "data Unit { .unit } data Step[S] { .next(S), .done } data List[A] { .nil, .cons(A, List[A]) } fn walk(xs: List[String]): Unit { for x in xs { x } } fn main(): Unit { walk(List.cons(\"a\", List.nil)) }",
+    );
+    assert!(
+        rs.contains("fn into_next(self) -> Option<List_AString_Z>"),
+        "Step should get a native-loop helper: {rs}"
+    );
+    assert!(
+        rs.contains("while let Some(__next) = "),
+        "for should become a native loop: {rs}"
+    );
+}
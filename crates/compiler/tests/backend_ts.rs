@@ -1,5 +1,5 @@
 use lumo_compiler::{
-    backend::{self, BackendError, CodegenTarget},
+    backend::{self, BackendError, CodegenTarget, TsOptions},
     hir,
     lexer::lex,
     lir,
@@ -113,6 +113,41 @@ fn ts_backend_match_checks_variant_tag_without_dot() {
     assert!(!js.contains("x[LUMO_TAG] === \"false\""), "{js}");
 }
 
+#[test]
+fn ts_backend_debug_assertions_check_the_last_match_arm() {
+    let file = lower_typed(
+        "data Bool { .true, .false } fn not(x: Bool): Bool { match x { .true => Bool.false(), .false => Bool.true() } }",
+    );
+    let options = TsOptions {
+        debug_assertions: true,
+        ..TsOptions::default()
+    };
+    let js = backend::emit_with_ts_options(&file, CodegenTarget::JavaScript, options)
+        .expect("js emit");
+    assert!(js.contains("x[LUMO_TAG] === \"false\""), "{js}");
+    assert!(js.contains("__lumo_match_error(x)"), "{js}");
+    assert!(js.contains("const __lumo_match_error = "), "{js}");
+}
+
+#[test]
+fn ts_backend_minify_puts_each_top_level_statement_on_one_line() {
+    let file = lower_typed(
+        "data Bool { .true, .false } fn not(x: Bool): Bool { match x { .true => Bool.false(), .false => Bool.true() } }",
+    );
+    let options = TsOptions {
+        minify: true,
+        ..TsOptions::default()
+    };
+    let js = backend::emit_with_ts_options(&file, CodegenTarget::JavaScript, options)
+        .expect("js emit");
+    let not = js
+        .lines()
+        .find(|l| l.starts_with("export function not(x)"))
+        .expect("one-line fn");
+    assert!(not.ends_with('}'), "{js}");
+    assert!(!js.contains("\n\n") && !js.contains("\n "), "{js}");
+}
+
 #[test]
 fn ts_backend_lowers_nested_match_patterns_as_tree() {
     let file = lower_typed(
@@ -256,9 +291,9 @@ fn ts_backend_handle_with_resume_uses_cps() {
     );
     let js = backend::emit(&file, CodegenTarget::JavaScript).expect("js emit");
     // CPS: handler entries get a __k_perform param; body is CPS-transformed.
-    // `resume(a)` in tail position emits inline as `__k_handle(__k_perform(...))`
-    // — there is no local `resume` binding any more, just direct
-    // continuation invocation.
+    // `resume(a)` in tail position emits inline as `__k_perform(...)` —
+    // there is no local `resume` binding any more, just direct continuation
+    // invocation. `__k_handle` takes the value of an aborting entry.
     assert!(
         js.contains("__k_perform"),
         "handler should reference __k_perform: {js}"
@@ -287,7 +322,7 @@ fn ts_backend_mixed_resume_entries() {
         "cap E { fn op1(): A; fn op2(x: A): B } fn f(a: A, b: B): A / {} { handle E with bundle { fn op1() { resume(a) }; fn op2(x) { b } } in E.op1 }",
     );
     let js = backend::emit(&file, CodegenTarget::JavaScript).expect("js emit");
-    // op1 has tail `resume(a)` → emits `__k_handle(__k_perform(a))`.
+    // op1 has tail `resume(a)` → emits `__k_perform(a)`.
     // op2 aborts (no resume) → its body value flows directly to __k_handle.
     assert!(js.contains("__k_perform"), "tail resume should reach __k_perform: {js}");
     assert!(js.contains("__k_handle"), "abort path should reach __k_handle: {js}");
@@ -482,17 +517,22 @@ fn ts_backend_tail_thunk_kept_for_k_call() {
 
 #[test]
 fn ts_backend_tail_resume_emits_inline_kperform() {
-    // Tail `resume(v)` emits inline as `__k_handle(__k_perform(v))` —
-    // no local `resume` binding, no nested `__trampoline`. The outer
-    // trampoline unwinds the returned thunk iteratively, so nested
-    // handler chains stay stack-safe.
+    // Tail `resume(v)` emits inline as `__k_perform(v)` — no local
+    // `resume` binding, no nested `__trampoline`, and no `__k_handle`: the
+    // resumed computation already continues into the `handle`'s own
+    // continuation. The outer trampoline unwinds the returned thunk
+    // iteratively, so nested handler chains stay stack-safe.
     let file = lower_typed(
         "cap E { fn op(): A } fn f(a: A): A / {} { handle E with bundle { fn op() { resume(a) } } in E.op }",
     );
     let js = backend::emit(&file, CodegenTarget::JavaScript).expect("js emit");
     assert!(
-        js.contains("__k_handle(__k_perform("),
-        "tail resume should emit `__k_handle(__k_perform(...))`: {js}"
+        js.contains("return __k_perform("),
+        "tail resume should emit `__k_perform(...)`: {js}"
+    );
+    assert!(
+        !js.contains("__k_handle(__k_perform("),
+        "tail resume must not feed the resumed computation to __k_handle: {js}"
     );
     assert!(
         !js.contains("const resume ="),
@@ -505,6 +545,28 @@ fn ts_backend_tail_resume_emits_inline_kperform() {
     );
 }
 
+#[test]
+fn ts_backend_tail_resume_in_match_arm_stays_tail() {
+    // A `match` at the end of an op hands `__k_handle` to its arms as is,
+    // so a `resume` in an arm is still a tail resume rather than running
+    // the rest of the program under a nested trampoline.
+    let file = lower_typed(
+        "data Bool { true, false } cap E { fn op(b: Bool): A } \
+         fn f(a: A, b: Bool): A / {} { \
+           handle E with bundle { fn op(b) { match b { Bool.true => resume(a), Bool.false => a } } } in E.op(b) \
+         }",
+    );
+    let js = backend::emit(&file, CodegenTarget::JavaScript).expect("js emit");
+    assert!(
+        js.contains("return __k_perform("),
+        "resume in a tail match arm should emit `__k_perform(...)`: {js}"
+    );
+    assert!(
+        !js.contains("__trampoline(__k_perform("),
+        "resume in a tail match arm must not invoke a nested __trampoline: {js}"
+    );
+}
+
 #[test]
 fn ts_backend_non_tail_resume_drives_synchronously() {
     // Non-tail `resume(v)` (used in let-value or compound expression
@@ -621,3 +683,27 @@ fn ts_backend_emits_named_cap_impl() {
         "named impl should not have mangled name: {js}"
     );
 }
+
+#[test]
+fn ts_backend_handle_in_pure_fn_binds_empty_caps() {
+    let file = lower_typed(
+        "cap E { fn op(): A } fn f(a: A): A / {} { handle E with bundle { fn op() { a } } in E.op }",
+    );
+    let js = backend::emit(&file, CodegenTarget::JavaScript).expect("js emit");
+    // `f` takes no `__caps`, so the handler bundle must not extend one.
+    assert!(!js.contains(", __caps, {"), "free __caps in pure fn: {js}");
+    assert!(js.contains("Object.assign("), "{js}");
+}
+
+#[test]
+fn ts_backend_types_bundle_values_as_handler_factories() {
+    let file = lower_typed(
+        "cap E { fn op(): A } fn f(h: Bundle[E]): A { handle E with h in E.op }",
+    );
+    let ts = backend::emit(&file, CodegenTarget::TypeScript).expect("ts emit");
+    assert!(
+        ts.contains("export function f(h: ((__k_handle: __Kont) => __Caps[string])): A"),
+        "{ts}"
+    );
+    assert!(ts.contains("type __Kont<"), "{ts}");
+}
@@ -1,6 +1,6 @@
 use lumo_compiler::{
-    backend::{self, CodegenTarget},
-    query::QueryEngine,
+    backend::{self, BackendKind, CodegenTarget},
+    query::{CacheStats, DiskCache, QueryEngine},
 };
 
 // ---------------------------------------------------------------------------
@@ -14,6 +14,9 @@ const STRING_SRC: &str = include_str!("../../../packages/libcore/src/string.lumo
 const STRING_JS_SRC: &str = include_str!("../../../packages/libcore/src#js/string.lumo");
 const NUMBER_SRC: &str = include_str!("../../../packages/libcore/src/number.lumo");
 const NUMBER_JS_SRC: &str = include_str!("../../../packages/libcore/src#js/number.lumo");
+const FMT_SRC: &str = include_str!("../../../packages/libcore/src/fmt.lumo");
+const OPTION_SRC: &str = include_str!("../../../packages/libcore/src/option.lumo");
+const HASH_SRC: &str = include_str!("../../../packages/libcore/src/hash.lumo");
 
 // ---------------------------------------------------------------------------
 // libstd sources (common + JS platform)
@@ -21,6 +24,57 @@ const NUMBER_JS_SRC: &str = include_str!("../../../packages/libcore/src#js/numbe
 const IO_SRC: &str = include_str!("../../../packages/libstd/src/io.lumo");
 const IO_JS_SRC: &str = include_str!("../../../packages/libstd/src#js/io.lumo");
 const LIST_SRC: &str = include_str!("../../../packages/libstd/src/list.lumo");
+const ASYNC_SRC: &str = include_str!("../../../packages/libstd/src/async.lumo");
+const ASYNC_JS_SRC: &str = include_str!("../../../packages/libstd/src#js/async.lumo");
+const ASYNC_NODE_SRC: &str = include_str!("../../../packages/libstd/src#js.node/async.lumo");
+const ARRAY_SRC: &str = include_str!("../../../packages/libstd/src/array.lumo");
+const ARRAY_JS_SRC: &str = include_str!("../../../packages/libstd/src#js/array.lumo");
+const MAP_SRC: &str = include_str!("../../../packages/libstd/src/map.lumo");
+const MAP_JS_SRC: &str = include_str!("../../../packages/libstd/src#js/map.lumo");
+const ITER_SRC: &str = include_str!("../../../packages/libstd/src/iter.lumo");
+const STATE_SRC: &str = include_str!("../../../packages/libstd/src/state.lumo");
+
+const PRELUDE_RS_SRC: &str = include_str!("../../../packages/libcore/src#rs/prelude.lumo");
+const OPS_RS_SRC: &str = include_str!("../../../packages/libcore/src#rs/ops.lumo");
+const NUMBER_RS_SRC: &str = include_str!("../../../packages/libcore/src#rs/number.lumo");
+const IO_RS_SRC: &str = include_str!("../../../packages/libstd/src#rs/io.lumo");
+const ARRAY_RS_SRC: &str = include_str!("../../../packages/libstd/src#rs/array.lumo");
+
+/// The modules the Rust tests below need, with their `src#rs` overlays.
+fn rust_stdlib_resolver(path: &[String]) -> Option<(String, String)> {
+    let (file, src) = match path {
+        [pkg, module] if pkg == "libcore" => match module.as_str() {
+            "prelude" => ("libcore/prelude.lumo", format!("{PRELUDE_SRC}\n{PRELUDE_RS_SRC}")),
+            "cmp" => ("libcore/cmp.lumo", CMP_SRC.to_owned()),
+            "ops" => ("libcore/ops.lumo", format!("{OPS_SRC}\n{OPS_RS_SRC}")),
+            "number" => ("libcore/number.lumo", format!("{NUMBER_SRC}\n{NUMBER_RS_SRC}")),
+            "option" => ("libcore/option.lumo", OPTION_SRC.to_owned()),
+            _ => return None,
+        },
+        [pkg, module] if pkg == "libstd" => match module.as_str() {
+            "io" => ("libstd/io.lumo", format!("{IO_SRC}\n{IO_RS_SRC}")),
+            "array" => ("libstd/array.lumo", format!("{ARRAY_SRC}\n{ARRAY_RS_SRC}")),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((file.to_owned(), src))
+}
+
+/// Compile `src` to Rust the way `lbs build --target rust` does under the
+/// dev profile, which turns LTO off.
+fn compile_rust_dev(src: &str) -> String {
+    let mut q = QueryEngine::new();
+    q.set_lto(false);
+    q.set_backend(BackendKind::Rust);
+    q.set_file("main.lumo", src);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], rust_stdlib_resolver)
+        .expect("compilation should succeed");
+    let errors = q.typecheck(&lir);
+    assert!(errors.is_empty(), "unexpected type errors: {errors:?}");
+    backend::emit(&lir, CodegenTarget::Rust).expect("codegen should succeed")
+}
 
 fn stdlib_resolver(path: &[String]) -> Option<(String, String)> {
     match path {
@@ -31,6 +85,9 @@ fn stdlib_resolver(path: &[String]) -> Option<(String, String)> {
                 "ops" => ("ops.lumo", format!("{OPS_SRC}\n{OPS_JS_SRC}")),
                 "string" => ("string.lumo", format!("{STRING_SRC}\n{STRING_JS_SRC}")),
                 "number" => ("number.lumo", format!("{NUMBER_SRC}\n{NUMBER_JS_SRC}")),
+                "fmt" => ("fmt.lumo", FMT_SRC.to_owned()),
+                "option" => ("option.lumo", OPTION_SRC.to_owned()),
+                "hash" => ("hash.lumo", HASH_SRC.to_owned()),
                 _ => return None,
             };
             Some((format!("libcore/{file}"), src))
@@ -39,6 +96,14 @@ fn stdlib_resolver(path: &[String]) -> Option<(String, String)> {
             let (file, src) = match module.as_str() {
                 "io" => ("io.lumo", format!("{IO_SRC}\n{IO_JS_SRC}")),
                 "list" => ("list.lumo", LIST_SRC.to_owned()),
+                "array" => ("array.lumo", format!("{ARRAY_SRC}\n{ARRAY_JS_SRC}")),
+                "map" => ("map.lumo", format!("{MAP_SRC}\n{MAP_JS_SRC}")),
+                "iter" => ("iter.lumo", ITER_SRC.to_owned()),
+                "state" => ("state.lumo", STATE_SRC.to_owned()),
+                "async" => (
+                    "async.lumo",
+                    format!("{ASYNC_SRC}\n{ASYNC_JS_SRC}\n{ASYNC_NODE_SRC}"),
+                ),
                 _ => return None,
             };
             Some((format!("libstd/{file}"), src))
@@ -76,6 +141,43 @@ fn main() { IO.println("Hello, World!") }"#,
     );
 }
 
+#[test]
+fn hello_world_compiles_the_same_from_disk_cache() {
+    let dir = std::env::temp_dir().join("lumo_test_disk_cache_hello");
+    let _ = std::fs::remove_dir_all(&dir);
+    let src = r#"use libstd.io.{IO};
+
+fn main() { IO.println("Hello, World!") }"#;
+
+    let compile = || {
+        let mut q = QueryEngine::new();
+        q.set_disk_cache(Some(DiskCache::new(&dir)));
+        q.set_file("main.lumo", src);
+        let lir = q
+            .compile_with_deps(&["main.lumo"], stdlib_resolver)
+            .expect("compilation should succeed");
+        let errors = q.typecheck(&lir);
+        assert!(errors.is_empty(), "unexpected type errors: {errors:?}");
+        let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+        (lir, js, q.stats())
+    };
+
+    let (cold_lir, cold_js, cold) = compile();
+    assert_eq!(cold.module_cache, CacheStats { hits: 0, misses: 1 });
+    assert_eq!(cold.typecheck_cache, CacheStats { hits: 0, misses: 1 });
+
+    let (warm_lir, warm_js, warm) = compile();
+    assert_eq!(warm_lir, cold_lir);
+    assert_eq!(warm_js, cold_js);
+    assert_eq!(warm.parse_computes, 0);
+    assert_eq!(warm.hir_cache.hits, cold.hir_cache.misses);
+    assert_eq!(warm.hir_cache.misses, 0);
+    assert_eq!(warm.module_cache, CacheStats { hits: 1, misses: 0 });
+    assert_eq!(warm.typecheck_cache, CacheStats { hits: 1, misses: 0 });
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[test]
 #[ignore] // requires Node.js
 fn hello_world_runs_on_node() {
@@ -110,6 +212,149 @@ fn main() { IO.println("Hello, World!") }"#,
     );
 }
 
+const TWO_LINES_SRC: &str = r#"use libstd.io.{IO};
+
+fn main() = { IO.println("a"); IO.println("b") }"#;
+
+#[test]
+fn dev_profile_rust_output_resolves_performs() {
+    let rs = compile_rust_dev(TWO_LINES_SRC);
+    assert!(!rs.contains("todo!"), "{rs}");
+    assert!(rs.contains("__println("), "{rs}");
+}
+
+/// Build `rs` as a cargo project and run it.
+fn cargo_run(name: &str, rs: &str) -> std::process::Output {
+    let dir = std::env::temp_dir().join(format!("lumo_test_rust_{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("src/main.rs"), rs).unwrap();
+
+    let output = std::process::Command::new("cargo")
+        .args(["run", "--quiet"])
+        .current_dir(&dir)
+        .output()
+        .expect("failed to execute cargo");
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(
+        output.status.success(),
+        "cargo run should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+#[ignore] // requires cargo
+fn dev_profile_rust_output_runs() {
+    let output = cargo_run("dev_hello", &compile_rust_dev(TWO_LINES_SRC));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a\nb\n");
+}
+
+const MATCH_ON_INSTANCE_SRC: &str = r#"use libcore.prelude.{Number};
+
+data Wrap[A] { .wrap(A) }
+
+fn unwrap_n(w: Wrap[Number]): Number = match w { .wrap(m) => m }
+
+fn main(): Number = unwrap_n(Wrap.wrap(2))"#;
+
+#[test]
+fn rust_output_matches_on_specialized_data() {
+    // Rust output is monomorphized before it is typechecked, so the match
+    // sees the `Wrap_ANumber_Z` instance and its qualified patterns.
+    let rs = compile_rust_dev(MATCH_ON_INSTANCE_SRC);
+    assert!(rs.contains("Wrap_ANumber_Z::Wrap(m) => m"), "{rs}");
+}
+
+#[test]
+#[ignore] // requires cargo
+fn rust_output_matches_on_specialized_data_runs() {
+    cargo_run("match_instance", &compile_rust_dev(MATCH_ON_INSTANCE_SRC));
+}
+
+const FOR_OVER_INSTANCE_SRC: &str = r#"use libcore.prelude.{Number};
+
+data List[A] { .nil, .cons(A, List[A]) }
+
+fn walk(xs: List[Number]): Number = {
+  for x in xs { x };
+  2
+}
+
+fn main(): Number = walk(List.cons(1, List.nil))"#;
+
+#[test]
+fn rust_output_loops_over_specialized_list() {
+    // After mono the loop steps through `Step_AList_ANumber_Z_Z`, not `Step[..]`.
+    let rs = compile_rust_dev(FOR_OVER_INSTANCE_SRC);
+    assert!(rs.contains("Step_AList_ANumber_Z_Z::Next(__rest1)"), "{rs}");
+}
+
+#[test]
+#[ignore] // requires cargo
+fn rust_output_loops_over_specialized_list_runs() {
+    cargo_run("for_instance", &compile_rust_dev(FOR_OVER_INSTANCE_SRC));
+}
+
+const ARRAY_BOUNDS_SRC: &str = r#"use libcore.prelude.{Number};
+use libcore.option.{Option};
+use libstd.io.{IO};
+use libstd.array.{Array, ArrayOps};
+
+fn say(o: Option[Number]) = match o { .some(_) => IO.println("some"), .none => IO.println("none") }
+
+fn main() = {
+  let xs = ArrayOps.new();
+  let _a = xs.push(1);
+  let _b = xs.push(2);
+  say(xs.get(0 - 1));
+  say(xs.get(2));
+  say(xs.get(0.5));
+  say(xs.set(0 - 1, 9));
+  say(xs.set(2, 9));
+  say(xs.set(1, 3));
+  say(xs.pop());
+  say(xs.pop());
+  say(xs.pop())
+}"#;
+
+/// Negative, fractional and past-the-end indices and popping an empty
+/// array are all `none`, and `set` out of range changes nothing.
+const ARRAY_BOUNDS_OUT: &str = "none\nnone\nnone\nnone\nnone\nsome\nsome\nsome\nnone\n";
+
+#[test]
+#[ignore] // requires Node.js
+fn array_bounds_on_node() {
+    let mut q = QueryEngine::new();
+    q.set_file("main.lumo", ARRAY_BOUNDS_SRC);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+    let output = std::process::Command::new("node")
+        .arg("-e")
+        .arg(format!("{js}\nmain();\n"))
+        .output()
+        .expect("failed to execute node");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), ARRAY_BOUNDS_OUT);
+}
+
+/// The Rust output of the same program can't run yet: the libcore default
+/// impls it performs through are still `todo!` there. Its index check is
+/// the one `Object.hasOwn` makes on JS.
+#[test]
+fn array_bounds_rust_output_checks_indices() {
+    let rs = compile_rust_dev(ARRAY_BOUNDS_SRC);
+    let check = "if idx >= 0.0 && idx.fract() == 0.0 && (idx as usize) < xs.borrow().len()";
+    assert!(rs.contains(check), "{rs}");
+}
+
 #[test]
 fn stdlib_string_ops_compile_to_js() {
     let mut q = QueryEngine::new();
@@ -232,6 +477,150 @@ fn sum(): Number = 1.add(2)
     );
 }
 
+#[test]
+fn string_interpolation_concats_displayed_values() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String, Number};
+use libcore.fmt.{Display};
+use libcore.string.{StrOps};
+use libcore.number.{NumOps};
+
+fn greet(name: String, n: Number): String = "hi ${name}\u{21} #${n}"
+"#,
+    );
+
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+
+    assert!(
+        js.contains("__impl_String_Display") && js.contains("__impl_Number_Display"),
+        "JS should show each value through its Display impl, got:\n{js}"
+    );
+    assert!(
+        js.contains("\"hi \"") && js.contains("\"! #\""),
+        "JS should keep the decoded text pieces, got:\n{js}"
+    );
+}
+
+#[test]
+fn int_literals_emit_bigints_and_convert_explicitly() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{Int, Float};
+use libcore.ops.{Add, Mul};
+use libcore.number.{IntOps, FloatOps};
+
+fn scale(id: Int, by: Float): Float = (id * 0xff_i + 1_000i).to_float() * by
+"#,
+    );
+
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+
+    assert!(
+        js.contains("255n") && js.contains("1000n"),
+        "JS should emit Int literals as decimal bigints, got:\n{js}"
+    );
+    assert!(
+        js.contains("__impl_Int_Mul") && js.contains("__impl_Float_Mul"),
+        "JS should multiply each type through its own impl, got:\n{js}"
+    );
+    assert!(
+        js.contains("globalThis.Number("),
+        "JS should convert Int to Float explicitly, got:\n{js}"
+    );
+}
+
+#[test]
+fn map_methods_specialize_per_key_type() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String, Number, Int};
+use libcore.option.{Option};
+use libcore.number.{NumOps};
+use libstd.io.{IO};
+use libstd.map.{Map, MapOps};
+
+fn count(m: Map[String, Number], word: String): Number =
+  match m.get(word) { .some(n) => n, .none => 0 }
+
+fn main() = {
+  let m = MapOps.new();
+  let _a = m.insert("lumo", 1);
+  let ids = MapOps.new();
+  let _b = ids.insert(7i, 2);
+  IO.println(StrOps.num_to_string(count(m, "lumo")))
+}
+"#,
+    );
+
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+
+    assert!(
+        js.contains("Map_Dget_AString_CNumber_Z") && js.contains("Map_Dinsert_AInt_CNumber_Z"),
+        "JS should specialize Map methods per key type, got:\n{js}"
+    );
+    assert!(
+        js.contains("__impl_String_Hash") && js.contains("__impl_Int_Hash"),
+        "JS should hash each key type through its own impl, got:\n{js}"
+    );
+    assert!(
+        js.contains("new globalThis.Map()"),
+        "JS should back Map with a global Map, got:\n{js}"
+    );
+}
+
+#[test]
+fn derived_impls_instantiate_per_type_argument() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String, Number, Bool};
+use libcore.cmp.{PartialEq};
+use libcore.fmt.{Display, Show};
+use libstd.io.{IO};
+
+#[derive(PartialEq, Show)]
+data Box[A] { .full(A), .empty }
+
+fn main() = {
+  IO.println("${Box.full(1) == Box.empty} ${Box.full("a") == Box.full("a")}");
+  IO.println(Show.debug(Box.full("a")))
+}
+"#,
+    );
+
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+
+    assert!(
+        js.contains("__impl_Box_ANumber_Z_PartialEq")
+            && js.contains("__impl_Box_AString_Z_PartialEq"),
+        "JS should instantiate the derived impl per type argument, got:\n{js}"
+    );
+    assert!(
+        js.contains("____derive__Box__debug_AString_Z"),
+        "JS should instantiate the derived helper for `Show`, got:\n{js}"
+    );
+    assert!(
+        !js.contains("__impl_Box["),
+        "instance names should be valid identifiers, got:\n{js}"
+    );
+}
+
 #[test]
 fn main_with_default_impl_caps_emits_cps_main_and_wrapper() {
     let mut q = QueryEngine::new();
@@ -302,6 +691,31 @@ fn main() {
     );
 }
 
+#[test]
+fn iterator_of_the_wrong_element_type_is_compile_error() {
+    // `for_each` is inlined before typecheck, so the yields of `range` type
+    // its handler: `IO.println` on a `Number` item must not build.
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libstd.io.{IO};
+use libstd.iter.{range, for_each};
+
+fn main() {
+  for_each(thunk range(0, 3), fn(s) { IO.println(s) })
+}
+"#,
+    );
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let errors = q.typecheck(&lir);
+    assert!(
+        errors.iter().any(|e| e.message.contains("expected String, got Number")),
+        "error should name the element types, got: {errors:?}"
+    );
+}
+
 #[test]
 fn main_requiring_undefaulted_cap_is_compile_error() {
     // Cap `MyCap` has no default impl (no `impl MyCap { ... }`),
@@ -413,8 +827,10 @@ fn tail_resume_compiles() {
     // A handler whose tail expression is `resume(v)` compiles cleanly and
     // references `__k_perform` / `__k_handle` per the algebraic-effects
     // calling convention. `resume(v)` is a tail-call back to the perform
-    // site — the outer trampoline drives its thunk.
+    // site — the outer trampoline drives its thunk. LTO would lower the
+    // handle away, so it is off here.
     let mut q = QueryEngine::new();
+    q.set_lto(false);
     q.set_file(
         "main.lumo",
         r#"use libcore.prelude.{Number};
@@ -489,8 +905,10 @@ fn arithmetic_operator_desugars_to_cap_call() {
     // providing Add, the handle expression must evaluate the handler body with
     // the perform's continuation. Under algebraic-effect semantics (abort by
     // default), the handler must *explicitly* `resume(a)` to thread `a` back
-    // into the perform site — otherwise `sum()` never completes.
+    // into the perform site — otherwise `sum()` never completes. LTO would
+    // inline the handler into `sum`, so it is off here.
     let mut q = QueryEngine::new();
+    q.set_lto(false);
     q.set_file(
         "main.lumo",
         r#"
@@ -529,3 +947,147 @@ fn main() {
     );
 }
 
+
+const ASYNC_TIMERS_MAIN: &str = r#"use libcore.prelude.{String};
+use libstd.io.{IO};
+use libstd.async.{Async, sleep};
+
+fn main() {
+  let _a = IO.println("start");
+  let first = Async.await(sleep(30, "first timer"));
+  let _b = IO.println(first);
+  let second = Async.await(sleep(10, "second timer"));
+  IO.println(second)
+}
+"#;
+
+#[test]
+fn async_await_hands_resume_to_promise_then() {
+    // The default `Async` bundle passes `resume` to `Promise.prototype.then`
+    // as a value, so the backend must bind it to a function that drives the
+    // rest of the computation rather than emit it inline.
+    let mut q = QueryEngine::new();
+    q.set_file("main.lumo", ASYNC_TIMERS_MAIN);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+    assert!(
+        js.contains("p.then(k, fail)"),
+        "await should chain on the promise, got:\n{js}"
+    );
+    assert!(
+        js.contains("}, __await_rejected)"),
+        "a rejection should be handled by `__await_rejected`, got:\n{js}"
+    );
+    assert!(
+        js.contains("__trampoline(__k_perform(__v))"),
+        "escaping resume should re-enter the trampoline, got:\n{js}"
+    );
+    assert!(
+        js.contains("Async_Async: Async(__identity)"),
+        "main should install the default Async bundle, got:\n{js}"
+    );
+}
+
+#[test]
+#[ignore] // requires Node.js
+fn async_await_suspends_until_timers_settle_on_node() {
+    // `main()` returns as soon as the first await suspends, handing back a
+    // Promise; the continuation then runs once per settled timer, in await
+    // order even though the second timer is shorter.
+    let mut q = QueryEngine::new();
+    q.set_file("main.lumo", ASYNC_TIMERS_MAIN);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+    let js_with_entry = format!(
+        "{js}\nconst r = main();\nconsole.log(r instanceof Promise ? \"suspended\" : \"sync\");\nr.then(() => console.log(\"done\"));\n"
+    );
+
+    let output = std::process::Command::new("node")
+        .arg("--input-type=module")
+        .arg("-e")
+        .arg(&js_with_entry)
+        .output()
+        .expect("failed to execute node");
+    assert!(
+        output.status.success(),
+        "node should exit successfully, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        ["start", "suspended", "first timer", "second timer", "done"],
+        "awaits should resume in order after main returns; got:\n{stdout}"
+    );
+}
+
+const ASYNC_REJECTED_MAIN: &str = r#"use libcore.prelude.{String, Number};
+use libcore.fmt.{Display};
+use libstd.io.{IO};
+use libstd.async.{Async, Promise, sleep};
+
+#[extern(name = "globalThis.Promise.reject()")] extern fn reject(reason: String): Promise[Number];
+
+fn main() {
+  let n = Async.await(sleep(10, 41));
+  IO.println("${n + 1}");
+  let _m = Async.await(reject("no luck"));
+  IO.println("unreachable")
+}
+"#;
+
+#[test]
+#[ignore] // requires Node.js
+fn async_await_rejection_fails_the_program_on_node() {
+    // `await` is generic over the promise's value, and a rejected promise
+    // prints its reason and exits 1 instead of resuming.
+    let mut q = QueryEngine::new();
+    q.set_file("main.lumo", ASYNC_REJECTED_MAIN);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+    let output = std::process::Command::new("node")
+        .arg("--input-type=module")
+        .arg("-e")
+        .arg(format!("{js}\nmain();\n"))
+        .output()
+        .expect("failed to execute node");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "no luck\n");
+}
+
+#[test]
+#[ignore] // requires Node.js
+fn async_await_rejection_rejects_main_without_process() {
+    // Hosts without Node's `process` get the rejection back from `main()`
+    // rather than a call to `process.exit`.
+    let mut q = QueryEngine::new();
+    q.set_file("main.lumo", ASYNC_REJECTED_MAIN);
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compilation should succeed");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("codegen should succeed");
+    let output = std::process::Command::new("node")
+        .arg("--input-type=module")
+        .arg("-e")
+        .arg(format!(
+            "globalThis.process = undefined;\n{js}\nmain().catch((e) => console.log(`rejected: ${{e}}`));\n"
+        ))
+        .output()
+        .expect("failed to execute node");
+    assert!(
+        output.status.success(),
+        "node should exit successfully, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "42\nrejected: no luck\n"
+    );
+}
@@ -33,6 +33,30 @@ fn lexer_golden_cases() {
             ],
             errors: &[],
         },
+        Case {
+            name: "kw_loops",
+            input: "while for loop break continue return",
+            tokens: &[
+                "kw(while)@0..5",
+                "kw(for)@6..9",
+                "kw(loop)@10..14",
+                "kw(break)@15..20",
+                "kw(continue)@21..29",
+                "kw(return)@30..36",
+            ],
+            errors: &[],
+        },
+        Case {
+            name: "kw_try_catch",
+            input: "try catch r?",
+            tokens: &[
+                "kw(try)@0..3",
+                "kw(catch)@4..9",
+                "ident(r)@10..11",
+                "sym(?)@11..12",
+            ],
+            errors: &[],
+        },
         Case {
             name: "kw_produce",
             input: "produce a",
@@ -212,6 +236,82 @@ fn lexer_golden_cases() {
             ],
             errors: &[],
         },
+        Case {
+            name: "string_escapes",
+            input: r#""a\n\u{1F600}\$""#,
+            tokens: &[r#"string("a\n\u{1F600}\$")@0..16"#],
+            errors: &[],
+        },
+        Case {
+            name: "string_invalid_escapes",
+            input: r#""\q\u{D800}\u{}""#,
+            tokens: &[r#"string("\q\u{D800}\u{}")@0..16"#],
+            errors: &[
+                "unknown escape sequence `\\q`@1..3",
+                "`\\u{D800}` is not a Unicode scalar value@3..11",
+                "`\\u{...}` takes one to six hex digits@11..15",
+            ],
+        },
+        Case {
+            name: "string_unterminated",
+            input: "x \"ab",
+            tokens: &["ident(x)@0..1", "string(\"ab)@2..5"],
+            errors: &["unterminated string literal@2..5"],
+        },
+        Case {
+            name: "string_interpolation_nests",
+            input: r#""a ${f("}")} b" c"#,
+            tokens: &[r#"string("a ${f("}")} b")@0..15"#, "ident(c)@16..17"],
+            errors: &[],
+        },
+        Case {
+            name: "raw_string",
+            input: r###"r#"a "\q" ${b}"# r"#""###,
+            tokens: &[r###"string(r#"a "\q" ${b}"#)@0..16"###, r##"string(r"#")@17..21"##],
+            errors: &[],
+        },
+        Case {
+            name: "multi_line_string",
+            input: "\"\"\"\n  a \"b\"\n  \"\"\"",
+            tokens: &["string(\"\"\"\n  a \"b\"\n  \"\"\")@0..17"],
+            errors: &[],
+        },
+        Case {
+            name: "multi_line_string_under_indented",
+            input: "\"\"\"\n a\n  \"\"\"",
+            tokens: &["string(\"\"\"\n a\n  \"\"\")@0..12"],
+            errors: &["line is indented less than the closing `\"\"\"`@4..4"],
+        },
+        Case {
+            name: "number_suffixes_and_radixes",
+            input: "1_000i 2.5f 0xff_i 0b10 1.to_string",
+            tokens: &[
+                "number(1_000i)@0..6",
+                "number(2.5f)@7..11",
+                "number(0xff_i)@12..18",
+                "number(0b10)@19..23",
+                "number(1)@24..25",
+                "sym(.)@25..26",
+                "ident(to_string)@26..35",
+            ],
+            errors: &[],
+        },
+        Case {
+            name: "number_invalid_literals",
+            input: "1.5i 2px 0b12 0x",
+            tokens: &[
+                "number(1.5i)@0..4",
+                "number(2px)@5..8",
+                "number(0b12)@9..13",
+                "number(0x)@14..16",
+            ],
+            errors: &[
+                "an `Int` literal can't have a fraction@0..4",
+                "unknown number suffix `px` (expected `i` or `f`)@5..8",
+                "invalid digit `2` in binary literal@9..13",
+                "hex literal has no digits@14..16",
+            ],
+        },
     ];
 
     assert!(cases.len() >= 20, "need at least 20 golden cases");
@@ -253,6 +353,14 @@ fn render_token(token: &lumo_compiler::lexer::Token) -> String {
         TokenKind::Keyword(Keyword::Impl) => "kw(impl)".to_owned(),
         TokenKind::Keyword(Keyword::If) => "kw(if)".to_owned(),
         TokenKind::Keyword(Keyword::Else) => "kw(else)".to_owned(),
+        TokenKind::Keyword(Keyword::While) => "kw(while)".to_owned(),
+        TokenKind::Keyword(Keyword::For) => "kw(for)".to_owned(),
+        TokenKind::Keyword(Keyword::Loop) => "kw(loop)".to_owned(),
+        TokenKind::Keyword(Keyword::Break) => "kw(break)".to_owned(),
+        TokenKind::Keyword(Keyword::Continue) => "kw(continue)".to_owned(),
+        TokenKind::Keyword(Keyword::Return) => "kw(return)".to_owned(),
+        TokenKind::Keyword(Keyword::Try) => "kw(try)".to_owned(),
+        TokenKind::Keyword(Keyword::Catch) => "kw(catch)".to_owned(),
         TokenKind::Keyword(Keyword::Lambda) => "kw(lambda)".to_owned(),
         TokenKind::Keyword(Keyword::Roll) => "kw(roll)".to_owned(),
         TokenKind::Keyword(Keyword::Unroll) => "kw(unroll)".to_owned(),
@@ -288,6 +396,7 @@ fn render_token(token: &lumo_compiler::lexer::Token) -> String {
         TokenKind::Symbol(Symbol::BangEq) => "sym(!=)".to_owned(),
         TokenKind::Symbol(Symbol::AmpAmp) => "sym(&&)".to_owned(),
         TokenKind::Symbol(Symbol::PipePipe) => "sym(||)".to_owned(),
+        TokenKind::Symbol(Symbol::Question) => "sym(?)".to_owned(),
         TokenKind::NumberLit(s) => format!("number({s})"),
     };
     format!("{head}@{}..{}", token.span.start, token.span.end)
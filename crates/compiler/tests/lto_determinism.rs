@@ -1,5 +1,5 @@
 use lumo_compiler::{
-    backend::{self, CodegenTarget},
+    backend::{self, BackendKind, CodegenTarget},
     query::QueryEngine,
 };
 
@@ -62,3 +62,40 @@ fn main(): Number = helper_a(helper_b(5))
         );
     }
 }
+
+#[test]
+fn monomorphized_rust_output_is_deterministic() {
+    // Instances are named from their type arguments and placed after their
+    // original sorted by name, so emission order never depends on hashing.
+    let src = r#"
+data List[A] { .nil, .cons(A, List[A]) }
+data Cell[A] { .wrap(A) }
+fn first[A](xs: List[A], d: A): A = match xs { .nil => d, .cons(h, _) => h }
+fn main(): Number = {
+  let a = first(List.cons("a", List.nil), "z");
+  let b = first(List.cons(Cell.wrap(1), List.nil), Cell.wrap(0));
+  first(List.cons(1, List.nil), 0)
+}
+"#;
+    let emit = || {
+        let mut q = QueryEngine::new();
+        q.set_backend(BackendKind::Rust);
+        q.set_file("main.lumo", src.to_owned());
+        let lir = q.lower_module(&["main.lumo"]).expect("lower_module failed");
+        backend::emit(&lir, CodegenTarget::Rust).expect("rust emit")
+    };
+    let first = emit();
+    assert!(first.contains("fn first_ACell_ANumber_Z_Z("), "{first}");
+    for i in 0..10 {
+        let next = emit();
+        assert_eq!(
+            first,
+            next,
+            "monomorphized output differs on run {}:\n--- first ---\n{}\n--- run {} ---\n{}",
+            i + 1,
+            first,
+            i + 1,
+            next
+        );
+    }
+}
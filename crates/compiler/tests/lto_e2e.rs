@@ -14,6 +14,7 @@ const STRING_SRC: &str = include_str!("../../../packages/libcore/src/string.lumo
 const STRING_JS_SRC: &str = include_str!("../../../packages/libcore/src#js/string.lumo");
 const NUMBER_SRC: &str = include_str!("../../../packages/libcore/src/number.lumo");
 const NUMBER_JS_SRC: &str = include_str!("../../../packages/libcore/src#js/number.lumo");
+const FMT_SRC: &str = include_str!("../../../packages/libcore/src/fmt.lumo");
 
 // ---------------------------------------------------------------------------
 // libstd sources (common + JS platform)
@@ -31,6 +32,7 @@ fn stdlib_resolver(path: &[String]) -> Option<(String, String)> {
                 "ops" => ("ops.lumo", format!("{OPS_SRC}\n{OPS_JS_SRC}")),
                 "string" => ("string.lumo", format!("{STRING_SRC}\n{STRING_JS_SRC}")),
                 "number" => ("number.lumo", format!("{NUMBER_SRC}\n{NUMBER_JS_SRC}")),
+                "fmt" => ("fmt.lumo", FMT_SRC.to_owned()),
                 _ => return None,
             };
             Some((format!("libcore/{file}"), src))
@@ -47,6 +49,106 @@ fn stdlib_resolver(path: &[String]) -> Option<(String, String)> {
     }
 }
 
+/// With LTO off, `main` keeps performing `Add` through the caps bundle, and
+/// the module cache key tells the two settings apart.
+#[test]
+fn lto_can_be_turned_off() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{Number};
+use libcore.ops.{Add};
+use libcore.number.{NumOps};
+
+fn main(): Number = 1 + 2 + 3
+"#
+        .to_owned(),
+    );
+    let main_cap = |lir: &lumo_compiler::lir::File| {
+        lir.items.iter().find_map(|item| match item {
+            lumo_compiler::lir::Item::Fn(f) if f.name == "main" => Some(f.cap.is_some()),
+            _ => None,
+        })
+    };
+
+    q.set_lto(false);
+    let plain = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compile_with_deps failed");
+    assert!(q.lto_report().is_none());
+    assert_eq!(main_cap(&plain), Some(true));
+
+    q.set_lto(true);
+    let optimized = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compile_with_deps failed");
+    assert!(q.lto_report().is_some());
+    assert_eq!(main_cap(&optimized), Some(false));
+}
+
+/// An impl method that resumes with a bare value — `impl String: Display`'s
+/// `resume(self)` — inlines as `produce self`, so the rewritten `main` still
+/// typechecks.
+#[test]
+fn lto_inlines_resume_of_a_value() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String, Number};
+use libcore.fmt.{Display};
+use libcore.string.{StrOps};
+use libcore.number.{NumOps};
+
+fn main(): String = "${"lu"}${3}"
+"#
+        .to_owned(),
+    );
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compile_with_deps failed");
+    let errors = q.typecheck(&lir);
+    assert!(errors.is_empty(), "type errors after LTO: {errors:?}");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("js emit");
+    assert!(
+        !js.contains("function __main_cps("),
+        "LTO should resolve every Display and StrOps call in main, got:\n{js}"
+    );
+}
+
+/// Once LTO resolves the caps a `for` loop's body performs, the loop no
+/// longer goes through CPS and the backend emits it as a native loop.
+#[test]
+fn lto_emits_resolved_loops_natively() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String, Number};
+use libcore.cmp.{PartialEq};
+use libcore.number.{NumOps};
+use libstd.io.{IO};
+use libstd.list.{List};
+
+fn walk(xs: List[Number]): Unit = {
+  for x in xs {
+    if x == 2 { break };
+    IO.println("item")
+  }
+}
+
+fn main() = walk(List.cons(1, List.cons(2, List.nil)))
+"#
+        .to_owned(),
+    );
+    let lir = q
+        .compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compile_with_deps failed");
+    let errors = q.typecheck(&lir);
+    assert!(errors.is_empty(), "type errors after LTO: {errors:?}");
+    let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("js emit");
+    assert!(js.contains("__lumo_loop("), "expected a native loop, got:\n{js}");
+    assert!(!js.contains("__loop_0"), "loop should not need CPS, got:\n{js}");
+}
+
 /// Smoke test: `1 + 2 + 3` directly in main compiles through real stdlib.
 ///
 /// After the LTO entry-point in-place rewrite fix: when `main` itself (no helper fn)
@@ -155,10 +257,11 @@ fn main(): Number = sum()
         .expect("compile_with_deps failed");
     let js = backend::emit(&lir, CodegenTarget::JavaScript).expect("js emit");
 
-    // Compilation must succeed.
+    // Once LTO resolves every Perform, nothing in `main` needs CPS, so
+    // selective CPS emits it in direct style without a `__main_cps` wrapper.
     assert!(
-        js.contains("function __main_cps("),
-        "JS should contain __main_cps, got:\n{js}"
+        js.contains("function main()") && !js.contains("__main_cps"),
+        "expected a direct-style main, got:\n{js}"
     );
 
     // Heuristic D: `sum` is small + single-caller → inlined, not cloned.
@@ -169,10 +272,10 @@ fn main(): Number = sum()
         "expected `sum` to be inlined (not cloned as sum__lto) by heuristic D, got:\n{js}"
     );
 
-    // Extract `__main_cps`'s body to check for cap bundle elimination.
+    // Extract `main`'s body to check for cap bundle elimination.
     let main_cps_start = js
-        .find("function __main_cps")
-        .expect("__main_cps not found in JS");
+        .find("function main")
+        .expect("main not found in JS");
     // Find the closing `}` of the function body — look for two newlines after `}`.
     let main_cps_end = js[main_cps_start..]
         .find("}\n\n")
@@ -181,17 +284,17 @@ fn main(): Number = sum()
     let main_cps_body = &js[main_cps_start..main_cps_end];
 
     // Core invariant: after LTO inlining sum's body into main,
-    // `__main_cps` must NOT access `__caps.Add_Number` or `__caps.NumOps_NumOps`.
+    // `main` must NOT access `__caps.Add_Number` or `__caps.NumOps_NumOps`.
     assert!(
         !main_cps_body.contains("__caps.Add_Number"),
-        "expected __main_cps body to not dispatch through __caps.Add_Number (LTO should have \
+        "expected main body to not dispatch through __caps.Add_Number (LTO should have \
          inlined sum's resolved body directly), got:\n{}\n\n(full js)\n{}",
         main_cps_body,
         js
     );
     assert!(
         !main_cps_body.contains("__caps.NumOps_NumOps"),
-        "expected __main_cps body to not dispatch through __caps.NumOps_NumOps (LTO should have \
+        "expected main body to not dispatch through __caps.NumOps_NumOps (LTO should have \
          fully resolved the stdlib chain), got:\n{}\n\n(full js)\n{}",
         main_cps_body,
         js
@@ -200,12 +303,46 @@ fn main(): Number = sum()
     // After full LTO inlining + recursive Perform resolution, the body should
     // use direct arithmetic — either `__num_add` or inlined `+` operator.
     // With the let-dedup fix enabling deeper IIFE flattening, the arithmetic may be
-    // fully inlined as `(a + b)` rather than a `__num_add(a, b)` call.
+    // fully inlined as `(a + b)` rather than a `__num_add(a, b)` call, and in
+    // direct style the literal bindings fold away to `((1 + 2) + 3)`.
     assert!(
-        main_cps_body.contains("__num_add") || main_cps_body.contains("(a + b)") || main_cps_body.contains("+ b"),
-        "expected __main_cps body to use direct arithmetic after LTO inlining, \
+        main_cps_body.contains("__num_add")
+            || main_cps_body.contains("(a + b)")
+            || main_cps_body.contains("+ b")
+            || main_cps_body.contains("(1 + 2)"),
+        "expected main body to use direct arithmetic after LTO inlining, \
          got:\n{}\n\n(full js)\n{}",
         main_cps_body,
         js
     );
 }
+
+/// A cap with no default impl is performed through the handler in scope, and
+/// the report says so rather than blaming an indirect call.
+#[test]
+fn lto_report_names_unresolved_performs() {
+    let mut q = QueryEngine::new();
+    q.set_file(
+        "main.lumo",
+        r#"use libcore.prelude.{String};
+
+cap Log { fn log(msg: String) }
+
+fn greet(): Unit / {Log} = Log.log("hi")
+"#
+        .to_owned(),
+    );
+    q.compile_with_deps(&["main.lumo"], stdlib_resolver)
+        .expect("compile_with_deps failed");
+    let report = q.lto_report().expect("LTO is on by default");
+    let text = report.to_text();
+    assert!(text.contains("  greet -> <perform Log>\n"), "{text}");
+    assert!(text.contains("  greet: blocked, performs `Log` with no default impl\n"), "{text}");
+    assert!(
+        report
+            .to_json()
+            .contains("{\"name\":\"greet\",\"status\":\"blocked\",\"reason\":{\"kind\":\"unresolved-perform\",\"target\":\"Log\"}}"),
+        "{}",
+        report.to_json()
+    );
+}
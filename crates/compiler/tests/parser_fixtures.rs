@@ -6,7 +6,7 @@ use lumo_compiler::{
     lexer::lex,
     lir,
     lst::lossless,
-    parser::{parse, parse_lossless, Expr, Item},
+    parser::{parse, parse_lossless, Expr, InterpPart, Item},
     query::QueryEngine,
 };
 
@@ -198,6 +198,17 @@ fn render_expr(expr: &Expr) -> String {
     match expr {
         Expr::Ident { name, .. } => format!("Variable(\"{}\")", name),
         Expr::String { value, .. } => format!("String(\"{}\")", value),
+        Expr::Interp { parts, .. } => format!(
+            "Interp([{}])",
+            parts
+                .iter()
+                .map(|part| match part {
+                    InterpPart::Text(text) => format!("\"{text}\""),
+                    InterpPart::Expr(expr) => render_expr(expr),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
         Expr::Member { object, member, .. } => {
             format!(
                 "Member(object={}, member=\"{}\")",
@@ -212,6 +223,11 @@ fn render_expr(expr: &Expr) -> String {
         ),
         Expr::Thunk { expr, .. } => format!("Thunk({})", render_expr(expr)),
         Expr::Force { expr, .. } => format!("Force({})", render_expr(expr)),
+        Expr::Lambda { params, body, .. } => format!(
+            "Lambda([{}], {})",
+            params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "),
+            render_expr(body)
+        ),
         Expr::Let {
             name, value, ..
         } => format!(
@@ -247,7 +263,7 @@ fn render_expr(expr: &Expr) -> String {
                     let params = e
                         .params
                         .iter()
-                        .map(|p| p.name.clone())
+                        .map(|(name, _)| name.clone())
                         .collect::<Vec<_>>()
                         .join(", ");
                     format!("{}({}) := {}", e.name, params, render_expr(&e.body))
@@ -300,6 +316,26 @@ fn render_expr(expr: &Expr) -> String {
                 None => format!("If(cond={}, then={})", render_expr(condition), render_expr(then_body)),
             }
         }
+        Expr::While { condition, body, .. } => {
+            format!("While(cond={}, body={})", render_expr(condition), render_expr(body))
+        }
+        Expr::For { name, iterable, body, .. } => format!(
+            "For(name=\"{}\", iterable={}, body={})",
+            name,
+            render_expr(iterable),
+            render_expr(body)
+        ),
+        Expr::Loop { body, .. } => format!("Loop({})", render_expr(body)),
+        Expr::Break { .. } => "Break".to_owned(),
+        Expr::Continue { .. } => "Continue".to_owned(),
+        Expr::Return { value, .. } => match value {
+            Some(v) => format!("Return({})", render_expr(v)),
+            None => "Return".to_owned(),
+        },
+        Expr::Try { body, name, handler, .. } => {
+            format!("Try({}, {name}, {})", render_expr(body), render_expr(handler))
+        }
+        Expr::Propagate { expr, .. } => format!("Propagate({})", render_expr(expr)),
         Expr::Error { .. } => "Error".to_owned(),
     }
 }
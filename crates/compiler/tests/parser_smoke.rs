@@ -1,6 +1,6 @@
 use lumo_compiler::{
     lexer::lex,
-    parser::{parse, BinaryOp, Expr, Item, UnaryOp},
+    parser::{parse, BinaryOp, BlockStmt, Expr, InterpPart, Item, UnaryOp},
 };
 
 #[test]
@@ -218,6 +218,41 @@ fn parses_extern_items_with_attributes() {
     assert_eq!(ext_fn.params[0].ty.repr, "String");
 }
 
+#[test]
+fn parses_generic_extern_items_and_applied_param_types() {
+    let src = "extern type Map[K, V];\n\
+        extern fn map_get[K, V](m: Map[K, V], key: K): V;\n\
+        cap MapOps { fn new[K, V](): Map[K, V], fn len[K, V](m: Map[K, V]): Number }";
+    let lexed = lex(src);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    assert!(parsed.errors.is_empty(), "errors: {:?}", parsed.errors);
+    assert_eq!(parsed.file.items.len(), 3);
+
+    let Item::ExternType(ext_ty) = &parsed.file.items[0] else {
+        panic!("expected extern type item")
+    };
+    let names: Vec<&str> = ext_ty.generics.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, ["K", "V"]);
+
+    let Item::ExternFn(ext_fn) = &parsed.file.items[1] else {
+        panic!("expected extern fn item")
+    };
+    assert_eq!(ext_fn.generics.len(), 2);
+    assert_eq!(ext_fn.params.len(), 2);
+    assert_eq!(ext_fn.params[0].ty.repr, "Map [ K , V ]");
+    assert_eq!(ext_fn.params[1].name, "key");
+
+    let Item::Cap(cap) = &parsed.file.items[2] else {
+        panic!("expected cap item")
+    };
+    assert_eq!(cap.operations.len(), 2);
+    assert_eq!(cap.operations[0].generics.len(), 2);
+    assert_eq!(
+        cap.operations[0].return_type.as_ref().map(|t| t.repr.as_str()),
+        Some("Map [ K , V ]")
+    );
+}
+
 #[test]
 fn parses_extern_fn_without_semicolon_before_next_item() {
     let src = "#[extern(name = \"console.log\")] extern fn console_log(msg: String) fn main(msg: String): Unit { console_log(msg) }";
@@ -614,3 +649,108 @@ fn attribute_accepts_empty_parens() {
     assert!(ext.attrs[0].flags.is_empty());
     assert!(ext.attrs[0].args.is_empty());
 }
+
+#[test]
+fn parses_string_interpolation_and_decodes_literals() {
+    let src = "fn f() { \"a\\t${x.len()}${\"}\"} b\" }\nfn g() { \"\"\"\n    one\n      two\\u{21}\n    \"\"\" }";
+    let lexed = lex(src);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    assert!(parsed.errors.is_empty(), "parse errors: {:?}", parsed.errors);
+
+    let Item::Fn(f) = &parsed.file.items[0] else {
+        panic!("expected fn item")
+    };
+    let Expr::Block { result, .. } = &f.body else {
+        panic!("expected block body")
+    };
+    let Expr::Interp { parts, .. } = result.as_ref() else {
+        panic!("expected interpolated string, got {result:?}")
+    };
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], InterpPart::Text("a\t".to_owned()));
+    assert!(matches!(&parts[1], InterpPart::Expr(Expr::Call { .. })));
+    assert!(
+        matches!(&parts[2], InterpPart::Expr(Expr::String { value, .. }) if value == "}")
+    );
+    assert_eq!(parts[3], InterpPart::Text(" b".to_owned()));
+
+    let Item::Fn(g) = &parsed.file.items[1] else {
+        panic!("expected fn item")
+    };
+    let Expr::Block { result, .. } = &g.body else {
+        panic!("expected block body")
+    };
+    let Expr::String { value, .. } = result.as_ref() else {
+        panic!("expected string, got {result:?}")
+    };
+    assert_eq!(value, "one\n  two!");
+}
+
+#[test]
+fn reports_errors_inside_string_interpolation() {
+    let src = "fn f() { \"${a b} ${\"\\q\"}\" }";
+    let lexed = lex(src);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    let messages: Vec<_> = parsed.errors.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["unexpected tokens in string interpolation", "unknown escape sequence `\\q`"]
+    );
+    // Spans point into the file, not into the interpolated source.
+    assert_eq!(&src[parsed.errors[0].span.start..parsed.errors[0].span.end], "b");
+    assert_eq!(&src[parsed.errors[1].span.start..parsed.errors[1].span.end], "\\q");
+}
+
+#[test]
+fn parses_loops_as_statements() {
+    let src = "fn f(xs: List[Number]) { for x in xs { if x == 0 { continue }; g(x) } while c() { break } loop { return 1 } }";
+    let lexed = lex(src);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    assert!(parsed.errors.is_empty(), "parse errors: {:?}", parsed.errors);
+
+    let Item::Fn(f) = &parsed.file.items[0] else {
+        panic!("expected fn item")
+    };
+    let Expr::Block { stmts, result, .. } = &f.body else {
+        panic!("expected block body")
+    };
+    // A loop needs no `;` to be followed by another statement.
+    assert_eq!(stmts.len(), 2);
+    let BlockStmt::Expr { expr: Expr::For { name, .. }, .. } = &stmts[0] else {
+        panic!("expected for loop, got {:?}", stmts[0])
+    };
+    assert_eq!(name, "x");
+    assert!(matches!(&stmts[1], BlockStmt::Expr { expr: Expr::While { .. }, .. }));
+    let Expr::Loop { body, .. } = result.as_ref() else {
+        panic!("expected loop, got {result:?}")
+    };
+    let Expr::Block { result, .. } = body.as_ref() else {
+        panic!("expected loop body block")
+    };
+    assert!(matches!(result.as_ref(), Expr::Return { value: Some(_), .. }));
+}
+
+#[test]
+fn parses_try_catch_and_propagation() {
+    let src = "fn f(s: String): Number = try { parse(s)? + 1 } catch e: String => 0";
+    let lexed = lex(src);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    assert!(parsed.errors.is_empty(), "parse errors: {:?}", parsed.errors);
+
+    let Item::Fn(f) = &parsed.file.items[0] else {
+        panic!("expected fn item")
+    };
+    let Expr::Try { body, name, error_type, .. } = &f.body else {
+        panic!("expected try, got {:?}", f.body)
+    };
+    assert_eq!(name, "e");
+    assert!(error_type.is_some());
+    let Expr::Block { result, .. } = body.as_ref() else {
+        panic!("expected try body block")
+    };
+    // `?` binds tighter than `+`.
+    let Expr::Binary { left, .. } = result.as_ref() else {
+        panic!("expected binary, got {result:?}")
+    };
+    assert!(matches!(left.as_ref(), Expr::Propagate { .. }));
+}
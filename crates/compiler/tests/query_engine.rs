@@ -1,7 +1,10 @@
+use std::collections::HashMap;
+
 use lumo_compiler::{
     backend::{self, CodegenTarget},
+    hir, lir,
     lst::lossless::{node_text, SyntaxKind},
-    query::QueryEngine,
+    query::{BadImplChoice, CacheStats, DiskCache, QueryEngine},
     typecheck,
 };
 
@@ -75,6 +78,77 @@ fn cache_is_invalidated_when_source_changes() {
     assert!(stats_after.diagnostics_computes > stats_before.diagnostics_computes);
 }
 
+#[test]
+fn disk_cache_recomputes_only_changed_files() {
+    let dir = std::env::temp_dir().join("lumo_test_disk_cache_edit");
+    let _ = std::fs::remove_dir_all(&dir);
+    let types = "data Bool { .true, .false }";
+    let compile = |fns: &str| {
+        let mut q = QueryEngine::new();
+        q.set_disk_cache(Some(DiskCache::new(&dir)));
+        q.set_file("types.lumo", types);
+        q.set_file("fns.lumo", fns);
+        let lir = q
+            .lower_module(&["types.lumo", "fns.lumo"])
+            .expect("module lowers");
+        (lir, q.stats())
+    };
+
+    let not = "fn not(x: Bool): Bool / {} { match x { .true => Bool.false, .false => Bool.true } }";
+    let (first, _) = compile(not);
+    let (again, stats) = compile(not);
+    assert_eq!(again, first);
+    assert_eq!(stats.module_cache, CacheStats { hits: 1, misses: 0 });
+
+    let id = "fn id(x: Bool): Bool / {} { x }";
+    let (edited, stats) = compile(id);
+    assert_ne!(edited, first);
+    assert_eq!(stats.hir_cache, CacheStats { hits: 1, misses: 1 });
+    assert_eq!(stats.module_cache, CacheStats { hits: 0, misses: 1 });
+    assert_eq!(stats.parse_computes, 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cfg_selects_items_per_target_and_package() {
+    let mut q = QueryEngine::new();
+    let src = "data Bool { .true, .false }\n\
+               #[cfg(target = \"js\")] fn pick(): Bool / {} { Bool.true }\n\
+               #[cfg(not(target = \"js\"))] fn pick(): Bool / {} { Bool.false }";
+    q.set_file("main.lumo", src);
+    q.set_file("dep/lib.lumo", "#[cfg(feature = \"extra\")] fn extra() { extra }");
+    let fns = |q: &mut QueryEngine, file: &str| -> Vec<String> {
+        let lir = q.lower(file).expect("lowers");
+        lir.items
+            .iter()
+            .filter_map(|item| match item {
+                lir::Item::Fn(f) => Some(f.name.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(fns(&mut q, "main.lumo").len(), 2);
+
+    let cfg = |target: &str, features: &[&str]| hir::Cfg {
+        target: target.into(),
+        features: features.iter().map(|f| f.to_string()).collect(),
+        declared: None,
+    };
+    q.set_cfg(Some(cfg("js.node", &[])), HashMap::new());
+    let js = q.lower("main.lumo").expect("lowers");
+    q.set_cfg(Some(cfg("rs", &[])), HashMap::new());
+    let rs = q.lower("main.lumo").expect("lowers");
+    assert_eq!(fns(&mut q, "main.lumo").len(), 1);
+    assert_ne!(js, rs);
+
+    assert_eq!(fns(&mut q, "dep/lib.lumo").len(), 0);
+    let packages = HashMap::from([("dep".to_owned(), cfg("rs", &["extra"]))]);
+    q.set_cfg(Some(cfg("rs", &[])), packages);
+    assert_eq!(fns(&mut q, "dep/lib.lumo").len(), 1);
+    assert_eq!(q.lower("main.lumo").expect("lowers"), rs);
+}
+
 #[test]
 fn eof_diagnostics_use_eof_span_instead_of_zero_zero() {
     let mut q = QueryEngine::new();
@@ -166,3 +240,112 @@ fn multi_file_backend_emits_all_items() {
     assert!(output.contains("Bool"), "output should contain Bool type");
     assert!(output.contains("not"), "output should contain not function");
 }
+
+/// Two deps that both `impl Log`, and a root that uses them.
+fn compile_log_deps(q: &mut QueryEngine, root: &str) -> Option<lir::File> {
+    q.set_file("main.lumo", root);
+    q.compile_with_deps(&["main.lumo"], |path: &[String]| {
+        let source = match path[0].as_str() {
+            "log" => "extern type String; cap Log { fn log(msg: String) }",
+            "loud" => "use log.api; impl Log { fn log(msg) = resume(Unit) }",
+            "quiet" => "use log.api; impl Log { fn log(msg) = resume(Unit) }",
+            _ => return None,
+        };
+        Some((format!("{}/{}.lumo", path[0], path[1]), source.to_owned()))
+    })
+}
+
+fn log_impls(file: &lir::File) -> usize {
+    file.items
+        .iter()
+        .filter(|item| matches!(item, lir::Item::Impl(i) if i.target_type.value.display() == "Log"))
+        .count()
+}
+
+#[test]
+fn conflicting_default_impls_across_deps_name_both_packages() {
+    let mut q = QueryEngine::new();
+    let root = "use log.api; use loud.lib; use quiet.lib;";
+    assert!(compile_log_deps(&mut q, root).is_none());
+    let conflicts = q.impl_conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].key, "Log");
+    assert_eq!(conflicts[0].packages, ["loud", "quiet"]);
+    assert!(conflicts[0].to_string().contains("`loud` and `quiet`"), "{}", conflicts[0]);
+
+    q.set_impl_choices(HashMap::from([("Log".to_owned(), "quiet".to_owned())]));
+    let file = compile_log_deps(&mut q, root).expect("the choice settles it");
+    assert!(q.impl_conflicts().is_empty());
+    assert_eq!(log_impls(&file), 1);
+}
+
+#[test]
+fn root_default_impl_overrides_deps() {
+    let mut q = QueryEngine::new();
+    let root = "use log.api; use loud.lib; use quiet.lib; impl Log { fn log(msg) = resume(Unit) }";
+    let file = compile_log_deps(&mut q, root).expect("the root's impl wins");
+    assert_eq!(log_impls(&file), 1);
+}
+
+/// Compile `root` against the `Log` deps with a single `[impls]` choice,
+/// expecting it to be rejected.
+fn bad_log_choice(root: &str, key: &str, package: &str) -> BadImplChoice {
+    let mut q = QueryEngine::new();
+    q.set_impl_choices(HashMap::from([(key.to_owned(), package.to_owned())]));
+    assert!(compile_log_deps(&mut q, root).is_none());
+    assert_eq!(q.bad_impl_choices().len(), 1);
+    q.bad_impl_choices()[0].clone()
+}
+
+#[test]
+fn impl_choice_for_an_unknown_cap_is_an_error() {
+    let root = "use log.api; use loud.lib;";
+    let bad = bad_log_choice(root, "Logg", "loud");
+    assert_eq!(bad, BadImplChoice::UnknownCap { key: "Logg".to_owned() });
+    assert!(bad.to_string().contains("`Logg`, which is not a cap"), "{bad}");
+}
+
+#[test]
+fn impl_choice_of_an_unknown_package_is_an_error() {
+    let root = "use log.api; use loud.lib; use quiet.lib;";
+    let mut q = QueryEngine::new();
+    q.set_impl_choices(HashMap::from([("Log".to_owned(), "silent".to_owned())]));
+    assert!(compile_log_deps(&mut q, root).is_none());
+    assert_eq!(
+        q.bad_impl_choices(),
+        [BadImplChoice::UnknownPackage {
+            key: "Log".to_owned(),
+            package: "silent".to_owned(),
+        }]
+    );
+    // The conflict it failed to settle is still reported.
+    assert_eq!(q.impl_conflicts().len(), 1);
+}
+
+#[test]
+fn impl_choice_without_a_conflict_is_unused() {
+    let unused = BadImplChoice::Unused {
+        key: "Log".to_owned(),
+        package: "loud".to_owned(),
+    };
+    assert_eq!(bad_log_choice("use log.api; use loud.lib;", "Log", "loud"), unused);
+    let root = "use log.api; use loud.lib; use quiet.lib; impl Log { fn log(msg) = resume(Unit) }";
+    let bad = bad_log_choice(root, "Log", "loud");
+    assert_eq!(bad, unused);
+    assert!(bad.to_string().contains("unused `[impls]` choice `Log = \"loud\"`"), "{bad}");
+}
+
+#[test]
+fn bad_string_escapes_are_diagnostics() {
+    let mut q = QueryEngine::new();
+    let src = "fn f(): String { \"bad \\q esc\" }";
+    q.set_file("main.lumo", src);
+    let diagnostics = q.diagnostics("main.lumo").expect("diagnostics result");
+    let escape = src.find("\\q").unwrap();
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.message.contains("escape") && d.start == escape),
+        "{diagnostics:?}"
+    );
+}
@@ -76,6 +76,24 @@ fn typecheck_fixtures() {
     }
 }
 
+#[test]
+fn for_over_non_list_reports_one_error() {
+    let source = "data Unit { .unit } data Step[S] { .next(S), .done }
+        extern type Number; extern type Array[A];
+        fn sum(xs: Array[Number]): Unit / {} { for x in xs { x } }";
+    let lexed = lex(source);
+    let parsed = parse(&lexed.tokens, &lexed.errors);
+    let hir = hir::lower(&parsed.file);
+    let (_, errors) = typecheck_and_bindings(&lir::lower(&hir));
+    let messages: Vec<_> = hir
+        .errors
+        .iter()
+        .map(|e| e.message.clone())
+        .chain(errors.into_iter().map(|e| e.message))
+        .collect();
+    assert_eq!(messages, ["`for` iterates over a `List`, got Array[Number]"]);
+}
+
 fn split_cases(text: &str) -> Vec<String> {
     text.replace("\r\n", "\n")
         .split("\n==========\n")
@@ -0,0 +1,171 @@
+//! `#[cfg(...)]` on top-level items.
+//!
+//! A predicate is `target = "<spec>"`, `feature = "<name>"`, or `not(..)`,
+//! `all(..)` and `any(..)` over predicates. Several predicates in one
+//! `cfg(...)`, like several `#[cfg]` attributes on one item, must all hold.
+//!
+//! `target` matches dotted prefixes the way `src#<suffix>/` overlays do, so
+//! `target = "js"` holds when building `js.node`.
+
+use std::collections::BTreeSet;
+
+use lumo_lst as lst;
+
+/// The configuration `#[cfg]` attributes are evaluated against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cfg {
+    /// The target spec being built, e.g. `"js.node"`.
+    pub target: String,
+    /// Features enabled for the package the file belongs to.
+    pub features: BTreeSet<String>,
+    /// Every feature the package declares; a `feature` predicate naming any
+    /// other one is an error. `None` accepts any name.
+    pub declared: Option<BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    Target(String),
+    Feature(String),
+    Not(Box<Predicate>),
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+}
+
+impl Cfg {
+    /// Whether an item carrying `attrs` is compiled in, or why one of its
+    /// `#[cfg]` attributes is malformed.
+    pub fn is_enabled(&self, attrs: &[lst::Attribute]) -> Result<bool, String> {
+        let mut enabled = true;
+        for attr in attrs.iter().filter(|a| a.name == "cfg") {
+            let predicate = Predicate::All(parse_list(attr)?);
+            self.check_features(&predicate)?;
+            enabled &= self.eval(&predicate);
+        }
+        Ok(enabled)
+    }
+
+    fn eval(&self, predicate: &Predicate) -> bool {
+        match predicate {
+            Predicate::Target(spec) => {
+                self.target == *spec || self.target.starts_with(&format!("{spec}."))
+            }
+            Predicate::Feature(name) => self.features.contains(name),
+            Predicate::Not(inner) => !self.eval(inner),
+            Predicate::All(list) => list.iter().all(|p| self.eval(p)),
+            Predicate::Any(list) => list.iter().any(|p| self.eval(p)),
+        }
+    }
+
+    /// Checked over the whole predicate, so a misspelt feature is reported
+    /// even where evaluation would not reach it.
+    fn check_features(&self, predicate: &Predicate) -> Result<(), String> {
+        match predicate {
+            Predicate::Target(_) => Ok(()),
+            Predicate::Feature(name) => match &self.declared {
+                Some(declared) if !declared.contains(name) => {
+                    Err(format!("unknown feature `{name}` in `#[cfg]`"))
+                }
+                _ => Ok(()),
+            },
+            Predicate::Not(inner) => self.check_features(inner),
+            Predicate::All(list) | Predicate::Any(list) => {
+                list.iter().try_for_each(|p| self.check_features(p))
+            }
+        }
+    }
+}
+
+/// The predicates inside `attr(...)`.
+fn parse_list(attr: &lst::Attribute) -> Result<Vec<Predicate>, String> {
+    if attr.value.is_some() {
+        return Err(format!("expected `{}(...)`", attr.name));
+    }
+    if let Some(flag) = attr.flags.first() {
+        return Err(format!("expected `{flag} = \"...\"` in `{}(...)`", attr.name));
+    }
+    let mut list = Vec::new();
+    for arg in &attr.args {
+        let lst::Expr::String { value, .. } = &arg.value else {
+            return Err(format!("`{}` in `#[cfg]` takes a string", arg.key));
+        };
+        list.push(match arg.key.as_str() {
+            "target" => Predicate::Target(value.clone()),
+            "feature" => Predicate::Feature(value.clone()),
+            other => return Err(format!("unknown `#[cfg]` key `{other}`")),
+        });
+    }
+    for nested in &attr.nested {
+        let mut inner = parse_list(nested)?;
+        list.push(match nested.name.as_str() {
+            "all" => Predicate::All(inner),
+            "any" => Predicate::Any(inner),
+            "not" if inner.len() == 1 => Predicate::Not(Box::new(inner.remove(0))),
+            "not" => return Err("`not(...)` takes exactly one predicate".into()),
+            other => return Err(format!("unknown `#[cfg]` operator `{other}`")),
+        });
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(src: &str) -> Vec<lst::Attribute> {
+        let parsed = lst::parser::parse_lossless(&lst::lossless::parse(src));
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        match parsed.file.items.into_iter().next() {
+            Some(lst::Item::Fn(f)) => f.attrs,
+            other => panic!("expected a fn, got {other:?}"),
+        }
+    }
+
+    fn cfg(target: &str, features: &[&str]) -> Cfg {
+        Cfg {
+            target: target.into(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            declared: None,
+        }
+    }
+
+    #[test]
+    fn target_matches_dotted_prefixes() {
+        let js = attrs("#[cfg(target = \"js\")] fn f() = 1");
+        assert_eq!(cfg("js.node", &[]).is_enabled(&js), Ok(true));
+        assert_eq!(cfg("js", &[]).is_enabled(&js), Ok(true));
+        assert_eq!(cfg("jsx", &[]).is_enabled(&js), Ok(false));
+        assert_eq!(cfg("rs", &[]).is_enabled(&js), Ok(false));
+    }
+
+    #[test]
+    fn combines_predicates() {
+        let src = "#[cfg(any(target = \"rs\", all(feature = \"a\", not(feature = \"b\"))))]\n\
+                   fn f() = 1";
+        let f = attrs(src);
+        assert_eq!(cfg("rs", &["b"]).is_enabled(&f), Ok(true));
+        assert_eq!(cfg("js", &["a"]).is_enabled(&f), Ok(true));
+        assert_eq!(cfg("js", &["a", "b"]).is_enabled(&f), Ok(false));
+
+        let both = attrs("#[cfg(target = \"js\")]\n#[cfg(feature = \"a\")]\nfn f() = 1");
+        assert_eq!(cfg("js", &[]).is_enabled(&both), Ok(false));
+        assert_eq!(cfg("js", &["a"]).is_enabled(&both), Ok(true));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_undeclared_features() {
+        let mut config = cfg("js", &[]);
+        let os = attrs("#[cfg(os = \"linux\")] fn f() = 1");
+        assert_eq!(
+            config.is_enabled(&os),
+            Err("unknown `#[cfg]` key `os`".into())
+        );
+
+        config.declared = Some(BTreeSet::from(["fast".to_owned()]));
+        let typo = attrs("#[cfg(any(target = \"js\", feature = \"fsat\"))] fn f() = 1");
+        assert_eq!(
+            config.is_enabled(&typo),
+            Err("unknown feature `fsat` in `#[cfg]`".into())
+        );
+    }
+}
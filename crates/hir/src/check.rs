@@ -3,7 +3,7 @@ use crate::{
     ImplMethodDecl, Item, MatchArm,
 };
 use lumo_span::Span;
-use lumo_types::{CapRef, Pattern, TypeExpr};
+use lumo_types::{CapEntry, CapRef, Pattern, TypeExpr};
 use std::collections::{HashMap, HashSet};
 
 // ---------------------------------------------------------------------------
@@ -205,11 +205,12 @@ impl CheckCtx {
     }
 
     fn check_extern_fn(&mut self, ext: &ExternFnDecl) {
+        let generics: HashSet<&str> = ext.generics.iter().map(|g| g.name()).collect();
         for param in &ext.params {
-            self.check_type_expr(&param.ty.value, param.ty.span);
+            self.check_type_expr_with_generics(&param.ty.value, param.ty.span, &generics);
         }
         if let Some(ret) = &ext.return_type {
-            self.check_type_expr(&ret.value, ret.span);
+            self.check_type_expr_with_generics(&ret.value, ret.span, &generics);
         }
         self.check_cap_ref(&ext.cap, ext.span);
     }
@@ -226,11 +227,12 @@ impl CheckCtx {
 
     fn check_cap(&mut self, cap: &CapDecl) {
         for op in &cap.operations {
+            let generics: HashSet<&str> = op.generics.iter().map(|g| g.name()).collect();
             for param in &op.params {
-                self.check_type_expr(&param.ty.value, param.ty.span);
+                self.check_type_expr_with_generics(&param.ty.value, param.ty.span, &generics);
             }
             if let Some(ret) = &op.return_type {
-                self.check_type_expr(&ret.value, ret.span);
+                self.check_type_expr_with_generics(&ret.value, ret.span, &generics);
             }
         }
     }
@@ -310,6 +312,39 @@ impl CheckCtx {
                     }
                 }
             }
+            // `Bundle[IO]`: a handler value for a cap, which may be generic
+            // over `Self` as in `Bundle[State[Number]]`. `Bundle[IO, R]`
+            // names the answer type its ops may abort with.
+            TypeExpr::App { head, args } if head == "Bundle" && !self.types.contains(head) => {
+                let (cap, answer) = match args.as_slice() {
+                    [cap] => (cap, None),
+                    [cap, answer] => (cap, Some(answer)),
+                    _ => {
+                        self.error(span, "`Bundle` takes a capability and an optional answer type, as in `Bundle[IO]`".to_owned());
+                        return;
+                    }
+                };
+                let cap = match cap {
+                    TypeExpr::Named(cap) => Some((cap, &[][..])),
+                    TypeExpr::App { head: cap, args } | TypeExpr::Cap { name: cap, type_args: args } => {
+                        Some((cap, args.as_slice()))
+                    }
+                    _ => None,
+                };
+                let Some((cap, cap_args)) = cap else {
+                    self.error(span, "`Bundle` takes a capability and an optional answer type, as in `Bundle[IO]`".to_owned());
+                    return;
+                };
+                if let Some(answer) = answer {
+                    self.check_type_expr_with_generics(answer, span, generics);
+                }
+                if !self.caps.contains_key(cap) {
+                    self.error(span, format!("unknown capability `{cap}`"));
+                }
+                for arg in cap_args {
+                    self.check_type_expr_with_generics(arg, span, generics);
+                }
+            }
             TypeExpr::App { head, args } => {
                 if !self.types.contains(head) && !generics.contains(head.as_str()) {
                     self.error(span, format!("unknown type `{head}`"));
@@ -332,6 +367,14 @@ impl CheckCtx {
             TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => {
                 self.check_type_expr_with_generics(inner, span, generics);
             }
+            TypeExpr::Comp { ty, cap } => {
+                self.check_type_expr_with_generics(ty, span, generics);
+                for entry in cap {
+                    if let CapEntry::Cap(cap) = entry {
+                        self.check_type_expr_with_generics(cap, span, generics);
+                    }
+                }
+            }
             TypeExpr::Cap { name, type_args } => {
                 if !self.caps.contains_key(name) {
                     self.error(span, format!("unknown capability `{name}`"));
@@ -459,6 +502,14 @@ impl CheckCtx {
                 self.check_expr(inner, locals);
                 self.check_type_expr(&ty.value, ty.span);
             }
+            Expr::Loop { var, init, body, .. } => {
+                self.check_expr(init, locals);
+                let is_new = locals.insert(var.clone());
+                self.check_expr(body, locals);
+                if is_new {
+                    locals.remove(var);
+                }
+            }
             Expr::Lambda { params, body, .. } => {
                 for (name, ty) in params {
                     if let Some(ty) = ty {
@@ -589,6 +640,22 @@ mod tests {
         assert!(msgs.iter().any(|m| m.contains("unknown type `Bar`")));
     }
 
+    #[test]
+    fn bundle_type_names_a_cap() {
+        let msgs = check_msgs(
+            "extern type Number
+             cap IO { fn println(msg: Number): Number }
+             fn f(h: Bundle[IO], g: Bundle[Nope], k: Bundle[IO, Number]): Bundle[IO, Number, Number] := produce h",
+        );
+        assert_eq!(
+            msgs,
+            vec![
+                "unknown capability `Nope`".to_owned(),
+                "`Bundle` takes a capability and an optional answer type, as in `Bundle[IO]`".to_owned(),
+            ]
+        );
+    }
+
     #[test]
     fn duplicate_fn() {
         let msgs = check_msgs(
@@ -0,0 +1,557 @@
+//! `while`, `for` and `loop`, and the `break`, `continue` and `return` that
+//! leave them, lowered onto [`Expr::Loop`] and the `Break`, `Continue` and
+//! `Return` caps of the prelude.
+//!
+//! A loop body produces the next state as a `Step`:
+//!
+//! ```text
+//! while c { b }    ~>  loop __loop0 = Unit { match c { .true => b; Step.next(Unit), .false => Step.done } }
+//! for x in xs { b } ~> loop __loop0 = xs {
+//!                        match __loop0 { .nil => Step.done, .cons(x, __rest0) => b; Step.next(__rest0) }
+//!                      }
+//! ```
+//!
+//! `break`, `continue` and `return e` are performs of abortive ops. Where one
+//! is the last thing its loop body or fn body does, it is replaced by the step
+//! or value it stands for, and a guard like `if c { break }; rest` becomes a
+//! `match` with `rest` in its other arm, so the usual loop needs no handler.
+//! The exits that remain are handled, without resuming, around the loop body
+//! or fn body.
+//!
+//! `try` and `?` go through the `Throw` cap the same way:
+//!
+//! ```text
+//! try { b } catch e => h  ~>  handle Throw with bundle { fn throw(e) := h } in b
+//! r?                      ~>  match r { .ok(__ok0) => __ok0, .err(__err0) => Throw.throw(__err0) }
+//! ```
+//!
+//! A `try` without `catch e: E` handles `Throw` at the error type its body
+//! throws, which the compiler fills in once the body is typechecked.
+
+use lumo_lst as lst;
+use lumo_span::Span;
+use lumo_types::{Pattern, Spanned, TypeExpr};
+
+use crate::{lower_expr, maybe_produce, BundleEntry, Expr, HirError, LowerCtx, MatchArm, Param};
+
+/// Lower `while condition { body }`.
+pub(crate) fn lower_while(
+    condition: &lst::Expr,
+    body: &lst::Expr,
+    span: Span,
+    ctx: &mut LowerCtx,
+) -> Expr {
+    let var = ctx.fresh("__loop");
+    let condition = lower_expr(condition, ctx);
+    let next = step_next(unit(span), span);
+    let body = lower_loop_body(body, &next, span, ctx);
+    let body = Expr::Match {
+        scrutinee: Box::new(condition),
+        arms: vec![
+            arm(ctor_pattern("true", vec![]), body, span),
+            arm(ctor_pattern("false", vec![]), step_done(span), span),
+        ],
+        span,
+    };
+    Expr::Loop {
+        var,
+        init: Box::new(unit(span)),
+        body: Box::new(body),
+        span,
+    }
+}
+
+/// Lower `for name in iterable { body }` over a `List`. The patterns name
+/// `List` outright so the typechecker can reject any other iterable once.
+pub(crate) fn lower_for(
+    name: &str,
+    iterable: &lst::Expr,
+    body: &lst::Expr,
+    span: Span,
+    ctx: &mut LowerCtx,
+) -> Expr {
+    let var = ctx.fresh("__loop");
+    let rest = ctx.fresh("__rest");
+    let init = lower_expr(iterable, ctx);
+    let next = step_next(ident(&rest, span), span);
+    let body = lower_loop_body(body, &next, span, ctx);
+    let body = Expr::Match {
+        scrutinee: Box::new(ident(&var, span)),
+        arms: vec![
+            arm(ctor_pattern("List.nil", vec![]), step_done(span), span),
+            arm(
+                ctor_pattern("List.cons", vec![Pattern::Bind(name.to_owned()), Pattern::Bind(rest)]),
+                body,
+                span,
+            ),
+        ],
+        span,
+    };
+    Expr::Loop {
+        var,
+        init: Box::new(init),
+        body: Box::new(body),
+        span,
+    }
+}
+
+/// Lower `loop { body }`.
+pub(crate) fn lower_loop(body: &lst::Expr, span: Span, ctx: &mut LowerCtx) -> Expr {
+    let var = ctx.fresh("__loop");
+    let next = step_next(unit(span), span);
+    let body = lower_loop_body(body, &next, span, ctx);
+    Expr::Loop {
+        var,
+        init: Box::new(unit(span)),
+        body: Box::new(body),
+        span,
+    }
+}
+
+/// Lower `break` or `continue`, which must sit in a loop.
+pub(crate) fn lower_break(is_break: bool, span: Span, ctx: &mut LowerCtx) -> Expr {
+    let keyword = if is_break { "break" } else { "continue" };
+    if ctx.loop_depth == 0 {
+        ctx.errors.push(HirError {
+            span,
+            message: format!("`{keyword}` outside of a loop"),
+        });
+        return Expr::Error { span };
+    }
+    let (cap, op) = if is_break { ("Break", "break_") } else { ("Continue", "continue_") };
+    exit_call(cap, op, vec![], span)
+}
+
+/// Lower `return value`, which must sit in a fn.
+pub(crate) fn lower_return(value: Option<&lst::Expr>, span: Span, ctx: &mut LowerCtx) -> Expr {
+    if !ctx.in_fn {
+        ctx.errors.push(HirError {
+            span,
+            message: "`return` outside of a fn".to_owned(),
+        });
+        return Expr::Error { span };
+    }
+    let value = match value {
+        Some(value) => lower_expr(value, ctx),
+        None => unit(span),
+    };
+    exit_call("Return", "return_", vec![value], span)
+}
+
+/// Lower `try { body } catch name => handler`.
+pub(crate) fn lower_try(
+    body: &lst::Expr,
+    name: &str,
+    error_type: Option<&lst::TypeSig>,
+    handler: &lst::Expr,
+    span: Span,
+    ctx: &mut LowerCtx,
+) -> Expr {
+    let body = maybe_produce(lower_expr(body, ctx), span);
+    let handler = maybe_produce(lower_expr(handler, ctx), span);
+    let type_args = error_type.map(|ty| vec![ty.repr.trim().to_owned()]).unwrap_or_default();
+    let param = Param {
+        name: name.to_owned(),
+        ty: Spanned {
+            value: TypeExpr::Named(type_args.first().cloned().unwrap_or_default()),
+            span,
+        },
+        span,
+    };
+    let entry = BundleEntry {
+        name: "throw".to_owned(),
+        params: vec![param],
+        body: handler,
+        span,
+    };
+    Expr::Handle {
+        cap: "Throw".to_owned(),
+        type_args,
+        handler: Box::new(Expr::Bundle {
+            entries: vec![entry],
+            span,
+        }),
+        body: Box::new(body),
+        span,
+    }
+}
+
+/// Lower `expr?` on a `Result`.
+pub(crate) fn lower_propagate(expr: &lst::Expr, span: Span, ctx: &mut LowerCtx) -> Expr {
+    let ok = ctx.fresh("__ok");
+    let err = ctx.fresh("__err");
+    let scrutinee = lower_expr(expr, ctx);
+    Expr::Match {
+        scrutinee: Box::new(scrutinee),
+        arms: vec![
+            arm(
+                ctor_pattern("ok", vec![Pattern::Bind(ok.clone())]),
+                produce(ident(&ok, span), span),
+                span,
+            ),
+            arm(
+                ctor_pattern("err", vec![Pattern::Bind(err.clone())]),
+                exit_call("Throw", "throw", vec![ident(&err, span)], span),
+                span,
+            ),
+        ],
+        span,
+    }
+}
+
+/// Finish a fn or impl method body: a `return` at its end becomes its
+/// value, and the others are handled around it.
+pub(crate) fn close_fn_body(body: Expr) -> Expr {
+    let exits = Exits {
+        break_: None,
+        continue_: None,
+        return_: true,
+    };
+    let body = end_exits(body, None, &exits);
+    if !performs(&body, "Return", true) {
+        return body;
+    }
+    let span = body.span();
+    let value = Param {
+        name: "value".to_owned(),
+        ty: Spanned {
+            value: TypeExpr::Named(String::new()),
+            span,
+        },
+        span,
+    };
+    handle_exit("Return", "return_", vec![value], produce(ident("value", span), span), body)
+}
+
+/// Lower a loop body that goes round again with `next`.
+fn lower_loop_body(body: &lst::Expr, next: &Expr, span: Span, ctx: &mut LowerCtx) -> Expr {
+    ctx.loop_depth += 1;
+    let body = lower_expr(body, ctx);
+    ctx.loop_depth -= 1;
+    let exits = Exits {
+        break_: Some(step_done(span)),
+        continue_: Some(next.clone()),
+        return_: false,
+    };
+    let mut body = end_exits(maybe_produce(body, span), Some(next), &exits);
+    if performs(&body, "Continue", false) {
+        body = handle_exit("Continue", "continue_", vec![], next.clone(), body);
+    }
+    if performs(&body, "Break", false) {
+        body = handle_exit("Break", "break_", vec![], step_done(span), body);
+    }
+    body
+}
+
+/// What each exit stands for where it ends a loop or fn body.
+struct Exits {
+    break_: Option<Expr>,
+    continue_: Option<Expr>,
+    /// Whether `return e` ends the body with `e`.
+    return_: bool,
+}
+
+impl Exits {
+    /// The replacement for `expr` if it is an exit this body handles.
+    fn replace(&self, expr: &Expr) -> Option<Expr> {
+        let (cap, args) = exit_of(expr)?;
+        match cap {
+            "Break" => self.break_.clone(),
+            "Continue" => self.continue_.clone(),
+            "Return" if self.return_ => {
+                let value = args.first()?.clone();
+                let span = value.span();
+                Some(maybe_produce(value, span))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Rewrite the exits at the end of `expr`, then go on with `then`, if any,
+/// where `expr` ends without one.
+fn end_exits(expr: Expr, then: Option<&Expr>, exits: &Exits) -> Expr {
+    if let Some(replacement) = exits.replace(&expr) {
+        return replacement;
+    }
+    if exit_of(&expr).is_some() {
+        // Leaves its loop or fn, so nothing runs after it.
+        return expr;
+    }
+    match expr {
+        Expr::Let {
+            name,
+            value,
+            body,
+            span,
+        } => {
+            let rest = end_exits(*body, then, exits);
+            match *value {
+                // `if c { break }; rest`: the exiting arms end the body and
+                // `rest` follows the one that falls through.
+                Expr::Match {
+                    scrutinee,
+                    arms,
+                    span: match_span,
+                } if name == "_"
+                    && arms.iter().any(|a| always_exits(&a.body))
+                    && arms.iter().filter(|a| !always_exits(&a.body)).count() <= 1 =>
+                {
+                    let arms = arms
+                        .into_iter()
+                        .map(|a| {
+                            let then = (!always_exits(&a.body)).then_some(&rest);
+                            MatchArm {
+                                body: end_exits(a.body, then, exits),
+                                ..a
+                            }
+                        })
+                        .collect();
+                    Expr::Match {
+                        scrutinee,
+                        arms,
+                        span: match_span,
+                    }
+                }
+                value => Expr::Let {
+                    name,
+                    value: Box::new(value),
+                    body: Box::new(rest),
+                    span,
+                },
+            }
+        }
+        Expr::Match {
+            scrutinee,
+            arms,
+            span,
+        } => Expr::Match {
+            scrutinee,
+            arms: arms
+                .into_iter()
+                .map(|a| MatchArm {
+                    body: end_exits(a.body, then, exits),
+                    ..a
+                })
+                .collect(),
+            span,
+        },
+        expr => match then {
+            Some(then) if is_unit(&expr) => then.clone(),
+            Some(then) => {
+                let span = expr.span();
+                Expr::Let {
+                    name: "_".to_owned(),
+                    value: Box::new(expr),
+                    body: Box::new(then.clone()),
+                    span,
+                }
+            }
+            None => expr,
+        },
+    }
+}
+
+/// Whether every way through `expr` ends in an exit.
+fn always_exits(expr: &Expr) -> bool {
+    match expr {
+        Expr::Let { body, .. } => always_exits(body),
+        Expr::Match { arms, .. } => !arms.is_empty() && arms.iter().all(|a| always_exits(&a.body)),
+        expr => exit_of(expr).is_some(),
+    }
+}
+
+/// The cap and args of an exit perform: `Break.break_()`,
+/// `Continue.continue_()` or `Return.return_(e)`.
+fn exit_of(expr: &Expr) -> Option<(&str, &[Expr])> {
+    let Expr::Call { callee, args, .. } = expr else {
+        return None;
+    };
+    let Expr::Member { object, member, .. } = callee.as_ref() else {
+        return None;
+    };
+    let Expr::Perform { cap, .. } = object.as_ref() else {
+        return None;
+    };
+    match (cap.as_str(), member.as_str()) {
+        ("Break", "break_") | ("Continue", "continue_") | ("Return", "return_") => {
+            Some((cap.as_str(), args.as_slice()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `expr` performs `cap`. A nested loop handles its own `break`
+/// and `continue`, so it is only searched when `into_loops` is set.
+fn performs(expr: &Expr, cap: &str, into_loops: bool) -> bool {
+    let go = |e: &Expr| performs(e, cap, into_loops);
+    match expr {
+        Expr::Perform { cap: c, .. } => c == cap,
+        Expr::Ident { .. } | Expr::String { .. } | Expr::Number { .. } | Expr::Error { .. } => false,
+        Expr::Call { callee, args, .. } => go(callee) || args.iter().any(go),
+        Expr::Member { object, .. } => go(object),
+        Expr::Produce { expr, .. }
+        | Expr::Thunk { expr, .. }
+        | Expr::Force { expr, .. }
+        | Expr::Ann { expr, .. } => go(expr),
+        Expr::Lambda { body, .. } => go(body),
+        Expr::Let { value, body, .. } => go(value) || go(body),
+        Expr::Match {
+            scrutinee, arms, ..
+        } => go(scrutinee) || arms.iter().any(|a| go(&a.body)),
+        Expr::Handle { handler, body, .. } => go(handler) || go(body),
+        Expr::Bundle { entries, .. } => entries.iter().any(|e| go(&e.body)),
+        Expr::Loop { init, body, .. } => go(init) || (into_loops && go(body)),
+    }
+}
+
+/// `handle Cap with bundle { fn op(params) := value } in body`.
+fn handle_exit(cap: &str, op: &str, params: Vec<Param>, value: Expr, body: Expr) -> Expr {
+    let span = body.span();
+    let entry = BundleEntry {
+        name: op.to_owned(),
+        params,
+        body: value,
+        span,
+    };
+    Expr::Handle {
+        cap: cap.to_owned(),
+        type_args: vec![],
+        handler: Box::new(Expr::Bundle {
+            entries: vec![entry],
+            span,
+        }),
+        body: Box::new(body),
+        span,
+    }
+}
+
+fn exit_call(cap: &str, op: &str, args: Vec<Expr>, span: Span) -> Expr {
+    let perform = Expr::Perform {
+        cap: cap.to_owned(),
+        span,
+    };
+    let member = Expr::Member {
+        object: Box::new(perform),
+        member: op.to_owned(),
+        span,
+    };
+    Expr::Call {
+        callee: Box::new(member),
+        args,
+        span,
+    }
+}
+
+fn is_unit(expr: &Expr) -> bool {
+    match expr {
+        Expr::Produce { expr, .. } => is_unit(expr),
+        Expr::Ident { name, .. } => name == "Unit",
+        _ => false,
+    }
+}
+
+/// `produce Step.done`
+fn step_done(span: Span) -> Expr {
+    produce(
+        Expr::Member {
+            object: Box::new(ident("Step", span)),
+            member: "done".to_owned(),
+            span,
+        },
+        span,
+    )
+}
+
+/// `Step.next(state)`
+fn step_next(state: Expr, span: Span) -> Expr {
+    Expr::Call {
+        callee: Box::new(Expr::Member {
+            object: Box::new(ident("Step", span)),
+            member: "next".to_owned(),
+            span,
+        }),
+        args: vec![state],
+        span,
+    }
+}
+
+fn unit(span: Span) -> Expr {
+    ident("Unit", span)
+}
+
+fn ident(name: &str, span: Span) -> Expr {
+    Expr::Ident {
+        name: name.to_owned(),
+        span,
+    }
+}
+
+fn produce(expr: Expr, span: Span) -> Expr {
+    Expr::Produce {
+        expr: Box::new(expr),
+        span,
+    }
+}
+
+fn ctor_pattern(name: &str, args: Vec<Pattern>) -> Pattern {
+    Pattern::Ctor {
+        name: name.to_owned(),
+        args,
+    }
+}
+
+fn arm(pattern: Pattern, body: Expr, span: Span) -> MatchArm {
+    MatchArm {
+        pattern,
+        body,
+        span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::print::print_file;
+
+    fn lower(src: &str) -> crate::File {
+        let lexed = lumo_lexer::lex(src);
+        let parsed = lumo_lst::parser::parse(&lexed.tokens, &lexed.errors);
+        crate::lower(&parsed.file)
+    }
+
+    #[test]
+    fn guard_exits_need_no_handler() {
+        let file = lower("fn f(xs: List[Number]) { for x in xs { if x == 0 { break }; g(x) } }");
+        assert!(file.errors.is_empty(), "{:?}", file.errors);
+        let out = print_file(&file);
+        assert!(out.contains("loop __loop0 = xs {"), "{out}");
+        assert!(!out.contains("handle"), "{out}");
+        assert!(!out.contains("Break"), "{out}");
+    }
+
+    #[test]
+    fn return_from_a_loop_is_handled_by_the_fn() {
+        let file = lower("fn f(xs: List[Number]): Number { for x in xs { if x == 0 { return x }; g(x) }; 1 }");
+        assert!(file.errors.is_empty(), "{:?}", file.errors);
+        let out = print_file(&file);
+        assert!(out.contains("handle Return"), "{out}");
+        assert!(out.contains("perform Return"), "{out}");
+    }
+
+    #[test]
+    fn try_handles_throw_without_resuming() {
+        let file = lower("fn f(s: String): Number = try { parse(s)? } catch e: String => 0");
+        assert!(file.errors.is_empty(), "{:?}", file.errors);
+        let out = print_file(&file);
+        assert!(out.contains("handle Throw[String]"), "{out}");
+        assert!(out.contains("perform Throw"), "{out}");
+        assert!(!out.contains("resume"), "{out}");
+    }
+
+    #[test]
+    fn exits_outside_their_scope_are_errors() {
+        let file = lower("fn f() { break }");
+        let messages: Vec<_> = file.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["`break` outside of a loop"]);
+    }
+}
@@ -0,0 +1,367 @@
+//! `#[derive(...)]` on a `data` decl: structural impls of `PartialEq`,
+//! `PartialOrd`, `Show` and `Hash`, written out when lowering to LIR.
+//!
+//! Each derive yields a helper fn holding the structural code and an impl
+//! that resumes with it, so the performs on the fields are typed like those
+//! of any fn body:
+//!
+//! ```text
+//! #[derive(PartialEq)] data List[A] { .nil, .cons(A, List[A]) }
+//!
+//! fn __derive_List_eq[A: PartialEq](a: List[A], b: List[A]): Bool = match a { ... }
+//! impl[A: PartialEq] List[A]: PartialEq {
+//!   fn eq(self: List[A], other: List[A]): Bool = resume(__derive_List_eq(self, other))
+//! }
+//! ```
+//!
+//! Every type parameter is bounded by the derived cap. Fields go through the
+//! cap's ops, so the `List[A]` tail above is compared by the derived impl
+//! itself. As for a hand-written impl, the derived cap has to be in scope.
+
+use lumo_span::Span;
+use lumo_types::{Pattern, Spanned, TypeExpr};
+
+use crate::{DataDecl, Expr, FnDecl, GenericParam, ImplDecl, ImplMethodDecl, Item, MatchArm, Param};
+
+/// Caps `#[derive(...)]` can write an impl of.
+pub const DERIVABLE: &[&str] = &["PartialEq", "PartialOrd", "Show", "Hash"];
+
+/// Modulus keeping derived hashes small, as `String`'s hash does.
+const HASH_MODULUS: &str = "2147483647i";
+
+/// The helper fns and impls for the derives of `data`, in derive order.
+pub fn expand(data: &DataDecl) -> Vec<Item> {
+    let mut items = Vec::new();
+    for cap in &data.derives {
+        let b = Builder { data, span: data.span };
+        let (op, binary, ret, body) = match cap.as_str() {
+            "PartialEq" => ("eq", true, "Bool", b.eq_body()),
+            "PartialOrd" => ("cmp", true, "Ordering", b.cmp_body()),
+            "Show" => ("debug", false, "String", b.debug_body()),
+            "Hash" => ("hash", false, "Int", b.hash_body()),
+            _ => continue,
+        };
+        let helper = format!("__derive_{}_{op}", data.name);
+        let generics: Vec<GenericParam> = data
+            .generics
+            .iter()
+            .map(|g| GenericParam::Type(g.clone(), vec![cap.clone()]))
+            .collect();
+        let params = |names: &[&str]| -> Vec<Param> {
+            names
+                .iter()
+                .take(if binary { 2 } else { 1 })
+                .map(|name| Param {
+                    name: (*name).to_owned(),
+                    ty: b.spanned(b.self_type()),
+                    span: b.span,
+                })
+                .collect()
+        };
+        let ret = Some(b.spanned(TypeExpr::Named(ret.to_owned())));
+
+        let self_args: Vec<Expr> = params(&["self", "other"])
+            .iter()
+            .map(|p| b.ident(&p.name))
+            .collect();
+        items.push(Item::Fn(FnDecl {
+            name: helper.clone(),
+            generics: generics.clone(),
+            params: params(&["a", "b"]),
+            return_type: ret.clone(),
+            cap: None,
+            body,
+            inline: false,
+            span: b.span,
+        }));
+        items.push(Item::Impl(ImplDecl {
+            name: None,
+            generics,
+            target_type: b.spanned(b.self_type()),
+            capability: Some(b.spanned(TypeExpr::Named(cap.clone()))),
+            methods: vec![ImplMethodDecl {
+                name: op.to_owned(),
+                params: params(&["self", "other"]),
+                return_type: ret,
+                body: b.call(b.ident("resume"), vec![b.call(b.ident(&helper), self_args)]),
+                span: b.span,
+            }],
+            span: b.span,
+        }));
+    }
+    items
+}
+
+struct Builder<'a> {
+    data: &'a DataDecl,
+    span: Span,
+}
+
+impl Builder<'_> {
+    /// `match a { .v(a0, ..) => match b { .v(b0, ..) => a0 == b0 && .., _ => Bool.false } }`
+    fn eq_body(&self) -> Expr {
+        self.match_variants("a", |variant, fields| {
+            let mut arms = vec![self.arm(
+                self.bind_fields(variant, "b", fields),
+                self.all_eq(fields),
+            )];
+            if self.data.variants.len() > 1 {
+                arms.push(self.arm(Pattern::Wildcard, self.ctor("Bool", "false")));
+            }
+            self.matching("b", arms)
+        })
+    }
+
+    /// `a0 == b0 && a1 == b1 && ..`, as nested matches.
+    fn all_eq(&self, fields: usize) -> Expr {
+        let Some(last) = fields.checked_sub(1) else {
+            return self.ctor("Bool", "true");
+        };
+        (0..last).rev().fold(self.field_op("PartialEq", "eq", last), |rest, i| {
+            Expr::Match {
+                scrutinee: Box::new(self.field_op("PartialEq", "eq", i)),
+                arms: vec![
+                    self.arm(Pattern::Ctor { name: "true".into(), args: vec![] }, rest),
+                    self.arm(
+                        Pattern::Ctor { name: "false".into(), args: vec![] },
+                        self.ctor("Bool", "false"),
+                    ),
+                ],
+                span: self.span,
+            }
+        })
+    }
+
+    /// Variants order by declaration, then fields left to right.
+    fn cmp_body(&self) -> Expr {
+        let last = self.data.variants.len().saturating_sub(1);
+        self.match_variants("a", |variant, fields| {
+            let index = self.variant_index(variant);
+            let mut arms: Vec<MatchArm> = self.data.variants[..index]
+                .iter()
+                .map(|v| {
+                    let pattern = Pattern::Ctor {
+                        name: v.name.clone(),
+                        args: vec![Pattern::Wildcard; v.payload.len()],
+                    };
+                    self.arm(pattern, self.ctor("Ordering", "greater"))
+                })
+                .collect();
+            arms.push(self.arm(self.bind_fields(variant, "b", fields), self.lex_cmp(fields)));
+            if index < last {
+                arms.push(self.arm(Pattern::Wildcard, self.ctor("Ordering", "less")));
+            }
+            self.matching("b", arms)
+        })
+    }
+
+    /// The first field `cmp` that is not `.equal`, as nested matches.
+    fn lex_cmp(&self, fields: usize) -> Expr {
+        let Some(last) = fields.checked_sub(1) else {
+            return self.ctor("Ordering", "equal");
+        };
+        (0..last).rev().fold(self.field_op("PartialOrd", "cmp", last), |rest, i| {
+            Expr::Match {
+                scrutinee: Box::new(self.field_op("PartialOrd", "cmp", i)),
+                arms: vec![
+                    self.arm(Pattern::Ctor { name: "equal".into(), args: vec![] }, rest),
+                    self.arm(Pattern::Bind("ord".into()), self.ident("ord")),
+                ],
+                span: self.span,
+            }
+        })
+    }
+
+    /// `List.cons(1, List.nil)`: the ctor call that builds the value.
+    fn debug_body(&self) -> Expr {
+        self.match_variants("a", |variant, fields| {
+            let name = format!("{}.{variant}", self.data.name);
+            if fields == 0 {
+                return self.string(&name);
+            }
+            let mut out = self.string(&format!("{name}("));
+            for i in 0..fields {
+                if i > 0 {
+                    out = self.perform("StrOps", "concat", vec![out, self.string(", ")]);
+                }
+                let field = self.perform("Show", "debug", vec![self.ident(&format!("a{i}"))]);
+                out = self.perform("StrOps", "concat", vec![out, field]);
+            }
+            self.perform("StrOps", "concat", vec![out, self.string(")")])
+        })
+    }
+
+    /// The variant index, then `h * 31 + hash(field)` for each field.
+    fn hash_body(&self) -> Expr {
+        self.match_variants("a", |variant, fields| {
+            let index = self.variant_index(variant);
+            (0..fields).fold(self.number(&format!("{index}i")), |h, i| {
+                let scaled = self.perform("Mul", "mul", vec![h, self.number("31i")]);
+                let field = self.perform("Hash", "hash", vec![self.ident(&format!("a{i}"))]);
+                let sum = self.perform("Add", "add", vec![scaled, field]);
+                self.perform("Mod", "mod_", vec![sum, self.number(HASH_MODULUS)])
+            })
+        })
+    }
+
+    /// `match <scrutinee> { .v(<scrutinee>0, ..) => body(v, fields), .. }`
+    fn match_variants(&self, scrutinee: &str, body: impl Fn(&str, usize) -> Expr) -> Expr {
+        let arms = self
+            .data
+            .variants
+            .iter()
+            .map(|v| {
+                let fields = v.payload.len();
+                self.arm(self.bind_fields(&v.name, scrutinee, fields), body(&v.name, fields))
+            })
+            .collect();
+        self.matching(scrutinee, arms)
+    }
+
+    fn matching(&self, scrutinee: &str, arms: Vec<MatchArm>) -> Expr {
+        Expr::Match {
+            scrutinee: Box::new(self.ident(scrutinee)),
+            arms,
+            span: self.span,
+        }
+    }
+
+    /// `.variant(<prefix>0, <prefix>1, ..)`
+    fn bind_fields(&self, variant: &str, prefix: &str, fields: usize) -> Pattern {
+        Pattern::Ctor {
+            name: variant.to_owned(),
+            args: (0..fields).map(|i| Pattern::Bind(format!("{prefix}{i}"))).collect(),
+        }
+    }
+
+    fn variant_index(&self, variant: &str) -> usize {
+        self.data
+            .variants
+            .iter()
+            .position(|v| v.name == variant)
+            .expect("variant of this data")
+    }
+
+    /// `Cap.op(a<i>, b<i>)`
+    fn field_op(&self, cap: &str, op: &str, i: usize) -> Expr {
+        let args = vec![self.ident(&format!("a{i}")), self.ident(&format!("b{i}"))];
+        self.perform(cap, op, args)
+    }
+
+    /// `T` or `T[A, ..]`
+    fn self_type(&self) -> TypeExpr {
+        if self.data.generics.is_empty() {
+            return TypeExpr::Named(self.data.name.clone());
+        }
+        TypeExpr::App {
+            head: self.data.name.clone(),
+            args: self
+                .data
+                .generics
+                .iter()
+                .map(|g| TypeExpr::Named(g.clone()))
+                .collect(),
+        }
+    }
+
+    fn arm(&self, pattern: Pattern, body: Expr) -> MatchArm {
+        MatchArm {
+            pattern,
+            body: crate::maybe_produce(body, self.span),
+            span: self.span,
+        }
+    }
+
+    fn perform(&self, cap: &str, op: &str, args: Vec<Expr>) -> Expr {
+        let member = Expr::Member {
+            object: Box::new(Expr::Perform {
+                cap: cap.to_owned(),
+                span: self.span,
+            }),
+            member: op.to_owned(),
+            span: self.span,
+        };
+        self.call(member, args)
+    }
+
+    fn call(&self, callee: Expr, args: Vec<Expr>) -> Expr {
+        Expr::Call {
+            callee: Box::new(callee),
+            args,
+            span: self.span,
+        }
+    }
+
+    fn ctor(&self, data: &str, variant: &str) -> Expr {
+        Expr::Member {
+            object: Box::new(self.ident(data)),
+            member: variant.to_owned(),
+            span: self.span,
+        }
+    }
+
+    fn ident(&self, name: &str) -> Expr {
+        Expr::Ident {
+            name: name.to_owned(),
+            span: self.span,
+        }
+    }
+
+    fn string(&self, value: &str) -> Expr {
+        Expr::String {
+            value: value.to_owned(),
+            span: self.span,
+        }
+    }
+
+    fn number(&self, value: &str) -> Expr {
+        Expr::Number {
+            value: value.to_owned(),
+            span: self.span,
+        }
+    }
+
+    fn spanned(&self, value: TypeExpr) -> Spanned<TypeExpr> {
+        Spanned {
+            value,
+            span: self.span,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::print::print_file;
+    use crate::File;
+
+    fn derive(src: &str) -> String {
+        let lexed = lumo_lexer::lex(src);
+        let parsed = lumo_lst::parser::parse(&lexed.tokens, &lexed.errors);
+        let file = crate::lower(&parsed.file);
+        let Some(Item::Data(data)) = file.items.first() else {
+            panic!("expected a data item");
+        };
+        print_file(&File {
+            items: expand(data),
+            content_hash: file.content_hash,
+            errors: vec![],
+        })
+    }
+
+    #[test]
+    fn derive_eq_on_generic_data() {
+        let out = derive("#[derive(PartialEq)] data List[A] { .nil, .cons(A, List[A]) }");
+        assert!(out.contains("fn __derive_List_eq[A: PartialEq](a: List[A], b: List[A]): Bool"), "{out}");
+        assert!(out.contains("impl[A: PartialEq] List[A]: PartialEq"), "{out}");
+        assert!(out.contains("resume(__derive_List_eq(self, other))"), "{out}");
+    }
+
+    #[test]
+    fn derive_order_follows_attribute() {
+        let out = derive("#[derive(Show, Hash)] data Unit { .unit }");
+        let show = out.find("fn __derive_Unit_debug(a: Unit): String").expect(&out);
+        let hash = out.find("fn __derive_Unit_hash(a: Unit): Int").expect(&out);
+        assert!(show < hash, "{out}");
+    }
+}
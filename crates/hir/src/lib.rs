@@ -1,4 +1,7 @@
+pub mod cfg;
 pub mod check;
+mod control;
+pub mod derive;
 pub mod parse;
 pub mod print;
 
@@ -7,6 +10,8 @@ use lumo_lst as lst;
 use lumo_lst::parser;
 use lumo_types::{CapRef, ContentHash, Pattern, Spanned, TypeExpr};
 
+pub use cfg::Cfg;
+
 /// A generic parameter in a function declaration.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GenericParam {
@@ -64,6 +69,7 @@ pub enum Item {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExternTypeDecl {
     pub name: String,
+    pub generics: Vec<String>,
     pub extern_name: Option<String>,
     pub span: Span,
 }
@@ -75,6 +81,7 @@ pub struct ExternFnDecl {
     /// Module import via `#[link(module = "...")]` — (module, js_name).
     pub link_module: Option<(String, String)>,
     pub inline: bool,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<Spanned<TypeExpr>>,
     pub cap: Option<CapRef>,
@@ -85,6 +92,9 @@ pub struct ExternFnDecl {
 pub struct DataDecl {
     pub name: String,
     pub generics: Vec<String>,
+    /// Caps named by `#[derive(...)]`; their impls are written out when
+    /// lowering to LIR (see [`derive`]).
+    pub derives: Vec<String>,
     pub variants: Vec<VariantDecl>,
     pub span: Span,
 }
@@ -113,6 +123,7 @@ pub struct CapDecl {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OperationDecl {
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<Spanned<TypeExpr>>,
     pub span: Span,
@@ -181,6 +192,9 @@ pub enum Expr {
     Handle { cap: String, type_args: Vec<String>, handler: Box<Expr>, body: Box<Expr>, span: Span },
     Bundle { entries: Vec<BundleEntry>, span: Span },
     Ann { expr: Box<Expr>, ty: Spanned<TypeExpr>, span: Span },
+    /// `loop var = init { body }`: `body` runs with `var` bound to the state
+    /// and produces `Step.next(s)` to go round again from `s`, or `Step.done`.
+    Loop { var: String, init: Box<Expr>, body: Box<Expr>, span: Span },
     Error { span: Span },
 }
 
@@ -221,6 +235,7 @@ impl Expr {
             | Expr::Handle { span, .. }
             | Expr::Bundle { span, .. }
             | Expr::Ann { span, .. }
+            | Expr::Loop { span, .. }
             | Expr::Error { span } => *span,
         }
     }
@@ -235,15 +250,26 @@ pub fn lower_lossless(parsed: &crate::lst::lossless::ParseOutput) -> File {
     lower(&parsed.file)
 }
 
+/// Lower every item, whatever its `#[cfg]` says.
 pub fn lower(file: &lst::File) -> File {
-    let mut ctx = LowerCtx { errors: Vec::new() };
-    let items: Vec<Item> = file
-        .items
-        .iter()
+    lower_with_cfg(file, None)
+}
+
+/// Lower the items whose `#[cfg]` attributes hold under `cfg`; `None`
+/// keeps every item, as [`lower`] does.
+pub fn lower_with_cfg(file: &lst::File, cfg: Option<&Cfg>) -> File {
+    let mut ctx = LowerCtx {
+        errors: Vec::new(),
+        loop_depth: 0,
+        in_fn: false,
+        fresh: 0,
+    };
+    let items: Vec<Item> = enabled_items(file, cfg, &mut ctx)
+        .into_iter()
         .map(|item| match item {
             lst::Item::ExternType(ext) => Item::ExternType(lower_extern_type(ext)),
             lst::Item::ExternFn(ext) => Item::ExternFn(lower_extern_fn(ext)),
-            lst::Item::Data(data) => Item::Data(lower_data(data)),
+            lst::Item::Data(data) => Item::Data(lower_data(data, &mut ctx)),
             lst::Item::Cap(cap) => Item::Cap(lower_cap(cap)),
             lst::Item::Fn(func) => Item::Fn(lower_fn(func, &mut ctx)),
             lst::Item::Use(u) => Item::Use(UseDecl {
@@ -270,6 +296,66 @@ pub fn lower(file: &lst::File) -> File {
 
 struct LowerCtx {
     errors: Vec<HirError>,
+    /// How many loops enclose the expression being lowered.
+    loop_depth: usize,
+    /// Whether a `return` would leave a fn or impl method.
+    in_fn: bool,
+    fresh: usize,
+}
+
+impl LowerCtx {
+    /// A name no source binding can take.
+    fn fresh(&mut self, prefix: &str) -> String {
+        let name = format!("{prefix}{}", self.fresh);
+        self.fresh += 1;
+        name
+    }
+
+    /// Lower `body` as the body of a fn or impl method.
+    fn lower_fn_body(&mut self, body: &lst::Expr) -> Expr {
+        self.in_fn = true;
+        let body = control::close_fn_body(lower_expr(body, self));
+        self.in_fn = false;
+        body
+    }
+}
+
+/// The items `cfg` keeps, reporting malformed `#[cfg]` attributes; an
+/// item whose `#[cfg]` cannot be evaluated is dropped.
+fn enabled_items<'a>(
+    file: &'a lst::File,
+    cfg: Option<&Cfg>,
+    ctx: &mut LowerCtx,
+) -> Vec<&'a lst::Item> {
+    let unconfigured = Cfg::default();
+    let mut items = Vec::new();
+    for item in &file.items {
+        let attrs = item_attrs(item);
+        match cfg.unwrap_or(&unconfigured).is_enabled(attrs) {
+            Ok(enabled) if enabled || cfg.is_none() => items.push(item),
+            Ok(_) => {}
+            Err(message) => {
+                let cfg_attr = attrs.iter().find(|a| a.name == "cfg");
+                ctx.errors.push(HirError {
+                    span: cfg_attr.map_or(Span::new(0, 0), |a| a.span),
+                    message,
+                });
+            }
+        }
+    }
+    items
+}
+
+fn item_attrs(item: &lst::Item) -> &[lst::Attribute] {
+    match item {
+        lst::Item::ExternType(ext) => &ext.attrs,
+        lst::Item::ExternFn(ext) => &ext.attrs,
+        lst::Item::Data(data) => &data.attrs,
+        lst::Item::Cap(cap) => &cap.attrs,
+        lst::Item::Fn(func) => &func.attrs,
+        lst::Item::Use(u) => &u.attrs,
+        lst::Item::Impl(impl_decl) => &impl_decl.attrs,
+    }
 }
 
 /// Merge multiple HIR files into a single combined File.
@@ -338,6 +424,7 @@ fn dedupe_data_with_as_raw(items: Vec<Item>) -> Vec<Item> {
 fn lower_extern_type(ext: &lst::ExternTypeDecl) -> ExternTypeDecl {
     ExternTypeDecl {
         name: ext.name.clone(),
+        generics: ext.generics.iter().map(|g| g.name.clone()).collect(),
         extern_name: find_extern_name(&ext.attrs, &ext.name),
         span: ext.span,
     }
@@ -349,6 +436,7 @@ fn lower_extern_fn(ext: &lst::ExternFnDecl) -> ExternFnDecl {
         extern_name: find_extern_name(&ext.attrs, &ext.name),
         link_module: find_link_module(&ext.attrs, &ext.name),
         inline: find_inline_hint(&ext.attrs),
+        generics: lower_generics(&ext.generics),
         params: ext.params.iter().map(lower_param).collect(),
         return_type: ext.return_type.as_ref().and_then(lower_type_sig),
         cap: ext.cap.as_ref().map(lower_cap_sig),
@@ -356,10 +444,11 @@ fn lower_extern_fn(ext: &lst::ExternFnDecl) -> ExternFnDecl {
     }
 }
 
-fn lower_data(data: &lst::DataDecl) -> DataDecl {
+fn lower_data(data: &lst::DataDecl, ctx: &mut LowerCtx) -> DataDecl {
     DataDecl {
         name: data.name.clone(),
         generics: data.generics.iter().map(|g| g.name.clone()).collect(),
+        derives: find_derives(&data.attrs, ctx),
         variants: data.variants.iter().map(lower_variant).collect(),
         span: data.span,
     }
@@ -378,6 +467,33 @@ fn lower_variant(variant: &lst::VariantDecl) -> VariantDecl {
     }
 }
 
+/// The caps named by `#[derive(...)]`, reporting any that cannot be derived.
+fn find_derives(attrs: &[lst::Attribute], ctx: &mut LowerCtx) -> Vec<String> {
+    let mut derives: Vec<String> = Vec::new();
+    for attr in attrs.iter().filter(|a| a.name == "derive") {
+        if attr.value.is_some() || !attr.args.is_empty() || !attr.nested.is_empty() {
+            ctx.errors.push(HirError {
+                span: attr.span,
+                message: "expected `#[derive(Cap, ...)]`".to_owned(),
+            });
+        }
+        for name in &attr.flags {
+            if !derive::DERIVABLE.contains(&name.as_str()) {
+                ctx.errors.push(HirError {
+                    span: attr.span,
+                    message: format!(
+                        "cannot derive `{name}`; derivable caps are {}",
+                        derive::DERIVABLE.join(", ")
+                    ),
+                });
+            } else if !derives.contains(name) {
+                derives.push(name.clone());
+            }
+        }
+    }
+    derives
+}
+
 fn find_as_raw(attrs: &[lst::Attribute]) -> Option<AsRawValue> {
     attrs.iter().find_map(|a| {
         if a.name != "as__raw" {
@@ -404,6 +520,7 @@ fn lower_cap(cap: &lst::CapDecl) -> CapDecl {
 fn lower_operation(op: &lst::OperationDecl) -> OperationDecl {
     OperationDecl {
         name: op.name.clone(),
+        generics: lower_generics(&op.generics),
         params: op.params.iter().map(lower_param).collect(),
         return_type: op.return_type.as_ref().and_then(lower_type_sig),
         span: op.span,
@@ -413,20 +530,11 @@ fn lower_operation(op: &lst::OperationDecl) -> OperationDecl {
 fn lower_fn(func: &lst::FnDecl, ctx: &mut LowerCtx) -> FnDecl {
     FnDecl {
         name: func.name.clone(),
-        generics: func.generics.iter().map(|g| {
-            if g.is_cap_row {
-                GenericParam::CapRow(g.name.clone())
-            } else {
-                let bounds = g.constraint.as_ref()
-                    .map(|c| parse_bounds(&c.repr))
-                    .unwrap_or_default();
-                GenericParam::Type(g.name.clone(), bounds)
-            }
-        }).collect(),
+        generics: lower_generics(&func.generics),
         params: func.params.iter().map(lower_param).collect(),
         return_type: func.return_type.as_ref().and_then(lower_type_sig),
         cap: func.cap.as_ref().map(lower_cap_sig),
-        body: lower_expr(&func.body, ctx),
+        body: ctx.lower_fn_body(&func.body),
         inline: find_inline_hint(&func.attrs),
         span: func.span,
     }
@@ -471,7 +579,7 @@ fn lower_impl(impl_decl: &lst::ImplDecl, ctx: &mut LowerCtx) -> ImplDecl {
                 name: m.name.clone(),
                 params,
                 return_type,
-                body: lower_expr(&m.body, ctx),
+                body: ctx.lower_fn_body(&m.body),
                 span: m.span,
             }
         })
@@ -479,16 +587,7 @@ fn lower_impl(impl_decl: &lst::ImplDecl, ctx: &mut LowerCtx) -> ImplDecl {
 
     ImplDecl {
         name: impl_decl.name.clone(),
-        generics: impl_decl.generics.iter().map(|g| {
-            if g.is_cap_row {
-                GenericParam::CapRow(g.name.clone())
-            } else {
-                let bounds = g.constraint.as_ref()
-                    .map(|c| parse_bounds(&c.repr))
-                    .unwrap_or_default();
-                GenericParam::Type(g.name.clone(), bounds)
-            }
-        }).collect(),
+        generics: lower_generics(&impl_decl.generics),
         target_type,
         capability,
         methods,
@@ -500,6 +599,22 @@ fn lower_impl(impl_decl: &lst::ImplDecl, ctx: &mut LowerCtx) -> ImplDecl {
 // Shared helpers
 // ---------------------------------------------------------------------------
 
+fn lower_generics(generics: &[lst::GenericParam]) -> Vec<GenericParam> {
+    generics
+        .iter()
+        .map(|g| {
+            if g.is_cap_row {
+                GenericParam::CapRow(g.name.clone())
+            } else {
+                let bounds = g.constraint.as_ref()
+                    .map(|c| parse_bounds(&c.repr))
+                    .unwrap_or_default();
+                GenericParam::Type(g.name.clone(), bounds)
+            }
+        })
+        .collect()
+}
+
 /// Parse `"Add + Eq"` → `["Add", "Eq"]`.
 fn parse_bounds(repr: &str) -> Vec<String> {
     repr.split('+').map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect()
@@ -545,7 +660,7 @@ fn parse_handle_cap(cap: &str) -> (String, Vec<String>) {
 // ---------------------------------------------------------------------------
 
 /// Wrap a value-form expression in `Produce`. Computation forms pass through.
-fn maybe_produce(expr: Expr, span: Span) -> Expr {
+pub(crate) fn maybe_produce(expr: Expr, span: Span) -> Expr {
     match &expr {
         Expr::Produce { .. }
         | Expr::Force { .. }
@@ -573,6 +688,7 @@ fn lower_expr(expr: &lst::Expr, ctx: &mut LowerCtx) -> Expr {
             value: value.clone(),
             span: *span,
         },
+        lst::Expr::Interp { parts, span } => desugar_interp(*span, parts, ctx),
         lst::Expr::Number { value, span } => Expr::Number {
             value: value.clone(),
             span: *span,
@@ -591,6 +707,24 @@ fn lower_expr(expr: &lst::Expr, ctx: &mut LowerCtx) -> Expr {
             expr: Box::new(lower_expr(inner, ctx)),
             span: *span,
         },
+        lst::Expr::Lambda { params, body, span } => {
+            // Like an op body, a fn literal can't leave a loop or fn around it.
+            let (loop_depth, in_fn) = (ctx.loop_depth, ctx.in_fn);
+            (ctx.loop_depth, ctx.in_fn) = (0, false);
+            let body = maybe_produce(lower_expr(body, ctx), *span);
+            (ctx.loop_depth, ctx.in_fn) = (loop_depth, in_fn);
+            Expr::Lambda {
+                params: params
+                    .iter()
+                    .map(|(name, ty)| {
+                        let ty = ty.as_ref().map(|ty| lower_type_sig_with_fallback(&ty.repr, ty.span));
+                        (name.clone(), ty)
+                    })
+                    .collect(),
+                body: Box::new(body),
+                span: *span,
+            }
+        }
         lst::Expr::Force { expr: inner, span } => Expr::Force {
             expr: Box::new(lower_expr(inner, ctx)),
             span: *span,
@@ -647,18 +781,36 @@ fn lower_expr(expr: &lst::Expr, ctx: &mut LowerCtx) -> Expr {
                 span: *span,
             }
         },
-        lst::Expr::Bundle { entries, span } => Expr::Bundle {
-            entries: entries
+        lst::Expr::Bundle { entries, span } => {
+            // An op body is a fn of its own: no loop or fn around the
+            // bundle can be left from inside it.
+            let (loop_depth, in_fn) = (ctx.loop_depth, ctx.in_fn);
+            (ctx.loop_depth, ctx.in_fn) = (0, false);
+            let entries = entries
                 .iter()
                 .map(|e| BundleEntry {
                     name: e.name.clone(),
-                    params: e.params.iter().map(lower_param).collect(),
+                    // An unannotated param takes the op's type, as the
+                    // `<missing>` sentinel tells the checker and backends.
+                    params: e
+                        .params
+                        .iter()
+                        .map(|(name, ty)| Param {
+                            name: name.clone(),
+                            ty: match ty {
+                                Some(ty) => lower_type_sig_with_fallback(&ty.repr, ty.span),
+                                None => lower_type_sig_with_fallback("<missing>", e.span),
+                            },
+                            span: e.span,
+                        })
+                        .collect(),
                     body: maybe_produce(lower_expr(&e.body, ctx), e.span),
                     span: e.span,
                 })
-                .collect(),
-            span: *span,
-        },
+                .collect();
+            (ctx.loop_depth, ctx.in_fn) = (loop_depth, in_fn);
+            Expr::Bundle { entries, span: *span }
+        }
         lst::Expr::Ann { expr: inner, ty, span } => Expr::Ann {
             expr: Box::new(lower_expr(inner, ctx)),
             ty: lower_type_sig_with_fallback(&ty.repr, ty.span),
@@ -724,7 +876,7 @@ fn lower_expr(expr: &lst::Expr, ctx: &mut LowerCtx) -> Expr {
                 body: match else_body {
                     Some(e) => maybe_produce(lower_expr(e, ctx), *span),
                     None => Expr::Produce {
-                        expr: Box::new(Expr::Ident { name: "unit".into(), span: *span }),
+                        expr: Box::new(Expr::Ident { name: "Unit".into(), span: *span }),
                         span: *span,
                     },
                 },
@@ -736,6 +888,22 @@ fn lower_expr(expr: &lst::Expr, ctx: &mut LowerCtx) -> Expr {
                 span: *span,
             }
         },
+        lst::Expr::While { condition, body, span } => {
+            control::lower_while(condition, body, *span, ctx)
+        }
+        lst::Expr::For { name, iterable, body, span } => {
+            control::lower_for(name, iterable, body, *span, ctx)
+        }
+        lst::Expr::Loop { body, span } => control::lower_loop(body, *span, ctx),
+        lst::Expr::Break { span } => control::lower_break(true, *span, ctx),
+        lst::Expr::Continue { span } => control::lower_break(false, *span, ctx),
+        lst::Expr::Return { value, span } => {
+            control::lower_return(value.as_deref(), *span, ctx)
+        }
+        lst::Expr::Try { body, name, error_type, handler, span } => {
+            control::lower_try(body, name, error_type.as_ref(), handler, *span, ctx)
+        }
+        lst::Expr::Propagate { expr, span } => control::lower_propagate(expr, *span, ctx),
         lst::Expr::Error { span } => Expr::Error { span: *span },
     }
 }
@@ -891,6 +1059,31 @@ fn desugar_unary_call(
     }
 }
 
+/// Desugar `"a ${b} c"` → `StrOps.concat(StrOps.concat("a ", Display.show(b)), " c")`,
+/// each call through `perform` like the operators.
+fn desugar_interp(span: Span, parts: &[lst::InterpPart], ctx: &mut LowerCtx) -> Expr {
+    let mut out: Option<Expr> = None;
+    for part in parts {
+        let piece = match part {
+            lst::InterpPart::Text(value) => Expr::String {
+                value: value.clone(),
+                span,
+            },
+            lst::InterpPart::Expr(expr) => {
+                desugar_unary_call(span, "Display", "show", lower_expr(expr, ctx))
+            }
+        };
+        out = Some(match out {
+            Some(left) => desugar_binary_call(span, "StrOps", "concat", left, piece),
+            None => piece,
+        });
+    }
+    out.unwrap_or(Expr::String {
+        value: String::new(),
+        span,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Attribute helpers
 // ---------------------------------------------------------------------------
@@ -1036,6 +1229,9 @@ fn hash_item(h: &mut FnvHasher, item: &Item) {
         Item::Data(d) => {
             h.write_tag("data");
             h.write_str(&d.name);
+            for cap in &d.derives {
+                h.write_str(cap);
+            }
             for v in &d.variants {
                 h.write_str(&v.name);
                 for ty in &v.payload {
@@ -1161,6 +1357,12 @@ fn hash_expr(h: &mut FnvHasher, expr: &Expr) {
             h.write_str(&ty.value.display());
             hash_expr(h, expr);
         }
+        Expr::Loop { var, init, body, .. } => {
+            h.write_tag("loop");
+            h.write_str(var);
+            hash_expr(h, init);
+            hash_expr(h, body);
+        }
         Expr::Error { .. } => {
             h.write_tag("error");
         }
@@ -108,10 +108,6 @@ impl Parser {
         }
     }
 
-    fn peek_ident_text(&self, text: &str) -> bool {
-        matches!(self.peek(), Some(TokenKind::Ident(name)) if name == text)
-    }
-
     fn eat_sym(&mut self, sym: Symbol) -> bool {
         if self.peek() == Some(&TokenKind::Symbol(sym)) {
             self.advance();
@@ -168,13 +164,14 @@ impl Parser {
     // -----------------------------------------------------------------------
 
     fn parse_item(&mut self) -> Option<Item> {
-        // Check for #[inline(always)]
+        // Check for #[inline(always)] and #[derive(...)]
         let inline = self.try_parse_inline_hint();
+        let derives = self.try_parse_derives();
 
         match self.peek()? {
             TokenKind::Keyword(Keyword::Extern) => self.parse_extern_item(inline),
             TokenKind::Keyword(Keyword::Data) => {
-                Some(Item::Data(self.parse_data_decl()?))
+                Some(Item::Data(self.parse_data_decl(derives)?))
             }
             TokenKind::Keyword(Keyword::Cap) => {
                 Some(Item::Cap(self.parse_cap_decl()?))
@@ -228,18 +225,45 @@ impl Parser {
         true
     }
 
+    fn try_parse_derives(&mut self) -> Vec<String> {
+        if self.peek() != Some(&TokenKind::Symbol(Symbol::Hash)) {
+            return Vec::new();
+        }
+        let save = self.pos;
+        self.advance(); // #
+        if !self.eat_sym(Symbol::LBracket) || !self.eat_ident("derive") || !self.eat_sym(Symbol::LParen) {
+            self.pos = save;
+            return Vec::new();
+        }
+        let mut derives = Vec::new();
+        while let Some(TokenKind::Ident(name)) = self.peek() {
+            derives.push(name.clone());
+            self.advance();
+            self.eat_sym(Symbol::Comma);
+        }
+        if !self.eat_sym(Symbol::RParen) || !self.eat_sym(Symbol::RBracket) {
+            self.pos = save;
+            return Vec::new();
+        }
+        derives
+    }
+
     fn parse_extern_item(&mut self, inline: bool) -> Option<Item> {
         let start = self.expect_kw(Keyword::Extern).ok()?;
         if self.eat_ident("type") {
             let (name, name_span) = self.expect_ident().ok()?;
+            let generics: Vec<String> = self.try_parse_generic_params()
+                .into_iter().map(|g| g.name().to_owned()).collect();
             let extern_name = self.try_parse_extern_as();
             Some(Item::ExternType(ExternTypeDecl {
                 name,
+                generics,
                 extern_name,
                 span: Span::new(start.start, name_span.end),
             }))
         } else if self.eat_kw(Keyword::Fn) {
             let (name, _) = self.expect_ident().ok()?;
+            let generics = self.try_parse_generic_params();
             let params = self.parse_param_list()?;
             let return_type = self.try_parse_return_ann();
             let cap = self.try_parse_cap_annotation();
@@ -250,6 +274,7 @@ impl Parser {
                 extern_name,
                 link_module: None,
                 inline,
+                generics,
                 params,
                 return_type,
                 cap,
@@ -281,7 +306,7 @@ impl Parser {
         }
     }
 
-    fn parse_data_decl(&mut self) -> Option<DataDecl> {
+    fn parse_data_decl(&mut self, derives: Vec<String>) -> Option<DataDecl> {
         let start = self.expect_kw(Keyword::Data).ok()?;
         let (name, _) = self.expect_ident().ok()?;
         let generics: Vec<String> = self.try_parse_generic_params()
@@ -298,6 +323,7 @@ impl Parser {
         Some(DataDecl {
             name,
             generics,
+            derives,
             variants,
             span: Span::new(start.start, end.end),
         })
@@ -350,11 +376,13 @@ impl Parser {
     fn parse_operation_decl(&mut self) -> Option<OperationDecl> {
         let start = self.expect_kw(Keyword::Fn).ok()?;
         let (name, _) = self.expect_ident().ok()?;
+        let generics = self.try_parse_generic_params();
         let params = self.parse_param_list()?;
         let return_type = self.try_parse_return_ann();
         let end = self.peek_span();
         Some(OperationDecl {
             name,
+            generics,
             params,
             return_type,
             span: Span::new(start.start, end.start),
@@ -580,8 +608,7 @@ impl Parser {
                 }
             } else {
                 let (name, _) = self.expect_ident().ok()?;
-                let type_args = if self.peek_ident_text("for") {
-                    self.advance(); // consume "for"
+                let type_args = if self.eat_kw(Keyword::For) {
                     let (ty, _) = self.expect_ident().ok()?;
                     vec![TypeExpr::Named(ty)]
                 } else {
@@ -729,6 +756,7 @@ impl Parser {
             TokenKind::Keyword(Keyword::Force) => self.parse_force_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Let) => self.parse_let_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Match) => self.parse_match_expr().map(|e| (e, true)),
+            TokenKind::Keyword(Keyword::Loop) => self.parse_loop_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Perform) => self.parse_perform_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Handle) => self.parse_handle_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Bundle) => self.parse_bundle_expr().map(|e| (e, true)),
@@ -751,7 +779,7 @@ impl Parser {
                 let tok = self.advance();
                 if let TokenKind::NumberLit(s) = &tok.kind {
                     Some((Expr::Number {
-                        value: s.clone(),
+                        value: lumo_lexer::decode_number(s).unwrap_or_else(|_| s.clone()),
                         span: tok.span,
                     }, false))
                 } else {
@@ -839,6 +867,22 @@ impl Parser {
         })
     }
 
+    fn parse_loop_expr(&mut self) -> Option<Expr> {
+        let start = self.expect_kw(Keyword::Loop).ok()?;
+        let (var, _) = self.expect_ident().ok()?;
+        self.expect_sym(Symbol::Equals).ok()?;
+        let init = self.parse_expr()?;
+        self.expect_sym(Symbol::LBrace).ok()?;
+        let body = self.parse_expr()?;
+        let end = self.expect_sym(Symbol::RBrace).ok()?;
+        Some(Expr::Loop {
+            var,
+            init: Box::new(init),
+            body: Box::new(body),
+            span: Span::new(start.start, end.end),
+        })
+    }
+
     fn parse_match_expr(&mut self) -> Option<Expr> {
         let start = self.expect_kw(Keyword::Match).ok()?;
         let scrutinee = self.parse_expr()?;
@@ -886,8 +930,7 @@ impl Parser {
     fn parse_handle_expr(&mut self) -> Option<Expr> {
         let start = self.expect_kw(Keyword::Handle).ok()?;
         let (cap, _) = self.expect_ident().ok()?;
-        let type_args = if self.peek_ident_text("for") {
-            self.advance(); // consume "for"
+        let type_args = if self.eat_kw(Keyword::For) {
             let (ty, _) = self.expect_ident().ok()?;
             vec![ty]
         } else {
@@ -1018,37 +1061,17 @@ impl Parser {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Decode a string literal token. The IR has no interpolation — it is
+/// desugared before lowering — so a `${...}` reads back as text.
 fn strip_string_quotes(s: &str) -> String {
-    let inner = s
-        .strip_prefix('"')
-        .unwrap_or(s)
-        .strip_suffix('"')
-        .unwrap_or(s);
-    unescape_string(inner)
-}
-
-fn unescape_string(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    let mut chars = s.chars();
-    while let Some(ch) = chars.next() {
-        if ch == '\\' {
-            match chars.next() {
-                Some('n') => out.push('\n'),
-                Some('r') => out.push('\r'),
-                Some('t') => out.push('\t'),
-                Some('\\') => out.push('\\'),
-                Some('"') => out.push('"'),
-                Some(c) => {
-                    out.push('\\');
-                    out.push(c);
-                }
-                None => out.push('\\'),
-            }
-        } else {
-            out.push(ch);
-        }
-    }
-    out
+    lumo_lexer::decode_string(s, 0)
+        .parts
+        .into_iter()
+        .map(|part| match part {
+            lumo_lexer::StringPart::Text(text) => text,
+            lumo_lexer::StringPart::Interp { source, .. } => format!("${{{source}}}"),
+        })
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
@@ -1112,6 +1135,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_data_derives() {
+        let file = parse("#[derive(PartialEq, Show)] data List[A] { .nil, .cons(A, List[A]) }").unwrap();
+        match &file.items[0] {
+            Item::Data(d) => {
+                assert_eq!(d.name, "List");
+                assert_eq!(d.derives, vec!["PartialEq", "Show"]);
+            }
+            _ => panic!("expected Data"),
+        }
+    }
+
     #[test]
     fn parse_fn_simple() {
         let file = parse("fn id(x: Bool): produce Bool := produce x").unwrap();
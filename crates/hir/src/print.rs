@@ -80,6 +80,7 @@ fn print_item(p: &mut Printer, item: &Item) {
 fn print_extern_type(p: &mut Printer, ext: &ExternTypeDecl) {
     p.push("extern type ");
     p.push(&ext.name);
+    print_str_generics(p, &ext.generics);
     if let Some(extern_name) = &ext.extern_name {
         p.push(" as \"");
         p.push(extern_name);
@@ -93,6 +94,7 @@ fn print_extern_fn(p: &mut Printer, ext: &ExternFnDecl) {
     }
     p.push("extern fn ");
     p.push(&ext.name);
+    print_generics(p, &ext.generics);
     print_param_list(p, &ext.params);
     if let Some(ret) = &ext.return_type {
         p.push(": ");
@@ -107,6 +109,11 @@ fn print_extern_fn(p: &mut Printer, ext: &ExternFnDecl) {
 }
 
 fn print_data(p: &mut Printer, data: &DataDecl) {
+    if !data.derives.is_empty() {
+        p.push("#[derive(");
+        p.push(&data.derives.join(", "));
+        p.push(")] ");
+    }
     p.push("data ");
     p.push(&data.name);
     print_str_generics(p, &data.generics);
@@ -158,6 +165,7 @@ fn print_cap(p: &mut Printer, cap: &CapDecl) {
 fn print_operation(p: &mut Printer, op: &OperationDecl) {
     p.push("fn ");
     p.push(&op.name);
+    print_generics(p, &op.generics);
     print_param_list(p, &op.params);
     if let Some(ret) = &op.return_type {
         p.push(": ");
@@ -244,7 +252,7 @@ fn print_expr(p: &mut Printer, expr: &Expr) {
         Expr::Ident { name, .. } => p.push(name),
         Expr::String { value, .. } => {
             p.push("\"");
-            p.push(&escape_string(value));
+            p.push(&lumo_lexer::escape_string(value));
             p.push("\"");
         }
         Expr::Number { value, .. } => p.push(value),
@@ -346,6 +354,19 @@ fn print_expr(p: &mut Printer, expr: &Expr) {
             p.push(&ty.value.display());
             p.push(")");
         }
+        Expr::Loop { var, init, body, .. } => {
+            p.push("loop ");
+            p.push(var);
+            p.push(" = ");
+            print_expr(p, init);
+            p.push(" {");
+            p.indent();
+            p.newline();
+            print_expr(p, body);
+            p.dedent();
+            p.newline();
+            p.push("}");
+        }
         Expr::Error { .. } => p.push("<error>"),
         Expr::Lambda { params, body, .. } => {
             p.push("fn(");
@@ -467,21 +488,6 @@ fn print_pattern(p: &mut Printer, pat: &Pattern) {
     p.push(&pat.display());
 }
 
-fn escape_string(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        match ch {
-            '"' => out.push_str("\\\""),
-            '\\' => out.push_str("\\\\"),
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            '\t' => out.push_str("\\t"),
-            c => out.push(c),
-        }
-    }
-    out
-}
-
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -508,6 +514,7 @@ mod tests {
         let file = File {
             items: vec![Item::ExternType(ExternTypeDecl {
                 name: "String".into(),
+                generics: vec![],
                 extern_name: None,
                 span: dummy_span(),
             })],
@@ -522,6 +529,7 @@ mod tests {
         let file = File {
             items: vec![Item::ExternType(ExternTypeDecl {
                 name: "Number".into(),
+                generics: vec![],
                 extern_name: Some("number".into()),
                 span: dummy_span(),
             })],
@@ -537,6 +545,7 @@ mod tests {
             items: vec![Item::Data(DataDecl {
                 name: "Bool".into(),
                 generics: vec![],
+                derives: vec![],
                 variants: vec![
                     VariantDecl {
                         name: "true".into(),
@@ -565,6 +574,7 @@ mod tests {
             items: vec![Item::Data(DataDecl {
                 name: "List".into(),
                 generics: vec!["A".into()],
+                derives: vec!["PartialEq".into(), "Show".into()],
                 variants: vec![
                     VariantDecl {
                         name: "nil".into(),
@@ -592,7 +602,7 @@ mod tests {
         };
         assert_eq!(
             print_file(&file),
-            "data List[A] { .nil, .cons(A, List[A]) }\n"
+            "#[derive(PartialEq, Show)] data List[A] { .nil, .cons(A, List[A]) }\n"
         );
     }
 
@@ -0,0 +1,381 @@
+//! `lbs build`: compile every selected package for each of its targets and
+//! write the output under `out_dir`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
+use lumo_compiler::backend::{self, CodegenTarget};
+use lumo_compiler::lir;
+
+use crate::cache::{engine, Engine};
+use crate::compile::{check_types, compile, Compiled};
+use crate::features::BuildCfg;
+use crate::lockfile::Update;
+use crate::manifest::{self, EntryKind};
+use crate::profile::Profile;
+use crate::sourcemap;
+use crate::timings::print_timings;
+use crate::watch::{run_or_watch, watched_roots};
+use crate::workspace::Package;
+use crate::{
+    build_cfg_or_exit, dep_paths_or_exit, enabled_features_or_exit, find_workspace,
+    parse_target_flag, select_packages, sync_lockfile, target_from_spec, Backend, Failed,
+    LtoReportFormat, Session, Target,
+};
+
+pub fn cmd_build(args: &[String]) {
+    let requested = parse_target_flag(args);
+
+    let workspace = find_workspace();
+    let session = Session::new(&workspace, args);
+    let packages = select_packages(&workspace, args);
+    let (registry, lock) = sync_lockfile(&workspace, args, Update::Nothing);
+    let mut plan = Vec::new();
+    for package in &packages {
+        let manifest = &package.manifest;
+        let Some(targets_to_build) = package_targets(manifest, requested.as_deref(), &packages)
+        else {
+            continue;
+        };
+        check_out_dirs_disjoint(manifest, &targets_to_build);
+        let features = enabled_features_or_exit(manifest, &session);
+        let profile = session.profile(manifest);
+        for target in targets_to_build {
+            let deps = dep_paths_or_exit(manifest, &target, false, lock.as_ref(), &registry);
+            let cfg = build_cfg_or_exit(manifest, &target, &features, &deps, false);
+            let label = package_label(package, &target, &packages);
+            plan.push((package, target, deps, cfg, profile.clone(), label));
+        }
+    }
+
+    let mut engines = HashMap::new();
+    let roots = watched_roots(&workspace, &packages, lock.as_ref(), &registry);
+    run_or_watch(&session, roots, || {
+        let mut built: Vec<(String, Vec<PathBuf>)> = Vec::new();
+        for (package, target, deps, cfg, profile, label) in &plan {
+            let engine = engine(&mut engines, label.clone(), &session);
+            let build = Build {
+                target,
+                cfg,
+                profile,
+                label,
+            };
+            let artifacts = build_target(engine, package, deps.clone(), &build, &session)?;
+            built.push((label.clone(), artifacts));
+        }
+        print_build_summary(&workspace.root, &built);
+        Ok(())
+    });
+}
+
+/// The targets to build `manifest` for. When several packages are selected,
+/// one whose `targets` leave out the requested `--target` is skipped
+/// (`None`) rather than failing the whole workspace.
+pub fn package_targets(
+    manifest: &manifest::Manifest,
+    requested: Option<&str>,
+    packages: &[&Package],
+) -> Option<Vec<Target>> {
+    if let Some(spec) = requested {
+        let listed = manifest.targets.is_empty() || manifest.targets.iter().any(|t| t == spec);
+        if packages.len() > 1 && !listed {
+            eprintln!(
+                "skipping {}: target `{spec}` is not in its `targets`",
+                manifest.name
+            );
+            return None;
+        }
+    }
+    Some(resolve_build_targets(manifest, requested))
+}
+
+/// `<spec>` for a single package, `<package> <spec>` across a workspace.
+pub fn package_label(package: &Package, target: &Target, packages: &[&Package]) -> String {
+    if packages.len() > 1 {
+        format!("{} {}", package.manifest.name, target.spec)
+    } else {
+        target.spec.clone()
+    }
+}
+
+/// Two targets of the same backend writing to one directory would overwrite
+/// each other's artifacts, so reject that before building anything.
+pub fn check_out_dirs_disjoint(manifest: &manifest::Manifest, targets: &[Target]) {
+    for (i, a) in targets.iter().enumerate() {
+        for b in &targets[i + 1..] {
+            if a.backend == b.backend
+                && manifest.target_out_dir(&a.spec) == manifest.target_out_dir(&b.spec)
+            {
+                eprintln!(
+                    "error: targets `{}` and `{}` both write to {}",
+                    a.spec,
+                    b.spec,
+                    manifest.target_out_dir(&a.spec).display()
+                );
+                process::exit(1);
+            }
+        }
+    }
+}
+
+pub fn print_build_summary(root: &Path, built: &[(String, Vec<PathBuf>)]) {
+    let width = built
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    for (label, artifacts) in built {
+        let paths: Vec<String> = artifacts
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).display().to_string())
+            .collect();
+        eprintln!("built {label:width$}  {}", paths.join(", "));
+    }
+}
+
+/// Pick which targets to build based on manifest config and optional CLI flag.
+/// - `--target X` with a manifest `targets` list: X must be listed (error otherwise).
+/// - `--target X` with no manifest `targets`: build X (legacy).
+/// - No `--target`, manifest `targets = [...]`: build each listed target.
+/// - No `--target`, no `targets`: build default `js`.
+pub fn resolve_build_targets(
+    manifest: &manifest::Manifest,
+    requested: Option<&str>,
+) -> Vec<Target> {
+    match (requested, manifest.targets.is_empty()) {
+        (Some(spec), true) => vec![target_from_spec(spec)],
+        (Some(spec), false) => {
+            if !manifest.targets.iter().any(|t| t == spec) {
+                eprintln!(
+                    "error: target `{spec}` is not in manifest `targets = {:?}`",
+                    manifest.targets
+                );
+                process::exit(1);
+            }
+            vec![target_from_spec(spec)]
+        }
+        (None, true) => vec![target_from_spec("js")],
+        (None, false) => manifest
+            .targets
+            .iter()
+            .map(|s| target_from_spec(s))
+            .collect(),
+    }
+}
+
+/// What one `lbs build` step builds a package for.
+pub struct Build<'a> {
+    pub target: &'a Target,
+    pub cfg: &'a BuildCfg,
+    pub profile: &'a Profile,
+    pub label: &'a str,
+}
+
+pub fn build_target(
+    engine: &mut Engine,
+    package: &Package,
+    deps: HashMap<String, PathBuf>,
+    build: &Build,
+    session: &Session,
+) -> Result<Vec<PathBuf>, Failed> {
+    let Build {
+        target,
+        cfg,
+        profile,
+        label,
+    } = *build;
+    let manifest = &package.manifest;
+    let Compiled {
+        lir,
+        lto_report: report,
+        type_errors,
+        mut timings,
+    } = compile(engine, deps, &package.root, target, cfg, profile)?;
+    // Debug: print LIR items with their span info
+    if std::env::var("LBS_DEBUG_SPANS").is_ok() {
+        for item in &lir.items {
+            if let lir::Item::Fn(f) = item {
+                eprintln!("FN {} span={}..{}", f.name, f.span.start, f.span.end);
+            }
+        }
+    }
+    check_types(&type_errors)?;
+
+    // The report goes to stdout so it can be piped; one per target.
+    match (session.lto_report, report) {
+        (Some(LtoReportFormat::Text), Some(report)) => {
+            print!("lto report for `{}`:\n{}", target.spec, report.to_text())
+        }
+        (Some(LtoReportFormat::Json), Some(report)) => print!("{}", report.to_json()),
+        (Some(_), None) if !profile.lto => eprintln!(
+            "note: no lto report for `{}`: the `{}` profile has `lto = false`",
+            target.spec, profile.name
+        ),
+        _ => {}
+    }
+
+    let codegen_started = Instant::now();
+    let artifacts = match target.backend {
+        Backend::Js => build_js(engine, package, &lir, target, profile)?,
+        Backend::Rust => build_rust(manifest, &lir, target, profile)?,
+    };
+    timings.codegen = Some(codegen_started.elapsed());
+    if session.timings {
+        print_timings(label, &timings);
+    }
+    Ok(artifacts)
+}
+
+/// Writes `<out>/<name>.js`, and `<name>.js.map` when `profile` asks for a
+/// source map, and returns the written paths.
+pub fn build_js(
+    engine: &mut Engine,
+    package: &Package,
+    lir: &lir::File,
+    target: &Target,
+    profile: &Profile,
+) -> Result<Vec<PathBuf>, Failed> {
+    let manifest = &package.manifest;
+    let options = profile.ts_options();
+    let js = match backend::emit_with_ts_options(lir, CodegenTarget::JavaScript, options) {
+        Ok(js) => js,
+        Err(e) => {
+            eprintln!("error: codegen failed: {e:?}");
+            return Err(Failed);
+        }
+    };
+
+    let out_dir = manifest.target_out_dir(&target.spec);
+    if let Err(e) = std::fs::create_dir_all(&out_dir) {
+        eprintln!("error: cannot create output dir {}: {e}", out_dir.display());
+        return Err(Failed);
+    }
+
+    let out_name = format!("{}.js", manifest.name);
+    let out_file = out_dir.join(&out_name);
+    // For bin entries, auto-invoke main()
+    let mut js = if matches!(manifest.entry, EntryKind::Bin(_)) {
+        let gap = if profile.minify { "" } else { "\n" };
+        format!("{js}{gap}main();\n")
+    } else {
+        js
+    };
+    let mut written = vec![out_file.clone()];
+    let map_file = out_dir.join(format!("{out_name}.map"));
+    if profile.source_map {
+        let (sources, origins) = source_map_inputs(engine, &package.root, &out_dir);
+        let map = sourcemap::function_map(&js, &out_name, &sources, &origins);
+        if let Err(e) = std::fs::write(&map_file, map) {
+            eprintln!("error: cannot write {}: {e}", map_file.display());
+            return Err(Failed);
+        }
+        js.push_str(&format!("//# sourceMappingURL={out_name}.map\n"));
+        written.push(map_file);
+    } else {
+        // Left over from a dev build, it would describe different code.
+        let _ = std::fs::remove_file(&map_file);
+    }
+    if let Err(e) = std::fs::write(&out_file, &js) {
+        eprintln!("error: cannot write {}: {e}", out_file.display());
+        return Err(Failed);
+    }
+
+    Ok(written)
+}
+
+/// The package's own sources, as paths relative to `out_dir` with their
+/// content, and where each of their fns is declared.
+pub fn source_map_inputs(
+    engine: &mut Engine,
+    root: &Path,
+    out_dir: &Path,
+) -> (Vec<(String, String)>, HashMap<String, sourcemap::Origin>) {
+    let mut files: Vec<(String, String)> = engine
+        .roots
+        .iter()
+        .map(|(name, source)| (name.clone(), source.clone()))
+        .collect();
+    files.sort();
+    let to_root = sourcemap::relative_path(out_dir, root);
+    let mut sources = Vec::new();
+    let mut origins = HashMap::new();
+    for (index, (name, source)) in files.into_iter().enumerate() {
+        if let Some(hir) = engine.queries.lower_hir(&name) {
+            for item in &hir.items {
+                if let lumo_compiler::hir::Item::Fn(f) = item {
+                    let origin = sourcemap::Origin::at(index, &source, f.span.start);
+                    origins.insert(f.name.clone(), origin);
+                }
+            }
+        }
+        let path = to_root.join(&name).to_string_lossy().replace('\\', "/");
+        sources.push((path, source));
+    }
+    (sources, origins)
+}
+
+/// Writes a Cargo project under `<out>/` and returns the written paths.
+pub fn build_rust(
+    manifest: &manifest::Manifest,
+    lir: &lir::File,
+    target: &Target,
+    profile: &Profile,
+) -> Result<Vec<PathBuf>, Failed> {
+    let rs_code = match backend::emit(lir, CodegenTarget::Rust) {
+        Ok(rs) => rs,
+        Err(e) => {
+            eprintln!("error: codegen failed: {e:?}");
+            return Err(Failed);
+        }
+    };
+
+    // Create Cargo project structure
+    let out_dir = manifest.target_out_dir(&target.spec);
+    let src_dir = out_dir.join("src");
+    if let Err(e) = std::fs::create_dir_all(&src_dir) {
+        eprintln!("error: cannot create output dir {}: {e}", src_dir.display());
+        return Err(Failed);
+    }
+
+    // Write Cargo.toml (with [workspace] to prevent parent workspace detection)
+    let mut cargo_toml = format!(
+        "[package]\nname = {}\nversion = \"{}\"\nedition = \"2021\"\n",
+        toml::Value::from(manifest.name.as_str()),
+        manifest.version
+    );
+    if let Some(description) = &manifest.description {
+        cargo_toml.push_str(&format!(
+            "description = {}\n",
+            toml::Value::from(description.as_str())
+        ));
+    }
+    if !manifest.authors.is_empty() {
+        let authors: Vec<String> = manifest
+            .authors
+            .iter()
+            .map(|a| toml::Value::from(a.as_str()).to_string())
+            .collect();
+        cargo_toml.push_str(&format!("authors = [{}]\n", authors.join(", ")));
+    }
+    cargo_toml.push_str(&format!("\n{}", profile.cargo_section()));
+    cargo_toml.push_str("\n[workspace]\n");
+    let cargo_toml_path = out_dir.join("Cargo.toml");
+    if let Err(e) = std::fs::write(&cargo_toml_path, &cargo_toml) {
+        eprintln!("error: cannot write {}: {e}", cargo_toml_path.display());
+        return Err(Failed);
+    }
+
+    // Write main.rs or lib.rs based on entry kind
+    let rs_file = match &manifest.entry {
+        EntryKind::Bin(_) => src_dir.join("main.rs"),
+        EntryKind::Lib(_) => src_dir.join("lib.rs"),
+    };
+    if let Err(e) = std::fs::write(&rs_file, &rs_code) {
+        eprintln!("error: cannot write {}: {e}", rs_file.display());
+        return Err(Failed);
+    }
+
+    Ok(vec![cargo_toml_path, rs_file])
+}
@@ -0,0 +1,61 @@
+//! Per-target query engines and their disk cache bookkeeping.
+
+use std::collections::HashMap;
+
+use lumo_compiler::query::{CacheStats, QueryEngine};
+
+use crate::Session;
+
+/// A query engine for one package target (or test) that lives as long as
+/// the command, so a `--watch` rebuild only recomputes what changed.
+pub struct Engine {
+    pub queries: QueryEngine,
+    /// The root sources last given to `queries`.
+    pub roots: HashMap<String, String>,
+}
+
+impl Engine {
+    pub fn new(session: &Session) -> Self {
+        let mut queries = QueryEngine::new();
+        queries.set_disk_cache(session.cache.clone());
+        Self {
+            queries,
+            roots: HashMap::new(),
+        }
+    }
+
+    /// Make the engine's root files match `sources`, passing on only the
+    /// ones that were added, changed or removed.
+    pub fn update_roots(&mut self, sources: &HashMap<String, String>) {
+        let queries = &mut self.queries;
+        self.roots.retain(|name, _| {
+            let kept = sources.contains_key(name);
+            if !kept {
+                queries.remove_file(name);
+            }
+            kept
+        });
+        for (name, source) in sources {
+            if self.roots.get(name) != Some(source) {
+                queries.set_file(name, source.clone());
+                self.roots.insert(name.clone(), source.clone());
+            }
+        }
+    }
+}
+
+/// The engine for `key`, created on first use.
+pub fn engine<'a>(
+    engines: &'a mut HashMap<String, Engine>,
+    key: String,
+    session: &Session,
+) -> &'a mut Engine {
+    engines.entry(key).or_insert_with(|| Engine::new(session))
+}
+
+pub fn cache_delta(after: CacheStats, before: CacheStats) -> CacheStats {
+    CacheStats {
+        hits: after.hits - before.hits,
+        misses: after.misses - before.misses,
+    }
+}
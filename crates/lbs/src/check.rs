@@ -0,0 +1,104 @@
+//! `lbs check`: typecheck every selected package without writing output.
+//!
+//! Plain `lbs check` checks each package's first target with the selected
+//! features. `--all-targets` checks every target, and unless features are
+//! given on the command line, each with no features, the default features
+//! and all features (see [`features::configurations`]). Every configuration
+//! is checked even after one fails, so a single run reports them all.
+
+use std::collections::HashMap;
+
+use crate::build::{package_label, package_targets};
+use crate::cache::engine;
+use crate::compile::compile;
+use crate::features;
+use crate::lockfile::Update;
+use crate::timings::print_timings;
+use crate::watch::{run_or_watch, watched_roots};
+use crate::{
+    build_cfg_or_exit, dep_paths_or_exit, enabled_features_or_exit, find_workspace,
+    parse_target_flag, select_packages, sync_lockfile, Failed, Session,
+};
+
+pub fn cmd_check(args: &[String]) {
+    let requested = parse_target_flag(args);
+    let workspace = find_workspace();
+    let session = Session::new(&workspace, args);
+    let packages = select_packages(&workspace, args);
+    let (registry, lock) = sync_lockfile(&workspace, args, Update::Nothing);
+
+    let all_targets = args.iter().any(|a| a == "--all-targets");
+    let mut plan = Vec::new();
+    for package in &packages {
+        let manifest = &package.manifest;
+        let Some(mut targets) = package_targets(manifest, requested.as_deref(), &packages) else {
+            continue;
+        };
+        // Without --all-targets, check just the first resolved target.
+        if !all_targets {
+            targets.truncate(1);
+        }
+        let profile = session.profile(manifest);
+        let feature_sets = if all_targets && session.features.is_default() {
+            features::configurations(manifest)
+        } else {
+            vec![enabled_features_or_exit(manifest, &session)]
+        };
+        for target in targets {
+            let deps = dep_paths_or_exit(manifest, &target, false, lock.as_ref(), &registry);
+            for features in &feature_sets {
+                let cfg = build_cfg_or_exit(manifest, &target, features, &deps, false);
+                let mut label = package_label(package, &target, &packages);
+                if feature_sets.len() > 1 {
+                    label = format!("{label} {}", features::describe(features));
+                }
+                plan.push((
+                    package,
+                    target.clone(),
+                    deps.clone(),
+                    cfg,
+                    profile.clone(),
+                    label,
+                ));
+            }
+        }
+    }
+
+    let mut engines = HashMap::new();
+    let roots = watched_roots(&workspace, &packages, lock.as_ref(), &registry);
+    run_or_watch(&session, roots, || {
+        let mut failed = Vec::new();
+        for (package, target, deps, cfg, profile, label) in &plan {
+            if all_targets {
+                eprintln!("checking {label}");
+            } else if packages.len() > 1 {
+                eprintln!("checking {}", package.manifest.name);
+            }
+
+            let engine = engine(&mut engines, label.clone(), &session);
+            let Ok(compiled) = compile(engine, deps.clone(), &package.root, target, cfg, profile) else {
+                failed.push(label);
+                continue;
+            };
+            if session.timings {
+                print_timings(label, &compiled.timings);
+            }
+
+            if !compiled.type_errors.is_empty() {
+                for e in &compiled.type_errors {
+                    eprintln!("error: {}", e.message);
+                }
+                failed.push(label);
+            }
+        }
+        if failed.is_empty() {
+            eprintln!("no errors");
+            return Ok(());
+        }
+        if plan.len() > 1 {
+            let failed: Vec<&str> = failed.iter().map(|l| l.as_str()).collect();
+            eprintln!("error: {} of {} failed: {}", failed.len(), plan.len(), failed.join(", "));
+        }
+        Err(Failed)
+    });
+}
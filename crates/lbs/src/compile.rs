@@ -0,0 +1,284 @@
+//! Compile a package target: read its sources, lower and typecheck them
+//! through the query engine and print any errors.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use lumo_compiler::backend::BackendKind;
+use lumo_compiler::lir;
+use lumo_compiler::lto::report::LtoReport;
+use lumo_compiler::typecheck::TypeError;
+
+use crate::cache::{cache_delta, Engine};
+use crate::features::BuildCfg;
+use crate::profile::Profile;
+use crate::resolve;
+use crate::timings::Timings;
+use crate::{Backend, Failed, Target};
+
+/// A lowered, typechecked module and what it took to get there.
+pub struct Compiled {
+    pub lir: lir::File,
+    pub lto_report: Option<LtoReport>,
+    pub type_errors: Vec<TypeError>,
+    pub timings: Timings,
+}
+
+pub fn compile(
+    engine: &mut Engine,
+    deps: HashMap<String, PathBuf>,
+    project_root: &std::path::Path,
+    target: &Target,
+    cfg: &BuildCfg,
+    profile: &Profile,
+) -> Result<Compiled, Failed> {
+    let mut sources: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+
+    let suffixes = target.suffixes();
+
+    // Load common .lumo files from src/
+    let src_dir = project_root.join("src");
+    collect_lumo_files(&src_dir, &mut sources)?;
+
+    // Merge platform-specific .lumo files from src#{suffix}/ for each target prefix.
+    // Earlier suffixes are base (e.g. "js"), later are variants (e.g. "js.node").
+    for suffix in &suffixes {
+        let platform_dir = project_root.join(format!("src#{suffix}"));
+        merge_lumo_files(&platform_dir, &mut sources)?;
+    }
+
+    if sources.is_empty() {
+        eprintln!("error: no .lumo files found in {}", src_dir.display());
+        return Err(Failed);
+    }
+    compile_sources(engine, &sources, deps, target, cfg, profile)
+}
+
+/// Compile and typecheck the root files in `sources` (file name → source)
+/// with `deps` available to `use`, evaluating `#[cfg]` under `cfg` and
+/// running LTO if `profile` asks for it. Syntax errors in the roots fail
+/// it even when lowering recovers from them, as it does from a bad string
+/// escape.
+pub fn compile_sources(
+    engine: &mut Engine,
+    sources: &HashMap<String, String>,
+    deps: HashMap<String, PathBuf>,
+    target: &Target,
+    cfg: &BuildCfg,
+    profile: &Profile,
+) -> Result<Compiled, Failed> {
+    let started = Instant::now();
+    let before = engine.queries.stats();
+    engine
+        .queries
+        .set_cfg(Some(cfg.root.clone()), cfg.deps.clone());
+    engine.queries.set_lto(profile.lto);
+    engine.queries.set_backend(match target.backend {
+        Backend::Js => BackendKind::TypeScript,
+        Backend::Rust => BackendKind::Rust,
+    });
+    engine.queries.set_impl_choices(cfg.impls.clone());
+    engine.update_roots(sources);
+    let mut file_names: Vec<String> = sources.keys().cloned().collect();
+    file_names.sort();
+
+    let file_refs: Vec<&str> = file_names.iter().map(|s| s.as_str()).collect();
+    let mut resolver = resolve::make_resolver(deps, target.suffixes());
+    let Some(lir) = engine.queries.compile_with_deps(&file_refs, &mut resolver) else {
+        print_syntax_errors(engine, &file_names);
+        for conflict in engine.queries.impl_conflicts() {
+            eprintln!("error: {conflict}");
+        }
+        for choice in engine.queries.bad_impl_choices() {
+            eprintln!("error: lumo.toml: {choice}");
+        }
+        eprintln!("error: compilation failed");
+        return Err(Failed);
+    };
+    // Both kinds print before either fails the compile.
+    let syntax_errors = print_syntax_errors(engine, &file_names);
+    if print_hir_errors(engine, &file_names) || syntax_errors {
+        return Err(Failed);
+    }
+    let compiled_at = Instant::now();
+    let type_errors = engine.queries.typecheck(&lir);
+    let after = engine.queries.stats();
+    Ok(Compiled {
+        lto_report: engine.queries.lto_report().cloned(),
+        type_errors,
+        timings: Timings {
+            compile: compiled_at - started,
+            typecheck: compiled_at.elapsed(),
+            codegen: None,
+            hir_cache: cache_delta(after.hir_cache, before.hir_cache),
+            module_cache: cache_delta(after.module_cache, before.module_cache),
+            typecheck_cache: cache_delta(after.typecheck_cache, before.typecheck_cache),
+        },
+        lir,
+    })
+}
+
+/// `<file>:<line>:<col>: <message>` for each parse error in `files`;
+/// returns whether there were any.
+pub fn print_syntax_errors(engine: &mut Engine, files: &[String]) -> bool {
+    let mut any = false;
+    for file in files {
+        let Some(parsed) = engine.queries.parse(file) else {
+            continue;
+        };
+        for e in &parsed.errors {
+            print_located(&engine.roots[file], file, e.span.start, &e.message);
+            any = true;
+        }
+    }
+    any
+}
+
+/// Like `print_syntax_errors`, for errors found while lowering, such as a
+/// malformed `#[cfg]`.
+pub fn print_hir_errors(engine: &mut Engine, files: &[String]) -> bool {
+    let mut any = false;
+    for file in files {
+        let Some(hir) = engine.queries.lower_hir(file) else {
+            continue;
+        };
+        for e in &hir.errors {
+            print_located(&engine.roots[file], file, e.span.start, &e.message);
+            any = true;
+        }
+    }
+    any
+}
+
+pub fn print_located(source: &str, file: &str, offset: usize, message: &str) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    eprintln!("error: {file}:{line}:{col}: {message}");
+}
+
+/// Scan a directory tree for .lumo files and collect their sources, keyed
+/// `src/<relative path>` so `src/parser/expr.lumo` keeps its directory.
+pub fn collect_lumo_files(
+    dir: &std::path::Path,
+    sources: &mut std::collections::HashMap<String, String>,
+) -> Result<(), Failed> {
+    for (name, source) in read_lumo_tree(dir)? {
+        sources.insert(name, source);
+    }
+    Ok(())
+}
+
+/// Scan a platform directory tree for .lumo files and merge them with common
+/// sources. If a common file with the same relative path exists, the
+/// platform source is appended. If no common file exists, the platform
+/// source stands alone.
+pub fn merge_lumo_files(
+    dir: &std::path::Path,
+    sources: &mut std::collections::HashMap<String, String>,
+) -> Result<(), Failed> {
+    for (name, source) in read_lumo_tree(dir)? {
+        sources
+            .entry(name)
+            .and_modify(|existing| {
+                existing.push('\n');
+                existing.push_str(&source);
+            })
+            .or_insert(source);
+    }
+    Ok(())
+}
+
+/// Every .lumo file under `root`, as `("src/<relative path>", source)`,
+/// sorted by path.
+pub fn read_lumo_tree(root: &Path) -> Result<Vec<(String, String)>, Failed> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().and_then(|e| e.to_str()) == Some("lumo") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let source = match std::fs::read_to_string(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("error: cannot read {}: {e}", path.display());
+                    return Err(Failed);
+                }
+            };
+            let relative: Vec<String> = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            Ok((format!("src/{}", relative.join("/")), source))
+        })
+        .collect()
+}
+
+pub fn check_types(type_errors: &[TypeError]) -> Result<(), Failed> {
+    if !type_errors.is_empty() {
+        for e in type_errors {
+            eprintln!("error in `{}`: {}", e.fn_name, e.message);
+        }
+        return Err(Failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target_from_spec;
+    use lumo_compiler::hir::Cfg;
+    use lumo_compiler::query::QueryEngine;
+    use std::collections::BTreeSet;
+
+    fn compile_main(src: &str) -> Result<Compiled, Failed> {
+        let mut engine = Engine {
+            queries: QueryEngine::new(),
+            roots: HashMap::new(),
+        };
+        let cfg = Cfg {
+            target: "js".to_owned(),
+            features: BTreeSet::new(),
+            declared: None,
+        };
+        let cfg = BuildCfg {
+            root: cfg,
+            deps: HashMap::new(),
+            impls: HashMap::new(),
+        };
+        let target = target_from_spec("js");
+        let sources = HashMap::from([("src/main.lumo".to_owned(), src.to_owned())]);
+        compile_sources(
+            &mut engine,
+            &sources,
+            HashMap::new(),
+            &target,
+            &cfg,
+            &Profile::dev(),
+        )
+    }
+
+    #[test]
+    fn syntax_errors_fail_a_compile_that_lowers() {
+        assert!(compile_main("fn main(): String { \"ok\" }").is_ok());
+        assert!(compile_main("fn main(): String { \"bad \\q esc\" }").is_err());
+    }
+}
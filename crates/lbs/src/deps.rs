@@ -0,0 +1,284 @@
+//! `lbs add`, `lbs update`, `lbs tree` and `lbs publish`: edit, lock,
+//! show and publish a package's deps.
+
+use std::collections::HashMap;
+use std::process;
+
+use crate::lockfile::{self, Lockfile, Update};
+use crate::manifest;
+use crate::registry::Registry;
+use crate::{
+    find_workspace, parse_value_flag, positional_args, select_one, select_packages, sync_lockfile,
+};
+
+/// `lbs add <name>[@<req>] [--path <dir>] [--dev]`: add a dep to
+/// `lumo.toml`, keeping its formatting, and lock it.
+pub fn cmd_add(args: &[String]) {
+    let workspace = find_workspace();
+    let project_root = select_one(&workspace, args, "add").root.clone();
+    let positional = positional_args(args, &["--path", "--registry", "-p", "--package"]);
+    let [spec] = positional.as_slice() else {
+        eprintln!("usage: lbs add <name>[@<version-req>] [--path <dir>] [--dev]");
+        process::exit(1);
+    };
+    let (name, req) = match spec.split_once('@') {
+        Some((name, req)) => (name, Some(req)),
+        None => (*spec, None),
+    };
+    let section = if args.iter().any(|a| a == "--dev") {
+        "dev-deps"
+    } else {
+        "deps"
+    };
+
+    let entry = match parse_value_flag(args, "--path") {
+        Some(path) => toml_edit::value(path),
+        None => {
+            let registry = Registry::locate(parse_value_flag(args, "--registry").as_deref())
+                .unwrap_or_else(|e| {
+                    eprintln!("error: {e}");
+                    process::exit(1);
+                });
+            let req = registry_req(&registry, name, req).unwrap_or_else(|e| {
+                eprintln!("error: {e}");
+                process::exit(1);
+            });
+            let mut table = toml_edit::InlineTable::new();
+            table.insert("version", req.to_string().into());
+            toml_edit::value(table)
+        }
+    };
+
+    let manifest_path = project_root.join("lumo.toml");
+    let content = std::fs::read_to_string(&manifest_path).unwrap_or_else(|e| {
+        eprintln!("error: cannot read {}: {e}", manifest_path.display());
+        process::exit(1);
+    });
+    let mut doc: toml_edit::DocumentMut = content.parse().unwrap_or_else(|e| {
+        eprintln!("error: {}: {e}", manifest_path.display());
+        process::exit(1);
+    });
+    if !doc.contains_key(section) {
+        doc[section] = toml_edit::table();
+    }
+    let Some(table) = doc[section].as_table_like_mut() else {
+        eprintln!(
+            "error: [{section}] in {} is not a table",
+            manifest_path.display()
+        );
+        process::exit(1);
+    };
+    table.insert(name, entry);
+    let updated = doc.to_string();
+    // Validate before writing, so a bad `add` leaves the manifest untouched.
+    if let Err(e) = manifest::parse(&updated, &project_root) {
+        eprintln!("error: {e}");
+        process::exit(1);
+    }
+    if let Err(e) = std::fs::write(&manifest_path, updated) {
+        eprintln!("error: cannot write {}: {e}", manifest_path.display());
+        process::exit(1);
+    }
+
+    let workspace = find_workspace();
+    sync_lockfile(&workspace, args, Update::Nothing);
+    let manifest = &select_one(&workspace, args, "add").manifest;
+    let added = manifest
+        .deps
+        .get(name)
+        .or_else(|| manifest.dev_deps.get(name));
+    match added.and_then(|d| d.version.as_ref()) {
+        Some(req) => eprintln!("added {name} {req} to [{section}]"),
+        None => eprintln!("added {name} to [{section}]"),
+    }
+}
+
+/// The requirement `lbs add` writes: the given one if some release
+/// matches it, otherwise `^<newest release>`.
+pub fn registry_req(
+    registry: &Registry,
+    name: &str,
+    req: Option<&str>,
+) -> Result<semver::VersionReq, String> {
+    let releases = registry.releases(name)?;
+    match req {
+        Some(raw) => {
+            let req = semver::VersionReq::parse(raw)
+                .map_err(|e| format!("invalid version requirement `{raw}`: {e}"))?;
+            if !releases.iter().any(|r| req.matches(&r.version)) {
+                return Err(format!("no release of `{name}` matches {req}"));
+            }
+            Ok(req)
+        }
+        None => {
+            let newest = releases
+                .iter()
+                .map(|r| &r.version)
+                .filter(|v| v.pre.is_empty())
+                .max()
+                .or_else(|| releases.iter().map(|r| &r.version).max())
+                .ok_or_else(|| format!("`{name}` has no releases"))?;
+            semver::VersionReq::parse(&format!("^{newest}")).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// `lbs update [<name>...]`: move locked versions to the newest ones the
+/// manifests allow. The lockfile is shared by the whole workspace.
+pub fn cmd_update(args: &[String]) {
+    let workspace = find_workspace();
+    let names: Vec<String> = positional_args(args, &["--registry"])
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+    let before = std::fs::read_to_string(workspace.root.join(lockfile::FILE_NAME))
+        .ok()
+        .and_then(|c| Lockfile::parse(&c).ok())
+        .unwrap_or_default();
+    let update = if names.is_empty() {
+        Update::All
+    } else {
+        Update::Only(&names)
+    };
+    let (_, after) = sync_lockfile(&workspace, args, update);
+    let after = after.unwrap_or_default();
+
+    let mut changed = false;
+    for p in &after.packages {
+        match before.get(&p.name) {
+            Some(old) if old.version == p.version => {}
+            Some(old) => eprintln!("updated {} {} -> {}", p.name, old.version, p.version),
+            None => eprintln!("locked {} {}", p.name, p.version),
+        }
+        changed |= before.get(&p.name) != Some(p);
+    }
+    for p in &before.packages {
+        if after.get(&p.name).is_none() {
+            eprintln!("removed {} {}", p.name, p.version);
+            changed = true;
+        }
+    }
+    if !changed {
+        eprintln!("{} is up to date", lockfile::FILE_NAME);
+    }
+}
+
+/// `lbs tree`: print the dependency graph, path deps with their
+/// directories and registry deps with their locked versions.
+pub fn cmd_tree(args: &[String]) {
+    let workspace = find_workspace();
+    let (_, lock) = sync_lockfile(&workspace, args, Update::Nothing);
+    for (i, package) in select_packages(&workspace, args).iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print_package_tree(&package.manifest, lock.as_ref());
+    }
+}
+
+pub fn print_package_tree(manifest: &manifest::Manifest, lock: Option<&Lockfile>) {
+    println!("{} v{}", manifest.name, manifest.version);
+
+    let mut stack = vec![manifest.name.clone()];
+    print_tree(&sorted_deps(&manifest.deps), "", lock, &mut stack);
+    let mut specs: Vec<&String> = manifest.target_configs.keys().collect();
+    specs.sort();
+    for spec in specs {
+        let deps = &manifest.target_configs[spec].deps;
+        if !deps.is_empty() {
+            println!("[target.{spec}.deps]");
+            print_tree(&sorted_deps(deps), "", lock, &mut stack);
+        }
+    }
+    if !manifest.dev_deps.is_empty() {
+        println!("[dev-deps]");
+        print_tree(&sorted_deps(&manifest.dev_deps), "", lock, &mut stack);
+    }
+}
+
+pub fn sorted_deps(deps: &HashMap<String, manifest::Dep>) -> Vec<(String, manifest::Dep)> {
+    let mut out: Vec<(String, manifest::Dep)> =
+        deps.iter().map(|(n, d)| (n.clone(), d.clone())).collect();
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+pub fn print_tree(
+    deps: &[(String, manifest::Dep)],
+    prefix: &str,
+    lock: Option<&Lockfile>,
+    stack: &mut Vec<String>,
+) {
+    for (i, (name, dep)) in deps.iter().enumerate() {
+        let (branch, indent) = if i + 1 == deps.len() {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        let (label, children) = match &dep.path {
+            Some(dir) => {
+                let dir = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+                let sub = std::fs::read_to_string(dir.join("lumo.toml"))
+                    .map_err(|e| e.to_string())
+                    .and_then(|c| manifest::parse(&c, &dir));
+                match sub {
+                    Ok(sub) => (
+                        format!("{name} v{} ({})", sub.version, dir.display()),
+                        sorted_deps(&sub.deps),
+                    ),
+                    Err(e) => (format!("{name} ({}) [{e}]", dir.display()), Vec::new()),
+                }
+            }
+            None => match lock.and_then(|l| l.get(name)) {
+                Some(locked) => {
+                    let children = locked
+                        .deps
+                        .iter()
+                        .map(|d| {
+                            let dep = manifest::Dep {
+                                path: None,
+                                version: None,
+                                features: Vec::new(),
+                            };
+                            (d.clone(), dep)
+                        })
+                        .collect();
+                    (format!("{name} v{}", locked.version), children)
+                }
+                None => (format!("{name} (not locked)"), Vec::new()),
+            },
+        };
+        if stack.contains(name) {
+            println!("{prefix}{branch}{label} (cycle)");
+            continue;
+        }
+        println!("{prefix}{branch}{label}");
+        stack.push(name.clone());
+        print_tree(&children, &format!("{prefix}{indent}"), lock, stack);
+        stack.pop();
+    }
+}
+
+/// `lbs publish`: pack this package into the registry.
+pub fn cmd_publish(args: &[String]) {
+    let workspace = find_workspace();
+    let package = select_one(&workspace, args, "publish");
+    let registry = Registry::locate(parse_value_flag(args, "--registry").as_deref())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            process::exit(1);
+        });
+    match registry.publish(&package.manifest, &package.root) {
+        Ok(release) => eprintln!(
+            "published {} {} to {} ({})",
+            release.name,
+            release.version,
+            registry.root().display(),
+            release.checksum
+        ),
+        Err(e) => {
+            eprintln!("error: {e}");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Feature selection: which `[features]` a build enables in each package,
+//! and the `#[cfg]` configuration each package's files are lowered with.
+//!
+//! The selected packages take their features from the command line. A dep
+//! gets its `default` features plus every feature that a package in the
+//! same build asks for in its dep entry (`features = [...]`).
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use lumo_compiler::hir::Cfg;
+
+use crate::manifest::{self, Manifest};
+
+/// `--features <name>,...`, `--all-features` and `--no-default-features`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub features: Vec<String>,
+    pub all: bool,
+    pub no_default: bool,
+}
+
+impl Selection {
+    pub fn from_args(features: Option<&str>, args: &[String]) -> Self {
+        Self {
+            features: features
+                .into_iter()
+                .flat_map(|list| list.split(','))
+                .map(|f| f.trim().to_owned())
+                .filter(|f| !f.is_empty())
+                .collect(),
+            all: args.iter().any(|a| a == "--all-features"),
+            no_default: args.iter().any(|a| a == "--no-default-features"),
+        }
+    }
+
+    /// No flags given, so `lbs check --all-targets` may try each
+    /// configuration itself.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The `#[cfg]` configurations for one compile: the package's own files,
+/// and each dep's by name. Also carries the package's `[impls]` choices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildCfg {
+    pub root: Cfg,
+    pub deps: HashMap<String, Cfg>,
+    pub impls: HashMap<String, String>,
+}
+
+/// The features `selection` enables in `manifest`, with everything they
+/// imply.
+pub fn enabled(manifest: &Manifest, selection: &Selection) -> Result<BTreeSet<String>, String> {
+    if let Some(unknown) = selection
+        .features
+        .iter()
+        .find(|f| !manifest.features.contains_key(*f))
+    {
+        return Err(format!("`{}` has no feature `{unknown}`", manifest.name));
+    }
+    let mut requested = selection.features.clone();
+    if !selection.no_default {
+        requested.extend(manifest.default_features.iter().cloned());
+    }
+    if selection.all {
+        requested.extend(manifest.features.keys().cloned());
+    }
+    Ok(expand(manifest, requested))
+}
+
+/// The distinct feature sets `lbs check --all-targets` checks: none, the
+/// defaults and all of them.
+pub fn configurations(manifest: &Manifest) -> Vec<BTreeSet<String>> {
+    let selections = [
+        Selection {
+            no_default: true,
+            ..Selection::default()
+        },
+        Selection::default(),
+        Selection {
+            all: true,
+            ..Selection::default()
+        },
+    ];
+    let mut sets: Vec<BTreeSet<String>> = Vec::new();
+    for selection in selections {
+        let set = enabled(manifest, &selection).expect("declared features only");
+        if !sets.contains(&set) {
+            sets.push(set);
+        }
+    }
+    sets
+}
+
+/// `#[cfg]` configurations for building `manifest` for `spec` with
+/// `features` enabled, against the deps at `deps`. A dep named after the
+/// package itself, as tests have, shares its configuration.
+pub fn build_cfg(
+    manifest: &Manifest,
+    spec: &str,
+    features: &BTreeSet<String>,
+    deps: &HashMap<String, PathBuf>,
+    dev: bool,
+) -> Result<BuildCfg, String> {
+    let cfg = |features: BTreeSet<String>, manifest: &Manifest| Cfg {
+        target: spec.to_owned(),
+        features,
+        declared: Some(manifest.features.keys().cloned().collect()),
+    };
+    let root = cfg(features.clone(), manifest);
+
+    let mut manifests: Vec<(String, Manifest)> = Vec::new();
+    for (name, path) in deps {
+        if *name == manifest.name {
+            continue;
+        }
+        let manifest_path = path.join("lumo.toml");
+        let content = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("cannot read {}: {e}", manifest_path.display()))?;
+        let dep = manifest::parse(&content, path)
+            .map_err(|e| format!("{}: {e}", manifest_path.display()))?;
+        manifests.push((name.clone(), dep));
+    }
+
+    // The features each dep is asked for, and by which package.
+    let mut entries = manifest.deps_for(spec);
+    if dev {
+        entries.extend(manifest.dev_deps.clone());
+    }
+    let askers = std::iter::once((manifest.name.clone(), entries)).chain(
+        manifests
+            .iter()
+            .map(|(_, m)| (m.name.clone(), m.deps_for(spec))),
+    );
+    let mut requested: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for (asker, entries) in askers {
+        for (name, dep) in entries {
+            for feature in dep.features {
+                requested
+                    .entry(name.clone())
+                    .or_default()
+                    .push((asker.clone(), feature));
+            }
+        }
+    }
+
+    let mut dep_cfgs = HashMap::new();
+    for (name, dep) in &manifests {
+        let mut features = dep.default_features.clone();
+        for (asker, feature) in requested.remove(name).unwrap_or_default() {
+            if !dep.features.contains_key(&feature) {
+                return Err(format!(
+                    "`{asker}` enables feature `{feature}` of `{name}`, which has no such feature"
+                ));
+            }
+            features.push(feature);
+        }
+        dep_cfgs.insert(name.clone(), cfg(expand(dep, features), dep));
+    }
+    if deps.contains_key(&manifest.name) {
+        dep_cfgs.insert(manifest.name.clone(), root.clone());
+    }
+    Ok(BuildCfg {
+        root,
+        deps: dep_cfgs,
+        impls: manifest.impls.clone(),
+    })
+}
+
+/// `features` plus every feature they imply, transitively.
+fn expand(manifest: &Manifest, features: Vec<String>) -> BTreeSet<String> {
+    let mut enabled = BTreeSet::new();
+    let mut work = features;
+    while let Some(feature) = work.pop() {
+        if enabled.insert(feature.clone()) {
+            work.extend(
+                manifest
+                    .features
+                    .get(&feature)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+        }
+    }
+    enabled
+}
+
+/// `[a, b]` for a label; `[]` when nothing is enabled.
+pub fn describe(features: &BTreeSet<String>) -> String {
+    let list: Vec<&str> = features.iter().map(String::as_str).collect();
+    format!("[{}]", list.join(", "))
+}
@@ -0,0 +1,263 @@
+//! `lumo.lock`: the exact registry versions a package was built with.
+//!
+//! The lockfile lists every registry package in the resolved graph with its
+//! version, tarball checksum and the registry deps it pulls in. Path deps
+//! are not locked; they are whatever is on disk. Resolution prefers locked
+//! versions, so the lockfile only changes when the manifest asks for
+//! something it no longer satisfies or on `lbs update`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use semver::{Version, VersionReq};
+
+use crate::manifest::Manifest;
+use crate::registry::{self, Registry};
+
+pub const FILE_NAME: &str = "lumo.lock";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Version,
+    pub checksum: String,
+    /// Registry deps, by name; their versions are their own entries.
+    pub deps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    /// Sorted by name; one version per package.
+    pub packages: Vec<LockedPackage>,
+}
+
+/// Which locked versions `sync` may move.
+pub enum Update<'a> {
+    /// Keep every locked version that still satisfies the manifest.
+    Nothing,
+    /// Re-resolve everything to the newest matching versions.
+    All,
+    /// Re-resolve just these packages.
+    Only(&'a [String]),
+}
+
+impl Lockfile {
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let table: toml::Table = content.parse().map_err(|e| format!("{e}"))?;
+        let mut packages = Vec::new();
+        let entries = match table.get("package") {
+            Some(toml::Value::Array(entries)) => entries.as_slice(),
+            Some(_) => return Err("`package` must be an array of tables".into()),
+            None => &[],
+        };
+        for entry in entries {
+            let field = |key: &str| {
+                entry
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("locked package is missing `{key}`"))
+            };
+            let deps = match entry.get("deps").and_then(|d| d.as_array()) {
+                Some(deps) => deps
+                    .iter()
+                    .map(|d| d.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("locked `deps` must be names")?,
+                None => Vec::new(),
+            };
+            packages.push(LockedPackage {
+                name: field("name")?.to_owned(),
+                version: Version::parse(field("version")?).map_err(|e| e.to_string())?,
+                checksum: field("checksum")?.to_owned(),
+                deps,
+            });
+        }
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Lockfile { packages })
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::from("# Generated by lbs. Do not edit by hand.\nversion = 1\n");
+        for p in &self.packages {
+            out.push_str("\n[[package]]\n");
+            out.push_str(&format!("name = {}\n", toml::Value::from(p.name.as_str())));
+            out.push_str(&format!("version = \"{}\"\n", p.version));
+            out.push_str(&format!("checksum = \"{}\"\n", p.checksum));
+            let deps: Vec<String> = p
+                .deps
+                .iter()
+                .map(|d| toml::Value::from(d.as_str()).to_string())
+                .collect();
+            out.push_str(&format!("deps = [{}]\n", deps.join(", ")));
+        }
+        out
+    }
+}
+
+/// Registry requirements named anywhere in the manifests: `[deps]`,
+/// `[dev-deps]` and every `[target.<spec>.deps]`, so one lockfile serves
+/// every target of every workspace member.
+pub fn registry_roots(manifests: &[&Manifest]) -> Vec<(String, VersionReq)> {
+    let mut roots: Vec<(String, VersionReq)> = Vec::new();
+    for manifest in manifests {
+        let target_deps = manifest.target_configs.values().flat_map(|c| &c.deps);
+        roots.extend(
+            manifest
+                .deps
+                .iter()
+                .chain(&manifest.dev_deps)
+                .chain(target_deps)
+                .filter(|(_, dep)| dep.path.is_none())
+                .filter_map(|(name, dep)| Some((name.clone(), dep.version.clone()?))),
+        );
+    }
+    roots.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| a.1.to_string().cmp(&b.1.to_string()))
+    });
+    roots.dedup();
+    roots
+}
+
+/// Bring the `lumo.lock` in `lock_dir` in line with the manifests,
+/// re-resolving what `update` allows, and write it back if it changed.
+/// Returns `None` (and writes nothing) when there are no registry deps and
+/// no lockfile.
+pub fn sync(
+    manifests: &[&Manifest],
+    lock_dir: &Path,
+    registry: &Registry,
+    update: Update<'_>,
+) -> Result<Option<Lockfile>, String> {
+    let path = lock_dir.join(FILE_NAME);
+    let existing = match registry::read_optional(&path)? {
+        Some(content) => {
+            Some(Lockfile::parse(&content).map_err(|e| format!("{}: {e}", path.display()))?)
+        }
+        None => None,
+    };
+    let roots = registry_roots(manifests);
+    if roots.is_empty() && existing.is_none() {
+        return Ok(None);
+    }
+
+    let mut prefer: HashMap<String, Version> = HashMap::new();
+    if let Some(lock) = &existing {
+        for p in &lock.packages {
+            let keep = match &update {
+                Update::Nothing => true,
+                Update::All => false,
+                Update::Only(names) => !names.contains(&p.name),
+            };
+            if keep {
+                prefer.insert(p.name.clone(), p.version.clone());
+            }
+        }
+    }
+
+    let releases = registry::resolve(registry, &roots, &prefer)?;
+    let mut packages = Vec::new();
+    for r in releases {
+        if let Some(old) = existing.as_ref().and_then(|l| l.get(&r.name)) {
+            if old.version == r.version && old.checksum != r.checksum {
+                return Err(format!(
+                    "{} {} in the registry no longer matches {FILE_NAME} \
+                     (checksum {} != {})",
+                    r.name, r.version, r.checksum, old.checksum
+                ));
+            }
+        }
+        packages.push(LockedPackage {
+            name: r.name,
+            version: r.version,
+            checksum: r.checksum,
+            deps: r.deps.into_keys().collect(),
+        });
+    }
+    let lock = Lockfile { packages };
+    if existing.as_ref() != Some(&lock) {
+        std::fs::write(&path, lock.render())
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    }
+    Ok(Some(lock))
+}
+
+/// Source directories of every dep a build of `spec` can `use`: path deps
+/// as written, registry deps (and their registry deps) unpacked from the
+/// registry at their locked versions. `dev` adds the `[dev-deps]`, for
+/// tests.
+pub fn dep_paths(
+    manifest: &Manifest,
+    spec: &str,
+    dev: bool,
+    lock: Option<&Lockfile>,
+    registry: &Registry,
+) -> Result<HashMap<String, PathBuf>, String> {
+    let mut paths = HashMap::new();
+    let mut work: Vec<String> = Vec::new();
+    let mut deps = manifest.deps_for(spec);
+    if dev {
+        deps.extend(manifest.dev_deps.clone());
+    }
+    for (name, dep) in deps {
+        match dep.path {
+            Some(path) => {
+                paths.insert(name, path);
+            }
+            None => work.push(name),
+        }
+    }
+
+    let mut seen: BTreeSet<String> = work.iter().cloned().collect();
+    while let Some(name) = work.pop() {
+        // A path dep of the same name overrides the registry copy.
+        if paths.contains_key(&name) {
+            continue;
+        }
+        let Some(locked) = lock.and_then(|l| l.get(&name)) else {
+            return Err(format!(
+                "`{name}` is missing from {FILE_NAME}; run `lbs update`"
+            ));
+        };
+        let dir = registry.fetch(&locked.name, &locked.version, &locked.checksum)?;
+        paths.insert(name, dir);
+        for dep in &locked.deps {
+            if seen.insert(dep.clone()) {
+                work.push(dep.clone());
+            }
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_and_parse_roundtrip() {
+        let lock = Lockfile {
+            packages: vec![
+                LockedPackage {
+                    name: "app".into(),
+                    version: Version::new(0, 1, 0),
+                    checksum: "sha256:00".into(),
+                    deps: vec!["base".into()],
+                },
+                LockedPackage {
+                    name: "base".into(),
+                    version: Version::new(1, 2, 0),
+                    checksum: "sha256:ff".into(),
+                    deps: Vec::new(),
+                },
+            ],
+        };
+        let rendered = lock.render();
+        assert!(rendered.starts_with("# Generated by lbs."));
+        assert_eq!(Lockfile::parse(&rendered).unwrap(), lock);
+    }
+}
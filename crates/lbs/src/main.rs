@@ -1,15 +1,55 @@
+mod build;
+mod cache;
+mod check;
+mod compile;
+mod deps;
+mod features;
+mod lockfile;
 mod manifest;
+mod profile;
+mod registry;
 mod resolve;
-
+mod scaffold;
+mod sourcemap;
+mod testing;
+mod timings;
+mod watch;
+mod workspace;
+
+use std::collections::{BTreeSet, HashMap};
 use std::path::PathBuf;
 use std::process;
 
-use lumo_compiler::backend::{self, CodegenTarget};
-use lumo_compiler::lir;
-use lumo_compiler::query::QueryEngine;
-use lumo_compiler::typecheck;
-
-use manifest::EntryKind;
+use lumo_compiler::backend::OptLevel;
+use lumo_compiler::query::DiskCache;
+
+use build::cmd_build;
+use check::cmd_check;
+use deps::{cmd_add, cmd_publish, cmd_tree, cmd_update};
+use features::{BuildCfg, Selection};
+use lockfile::{Lockfile, Update};
+use profile::Profile;
+use registry::Registry;
+use scaffold::{cmd_init, cmd_new};
+use testing::cmd_test;
+use workspace::{Package, Workspace};
+
+const USAGE: &str = "usage: lbs <build|check> [--target js|rust] [--release] [--opt-level 0|1|2]
+                   [--lto-report[=json]] [--timings] [--watch]
+       lbs check --all-targets (each target with no, default and all features)
+       lbs test [--target js|js.node] [--release] [--timings] [--watch] [<filter>]
+       lbs add <name>[@<version-req>] [--path <dir>] [--dev]
+       lbs update [<name>...]
+       lbs tree
+       lbs publish
+       lbs new <name> [--lib] [--targets <spec>,...] [--toolchain <dir>]
+       lbs init [--name <name>] [--lib] [--targets <spec>,...] [--toolchain <dir>]
+package selection: [-p <name>]... [--workspace] (default: the current package,
+    or every member at a workspace root)
+feature selection for build, check and test: [--features <name>,...] [--all-features]
+    [--no-default-features]
+registry commands take [--registry <dir>] (default: $LUMO_REGISTRY, then ~/.lumo/registry)
+libcore and libstd come from --toolchain <dir> (default: $LUMO_TOOLCHAIN, then ~/.lumo/toolchain)";
 
 /// A build target. The `spec` is a dotted path like `"js"`, `"js.node"`, or `"js.web"`.
 /// Directory resolution scans `src#{prefix}/` for each dotted prefix, so
@@ -42,13 +82,20 @@ fn main() {
     match subcommand {
         Some("build") => cmd_build(&args[1..]),
         Some("check") => cmd_check(&args[1..]),
+        Some("test") => cmd_test(&args[1..]),
+        Some("add") => cmd_add(&args[1..]),
+        Some("update") => cmd_update(&args[1..]),
+        Some("tree") => cmd_tree(&args[1..]),
+        Some("publish") => cmd_publish(&args[1..]),
+        Some("new") => cmd_new(&args[1..]),
+        Some("init") => cmd_init(&args[1..]),
         Some(other) => {
             eprintln!("unknown command: {other}");
-            eprintln!("usage: lbs <build|check> [--target js|rust]");
+            eprintln!("{USAGE}");
             process::exit(1);
         }
         None => {
-            eprintln!("usage: lbs <build|check> [--target js|rust]");
+            eprintln!("{USAGE}");
             process::exit(1);
         }
     }
@@ -68,338 +115,227 @@ fn parse_target_flag(args: &[String]) -> Option<String> {
     None
 }
 
-fn target_from_spec(raw: &str) -> Target {
-    let normalized = match raw {
-        "javascript" => "js",
-        "rust" => "rs",
-        other => other,
-    };
-    let base = normalized.split('.').next().unwrap_or("");
-    let backend = match base {
-        "js" => Backend::Js,
-        "rs" => Backend::Rust,
-        _ => {
-            eprintln!("unknown target: {raw}");
-            eprintln!("supported bases: js (js.node, js.web, ...), rs");
+/// Returns the value of `--<name> <value>`, or None if the flag is absent.
+fn parse_value_flag(args: &[String], name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    match args.get(i + 1) {
+        Some(value) => Some(value.clone()),
+        None => {
+            eprintln!("error: `{name}` needs a value");
             process::exit(1);
         }
-    };
-    Target {
-        backend,
-        spec: normalized.to_owned(),
     }
 }
 
-fn find_manifest() -> Result<(PathBuf, manifest::Manifest), String> {
-    let mut dir = std::env::current_dir().map_err(|e| format!("cannot get cwd: {e}"))?;
-
-    loop {
-        let candidate = dir.join("lumo.toml");
-        if candidate.exists() {
-            let content =
-                std::fs::read_to_string(&candidate).map_err(|e| format!("read error: {e}"))?;
-            let m = manifest::parse(&content, &dir)?;
-            return Ok((dir, m));
+/// Arguments that are neither flags nor the values of `value_flags`.
+fn positional_args<'a>(args: &'a [String], value_flags: &[&str]) -> Vec<&'a str> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if value_flags.contains(&args[i].as_str()) {
+            i += 2;
+            continue;
         }
-        if !dir.pop() {
-            return Err("lumo.toml not found (searched from cwd upwards)".into());
+        if !args[i].starts_with("--") {
+            out.push(args[i].as_str());
         }
+        i += 1;
     }
+    out
 }
 
-fn compile(
-    manifest: &manifest::Manifest,
-    project_root: &std::path::Path,
-    target: &Target,
-) -> lir::File {
-    let mut engine = QueryEngine::new();
-    let mut sources: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-
-    let suffixes = target.suffixes();
-
-    // Load common .lumo files from src/
-    let src_dir = project_root.join("src");
-    collect_lumo_files(&src_dir, &mut sources);
-
-    // Merge platform-specific .lumo files from src#{suffix}/ for each target prefix.
-    // Earlier suffixes are base (e.g. "js"), later are variants (e.g. "js.node").
-    for suffix in &suffixes {
-        let platform_dir = project_root.join(format!("src#{suffix}"));
-        merge_lumo_files(&platform_dir, &mut sources);
-    }
-
-    if sources.is_empty() {
-        eprintln!("error: no .lumo files found in {}", src_dir.display());
-        process::exit(1);
-    }
-
-    let mut file_names: Vec<String> = sources.keys().cloned().collect();
-    file_names.sort();
-    for name in &file_names {
-        let source = sources.get(name).expect("present by construction");
-        engine.set_file(name, source);
-    }
-
-    let file_refs: Vec<&str> = file_names.iter().map(|s| s.as_str()).collect();
-    let mut resolver = resolve::make_resolver(manifest.deps.clone(), suffixes.clone());
-    match engine.compile_with_deps(&file_refs, &mut resolver) {
-        Some(lir) => lir,
+/// Returns the `--opt-level` for the JS backend's CPS passes, or None to
+/// use the profile's.
+fn parse_opt_level_flag(args: &[String]) -> Option<OptLevel> {
+    let i = args.iter().position(|a| a == "--opt-level")?;
+    let raw = args.get(i + 1).map(|s| s.as_str()).unwrap_or("");
+    match OptLevel::parse(raw) {
+        Some(level) => Some(level),
         None => {
-            eprintln!("error: compilation failed");
+            eprintln!("error: invalid --opt-level `{raw}` (expected 0, 1 or 2)");
             process::exit(1);
         }
     }
 }
 
-/// Scan a directory for .lumo files and collect their sources.
-fn collect_lumo_files(
-    dir: &std::path::Path,
-    sources: &mut std::collections::HashMap<String, String>,
-) {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("lumo") {
-            continue;
+/// How `--lto-report` prints what LTO decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LtoReportFormat {
+    Text,
+    Json,
+}
+
+/// Returns the `--lto-report[=json]` format, or None if the flag is absent.
+fn parse_lto_report_flag(args: &[String]) -> Option<LtoReportFormat> {
+    let arg = args.iter().find(|a| a.starts_with("--lto-report"))?;
+    match arg.as_str() {
+        "--lto-report" | "--lto-report=text" => Some(LtoReportFormat::Text),
+        "--lto-report=json" => Some(LtoReportFormat::Json),
+        other => {
+            eprintln!("error: invalid `{other}` (expected --lto-report or --lto-report=json)");
+            process::exit(1);
         }
-        let source = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("error: cannot read {}: {e}", path.display());
-                process::exit(1);
-            }
-        };
-        let basename = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let name = format!("src/{basename}");
-        sources.insert(name, source);
     }
 }
 
-/// Scan a platform directory for .lumo files and merge them with common sources.
-/// If a common file with the same basename exists, the platform source is appended.
-/// If no common file exists, the platform source stands alone.
-fn merge_lumo_files(
-    dir: &std::path::Path,
-    sources: &mut std::collections::HashMap<String, String>,
-) {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("lumo") {
-            continue;
+/// Settings shared by every compile in one `lbs build`, `check` or `test`.
+struct Session {
+    /// `--release`: build with `[profile.release]` instead of `[profile.dev]`.
+    release: bool,
+    opt_level: Option<OptLevel>,
+    lto_report: Option<LtoReportFormat>,
+    /// `<workspace>/target/.lumo-cache`; off under `--lto-report`, since a
+    /// module served from the cache has no report to print.
+    cache: Option<DiskCache>,
+    timings: bool,
+    watch: bool,
+    features: Selection,
+}
+
+impl Session {
+    fn new(workspace: &Workspace, args: &[String]) -> Self {
+        let lto_report = parse_lto_report_flag(args);
+        let cache = lto_report
+            .is_none()
+            .then(|| DiskCache::new(workspace.root.join("target").join(".lumo-cache")));
+        Self {
+            release: args.iter().any(|a| a == "--release"),
+            opt_level: parse_opt_level_flag(args),
+            lto_report,
+            cache,
+            timings: args.iter().any(|a| a == "--timings"),
+            watch: args.iter().any(|a| a == "--watch"),
+            features: Selection::from_args(parse_value_flag(args, "--features").as_deref(), args),
         }
-        let source = match std::fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("error: cannot read {}: {e}", path.display());
-                process::exit(1);
-            }
-        };
-        let basename = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let name = format!("src/{basename}");
-        sources
-            .entry(name)
-            .and_modify(|existing| {
-                existing.push('\n');
-                existing.push_str(&source);
-            })
-            .or_insert(source);
     }
 }
 
-fn typecheck_or_exit(lir: &lir::File) {
-    let type_errors = typecheck::typecheck_file(lir);
-    if !type_errors.is_empty() {
-        for e in &type_errors {
-            eprintln!("error in `{}`: {}", e.fn_name, e.message);
+impl Session {
+    /// The profile `manifest` is built with, after `--opt-level`.
+    fn profile(&self, manifest: &manifest::Manifest) -> Profile {
+        let mut profile = manifest.profiles.get(self.release).clone();
+        if let Some(level) = self.opt_level {
+            profile.opt_level = level;
         }
-        process::exit(1);
+        profile
     }
 }
 
-fn cmd_build(args: &[String]) {
-    let requested = parse_target_flag(args);
-
-    let (project_root, manifest) = match find_manifest() {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("error: {e}");
+fn target_from_spec(raw: &str) -> Target {
+    let normalized = match raw {
+        "javascript" => "js",
+        "rust" => "rs",
+        other => other,
+    };
+    let base = normalized.split('.').next().unwrap_or("");
+    let backend = match base {
+        "js" => Backend::Js,
+        "rs" => Backend::Rust,
+        _ => {
+            eprintln!("unknown target: {raw}");
+            eprintln!("supported bases: js (js.node, js.web, ...), rs");
             process::exit(1);
         }
     };
-
-    let targets_to_build = resolve_build_targets(&manifest, requested.as_deref());
-    for target in &targets_to_build {
-        build_target(&project_root, &manifest, target);
+    Target {
+        backend,
+        spec: normalized.to_owned(),
     }
 }
 
-/// Pick which targets to build based on manifest config and optional CLI flag.
-/// - `--target X` with a manifest `targets` list: X must be listed (error otherwise).
-/// - `--target X` with no manifest `targets`: build X (legacy).
-/// - No `--target`, manifest `targets = [...]`: build each listed target.
-/// - No `--target`, no `targets`: build default `js`.
-fn resolve_build_targets(manifest: &manifest::Manifest, requested: Option<&str>) -> Vec<Target> {
-    match (requested, manifest.targets.is_empty()) {
-        (Some(spec), true) => vec![target_from_spec(spec)],
-        (Some(spec), false) => {
-            if !manifest.targets.iter().any(|t| t == spec) {
-                eprintln!(
-                    "error: target `{spec}` is not in manifest `targets = {:?}`",
-                    manifest.targets
-                );
-                process::exit(1);
-            }
-            vec![target_from_spec(spec)]
-        }
-        (None, true) => vec![target_from_spec("js")],
-        (None, false) => manifest
-            .targets
-            .iter()
-            .map(|s| target_from_spec(s))
-            .collect(),
-    }
+fn find_workspace() -> Workspace {
+    let cwd = std::env::current_dir().unwrap_or_else(|e| {
+        eprintln!("error: cannot get cwd: {e}");
+        process::exit(1);
+    });
+    Workspace::discover(&cwd).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        process::exit(1);
+    })
 }
 
-fn build_target(
-    project_root: &std::path::Path,
-    manifest: &manifest::Manifest,
-    target: &Target,
-) {
-    let lir = compile(manifest, project_root, target);
-    // Debug: print LIR items with their span info
-    if std::env::var("LBS_DEBUG_SPANS").is_ok() {
-        for item in &lir.items {
-            match item {
-                lir::Item::Fn(f) => {
-                    eprintln!("FN {} span={}..{}", f.name, f.span.start, f.span.end);
-                }
-                _ => {}
-            }
-        }
-    }
-    typecheck_or_exit(&lir);
-
-    match target.backend {
-        Backend::Js => build_js(&manifest, &lir),
-        Backend::Rust => build_rust(&manifest, &lir),
-    }
+/// The packages `-p` / `--workspace` select, in dependency order.
+fn select_packages<'a>(workspace: &'a Workspace, args: &[String]) -> Vec<&'a Package> {
+    workspace::select(workspace, args).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        process::exit(1);
+    })
 }
 
-fn build_js(manifest: &manifest::Manifest, lir: &lir::File) {
-    let js = match backend::emit(lir, CodegenTarget::JavaScript) {
-        Ok(js) => js,
-        Err(e) => {
-            eprintln!("error: codegen failed: {e:?}");
+/// The single package a command that edits or publishes one works on.
+fn select_one<'a>(workspace: &'a Workspace, args: &[String], command: &str) -> &'a Package {
+    match select_packages(workspace, args).as_slice() {
+        [package] => package,
+        _ => {
+            eprintln!("error: `lbs {command}` works on one package; pick it with `-p <name>`");
             process::exit(1);
         }
-    };
-
-    if let Err(e) = std::fs::create_dir_all(&manifest.out_dir) {
-        eprintln!(
-            "error: cannot create output dir {}: {e}",
-            manifest.out_dir.display()
-        );
-        process::exit(1);
-    }
-
-    let out_file = manifest.out_dir.join(format!("{}.js", manifest.name));
-    // For bin entries, auto-invoke main()
-    let js = if matches!(manifest.entry, EntryKind::Bin(_)) {
-        format!("{js}\nmain();\n")
-    } else {
-        js
-    };
-    if let Err(e) = std::fs::write(&out_file, &js) {
-        eprintln!("error: cannot write {}: {e}", out_file.display());
-        process::exit(1);
     }
-
-    eprintln!("built {}", out_file.display());
 }
 
-fn build_rust(manifest: &manifest::Manifest, lir: &lir::File) {
-    let rs_code = match backend::emit(lir, CodegenTarget::Rust) {
-        Ok(rs) => rs,
+/// Sync the workspace's `lumo.lock` against every member's manifest and
+/// the registry, exiting on error.
+fn sync_lockfile(
+    workspace: &Workspace,
+    args: &[String],
+    update: Update<'_>,
+) -> (Registry, Option<Lockfile>) {
+    let registry = Registry::locate(parse_value_flag(args, "--registry").as_deref())
+        .unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            process::exit(1);
+        });
+    let manifests: Vec<&manifest::Manifest> =
+        workspace.members.iter().map(|p| &p.manifest).collect();
+    match lockfile::sync(&manifests, &workspace.root, &registry, update) {
+        Ok(lock) => (registry, lock),
         Err(e) => {
-            eprintln!("error: codegen failed: {e:?}");
+            eprintln!("error: {e}");
             process::exit(1);
         }
-    };
-
-    // Create Cargo project structure
-    let src_dir = manifest.out_dir.join("src");
-    if let Err(e) = std::fs::create_dir_all(&src_dir) {
-        eprintln!(
-            "error: cannot create output dir {}: {e}",
-            src_dir.display()
-        );
-        process::exit(1);
     }
+}
 
-    // Write Cargo.toml (with [workspace] to prevent parent workspace detection)
-    let cargo_toml = format!(
-        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
-        manifest.name
-    );
-    let cargo_toml_path = manifest.out_dir.join("Cargo.toml");
-    if let Err(e) = std::fs::write(&cargo_toml_path, &cargo_toml) {
-        eprintln!("error: cannot write {}: {e}", cargo_toml_path.display());
+/// Source directories of `target`'s deps, fetching registry packages.
+fn dep_paths_or_exit(
+    manifest: &manifest::Manifest,
+    target: &Target,
+    dev: bool,
+    lock: Option<&Lockfile>,
+    registry: &Registry,
+) -> HashMap<String, PathBuf> {
+    lockfile::dep_paths(manifest, &target.spec, dev, lock, registry).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
         process::exit(1);
-    }
+    })
+}
 
-    // Write main.rs or lib.rs based on entry kind
-    let rs_file = match &manifest.entry {
-        EntryKind::Bin(_) => src_dir.join("main.rs"),
-        EntryKind::Lib(_) => src_dir.join("lib.rs"),
-    };
-    if let Err(e) = std::fs::write(&rs_file, &rs_code) {
-        eprintln!("error: cannot write {}: {e}", rs_file.display());
+/// The features `session` selects in `manifest`, exiting on an unknown one.
+fn enabled_features_or_exit(manifest: &manifest::Manifest, session: &Session) -> BTreeSet<String> {
+    features::enabled(manifest, &session.features).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
         process::exit(1);
-    }
-
-    eprintln!("built {}", manifest.out_dir.display());
+    })
 }
 
-fn cmd_check(args: &[String]) {
-    let requested = parse_target_flag(args);
-    let (project_root, manifest) = match find_manifest() {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("error: {e}");
-            process::exit(1);
-        }
-    };
-
-    // For check, just use the first resolved target.
-    let target = resolve_build_targets(&manifest, requested.as_deref())
-        .into_iter()
-        .next()
-        .expect("at least one target");
+/// The `#[cfg]` configurations for building `manifest` for `target`.
+fn build_cfg_or_exit(
+    manifest: &manifest::Manifest,
+    target: &Target,
+    features: &BTreeSet<String>,
+    deps: &HashMap<String, PathBuf>,
+    dev: bool,
+) -> BuildCfg {
+    features::build_cfg(manifest, &target.spec, features, deps, dev).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        process::exit(1);
+    })
+}
 
-    let lir = compile(&manifest, &project_root, &target);
+/// A build step failed after printing why. Commands exit 1 on it, except
+/// under `--watch`, which waits for the next change instead.
+struct Failed;
 
-    let type_errors = typecheck::typecheck_file(&lir);
-    if type_errors.is_empty() {
-        eprintln!("no errors");
-    } else {
-        for e in &type_errors {
-            eprintln!("error: {}", e.message);
-        }
-        process::exit(1);
-    }
+fn or_exit<T>(result: Result<T, Failed>) -> T {
+    result.unwrap_or_else(|Failed| process::exit(1))
 }
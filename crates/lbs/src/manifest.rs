@@ -1,6 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use lumo_compiler::backend::OptLevel;
+use semver::{Version, VersionReq};
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+use crate::profile::{Profile, Profiles};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EntryKind {
     /// Binary: has `main.lumo`
@@ -9,92 +17,513 @@ pub enum EntryKind {
     Lib(PathBuf),
 }
 
+/// A `[deps]` / `[dev-deps]` entry. `name = "../path"` is shorthand for
+/// `name = { path = "../path" }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dep {
+    /// A local package directory, or `None` for a registry dep.
+    pub path: Option<PathBuf>,
+    /// For path deps, checked against the dependency's own
+    /// `package.version`; for registry deps, required and resolved through
+    /// the registry index.
+    pub version: Option<VersionReq>,
+    pub features: Vec<String>,
+}
+
+/// Per-target settings from a `[target.<spec>]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetConfig {
+    /// Overrides the target's output directory (default `<out-dir>/<spec>`).
+    pub out_dir: Option<PathBuf>,
+    /// Extra deps for this target and every target it is a prefix of, so
+    /// `[target.js.deps]` also applies to `js.node`.
+    pub deps: HashMap<String, Dep>,
+}
+
+/// A `[workspace]` section: packages built together, sharing one
+/// `lumo.lock` and one output directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceConfig {
+    /// Member directories relative to the workspace root. A last component
+    /// of `*` stands for every subdirectory that has a `lumo.toml`.
+    pub members: Vec<String>,
+    /// Members without their own `out-dir` build into `<out-dir>/<name>`.
+    pub out_dir: PathBuf,
+}
+
 #[derive(Debug, Clone)]
 pub struct Manifest {
     pub name: String,
+    pub version: Version,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
     pub entry: EntryKind,
     pub out_dir: PathBuf,
-    pub deps: HashMap<String, PathBuf>,
+    pub deps: HashMap<String, Dep>,
+    /// Deps only needed by tests and examples; never linked into `lbs build`.
+    pub dev_deps: HashMap<String, Dep>,
     /// Target specs this package supports, e.g. `["js.node", "rs"]`.
     /// Empty means legacy default (single `js` target).
     pub targets: Vec<String>,
+    /// `[target.<spec>]` sections, keyed by spec.
+    pub target_configs: HashMap<String, TargetConfig>,
+    /// `[features]`: each feature `#[cfg(feature = "...")]` can test, with
+    /// the features enabling it also enables.
+    pub features: BTreeMap<String, Vec<String>>,
+    /// `[features] default`: enabled unless `--no-default-features`.
+    pub default_features: Vec<String>,
+    /// The built-in profiles with this package's `[profile.<name>]` keys.
+    pub profiles: Profiles,
+    /// `[impls]`: for a default impl key (`IO`, `"Number: Add"`) that more
+    /// than one dep gives an impl for, the dep whose impl is installed.
+    pub impls: HashMap<String, String>,
 }
 
-pub fn parse(content: &str, project_root: &Path) -> Result<Manifest, String> {
-    let mut name = None;
-    let mut out_dir = None;
-    let mut targets: Vec<String> = Vec::new();
-    let mut deps = HashMap::new();
-    let mut current_section = "";
+impl Manifest {
+    /// Where the artifacts for `spec` go: the `[target.<spec>]` `out-dir`
+    /// if set, otherwise a per-target subdirectory of `out-dir`.
+    pub fn target_out_dir(&self, spec: &str) -> PathBuf {
+        self.target_configs
+            .get(spec)
+            .and_then(|c| c.out_dir.clone())
+            .unwrap_or_else(|| self.out_dir.join(spec))
+    }
 
-    for (line_no, raw_line) in content.lines().enumerate() {
-        let line = raw_line.split('#').next().unwrap_or("").trim();
-        if line.is_empty() {
-            continue;
+    /// Package deps plus the `[target.<prefix>.deps]` of every dotted
+    /// prefix of `spec`, later (more specific) prefixes winning.
+    pub fn deps_for(&self, spec: &str) -> HashMap<String, Dep> {
+        let mut deps = self.deps.clone();
+        let parts: Vec<&str> = spec.split('.').collect();
+        for n in 1..=parts.len() {
+            if let Some(config) = self.target_configs.get(&parts[..n].join(".")) {
+                deps.extend(config.deps.clone());
+            }
         }
+        deps
+    }
 
-        if line.starts_with('[') && line.ends_with(']') {
-            current_section = raw_line.trim();
-            continue;
+    /// Check every dep that names a `version` against the `package.version`
+    /// in the dep's own `lumo.toml`.
+    pub fn check_dep_versions(&self) -> Result<(), String> {
+        let mut all: Vec<(&String, &Dep)> = self.deps.iter().chain(&self.dev_deps).collect();
+        for config in self.target_configs.values() {
+            all.extend(&config.deps);
         }
+        all.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, dep) in all {
+            let (Some(path), Some(req)) = (&dep.path, &dep.version) else {
+                continue;
+            };
+            let manifest_path = path.join("lumo.toml");
+            let content = std::fs::read_to_string(&manifest_path).map_err(|e| {
+                format!("dep `{name}`: cannot read {}: {e}", manifest_path.display())
+            })?;
+            let found = parse_version(&content)
+                .map_err(|e| format!("dep `{name}`: {}: {e}", manifest_path.display()))?;
+            if !req.matches(&found) {
+                return Err(format!(
+                    "dep `{name}` requires version {req}, but {} is {found}",
+                    path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
 
-        let Some((key, value)) = parse_key_value(line) else {
-            return Err(format!("line {}: invalid syntax: {line}", line_no + 1));
-        };
+pub fn parse(content: &str, project_root: &Path) -> Result<Manifest, String> {
+    parse_in(content, project_root, None)
+}
 
-        match current_section {
-            "[package]" => match key {
-                "name" => name = Some(unquote(value).to_owned()),
-                "out-dir" => out_dir = Some(unquote(value).to_owned()),
-                "targets" => {
-                    targets = parse_string_array(value).ok_or_else(|| {
-                        format!("line {}: targets must be a string array", line_no + 1)
-                    })?;
+/// Parse the manifest of a workspace member, whose default output
+/// directory is `<shared_out>/<name>` rather than its own `dist`.
+pub fn parse_member(
+    content: &str,
+    project_root: &Path,
+    shared_out: &Path,
+) -> Result<Manifest, String> {
+    parse_in(content, project_root, Some(shared_out))
+}
+
+/// The `[workspace]` section of a manifest, if it has one, and whether the
+/// manifest also describes a `[package]` of its own.
+pub fn parse_workspace(
+    content: &str,
+    root: &Path,
+) -> Result<Option<(WorkspaceConfig, bool)>, String> {
+    let cx = Cx { src: content, root };
+    let doc = DeTable::parse(content).map_err(|e| e.to_string())?;
+    let Some(workspace) = doc.get_ref().get("workspace") else {
+        return Ok(None);
+    };
+    let mut members = Vec::new();
+    let mut out_dir = None;
+    for (key, value) in entries(cx.table(workspace, "[workspace]")?) {
+        match key.get_ref().as_ref() {
+            "members" => members = cx.string_array(value, "members")?,
+            "out-dir" => out_dir = Some(cx.string(value, "out-dir")?),
+            other => return Err(cx.error(key.span(), format!("unknown workspace key: {other}"))),
+        }
+    }
+    let config = WorkspaceConfig {
+        members,
+        out_dir: root.join(out_dir.unwrap_or_else(|| "dist".to_owned())),
+    };
+    Ok(Some((config, doc.get_ref().contains_key("package"))))
+}
+
+fn parse_in(
+    content: &str,
+    project_root: &Path,
+    shared_out: Option<&Path>,
+) -> Result<Manifest, String> {
+    let cx = Cx {
+        src: content,
+        root: project_root,
+    };
+    let doc = DeTable::parse(content).map_err(|e| e.to_string())?;
+
+    let mut package = None;
+    let mut deps = HashMap::new();
+    let mut dev_deps = HashMap::new();
+    let mut target_configs: HashMap<String, TargetConfig> = HashMap::new();
+    let mut features = BTreeMap::new();
+    let mut default_features = Vec::new();
+    let mut profiles = Profiles::default();
+    let mut impls = HashMap::new();
+    for (key, value) in entries(doc.get_ref()) {
+        match key.get_ref().as_ref() {
+            "package" => package = Some(cx.table(value, "[package]")?),
+            "deps" => deps = cx.deps(cx.table(value, "[deps]")?, "deps")?,
+            "dev-deps" => dev_deps = cx.deps(cx.table(value, "[dev-deps]")?, "dev-deps")?,
+            "target" => cx.targets(cx.table(value, "[target]")?, "", &mut target_configs)?,
+            "features" => {
+                for (key, value) in entries(cx.table(value, "[features]")?) {
+                    let name = key.get_ref().to_string();
+                    let implied = cx.string_array(value, &name)?;
+                    if name == "default" {
+                        default_features = implied;
+                    } else {
+                        features.insert(name, implied);
+                    }
                 }
-                _ => return Err(format!("line {}: unknown package key: {key}", line_no + 1)),
-            },
-            "[deps]" => {
-                deps.insert(key.to_owned(), project_root.join(unquote(value)));
             }
-            "" => return Err(format!("line {}: key outside of section: {key}", line_no + 1)),
-            _ => return Err(format!("line {}: unknown section: {current_section}", line_no + 1)),
+            "profile" => {
+                for (key, value) in entries(cx.table(value, "[profile]")?) {
+                    let profile = match key.get_ref().as_ref() {
+                        "dev" => &mut profiles.dev,
+                        "release" => &mut profiles.release,
+                        other => {
+                            return Err(cx.error(
+                                key.span(),
+                                format!("unknown profile `{other}` (expected dev or release)"),
+                            ))
+                        }
+                    };
+                    cx.profile(cx.table(value, "a profile")?, profile)?;
+                }
+            }
+            "impls" => {
+                for (key, value) in entries(cx.table(value, "[impls]")?) {
+                    let cap = key.get_ref().to_string();
+                    let package = cx.string(value, &cap)?;
+                    impls.insert(cap, package);
+                }
+            }
+            // Read by `parse_workspace`.
+            "workspace" => {}
+            other => return Err(cx.error(key.span(), format!("unknown section: {other}"))),
+        }
+    }
+
+    let Some(package) = package else {
+        return Err("missing [package] name".into());
+    };
+    let mut name = None;
+    let mut version = None;
+    let mut description = None;
+    let mut authors = Vec::new();
+    let mut out_dir = None;
+    let mut targets: Vec<String> = Vec::new();
+    for (key, value) in entries(package) {
+        match key.get_ref().as_ref() {
+            "name" => name = Some(cx.string(value, "name")?),
+            "version" => {
+                let raw = cx.string(value, "version")?;
+                let parsed = Version::parse(&raw)
+                    .map_err(|e| cx.error(value.span(), format!("invalid version `{raw}`: {e}")))?;
+                version = Some(parsed);
+            }
+            "description" => description = Some(cx.string(value, "description")?),
+            "authors" => {
+                authors = cx.string_array(value, "authors")?;
+            }
+            "out-dir" => out_dir = Some(cx.string(value, "out-dir")?),
+            "targets" => targets = cx.string_array(value, "targets")?,
+            other => return Err(cx.error(key.span(), format!("unknown package key: {other}"))),
         }
     }
 
     let name = name.ok_or("missing [package] name")?;
-    let out_dir = out_dir.unwrap_or_else(|| "dist".to_owned());
+    let out_dir = match (out_dir, shared_out) {
+        (Some(dir), _) => project_root.join(dir),
+        (None, Some(shared)) => shared.join(&name),
+        (None, None) => project_root.join("dist"),
+    };
     let entry = detect_entry(project_root)?;
+    if !targets.is_empty() {
+        let mut configured: Vec<&String> = target_configs.keys().collect();
+        configured.sort();
+        let covers = |spec: &str| {
+            targets
+                .iter()
+                .any(|t| t == spec || t.starts_with(&format!("{spec}.")))
+        };
+        if let Some(spec) = configured.into_iter().find(|s| !covers(s)) {
+            return Err(format!(
+                "[target.{spec}] configures a target not in `targets`"
+            ));
+        }
+    }
+    if let Some(name) = deps.keys().find(|n| dev_deps.contains_key(*n)) {
+        return Err(format!("`{name}` is listed in both [deps] and [dev-deps]"));
+    }
+    let implied = features.values().chain([&default_features]).flatten();
+    if let Some(unknown) = implied.into_iter().find(|f| !features.contains_key(*f)) {
+        return Err(format!("[features] names undeclared feature `{unknown}`"));
+    }
 
     Ok(Manifest {
         name,
+        version: version.unwrap_or_else(|| Version::new(0, 0, 0)),
+        description,
+        authors,
         entry,
-        out_dir: project_root.join(out_dir),
+        out_dir,
         deps,
+        dev_deps,
         targets,
+        target_configs,
+        features,
+        default_features,
+        profiles,
+        impls,
     })
 }
 
-fn unquote(v: &str) -> &str {
-    v.strip_prefix('"')
-        .and_then(|v| v.strip_suffix('"'))
-        .unwrap_or(v)
+/// Read just `package.version` from a manifest, defaulting to `0.0.0`.
+fn parse_version(content: &str) -> Result<Version, String> {
+    let doc = DeTable::parse(content).map_err(|e| e.to_string())?;
+    let version = doc
+        .get_ref()
+        .get("package")
+        .and_then(|p| match p.get_ref() {
+            DeValue::Table(t) => t.get("version"),
+            _ => None,
+        })
+        .map(|v| match v.get_ref() {
+            DeValue::String(s) => Version::parse(s).map_err(|e| format!("invalid version: {e}")),
+            _ => Err("`version` must be a string".to_owned()),
+        });
+    version.unwrap_or_else(|| Ok(Version::new(0, 0, 0)))
+}
+
+/// Table entries in source order, so the first error reported is the first
+/// one in the file.
+fn entries<'a, 'i>(
+    table: &'a DeTable<'i>,
+) -> Vec<(
+    &'a Spanned<toml::de::DeString<'i>>,
+    &'a Spanned<DeValue<'i>>,
+)> {
+    let mut out: Vec<_> = table.iter().collect();
+    out.sort_by_key(|(k, _)| k.span().start);
+    out
 }
 
-fn parse_string_array(value: &str) -> Option<Vec<String>> {
-    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
-    let inner = inner.trim();
-    if inner.is_empty() {
-        return Some(Vec::new());
+/// Parsing context: the source text (for line/column positions) and the
+/// project root (for resolving relative paths).
+struct Cx<'a> {
+    src: &'a str,
+    root: &'a Path,
+}
+
+impl Cx<'_> {
+    fn error(&self, span: Range<usize>, message: String) -> String {
+        let before = &self.src[..span.start.min(self.src.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        format!("line {line}, column {column}: {message}")
+    }
+
+    fn table<'v, 'i>(
+        &self,
+        value: &'v Spanned<DeValue<'i>>,
+        what: &str,
+    ) -> Result<&'v DeTable<'i>, String> {
+        match value.get_ref() {
+            DeValue::Table(t) => Ok(t),
+            _ => Err(self.error(value.span(), format!("{what} must be a table"))),
+        }
     }
-    let mut out = Vec::new();
-    for part in inner.split(',') {
-        let part = part.trim();
-        let Some(s) = part.strip_prefix('"').and_then(|s| s.strip_suffix('"')) else {
-            return None;
+
+    fn string(&self, value: &Spanned<DeValue<'_>>, key: &str) -> Result<String, String> {
+        match value.get_ref() {
+            DeValue::String(s) => Ok(s.to_string()),
+            _ => Err(self.error(value.span(), format!("`{key}` must be a string"))),
+        }
+    }
+
+    fn string_array(&self, value: &Spanned<DeValue<'_>>, key: &str) -> Result<Vec<String>, String> {
+        let DeValue::Array(items) = value.get_ref() else {
+            return Err(self.error(value.span(), format!("`{key}` must be a string array")));
         };
-        out.push(s.to_owned());
+        items
+            .iter()
+            .map(|item| match item.get_ref() {
+                DeValue::String(s) => Ok(s.to_string()),
+                _ => Err(self.error(item.span(), format!("`{key}` must be a string array"))),
+            })
+            .collect()
+    }
+
+    fn boolean(&self, value: &Spanned<DeValue<'_>>, key: &str) -> Result<bool, String> {
+        match value.get_ref() {
+            DeValue::Boolean(b) => Ok(*b),
+            _ => Err(self.error(value.span(), format!("`{key}` must be true or false"))),
+        }
+    }
+
+    /// Keys of a `[profile.<name>]` section, each overriding `profile`'s.
+    fn profile(&self, table: &DeTable<'_>, profile: &mut Profile) -> Result<(), String> {
+        for (key, value) in entries(table) {
+            match key.get_ref().as_ref() {
+                "lto" => profile.lto = self.boolean(value, "lto")?,
+                "opt-level" => {
+                    let level = match value.get_ref() {
+                        DeValue::Integer(i) if i.radix() == 10 => OptLevel::parse(i.as_str()),
+                        _ => None,
+                    };
+                    profile.opt_level = level.ok_or_else(|| {
+                        self.error(value.span(), "`opt-level` must be 0, 1 or 2".into())
+                    })?;
+                }
+                "minify" => profile.minify = self.boolean(value, "minify")?,
+                "source-map" => profile.source_map = self.boolean(value, "source-map")?,
+                "debug-assertions" => {
+                    profile.debug_assertions = self.boolean(value, "debug-assertions")?
+                }
+                other => {
+                    return Err(self.error(key.span(), format!("unknown profile key: {other}")))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn deps(&self, table: &DeTable<'_>, section: &str) -> Result<HashMap<String, Dep>, String> {
+        let mut deps = HashMap::new();
+        for (key, value) in entries(table) {
+            let name = key.get_ref().to_string();
+            let dep = match value.get_ref() {
+                DeValue::String(path) => Dep {
+                    path: Some(self.root.join(path.as_ref())),
+                    version: None,
+                    features: Vec::new(),
+                },
+                DeValue::Table(fields) => self.dep_table(&name, value.span(), fields)?,
+                _ => {
+                    return Err(self.error(
+                        value.span(),
+                        format!("[{section}] `{name}` must be a path string or a table"),
+                    ))
+                }
+            };
+            deps.insert(name, dep);
+        }
+        Ok(deps)
+    }
+
+    fn dep_table(
+        &self,
+        name: &str,
+        span: Range<usize>,
+        fields: &DeTable<'_>,
+    ) -> Result<Dep, String> {
+        let mut path = None;
+        let mut version = None;
+        let mut features = Vec::new();
+        for (key, value) in entries(fields) {
+            match key.get_ref().as_ref() {
+                "path" => path = Some(self.root.join(self.string(value, "path")?)),
+                "version" => {
+                    let raw = self.string(value, "version")?;
+                    let req = VersionReq::parse(&raw).map_err(|e| {
+                        self.error(
+                            value.span(),
+                            format!("invalid version requirement `{raw}`: {e}"),
+                        )
+                    })?;
+                    version = Some(req);
+                }
+                "features" => {
+                    features = self.string_array(value, "features")?;
+                }
+                other => {
+                    return Err(
+                        self.error(key.span(), format!("unknown key in dep `{name}`: {other}"))
+                    )
+                }
+            }
+        }
+        if path.is_none() && version.is_none() {
+            return Err(self.error(span, format!("dep `{name}` needs a `path` or a `version`")));
+        }
+        Ok(Dep {
+            path,
+            version,
+            features,
+        })
+    }
+
+    /// Flatten `[target.js.node]` (a `node` table inside a `js` table) and
+    /// `[target."js.node"]` alike into the `js.node` spec.
+    fn targets(
+        &self,
+        table: &DeTable<'_>,
+        prefix: &str,
+        out: &mut HashMap<String, TargetConfig>,
+    ) -> Result<(), String> {
+        for (key, value) in entries(table) {
+            let key_name = key.get_ref().as_ref();
+            match (key_name, prefix.is_empty()) {
+                ("out-dir", false) => {
+                    let dir = self.root.join(self.string(value, "out-dir")?);
+                    out.entry(prefix.to_owned()).or_default().out_dir = Some(dir);
+                }
+                ("deps", false) => {
+                    let deps = self.deps(self.table(value, "target deps")?, "target deps")?;
+                    out.entry(prefix.to_owned()).or_default().deps = deps;
+                }
+                _ => match value.get_ref() {
+                    DeValue::Table(nested) => {
+                        let spec = if prefix.is_empty() {
+                            key_name.to_owned()
+                        } else {
+                            format!("{prefix}.{key_name}")
+                        };
+                        self.targets(nested, &spec, out)?;
+                    }
+                    _ => {
+                        return Err(
+                            self.error(key.span(), format!("unknown target key: {key_name}"))
+                        )
+                    }
+                },
+            }
+        }
+        Ok(())
     }
-    Some(out)
 }
 
 fn detect_entry(project_root: &Path) -> Result<EntryKind, String> {
@@ -110,17 +539,6 @@ fn detect_entry(project_root: &Path) -> Result<EntryKind, String> {
     }
 }
 
-fn parse_key_value(line: &str) -> Option<(&str, &str)> {
-    let eq = line.find('=')?;
-    let key = line[..eq].trim();
-    let value = line[eq + 1..].trim();
-    let value = value
-        .strip_prefix('"')
-        .and_then(|v| v.strip_suffix('"'))
-        .unwrap_or(value);
-    Some((key, value))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +572,30 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
     }
 
+    #[test]
+    fn target_out_dirs_default_per_spec_and_can_be_overridden() {
+        let tmp = std::env::temp_dir().join("lbs_test_target_out_dirs");
+        let src = tmp.join("src");
+        let _ = fs::create_dir_all(&src);
+        fs::write(src.join("main.lumo"), "fn main() := produce 1").unwrap();
+
+        let content = "[package]\nname = \"app\"\ntargets = [\"js.node\", \"rs\"]\n\n\
+                       [target.rs]\nout-dir = \"native\"\n";
+        let m = parse(content, &tmp).unwrap();
+        assert_eq!(
+            m.target_out_dir("js.node"),
+            tmp.join("dist").join("js.node")
+        );
+        assert_eq!(m.target_out_dir("rs"), tmp.join("native"));
+
+        let content = "[package]\nname = \"app\"\ntargets = [\"rs\"]\n\n\
+                       [target.js.web]\nout-dir = \"web\"\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert!(err.contains("[target.js.web]"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn errors_when_no_entry() {
         let tmp = std::env::temp_dir().join("lbs_test_no_entry");
@@ -162,7 +604,147 @@ mod tests {
         let content = "[package]\nname = \"empty\"\n";
         let result = parse(content, &tmp);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("no src/main.lumo or src/lib.lumo"));
+        assert!(result
+            .unwrap_err()
+            .contains("no src/main.lumo or src/lib.lumo"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn parses_full_schema() {
+        let tmp = std::env::temp_dir().join("lbs_test_full_schema");
+        let src = tmp.join("src");
+        let _ = fs::create_dir_all(&src);
+        fs::write(src.join("lib.lumo"), "fn hello() := produce 1").unwrap();
+
+        let content = r#"
+            [package]
+            name = "app"
+            version = "1.2.3"
+            description = "issue #42 # not a comment" # a comment
+            authors = [
+                "Ada",
+                "Grace",
+            ]
+            targets = ["js.node", "js.web"]
+
+            [deps]
+            libcore = "../libcore"
+            libstd = { path = "../libstd", version = "^0.1", features = ["fs"] }
+
+            [dev-deps]
+            check = { path = "../check" }
+
+            [target.js.deps]
+            dom = { path = "../dom" }
+
+            [target."js.node"]
+            out-dir = "bin"
+
+            [profile.release]
+            opt-level = 1
+            source-map = true
+
+            [impls]
+            IO = "libstd"
+            "Number: Add" = "libcore"
+
+            [features]
+            default = ["fs"]
+            fs = []
+            fast = ["fs"]
+        "#;
+        let m = parse(content, &tmp).unwrap();
+        assert_eq!(m.version, Version::new(1, 2, 3));
+        assert_eq!(m.description.as_deref(), Some("issue #42 # not a comment"));
+        assert_eq!(m.authors, vec!["Ada".to_owned(), "Grace".to_owned()]);
+        let libstd = &m.deps["libstd"];
+        assert_eq!(libstd.path, Some(tmp.join("../libstd")));
+        assert_eq!(libstd.version, Some(VersionReq::parse("^0.1").unwrap()));
+        assert_eq!(libstd.features, vec!["fs".to_owned()]);
+        assert!(m.dev_deps.contains_key("check"));
+        assert!(m.deps_for("js.node").contains_key("dom"));
+        assert!(!m.deps_for("rs").contains_key("dom"));
+        assert_eq!(m.target_out_dir("js.node"), tmp.join("bin"));
+        assert_eq!(m.features["fast"], vec!["fs".to_owned()]);
+        assert_eq!(m.default_features, vec!["fs".to_owned()]);
+        assert_eq!(m.profiles.dev, Profile::dev());
+        let release = m.profiles.get(true);
+        assert_eq!(release.opt_level, OptLevel::O1);
+        assert!(release.source_map && release.lto && release.minify);
+        assert_eq!(m.impls["IO"], "libstd");
+        assert_eq!(m.impls["Number: Add"], "libcore");
+
+        let undeclared = format!("{content}\nturbo = [\"nitro\"]\n");
+        let err = parse(&undeclared, &tmp).unwrap_err();
+        assert!(err.contains("undeclared feature `nitro`"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn unknown_keys_report_line_and_column() {
+        let tmp = std::env::temp_dir().join("lbs_test_unknown_keys");
+        let src = tmp.join("src");
+        let _ = fs::create_dir_all(&src);
+        fs::write(src.join("main.lumo"), "fn main() := produce 1").unwrap();
+
+        let content = "[package]\nname = \"app\"\n  edition = \"2021\"\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert_eq!(err, "line 3, column 3: unknown package key: edition");
+
+        let content = "[package]\nname = \"app\"\n\n[deps]\nx = { path = \"../x\", rev = \"1\" }\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert_eq!(err, "line 5, column 22: unknown key in dep `x`: rev");
+
+        let content = "[package]\nname = \"app\"\n\n[profile.test]\nlto = true\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert!(err.contains("unknown profile `test`"), "{err}");
+
+        let content = "[package]\nname = \"app\"\n\n[profile.dev]\nopt-level = 3\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert_eq!(err, "line 5, column 13: `opt-level` must be 0, 1 or 2");
+
+        let content = "[package]\nname = \"app\"\nversion = \"one\"\n";
+        let err = parse(content, &tmp).unwrap_err();
+        assert!(
+            err.starts_with("line 3, column 11: invalid version `one`"),
+            "{err}"
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn dep_version_is_checked_against_the_dep_manifest() {
+        let tmp = std::env::temp_dir().join("lbs_test_dep_versions");
+        let _ = fs::create_dir_all(tmp.join("app/src"));
+        let _ = fs::create_dir_all(tmp.join("dep/src"));
+        fs::write(tmp.join("app/src/main.lumo"), "fn main() := produce 1").unwrap();
+        fs::write(
+            tmp.join("dep/lumo.toml"),
+            "[package]\nname = \"dep\"\nversion = \"0.3.1\"\n",
+        )
+        .unwrap();
+
+        let ok =
+            "[package]\nname = \"app\"\n\n[deps]\ndep = { path = \"../dep\", version = \"0.3\" }\n";
+        parse(ok, &tmp.join("app"))
+            .unwrap()
+            .check_dep_versions()
+            .unwrap();
+
+        let bad =
+            "[package]\nname = \"app\"\n\n[deps]\ndep = { path = \"../dep\", version = \"^1\" }\n";
+        let err = parse(bad, &tmp.join("app"))
+            .unwrap()
+            .check_dep_versions()
+            .unwrap_err();
+        assert!(
+            err.contains("requires version ^1") && err.ends_with("is 0.3.1"),
+            "{err}"
+        );
 
         let _ = fs::remove_dir_all(&tmp);
     }
@@ -0,0 +1,124 @@
+//! Build profiles: `[profile.dev]`, used unless `--release` is given, and
+//! `[profile.release]`.
+//!
+//! Dev builds keep the output close to the source: no LTO or CPS rewrites,
+//! runtime match checks and a source map. Rust output gets LTO under every
+//! profile, since its backend can't dispatch caps at run time. Release
+//! builds run LTO and every CPS pass, drop the checks and minify. A manifest
+//! overrides single keys; `--opt-level` overrides `opt-level` for one build.
+
+use lumo_compiler::backend::{OptLevel, TsOptions};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// `"dev"` or `"release"`.
+    pub name: &'static str,
+    pub lto: bool,
+    /// Level of the CPS optimization passes on JS output.
+    pub opt_level: OptLevel,
+    /// Strip indentation and blank lines from JS output.
+    pub minify: bool,
+    /// Write `<name>.js.map` next to JS output.
+    pub source_map: bool,
+    /// Keep runtime checks the typechecker makes redundant; for Rust output,
+    /// cargo's `debug-assertions` and `overflow-checks`.
+    pub debug_assertions: bool,
+}
+
+impl Profile {
+    pub fn dev() -> Self {
+        Self {
+            name: "dev",
+            lto: false,
+            opt_level: OptLevel::O0,
+            minify: false,
+            source_map: true,
+            debug_assertions: true,
+        }
+    }
+
+    pub fn release() -> Self {
+        Self {
+            name: "release",
+            lto: true,
+            opt_level: OptLevel::O2,
+            minify: true,
+            source_map: false,
+            debug_assertions: false,
+        }
+    }
+
+    pub fn ts_options(&self) -> TsOptions {
+        TsOptions {
+            opt_level: self.opt_level,
+            debug_assertions: self.debug_assertions,
+            minify: self.minify,
+        }
+    }
+
+    /// The `[profile.<name>]` section for the generated `Cargo.toml`, so
+    /// `cargo build` (dev) or `cargo build --release` builds the Rust output
+    /// the same way: `opt-level` 0, 2 and 3 for lbs's 0, 1 and 2, source
+    /// maps as debug info and minification as stripping symbols.
+    pub fn cargo_section(&self) -> String {
+        let opt_level = match self.opt_level {
+            OptLevel::O0 => 0,
+            OptLevel::O1 => 2,
+            OptLevel::O2 => 3,
+        };
+        let mut out = format!("[profile.{}]\nopt-level = {opt_level}\n", self.name);
+        out.push_str(&format!("debug = {}\n", self.source_map));
+        out.push_str(&format!("debug-assertions = {}\n", self.debug_assertions));
+        out.push_str(&format!("overflow-checks = {}\n", self.debug_assertions));
+        out.push_str(&format!("lto = {}\n", self.lto));
+        if self.lto {
+            out.push_str("codegen-units = 1\n");
+        }
+        out.push_str(&format!("strip = {}\n", self.minify));
+        out
+    }
+}
+
+/// The `[profile.dev]` and `[profile.release]` of one package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profiles {
+    pub dev: Profile,
+    pub release: Profile,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            dev: Profile::dev(),
+            release: Profile::release(),
+        }
+    }
+}
+
+impl Profiles {
+    pub fn get(&self, release: bool) -> &Profile {
+        if release {
+            &self.release
+        } else {
+            &self.dev
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_sections_follow_the_profile() {
+        let dev = Profile::dev().cargo_section();
+        assert!(dev.starts_with("[profile.dev]\nopt-level = 0\ndebug = true\n"));
+        assert!(dev.contains("overflow-checks = true\nlto = false\nstrip = false\n"));
+
+        let mut release = Profile::release();
+        release.opt_level = OptLevel::O1;
+        let section = release.cargo_section();
+        assert!(section.starts_with("[profile.release]\nopt-level = 2\ndebug = false\n"));
+        assert!(section.contains("lto = true\ncodegen-units = 1\nstrip = true\n"));
+    }
+}
@@ -0,0 +1,517 @@
+//! Local, directory-based package registry.
+//!
+//! ```text
+//! <root>/index/<name>.toml              one [[version]] entry per release
+//! <root>/packages/<name>-<ver>.tar.gz   the published sources
+//! <root>/src/<name>-<ver>/              unpacked copies, checked against
+//!                                       the release's checksum
+//! ```
+//!
+//! Everything is plain files, so a registry can live on a shared drive or
+//! in a git repo and works offline. The root comes from `--registry`, then
+//! `LUMO_REGISTRY`, then `~/.lumo/registry`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use semver::{Version, VersionReq};
+use sha2::{Digest, Sha256};
+
+use crate::manifest::Manifest;
+
+/// Resolution re-walks the graph after every round of choices; a graph
+/// that keeps changing after this many rounds has conflicting requirements.
+const MAX_ROUNDS: usize = 64;
+
+/// One published version of a package, as listed in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub name: String,
+    pub version: Version,
+    /// `sha256:<hex>` of the package tarball.
+    pub checksum: String,
+    pub deps: BTreeMap<String, VersionReq>,
+}
+
+pub struct Registry {
+    root: PathBuf,
+}
+
+impl Registry {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The registry named by `--registry`, `LUMO_REGISTRY` or the default
+    /// `~/.lumo/registry`, in that order.
+    pub fn locate(flag: Option<&str>) -> Result<Self, String> {
+        if let Some(dir) = flag {
+            return Ok(Self::new(dir));
+        }
+        if let Some(dir) = std::env::var_os("LUMO_REGISTRY") {
+            return Ok(Self::new(dir));
+        }
+        match std::env::var_os("HOME") {
+            Some(home) => Ok(Self::new(Path::new(&home).join(".lumo").join("registry"))),
+            None => Err("no registry: pass --registry or set LUMO_REGISTRY".into()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn index_path(&self, name: &str) -> PathBuf {
+        self.root.join("index").join(format!("{name}.toml"))
+    }
+
+    fn tarball_path(&self, name: &str, version: &Version) -> PathBuf {
+        self.root
+            .join("packages")
+            .join(format!("{name}-{version}.tar.gz"))
+    }
+
+    /// Every release of `name`, oldest first.
+    pub fn releases(&self, name: &str) -> Result<Vec<Release>, String> {
+        self.read_index(name)?.ok_or_else(|| {
+            format!(
+                "package `{name}` not found in registry {}",
+                self.root.display()
+            )
+        })
+    }
+
+    fn read_index(&self, name: &str) -> Result<Option<Vec<Release>>, String> {
+        let path = self.index_path(name);
+        let Some(content) = read_optional(&path)? else {
+            return Ok(None);
+        };
+        let releases =
+            parse_index(name, &content).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Some(releases))
+    }
+
+    /// Pack the package at `project_root` into the registry and add it to
+    /// the index. Every dep must carry a version requirement, since that is
+    /// all a consumer of the registry can resolve.
+    pub fn publish(&self, manifest: &Manifest, project_root: &Path) -> Result<Release, String> {
+        let mut deps = BTreeMap::new();
+        let target_deps = manifest.target_configs.values().flat_map(|c| &c.deps);
+        for (dep_name, dep) in manifest.deps.iter().chain(target_deps) {
+            let Some(req) = &dep.version else {
+                return Err(format!(
+                    "cannot publish: dep `{dep_name}` has no version requirement"
+                ));
+            };
+            deps.insert(dep_name.clone(), req.clone());
+        }
+
+        let mut releases = self.read_index(&manifest.name)?.unwrap_or_default();
+        if releases.iter().any(|r| r.version == manifest.version) {
+            return Err(format!(
+                "{} {} is already published",
+                manifest.name, manifest.version
+            ));
+        }
+
+        let tarball = pack(
+            project_root,
+            &format!("{}-{}", manifest.name, manifest.version),
+        )?;
+        let release = Release {
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            checksum: checksum(&tarball),
+            deps,
+        };
+        let tarball_path = self.tarball_path(&release.name, &release.version);
+        write_file(&tarball_path, &tarball)?;
+
+        releases.push(release.clone());
+        releases.sort_by(|a, b| a.version.cmp(&b.version));
+        write_file(
+            &self.index_path(&release.name),
+            render_index(&releases).as_bytes(),
+        )?;
+        Ok(release)
+    }
+
+    /// The unpacked sources of `name` `version`, unpacking the tarball
+    /// first if needed. The tarball must hash to `expected` (from the
+    /// lockfile), so a registry edited after locking is caught here.
+    pub fn fetch(&self, name: &str, version: &Version, expected: &str) -> Result<PathBuf, String> {
+        let dir = self.root.join("src").join(format!("{name}-{version}"));
+        let marker = dir.join(".lumo-checksum");
+        if std::fs::read_to_string(&marker).ok().as_deref() == Some(expected) {
+            return Ok(dir);
+        }
+
+        let tarball_path = self.tarball_path(name, version);
+        let tarball = std::fs::read(&tarball_path)
+            .map_err(|e| format!("cannot read {}: {e}", tarball_path.display()))?;
+        let found = checksum(&tarball);
+        if found != expected {
+            return Err(format!(
+                "checksum mismatch for {name} {version}: lockfile has {expected}, registry has {found}"
+            ));
+        }
+
+        let staging = self
+            .root
+            .join("src")
+            .join(format!(".unpack-{name}-{version}"));
+        let _ = std::fs::remove_dir_all(&staging);
+        tar::Archive::new(GzDecoder::new(&tarball[..]))
+            .unpack(&staging)
+            .map_err(|e| format!("cannot unpack {}: {e}", tarball_path.display()))?;
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::rename(staging.join(format!("{name}-{version}")), &dir)
+            .map_err(|e| format!("cannot unpack {}: {e}", tarball_path.display()))?;
+        let _ = std::fs::remove_dir_all(&staging);
+        write_file(&marker, expected.as_bytes())?;
+        Ok(dir)
+    }
+}
+
+/// Pick one version per package so that every requirement — from `roots`
+/// and from the deps of every chosen release — is met. The newest matching
+/// version wins, except that a `prefer`red (locked) version is kept while it
+/// still matches. Returns the chosen releases sorted by name.
+pub fn resolve(
+    registry: &Registry,
+    roots: &[(String, VersionReq)],
+    prefer: &HashMap<String, Version>,
+) -> Result<Vec<Release>, String> {
+    let mut cache: HashMap<String, Vec<Release>> = HashMap::new();
+    let mut chosen: BTreeMap<String, Release> = BTreeMap::new();
+    for _ in 0..MAX_ROUNDS {
+        // Requirements reachable from the roots through the current choices.
+        let mut reqs: BTreeMap<String, Vec<(VersionReq, String)>> = BTreeMap::new();
+        let mut work: Vec<String> = Vec::new();
+        for (name, req) in roots {
+            reqs.entry(name.clone())
+                .or_default()
+                .push((req.clone(), "the manifest".to_owned()));
+            work.push(name.clone());
+        }
+        let mut seen: BTreeSet<String> = work.iter().cloned().collect();
+        while let Some(name) = work.pop() {
+            let Some(release) = chosen.get(&name) else {
+                continue;
+            };
+            for (dep, req) in &release.deps {
+                reqs.entry(dep.clone())
+                    .or_default()
+                    .push((req.clone(), format!("{name} {}", release.version)));
+                if seen.insert(dep.clone()) {
+                    work.push(dep.clone());
+                }
+            }
+        }
+
+        let mut next: BTreeMap<String, Release> = BTreeMap::new();
+        for (name, wanted) in &reqs {
+            if !cache.contains_key(name) {
+                cache.insert(name.clone(), registry.releases(name)?);
+            }
+            let releases = &cache[name];
+            let fits = |r: &&Release| wanted.iter().all(|(req, _)| req.matches(&r.version));
+            let locked = prefer
+                .get(name)
+                .and_then(|v| releases.iter().filter(fits).find(|r| &r.version == v));
+            let pick = locked.or_else(|| {
+                releases
+                    .iter()
+                    .filter(fits)
+                    .max_by(|a, b| a.version.cmp(&b.version))
+            });
+            let Some(pick) = pick else {
+                let wanted: Vec<String> = wanted
+                    .iter()
+                    .map(|(req, by)| format!("{req} (from {by})"))
+                    .collect();
+                return Err(format!(
+                    "no version of `{name}` satisfies {}",
+                    wanted.join(", ")
+                ));
+            };
+            next.insert(name.clone(), pick.clone());
+        }
+
+        if next == chosen {
+            return Ok(chosen.into_values().collect());
+        }
+        chosen = next;
+    }
+    Err("dependency resolution did not settle; the requirements conflict".into())
+}
+
+pub fn checksum(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256:{hex}")
+}
+
+/// A reproducible `.tar.gz` of `lumo.toml`, `src/` and every `src#<target>/`
+/// overlay, rooted at `prefix/`. Entries are sorted and carry no
+/// timestamps or owners, so republishing the same sources gives the same
+/// checksum.
+fn pack(project_root: &Path, prefix: &str) -> Result<Vec<u8>, String> {
+    let mut files: Vec<PathBuf> = vec![PathBuf::from("lumo.toml")];
+    let entries = std::fs::read_dir(project_root)
+        .map_err(|e| format!("cannot read {}: {e}", project_root.display()))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && (name == "src" || name.starts_with("src#")) {
+            collect_files(project_root, Path::new(&name), &mut files)?;
+        }
+    }
+    files.sort();
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for rel in &files {
+        let data = std::fs::read(project_root.join(rel))
+            .map_err(|e| format!("cannot read {}: {e}", rel.display()))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        builder
+            .append_data(&mut header, Path::new(prefix).join(rel), &data[..])
+            .map_err(|e| format!("cannot pack {}: {e}", rel.display()))?;
+    }
+    let encoder = builder
+        .into_inner()
+        .map_err(|e| format!("cannot pack: {e}"))?;
+    encoder.finish().map_err(|e| format!("cannot pack: {e}"))
+}
+
+fn collect_files(root: &Path, rel: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let dir = root.join(rel);
+    let entries =
+        std::fs::read_dir(&dir).map_err(|e| format!("cannot read {}: {e}", dir.display()))?;
+    for entry in entries.flatten() {
+        let child = rel.join(entry.file_name());
+        if entry.path().is_dir() {
+            collect_files(root, &child, out)?;
+        } else {
+            out.push(child);
+        }
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("cannot create {}: {e}", parent.display()))?;
+    }
+    let mut file =
+        std::fs::File::create(path).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    file.write_all(bytes)
+        .map_err(|e| format!("cannot write {}: {e}", path.display()))
+}
+
+fn parse_index(name: &str, content: &str) -> Result<Vec<Release>, String> {
+    let table: toml::Table = content.parse().map_err(|e| format!("{e}"))?;
+    let Some(toml::Value::Array(entries)) = table.get("version") else {
+        return Ok(Vec::new());
+    };
+    let mut releases = Vec::new();
+    for entry in entries {
+        let field = |key: &str| {
+            entry
+                .get(key)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("release entry is missing `{key}`"))
+        };
+        let version = Version::parse(field("version")?).map_err(|e| e.to_string())?;
+        let mut deps = BTreeMap::new();
+        if let Some(table) = entry.get("deps").and_then(|d| d.as_table()) {
+            for (dep, req) in table {
+                let req = req
+                    .as_str()
+                    .ok_or_else(|| format!("dep `{dep}` must be a version requirement"))?;
+                deps.insert(
+                    dep.clone(),
+                    VersionReq::parse(req).map_err(|e| e.to_string())?,
+                );
+            }
+        }
+        releases.push(Release {
+            name: name.to_owned(),
+            version,
+            checksum: field("checksum")?.to_owned(),
+            deps,
+        });
+    }
+    Ok(releases)
+}
+
+fn render_index(releases: &[Release]) -> String {
+    let mut out = String::new();
+    for (i, r) in releases.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str("[[version]]\n");
+        out.push_str(&format!("version = \"{}\"\n", r.version));
+        out.push_str(&format!("checksum = \"{}\"\n", r.checksum));
+        let deps: Vec<String> = r
+            .deps
+            .iter()
+            .map(|(dep, req)| format!("{} = \"{req}\"", toml_key(dep)))
+            .collect();
+        out.push_str(&format!("deps = {{ {} }}\n", deps.join(", ")));
+    }
+    out
+}
+
+/// A bare key if it is one, otherwise a quoted one.
+pub fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        key.to_owned()
+    } else {
+        toml::Value::from(key).to_string()
+    }
+}
+
+/// Read a whole file, for callers that treat a missing file as "absent".
+pub fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match std::fs::File::open(path) {
+        Ok(mut f) => {
+            let mut s = String::new();
+            f.read_to_string(&mut s)
+                .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+            Ok(Some(s))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("cannot read {}: {e}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest;
+    use std::fs;
+
+    fn publish(registry: &Registry, tmp: &Path, name: &str, version: &str, deps: &str) -> Release {
+        let root = tmp.join(format!("{name}-{version}"));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.lumo"),
+            format!("fn v() := produce \"{version}\""),
+        )
+        .unwrap();
+        let content =
+            format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n\n[deps]\n{deps}");
+        fs::write(root.join("lumo.toml"), &content).unwrap();
+        let m = manifest::parse(&content, &root).unwrap();
+        registry.publish(&m, &root).unwrap()
+    }
+
+    fn req(s: &str) -> VersionReq {
+        VersionReq::parse(s).unwrap()
+    }
+
+    #[test]
+    fn resolve_picks_newest_match_unless_locked() {
+        let tmp = std::env::temp_dir().join("lbs_test_registry_resolve");
+        let _ = fs::remove_dir_all(&tmp);
+        let registry = Registry::new(tmp.join("registry"));
+        publish(&registry, &tmp, "base", "1.0.0", "");
+        publish(&registry, &tmp, "base", "1.2.0", "");
+        publish(&registry, &tmp, "base", "2.0.0", "");
+        publish(
+            &registry,
+            &tmp,
+            "app",
+            "0.1.0",
+            "base = { version = \"^1.0\" }\n",
+        );
+
+        let roots = [("app".to_owned(), req("^0.1"))];
+        let picked = resolve(&registry, &roots, &HashMap::new()).unwrap();
+        let versions: Vec<String> = picked
+            .iter()
+            .map(|r| format!("{} {}", r.name, r.version))
+            .collect();
+        assert_eq!(versions, ["app 0.1.0", "base 1.2.0"]);
+
+        let prefer = HashMap::from([("base".to_owned(), Version::new(1, 0, 0))]);
+        let picked = resolve(&registry, &roots, &prefer).unwrap();
+        assert_eq!(picked[1].version, Version::new(1, 0, 0));
+
+        // A locked version the requirements no longer allow is dropped.
+        let roots = [("base".to_owned(), req("^2"))];
+        let picked = resolve(&registry, &roots, &prefer).unwrap();
+        assert_eq!(picked[0].version, Version::new(2, 0, 0));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn resolve_reports_conflicting_requirements() {
+        let tmp = std::env::temp_dir().join("lbs_test_registry_conflict");
+        let _ = fs::remove_dir_all(&tmp);
+        let registry = Registry::new(tmp.join("registry"));
+        publish(&registry, &tmp, "base", "1.0.0", "");
+        publish(&registry, &tmp, "base", "2.0.0", "");
+        publish(
+            &registry,
+            &tmp,
+            "app",
+            "0.1.0",
+            "base = { version = \"^1\" }\n",
+        );
+
+        let roots = [("app".to_owned(), req("*")), ("base".to_owned(), req("^2"))];
+        let err = resolve(&registry, &roots, &HashMap::new()).unwrap_err();
+        assert!(err.contains("no version of `base` satisfies"), "{err}");
+        assert!(err.contains("(from app 0.1.0)"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn fetch_unpacks_and_verifies_the_checksum() {
+        let tmp = std::env::temp_dir().join("lbs_test_registry_fetch");
+        let _ = fs::remove_dir_all(&tmp);
+        let registry = Registry::new(tmp.join("registry"));
+        let release = publish(&registry, &tmp, "base", "1.0.0", "");
+        let root = tmp.join("base-1.0.0");
+        let m = manifest::parse(&fs::read_to_string(root.join("lumo.toml")).unwrap(), &root);
+        let err = registry.publish(&m.unwrap(), &root).unwrap_err();
+        assert!(err.contains("already published"), "{err}");
+
+        let dir = registry
+            .fetch("base", &release.version, &release.checksum)
+            .unwrap();
+        assert!(dir.join("lumo.toml").is_file());
+        let lib = fs::read_to_string(dir.join("src/lib.lumo")).unwrap();
+        assert!(lib.contains("1.0.0"));
+
+        let tarball = registry.tarball_path("base", &release.version);
+        let mut bytes = fs::read(&tarball).unwrap();
+        bytes.push(0);
+        fs::write(&tarball, bytes).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let err = registry
+            .fetch("base", &release.version, &release.checksum)
+            .unwrap_err();
+        assert!(err.contains("checksum mismatch"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
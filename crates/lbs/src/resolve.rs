@@ -5,8 +5,10 @@ use std::path::PathBuf;
 ///
 /// Maps use-paths like `["libcore", "io"]` to `(filename, source)` pairs
 /// by looking up the package name in the deps table and reading the file.
-/// For each target suffix (in order, e.g. `["js", "js.node"]`), platform
-/// sources in `src#{suffix}/` are appended after the common `src/` source.
+/// Longer paths name nested files: `["mylib", "parser", "expr"]` reads
+/// `src/parser/expr.lumo`. For each target suffix (in order, e.g.
+/// `["js", "js.node"]`), the platform source at the same relative path under
+/// `src#{suffix}/` is appended after the common `src/` source.
 pub struct FsResolver {
     deps: HashMap<String, PathBuf>,
     /// Ordered list of directory suffixes to merge, e.g. `["js", "js.node"]`.
@@ -29,7 +31,7 @@ impl FsResolver {
         }
 
         let pkg = &path[0];
-        let module = &path[1];
+        let module = path[1..].join("/");
         let canonical_name = format!("{pkg}/{module}.lumo");
 
         if let Some(cached) = self.cache.get(&canonical_name) {
@@ -98,6 +100,35 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
     }
 
+    #[test]
+    fn resolves_nested_module_with_overlays() {
+        let tmp = std::env::temp_dir().join("lbs_test_resolve_nested");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("src/parser")).unwrap();
+        fs::create_dir_all(tmp.join("src#js.node/parser")).unwrap();
+        fs::write(tmp.join("src/parser/expr.lumo"), "fn common() := produce 1").unwrap();
+        fs::write(
+            tmp.join("src#js.node/parser/expr.lumo"),
+            "fn node_only() := produce 2",
+        )
+        .unwrap();
+
+        let deps = HashMap::from([("mylib".to_owned(), tmp.clone())]);
+        let mut resolver = FsResolver::new(deps, vec!["js".into(), "js.node".into()]);
+        let path = ["mylib".into(), "parser".into(), "expr".into()];
+        let (name, source) = resolver.resolve(&path).unwrap();
+        assert_eq!(name, "mylib/parser/expr.lumo");
+        assert!(
+            source.contains("common") && source.contains("node_only"),
+            "{source}"
+        );
+        assert!(resolver
+            .resolve(&["mylib".into(), "parser".into()])
+            .is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn returns_none_for_unknown_package() {
         let mut resolver = FsResolver::new(HashMap::new(), vec!["js".into()]);
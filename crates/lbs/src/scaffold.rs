@@ -0,0 +1,293 @@
+//! `lbs new` / `lbs init`: lay out a fresh package.
+//!
+//! A package gets a `lumo.toml`, a `src/main.lumo` or `src/lib.lumo`, an
+//! empty `src#<target>/` overlay directory per requested target and a sample
+//! test under `tests/`. `libcore` and `libstd` come from the toolchain
+//! directory as path deps, since they are not published to any registry.
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::{parse_value_flag, positional_args, target_from_spec};
+
+pub struct Options {
+    pub name: String,
+    pub lib: bool,
+    /// Target specs for `targets = [...]`; empty leaves the key out.
+    pub targets: Vec<String>,
+    /// A directory holding the `libcore` and `libstd` packages.
+    pub toolchain: PathBuf,
+}
+
+/// The toolchain named by `--toolchain`, `LUMO_TOOLCHAIN` or the default
+/// `~/.lumo/toolchain`, in that order. It must contain `libcore` and
+/// `libstd`; the result is absolute so the manifest works from anywhere.
+pub fn locate_toolchain(flag: Option<&str>) -> Result<PathBuf, String> {
+    let dir = match (flag, std::env::var_os("LUMO_TOOLCHAIN")) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Some(dir)) => PathBuf::from(dir),
+        (None, None) => match std::env::var_os("HOME") {
+            Some(home) => Path::new(&home).join(".lumo").join("toolchain"),
+            None => return Err("no toolchain: pass --toolchain or set LUMO_TOOLCHAIN".into()),
+        },
+    };
+    for lib in ["libcore", "libstd"] {
+        if !dir.join(lib).join("lumo.toml").is_file() {
+            return Err(format!(
+                "toolchain {} has no {lib} package; pass --toolchain or set LUMO_TOOLCHAIN",
+                dir.display()
+            ));
+        }
+    }
+    dir.canonicalize()
+        .map_err(|e| format!("cannot resolve toolchain {}: {e}", dir.display()))
+}
+
+/// Package names double as the first segment of `use` paths, so they must
+/// be identifiers.
+pub fn check_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid package name `{name}`: use letters, digits and `_`, not starting with a digit"
+        ))
+    }
+}
+
+/// Write the package into `root`, leaving files that already exist alone,
+/// and return the paths created, relative to `root`.
+pub fn create(root: &Path, options: &Options) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<(PathBuf, String)> = vec![
+        ("lumo.toml".into(), manifest(options)),
+        (".gitignore".into(), "/dist\n/target\n".to_owned()),
+    ];
+    if options.lib {
+        files.push(("src/lib.lumo".into(), LIB.to_owned()));
+        files.push((
+            "tests/greeting.lumo".into(),
+            LIB_TEST.replace("{name}", &options.name),
+        ));
+    } else {
+        files.push(("src/main.lumo".into(), MAIN.to_owned()));
+        files.push(("tests/basics.lumo".into(), MAIN_TEST.to_owned()));
+    }
+
+    let mut created = Vec::new();
+    for spec in &options.targets {
+        let dir = PathBuf::from(format!("src#{spec}"));
+        if !root.join(&dir).exists() {
+            mkdir(&root.join(&dir))?;
+            created.push(dir);
+        }
+    }
+    for (path, content) in files {
+        let full = root.join(&path);
+        if full.exists() {
+            continue;
+        }
+        if let Some(parent) = full.parent() {
+            mkdir(parent)?;
+        }
+        std::fs::write(&full, content)
+            .map_err(|e| format!("cannot write {}: {e}", full.display()))?;
+        created.push(path);
+    }
+    created.sort();
+    Ok(created)
+}
+
+fn mkdir(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {e}", dir.display()))
+}
+
+fn manifest(options: &Options) -> String {
+    let mut out = format!(
+        "[package]\nname = {}\nversion = \"0.1.0\"\n",
+        toml_edit::Value::from(options.name.as_str())
+    );
+    if !options.targets.is_empty() {
+        let specs: toml_edit::Array = options.targets.iter().map(String::as_str).collect();
+        out.push_str(&format!("targets = {specs}\n"));
+    }
+    out.push_str("\n[deps]\n");
+    for lib in ["libcore", "libstd"] {
+        let path = options.toolchain.join(lib).to_string_lossy().into_owned();
+        out.push_str(&format!("{lib} = {}\n", toml_edit::Value::from(path)));
+    }
+    out
+}
+
+const MAIN: &str = r#"use libcore.prelude.{String};
+use libstd.io.{IO};
+
+fn main() = IO.println("Hello, World!")
+"#;
+
+const MAIN_TEST: &str = r#"use libcore.prelude.{Number, Bool};
+use libcore.cmp.{PartialEq};
+use libcore.ops.{Add};
+use libcore.number.{NumOps};
+use libstd.io.{IO};
+use libstd.process.{Process};
+
+fn main() =
+  if 1 + 2 == 3 {
+    IO.println("basics ok")
+  } else {
+    Process.panic_with("number addition is broken")
+  }
+"#;
+
+const LIB: &str = r#"use libcore.prelude.{String};
+use libcore.string.{StrOps};
+
+fn greeting(name: String): String = "Hello, " + name + "!"
+"#;
+
+const LIB_TEST: &str = r#"use libcore.prelude.{String, Bool};
+use libcore.cmp.{PartialEq};
+use libcore.string.{StrOps};
+use libstd.io.{IO};
+use libstd.process.{Process};
+use {name}.lib.{greeting};
+
+fn main() =
+  if greeting("Lumo") == "Hello, Lumo!" {
+    IO.println("greeting ok")
+  } else {
+    Process.panic_with("unexpected greeting")
+  }
+"#;
+
+/// `lbs new <name>`: create a package in a new `<name>` directory.
+pub fn cmd_new(args: &[String]) {
+    let positional = positional_args(args, &["--targets", "--toolchain"]);
+    let [name] = positional.as_slice() else {
+        eprintln!("usage: lbs new <name> [--lib] [--targets <spec>,...] [--toolchain <dir>]");
+        process::exit(1);
+    };
+    let root = PathBuf::from(name);
+    if root.exists() {
+        eprintln!("error: {} already exists", root.display());
+        process::exit(1);
+    }
+    scaffold_package(&root, name, args);
+}
+
+/// `lbs init`: turn the current directory into a package, named after the
+/// directory unless `--name` says otherwise.
+pub fn cmd_init(args: &[String]) {
+    let root = std::env::current_dir().unwrap_or_else(|e| {
+        eprintln!("error: cannot get cwd: {e}");
+        process::exit(1);
+    });
+    if root.join("lumo.toml").exists() {
+        eprintln!("error: {} already has a lumo.toml", root.display());
+        process::exit(1);
+    }
+    let name = parse_value_flag(args, "--name").unwrap_or_else(|| {
+        root.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    });
+    scaffold_package(&root, &name, args);
+}
+
+pub fn scaffold_package(root: &Path, name: &str, args: &[String]) {
+    let targets: Vec<String> = match parse_value_flag(args, "--targets") {
+        Some(raw) => raw
+            .split(',')
+            .map(|spec| target_from_spec(spec.trim()).spec)
+            .collect(),
+        None => Vec::new(),
+    };
+    let lib = args.iter().any(|a| a == "--lib");
+    let result = check_name(name)
+        .and_then(|()| locate_toolchain(parse_value_flag(args, "--toolchain").as_deref()))
+        .and_then(|toolchain| {
+            let options = Options {
+                name: name.to_owned(),
+                lib,
+                targets: targets.clone(),
+                toolchain,
+            };
+            create(root, &options)
+        });
+    let created = result.unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        process::exit(1);
+    });
+    for path in &created {
+        eprintln!("created {}", root.join(path).display());
+    }
+    let kind = if lib { "library" } else { "binary" };
+    eprintln!("created {kind} package `{name}`");
+    if !targets.is_empty() && !targets.iter().any(|t| t.starts_with("js")) {
+        eprintln!("note: `lbs test` runs tests on node; add a js target to run tests/");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{self, EntryKind};
+    use std::fs;
+
+    #[test]
+    fn creates_a_library_that_parses_and_keeps_existing_files() {
+        let tmp = std::env::temp_dir().join("lbs_test_scaffold_lib");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("src#rs")).unwrap();
+        fs::write(tmp.join(".gitignore"), "mine\n").unwrap();
+        let options = Options {
+            name: "greet".into(),
+            lib: true,
+            targets: vec!["js.node".into(), "rs".into()],
+            toolchain: PathBuf::from("/opt/lumo"),
+        };
+
+        let created = create(&tmp, &options).unwrap();
+        assert_eq!(
+            created,
+            [
+                "lumo.toml",
+                "src/lib.lumo",
+                "src#js.node",
+                "tests/greeting.lumo"
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            fs::read_to_string(tmp.join(".gitignore")).unwrap(),
+            "mine\n"
+        );
+        let test = fs::read_to_string(tmp.join("tests/greeting.lumo")).unwrap();
+        assert!(test.contains("use greet.lib.{greeting};"));
+
+        let content = fs::read_to_string(tmp.join("lumo.toml")).unwrap();
+        let manifest = manifest::parse(&content, &tmp).unwrap();
+        assert_eq!(manifest.name, "greet");
+        assert_eq!(manifest.targets, ["js.node", "rs"]);
+        assert!(matches!(manifest.entry, EntryKind::Lib(_)));
+        assert_eq!(
+            manifest.deps["libstd"].path,
+            Some(PathBuf::from("/opt/lumo/libstd"))
+        );
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_identifiers() {
+        assert!(check_name("my_app2").is_ok());
+        assert!(check_name("my-app").is_err());
+        assert!(check_name("2app").is_err());
+        assert!(check_name("").is_err());
+    }
+}
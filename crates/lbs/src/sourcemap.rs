@@ -0,0 +1,197 @@
+//! Source maps for JS output, at function granularity.
+//!
+//! Every line of an emitted top-level `function` maps to the declaration of
+//! the Lumo fn it came from, so stack traces and debuggers land on the right
+//! fn. Functions without an origin — ones from deps, or ones LTO copied under
+//! a new name — stay unmapped. The sources are embedded, since root files
+//! may be merged from several `src#<target>/` overlays.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Where a fn is declared: an index into the map's sources and a zero-based
+/// line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    pub source: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Origin {
+    /// The origin of the byte `offset` in `text`, the `source`th source.
+    pub fn at(source: usize, text: &str, offset: usize) -> Self {
+        let before = &text[..offset.min(text.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            source,
+            line: before.matches('\n').count(),
+            column: before[line_start..].chars().count(),
+        }
+    }
+}
+
+/// A version 3 source map for `js`, written as `file`. `sources` are
+/// `(path, content)` pairs, the paths relative to the map; `origins` maps
+/// fn names to their declarations.
+pub fn function_map(
+    js: &str,
+    file: &str,
+    sources: &[(String, String)],
+    origins: &HashMap<String, Origin>,
+) -> String {
+    let mut mappings = String::new();
+    // Fields after the first are relative to the previous segment's.
+    let mut previous = Origin {
+        source: 0,
+        line: 0,
+        column: 0,
+    };
+    let mut current: Option<Origin> = None;
+    for (i, line) in js.lines().enumerate() {
+        if i > 0 {
+            mappings.push(';');
+        }
+        if let Some(name) = function_name(line) {
+            current = origins.get(name).copied();
+        }
+        let Some(origin) = current else {
+            continue;
+        };
+        mappings.push('A');
+        vlq(&mut mappings, origin.source as i64 - previous.source as i64);
+        vlq(&mut mappings, origin.line as i64 - previous.line as i64);
+        vlq(&mut mappings, origin.column as i64 - previous.column as i64);
+        previous = origin;
+        // A body ends with a `}` alone; a minified function is one line.
+        if line == "}" || (line.ends_with('}') && function_name(line).is_some()) {
+            current = None;
+        }
+    }
+
+    let paths: Vec<String> = sources.iter().map(|(p, _)| json_string(p)).collect();
+    let contents: Vec<String> = sources.iter().map(|(_, c)| json_string(c)).collect();
+    format!(
+        "{{\"version\":3,\"file\":{},\"sources\":[{}],\"sourcesContent\":[{}],\"names\":[],\"mappings\":{}}}\n",
+        json_string(file),
+        paths.join(","),
+        contents.join(","),
+        json_string(&mappings)
+    )
+}
+
+/// The path from the directory `from` to `to`, both absolute.
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    out.extend(&to[common..]);
+    out
+}
+
+/// The name declared by a top-level `function` line.
+fn function_name(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("export ").unwrap_or(line);
+    let rest = rest.strip_prefix("function ")?;
+    Some(&rest[..rest.find('(')?])
+}
+
+/// Append `value` as a base64 VLQ.
+fn vlq(out: &mut String, value: i64) {
+    const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut rest = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (rest & 0b11111) as usize;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64[digit] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_each_line_of_a_function_to_its_declaration() {
+        let source = "fn helper() = 1\n\nfn main() =\n  helper()\n";
+        let origins = HashMap::from([
+            ("helper".to_owned(), Origin::at(0, source, 0)),
+            ("main".to_owned(), Origin::at(0, source, 17)),
+        ]);
+        let js = "const LUMO_TAG = 1;\n\
+                  export function helper() {\n  return 1;\n}\n\
+                  \n\
+                  export function main() {\n  return helper();\n}\n\
+                  export function unmapped() {}\n\
+                  main();\n";
+        let map = function_map(
+            js,
+            "app.js",
+            &[("src/main.lumo".into(), source.into())],
+            &origins,
+        );
+        // `main` is two lines below `helper`: `E` is +2.
+        assert!(
+            map.contains("\"mappings\":\";AAAA;AAAA;AAAA;;AAEA;AAAA;AAAA;;\""),
+            "{map}"
+        );
+        assert!(map.contains("\"sourcesContent\":[\"fn helper() = 1\\n\\nfn main()"));
+
+        let minified = "export function helper() {return 1;}\nexport function main() {return helper();}\nmain();\n";
+        let map = function_map(minified, "app.js", &[("m".into(), source.into())], &origins);
+        assert!(map.contains("\"mappings\":\"AAAA;AAEA;\""), "{map}");
+    }
+
+    #[test]
+    fn relative_paths_climb_to_the_common_ancestor() {
+        let path = |from: &str, to: &str| relative_path(Path::new(from), Path::new(to));
+        assert_eq!(path("/w/app/dist/js", "/w/app"), PathBuf::from("../.."));
+        assert_eq!(
+            path("/w/target/lumo/app/js", "/w/packages/app"),
+            PathBuf::from("../../../../packages/app")
+        );
+    }
+
+    #[test]
+    fn encodes_vlq() {
+        let mut out = String::new();
+        for value in [0, 1, -1, 15, 16, -17, 1000] {
+            vlq(&mut out, value);
+            out.push(',');
+        }
+        assert_eq!(out, "A,C,D,e,gB,jB,w+B,");
+    }
+}
@@ -0,0 +1,246 @@
+//! `lbs test`: build and run each `tests/<name>.lumo` of a package.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
+use lumo_compiler::backend::{self, CodegenTarget};
+use lumo_compiler::lir;
+
+use crate::build::{package_targets, resolve_build_targets, Build};
+use crate::cache::{engine, Engine};
+use crate::compile::{check_types, compile_sources, Compiled};
+use crate::lockfile::Update;
+use crate::manifest;
+use crate::timings::print_timings;
+use crate::watch::{run_or_watch, watched_roots};
+use crate::workspace::Package;
+use crate::{
+    build_cfg_or_exit, dep_paths_or_exit, enabled_features_or_exit, find_workspace,
+    parse_target_flag, positional_args, select_packages, sync_lockfile, target_from_spec, Backend,
+    Failed, Session, Target,
+};
+
+/// `lbs test [<filter>]`: each `tests/<name>.lumo` is a program with its
+/// own `main`, compiled against the package (as a dep under its own name)
+/// and its dev-deps, then run on node. A test passes when it exits with
+/// status 0, so `Process.panic_with` fails it.
+pub fn cmd_test(args: &[String]) {
+    let requested = parse_target_flag(args);
+    let value_flags = [
+        "--target",
+        "--opt-level",
+        "-p",
+        "--package",
+        "--registry",
+        "--features",
+    ];
+    let filter = positional_args(args, &value_flags).first().copied();
+    let workspace = find_workspace();
+    let session = Session::new(&workspace, args);
+    let packages = select_packages(&workspace, args);
+    let (registry, lock) = sync_lockfile(&workspace, args, Update::Nothing);
+
+    let mut plan = Vec::new();
+    for package in &packages {
+        let manifest = &package.manifest;
+        if test_files(&package.root).is_empty() {
+            continue;
+        }
+        let Some(target) = test_target(manifest, requested.as_deref(), &packages) else {
+            continue;
+        };
+        let mut deps = dep_paths_or_exit(manifest, &target, true, lock.as_ref(), &registry);
+        deps.insert(manifest.name.clone(), package.root.clone());
+        let features = enabled_features_or_exit(manifest, &session);
+        let cfg = build_cfg_or_exit(manifest, &target, &features, &deps, true);
+        let out_dir = manifest.target_out_dir(&target.spec).join("tests");
+        plan.push((
+            package,
+            target,
+            deps,
+            cfg,
+            session.profile(manifest),
+            out_dir,
+        ));
+    }
+
+    let mut engines = HashMap::new();
+    let roots = watched_roots(&workspace, &packages, lock.as_ref(), &registry);
+    run_or_watch(&session, roots, || {
+        let mut passed = 0;
+        let mut failures: Vec<(String, String)> = Vec::new();
+        for (package, target, deps, cfg, profile, out_dir) in &plan {
+            let manifest = &package.manifest;
+            if let Err(e) = std::fs::create_dir_all(out_dir) {
+                eprintln!("error: cannot create output dir {}: {e}", out_dir.display());
+                return Err(Failed);
+            }
+
+            // Listed on every run, so `--watch` picks up new tests.
+            let tests = test_files(&package.root)
+                .into_iter()
+                .filter(|t| filter.is_none_or(|f| test_name(t).contains(f)));
+            for test in tests {
+                let name = test_name(&test);
+                let label = if packages.len() > 1 {
+                    format!("{}::{name}", manifest.name)
+                } else {
+                    name.clone()
+                };
+                let js_file = out_dir.join(format!("{name}.mjs"));
+                let engine = engine(&mut engines, format!("{}::{name}", manifest.name), &session);
+                let build = Build {
+                    target,
+                    cfg,
+                    profile,
+                    label: &label,
+                };
+                build_test(engine, &test, &js_file, deps.clone(), &build, &session)?;
+                let output = match process::Command::new("node").arg(&js_file).output() {
+                    Ok(output) => output,
+                    Err(e) => {
+                        eprintln!("error: cannot run node: {e}");
+                        return Err(Failed);
+                    }
+                };
+                if output.status.success() {
+                    eprintln!("test {label} ... ok");
+                    passed += 1;
+                } else {
+                    eprintln!("test {label} ... FAILED");
+                    let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+                    log.push_str(&String::from_utf8_lossy(&output.stderr));
+                    failures.push((label, log));
+                }
+            }
+        }
+
+        for (label, log) in &failures {
+            eprintln!("\n---- {label} ----\n{}", log.trim_end());
+        }
+        let status = if failures.is_empty() { "ok" } else { "FAILED" };
+        eprintln!(
+            "\ntest result: {status}. {passed} passed; {} failed",
+            failures.len()
+        );
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Failed)
+        }
+    });
+}
+
+/// `tests/*.lumo` under `root`, sorted.
+pub fn test_files(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root.join("tests")) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("lumo"))
+        .collect();
+    files.sort();
+    files
+}
+
+pub fn test_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Tests run on node, so they build for the package's first `js.node`
+/// target (or any `js` one), and for `js.node` when it lists none.
+pub fn test_target(
+    manifest: &manifest::Manifest,
+    requested: Option<&str>,
+    packages: &[&Package],
+) -> Option<Target> {
+    let target = match requested {
+        Some(_) => package_targets(manifest, requested, packages)?
+            .into_iter()
+            .next(),
+        None if manifest.targets.is_empty() => Some(target_from_spec("js.node")),
+        None => {
+            let targets = resolve_build_targets(manifest, None);
+            let node = targets.iter().position(|t| t.spec.starts_with("js.node"));
+            let js = targets.iter().position(|t| t.backend == Backend::Js);
+            node.or(js).map(|i| targets[i].clone())
+        }
+    };
+    match target {
+        Some(target) if target.backend == Backend::Js => Some(target),
+        _ => {
+            eprintln!(
+                "error: {}: `lbs test` runs tests on node and needs a js target",
+                manifest.name
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Compile one test program to `js_file`.
+pub fn build_test(
+    engine: &mut Engine,
+    test: &Path,
+    js_file: &Path,
+    deps: HashMap<String, PathBuf>,
+    build: &Build,
+    session: &Session,
+) -> Result<(), Failed> {
+    let source = match std::fs::read_to_string(test) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: cannot read {}: {e}", test.display());
+            return Err(Failed);
+        }
+    };
+    let file_name = format!("tests/{}.lumo", test_name(test));
+    let sources = HashMap::from([(file_name.clone(), source)]);
+    let Compiled {
+        lir,
+        type_errors,
+        mut timings,
+        ..
+    } = compile_sources(
+        engine,
+        &sources,
+        deps,
+        build.target,
+        build.cfg,
+        build.profile,
+    )?;
+    check_types(&type_errors)?;
+    let has_main = lir
+        .items
+        .iter()
+        .any(|item| matches!(item, lir::Item::Fn(f) if f.name == "main"));
+    if !has_main {
+        eprintln!("error: {file_name} has no `fn main`");
+        return Err(Failed);
+    }
+    let codegen_started = Instant::now();
+    let options = build.profile.ts_options();
+    let js = match backend::emit_with_ts_options(&lir, CodegenTarget::JavaScript, options) {
+        Ok(js) => js,
+        Err(e) => {
+            eprintln!("error: codegen failed for {file_name}: {e:?}");
+            return Err(Failed);
+        }
+    };
+    timings.codegen = Some(codegen_started.elapsed());
+    if session.timings {
+        print_timings(build.label, &timings);
+    }
+    if let Err(e) = std::fs::write(js_file, format!("{js}\nmain();\n")) {
+        eprintln!("error: cannot write {}: {e}", js_file.display());
+        return Err(Failed);
+    }
+    Ok(())
+}
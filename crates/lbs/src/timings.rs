@@ -0,0 +1,44 @@
+//! `--timings`: where each compile spent its time.
+
+use std::time::Duration;
+
+use lumo_compiler::query::CacheStats;
+
+/// Where one compile spent its time, for `--timings`.
+pub struct Timings {
+    pub compile: Duration,
+    pub typecheck: Duration,
+    pub codegen: Option<Duration>,
+    /// Disk cache traffic during this compile only.
+    pub hir_cache: CacheStats,
+    pub module_cache: CacheStats,
+    pub typecheck_cache: CacheStats,
+}
+
+/// The `--timings` report for one compile.
+pub fn print_timings(label: &str, timings: &Timings) {
+    let cache = if timings.module_cache == CacheStats::default() {
+        "cache off".to_owned()
+    } else {
+        let module = if timings.module_cache.hits > 0 {
+            "hit"
+        } else {
+            "miss"
+        };
+        format!(
+            "hir cache {} hit, {} miss; module cache {module}",
+            timings.hir_cache.hits, timings.hir_cache.misses
+        )
+    };
+    let typecheck = if timings.typecheck_cache.hits > 0 {
+        "  cached"
+    } else {
+        ""
+    };
+    eprintln!("timings for {label}:");
+    eprintln!("  compile   {:>10.2?}  {cache}", timings.compile);
+    eprintln!("  typecheck {:>10.2?}{typecheck}", timings.typecheck);
+    if let Some(codegen) = timings.codegen {
+        eprintln!("  codegen   {codegen:>10.2?}");
+    }
+}
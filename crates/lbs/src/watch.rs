@@ -0,0 +1,266 @@
+//! The file watcher behind `--watch`.
+//!
+//! It polls: each tick stats the `.lumo` files under every watched package's
+//! `src/`, `src#<suffix>/` and `tests/` directories, and its `lumo.toml` and
+//! `lumo.lock`, and compares their modification times and lengths with the
+//! previous tick. A few directories of sources are cheap to stat, and
+//! polling needs nothing from the platform.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::build::resolve_build_targets;
+use crate::lockfile::{self, Lockfile};
+use crate::registry::Registry;
+use crate::workspace::{Package, Workspace};
+use crate::{or_exit, Failed, Session};
+
+const POLL: Duration = Duration::from_millis(200);
+
+/// Editors often save in several writes; a change waits this long for the
+/// rest before the rebuild starts.
+const SETTLE: Duration = Duration::from_millis(50);
+
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    last: Snapshot,
+}
+
+impl Watcher {
+    /// Watch the sources of the packages at `roots`, as they are now.
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        let last = snapshot(&roots);
+        Self { roots, last }
+    }
+
+    /// Block until a watched file is added, removed or modified, and return
+    /// the changed paths.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        loop {
+            thread::sleep(POLL);
+            let mut changed = self.poll();
+            if changed.is_empty() {
+                continue;
+            }
+            thread::sleep(SETTLE);
+            changed.extend(self.poll());
+            changed.sort();
+            changed.dedup();
+            return changed;
+        }
+    }
+
+    /// The files that changed since the last poll.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let current = snapshot(&self.roots);
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, stamp)| self.last.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.extend(
+            self.last
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort();
+        self.last = current;
+        changed
+    }
+}
+
+/// Whether `changed` includes a manifest or lockfile, which the build plan
+/// was made from: rebuilding the sources is not enough.
+pub fn config_changed(changed: &[PathBuf]) -> bool {
+    changed.iter().any(|path| is_config(path))
+}
+
+fn is_config(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "lumo.toml" || name == lockfile::FILE_NAME)
+}
+
+fn snapshot(roots: &[PathBuf]) -> Snapshot {
+    let mut files = Snapshot::new();
+    for root in roots {
+        for config in ["lumo.toml", lockfile::FILE_NAME] {
+            let path = root.join(config);
+            if let Ok(meta) = std::fs::metadata(&path) {
+                files.insert(path, (meta.modified().ok(), meta.len()));
+            }
+        }
+        let Ok(entries) = std::fs::read_dir(root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name == "src" || name == "tests" || name.starts_with("src#") {
+                stat_tree(&entry.path(), &mut files);
+            }
+        }
+    }
+    files
+}
+
+fn stat_tree(dir: &Path, files: &mut Snapshot) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            stat_tree(&path, files);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("lumo") {
+            files.insert(path, (meta.modified().ok(), meta.len()));
+        }
+    }
+}
+
+/// Run `step` once, exiting 1 if it fails; under `--watch`, run it again
+/// after every change to the sources under `roots`, until interrupted. A
+/// change to a manifest or lockfile restarts the whole command instead,
+/// since the packages, deps and features to build were read from them.
+pub fn run_or_watch(
+    session: &Session,
+    roots: Vec<PathBuf>,
+    mut step: impl FnMut() -> Result<(), Failed>,
+) {
+    if !session.watch {
+        or_exit(step());
+        return;
+    }
+    let mut watcher = Watcher::new(roots);
+    loop {
+        let started = Instant::now();
+        let status = match step() {
+            Ok(()) => "finished",
+            Err(Failed) => "failed",
+        };
+        eprintln!(
+            "[watch] {status} in {:.2?}; waiting for changes",
+            started.elapsed()
+        );
+        let changed = watcher.wait();
+        let names: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+        eprintln!("\n[watch] changed: {}", names.join(", "));
+        if config_changed(&changed) {
+            eprintln!("[watch] restarting to pick up the new configuration");
+            restart();
+        }
+    }
+}
+
+/// Run this command again from scratch. On Unix the process replaces
+/// itself, so it keeps its pid and terminal and nothing waits on a child.
+pub fn restart() -> ! {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("error: cannot restart lbs: {e}");
+            process::exit(1);
+        }
+    };
+    let mut command = process::Command::new(exe);
+    command.args(std::env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = command.exec();
+        eprintln!("error: cannot restart lbs: {e}");
+        process::exit(1);
+    }
+    #[cfg(not(unix))]
+    match command.status() {
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("error: cannot restart lbs: {e}");
+            process::exit(1);
+        }
+    }
+}
+
+/// The packages whose sources `--watch` polls: the selected ones and every
+/// dep they build against, plus the workspace root holding `lumo.lock`.
+pub fn watched_roots(
+    workspace: &Workspace,
+    packages: &[&Package],
+    lock: Option<&Lockfile>,
+    registry: &Registry,
+) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = vec![workspace.root.clone()];
+    for package in packages {
+        let manifest = &package.manifest;
+        roots.push(package.root.clone());
+        for target in resolve_build_targets(manifest, None) {
+            if let Ok(deps) = lockfile::dep_paths(manifest, &target.spec, true, lock, registry) {
+                roots.extend(deps.into_values());
+            }
+        }
+    }
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn poll_reports_added_changed_and_removed_sources() {
+        let tmp = std::env::temp_dir().join("lbs_test_watch_poll");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("src#js")).unwrap();
+        fs::create_dir_all(tmp.join("dist")).unwrap();
+        fs::write(tmp.join("src#js/io.lumo"), "fn a() { a }").unwrap();
+        let mut watcher = Watcher::new(vec![tmp.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::write(tmp.join("dist/out.lumo"), "ignored").unwrap();
+        fs::create_dir_all(tmp.join("src/nested")).unwrap();
+        fs::write(tmp.join("src/nested/new.lumo"), "fn b() { b }").unwrap();
+        fs::write(tmp.join("src#js/io.lumo"), "fn a() { aa }").unwrap();
+        assert_eq!(
+            watcher.poll(),
+            vec![tmp.join("src/nested/new.lumo"), tmp.join("src#js/io.lumo")]
+        );
+
+        fs::remove_file(tmp.join("src/nested/new.lumo")).unwrap();
+        assert_eq!(watcher.poll(), vec![tmp.join("src/nested/new.lumo")]);
+        assert!(watcher.poll().is_empty());
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn poll_reports_manifest_and_lockfile_changes() {
+        let tmp = std::env::temp_dir().join("lbs_test_watch_config");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("src")).unwrap();
+        fs::write(tmp.join("src/main.lumo"), "fn main() { main }").unwrap();
+        fs::write(tmp.join("lumo.toml"), "[package]\nname = \"w\"\n").unwrap();
+        let mut watcher = Watcher::new(vec![tmp.clone()]);
+
+        fs::write(tmp.join("lumo.toml"), "[package]\nname = \"w\"\n\n[deps]\n").unwrap();
+        fs::write(tmp.join("lumo.lock"), "version = 1\n").unwrap();
+        let changed = watcher.poll();
+        assert_eq!(changed, vec![tmp.join("lumo.lock"), tmp.join("lumo.toml")]);
+        assert!(config_changed(&changed));
+
+        fs::write(tmp.join("src/main.lumo"), "fn main() { mainn }").unwrap();
+        let changed = watcher.poll();
+        assert_eq!(changed, vec![tmp.join("src/main.lumo")]);
+        assert!(!config_changed(&changed));
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
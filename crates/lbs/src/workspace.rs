@@ -0,0 +1,406 @@
+//! Workspaces: a root `lumo.toml` with `[workspace] members = [...]` that
+//! groups several packages.
+//!
+//! Members share the root's `lumo.lock` and build into one output
+//! directory. A package outside any workspace is treated as a workspace of
+//! one, so every command works on a [`Workspace`].
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::{self, Manifest, WorkspaceConfig};
+
+/// A package directory and its manifest.
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Where `lumo.lock` lives: the `[workspace]` manifest's directory, or
+    /// the package's own for a standalone package.
+    pub root: PathBuf,
+    /// Members in dependency order: each comes after every member it
+    /// depends on through a path dep. Dev-deps don't count, so a member's
+    /// tests may use a member that depends on it.
+    pub members: Vec<Package>,
+    /// The member the command was run from; `None` at a workspace root
+    /// that is not itself a package.
+    pub current: Option<usize>,
+}
+
+/// How the command line picks packages: `-p <name>` (repeatable),
+/// `--workspace` for every member, otherwise the current package (or every
+/// member when run from a workspace root without a package).
+pub fn select<'a>(workspace: &'a Workspace, args: &[String]) -> Result<Vec<&'a Package>, String> {
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "-p" || args[i] == "--package" {
+            let name = args.get(i + 1).ok_or("`-p` needs a package name")?;
+            names.push(name.as_str());
+            i += 1;
+        }
+        i += 1;
+    }
+
+    if !names.is_empty() {
+        if let Some(unknown) = names.iter().find(|n| workspace.member(n).is_none()) {
+            let known: Vec<&str> = workspace
+                .members
+                .iter()
+                .map(|p| p.manifest.name.as_str())
+                .collect();
+            return Err(format!(
+                "no package `{unknown}` in the workspace (members: {})",
+                known.join(", ")
+            ));
+        }
+        return Ok(workspace
+            .members
+            .iter()
+            .filter(|p| names.contains(&p.manifest.name.as_str()))
+            .collect());
+    }
+    match workspace.current {
+        Some(i) if !args.iter().any(|a| a == "--workspace") => Ok(vec![&workspace.members[i]]),
+        _ => Ok(workspace.members.iter().collect()),
+    }
+}
+
+impl Workspace {
+    pub fn member(&self, name: &str) -> Option<&Package> {
+        self.members.iter().find(|p| p.manifest.name == name)
+    }
+
+    /// Find the workspace for `dir`: the nearest `lumo.toml` at or above
+    /// it, and, if that is a package, the nearest `[workspace]` above it
+    /// that lists it as a member.
+    pub fn discover(dir: &Path) -> Result<Self, String> {
+        let mut dir = dir.to_path_buf();
+        loop {
+            let candidate = dir.join("lumo.toml");
+            if candidate.exists() {
+                break;
+            }
+            if !dir.pop() {
+                return Err("lumo.toml not found (searched from cwd upwards)".into());
+            }
+        }
+
+        let content = read_manifest(&dir)?;
+        if let Some((config, is_package)) = manifest::parse_workspace(&content, &dir)
+            .map_err(|e| format!("{}: {e}", dir.join("lumo.toml").display()))?
+        {
+            return Self::load(&dir, &content, &config, is_package, &dir);
+        }
+
+        let mut above = dir.clone();
+        while above.pop() {
+            if !above.join("lumo.toml").exists() {
+                continue;
+            }
+            let ws_content = read_manifest(&above)?;
+            let Some((config, is_package)) = manifest::parse_workspace(&ws_content, &above)
+                .map_err(|e| format!("{}: {e}", above.join("lumo.toml").display()))?
+            else {
+                continue;
+            };
+            if member_dirs(&above, &config)?
+                .iter()
+                .any(|m| same_dir(m, &dir))
+            {
+                return Self::load(&above, &ws_content, &config, is_package, &dir);
+            }
+            break;
+        }
+
+        let manifest = parse_package(&dir, &content, None)?;
+        Ok(Workspace {
+            root: dir.clone(),
+            members: vec![Package {
+                root: dir,
+                manifest,
+            }],
+            current: Some(0),
+        })
+    }
+
+    fn load(
+        root: &Path,
+        content: &str,
+        config: &WorkspaceConfig,
+        root_is_package: bool,
+        package_dir: &Path,
+    ) -> Result<Self, String> {
+        let mut members = Vec::new();
+        if root_is_package {
+            let manifest = parse_package(root, content, Some(&config.out_dir))?;
+            members.push(Package {
+                root: root.to_path_buf(),
+                manifest,
+            });
+        }
+        for dir in member_dirs(root, config)? {
+            let content = read_manifest(&dir)?;
+            let manifest = parse_package(&dir, &content, Some(&config.out_dir))?;
+            if let Some(other) = members
+                .iter()
+                .find(|p: &&Package| p.manifest.name == manifest.name)
+            {
+                return Err(format!(
+                    "workspace members {} and {} are both named `{}`",
+                    other.root.display(),
+                    dir.display(),
+                    manifest.name
+                ));
+            }
+            members.push(Package {
+                root: dir,
+                manifest,
+            });
+        }
+
+        let members = dependency_order(members)?;
+        let current = members.iter().position(|p| same_dir(&p.root, package_dir));
+        Ok(Workspace {
+            root: root.to_path_buf(),
+            members,
+            current,
+        })
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<String, String> {
+    let path = dir.join("lumo.toml");
+    std::fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))
+}
+
+fn parse_package(dir: &Path, content: &str, shared_out: Option<&Path>) -> Result<Manifest, String> {
+    let path = dir.join("lumo.toml");
+    let m = match shared_out {
+        Some(out) => manifest::parse_member(content, dir, out),
+        None => manifest::parse(content, dir),
+    }
+    .map_err(|e| format!("{}: {e}", path.display()))?;
+    m.check_dep_versions()?;
+    Ok(m)
+}
+
+/// Expand `members`, in order; a `*` component matches every subdirectory
+/// with a `lumo.toml`, sorted by name.
+fn member_dirs(root: &Path, config: &WorkspaceConfig) -> Result<Vec<PathBuf>, String> {
+    let mut dirs = Vec::new();
+    for pattern in &config.members {
+        match pattern.strip_suffix('*') {
+            Some(parent) if parent.is_empty() || parent.ends_with('/') => {
+                let parent = root.join(parent);
+                let entries = std::fs::read_dir(&parent)
+                    .map_err(|e| format!("workspace member `{pattern}`: {e}"))?;
+                let mut found: Vec<PathBuf> = entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.join("lumo.toml").is_file())
+                    .collect();
+                found.sort();
+                dirs.extend(found);
+            }
+            _ => {
+                let dir = root.join(pattern);
+                if !dir.join("lumo.toml").is_file() {
+                    return Err(format!(
+                        "workspace member `{pattern}` has no lumo.toml ({})",
+                        dir.display()
+                    ));
+                }
+                dirs.push(dir);
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Sort members so path deps between them build first, keeping the listed
+/// order otherwise.
+fn dependency_order(members: Vec<Package>) -> Result<Vec<Package>, String> {
+    let index: HashMap<PathBuf, usize> = members
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (canonical(&p.root), i))
+        .collect();
+    let edges: Vec<Vec<usize>> = members
+        .iter()
+        .map(|p| {
+            let m = &p.manifest;
+            let target_deps = m.target_configs.values().flat_map(|c| &c.deps);
+            let mut out: Vec<usize> = m
+                .deps
+                .iter()
+                .chain(target_deps)
+                .filter_map(|(_, dep)| index.get(&canonical(dep.path.as_ref()?)).copied())
+                .collect();
+            out.sort();
+            out.dedup();
+            out
+        })
+        .collect();
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        Visiting,
+        Done,
+    }
+    fn visit(
+        i: usize,
+        edges: &[Vec<usize>],
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        match marks[i] {
+            Mark::Done => return Ok(()),
+            Mark::Visiting => {
+                let start = stack.iter().position(|&s| s == i).unwrap_or(0);
+                let mut cycle = stack[start..].to_vec();
+                cycle.push(i);
+                return Err(cycle);
+            }
+            Mark::Unvisited => {}
+        }
+        marks[i] = Mark::Visiting;
+        stack.push(i);
+        for &dep in &edges[i] {
+            visit(dep, edges, marks, stack, order)?;
+        }
+        stack.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    let mut marks = vec![Mark::Unvisited; members.len()];
+    let mut order = Vec::new();
+    for i in 0..members.len() {
+        if let Err(cycle) = visit(i, &edges, &mut marks, &mut Vec::new(), &mut order) {
+            let names: Vec<&str> = cycle
+                .iter()
+                .map(|&c| members[c].manifest.name.as_str())
+                .collect();
+            return Err(format!(
+                "workspace members depend on each other in a cycle: {}",
+                names.join(" -> ")
+            ));
+        }
+    }
+
+    let mut slots: Vec<Option<Package>> = members.into_iter().map(Some).collect();
+    Ok(order
+        .into_iter()
+        .map(|i| slots[i].take().expect("each member is ordered once"))
+        .collect())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    canonical(a) == canonical(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn package(dir: &Path, manifest: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("lumo.toml"), manifest).unwrap();
+        fs::write(dir.join("src/lib.lumo"), "fn f() := produce 1").unwrap();
+    }
+
+    #[test]
+    fn members_are_ordered_by_path_deps_and_share_out_dir() {
+        let tmp = std::env::temp_dir().join("lbs_test_workspace_order");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(
+            tmp.join("lumo.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        package(
+            &tmp.join("packages/app"),
+            "[package]\nname = \"app\"\n\n[deps]\nutil = \"../util\"\n",
+        );
+        package(
+            &tmp.join("packages/util"),
+            "[package]\nname = \"util\"\nout-dir = \"out\"\n\n[deps]\nbase = \"../base\"\n",
+        );
+        package(&tmp.join("packages/base"), "[package]\nname = \"base\"\n");
+
+        let ws = Workspace::discover(&tmp).unwrap();
+        let names: Vec<&str> = ws
+            .members
+            .iter()
+            .map(|p| p.manifest.name.as_str())
+            .collect();
+        assert_eq!(names, ["base", "util", "app"]);
+        assert_eq!(ws.current, None);
+        assert_eq!(ws.members[0].manifest.out_dir, tmp.join("dist/base"));
+        assert_eq!(
+            ws.members[1].manifest.out_dir,
+            tmp.join("packages/util/out")
+        );
+
+        // From inside a member, that member is current but the workspace is
+        // still the root's.
+        let ws = Workspace::discover(&tmp.join("packages/util/src")).unwrap();
+        assert_eq!(ws.root, tmp);
+        let selected = select(&ws, &[]).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].manifest.name, "util");
+        let selected = select(
+            &ws,
+            &["-p".into(), "app".into(), "-p".into(), "base".into()],
+        );
+        let names: Vec<&str> = selected
+            .unwrap()
+            .iter()
+            .map(|p| p.manifest.name.as_str())
+            .collect();
+        assert_eq!(names, ["base", "app"]);
+        let err = select(&ws, &["-p".into(), "nope".into()]).unwrap_err();
+        assert!(err.contains("no package `nope`"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn dependency_cycles_between_members_are_errors() {
+        let tmp = std::env::temp_dir().join("lbs_test_workspace_cycle");
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).unwrap();
+        fs::write(
+            tmp.join("lumo.toml"),
+            "[workspace]\nmembers = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+        package(
+            &tmp.join("a"),
+            "[package]\nname = \"a\"\n\n[deps]\nb = \"../b\"\n",
+        );
+        package(
+            &tmp.join("b"),
+            "[package]\nname = \"b\"\n\n[deps]\na = \"../a\"\n",
+        );
+
+        let err = Workspace::discover(&tmp).unwrap_err();
+        assert!(err.contains("cycle: a -> b -> a"), "{err}");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,4 +1,9 @@
 pub use lumo_span::Span;
+pub use number::{decode_number, NumberKind};
+pub use string::{decode_string, escape_string, DecodedString, StringPart};
+
+mod number;
+mod string;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
@@ -34,6 +39,14 @@ pub enum Keyword {
     Impl,
     If,
     Else,
+    While,
+    For,
+    Loop,
+    Break,
+    Continue,
+    Return,
+    Try,
+    Catch,
     // LIR-specific keywords
     Lambda,
     Roll,
@@ -72,6 +85,7 @@ pub enum Symbol {
     BangEq,
     AmpAmp,
     PipePipe,
+    Question,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -124,14 +138,27 @@ pub fn lex(input: &str) -> LexOutput {
                 kind: TokenKind::Ident(token.text),
                 span: token.span,
             }),
-            LosslessTokenKind::StringLit => output.tokens.push(Token {
-                kind: TokenKind::StringLit(token.text),
-                span: token.span,
-            }),
-            LosslessTokenKind::NumberLit => output.tokens.push(Token {
-                kind: TokenKind::NumberLit(token.text),
-                span: token.span,
-            }),
+            LosslessTokenKind::StringLit => {
+                output
+                    .errors
+                    .extend(decode_string(&token.text, token.span.start).errors);
+                output.tokens.push(Token {
+                    kind: TokenKind::StringLit(token.text),
+                    span: token.span,
+                });
+            }
+            LosslessTokenKind::NumberLit => {
+                if let Err(message) = decode_number(&token.text) {
+                    output.errors.push(LexError {
+                        span: token.span,
+                        message,
+                    });
+                }
+                output.tokens.push(Token {
+                    kind: TokenKind::NumberLit(token.text),
+                    span: token.span,
+                });
+            }
             LosslessTokenKind::Symbol(sym) => output.tokens.push(Token {
                 kind: TokenKind::Symbol(sym),
                 span: token.span,
@@ -191,6 +218,16 @@ pub fn lex_lossless(input: &str) -> LosslessLexOutput {
             continue;
         }
 
+        if let Some(end) = string::scan(input, index) {
+            output.tokens.push(LosslessToken {
+                kind: LosslessTokenKind::StringLit,
+                span: Span::new(index, end),
+                text: input[index..end].to_owned(),
+            });
+            index = end;
+            continue;
+        }
+
         if is_ident_start(ch) {
             let start = index;
             index += ch.len_utf8();
@@ -222,6 +259,14 @@ pub fn lex_lossless(input: &str) -> LosslessLexOutput {
                 "impl" => LosslessTokenKind::Keyword(Keyword::Impl),
                 "if" => LosslessTokenKind::Keyword(Keyword::If),
                 "else" => LosslessTokenKind::Keyword(Keyword::Else),
+                "while" => LosslessTokenKind::Keyword(Keyword::While),
+                "for" => LosslessTokenKind::Keyword(Keyword::For),
+                "loop" => LosslessTokenKind::Keyword(Keyword::Loop),
+                "break" => LosslessTokenKind::Keyword(Keyword::Break),
+                "continue" => LosslessTokenKind::Keyword(Keyword::Continue),
+                "return" => LosslessTokenKind::Keyword(Keyword::Return),
+                "try" => LosslessTokenKind::Keyword(Keyword::Try),
+                "catch" => LosslessTokenKind::Keyword(Keyword::Catch),
                 "lambda" => LosslessTokenKind::Keyword(Keyword::Lambda),
                 "roll" => LosslessTokenKind::Keyword(Keyword::Roll),
                 "unroll" => LosslessTokenKind::Keyword(Keyword::Unroll),
@@ -237,58 +282,9 @@ pub fn lex_lossless(input: &str) -> LosslessLexOutput {
             continue;
         }
 
-        if ch == '"' {
-            let start = index;
-            index += ch.len_utf8();
-            let mut escaped = false;
-            while index < input.len() {
-                let c = next_char(input, index);
-                index += c.len_utf8();
-                if escaped {
-                    escaped = false;
-                    continue;
-                }
-                if c == '\\' {
-                    escaped = true;
-                    continue;
-                }
-                if c == '"' {
-                    break;
-                }
-            }
-            output.tokens.push(LosslessToken {
-                kind: LosslessTokenKind::StringLit,
-                span: Span::new(start, index),
-                text: input[start..index].to_owned(),
-            });
-            continue;
-        }
-
         if ch.is_ascii_digit() {
             let start = index;
-            index += ch.len_utf8();
-            while index < input.len() {
-                let c = next_char(input, index);
-                if !c.is_ascii_digit() {
-                    break;
-                }
-                index += c.len_utf8();
-            }
-            if index < input.len() && next_char(input, index) == '.' {
-                let dot_pos = index;
-                index += 1;
-                if index < input.len() && next_char(input, index).is_ascii_digit() {
-                    while index < input.len() {
-                        let c = next_char(input, index);
-                        if !c.is_ascii_digit() {
-                            break;
-                        }
-                        index += c.len_utf8();
-                    }
-                } else {
-                    index = dot_pos;
-                }
-            }
+            index = number::scan(input, index);
             output.tokens.push(LosslessToken {
                 kind: LosslessTokenKind::NumberLit,
                 span: Span::new(start, index),
@@ -421,6 +417,7 @@ pub fn lex_lossless(input: &str) -> LosslessLexOutput {
             '!' => Some(Symbol::Bang),
             '<' => Some(Symbol::Lt),
             '>' => Some(Symbol::Gt),
+            '?' => Some(Symbol::Question),
             _ => None,
         };
 
@@ -0,0 +1,130 @@
+//! Number literals: where they end and what they decode to.
+//!
+//! A literal is decimal digits with an optional `.` fraction, or an integer
+//! in hex (`0x`), octal (`0o`) or binary (`0b`); `_` may separate digits.
+//! A suffix picks the type: none for `Number`, `i` for `Int` and `f` for
+//! `Float`. Hex digits include `f`, so only decimal literals can be `Float`.
+//!
+//! Literals decode to a canonical decimal form that keeps the suffix —
+//! `0xff_i` is `255i` — and lexes back to the same value, so the IR text
+//! formats and the backends only ever see decimal.
+
+/// The type a number literal has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberKind {
+    Number,
+    Int,
+    Float,
+}
+
+impl NumberKind {
+    /// The kind of a canonical literal, as [`decode_number`] returns it.
+    pub fn of(value: &str) -> Self {
+        if value.ends_with('i') {
+            NumberKind::Int
+        } else if value.ends_with('f') {
+            NumberKind::Float
+        } else {
+            NumberKind::Number
+        }
+    }
+
+    pub fn type_name(self) -> &'static str {
+        match self {
+            NumberKind::Number => "Number",
+            NumberKind::Int => "Int",
+            NumberKind::Float => "Float",
+        }
+    }
+}
+
+/// The canonical form of the number literal `text`, or why it is invalid.
+pub fn decode_number(text: &str) -> Result<String, String> {
+    if let Some((radix, name, digits)) = radix_prefix(text) {
+        let (digits, kind) = match digits.strip_suffix('i') {
+            Some(digits) => (digits, NumberKind::Int),
+            None => (digits, NumberKind::Number),
+        };
+        let digits: String = digits.chars().filter(|&c| c != '_').collect();
+        if digits.is_empty() {
+            return Err(format!("{name} literal has no digits"));
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+            return Err(format!("invalid digit `{c}` in {name} literal"));
+        }
+        let value = u128::from_str_radix(&digits, radix)
+            .map_err(|_| format!("{name} literal is too large"))?;
+        return canonical(value.to_string(), kind);
+    }
+
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '_' || c == '.'))
+        .unwrap_or(text.len());
+    let digits: String = text[..end].chars().filter(|&c| c != '_').collect();
+    let kind = match &text[end..] {
+        "" => NumberKind::Number,
+        "i" => NumberKind::Int,
+        "f" => NumberKind::Float,
+        suffix => {
+            return Err(format!(
+                "unknown number suffix `{suffix}` (expected `i` or `f`)"
+            ))
+        }
+    };
+    if kind == NumberKind::Int && digits.contains('.') {
+        return Err("an `Int` literal can't have a fraction".to_owned());
+    }
+    canonical(digits, kind)
+}
+
+fn canonical(digits: String, kind: NumberKind) -> Result<String, String> {
+    match kind {
+        NumberKind::Number => Ok(digits),
+        NumberKind::Int => {
+            if digits.parse::<i128>().is_err() {
+                return Err("`Int` literal does not fit in 128 bits".to_owned());
+            }
+            Ok(format!("{digits}i"))
+        }
+        NumberKind::Float => Ok(format!("{digits}f")),
+    }
+}
+
+/// The end of the number literal starting with the digit at `index`. A
+/// fraction needs a digit after the `.`, so `1.to_string()` stays a call.
+pub(crate) fn scan(input: &str, index: usize) -> usize {
+    let bytes = input.as_bytes();
+    let mut end = index;
+    let word = |end: &mut usize| {
+        while *end < bytes.len() && (bytes[*end].is_ascii_alphanumeric() || bytes[*end] == b'_') {
+            *end += 1;
+        }
+    };
+    if radix_prefix(&input[index..]).is_some() {
+        end += 2;
+        word(&mut end);
+        return end;
+    }
+    while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'_') {
+        end += 1;
+    }
+    if end + 1 < bytes.len() && bytes[end] == b'.' && bytes[end + 1].is_ascii_digit() {
+        end += 1;
+        while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'_') {
+            end += 1;
+        }
+    }
+    // A suffix, valid or not, belongs to the literal.
+    word(&mut end);
+    end
+}
+
+fn radix_prefix(text: &str) -> Option<(u32, &'static str, &str)> {
+    if let Some(digits) = text.strip_prefix("0x") {
+        Some((16, "hex", digits))
+    } else if let Some(digits) = text.strip_prefix("0o") {
+        Some((8, "octal", digits))
+    } else {
+        text.strip_prefix("0b").map(|digits| (2, "binary", digits))
+    }
+}
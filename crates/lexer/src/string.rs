@@ -0,0 +1,386 @@
+//! String literals: where they end and what they decode to.
+//!
+//! Three forms lex as one [`LosslessTokenKind::StringLit`](crate::LosslessTokenKind):
+//!
+//! - `"..."`, with escapes and `${expr}` interpolation;
+//! - `"""..."""`, the same over several lines: a newline right after the
+//!   opening quotes is dropped, and when the closing quotes sit on a line of
+//!   their own, that line's indentation is stripped from every line;
+//! - `r"..."`, raw: no escapes or interpolation. `r#"..."#` takes as many
+//!   `#` as needed for `"` to appear inside.
+//!
+//! The escapes are `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\$` and `\u{X}`,
+//! with one to six hex digits naming a Unicode scalar value. Any other
+//! character after a backslash is an error, so every backend sees the same
+//! decoded text.
+
+use crate::{LexError, Span};
+
+/// A piece of a decoded string literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringPart {
+    Text(String),
+    /// `${expr}`: the source of `expr` and its span in the file.
+    Interp {
+        source: String,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DecodedString {
+    pub parts: Vec<StringPart>,
+    pub errors: Vec<LexError>,
+}
+
+impl DecodedString {
+    /// The decoded text, or `None` if the literal interpolates.
+    pub fn text(&self) -> Option<String> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                StringPart::Text(text) => out.push_str(text),
+                StringPart::Interp { .. } => return None,
+            }
+        }
+        Some(out)
+    }
+}
+
+/// Decode the string literal `text`, a whole `StringLit` token starting at
+/// byte `start` of its file.
+pub fn decode_string(text: &str, start: usize) -> DecodedString {
+    let mut decoder = Decoder {
+        text,
+        start,
+        out: DecodedString::default(),
+        buf: String::new(),
+    };
+    decoder.literal();
+    decoder.flush();
+    if decoder.out.parts.is_empty() {
+        decoder.out.parts.push(StringPart::Text(String::new()));
+    }
+    decoder.out
+}
+
+/// `s` as the body of a `"..."` literal that decodes back to `s`.
+pub fn escape_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The end of the string literal starting at `index`, if one does. An
+/// unterminated literal runs to the end of `input`.
+pub(crate) fn scan(input: &str, index: usize) -> Option<usize> {
+    let rest = &input[index..];
+    if let Some(hashes) = raw_hashes(rest) {
+        let body = index + 2 + hashes;
+        let close = raw_close(hashes);
+        return Some(
+            input[body..]
+                .find(&close)
+                .map_or(input.len(), |i| body + i + close.len()),
+        );
+    }
+    if rest.starts_with("\"\"\"") {
+        return Some(scan_body(input, index + 3, "\"\"\"").0);
+    }
+    if rest.starts_with('"') {
+        return Some(scan_body(input, index + 1, "\"").0);
+    }
+    None
+}
+
+/// The number of `#` in a raw literal's `r#"` opening, if `rest` starts
+/// with one.
+fn raw_hashes(rest: &str) -> Option<usize> {
+    let after_r = rest.strip_prefix('r')?;
+    let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+    after_r[hashes..].starts_with('"').then_some(hashes)
+}
+
+fn raw_close(hashes: usize) -> String {
+    format!("\"{}", "#".repeat(hashes))
+}
+
+/// The end of a body starting at `index` and whether `close` was found.
+fn scan_body(input: &str, mut index: usize, close: &str) -> (usize, bool) {
+    while index < input.len() {
+        let rest = &input[index..];
+        if rest.starts_with(close) {
+            return (index + close.len(), true);
+        }
+        if rest.starts_with("${") {
+            index = scan_interp(input, index + 2).0;
+            continue;
+        }
+        let mut chars = rest.chars();
+        let c = chars.next().expect("index must be valid");
+        index += c.len_utf8();
+        if c == '\\' {
+            index += chars.next().map_or(0, char::len_utf8);
+        }
+    }
+    (input.len(), false)
+}
+
+/// The end of an interpolation whose expression starts at `index`, past its
+/// closing `}`, and whether that `}` was found. Braces nest, and string
+/// literals inside are skipped whole.
+fn scan_interp(input: &str, mut index: usize) -> (usize, bool) {
+    let mut depth = 0usize;
+    while index < input.len() {
+        if let Some(end) = scan(input, index) {
+            index = end;
+            continue;
+        }
+        let c = input[index..].chars().next().expect("index must be valid");
+        index += c.len_utf8();
+        if crate::is_ident_start(c) {
+            // Skip the rest of a word, so its `r` can't start a raw literal.
+            while let Some(c) = input[index..].chars().next() {
+                if !crate::is_ident_continue(c) {
+                    break;
+                }
+                index += c.len_utf8();
+            }
+            continue;
+        }
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return (index, true),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    (input.len(), false)
+}
+
+struct Decoder<'a> {
+    text: &'a str,
+    start: usize,
+    out: DecodedString,
+    buf: String,
+}
+
+impl Decoder<'_> {
+    fn literal(&mut self) {
+        let text = self.text;
+        if let Some(hashes) = raw_hashes(text) {
+            let body = 2 + hashes;
+            match text[body..].find(&raw_close(hashes)) {
+                Some(len) => self.buf.push_str(&text[body..body + len]),
+                None => {
+                    self.buf.push_str(&text[body..]);
+                    self.unterminated();
+                }
+            }
+            return;
+        }
+
+        let delim = if text.starts_with("\"\"\"") {
+            "\"\"\""
+        } else {
+            "\""
+        };
+        let (end, closed) = scan_body(text, delim.len(), delim);
+        if !closed {
+            self.unterminated();
+        }
+        let body_end = if closed { end - delim.len() } else { end };
+        if delim == "\"" {
+            self.body(delim.len(), body_end, None);
+            return;
+        }
+
+        let raw = &text[delim.len()..body_end];
+        let opening_newline = if raw.starts_with("\r\n") {
+            2
+        } else {
+            usize::from(raw.starts_with('\n'))
+        };
+        let from = delim.len() + opening_newline;
+        match raw.rfind('\n') {
+            Some(nl) if raw[nl + 1..].chars().all(|c| c == ' ' || c == '\t') => {
+                let indent = &raw[nl + 1..];
+                let nl = if raw[..nl].ends_with('\r') {
+                    nl - 1
+                } else {
+                    nl
+                };
+                let to = delim.len() + nl;
+                // Nothing between the quotes' lines is an empty string.
+                if to > from {
+                    let from = if opening_newline > 0 {
+                        self.dedent(from, indent)
+                    } else {
+                        from
+                    };
+                    self.body(from, to, Some(indent));
+                }
+            }
+            _ => self.body(from, body_end, None),
+        }
+    }
+
+    /// Decode `text[from..to]`, stripping `indent` after every newline.
+    fn body(&mut self, from: usize, to: usize, indent: Option<&str>) {
+        let text = self.text;
+        let mut index = from;
+        while index < to {
+            let rest = &text[index..to];
+            if rest.starts_with('\\') {
+                index = self.escape(index, to);
+                continue;
+            }
+            if rest.starts_with("${") {
+                index = self.interp(index, to);
+                continue;
+            }
+            let c = rest.chars().next().expect("index must be valid");
+            self.buf.push(c);
+            index += c.len_utf8();
+            if c == '\n' {
+                if let Some(indent) = indent {
+                    index = self.dedent(index, indent);
+                }
+            }
+        }
+    }
+
+    /// Skip `indent` at the line starting at `index`; blank lines may have
+    /// less of it.
+    fn dedent(&mut self, index: usize, indent: &str) -> usize {
+        let line = &self.text[index..];
+        if line.starts_with(indent) {
+            return index + indent.len();
+        }
+        let blank = line.trim_start_matches([' ', '\t']);
+        if !(blank.starts_with('\n') || blank.starts_with("\r\n")) {
+            self.error(
+                index,
+                index,
+                "line is indented less than the closing `\"\"\"`".into(),
+            );
+        }
+        index + (line.len() - blank.len())
+    }
+
+    /// Decode the escape at `index`, returning the index past it.
+    fn escape(&mut self, index: usize, to: usize) -> usize {
+        let text = self.text;
+        let Some(c) = text[index + 1..to].chars().next() else {
+            self.error(index, index + 1, "unterminated escape sequence".into());
+            return to;
+        };
+        let end = index + 1 + c.len_utf8();
+        let decoded = match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            'u' => return self.unicode_escape(index, end, to),
+            _ => {
+                let message = if c == '\n' {
+                    "unknown escape sequence `\\` before a newline".to_owned()
+                } else {
+                    format!("unknown escape sequence `\\{c}`")
+                };
+                self.error(index, end, message);
+                return end;
+            }
+        };
+        self.buf.push(decoded);
+        end
+    }
+
+    /// Decode `\u{X}` whose `{` is at `brace`.
+    fn unicode_escape(&mut self, index: usize, brace: usize, to: usize) -> usize {
+        let text = self.text;
+        if !text[brace..to].starts_with('{') {
+            self.error(index, brace, "expected `{` after `\\u`".into());
+            return brace;
+        }
+        let Some(len) = text[brace + 1..to].find('}') else {
+            self.error(index, to, "unterminated `\\u{` escape".into());
+            return to;
+        };
+        let digits = &text[brace + 1..brace + 1 + len];
+        let end = brace + len + 2;
+        if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            self.error(index, end, "`\\u{...}` takes one to six hex digits".into());
+            return end;
+        }
+        match u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) => self.buf.push(c),
+            None => self.error(
+                index,
+                end,
+                format!("`\\u{{{digits}}}` is not a Unicode scalar value"),
+            ),
+        }
+        end
+    }
+
+    /// Record the interpolation at `index`, returning the index past it.
+    fn interp(&mut self, index: usize, to: usize) -> usize {
+        let text = self.text;
+        let expr_start = index + 2;
+        let (end, closed) = scan_interp(text, expr_start);
+        if !closed || end > to {
+            self.error(index, to, "unterminated `${` in string literal".into());
+            return to;
+        }
+        let source = &text[expr_start..end - 1];
+        if source.trim().is_empty() {
+            self.error(index, end, "empty `${}` in string literal".into());
+            return end;
+        }
+        self.flush();
+        self.out.parts.push(StringPart::Interp {
+            source: source.to_owned(),
+            span: Span::new(self.start + expr_start, self.start + end - 1),
+        });
+        end
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            self.out
+                .parts
+                .push(StringPart::Text(std::mem::take(&mut self.buf)));
+        }
+    }
+
+    fn unterminated(&mut self) {
+        self.error(0, self.text.len(), "unterminated string literal".into());
+    }
+
+    fn error(&mut self, from: usize, to: usize, message: String) {
+        self.out.errors.push(LexError {
+            span: Span::new(self.start + from, self.start + to),
+            message,
+        });
+    }
+}
@@ -41,6 +41,7 @@ pub enum Item {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExternTypeDecl {
     pub name: String,
+    pub generics: Vec<String>,
     pub extern_name: Option<String>,
     pub span: Span,
 }
@@ -51,6 +52,7 @@ pub struct ExternFnDecl {
     pub extern_name: Option<String>,
     pub link_module: Option<(String, String)>,
     pub inline: bool,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<Spanned<TypeExpr>>,
     pub cap: Option<CapRef>,
@@ -89,6 +91,7 @@ pub struct CapDecl {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OperationDecl {
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<Spanned<TypeExpr>>,
     pub span: Span,
@@ -159,6 +162,9 @@ pub enum Expr {
     Handle { id: ExprId, cap: String, type_args: Vec<String>, handler: Box<Expr>, body: Box<Expr> },
     Member { id: ExprId, object: Box<Expr>, field: String },
     Ann { id: ExprId, expr: Box<Expr>, ty: TypeExpr },
+    /// Runs `body` with `var` bound to the state, first the value `init`
+    /// produces, until `body` produces `Step.done` rather than `Step.next(s)`.
+    Loop { id: ExprId, var: String, init: Box<Expr>, body: Box<Expr> },
     Error { id: ExprId },
 }
 
@@ -202,9 +208,35 @@ impl Expr {
             | Expr::Handle { id, .. }
             | Expr::Member { id, .. }
             | Expr::Ann { id, .. }
+            | Expr::Loop { id, .. }
             | Expr::Error { id } => *id,
         }
     }
+
+    pub fn id_mut(&mut self) -> &mut ExprId {
+        match self {
+            Expr::Ident { id, .. }
+            | Expr::String { id, .. }
+            | Expr::Number { id, .. }
+            | Expr::Ctor { id, .. }
+            | Expr::Thunk { id, .. }
+            | Expr::Roll { id, .. }
+            | Expr::Bundle { id, .. }
+            | Expr::Produce { id, .. }
+            | Expr::Force { id, .. }
+            | Expr::Lambda { id, .. }
+            | Expr::Apply { id, .. }
+            | Expr::Let { id, .. }
+            | Expr::Match { id, .. }
+            | Expr::Unroll { id, .. }
+            | Expr::Perform { id, .. }
+            | Expr::Handle { id, .. }
+            | Expr::Member { id, .. }
+            | Expr::Ann { id, .. }
+            | Expr::Loop { id, .. }
+            | Expr::Error { id } => id,
+        }
+    }
 }
 
 pub fn expr_references_name(expr: &Expr, target: &str) -> bool {
@@ -251,6 +283,12 @@ pub fn expr_references_name(expr: &Expr, target: &str) -> bool {
             .iter()
             .any(|e| expr_references_name(&e.body, target)),
         Expr::Member { object, .. } => expr_references_name(object, target),
+        Expr::Loop {
+            var, init, body, ..
+        } => {
+            expr_references_name(init, target)
+                || (var != target && expr_references_name(body, target))
+        }
     }
 }
 
@@ -282,11 +320,16 @@ impl LoweringCtx {
 
 pub fn lower(file: &hir::File) -> File {
     let mut ctx = LoweringCtx::new(file);
-    let items: Vec<Item> = file
-        .items
-        .iter()
-        .map(|item| lower_item(&mut ctx, item))
-        .collect();
+    let mut items = Vec::with_capacity(file.items.len());
+    for item in &file.items {
+        items.push(lower_item(&mut ctx, item));
+        // Derived impls follow their `data`, as if written after it.
+        if let hir::Item::Data(data) = item {
+            for derived in hir::derive::expand(data) {
+                items.push(lower_item(&mut ctx, &derived));
+            }
+        }
+    }
 
     let content_hash = file.content_hash;
     File {
@@ -300,6 +343,7 @@ fn lower_item(ctx: &mut LoweringCtx, item: &hir::Item) -> Item {
     match item {
         hir::Item::ExternType(ext) => Item::ExternType(ExternTypeDecl {
             name: ext.name.clone(),
+            generics: ext.generics.clone(),
             extern_name: ext.extern_name.clone(),
             span: ext.span,
         }),
@@ -308,6 +352,7 @@ fn lower_item(ctx: &mut LoweringCtx, item: &hir::Item) -> Item {
             extern_name: ext.extern_name.clone(),
             link_module: ext.link_module.clone(),
             inline: ext.inline,
+            generics: ext.generics.clone(),
             params: ext.params.iter().map(lower_param).collect(),
             return_type: ext.return_type.clone(),
             cap: ext.cap.clone(),
@@ -338,6 +383,7 @@ fn lower_item(ctx: &mut LoweringCtx, item: &hir::Item) -> Item {
                 .iter()
                 .map(|op| OperationDecl {
                     name: op.name.clone(),
+                    generics: op.generics.clone(),
                     params: op.params.iter().map(lower_param).collect(),
                     return_type: op.return_type.clone(),
                     span: op.span,
@@ -594,6 +640,16 @@ fn lower_expr(ctx: &mut LoweringCtx, expr: &hir::Expr) -> Expr {
                 ty: ty.value.clone(),
             }
         }
+        hir::Expr::Loop { var, init, body, .. } => {
+            let init = Box::new(lower_expr(ctx, init));
+            let body = Box::new(lower_expr(ctx, body));
+            Expr::Loop {
+                id: ctx.alloc(span),
+                var: var.clone(),
+                init,
+                body,
+            }
+        }
         hir::Expr::Error { .. } => Expr::Error {
             id: ctx.alloc(span),
         },
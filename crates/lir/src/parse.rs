@@ -115,10 +115,6 @@ impl Parser {
         }
     }
 
-    fn peek_ident_text(&self, text: &str) -> bool {
-        matches!(self.peek(), Some(TokenKind::Ident(name)) if name == text)
-    }
-
     fn eat_sym(&mut self, sym: Symbol) -> bool {
         if self.peek() == Some(&TokenKind::Symbol(sym)) {
             self.advance();
@@ -212,14 +208,18 @@ impl Parser {
         let start = self.expect_kw(Keyword::Extern).ok()?;
         if self.eat_ident("type") {
             let (name, name_span) = self.expect_ident().ok()?;
+            let generics: Vec<String> = self.try_parse_generic_params()
+                .into_iter().map(|g| g.name().to_owned()).collect();
             let extern_name = self.try_parse_extern_as();
             Some(Item::ExternType(ExternTypeDecl {
                 name,
+                generics,
                 extern_name,
                 span: Span::new(start.start, name_span.end),
             }))
         } else if self.eat_kw(Keyword::Fn) {
             let (name, _) = self.expect_ident().ok()?;
+            let generics = self.try_parse_generic_params();
             let params = self.parse_param_list()?;
             let return_type = self.try_parse_return_ann();
             let cap = self.try_parse_cap_annotation();
@@ -230,6 +230,7 @@ impl Parser {
                 extern_name,
                 link_module: None,
                 inline,
+                generics,
                 params,
                 return_type,
                 cap,
@@ -322,11 +323,13 @@ impl Parser {
     fn parse_operation_decl(&mut self) -> Option<OperationDecl> {
         let start = self.expect_kw(Keyword::Fn).ok()?;
         let (name, _) = self.expect_ident().ok()?;
+        let generics = self.try_parse_generic_params();
         let params = self.parse_param_list()?;
         let return_type = self.try_parse_return_ann();
         let end = self.peek_span();
         Some(OperationDecl {
             name,
+            generics,
             params,
             return_type,
             span: Span::new(start.start, end.start),
@@ -549,8 +552,7 @@ impl Parser {
         }
         loop {
             let (name, _) = self.expect_ident().ok()?;
-            let type_args = if self.peek_ident_text("for") {
-                self.advance(); // consume "for"
+            let type_args = if self.eat_kw(Keyword::For) {
                 let (ty, _) = self.expect_ident().ok()?;
                 vec![TypeExpr::Named(ty)]
             } else {
@@ -657,6 +659,7 @@ impl Parser {
             TokenKind::Keyword(Keyword::Ctor) => self.parse_ctor_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Let) => self.parse_let_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Match) => self.parse_match_expr().map(|e| (e, true)),
+            TokenKind::Keyword(Keyword::Loop) => self.parse_loop_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Perform) => self.parse_perform_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Handle) => self.parse_handle_expr().map(|e| (e, false)),
             TokenKind::Keyword(Keyword::Bundle) => self.parse_bundle_expr().map(|e| (e, true)),
@@ -680,7 +683,7 @@ impl Parser {
                 let tok = self.advance();
                 let span = tok.span;
                 let s = if let TokenKind::NumberLit(s) = &tok.kind {
-                    s.clone()
+                    lumo_lexer::decode_number(s).unwrap_or_else(|_| s.clone())
                 } else {
                     return None;
                 };
@@ -816,6 +819,23 @@ impl Parser {
         })
     }
 
+    fn parse_loop_expr(&mut self) -> Option<Expr> {
+        let start = self.expect_kw(Keyword::Loop).ok()?;
+        let (var, _) = self.expect_ident().ok()?;
+        self.expect_sym(Symbol::Equals).ok()?;
+        let init = self.parse_expr()?;
+        self.expect_sym(Symbol::LBrace).ok()?;
+        let body = self.parse_expr()?;
+        self.expect_sym(Symbol::RBrace).ok()?;
+        let id = self.alloc(start);
+        Some(Expr::Loop {
+            id,
+            var,
+            init: Box::new(init),
+            body: Box::new(body),
+        })
+    }
+
     fn parse_match_expr(&mut self) -> Option<Expr> {
         let start = self.expect_kw(Keyword::Match).ok()?;
         let scrutinee = self.parse_expr()?;
@@ -861,8 +881,7 @@ impl Parser {
     fn parse_handle_expr(&mut self) -> Option<Expr> {
         let start = self.expect_kw(Keyword::Handle).ok()?;
         let (cap, _) = self.expect_ident().ok()?;
-        let type_args = if self.peek_ident_text("for") {
-            self.advance(); // consume "for"
+        let type_args = if self.eat_kw(Keyword::For) {
             let (ty, _) = self.expect_ident().ok()?;
             vec![ty]
         } else {
@@ -989,37 +1008,17 @@ impl Parser {
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Decode a string literal token. The IR has no interpolation — it is
+/// desugared before lowering — so a `${...}` reads back as text.
 fn strip_string_quotes(s: &str) -> String {
-    let inner = s
-        .strip_prefix('"')
-        .unwrap_or(s)
-        .strip_suffix('"')
-        .unwrap_or(s);
-    unescape_string(inner)
-}
-
-fn unescape_string(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    let mut chars = s.chars();
-    while let Some(ch) = chars.next() {
-        if ch == '\\' {
-            match chars.next() {
-                Some('n') => out.push('\n'),
-                Some('r') => out.push('\r'),
-                Some('t') => out.push('\t'),
-                Some('\\') => out.push('\\'),
-                Some('"') => out.push('"'),
-                Some(c) => {
-                    out.push('\\');
-                    out.push(c);
-                }
-                None => out.push('\\'),
-            }
-        } else {
-            out.push(ch);
-        }
-    }
-    out
+    lumo_lexer::decode_string(s, 0)
+        .parts
+        .into_iter()
+        .map(|part| match part {
+            lumo_lexer::StringPart::Text(text) => text,
+            lumo_lexer::StringPart::Interp { source, .. } => format!("${{{source}}}"),
+        })
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
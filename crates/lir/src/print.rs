@@ -79,6 +79,7 @@ fn print_item(p: &mut Printer, item: &Item) {
 fn print_extern_type(p: &mut Printer, ext: &ExternTypeDecl) {
     p.push("extern type ");
     p.push(&ext.name);
+    print_str_generics(p, &ext.generics);
     if let Some(extern_name) = &ext.extern_name {
         p.push(" as \"");
         p.push(extern_name);
@@ -92,6 +93,7 @@ fn print_extern_fn(p: &mut Printer, ext: &ExternFnDecl) {
     }
     p.push("extern fn ");
     p.push(&ext.name);
+    print_generics(p, &ext.generics);
     print_param_list(p, &ext.params);
     if let Some(ret) = &ext.return_type {
         p.push(": ");
@@ -157,6 +159,7 @@ fn print_cap(p: &mut Printer, cap: &CapDecl) {
 fn print_operation(p: &mut Printer, op: &OperationDecl) {
     p.push("fn ");
     p.push(&op.name);
+    print_generics(p, &op.generics);
     print_param_list(p, &op.params);
     if let Some(ret) = &op.return_type {
         p.push(": ");
@@ -243,7 +246,7 @@ fn print_expr(p: &mut Printer, expr: &Expr) {
         Expr::Ident { name, .. } => p.push(name),
         Expr::String { value, .. } => {
             p.push("\"");
-            p.push(&escape_string(value));
+            p.push(&lumo_lexer::escape_string(value));
             p.push("\"");
         }
         Expr::Number { value, .. } => p.push(value),
@@ -363,6 +366,21 @@ fn print_expr(p: &mut Printer, expr: &Expr) {
             print_expr(p, body);
             p.dedent();
         }
+        Expr::Loop {
+            var, init, body, ..
+        } => {
+            p.push("loop ");
+            p.push(var);
+            p.push(" = ");
+            print_expr(p, init);
+            p.push(" {");
+            p.indent();
+            p.newline();
+            print_expr(p, body);
+            p.dedent();
+            p.newline();
+            p.push("}");
+        }
         Expr::Member { object, field, .. } => {
             print_expr_atom(p, object);
             p.push(".");
@@ -496,21 +514,6 @@ fn print_pattern(p: &mut Printer, pat: &Pattern) {
     p.push(&pat.display());
 }
 
-fn escape_string(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for ch in s.chars() {
-        match ch {
-            '"' => out.push_str("\\\""),
-            '\\' => out.push_str("\\\\"),
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            '\t' => out.push_str("\\t"),
-            c => out.push(c),
-        }
-    }
-    out
-}
-
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
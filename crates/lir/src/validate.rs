@@ -168,6 +168,10 @@ fn validate_expr(warnings: &mut Vec<LirWarning>, expr: &Expr) {
             validate_expr(warnings, value);
             validate_expr(warnings, body);
         }
+        Expr::Loop { init, body, .. } => {
+            validate_expr(warnings, init);
+            validate_expr(warnings, body);
+        }
         Expr::Handle { handler, body, .. } => {
             validate_expr(warnings, handler);
             validate_expr(warnings, body);
@@ -213,6 +217,7 @@ fn expr_kind(expr: &Expr) -> &'static str {
         Expr::Match { .. } => "Match",
         Expr::Unroll { .. } => "Unroll",
         Expr::Perform { .. } => "Perform",
+        Expr::Loop { .. } => "Loop",
         Expr::Handle { .. } => "Handle",
         Expr::Member { .. } => "Member",
         Expr::Ann { .. } => "Ann",
@@ -58,7 +58,8 @@ pub fn highlight(source: &str) -> Vec<HighlightToken> {
             | TokenKind::Symbol(Symbol::EqEq)
             | TokenKind::Symbol(Symbol::BangEq)
             | TokenKind::Symbol(Symbol::AmpAmp)
-            | TokenKind::Symbol(Symbol::PipePipe) => HighlightKind::Symbol,
+            | TokenKind::Symbol(Symbol::PipePipe)
+            | TokenKind::Symbol(Symbol::Question) => HighlightKind::Symbol,
             TokenKind::NumberLit(_) => HighlightKind::Number,
         };
 
@@ -3,7 +3,7 @@ pub mod parser;
 
 pub use parser::{
     Attribute, AttributeArg, BinaryOp, BlockStmt, BundleEntry, CapDecl, CapSig, DataDecl, Expr,
-    ExternFnDecl, ExternTypeDecl, File, FnDecl, GenericParam, ImplDecl, ImplMethod, Item,
-    MatchArm, OperationDecl, Param, ParseError, ParseOutput, TypeSig, UnaryOp, UseDecl,
+    ExternFnDecl, ExternTypeDecl, File, FnDecl, GenericParam, ImplDecl, ImplMethod, InterpPart,
+    Item, MatchArm, OperationDecl, Param, ParseError, ParseOutput, TypeSig, UnaryOp, UseDecl,
     VariantDecl,
 };
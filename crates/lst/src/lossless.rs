@@ -273,6 +273,29 @@ impl Parser {
         let mut children = Vec::new();
         children.push(SyntaxElement::Token(self.bump().unwrap())); // extern
 
+        while self.at_trivia() {
+            children.push(SyntaxElement::Token(self.bump().unwrap()));
+        }
+
+        // `extern { ... }` block: its items end in `;`, so run to the matching `}`.
+        if self.at_symbol_text("{") {
+            let mut depth = 0usize;
+            while !self.eof() {
+                if self.at_symbol_text("{") {
+                    depth += 1;
+                } else if self.at_symbol_text("}") {
+                    depth -= 1;
+                    if depth == 0 {
+                        children.push(SyntaxElement::Token(self.bump().unwrap()));
+                        return node_from_children(SyntaxKind::ExternDecl, children);
+                    }
+                }
+                children.push(SyntaxElement::Token(self.bump().unwrap()));
+            }
+            self.error_here("expected `}` in extern block");
+            return node_from_children(SyntaxKind::ExternDecl, children);
+        }
+
         while !self.eof() && !self.at_symbol_text(";") {
             children.push(SyntaxElement::Token(self.bump().unwrap()));
         }
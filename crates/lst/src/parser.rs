@@ -31,6 +31,9 @@ pub struct Attribute {
     pub value: Option<Expr>,
     pub args: Vec<AttributeArg>,
     pub flags: Vec<String>,
+    /// `name(...)` entries of the argument list, as in
+    /// `#[cfg(not(feature = "x"))]`.
+    pub nested: Vec<Attribute>,
     pub span: Span,
 }
 
@@ -45,6 +48,7 @@ pub struct AttributeArg {
 pub struct ExternTypeDecl {
     pub attrs: Vec<Attribute>,
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub span: Span,
 }
 
@@ -52,6 +56,7 @@ pub struct ExternTypeDecl {
 pub struct ExternFnDecl {
     pub attrs: Vec<Attribute>,
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<TypeSig>,
     pub cap: Option<CapSig>,
@@ -60,6 +65,7 @@ pub struct ExternFnDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DataDecl {
+    pub attrs: Vec<Attribute>,
     pub name: String,
     pub generics: Vec<GenericParam>,
     pub variants: Vec<VariantDecl>,
@@ -76,6 +82,7 @@ pub struct VariantDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CapDecl {
+    pub attrs: Vec<Attribute>,
     pub name: String,
     pub operations: Vec<OperationDecl>,
     pub span: Span,
@@ -84,6 +91,7 @@ pub struct CapDecl {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OperationDecl {
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<Param>,
     pub return_type: Option<TypeSig>,
     pub span: Span,
@@ -103,6 +111,7 @@ pub struct FnDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UseDecl {
+    pub attrs: Vec<Attribute>,
     pub path: Vec<String>,
     pub names: Option<Vec<String>>,
     pub span: Span,
@@ -110,6 +119,7 @@ pub struct UseDecl {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImplDecl {
+    pub attrs: Vec<Attribute>,
     /// None = unnamed (author's impl), Some = named impl
     pub name: Option<String>,
     pub generics: Vec<GenericParam>,
@@ -189,6 +199,11 @@ pub enum Expr {
         value: String,
         span: Span,
     },
+    /// `"a ${b} c"`: a string literal with interpolation.
+    Interp {
+        parts: Vec<InterpPart>,
+        span: Span,
+    },
     Member {
         object: Box<Expr>,
         member: String,
@@ -203,6 +218,12 @@ pub enum Expr {
         expr: Box<Expr>,
         span: Span,
     },
+    /// `fn(x, y: T) { body }`: a fn literal, passed to a runner's fn param.
+    Lambda {
+        params: Vec<(String, Option<TypeSig>)>,
+        body: Box<Expr>,
+        span: Span,
+    },
     Force {
         expr: Box<Expr>,
         span: Span,
@@ -268,11 +289,60 @@ pub enum Expr {
         else_body: Option<Box<Expr>>,
         span: Span,
     },
+    /// `while cond { body }`
+    While {
+        condition: Box<Expr>,
+        body: Box<Expr>,
+        span: Span,
+    },
+    /// `for x in xs { body }`
+    For {
+        name: String,
+        iterable: Box<Expr>,
+        body: Box<Expr>,
+        span: Span,
+    },
+    /// `loop { body }`, left only by `break` or `return`.
+    Loop {
+        body: Box<Expr>,
+        span: Span,
+    },
+    Break {
+        span: Span,
+    },
+    Continue {
+        span: Span,
+    },
+    /// `return e`, or a bare `return` for `Unit`.
+    Return {
+        value: Option<Box<Expr>>,
+        span: Span,
+    },
+    /// `try { body } catch e => handler`, or `catch e: E => handler` to
+    /// name the error type.
+    Try {
+        body: Box<Expr>,
+        name: String,
+        error_type: Option<TypeSig>,
+        handler: Box<Expr>,
+        span: Span,
+    },
+    /// `r?`: the value of an `.ok` result, or throw the error of an `.err`.
+    Propagate {
+        expr: Box<Expr>,
+        span: Span,
+    },
     Error {
         span: Span,
     },
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpPart {
+    Text(String),
+    Expr(Expr),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BlockStmt {
     Let {
@@ -289,7 +359,7 @@ pub enum BlockStmt {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BundleEntry {
     pub name: String,
-    pub params: Vec<Param>,
+    pub params: Vec<(String, Option<TypeSig>)>,
     pub body: Expr,
     pub span: Span,
 }
@@ -329,17 +399,13 @@ pub fn parse(tokens: &[Token], lex_errors: &[LexError]) -> ParseOutput {
             continue;
         }
         if p.at_keyword(Keyword::Cap) {
-            if !attrs.is_empty() {
-                p.error_here("attributes are only supported on `extern` items");
-            }
-            items.push(Item::Cap(p.parse_cap_decl()));
+            p.check_only_cfg(&attrs);
+            items.push(Item::Cap(p.parse_cap_decl(attrs)));
             continue;
         }
         if p.at_keyword(Keyword::Data) {
-            if !attrs.is_empty() {
-                p.error_here("attributes are only supported on `extern` items");
-            }
-            items.push(Item::Data(p.parse_data_decl()));
+            p.check_data_attrs(&attrs);
+            items.push(Item::Data(p.parse_data_decl(attrs)));
             continue;
         }
         if p.at_keyword(Keyword::Fn) {
@@ -347,17 +413,13 @@ pub fn parse(tokens: &[Token], lex_errors: &[LexError]) -> ParseOutput {
             continue;
         }
         if p.at_keyword(Keyword::Use) {
-            if !attrs.is_empty() {
-                p.error_here("attributes are only supported on `extern` items");
-            }
-            items.push(Item::Use(p.parse_use_decl()));
+            p.check_only_cfg(&attrs);
+            items.push(Item::Use(p.parse_use_decl(attrs)));
             continue;
         }
         if p.at_keyword(Keyword::Impl) {
-            if !attrs.is_empty() {
-                p.error_here("attributes are only supported on `extern` items");
-            }
-            items.push(Item::Impl(p.parse_impl_decl()));
+            p.check_only_cfg(&attrs);
+            items.push(Item::Impl(p.parse_impl_decl(attrs)));
             continue;
         }
 
@@ -409,14 +471,25 @@ fn collect_lossless_tokens(
                     kind: TokenKind::Ident(t.text.clone()),
                     span: t.span,
                 }),
-                lumo_lexer::LosslessTokenKind::StringLit => tokens.push(Token {
-                    kind: TokenKind::StringLit(t.text.clone()),
-                    span: t.span,
-                }),
-                lumo_lexer::LosslessTokenKind::NumberLit => tokens.push(Token {
-                    kind: TokenKind::NumberLit(t.text.clone()),
-                    span: t.span,
-                }),
+                lumo_lexer::LosslessTokenKind::StringLit => {
+                    lex_errors.extend(lumo_lexer::decode_string(&t.text, t.span.start).errors);
+                    tokens.push(Token {
+                        kind: TokenKind::StringLit(t.text.clone()),
+                        span: t.span,
+                    });
+                }
+                lumo_lexer::LosslessTokenKind::NumberLit => {
+                    if let Err(message) = lumo_lexer::decode_number(&t.text) {
+                        lex_errors.push(LexError {
+                            span: t.span,
+                            message,
+                        });
+                    }
+                    tokens.push(Token {
+                        kind: TokenKind::NumberLit(t.text.clone()),
+                        span: t.span,
+                    });
+                }
                 lumo_lexer::LosslessTokenKind::Symbol(sym) => tokens.push(Token {
                     kind: TokenKind::Symbol(*sym),
                     span: t.span,
@@ -455,13 +528,39 @@ impl<'a> Parser<'a> {
         attrs
     }
 
+    /// Only `#[cfg(...)]` applies to every kind of item; other attributes
+    /// are read by `extern` and `fn` lowering alone.
+    fn check_only_cfg(&mut self, attrs: &[Attribute]) {
+        if attrs.iter().any(|a| a.name != "cfg") {
+            self.error_here("attributes are only supported on `extern` items");
+        }
+    }
+
+    /// `data` also takes `#[derive(...)]`, read by `data` lowering.
+    fn check_data_attrs(&mut self, attrs: &[Attribute]) {
+        if attrs.iter().any(|a| a.name != "cfg" && a.name != "derive") {
+            self.error_here("only `#[cfg]` and `#[derive]` are supported on `data` items");
+        }
+    }
+
     fn parse_attribute(&mut self) -> Attribute {
         let hash = self.expect_symbol(Symbol::Hash);
         self.expect_symbol(Symbol::LBracket);
+        let mut attr = self.parse_meta();
+        let end = self.expect_symbol(Symbol::RBracket);
+        attr.span = Span::new(hash.start, end.end);
+        attr
+    }
+
+    /// `name`, `name = expr` or `name(flag, key = expr, nested(...), ...)`.
+    fn parse_meta(&mut self) -> Attribute {
+        let start = self.current_span();
         let name = self.expect_word();
         let mut value = None;
         let mut args = Vec::new();
         let mut flags = Vec::new();
+        let mut nested = Vec::new();
+        let mut end = start;
         if self.at_symbol(Symbol::Equals) {
             self.bump();
             value = Some(self.parse_attribute_expr_until(
@@ -471,6 +570,13 @@ impl<'a> Parser<'a> {
         } else if self.at_symbol(Symbol::LParen) {
             self.bump();
             while !self.eof() && !self.at_symbol(Symbol::RParen) {
+                if self.at_ident() && self.peek_is_symbol(Symbol::LParen) {
+                    nested.push(self.parse_meta());
+                    if self.at_symbol(Symbol::Comma) {
+                        self.bump();
+                    }
+                    continue;
+                }
                 // Positional flag: an Ident NOT followed by `=` or `(`.
                 let is_positional = self.at_ident()
                     && !self.peek_is_symbol(Symbol::Equals)
@@ -507,15 +613,15 @@ impl<'a> Parser<'a> {
                     break;
                 }
             }
-            self.expect_symbol(Symbol::RParen);
+            end = self.expect_symbol(Symbol::RParen);
         }
-        let end = self.expect_symbol(Symbol::RBracket);
         Attribute {
             name,
             value,
             args,
             flags,
-            span: Span::new(hash.start, end.end),
+            nested,
+            span: Span::new(start.start, end.end),
         }
     }
 
@@ -584,6 +690,58 @@ impl<'a> Parser<'a> {
         expr
     }
 
+    /// A string literal, its `${...}` parts parsed as expressions. Errors in
+    /// the literal itself were reported by the lexer.
+    fn parse_string_lit(&mut self, token: &Token) -> Expr {
+        let decoded = lumo_lexer::decode_string(&token_text(token), token.span.start);
+        if let Some(value) = decoded.text() {
+            return Expr::String {
+                value,
+                span: token.span,
+            };
+        }
+        let mut parts = Vec::new();
+        for part in decoded.parts {
+            match part {
+                lumo_lexer::StringPart::Text(text) => parts.push(InterpPart::Text(text)),
+                lumo_lexer::StringPart::Interp { source, span } => {
+                    let lexed = lumo_lexer::lex(&source);
+                    let shift = |s: Span| Span::new(s.start + span.start, s.end + span.start);
+                    let tokens: Vec<Token> = lexed
+                        .tokens
+                        .into_iter()
+                        .map(|t| Token {
+                            kind: t.kind,
+                            span: shift(t.span),
+                        })
+                        .collect();
+                    let mut sub = Parser {
+                        tokens: &tokens,
+                        index: 0,
+                        errors: lexed
+                            .errors
+                            .iter()
+                            .map(|e| ParseError {
+                                span: shift(e.span),
+                                message: e.message.clone(),
+                            })
+                            .collect(),
+                    };
+                    let expr = sub.parse_expr();
+                    if !sub.eof() {
+                        sub.error_here("unexpected tokens in string interpolation");
+                    }
+                    self.errors.extend(sub.errors);
+                    parts.push(InterpPart::Expr(expr));
+                }
+            }
+        }
+        Expr::Interp {
+            parts,
+            span: token.span,
+        }
+    }
+
     fn parse_extern_item(&mut self, attrs: Vec<Attribute>) -> Vec<Item> {
         let start = self.expect_keyword(Keyword::Extern);
         if self.at_ident_text("type") {
@@ -659,10 +817,16 @@ impl<'a> Parser<'a> {
     fn parse_extern_type_decl(&mut self, attrs: Vec<Attribute>, start: Span) -> ExternTypeDecl {
         self.expect_ident_text("type");
         let name = self.expect_ident();
+        let generics = if self.at_symbol(Symbol::LBracket) {
+            self.parse_generics()
+        } else {
+            Vec::new()
+        };
         let end = self.expect_symbol(Symbol::Semi);
         ExternTypeDecl {
             attrs,
             name,
+            generics,
             span: Span::new(start.start, end.end),
         }
     }
@@ -670,6 +834,11 @@ impl<'a> Parser<'a> {
     fn parse_extern_fn_decl(&mut self, attrs: Vec<Attribute>, start: Span) -> ExternFnDecl {
         self.expect_keyword(Keyword::Fn);
         let name = self.expect_ident();
+        let generics = if self.at_symbol(Symbol::LBracket) {
+            self.parse_generics()
+        } else {
+            Vec::new()
+        };
         let params = if self.at_symbol(Symbol::LParen) {
             self.parse_params()
         } else {
@@ -722,6 +891,7 @@ impl<'a> Parser<'a> {
         ExternFnDecl {
             attrs,
             name,
+            generics,
             params,
             return_type,
             cap,
@@ -729,7 +899,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_cap_decl(&mut self) -> CapDecl {
+    fn parse_cap_decl(&mut self, attrs: Vec<Attribute>) -> CapDecl {
         let start = self.expect_keyword(Keyword::Cap);
         let name = self.expect_ident();
         self.expect_symbol(Symbol::LBrace);
@@ -750,6 +920,7 @@ impl<'a> Parser<'a> {
 
         let end = self.expect_symbol(Symbol::RBrace);
         CapDecl {
+            attrs,
             name,
             operations,
             span: Span::new(start.start, end.end),
@@ -759,6 +930,11 @@ impl<'a> Parser<'a> {
     fn parse_operation_decl(&mut self) -> OperationDecl {
         let start = self.expect_keyword(Keyword::Fn);
         let name = self.expect_ident();
+        let generics = if self.at_symbol(Symbol::LBracket) {
+            self.parse_generics()
+        } else {
+            Vec::new()
+        };
         let params = if self.at_symbol(Symbol::LParen) {
             self.parse_params()
         } else {
@@ -788,13 +964,14 @@ impl<'a> Parser<'a> {
         }
         OperationDecl {
             name,
+            generics,
             params,
             return_type,
             span: Span::new(start.start, end.end),
         }
     }
 
-    fn parse_data_decl(&mut self) -> DataDecl {
+    fn parse_data_decl(&mut self, attrs: Vec<Attribute>) -> DataDecl {
         let start = self.expect_keyword(Keyword::Data);
         let name = self.expect_ident();
         let generics = if self.at_symbol(Symbol::LBracket) {
@@ -821,6 +998,7 @@ impl<'a> Parser<'a> {
 
         let end = self.expect_symbol(Symbol::RBrace);
         DataDecl {
+            attrs,
             name,
             generics,
             variants,
@@ -936,7 +1114,7 @@ impl<'a> Parser<'a> {
     }
 
     // Grammar: 'use' ident ('.' ident)* ['.' '{' ident (',' ident)* '}'] ';'
-    fn parse_use_decl(&mut self) -> UseDecl {
+    fn parse_use_decl(&mut self, attrs: Vec<Attribute>) -> UseDecl {
         let start = self.expect_keyword(Keyword::Use);
         let mut path = Vec::new();
 
@@ -946,6 +1124,7 @@ impl<'a> Parser<'a> {
         } else {
             self.error_here("expected module path after `use`");
             return UseDecl {
+                attrs,
                 path,
                 names: None,
                 span: start,
@@ -1002,6 +1181,7 @@ impl<'a> Parser<'a> {
         }
 
         UseDecl {
+            attrs,
             path,
             names,
             span: Span::new(start.start, end.end),
@@ -1013,7 +1193,7 @@ impl<'a> Parser<'a> {
     //   impl[generics] TargetType : Cap { methods }           -- unnamed cap impl
     //   impl TargetType { methods }                           -- inherent impl
     //   impl Name = TargetType { methods }                    -- named inherent impl
-    fn parse_impl_decl(&mut self) -> ImplDecl {
+    fn parse_impl_decl(&mut self, attrs: Vec<Attribute>) -> ImplDecl {
         let start = self.expect_keyword(Keyword::Impl);
 
         // Optional generics: impl[T: Clone]
@@ -1124,6 +1304,7 @@ impl<'a> Parser<'a> {
 
         let end = self.expect_symbol(Symbol::RBrace);
         ImplDecl {
+            attrs,
             name,
             generics,
             target_type,
@@ -1133,7 +1314,8 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parse params for impl methods. `self` is allowed without a type annotation.
+    /// Parse params for impl methods. `self` is allowed without a type
+    /// annotation, and so is any param of a cap op, which takes the op's type.
     fn parse_impl_params(&mut self) -> Vec<Param> {
         let mut out = Vec::new();
         self.expect_symbol(Symbol::LParen);
@@ -1148,21 +1330,21 @@ impl<'a> Parser<'a> {
             let name_token = self.bump().expect("checked at_ident").clone();
             let name = ident_text(&name_token).unwrap_or_default().to_owned();
 
-            if name == "self" && !self.at_symbol(Symbol::Colon) {
-                // `self` without type annotation — type injected during HIR lowering
+            if !self.at_symbol(Symbol::Colon) {
+                // No type annotation: `self` is `Self`, the rest get the
+                // `<missing>` sentinel, filled in from the op when typechecking.
+                let repr = if name == "self" { "Self" } else { "<missing>" };
                 out.push(Param {
                     name,
                     ty: TypeSig {
-                        repr: "Self".to_owned(),
+                        repr: repr.to_owned(),
                         span: name_token.span,
                     },
                     span: name_token.span,
                 });
             } else {
                 self.expect_symbol(Symbol::Colon);
-                let (repr, span) = self.collect_signature_until(|p| {
-                    p.at_symbol(Symbol::Comma) || p.at_symbol(Symbol::RParen)
-                });
+                let (repr, span) = self.collect_param_type_signature();
                 let ty = if let Some(span) = span {
                     TypeSig { repr, span }
                 } else {
@@ -1281,8 +1463,9 @@ impl<'a> Parser<'a> {
         out
     }
 
-    /// Like `collect_signature_until` but stops at `,` or `)` only at paren
-    /// depth 0, so nested types like `fn(T): R` are collected in full.
+    /// Like `collect_signature_until` but stops at `,` or `)` only at
+    /// paren/bracket/brace depth 0, so nested types like `fn(T): R`,
+    /// `Map[K, V]` and `thunk Unit / { .., Yield[A] }` are collected in full.
     fn collect_param_type_signature(&mut self) -> (String, Option<Span>) {
         let mut parts = Vec::new();
         let mut start = None;
@@ -1295,9 +1478,14 @@ impl<'a> Parser<'a> {
                     break;
                 }
                 depth -= 1;
+            } else if self.at_symbol(Symbol::RBracket) || self.at_symbol(Symbol::RBrace) {
+                depth = depth.saturating_sub(1);
             } else if self.at_symbol(Symbol::Comma) && depth == 0 {
                 break;
-            } else if self.at_symbol(Symbol::LParen) {
+            } else if self.at_symbol(Symbol::LParen)
+                || self.at_symbol(Symbol::LBracket)
+                || self.at_symbol(Symbol::LBrace)
+            {
                 depth += 1;
             }
             let token = match self.bump() {
@@ -1314,6 +1502,8 @@ impl<'a> Parser<'a> {
         (repr, span)
     }
 
+    /// Collect tokens until `stop` holds outside any `[...]`, so a `,`
+    /// inside `Map[K, V]` does not end the signature.
     fn collect_signature_until<F>(&mut self, stop: F) -> (String, Option<Span>)
     where
         F: Fn(&Self) -> bool,
@@ -1321,8 +1511,14 @@ impl<'a> Parser<'a> {
         let mut parts = Vec::new();
         let mut start = None;
         let mut end = None;
+        let mut depth: usize = 0;
 
-        while !self.eof() && !stop(self) {
+        while !(self.eof() || depth == 0 && stop(self)) {
+            if self.at_symbol(Symbol::LBracket) {
+                depth += 1;
+            } else if self.at_symbol(Symbol::RBracket) {
+                depth = depth.saturating_sub(1);
+            }
             let token = match self.bump() {
                 Some(t) => t,
                 None => break,
@@ -1426,6 +1622,10 @@ impl<'a> Parser<'a> {
             };
         }
 
+        if self.at_keyword(Keyword::Fn) {
+            return self.parse_lambda_expr();
+        }
+
         if self.at_keyword(Keyword::Force) {
             let start = self.expect_keyword(Keyword::Force);
             let expr = self.parse_expr();
@@ -1444,6 +1644,97 @@ impl<'a> Parser<'a> {
             return self.parse_if_expr();
         }
 
+        if self.at_keyword(Keyword::While) {
+            let start = self.expect_keyword(Keyword::While);
+            let condition = self.parse_expr_bp(0);
+            let body = self.parse_block();
+            let end = expr_span(&body);
+            return Expr::While {
+                condition: Box::new(condition),
+                body: Box::new(body),
+                span: Span::new(start.start, end.end),
+            };
+        }
+
+        if self.at_keyword(Keyword::For) {
+            let start = self.expect_keyword(Keyword::For);
+            let name = self.expect_ident();
+            self.expect_keyword(Keyword::In);
+            let iterable = self.parse_expr_bp(0);
+            let body = self.parse_block();
+            let end = expr_span(&body);
+            return Expr::For {
+                name,
+                iterable: Box::new(iterable),
+                body: Box::new(body),
+                span: Span::new(start.start, end.end),
+            };
+        }
+
+        if self.at_keyword(Keyword::Loop) {
+            let start = self.expect_keyword(Keyword::Loop);
+            let body = self.parse_block();
+            let end = expr_span(&body);
+            return Expr::Loop {
+                body: Box::new(body),
+                span: Span::new(start.start, end.end),
+            };
+        }
+
+        if self.at_keyword(Keyword::Break) {
+            let span = self.expect_keyword(Keyword::Break);
+            return Expr::Break { span };
+        }
+
+        if self.at_keyword(Keyword::Continue) {
+            let span = self.expect_keyword(Keyword::Continue);
+            return Expr::Continue { span };
+        }
+
+        if self.at_keyword(Keyword::Return) {
+            let start = self.expect_keyword(Keyword::Return);
+            // A bare `return` ends where its statement or block does.
+            if self.at_symbol(Symbol::Semi) || self.at_symbol(Symbol::RBrace) || self.at_symbol(Symbol::Comma) {
+                return Expr::Return { value: None, span: start };
+            }
+            let value = self.parse_expr();
+            let end = expr_span(&value);
+            return Expr::Return {
+                value: Some(Box::new(value)),
+                span: Span::new(start.start, end.end),
+            };
+        }
+
+        if self.at_keyword(Keyword::Try) {
+            let start = self.expect_keyword(Keyword::Try);
+            let body = self.parse_block();
+            self.expect_keyword(Keyword::Catch);
+            let name = self.expect_ident();
+            let error_type = if self.at_symbol(Symbol::Colon) {
+                self.bump();
+                let (repr, ty_span) = self.collect_signature_until(|p| p.at_symbol(Symbol::FatArrow));
+                match ty_span {
+                    Some(span) => Some(TypeSig { repr, span }),
+                    None => {
+                        self.error_here("expected type after `:`");
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            self.expect_symbol(Symbol::FatArrow);
+            let handler = self.parse_expr();
+            let end = expr_span(&handler);
+            return Expr::Try {
+                body: Box::new(body),
+                name,
+                error_type,
+                handler: Box::new(handler),
+                span: Span::new(start.start, end.end),
+            };
+        }
+
         if self.at_keyword(Keyword::Handle) {
             let start = self.expect_keyword(Keyword::Handle);
             let (cap, _) = self.collect_signature_until(|p| {
@@ -1494,14 +1785,12 @@ impl<'a> Parser<'a> {
             }
         } else if self.at_string_lit() {
             let token = self.bump().expect("checked at_string_lit").clone();
-            Expr::String {
-                value: decode_string_lit(token_text(&token).as_str()),
-                span: token.span,
-            }
+            self.parse_string_lit(&token)
         } else if self.at_number_lit() {
             let token = self.bump().expect("checked at_number_lit").clone();
+            let text = token_text(&token);
             Expr::Number {
-                value: token_text(&token),
+                value: lumo_lexer::decode_number(&text).unwrap_or(text),
                 span: token.span,
             }
         } else if self.at_symbol(Symbol::LParen) {
@@ -1563,6 +1852,17 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
+            // Postfix: ? (highest precedence)
+            if self.at_symbol(Symbol::Question) {
+                let start = expr_span(&expr).start;
+                let end = self.expect_symbol(Symbol::Question).end;
+                expr = Expr::Propagate {
+                    expr: Box::new(expr),
+                    span: Span::new(start, end),
+                };
+                continue;
+            }
+
             // Postfix: (args) (highest precedence)
             if self.at_symbol(Symbol::LParen) {
                 let start = expr_span(&expr).start;
@@ -1733,9 +2033,13 @@ impl<'a> Parser<'a> {
             let expr = self.parse_expr();
             let expr_span_val = expr_span(&expr);
 
-            if self.at_symbol(Symbol::Semi) {
+            // A loop ends in its own `}`, so it needs no `;` to be a statement.
+            let is_loop = matches!(expr, Expr::While { .. } | Expr::For { .. } | Expr::Loop { .. });
+            if self.at_symbol(Symbol::Semi) || (is_loop && !self.at_symbol(Symbol::RBrace)) {
                 // Expression statement
-                self.bump();
+                if self.at_symbol(Symbol::Semi) {
+                    self.bump();
+                }
                 stmts.push(BlockStmt::Expr {
                     expr,
                     span: expr_span_val,
@@ -1822,6 +2126,53 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// `fn(x, y: T) { body }`; a param's type may be left out.
+    fn parse_lambda_expr(&mut self) -> Expr {
+        let start = self.expect_keyword(Keyword::Fn);
+        let params = self.parse_optionally_typed_params();
+        let body = self.parse_block();
+        let end = expr_span(&body);
+        Expr::Lambda {
+            params,
+            body: Box::new(body),
+            span: Span::new(start.start, end.end),
+        }
+    }
+
+    /// `(x, y: T)`, as taken by fn literals and bundle entries, whose
+    /// param types can be inferred.
+    fn parse_optionally_typed_params(&mut self) -> Vec<(String, Option<TypeSig>)> {
+        self.expect_symbol(Symbol::LParen);
+        let mut params = Vec::new();
+        while !self.eof() && !self.at_symbol(Symbol::RParen) {
+            if !self.at_ident() {
+                self.error_here("expected parameter name");
+                self.bump();
+                continue;
+            }
+            let name = self.expect_ident();
+            let ty = if self.at_symbol(Symbol::Colon) {
+                self.bump();
+                let (repr, span) = self.collect_param_type_signature();
+                match span {
+                    Some(span) => Some(TypeSig { repr, span }),
+                    None => {
+                        self.error_here("expected parameter type");
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            params.push((name, ty));
+            if self.at_symbol(Symbol::Comma) {
+                self.bump();
+            }
+        }
+        self.expect_symbol(Symbol::RParen);
+        params
+    }
+
     fn parse_bundle_expr(&mut self) -> Expr {
         let start = self.expect_keyword(Keyword::Bundle);
         self.expect_symbol(Symbol::LBrace);
@@ -1838,7 +2189,7 @@ impl<'a> Parser<'a> {
 
             let name = self.expect_ident();
             let params = if self.at_symbol(Symbol::LParen) {
-                self.parse_params()
+                self.parse_optionally_typed_params()
             } else {
                 Vec::new()
             };
@@ -2012,9 +2363,11 @@ fn expr_span(expr: &Expr) -> Span {
     match expr {
         Expr::Ident { span, .. } => *span,
         Expr::String { span, .. } => *span,
+        Expr::Interp { span, .. } => *span,
         Expr::Member { span, .. } => *span,
         Expr::Call { span, .. } => *span,
         Expr::Thunk { span, .. } => *span,
+        Expr::Lambda { span, .. } => *span,
         Expr::Force { span, .. } => *span,
         Expr::Let { span, .. } => *span,
         Expr::Match { span, .. } => *span,
@@ -2028,38 +2381,18 @@ fn expr_span(expr: &Expr) -> Span {
         Expr::Ann { span, .. } => *span,
         Expr::Block { span, .. } => *span,
         Expr::IfElse { span, .. } => *span,
+        Expr::While { span, .. } => *span,
+        Expr::For { span, .. } => *span,
+        Expr::Loop { span, .. } => *span,
+        Expr::Break { span } => *span,
+        Expr::Continue { span } => *span,
+        Expr::Return { span, .. } => *span,
+        Expr::Try { span, .. } => *span,
+        Expr::Propagate { span, .. } => *span,
         Expr::Error { span } => *span,
     }
 }
 
-fn decode_string_lit(text: &str) -> String {
-    let inner = text
-        .strip_prefix('"')
-        .and_then(|s| s.strip_suffix('"'))
-        .unwrap_or(text);
-    let mut out = String::new();
-    let mut chars = inner.chars();
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            match chars.next() {
-                Some('n') => out.push('\n'),
-                Some('t') => out.push('\t'),
-                Some('r') => out.push('\r'),
-                Some('\\') => out.push('\\'),
-                Some('"') => out.push('"'),
-                Some(other) => {
-                    out.push('\\');
-                    out.push(other);
-                }
-                None => out.push('\\'),
-            }
-        } else {
-            out.push(c);
-        }
-    }
-    out
-}
-
 fn token_text(token: &Token) -> String {
     match &token.kind {
         TokenKind::Keyword(Keyword::Data) => "data".to_owned(),
@@ -2079,6 +2412,14 @@ fn token_text(token: &Token) -> String {
         TokenKind::Keyword(Keyword::Impl) => "impl".to_owned(),
         TokenKind::Keyword(Keyword::If) => "if".to_owned(),
         TokenKind::Keyword(Keyword::Else) => "else".to_owned(),
+        TokenKind::Keyword(Keyword::While) => "while".to_owned(),
+        TokenKind::Keyword(Keyword::For) => "for".to_owned(),
+        TokenKind::Keyword(Keyword::Loop) => "loop".to_owned(),
+        TokenKind::Keyword(Keyword::Break) => "break".to_owned(),
+        TokenKind::Keyword(Keyword::Continue) => "continue".to_owned(),
+        TokenKind::Keyword(Keyword::Return) => "return".to_owned(),
+        TokenKind::Keyword(Keyword::Try) => "try".to_owned(),
+        TokenKind::Keyword(Keyword::Catch) => "catch".to_owned(),
         TokenKind::Keyword(Keyword::Lambda) => "lambda".to_owned(),
         TokenKind::Keyword(Keyword::Roll) => "roll".to_owned(),
         TokenKind::Keyword(Keyword::Unroll) => "unroll".to_owned(),
@@ -2114,6 +2455,7 @@ fn token_text(token: &Token) -> String {
         TokenKind::Symbol(Symbol::BangEq) => "!=".to_owned(),
         TokenKind::Symbol(Symbol::AmpAmp) => "&&".to_owned(),
         TokenKind::Symbol(Symbol::PipePipe) => "||".to_owned(),
+        TokenKind::Symbol(Symbol::Question) => "?".to_owned(),
         TokenKind::NumberLit(s) => s.clone(),
     }
 }
@@ -123,6 +123,9 @@ pub enum Stmt {
         else_branch: Option<Block>,
     },
     Block(Block),
+    /// `while (cond) { body }`
+    While { cond: Expr, body: Block },
+    Continue,
     Function(FunctionDecl),
     TypeAlias(TypeAlias),
     Interface(InterfaceDecl),
@@ -133,6 +136,8 @@ pub enum Expr {
     Ident(String),
     String(String),
     Number(f64),
+    /// A `bigint` literal: decimal digits, emitted with an `n` suffix.
+    BigInt(String),
     Bool(bool),
     Null,
     Undefined,
@@ -191,6 +196,8 @@ pub enum UnaryOp {
     Minus,
     Not,
     BitNot,
+    /// `new <call>` — constructs a JS class instance.
+    New,
 }
 
 impl UnaryOp {
@@ -200,6 +207,7 @@ impl UnaryOp {
             UnaryOp::Minus => "-",
             UnaryOp::Not => "!",
             UnaryOp::BitNot => "~",
+            UnaryOp::New => "new ",
         }
     }
 }
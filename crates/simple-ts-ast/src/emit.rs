@@ -11,15 +11,30 @@ pub enum EmitTarget {
 pub struct Emitter {
     out: String,
     indent: usize,
+    /// One line per top-level statement, with no indentation.
+    compact: bool,
 }
 
 impl Emitter {
+    /// An emitter that puts each top-level statement on a single line.
+    /// Every emitted statement ends in `;` or `}`, so joining its lines
+    /// changes nothing for the parser.
+    pub fn compact() -> Self {
+        Self {
+            compact: true,
+            ..Self::default()
+        }
+    }
+
     pub fn emit_program(mut self, program: &Program, target: EmitTarget) -> String {
         for (i, stmt) in program.body.iter().enumerate() {
-            if i > 0 {
+            if i > 0 && !self.compact {
                 self.newline();
             }
             self.emit_stmt(stmt, target);
+            if self.compact && !self.out.is_empty() && !self.out.ends_with('\n') {
+                self.newline();
+            }
         }
         self.out
     }
@@ -100,6 +115,14 @@ impl Emitter {
                 self.indent -= 1;
                 self.line("}");
             }
+            Stmt::While { cond, body } => {
+                self.line(&format!("while ({}) {{", self.emit_expr(cond, target)));
+                self.indent += 1;
+                self.emit_block_items(body, target);
+                self.indent -= 1;
+                self.line("}");
+            }
+            Stmt::Continue => self.line("continue;"),
             Stmt::Function(decl) => {
                 if target == EmitTarget::TypeScriptDefinition {
                     self.emit_function_decl_signature(decl, true);
@@ -266,16 +289,9 @@ impl Emitter {
     fn emit_expr(&self, expr: &Expr, target: EmitTarget) -> String {
         match expr {
             Expr::Ident(name) => name.clone(),
-            Expr::String(value) => {
-                let escaped = value
-                    .replace('\\', "\\\\")
-                    .replace('"', "\\\"")
-                    .replace('\n', "\\n")
-                    .replace('\r', "\\r")
-                    .replace('\t', "\\t");
-                format!("\"{escaped}\"")
-            }
+            Expr::String(value) => format!("\"{}\"", escape_string(value)),
             Expr::Number(value) => value.to_string(),
+            Expr::BigInt(digits) => format!("{digits}n"),
             Expr::Bool(value) => value.to_string(),
             Expr::Null => "null".to_string(),
             Expr::Undefined => "undefined".to_string(),
@@ -354,6 +370,7 @@ impl Emitter {
                         let mut body_emitter = Emitter {
                             out: String::new(),
                             indent: 0,
+                            compact: self.compact,
                         };
                         body_emitter.line("{");
                         body_emitter.indent += 1;
@@ -380,7 +397,7 @@ impl Emitter {
     fn emit_object_key(&self, key: &ObjectKey, target: EmitTarget) -> String {
         match key {
             ObjectKey::Ident(name) => name.clone(),
-            ObjectKey::String(value) => format!("\"{}\"", value.replace('"', "\\\"")),
+            ObjectKey::String(value) => format!("\"{}\"", escape_string(value)),
             ObjectKey::Computed(expr) => format!("[{}]", self.emit_expr(expr, target)),
         }
     }
@@ -424,6 +441,12 @@ impl Emitter {
     }
 
     fn line(&mut self, text: &str) {
+        if self.compact {
+            for segment in text.split('\n') {
+                self.out.push_str(segment.trim_start());
+            }
+            return;
+        }
         // Multi-line expressions (e.g. `emit_expr` returning a block-body arrow)
         // arrive with their own internal indentation relative to column 0. To
         // nest them into the current context, prefix every non-empty line —
@@ -454,3 +477,23 @@ fn needs_parens_as_callee(expr: &Expr) -> bool {
         Expr::Ident(_) | Expr::Member { .. } | Expr::Index { .. } | Expr::Call { .. }
     )
 }
+
+/// `value` as the body of a double-quoted JS string literal.
+fn escape_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // Line terminators and other control characters.
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                out.push_str(&format!("\\u{{{:x}}}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -7,7 +7,11 @@ mod tests;
 pub use ast::*;
 pub use emit::{EmitTarget, Emitter};
 pub use pass::{
-    collapse_let_to_const, expr_to_block, flatten_iifes, inline_always_calls,
-    inline_literal_consts, inline_single_use_consts, inline_trivial_consts,
-    lower_expression_bodies, return_lifting, simplify_bool_comparisons,
+    collapse_let_to_const, direct_perform_dispatch, drop_unused_caps_params,
+    elide_tail_thunks, eta_reduce_continuations, expr_references_name, expr_to_block,
+    flatten_iifes,
+    inline_always_calls, inline_literal_consts, inline_single_use_consts,
+    inline_trivial_consts, loop_self_tail_calls, lower_expression_bodies, optimize_cps,
+    return_lifting,
+    simplify_bool_comparisons, OptLevel,
 };
@@ -406,8 +406,8 @@ pub fn inline_single_use_consts(program: &mut Program) {
 
 fn is_pure_expr(expr: &Expr) -> bool {
     match expr {
-        Expr::Ident(_) | Expr::String(_) | Expr::Number(_) | Expr::Bool(_)
-        | Expr::Null | Expr::Undefined => true,
+        Expr::Ident(_) | Expr::String(_) | Expr::Number(_) | Expr::BigInt(_)
+        | Expr::Bool(_) | Expr::Null | Expr::Undefined => true,
         Expr::Binary { left, right, .. } => is_pure_expr(left) && is_pure_expr(right),
         Expr::Unary { expr, .. } | Expr::Void(expr) => is_pure_expr(expr),
         Expr::Member { object, .. } => is_pure_expr(object),
@@ -684,9 +684,12 @@ fn subst_first_stmt(stmt: &mut Stmt, name: &str, replacement: &Expr) -> bool {
     }
 }
 
+// Walks back to front, so a later impure const is already folded into its
+// use by the time an earlier one checks what lies between.
 fn single_use_in_block(block: &mut Block) {
-    let mut i = 0;
-    while i < block.stmts.len() {
+    let mut i = block.stmts.len();
+    while i > 0 {
+        i -= 1;
         if let Stmt::Const(decl) = &block.stmts[i] {
             if !decl.export {
                 let name = decl.name.clone();
@@ -696,21 +699,34 @@ fn single_use_in_block(block: &mut Block) {
                     .iter()
                     .map(|s| count_refs_stmt(s, &name))
                     .sum();
-                if total == 1 {
+                // A const that refers to itself (a recursive arrow) needs its name.
+                if total == 1 && count_refs_expr(&init, &name) == 0 {
+                    let use_at = (i + 1..block.stmts.len())
+                        .find(|&j| count_refs_stmt(&block.stmts[j], &name) > 0)
+                        .unwrap_or(i);
+                    // An impure `init` keeps its place among other effects:
+                    // nothing between it and its use may run a call, and the
+                    // use must be read before its statement runs one.
                     let inlinable = pure || {
                         let top: usize = block.stmts[i + 1..]
                             .iter()
                             .map(|s| count_top_refs_stmt(s, &name))
                             .sum();
                         top == 1
+                            && block.stmts[i + 1..use_at].iter().all(stmt_is_effect_free)
+                            && use_precedes_effects(&block.stmts[use_at], &name)
                     };
-                    if inlinable {
-                        for j in (i + 1)..block.stmts.len() {
-                            if count_refs_stmt(&block.stmts[j], &name) > 0 {
-                                subst_first_stmt(&mut block.stmts[j], &name, &init);
-                                break;
-                            }
+                    // Moving `init` past `const x = ...` would make its `x`
+                    // read that binding instead of the outer one.
+                    let captured = block.stmts[i + 1..=use_at].iter().any(|s| match s {
+                        Stmt::Const(d) => expr_references_name(&init, &d.name),
+                        Stmt::Let { name: n, .. } | Stmt::Assign { name: n, .. } => {
+                            expr_references_name(&init, n)
                         }
+                        _ => false,
+                    });
+                    if inlinable && !captured {
+                        subst_first_stmt(&mut block.stmts[use_at], &name, &init);
                         block.stmts.remove(i);
                         continue;
                     }
@@ -718,7 +734,68 @@ fn single_use_in_block(block: &mut Block) {
             }
         }
         single_use_in_stmt(&mut block.stmts[i]);
-        i += 1;
+    }
+}
+
+fn stmt_is_effect_free(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Const(d) => is_pure_expr(&d.init),
+        Stmt::Let { init, .. } => init.as_ref().is_none_or(is_pure_expr),
+        Stmt::Assign { value, .. } => is_pure_expr(value),
+        Stmt::Function(_) | Stmt::TypeAlias(_) | Stmt::Interface(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether running `stmt` reads `name` before it makes any call.
+fn use_precedes_effects(stmt: &Stmt, name: &str) -> bool {
+    let first = match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => first_use_or_effect(e, name),
+        Stmt::Const(d) => first_use_or_effect(&d.init, name),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => {
+            first_use_or_effect(e, name)
+        }
+        Stmt::If { cond, .. } => first_use_or_effect(cond, name),
+        _ => None,
+    };
+    first == Some(true)
+}
+
+/// In evaluation order, `Some(true)` if `expr` reads `name` before making a
+/// call, `Some(false)` if it may call first, `None` if it does neither. A
+/// read that may be skipped (behind `&&`, `||` or `?:`) counts as a call.
+fn first_use_or_effect(expr: &Expr, name: &str) -> Option<bool> {
+    let conditional = |e: &Expr| {
+        (expr_references_name(e, name) || !is_pure_expr(e)).then_some(false)
+    };
+    match expr {
+        Expr::Ident(n) => (n == name).then_some(true),
+        Expr::Call { callee, args } => first_use_or_effect(callee, name)
+            .or_else(|| args.iter().find_map(|a| first_use_or_effect(a, name)))
+            .or(Some(false)),
+        Expr::Member { object, .. } => first_use_or_effect(object, name),
+        Expr::Index { object, index } => {
+            first_use_or_effect(object, name).or_else(|| first_use_or_effect(index, name))
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => first_use_or_effect(expr, name),
+        Expr::Binary { op: BinaryOp::AndAnd | BinaryOp::OrOr, left, right } => {
+            first_use_or_effect(left, name).or_else(|| conditional(right))
+        }
+        Expr::Binary { left, right, .. } => {
+            first_use_or_effect(left, name).or_else(|| first_use_or_effect(right, name))
+        }
+        Expr::Array(items) => items.iter().find_map(|i| first_use_or_effect(i, name)),
+        Expr::Object(props) => props.iter().find_map(|p| {
+            let key = match &p.key {
+                ObjectKey::Computed(e) => first_use_or_effect(e, name),
+                _ => None,
+            };
+            key.or_else(|| first_use_or_effect(&p.value, name))
+        }),
+        Expr::IfElse { cond, then_expr, else_expr } => first_use_or_effect(cond, name)
+            .or_else(|| conditional(then_expr))
+            .or_else(|| conditional(else_expr)),
+        _ => None,
     }
 }
 
@@ -806,7 +883,12 @@ pub fn inline_literal_consts(program: &mut Program) {
 fn is_literal(expr: &Expr) -> bool {
     matches!(
         expr,
-        Expr::String(_) | Expr::Number(_) | Expr::Bool(_) | Expr::Null | Expr::Undefined
+        Expr::String(_)
+            | Expr::Number(_)
+            | Expr::BigInt(_)
+            | Expr::Bool(_)
+            | Expr::Null
+            | Expr::Undefined
     )
 }
 
@@ -1047,6 +1129,7 @@ fn inline_in_expr(expr: &mut Expr) {
         Expr::Ident(_)
         | Expr::String(_)
         | Expr::Number(_)
+        | Expr::BigInt(_)
         | Expr::Bool(_)
         | Expr::Null
         | Expr::Undefined => {}
@@ -1155,7 +1238,7 @@ fn inline_subst_expr(expr: &mut Expr, subs: &std::collections::HashMap<String, S
                 inline_subst_function_body(body, &filtered);
             }
         }
-        Expr::String(_) | Expr::Number(_) | Expr::Bool(_)
+        Expr::String(_) | Expr::Number(_) | Expr::BigInt(_) | Expr::Bool(_)
         | Expr::Null | Expr::Undefined => {}
     }
 }
@@ -1198,7 +1281,11 @@ fn lower_stmt_expr_bodies(stmt: &mut Stmt) {
             }
         }
         Stmt::Assign { value, .. } => lower_expr(value),
-        Stmt::TypeAlias(_) | Stmt::Interface(_) => {}
+        Stmt::While { cond, body } => {
+            lower_expr(cond);
+            lower_block_expr_bodies(body);
+        }
+        Stmt::Continue | Stmt::TypeAlias(_) | Stmt::Interface(_) => {}
     }
 }
 
@@ -1266,6 +1353,7 @@ fn lower_expr(expr: &mut Expr) {
         Expr::Ident(_)
         | Expr::String(_)
         | Expr::Number(_)
+        | Expr::BigInt(_)
         | Expr::Bool(_)
         | Expr::Null
         | Expr::Undefined => {}
@@ -1299,7 +1387,11 @@ fn lift_stmt(stmt: &mut Stmt) {
             }
         }
         Stmt::Assign { value, .. } => lift_expr(value),
-        Stmt::TypeAlias(_) | Stmt::Interface(_) => {}
+        Stmt::While { cond, body } => {
+            lift_expr(cond);
+            lift_block(body);
+        }
+        Stmt::Continue | Stmt::TypeAlias(_) | Stmt::Interface(_) => {}
     }
 }
 
@@ -1433,7 +1525,11 @@ fn rename_free_in_stmt(stmt: &mut Stmt, old: &str, new: &str) {
 /// Walk `block`'s statements in order; stop renaming once a same-block
 /// `let`/`const` binds `old` (it shadows from declaration onward).
 fn rename_free_in_block(block: &mut Block, old: &str, new: &str) {
-    for stmt in block.stmts.iter_mut() {
+    rename_free_in_stmts(&mut block.stmts, old, new);
+}
+
+fn rename_free_in_stmts(stmts: &mut [Stmt], old: &str, new: &str) {
+    for stmt in stmts.iter_mut() {
         rename_free_in_stmt(stmt, old, new);
         let shadowed = match stmt {
             Stmt::Const(decl) => decl.name == old,
@@ -1496,12 +1592,13 @@ fn rename_free_in_expr(expr: &mut Expr, old: &str, new: &str) {
                 FunctionBody::Block(b) => rename_free_in_block(b, old, new),
             }
         }
-        Expr::String(_) | Expr::Number(_) | Expr::Bool(_) | Expr::Null | Expr::Undefined => {}
+        Expr::String(_) | Expr::Number(_) | Expr::BigInt(_) | Expr::Bool(_)
+        | Expr::Null | Expr::Undefined => {}
     }
 }
 
 /// Returns `true` if `expr` references the given identifier as a free name.
-fn expr_references_name(expr: &Expr, name: &str) -> bool {
+pub fn expr_references_name(expr: &Expr, name: &str) -> bool {
     match expr {
         Expr::Ident(n) => n == name,
         Expr::Call { callee, args } => {
@@ -1536,7 +1633,7 @@ fn expr_references_name(expr: &Expr, name: &str) -> bool {
                 FunctionBody::Block(b) => b.stmts.iter().any(|s| stmt_references_name(s, name)),
             }
         }
-        Expr::String(_) | Expr::Number(_) | Expr::Bool(_)
+        Expr::String(_) | Expr::Number(_) | Expr::BigInt(_) | Expr::Bool(_)
         | Expr::Null | Expr::Undefined => false,
     }
 }
@@ -1582,9 +1679,39 @@ fn param_const_stmts(params: Vec<Param>, args: Vec<Expr>) -> Vec<Stmt> {
         .collect()
 }
 
-/// Try to flatten an IIFE in the given statement.
+/// Alpha-rename IIFE params and top-level body bindings that `rest` (the
+/// statements following the IIFE in its block) still references. Once the
+/// body is spliced into the block those bindings would otherwise shadow the
+/// outer names for the remainder of the block.
+fn avoid_capturing_rest(params: &mut [Param], body: &mut [Stmt], rest: &[Stmt]) {
+    let used_later = |name: &str| rest.iter().any(|s| stmt_references_name(s, name));
+    for param in params.iter_mut() {
+        if !used_later(&param.name) {
+            continue;
+        }
+        let fresh = fresh_iife_param(&param.name);
+        let old = std::mem::replace(&mut param.name, fresh.clone());
+        rename_free_in_stmts(body, &old, &fresh);
+    }
+    for i in 0..body.len() {
+        let decl_name = match &mut body[i] {
+            Stmt::Const(decl) => &mut decl.name,
+            Stmt::Let { name, .. } => name,
+            _ => continue,
+        };
+        if !used_later(decl_name) {
+            continue;
+        }
+        let fresh = fresh_iife_param(decl_name);
+        let old = std::mem::replace(decl_name, fresh.clone());
+        rename_free_in_stmts(&mut body[i + 1..], &old, &fresh);
+    }
+}
+
+/// Try to flatten an IIFE in the given statement. `rest` holds the
+/// statements that follow it in the same block.
 /// Returns `Some(replacement_stmts)` if flattened, `None` otherwise.
-fn try_flatten(stmt: &mut Stmt) -> Option<Vec<Stmt>> {
+fn try_flatten(stmt: &mut Stmt, rest: &[Stmt]) -> Option<Vec<Stmt>> {
     match stmt {
         Stmt::Return(Some(expr)) => {
             let (params, body_stmts, args) = take_iife(expr)?;
@@ -1593,7 +1720,8 @@ fn try_flatten(stmt: &mut Stmt) -> Option<Vec<Stmt>> {
             Some(out)
         }
         Stmt::Expr(expr) => {
-            let (params, mut body_stmts, args) = take_iife(expr)?;
+            let (mut params, mut body_stmts, args) = take_iife(expr)?;
+            avoid_capturing_rest(&mut params, &mut body_stmts, rest);
             // Rewrite final `return e` → `Stmt::Expr(e)`, drop bare `return`
             if let Some(last) = body_stmts.last_mut() {
                 if let Stmt::Return(ret_expr) = last {
@@ -1608,7 +1736,8 @@ fn try_flatten(stmt: &mut Stmt) -> Option<Vec<Stmt>> {
             Some(out)
         }
         Stmt::Const(decl) => {
-            let (params, mut body_stmts, args) = take_iife(&mut decl.init)?;
+            let (mut params, mut body_stmts, args) = take_iife(&mut decl.init)?;
+            avoid_capturing_rest(&mut params, &mut body_stmts, rest);
             let outer_name = std::mem::take(&mut decl.name);
             let outer_export = decl.export;
             let outer_type_ann = decl.type_ann.take();
@@ -1632,7 +1761,8 @@ fn try_flatten(stmt: &mut Stmt) -> Option<Vec<Stmt>> {
             // `outer = ((x) => { ...; return e; })(v)` → `const x = v; ...; outer = e`
             // `outer` is already declared elsewhere (caller's `let outer;`), so no
             // declaration is inserted — just rewrite leaf returns to assignments.
-            let (params, mut body_stmts, args) = take_iife(value)?;
+            let (mut params, mut body_stmts, args) = take_iife(value)?;
+            avoid_capturing_rest(&mut params, &mut body_stmts, rest);
             let outer_name = std::mem::take(name);
             if !rewrite_returns_to_assign(&mut body_stmts, &outer_name) {
                 return None;
@@ -1686,7 +1816,8 @@ fn flatten_block_with_params(block: &mut Block, enclosing_names: &[String]) {
     // Phase 1: inline IIFEs at statement level
     let mut i = 0;
     while i < block.stmts.len() {
-        if let Some(replacement) = try_flatten(&mut block.stmts[i]) {
+        let (head, rest) = block.stmts.split_at_mut(i + 1);
+        if let Some(replacement) = try_flatten(&mut head[i], rest) {
             let _ = block.stmts.remove(i);
             for (j, s) in replacement.into_iter().enumerate() {
                 block.stmts.insert(i + j, s);
@@ -1740,7 +1871,10 @@ fn dedup_const_names_with_params(block: &mut Block, param_names: &[String]) {
             _ => None,
         };
         if let Some(orig) = decl_name {
-            if declared.contains(&orig) {
+            // An earlier statement reading `orig` outside any closure meant
+            // the outer binding; keeping the name would put it in the TDZ.
+            let read_before = block.stmts[..i].iter().any(|s| stmt_reads_eagerly(s, &orig));
+            if declared.contains(&orig) || read_before {
                 let fresh = loop {
                     let candidate = format!("{}_{}", orig, counter);
                     counter += 1;
@@ -1766,6 +1900,50 @@ fn dedup_const_names_with_params(block: &mut Block, param_names: &[String]) {
     }
 }
 
+/// Whether running `stmt` reads `name` right away, i.e. outside any arrow.
+fn stmt_reads_eagerly(stmt: &Stmt, name: &str) -> bool {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => reads_eagerly(e, name),
+        Stmt::Const(decl) => reads_eagerly(&decl.init, name),
+        Stmt::Let { init: Some(init), .. } => reads_eagerly(init, name),
+        Stmt::Assign { value, .. } => reads_eagerly(value, name),
+        Stmt::If { cond, then_branch, else_branch } => {
+            reads_eagerly(cond, name)
+                || then_branch.stmts.iter().any(|s| stmt_reads_eagerly(s, name))
+                || else_branch
+                    .as_ref()
+                    .is_some_and(|eb| eb.stmts.iter().any(|s| stmt_reads_eagerly(s, name)))
+        }
+        Stmt::Block(b) => b.stmts.iter().any(|s| stmt_reads_eagerly(s, name)),
+        _ => false,
+    }
+}
+
+fn reads_eagerly(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Ident(n) => n == name,
+        Expr::Call { callee, args } => {
+            reads_eagerly(callee, name) || args.iter().any(|a| reads_eagerly(a, name))
+        }
+        Expr::Member { object, .. } => reads_eagerly(object, name),
+        Expr::Index { object, index } => reads_eagerly(object, name) || reads_eagerly(index, name),
+        Expr::Unary { expr, .. } | Expr::Void(expr) => reads_eagerly(expr, name),
+        Expr::Binary { left, right, .. } => reads_eagerly(left, name) || reads_eagerly(right, name),
+        Expr::Array(items) => items.iter().any(|i| reads_eagerly(i, name)),
+        Expr::Object(props) => props.iter().any(|p| {
+            matches!(&p.key, ObjectKey::Computed(e) if reads_eagerly(e, name))
+                || reads_eagerly(&p.value, name)
+        }),
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            reads_eagerly(cond, name) || reads_eagerly(then_expr, name) || reads_eagerly(else_expr, name)
+        }
+        // An arrow only reads its captures when called.
+        Expr::Arrow { .. } => false,
+        Expr::String(_) | Expr::Number(_) | Expr::BigInt(_) | Expr::Bool(_)
+        | Expr::Null | Expr::Undefined => false,
+    }
+}
+
 fn rename_idents_in_stmt(stmt: &mut Stmt, map: &std::collections::HashMap<String, String>) {
     match stmt {
         Stmt::Expr(expr) | Stmt::Return(Some(expr)) => rename_idents_in_expr(expr, map),
@@ -1830,9 +2008,14 @@ fn rename_idents_in_expr(expr: &mut Expr, map: &std::collections::HashMap<String
             rename_idents_in_expr(then_expr, map);
             rename_idents_in_expr(else_expr, map);
         }
-        // Don't recurse into Arrow bodies — they have their own scope
-        Expr::Arrow { .. } => {}
-        Expr::String(_) | Expr::Number(_) | Expr::Bool(_)
+        // An arrow's captures follow the rename unless its own bindings
+        // shadow them.
+        Expr::Arrow { .. } => {
+            for (old, new) in map {
+                rename_free_in_expr(expr, old, new);
+            }
+        }
+        Expr::String(_) | Expr::Number(_) | Expr::BigInt(_) | Expr::Bool(_)
         | Expr::Null | Expr::Undefined => {}
     }
 }
@@ -1867,7 +2050,15 @@ fn flatten_stmt_with_enclosing(stmt: &mut Stmt, enclosing_names: &[String]) {
         Stmt::Let { init: Some(init), .. } => flatten_expr_arrows(init),
         Stmt::Assign { value, .. } => flatten_expr_arrows(value),
         Stmt::Expr(expr) | Stmt::Return(Some(expr)) => flatten_expr_arrows(expr),
-        Stmt::Return(None) | Stmt::Let { .. } | Stmt::TypeAlias(_) | Stmt::Interface(_) => {}
+        Stmt::While { cond, body } => {
+            flatten_expr_arrows(cond);
+            flatten_block_with_params(body, enclosing_names);
+        }
+        Stmt::Return(None)
+        | Stmt::Let { .. }
+        | Stmt::Continue
+        | Stmt::TypeAlias(_)
+        | Stmt::Interface(_) => {}
     }
 }
 
@@ -1930,6 +2121,7 @@ fn flatten_expr_arrows(expr: &mut Expr) {
         Expr::Ident(_)
         | Expr::String(_)
         | Expr::Number(_)
+        | Expr::BigInt(_)
         | Expr::Bool(_)
         | Expr::Null
         | Expr::Undefined => {}
@@ -1983,8 +2175,1274 @@ fn lift_expr(expr: &mut Expr) {
         Expr::Ident(_)
         | Expr::String(_)
         | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CPS optimization passes
+//
+// Unlike the readability passes above, these know the shape of the Lumo CPS
+// runtime: effectful functions take a `__caps` bundle first and a
+// continuation last, and return `__thunk(() => ...)` for `__trampoline` to
+// bounce. Each rewrite relies on invariants the backend guarantees:
+//   - a continuation is always invoked with exactly one argument;
+//   - a caps bundle is never mutated (handles build a fresh one);
+//   - every `__caps.<Cap>.<op>(...)` call returns a thunk.
+// See plans/cps-optimization.md.
+// ---------------------------------------------------------------------------
+
+const THUNK_FN: &str = "__thunk";
+const CAPS_NAME: &str = "__caps";
+
+/// How aggressively `optimize_cps` rewrites CPS output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptLevel {
+    /// No CPS rewrites; output mirrors the lowering one-to-one.
+    #[default]
+    O0,
+    /// Rewrites that keep every emitted signature intact: continuation
+    /// eta-reduction, tail `__thunk` elision and direct perform dispatch.
+    O1,
+    /// Everything in `O1`, plus dropping `__caps` params that a function
+    /// never reads. Changes the arity of exported functions, so JS callers
+    /// outside the emitted module must not rely on the CPS calling convention.
+    O2,
+}
+
+impl OptLevel {
+    /// Parse `"0"`, `"1"` or `"2"` (as given to `--opt-level`).
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "0" => Some(OptLevel::O0),
+            "1" => Some(OptLevel::O1),
+            "2" => Some(OptLevel::O2),
+            _ => None,
+        }
+    }
+}
+
+/// Run the CPS optimization pipeline at `level`. Expects a program that has
+/// already been through the readability passes (`return_lifting`,
+/// `flatten_iifes`, ...), so function bodies are blocks ending in `return`.
+pub fn optimize_cps(program: &mut Program, level: OptLevel) {
+    if level == OptLevel::O0 {
+        return;
+    }
+    eta_reduce_continuations(program);
+    elide_tail_thunks(program);
+    if level >= OptLevel::O2 {
+        drop_unused_caps_params(program);
+        // `(v) => f(__caps, v)` becomes `(v) => f(v)` once `f` stops taking
+        // `__caps`, which is eta-reducible in turn.
+        eta_reduce_continuations(program);
+    }
+    direct_perform_dispatch(program);
+}
+
+// ---------------------------------------------------------------------------
+// Pass: eta_reduce_continuations
+//
+// Rewrites a continuation argument `(v) => k(v)` to plain `k`, saving one
+// closure allocation per call. Only arrows in continuation position (the
+// last argument of a call) with a single param are considered, and only when
+// the param is used exactly once, as the sole argument. `k` must be an
+// identifier whose binding is safe to read early: a parameter of an
+// enclosing function or a hoisted top-level function, never reassigned.
+// `const`/`let` bindings are excluded because reading them at closure
+// creation time may hit the TDZ.
+// ---------------------------------------------------------------------------
+
+pub fn eta_reduce_continuations(program: &mut Program) {
+    let mut assigned = std::collections::HashSet::new();
+    for stmt in &program.body {
+        collect_assigned_names(stmt, &mut assigned);
+    }
+    let mut scope: Vec<(String, bool)> = Vec::new();
+    push_block_bindings(&program.body, &mut scope);
+    for stmt in &mut program.body {
+        eta_stmt(stmt, &mut scope, &assigned);
+    }
+}
+
+fn collect_assigned_names(stmt: &Stmt, out: &mut std::collections::HashSet<String>) {
+    let mut visit = |s: &Stmt| {
+        if let Stmt::Assign { name, .. } = s {
+            out.insert(name.clone());
+        }
+    };
+    walk_stmts(std::slice::from_ref(stmt), &mut visit);
+}
+
+/// Call `f` on every statement reachable from `stmts`, including statements
+/// nested in function and arrow bodies.
+fn walk_stmts(stmts: &[Stmt], f: &mut impl FnMut(&Stmt)) {
+    for stmt in stmts {
+        f(stmt);
+        match stmt {
+            Stmt::Expr(e) | Stmt::Return(Some(e)) => walk_expr_stmts(e, f),
+            Stmt::Const(decl) => walk_expr_stmts(&decl.init, f),
+            Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => {
+                walk_expr_stmts(e, f)
+            }
+            Stmt::If { cond, then_branch, else_branch } => {
+                walk_expr_stmts(cond, f);
+                walk_stmts(&then_branch.stmts, f);
+                if let Some(eb) = else_branch {
+                    walk_stmts(&eb.stmts, f);
+                }
+            }
+            Stmt::Block(b) => walk_stmts(&b.stmts, f),
+            Stmt::Function(func) => match &func.body {
+                FunctionBody::Expr(e) => walk_expr_stmts(e, f),
+                FunctionBody::Block(b) => walk_stmts(&b.stmts, f),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn walk_expr_stmts(expr: &Expr, f: &mut impl FnMut(&Stmt)) {
+    match expr {
+        Expr::Arrow { body, .. } => match body.as_ref() {
+            FunctionBody::Expr(e) => walk_expr_stmts(e, f),
+            FunctionBody::Block(b) => walk_stmts(&b.stmts, f),
+        },
+        Expr::Call { callee, args } => {
+            walk_expr_stmts(callee, f);
+            for a in args {
+                walk_expr_stmts(a, f);
+            }
+        }
+        Expr::Member { object, .. } => walk_expr_stmts(object, f),
+        Expr::Index { object, index } => {
+            walk_expr_stmts(object, f);
+            walk_expr_stmts(index, f);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => walk_expr_stmts(expr, f),
+        Expr::Binary { left, right, .. } => {
+            walk_expr_stmts(left, f);
+            walk_expr_stmts(right, f);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                walk_expr_stmts(item, f);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &prop.key {
+                    walk_expr_stmts(e, f);
+                }
+                walk_expr_stmts(&prop.value, f);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            walk_expr_stmts(cond, f);
+            walk_expr_stmts(then_expr, f);
+            walk_expr_stmts(else_expr, f);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+}
+
+/// Push the bindings a block introduces. Function declarations are hoisted
+/// and therefore safe to reference early; `const`/`let` are not.
+fn push_block_bindings(stmts: &[Stmt], scope: &mut Vec<(String, bool)>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Function(f) => scope.push((f.name.clone(), true)),
+            Stmt::Const(d) => scope.push((d.name.clone(), false)),
+            Stmt::Let { name, .. } => scope.push((name.clone(), false)),
+            _ => {}
+        }
+    }
+}
+
+fn is_early_readable(
+    name: &str,
+    scope: &[(String, bool)],
+    assigned: &std::collections::HashSet<String>,
+) -> bool {
+    if assigned.contains(name) {
+        return false;
+    }
+    scope
+        .iter()
+        .rev()
+        .find(|(n, _)| n == name)
+        .is_some_and(|(_, safe)| *safe)
+}
+
+fn eta_block(
+    block: &mut Block,
+    scope: &mut Vec<(String, bool)>,
+    assigned: &std::collections::HashSet<String>,
+) {
+    let mark = scope.len();
+    push_block_bindings(&block.stmts, scope);
+    for stmt in &mut block.stmts {
+        eta_stmt(stmt, scope, assigned);
+    }
+    scope.truncate(mark);
+}
+
+fn eta_function_body(
+    params: &[Param],
+    body: &mut FunctionBody,
+    scope: &mut Vec<(String, bool)>,
+    assigned: &std::collections::HashSet<String>,
+) {
+    let mark = scope.len();
+    scope.extend(params.iter().map(|p| (p.name.clone(), true)));
+    match body {
+        FunctionBody::Expr(e) => eta_expr(e, scope, assigned),
+        FunctionBody::Block(b) => eta_block(b, scope, assigned),
+    }
+    scope.truncate(mark);
+}
+
+fn eta_stmt(
+    stmt: &mut Stmt,
+    scope: &mut Vec<(String, bool)>,
+    assigned: &std::collections::HashSet<String>,
+) {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => eta_expr(e, scope, assigned),
+        Stmt::Const(decl) => eta_expr(&mut decl.init, scope, assigned),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => {
+            eta_expr(e, scope, assigned)
+        }
+        Stmt::If { cond, then_branch, else_branch } => {
+            eta_expr(cond, scope, assigned);
+            eta_block(then_branch, scope, assigned);
+            if let Some(eb) = else_branch {
+                eta_block(eb, scope, assigned);
+            }
+        }
+        Stmt::Block(b) => eta_block(b, scope, assigned),
+        Stmt::Function(f) => eta_function_body(&f.params, &mut f.body, scope, assigned),
+        _ => {}
+    }
+}
+
+fn eta_expr(
+    expr: &mut Expr,
+    scope: &mut Vec<(String, bool)>,
+    assigned: &std::collections::HashSet<String>,
+) {
+    match expr {
+        Expr::Call { callee, args } => {
+            eta_expr(callee, scope, assigned);
+            for a in args.iter_mut() {
+                eta_expr(a, scope, assigned);
+            }
+            if let Some(last) = args.last_mut() {
+                if let Some(k) = eta_target(last) {
+                    if is_early_readable(&k, scope, assigned) {
+                        *last = Expr::Ident(k);
+                    }
+                }
+            }
+        }
+        Expr::Arrow { params, body, .. } => eta_function_body(params, body, scope, assigned),
+        Expr::Member { object, .. } => eta_expr(object, scope, assigned),
+        Expr::Index { object, index } => {
+            eta_expr(object, scope, assigned);
+            eta_expr(index, scope, assigned);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => eta_expr(expr, scope, assigned),
+        Expr::Binary { left, right, .. } => {
+            eta_expr(left, scope, assigned);
+            eta_expr(right, scope, assigned);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                eta_expr(item, scope, assigned);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &mut prop.key {
+                    eta_expr(e, scope, assigned);
+                }
+                eta_expr(&mut prop.value, scope, assigned);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            eta_expr(cond, scope, assigned);
+            eta_expr(then_expr, scope, assigned);
+            eta_expr(else_expr, scope, assigned);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
         | Expr::Bool(_)
         | Expr::Null
         | Expr::Undefined => {}
     }
 }
+
+/// If `expr` is `(v) => k(v)` (expression or single-`return` block body),
+/// return `k`.
+fn eta_target(expr: &Expr) -> Option<String> {
+    let Expr::Arrow { params, body, .. } = expr else {
+        return None;
+    };
+    let [param] = params.as_slice() else {
+        return None;
+    };
+    let call = match body.as_ref() {
+        FunctionBody::Expr(e) => e.as_ref(),
+        FunctionBody::Block(b) => match b.stmts.as_slice() {
+            [Stmt::Return(Some(e))] => e,
+            _ => return None,
+        },
+    };
+    let Expr::Call { callee, args } = call else {
+        return None;
+    };
+    let Expr::Ident(k) = callee.as_ref() else {
+        return None;
+    };
+    match args.as_slice() {
+        [Expr::Ident(arg)] if arg == &param.name && k != &param.name => Some(k.clone()),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pass: elide_tail_thunks
+//
+// `return __thunk(() => g(...))` bounces the trampoline twice when `g`
+// already returns a thunk: once for the outer wrap, once for `g`'s own.
+// When a function's whole body is such a tail call, drop the outer wrap and
+// return the call directly. Covers top-level functions and methods of
+// top-level object literals (inherent impls, handler bundles).
+//
+// A callee is known to return a thunk when it is a top-level function whose
+// body is `__thunk`-wrapped, or a `__caps.<Cap>.<op>` dispatch. Eliding the
+// wrap on a chain of passthroughs that calls back into itself would turn a
+// bounce into unbounded recursion, so one member of every such cycle keeps
+// its wrap.
+// ---------------------------------------------------------------------------
+
+pub fn elide_tail_thunks(program: &mut Program) {
+    use std::collections::{HashMap, HashSet};
+
+    let thunked: HashSet<String> = program
+        .body
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function(f) if thunk_wrapped_body(&f.body).is_some() => Some(f.name.clone()),
+            _ => None,
+        })
+        .collect();
+    let returns_thunk = |call: &Expr| match call {
+        Expr::Call { callee, .. } => match callee.as_ref() {
+            Expr::Ident(name) => thunked.contains(name),
+            callee @ Expr::Member { .. } => cap_dispatch_key(callee).is_some(),
+            _ => false,
+        },
+        _ => false,
+    };
+
+    // Tail edges between top-level candidates, used to break cycles.
+    let mut edges: HashMap<String, String> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for stmt in &program.body {
+        let Stmt::Function(f) = stmt else { continue };
+        let Some(call) = thunk_wrapped_body(&f.body).and_then(tail_call_of_thunk) else {
+            continue;
+        };
+        if !returns_thunk(call) {
+            continue;
+        }
+        order.push(f.name.clone());
+        if let Expr::Call { callee, .. } = call {
+            if let Expr::Ident(target) = callee.as_ref() {
+                edges.insert(f.name.clone(), target.clone());
+            }
+        }
+    }
+    let mut keep: HashSet<String> = HashSet::new();
+    for start in &order {
+        let mut path: Vec<&String> = Vec::new();
+        let mut cursor = start;
+        while let Some(next) = edges.get(cursor) {
+            if keep.contains(cursor) {
+                break;
+            }
+            if path.contains(&cursor) {
+                keep.insert(cursor.clone());
+                break;
+            }
+            path.push(cursor);
+            cursor = next;
+        }
+    }
+    let elide: HashSet<String> = order.into_iter().filter(|n| !keep.contains(n)).collect();
+
+    for stmt in &mut program.body {
+        match stmt {
+            Stmt::Function(f) if elide.contains(&f.name) => unwrap_tail_thunk(&mut f.body),
+            Stmt::Const(decl) => {
+                let Expr::Object(props) = &mut decl.init else { continue };
+                for prop in props {
+                    let Expr::Arrow { body, .. } = &mut prop.value else { continue };
+                    let elidable = thunk_wrapped_body(body)
+                        .and_then(tail_call_of_thunk)
+                        .is_some_and(returns_thunk);
+                    if elidable {
+                        unwrap_tail_thunk(body);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// If `body` is `__thunk(inner)` (as an expression body or a single
+/// `return`), return the `__thunk` call.
+fn thunk_wrapped_body(body: &FunctionBody) -> Option<&Expr> {
+    let expr = match body {
+        FunctionBody::Expr(e) => e.as_ref(),
+        FunctionBody::Block(b) => match b.stmts.as_slice() {
+            [Stmt::Return(Some(e))] => e,
+            _ => return None,
+        },
+    };
+    match expr {
+        Expr::Call { callee, args }
+            if args.len() == 1 && matches!(callee.as_ref(), Expr::Ident(n) if n == THUNK_FN) =>
+        {
+            Some(expr)
+        }
+        _ => None,
+    }
+}
+
+/// For `__thunk(() => call)` whose arrow body is a single tail call, return
+/// that call.
+fn tail_call_of_thunk(thunk: &Expr) -> Option<&Expr> {
+    let Expr::Call { args, .. } = thunk else {
+        return None;
+    };
+    let Expr::Arrow { params, body, .. } = args.first()? else {
+        return None;
+    };
+    if !params.is_empty() {
+        return None;
+    }
+    let inner = match body.as_ref() {
+        FunctionBody::Expr(e) => e.as_ref(),
+        FunctionBody::Block(b) => match b.stmts.as_slice() {
+            [Stmt::Return(Some(e))] => e,
+            _ => return None,
+        },
+    };
+    matches!(inner, Expr::Call { .. }).then_some(inner)
+}
+
+fn unwrap_tail_thunk(body: &mut FunctionBody) {
+    let Some(call) = thunk_wrapped_body(body).and_then(tail_call_of_thunk).cloned() else {
+        return;
+    };
+    *body = FunctionBody::Block(expr_to_block(call));
+}
+
+// ---------------------------------------------------------------------------
+// Pass: drop_unused_caps_params
+//
+// Every effectful function takes `__caps` first, even when it never reads it
+// (e.g. a function whose only effect was resolved away by LTO). For a
+// top-level function whose `__caps` param is unused and whose name only ever
+// appears as a direct callee, remove the param and the matching argument at
+// every call site. Repeats until no more functions qualify, since dropping an
+// argument can leave the caller's own `__caps` unused.
+// ---------------------------------------------------------------------------
+
+pub fn drop_unused_caps_params(program: &mut Program) {
+    loop {
+        let droppable: std::collections::HashSet<String> = program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Function(f) if caps_param_unused(f) => Some(f.name.clone()),
+                _ => None,
+            })
+            .filter(|name| {
+                let (total, as_callee) = count_callee_uses(&program.body, name);
+                total == as_callee
+            })
+            .collect();
+        if droppable.is_empty() {
+            return;
+        }
+        for stmt in &mut program.body {
+            if let Stmt::Function(f) = stmt {
+                if droppable.contains(&f.name) {
+                    f.params.remove(0);
+                }
+            }
+            drop_first_arg_in_stmt(stmt, &droppable);
+        }
+    }
+}
+
+fn caps_param_unused(f: &FunctionDecl) -> bool {
+    if f.params.first().is_none_or(|p| p.name != CAPS_NAME) {
+        return false;
+    }
+    let uses = match &f.body {
+        FunctionBody::Expr(e) => count_refs_expr(e, CAPS_NAME),
+        FunctionBody::Block(b) => count_refs_stmts(&b.stmts, CAPS_NAME),
+    };
+    uses == 0
+}
+
+/// Count `(all references, references as a call's callee with >= 1 arg)` of
+/// a top-level name. Shadowing is ignored, which only makes the check more
+/// conservative.
+fn count_callee_uses(stmts: &[Stmt], name: &str) -> (usize, usize) {
+    let mut total = 0;
+    let mut as_callee = 0;
+    let mut visit = |e: &Expr| match e {
+        Expr::Ident(n) if n == name => total += 1,
+        Expr::Call { callee, args } if !args.is_empty() => {
+            if matches!(callee.as_ref(), Expr::Ident(n) if n == name) {
+                as_callee += 1;
+            }
+        }
+        _ => {}
+    };
+    for stmt in stmts {
+        visit_stmt_exprs(stmt, &mut visit);
+    }
+    (total, as_callee)
+}
+
+/// Call `f` on every expression reachable from `stmt`, parents before
+/// children.
+fn visit_stmt_exprs(stmt: &Stmt, f: &mut impl FnMut(&Expr)) {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => visit_expr(e, f),
+        Stmt::Const(decl) => visit_expr(&decl.init, f),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => visit_expr(e, f),
+        Stmt::If { cond, then_branch, else_branch } => {
+            visit_expr(cond, f);
+            for s in &then_branch.stmts {
+                visit_stmt_exprs(s, f);
+            }
+            if let Some(eb) = else_branch {
+                for s in &eb.stmts {
+                    visit_stmt_exprs(s, f);
+                }
+            }
+        }
+        Stmt::Block(b) => {
+            for s in &b.stmts {
+                visit_stmt_exprs(s, f);
+            }
+        }
+        Stmt::Function(func) => match &func.body {
+            FunctionBody::Expr(e) => visit_expr(e, f),
+            FunctionBody::Block(b) => {
+                for s in &b.stmts {
+                    visit_stmt_exprs(s, f);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+fn visit_expr(expr: &Expr, f: &mut impl FnMut(&Expr)) {
+    f(expr);
+    match expr {
+        Expr::Call { callee, args } => {
+            visit_expr(callee, f);
+            for a in args {
+                visit_expr(a, f);
+            }
+        }
+        Expr::Arrow { body, .. } => match body.as_ref() {
+            FunctionBody::Expr(e) => visit_expr(e, f),
+            FunctionBody::Block(b) => {
+                for s in &b.stmts {
+                    visit_stmt_exprs(s, f);
+                }
+            }
+        },
+        Expr::Member { object, .. } => visit_expr(object, f),
+        Expr::Index { object, index } => {
+            visit_expr(object, f);
+            visit_expr(index, f);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => visit_expr(expr, f),
+        Expr::Binary { left, right, .. } => {
+            visit_expr(left, f);
+            visit_expr(right, f);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                visit_expr(item, f);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &prop.key {
+                    visit_expr(e, f);
+                }
+                visit_expr(&prop.value, f);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            visit_expr(cond, f);
+            visit_expr(then_expr, f);
+            visit_expr(else_expr, f);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+}
+
+/// Mutable counterpart of `visit_stmt_exprs`; children are visited before
+/// `f` sees their parent.
+fn visit_stmt_exprs_mut(stmt: &mut Stmt, f: &mut impl FnMut(&mut Expr)) {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => visit_expr_mut(e, f),
+        Stmt::Const(decl) => visit_expr_mut(&mut decl.init, f),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => visit_expr_mut(e, f),
+        Stmt::If { cond, then_branch, else_branch } => {
+            visit_expr_mut(cond, f);
+            for s in &mut then_branch.stmts {
+                visit_stmt_exprs_mut(s, f);
+            }
+            if let Some(eb) = else_branch {
+                for s in &mut eb.stmts {
+                    visit_stmt_exprs_mut(s, f);
+                }
+            }
+        }
+        Stmt::Block(b) => {
+            for s in &mut b.stmts {
+                visit_stmt_exprs_mut(s, f);
+            }
+        }
+        Stmt::Function(func) => match &mut func.body {
+            FunctionBody::Expr(e) => visit_expr_mut(e, f),
+            FunctionBody::Block(b) => {
+                for s in &mut b.stmts {
+                    visit_stmt_exprs_mut(s, f);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+fn visit_expr_mut(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+    match expr {
+        Expr::Call { callee, args } => {
+            visit_expr_mut(callee, f);
+            for a in args {
+                visit_expr_mut(a, f);
+            }
+        }
+        Expr::Arrow { body, .. } => match body.as_mut() {
+            FunctionBody::Expr(e) => visit_expr_mut(e, f),
+            FunctionBody::Block(b) => {
+                for s in &mut b.stmts {
+                    visit_stmt_exprs_mut(s, f);
+                }
+            }
+        },
+        Expr::Member { object, .. } => visit_expr_mut(object, f),
+        Expr::Index { object, index } => {
+            visit_expr_mut(object, f);
+            visit_expr_mut(index, f);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => visit_expr_mut(expr, f),
+        Expr::Binary { left, right, .. } => {
+            visit_expr_mut(left, f);
+            visit_expr_mut(right, f);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                visit_expr_mut(item, f);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &mut prop.key {
+                    visit_expr_mut(e, f);
+                }
+                visit_expr_mut(&mut prop.value, f);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            visit_expr_mut(cond, f);
+            visit_expr_mut(then_expr, f);
+            visit_expr_mut(else_expr, f);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+    f(expr);
+}
+
+fn drop_first_arg_in_stmt(stmt: &mut Stmt, droppable: &std::collections::HashSet<String>) {
+    visit_stmt_exprs_mut(stmt, &mut |e| {
+        if let Expr::Call { callee, args } = e {
+            if matches!(callee.as_ref(), Expr::Ident(n) if droppable.contains(n)) && !args.is_empty()
+            {
+                args.remove(0);
+            }
+        }
+    });
+}
+
+// ---------------------------------------------------------------------------
+// Pass: direct_perform_dispatch
+//
+// A perform compiles to `__caps.<Cap>.<op>(...)`, re-reading the handler from
+// the bundle at every site. Inside a function or arrow that binds `__caps`,
+// when the same `__caps.<Cap>` is dispatched through more than once, bind it
+// to a local (`const __cap_<Cap> = __caps.<Cap>;`) at the top of the body
+// and dispatch through that. Continuations nested in the body capture the
+// local instead of repeating the lookup. Nested scopes that rebind `__caps`
+// (handler methods, handle sites) are left to their own rewrite.
+// ---------------------------------------------------------------------------
+
+pub fn direct_perform_dispatch(program: &mut Program) {
+    for stmt in &mut program.body {
+        dispatch_in_stmt(stmt);
+    }
+}
+
+fn dispatch_in_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Function(f) => {
+            dispatch_in_body(&mut f.body);
+            if f.params.iter().any(|p| p.name == CAPS_NAME) {
+                hoist_cap_lookups(&mut f.body);
+            }
+        }
+        _ => visit_stmt_exprs_mut(stmt, &mut |e| {
+            if let Expr::Arrow { params, body, .. } = e {
+                if params.iter().any(|p| p.name == CAPS_NAME) {
+                    hoist_cap_lookups(body);
+                }
+            }
+        }),
+    }
+}
+
+fn dispatch_in_body(body: &mut FunctionBody) {
+    let mut visit = |e: &mut Expr| {
+        if let Expr::Arrow { params, body, .. } = e {
+            if params.iter().any(|p| p.name == CAPS_NAME) {
+                hoist_cap_lookups(body);
+            }
+        }
+    };
+    match body {
+        FunctionBody::Expr(e) => visit_expr_mut(e, &mut visit),
+        FunctionBody::Block(b) => {
+            for s in &mut b.stmts {
+                visit_stmt_exprs_mut(s, &mut visit);
+            }
+        }
+    }
+}
+
+/// Hoist repeated `__caps.<Cap>` dispatches in `body` (whose enclosing
+/// function binds `__caps`). Nested arrows that rebind `__caps` have already
+/// been rewritten by the post-order walk and are skipped here.
+fn hoist_cap_lookups(body: &mut FunctionBody) {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    match body {
+        FunctionBody::Expr(e) => count_cap_dispatches(e, &mut counts),
+        FunctionBody::Block(b) => {
+            for s in &b.stmts {
+                if shadows_name(s, CAPS_NAME) {
+                    return;
+                }
+                count_cap_dispatches_in_stmt(s, &mut counts);
+            }
+        }
+    }
+    let hoisted: Vec<String> = counts
+        .into_iter()
+        .filter(|(_, n)| *n > 1)
+        .map(|(cap, _)| cap)
+        .collect();
+    if hoisted.is_empty() {
+        return;
+    }
+    let mut stmts = match std::mem::replace(body, FunctionBody::Block(Block::new(Vec::new()))) {
+        FunctionBody::Expr(e) => expr_to_block(*e).stmts,
+        FunctionBody::Block(b) => b.stmts,
+    };
+    for s in &mut stmts {
+        rewrite_cap_dispatches_in_stmt(s, &hoisted);
+    }
+    let mut out: Vec<Stmt> = hoisted
+        .iter()
+        .map(|cap| {
+            Stmt::Const(ConstDecl {
+                export: false,
+                name: local_cap_name(cap),
+                type_ann: None,
+                init: Expr::Member {
+                    object: Box::new(Expr::Ident(CAPS_NAME.to_owned())),
+                    property: cap.clone(),
+                },
+            })
+        })
+        .collect();
+    out.extend(stmts);
+    *body = FunctionBody::Block(Block::new(out));
+}
+
+fn local_cap_name(cap: &str) -> String {
+    format!("__cap_{cap}")
+}
+
+/// If `expr` is a `__caps.<Cap>.<op>` callee, return `<Cap>`.
+fn cap_dispatch_key(expr: &Expr) -> Option<&str> {
+    let Expr::Member { object, .. } = expr else {
+        return None;
+    };
+    caps_member_key(object)
+}
+
+/// `__caps.<Cap>` → `<Cap>`.
+fn caps_member_key(expr: &Expr) -> Option<&str> {
+    let Expr::Member { object, property } = expr else {
+        return None;
+    };
+    matches!(object.as_ref(), Expr::Ident(n) if n == CAPS_NAME).then_some(property.as_str())
+}
+
+fn count_cap_dispatches_in_stmt(stmt: &Stmt, counts: &mut Vec<(String, usize)>) {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => count_cap_dispatches(e, counts),
+        Stmt::Const(decl) => count_cap_dispatches(&decl.init, counts),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => {
+            count_cap_dispatches(e, counts)
+        }
+        Stmt::If { cond, then_branch, else_branch } => {
+            count_cap_dispatches(cond, counts);
+            for s in &then_branch.stmts {
+                count_cap_dispatches_in_stmt(s, counts);
+            }
+            if let Some(eb) = else_branch {
+                for s in &eb.stmts {
+                    count_cap_dispatches_in_stmt(s, counts);
+                }
+            }
+        }
+        Stmt::Block(b) => {
+            for s in &b.stmts {
+                count_cap_dispatches_in_stmt(s, counts);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn count_cap_dispatches(expr: &Expr, counts: &mut Vec<(String, usize)>) {
+    if let Expr::Call { callee, .. } = expr {
+        if let Some(cap) = cap_dispatch_key(callee) {
+            match counts.iter_mut().find(|(c, _)| c == cap) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((cap.to_owned(), 1)),
+            }
+        }
+    }
+    match expr {
+        Expr::Arrow { params, body, .. } => {
+            if params.iter().any(|p| p.name == CAPS_NAME) {
+                return;
+            }
+            match body.as_ref() {
+                FunctionBody::Expr(e) => count_cap_dispatches(e, counts),
+                FunctionBody::Block(b) => {
+                    for s in &b.stmts {
+                        if shadows_name(s, CAPS_NAME) {
+                            return;
+                        }
+                        count_cap_dispatches_in_stmt(s, counts);
+                    }
+                }
+            }
+        }
+        Expr::Call { callee, args } => {
+            count_cap_dispatches(callee, counts);
+            for a in args {
+                count_cap_dispatches(a, counts);
+            }
+        }
+        Expr::Member { object, .. } => count_cap_dispatches(object, counts),
+        Expr::Index { object, index } => {
+            count_cap_dispatches(object, counts);
+            count_cap_dispatches(index, counts);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => count_cap_dispatches(expr, counts),
+        Expr::Binary { left, right, .. } => {
+            count_cap_dispatches(left, counts);
+            count_cap_dispatches(right, counts);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                count_cap_dispatches(item, counts);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &prop.key {
+                    count_cap_dispatches(e, counts);
+                }
+                count_cap_dispatches(&prop.value, counts);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            count_cap_dispatches(cond, counts);
+            count_cap_dispatches(then_expr, counts);
+            count_cap_dispatches(else_expr, counts);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+}
+
+fn rewrite_cap_dispatches_in_stmt(stmt: &mut Stmt, hoisted: &[String]) {
+    match stmt {
+        Stmt::Expr(e) | Stmt::Return(Some(e)) => rewrite_cap_dispatches(e, hoisted),
+        Stmt::Const(decl) => rewrite_cap_dispatches(&mut decl.init, hoisted),
+        Stmt::Let { init: Some(e), .. } | Stmt::Assign { value: e, .. } => {
+            rewrite_cap_dispatches(e, hoisted)
+        }
+        Stmt::If { cond, then_branch, else_branch } => {
+            rewrite_cap_dispatches(cond, hoisted);
+            for s in &mut then_branch.stmts {
+                rewrite_cap_dispatches_in_stmt(s, hoisted);
+            }
+            if let Some(eb) = else_branch {
+                for s in &mut eb.stmts {
+                    rewrite_cap_dispatches_in_stmt(s, hoisted);
+                }
+            }
+        }
+        Stmt::Block(b) => {
+            for s in &mut b.stmts {
+                rewrite_cap_dispatches_in_stmt(s, hoisted);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rewrite_cap_dispatches(expr: &mut Expr, hoisted: &[String]) {
+    if let Expr::Call { callee, .. } = expr {
+        if let Expr::Member { object, .. } = callee.as_mut() {
+            let local = caps_member_key(object)
+                .filter(|cap| hoisted.iter().any(|h| h == cap))
+                .map(local_cap_name);
+            if let Some(local) = local {
+                **object = Expr::Ident(local);
+            }
+        }
+    }
+    match expr {
+        Expr::Arrow { params, body, .. } => {
+            if params.iter().any(|p| p.name == CAPS_NAME) {
+                return;
+            }
+            match body.as_mut() {
+                FunctionBody::Expr(e) => rewrite_cap_dispatches(e, hoisted),
+                FunctionBody::Block(b) => {
+                    for s in &mut b.stmts {
+                        if shadows_name(s, CAPS_NAME) {
+                            return;
+                        }
+                        rewrite_cap_dispatches_in_stmt(s, hoisted);
+                    }
+                }
+            }
+        }
+        Expr::Call { callee, args } => {
+            rewrite_cap_dispatches(callee, hoisted);
+            for a in args {
+                rewrite_cap_dispatches(a, hoisted);
+            }
+        }
+        Expr::Member { object, .. } => rewrite_cap_dispatches(object, hoisted),
+        Expr::Index { object, index } => {
+            rewrite_cap_dispatches(object, hoisted);
+            rewrite_cap_dispatches(index, hoisted);
+        }
+        Expr::Unary { expr, .. } | Expr::Void(expr) => rewrite_cap_dispatches(expr, hoisted),
+        Expr::Binary { left, right, .. } => {
+            rewrite_cap_dispatches(left, hoisted);
+            rewrite_cap_dispatches(right, hoisted);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                rewrite_cap_dispatches(item, hoisted);
+            }
+        }
+        Expr::Object(props) => {
+            for prop in props {
+                if let ObjectKey::Computed(e) = &mut prop.key {
+                    rewrite_cap_dispatches(e, hoisted);
+                }
+                rewrite_cap_dispatches(&mut prop.value, hoisted);
+            }
+        }
+        Expr::IfElse { cond, then_expr, else_expr } => {
+            rewrite_cap_dispatches(cond, hoisted);
+            rewrite_cap_dispatches(then_expr, hoisted);
+            rewrite_cap_dispatches(else_expr, hoisted);
+        }
+        Expr::Ident(_)
+        | Expr::String(_)
+        | Expr::Number(_)
+        | Expr::BigInt(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Undefined => {}
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pass: loop_self_tail_calls
+//
+// A direct-style function that recurses in tail position, such as a
+// generator whose handler LTO inlined, grows the JS stack by one frame per
+// turn. When a top-level function's body returns a call of itself at
+// statement level, wrap the body in `while (true)` and turn each such
+// `return f(a, b)` into `x = a; y = b; continue;`. Args go through
+// temporaries first when a later one reads a param assigned before it.
+// A param that an arrow in the body captures, other than one called on
+// the spot, is renamed in the signature and rebound by a `const` at the
+// top of each turn, so every closure keeps the value of its own turn.
+//
+// Runs after every other pass: they don't look inside `while` bodies.
+// ---------------------------------------------------------------------------
+
+pub fn loop_self_tail_calls(program: &mut Program) {
+    for stmt in &mut program.body {
+        if let Stmt::Function(f) = stmt {
+            loop_self_tail_calls_in(f);
+        }
+    }
+}
+
+fn loop_self_tail_calls_in(f: &mut FunctionDecl) {
+    let FunctionBody::Block(block) = &mut f.body else {
+        return;
+    };
+    let names: Vec<String> = f.params.iter().map(|p| p.name.clone()).collect();
+    if !has_self_tail_call(&block.stmts, &f.name, names.len())
+        || block.stmts.iter().any(|s| declares_any(s, &names))
+    {
+        return;
+    }
+    // A nested function could capture a param without an arrow.
+    let mut nested_fn = false;
+    walk_stmts(&block.stmts, &mut |s| nested_fn |= matches!(s, Stmt::Function(_)));
+    if nested_fn {
+        return;
+    }
+
+    // An arrow called on the spot runs within its turn, so only the others
+    // capture. Parents are visited first, so a call marks its arrow before
+    // the arrow is seen.
+    let mut captured: Vec<String> = Vec::new();
+    let mut called: Vec<*const Expr> = Vec::new();
+    for s in &block.stmts {
+        visit_stmt_exprs(s, &mut |e| {
+            if let Expr::Call { callee, .. } = e {
+                if let Expr::Arrow { .. } = callee.as_ref() {
+                    called.push(callee.as_ref());
+                }
+            }
+            if let Expr::Arrow { .. } = e {
+                if called.contains(&(e as *const Expr)) {
+                    return;
+                }
+                for n in &names {
+                    if !captured.contains(n) && expr_references_name(e, n) {
+                        captured.push(n.clone());
+                    }
+                }
+            }
+        });
+    }
+    // The names a tail call assigns: the param itself, or the renamed one
+    // a captured param is rebound from.
+    let targets: Vec<String> = names
+        .iter()
+        .map(|n| if captured.contains(n) { format!("__{n}_turn") } else { n.clone() })
+        .collect();
+
+    let mut stmts = std::mem::take(&mut block.stmts);
+    rewrite_self_tail_calls(&mut stmts, &f.name, &names, &targets);
+    if !always_returns(&stmts) {
+        stmts.push(Stmt::Return(None));
+    }
+    let mut turn: Vec<Stmt> = Vec::new();
+    for (param, target) in f.params.iter_mut().zip(&targets) {
+        if param.name != *target {
+            turn.push(Stmt::Const(ConstDecl {
+                export: false,
+                name: param.name.clone(),
+                type_ann: None,
+                init: Expr::Ident(target.clone()),
+            }));
+            param.name = target.clone();
+        }
+    }
+    turn.extend(stmts);
+    block.stmts = vec![Stmt::While { cond: Expr::Bool(true), body: Block::new(turn) }];
+}
+
+/// The args of `return name(..)` with `arity` args, if `stmt` is one.
+fn self_tail_call<'a>(stmt: &'a Stmt, name: &str, arity: usize) -> Option<&'a [Expr]> {
+    let Stmt::Return(Some(Expr::Call { callee, args })) = stmt else {
+        return None;
+    };
+    (matches!(callee.as_ref(), Expr::Ident(n) if n == name) && args.len() == arity)
+        .then_some(args.as_slice())
+}
+
+/// Whether `stmts` return a call of `name` at statement level, outside any
+/// arrow.
+fn has_self_tail_call(stmts: &[Stmt], name: &str, arity: usize) -> bool {
+    stmts.iter().any(|s| match s {
+        Stmt::If { then_branch, else_branch, .. } => {
+            has_self_tail_call(&then_branch.stmts, name, arity)
+                || else_branch
+                    .as_ref()
+                    .is_some_and(|b| has_self_tail_call(&b.stmts, name, arity))
+        }
+        Stmt::Block(b) => has_self_tail_call(&b.stmts, name, arity),
+        _ => self_tail_call(s, name, arity).is_some(),
+    })
+}
+
+/// Whether `stmt` binds one of `names` with `const` or `let`, shadowing a
+/// param that a tail call would then assign.
+fn declares_any(stmt: &Stmt, names: &[String]) -> bool {
+    match stmt {
+        Stmt::Const(ConstDecl { name, .. }) | Stmt::Let { name, .. } => names.contains(name),
+        Stmt::If { then_branch, else_branch, .. } => {
+            then_branch.stmts.iter().any(|s| declares_any(s, names))
+                || else_branch
+                    .as_ref()
+                    .is_some_and(|b| b.stmts.iter().any(|s| declares_any(s, names)))
+        }
+        Stmt::Block(b) => b.stmts.iter().any(|s| declares_any(s, names)),
+        _ => false,
+    }
+}
+
+fn rewrite_self_tail_calls(stmts: &mut Vec<Stmt>, name: &str, params: &[String], targets: &[String]) {
+    for mut stmt in std::mem::take(stmts) {
+        match &mut stmt {
+            Stmt::If { then_branch, else_branch, .. } => {
+                rewrite_self_tail_calls(&mut then_branch.stmts, name, params, targets);
+                if let Some(b) = else_branch {
+                    rewrite_self_tail_calls(&mut b.stmts, name, params, targets);
+                }
+            }
+            Stmt::Block(b) => rewrite_self_tail_calls(&mut b.stmts, name, params, targets),
+            _ => {
+                if let Some(args) = self_tail_call(&stmt, name, params.len()) {
+                    // A `return` ends its block, so the turn's statements
+                    // can take its place.
+                    stmts.extend(next_turn(args.to_vec(), params, targets));
+                    continue;
+                }
+            }
+        }
+        stmts.push(stmt);
+    }
+}
+
+/// `targets[i] = args[i]` for each arg that changes its param, then
+/// `continue`.
+fn next_turn(args: Vec<Expr>, params: &[String], targets: &[String]) -> Vec<Stmt> {
+    let changed: Vec<(usize, Expr)> = args
+        .into_iter()
+        .enumerate()
+        .filter(|(i, a)| !matches!(a, Expr::Ident(n) if *n == params[*i]))
+        .collect();
+    let reads_assigned = changed.iter().enumerate().any(|(k, (_, later))| {
+        changed[..k].iter().any(|(i, _)| expr_references_name(later, &params[*i]))
+    });
+    let mut out = Vec::new();
+    if reads_assigned {
+        for (i, arg) in &changed {
+            out.push(Stmt::Const(ConstDecl {
+                export: false,
+                name: format!("__{}_next", params[*i]),
+                type_ann: None,
+                init: arg.clone(),
+            }));
+        }
+        for (i, _) in &changed {
+            out.push(Stmt::Assign {
+                name: targets[*i].clone(),
+                value: Expr::Ident(format!("__{}_next", params[*i])),
+            });
+        }
+    } else {
+        for (i, arg) in changed {
+            out.push(Stmt::Assign { name: targets[i].clone(), value: arg });
+        }
+    }
+    out.push(Stmt::Continue);
+    out
+}
+
+/// Whether running `stmts` always ends in a `return` or `continue`.
+fn always_returns(stmts: &[Stmt]) -> bool {
+    match stmts.last() {
+        Some(Stmt::Return(_) | Stmt::Continue) => true,
+        Some(Stmt::If { then_branch, else_branch: Some(else_branch), .. }) => {
+            always_returns(&then_branch.stmts) && always_returns(&else_branch.stmts)
+        }
+        Some(Stmt::Block(b)) => always_returns(&b.stmts),
+        _ => false,
+    }
+}
@@ -1,5 +1,6 @@
 use crate::ast::*;
-use crate::pass::flatten_iifes;
+use crate::emit::{EmitTarget, Emitter};
+use crate::pass::{flatten_iifes, loop_self_tail_calls};
 
 // ---------------------------------------------------------------------------
 // Helper: run flatten_iifes (which internally runs dedup_const_names) on a
@@ -128,3 +129,148 @@ fn dedup_non_duplicate_lets_unchanged() {
     assert!(matches!(&result[1], Stmt::Let { name, .. } if name == "b"));
     assert!(matches!(&result[2], Stmt::Assign { name, .. } if name == "a"));
 }
+
+// ---------------------------------------------------------------------------
+// Test: a flattened IIFE param must not capture later uses of the same name
+// ---------------------------------------------------------------------------
+
+#[test]
+fn flattened_iife_param_does_not_capture_following_stmts() {
+    // r = ((a) => { return a; })(1);
+    // use(a);           ← refers to the OUTER `a`
+    let stmts = vec![
+        Stmt::Let { name: "r".to_owned(), export: false, type_ann: None, init: None },
+        Stmt::Assign {
+            name: "r".to_owned(),
+            value: Expr::Call {
+                callee: Box::new(Expr::Arrow {
+                    params: vec![Param::new("a")],
+                    return_type: None,
+                    body: Box::new(FunctionBody::Block(Block::new(vec![Stmt::Return(Some(
+                        Expr::Ident("a".to_owned()),
+                    ))]))),
+                }),
+                args: vec![Expr::Number(1.0)],
+            },
+        },
+        Stmt::Expr(Expr::Call {
+            callee: Box::new(Expr::Ident("use".to_owned())),
+            args: vec![Expr::Ident("a".to_owned())],
+        }),
+    ];
+
+    let result = run_dedup(stmts);
+
+    let Stmt::Const(param) = &result[1] else {
+        panic!("expected flattened param const, got: {:?}", &result[1]);
+    };
+    assert_ne!(param.name, "a", "param should be renamed away from 'a'");
+    assert!(
+        matches!(&result[2], Stmt::Assign { value: Expr::Ident(v), .. } if *v == param.name),
+        "body should read the renamed param, got: {:?}", &result[2]
+    );
+    assert!(
+        matches!(&result[3], Stmt::Expr(Expr::Call { args, .. }) if args[0] == Expr::Ident("a".to_owned())),
+        "following stmt should still read outer 'a', got: {:?}", &result[3]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Test: self tail calls become a `while (true)` loop
+// ---------------------------------------------------------------------------
+
+fn ident(name: &str) -> Expr {
+    Expr::Ident(name.to_owned())
+}
+
+fn call(callee: &str, args: Vec<Expr>) -> Expr {
+    Expr::Call { callee: Box::new(ident(callee)), args }
+}
+
+/// `function f(<params>) { if (done) { return acc; } else { <recur> } }`,
+/// run through `loop_self_tail_calls` and emitted compactly.
+fn loop_tail_calls(params: &[&str], recur: Stmt) -> String {
+    let body = vec![Stmt::If {
+        cond: ident("done"),
+        then_branch: Block::new(vec![Stmt::Return(Some(ident("acc")))]),
+        else_branch: Some(Block::new(vec![recur])),
+    }];
+    let mut func = FunctionDecl::new("f", FunctionBody::Block(Block::new(body)));
+    func.params = params.iter().map(|p| Param::new(*p)).collect();
+    let mut program = Program::new(vec![Stmt::Function(func)]);
+    loop_self_tail_calls(&mut program);
+    Emitter::compact().emit_program(&program, EmitTarget::JavaScript)
+}
+
+#[test]
+fn self_tail_call_assigns_params_and_continues() {
+    let recur = Stmt::Return(Some(call(
+        "f",
+        vec![
+            Expr::Binary {
+                left: Box::new(ident("n")),
+                op: BinaryOp::Sub,
+                right: Box::new(Expr::Number(1.0)),
+            },
+            ident("acc"),
+        ],
+    )));
+    let js = loop_tail_calls(&["n", "acc"], recur);
+    assert_eq!(
+        js.trim(),
+        "function f(n, acc) {while (true) {if (done) {return acc;} else {n = (n - 1);continue;}}}"
+    );
+}
+
+#[test]
+fn self_tail_call_reading_an_assigned_param_goes_through_temporaries() {
+    let recur = Stmt::Return(Some(call("f", vec![ident("acc"), ident("n")])));
+    let js = loop_tail_calls(&["n", "acc"], recur);
+    assert!(
+        js.contains("{const __n_next = acc;const __acc_next = n;n = __n_next;acc = __acc_next;continue;}"),
+        "{js}"
+    );
+}
+
+#[test]
+fn self_tail_call_rebinds_a_captured_param_each_turn() {
+    let recur = Stmt::Block(Block::new(vec![
+        Stmt::Expr(call(
+            "later",
+            vec![Expr::Arrow {
+                params: vec![],
+                return_type: None,
+                body: Box::new(FunctionBody::Expr(Box::new(ident("n")))),
+            }],
+        )),
+        Stmt::Return(Some(call("f", vec![call("next", vec![ident("n")]), ident("acc")]))),
+    ]));
+    let js = loop_tail_calls(&["n", "acc"], recur);
+    assert!(js.starts_with("function f(__n_turn, acc) {while (true) {const n = __n_turn;"), "{js}");
+    assert!(js.contains("__n_turn = next(n);continue;"), "{js}");
+}
+
+#[test]
+fn non_tail_self_call_is_left_alone() {
+    let recur = Stmt::Return(Some(Expr::Binary {
+        left: Box::new(call("f", vec![ident("n"), ident("acc")])),
+        op: BinaryOp::Add,
+        right: Box::new(Expr::Number(1.0)),
+    }));
+    assert!(!loop_tail_calls(&["n", "acc"], recur).contains("while"));
+}
+
+#[test]
+fn self_tail_call_keeps_a_param_read_by_an_arrow_called_on_the_spot() {
+    let iife = Expr::Call {
+        callee: Box::new(Expr::Arrow {
+            params: vec![Param::new("x")],
+            return_type: None,
+            body: Box::new(FunctionBody::Expr(Box::new(ident("n")))),
+        }),
+        args: vec![Expr::Number(1.0)],
+    };
+    let recur = Stmt::Return(Some(call("f", vec![iife, ident("acc")])));
+    let js = loop_tail_calls(&["n", "acc"], recur);
+    assert!(js.starts_with("function f(n, acc) {while (true) {if"), "{js}");
+}
@@ -1,7 +1,9 @@
 use simple_ts_ast::{
-    expr_to_block, lower_expression_bodies, return_lifting, BinaryOp, Block, ConstDecl, EmitTarget,
-    Emitter, Expr, FunctionBody, FunctionDecl, ObjectKey, ObjectProp, Param, Program, Stmt, TsType,
-    UnaryOp,
+    direct_perform_dispatch, drop_unused_caps_params, elide_tail_thunks, eta_reduce_continuations,
+    expr_to_block, flatten_iifes, inline_single_use_consts, lower_expression_bodies, optimize_cps,
+    return_lifting, BinaryOp, Block,
+    ConstDecl, EmitTarget, Emitter, Expr, FunctionBody, FunctionDecl, ObjectKey, ObjectProp,
+    OptLevel, Param, Program, Stmt, TsType, UnaryOp,
 };
 
 #[test]
@@ -143,3 +145,324 @@ fn emits_unary_and_exponent_expressions() {
     assert!(js.contains("(!flag);"), "{js}");
     assert!(js.contains("(lhs ** rhs);"), "{js}");
 }
+
+#[test]
+fn compact_emitter_joins_each_statement_onto_one_line() {
+    let body = Block::new(vec![
+        Stmt::If {
+            cond: ident("flag"),
+            then_branch: Block::new(vec![Stmt::Return(Some(Expr::Arrow {
+                params: vec![Param::new("x")],
+                return_type: None,
+                body: Box::new(FunctionBody::Block(Block::new(vec![Stmt::Return(Some(
+                    ident("x"),
+                ))]))),
+            }))]),
+            else_branch: None,
+        },
+        Stmt::Return(Some(Expr::Null)),
+    ]);
+    let program = Program::new(vec![
+        Stmt::Function(FunctionDecl::new("f", FunctionBody::Block(body))),
+        Stmt::TypeAlias(simple_ts_ast::TypeAlias {
+            export: false,
+            name: "T".into(),
+            type_params: Vec::new(),
+            ty: TsType::Number,
+        }),
+        Stmt::Expr(call(ident("f"), Vec::new())),
+    ]);
+    let js = Emitter::compact().emit_program(&program, EmitTarget::JavaScript);
+    assert_eq!(
+        js,
+        "function f() {if (flag) {return (x) => {return x;};}return null;}\nf();\n"
+    );
+}
+
+fn ident(name: &str) -> Expr {
+    Expr::Ident(name.into())
+}
+
+fn call(callee: Expr, args: Vec<Expr>) -> Expr {
+    Expr::Call {
+        callee: Box::new(callee),
+        args,
+    }
+}
+
+fn arrow(params: &[&str], body: Expr) -> Expr {
+    Expr::Arrow {
+        params: params.iter().map(|p| Param::new(*p)).collect(),
+        return_type: None,
+        body: Box::new(FunctionBody::Expr(Box::new(body))),
+    }
+}
+
+fn thunk(body: Expr) -> Expr {
+    call(ident("__thunk"), vec![arrow(&[], body)])
+}
+
+/// `__caps.<cap>.<op>`
+fn dispatch(cap: &str, op: &str) -> Expr {
+    Expr::Member {
+        object: Box::new(Expr::Member {
+            object: Box::new(ident("__caps")),
+            property: cap.into(),
+        }),
+        property: op.into(),
+    }
+}
+
+fn cps_fn(name: &str, params: &[&str], stmts: Vec<Stmt>) -> Stmt {
+    let mut f = FunctionDecl::new(name, FunctionBody::Block(Block::new(stmts)));
+    f.params = params.iter().map(|p| Param::new(*p)).collect();
+    Stmt::Function(f)
+}
+
+fn js(program: &Program) -> String {
+    Emitter::default().emit_program(program, EmitTarget::JavaScript)
+}
+
+#[test]
+fn eta_reduces_continuation_to_enclosing_param() {
+    // function f(__caps, x, __k) { return g(__caps, x, (v) => __k(v)); }
+    let mut program = Program::new(vec![cps_fn(
+        "f",
+        &["__caps", "x", "__k"],
+        vec![Stmt::Return(Some(call(
+            ident("g"),
+            vec![ident("__caps"), ident("x"), arrow(&["v"], call(ident("__k"), vec![ident("v")]))],
+        )))],
+    )]);
+    eta_reduce_continuations(&mut program);
+    let out = js(&program);
+    assert!(out.contains("return g(__caps, x, __k);"), "{out}");
+}
+
+#[test]
+fn eta_keeps_continuation_over_const_binding() {
+    // `k` is a `const`, which may still be in its TDZ when the arrow is built.
+    let mut program = Program::new(vec![cps_fn(
+        "f",
+        &["__caps"],
+        vec![
+            Stmt::Const(ConstDecl {
+                export: false,
+                name: "k".into(),
+                type_ann: None,
+                init: ident("mk"),
+            }),
+            Stmt::Return(Some(call(
+                ident("g"),
+                vec![ident("__caps"), arrow(&["v"], call(ident("k"), vec![ident("v")]))],
+            ))),
+        ],
+    )]);
+    eta_reduce_continuations(&mut program);
+    let out = js(&program);
+    assert!(out.contains("(v) => k(v)"), "{out}");
+}
+
+#[test]
+fn elides_tail_thunk_around_thunk_returning_call() {
+    // `leaf` dispatches a perform; `pass` only forwards to `leaf`.
+    let mut program = Program::new(vec![
+        cps_fn(
+            "leaf",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(thunk(call(
+                dispatch("Log", "log"),
+                vec![ident("__caps"), ident("__k")],
+            ))))],
+        ),
+        cps_fn(
+            "pass",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(thunk(call(
+                ident("leaf"),
+                vec![ident("__caps"), ident("__k")],
+            ))))],
+        ),
+        cps_fn(
+            "opaque",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(thunk(call(
+                ident("__k"),
+                vec![Expr::Number(1.0)],
+            ))))],
+        ),
+    ]);
+    elide_tail_thunks(&mut program);
+    let out = js(&program);
+    assert!(out.contains("return __caps.Log.log(__caps, __k);"), "{out}");
+    assert!(out.contains("return leaf(__caps, __k);"), "{out}");
+    // `__k(1)` is not known to return a thunk — the wrap must stay.
+    assert_eq!(out.matches("__thunk(").count(), 1, "{out}");
+}
+
+#[test]
+fn tail_thunk_elision_keeps_one_wrap_per_cycle() {
+    let mut program = Program::new(vec![
+        cps_fn(
+            "ping",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(thunk(call(
+                ident("pong"),
+                vec![ident("__caps"), ident("__k")],
+            ))))],
+        ),
+        cps_fn(
+            "pong",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(thunk(call(
+                ident("ping"),
+                vec![ident("__caps"), ident("__k")],
+            ))))],
+        ),
+    ]);
+    elide_tail_thunks(&mut program);
+    let out = js(&program);
+    assert_eq!(out.matches("__thunk(").count(), 1, "{out}");
+}
+
+#[test]
+fn drops_unused_caps_param_and_call_site_argument() {
+    let mut program = Program::new(vec![
+        cps_fn(
+            "pure",
+            &["__caps", "x", "__k"],
+            vec![Stmt::Return(Some(call(ident("__k"), vec![ident("x")])))],
+        ),
+        cps_fn(
+            "user",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(call(
+                ident("pure"),
+                vec![ident("__caps"), Expr::Number(1.0), ident("__k")],
+            )))],
+        ),
+        // Escapes as a value, so its arity must stay intact.
+        cps_fn(
+            "escaping",
+            &["__caps", "__k"],
+            vec![Stmt::Return(Some(call(ident("__k"), vec![Expr::Number(2.0)])))],
+        ),
+        Stmt::Expr(call(ident("register"), vec![ident("escaping")])),
+    ]);
+    drop_unused_caps_params(&mut program);
+    let out = js(&program);
+    assert!(out.contains("function pure(x, __k)"), "{out}");
+    // `user` only read `__caps` to forward it to `pure`, so it goes too.
+    assert!(out.contains("function user(__k)"), "{out}");
+    assert!(out.contains("return pure(1, __k);"), "{out}");
+    assert!(out.contains("function escaping(__caps, __k)"), "{out}");
+}
+
+#[test]
+fn hoists_repeated_cap_lookups() {
+    let mut program = Program::new(vec![cps_fn(
+        "twice",
+        &["__caps", "__k"],
+        vec![Stmt::Return(Some(call(
+            dispatch("Log", "log"),
+            vec![
+                ident("__caps"),
+                arrow(
+                    &["v"],
+                    call(dispatch("Log", "log"), vec![ident("__caps"), ident("__k")]),
+                ),
+            ],
+        )))],
+    )]);
+    direct_perform_dispatch(&mut program);
+    let out = js(&program);
+    assert!(out.contains("const __cap_Log = __caps.Log;"), "{out}");
+    assert!(!out.contains("__caps.Log.log"), "{out}");
+    assert_eq!(out.matches("__cap_Log.log(").count(), 2, "{out}");
+}
+
+#[test]
+fn opt_level_zero_leaves_program_untouched() {
+    let original = Program::new(vec![cps_fn(
+        "f",
+        &["__caps", "__k"],
+        vec![Stmt::Return(Some(thunk(call(
+            dispatch("Log", "log"),
+            vec![ident("__caps"), arrow(&["v"], call(ident("__k"), vec![ident("v")]))],
+        ))))],
+    )]);
+    let mut program = original.clone();
+    optimize_cps(&mut program, OptLevel::O0);
+    assert_eq!(program, original);
+
+    optimize_cps(&mut program, OptLevel::O1);
+    let out = js(&program);
+    assert!(out.contains("return __caps.Log.log(__caps, __k);"), "{out}");
+    assert_eq!(OptLevel::parse("2"), Some(OptLevel::O2));
+    assert_eq!(OptLevel::parse("3"), None);
+}
+
+#[test]
+fn flattened_nested_caps_bindings_keep_reading_the_outer_caps() {
+    // function f(__caps, __k) {
+    //   return ((__caps) => ((__caps) => g(__caps, (v) => h(__caps, v)))(ext(__caps, 2)))(ext(__caps, 1));
+    // }
+    let ext = |n: f64| call(ident("ext"), vec![ident("__caps"), Expr::Number(n)]);
+    let k = arrow(&["v"], call(ident("h"), vec![ident("__caps"), ident("v")]));
+    let inner = call(arrow(&["__caps"], call(ident("g"), vec![ident("__caps"), k])), vec![ext(2.0)]);
+    let outer = call(arrow(&["__caps"], inner), vec![ext(1.0)]);
+    let mut program = Program::new(vec![cps_fn("f", &["__caps", "__k"], vec![Stmt::Return(Some(outer))])]);
+    lower_expression_bodies(&mut program);
+    flatten_iifes(&mut program);
+    inline_single_use_consts(&mut program);
+    let out = js(&program);
+    // Inlining the outer binding into `const __caps = ...` would read that
+    // `__caps` in its own initializer.
+    assert!(!out.contains("const __caps = ext(ext(__caps"), "{out}");
+    assert!(!out.contains("h(__caps, v)"), "{out}");
+}
+
+#[test]
+fn flattened_const_read_earlier_in_its_block_is_renamed() {
+    // function f(__caps, __k) { return ((a) => ((__caps) => g(__caps, a))(h()))(ext(__caps)); }
+    let inner = call(arrow(&["__caps"], call(ident("g"), vec![ident("__caps"), ident("a")])), vec![call(ident("h"), vec![])]);
+    let outer = call(arrow(&["a"], inner), vec![call(ident("ext"), vec![ident("__caps")])]);
+    let mut program = Program::new(vec![cps_fn("f", &["__caps", "__k"], vec![Stmt::Return(Some(outer))])]);
+    lower_expression_bodies(&mut program);
+    flatten_iifes(&mut program);
+    let out = js(&program);
+    assert!(out.contains("const a = ext(__caps);"), "{out}");
+    assert!(!out.contains("const __caps = h()"), "{out}");
+}
+
+fn local(name: &str, init: Expr) -> Stmt {
+    Stmt::Const(ConstDecl { export: false, name: name.into(), type_ann: None, init })
+}
+
+#[test]
+fn single_use_impure_const_keeps_its_place_among_effects() {
+    // function f(__caps, __k) { const x = get(); put(7); return x; }
+    let body = vec![
+        local("x", call(ident("get"), vec![])),
+        Stmt::Expr(call(ident("put"), vec![Expr::Number(7.0)])),
+        Stmt::Return(Some(ident("x"))),
+    ];
+    let mut program = Program::new(vec![cps_fn("f", &["__caps", "__k"], body)]);
+    inline_single_use_consts(&mut program);
+    let out = js(&program);
+    assert!(out.contains("const x = get();"), "{out}");
+}
+
+#[test]
+fn single_use_impure_consts_fold_in_evaluation_order() {
+    // function f(__caps, __k) { const a = g(); const b = h(); return add(a, b); }
+    let body = vec![
+        local("a", call(ident("g"), vec![])),
+        local("b", call(ident("h"), vec![])),
+        Stmt::Return(Some(call(ident("add"), vec![ident("a"), ident("b")]))),
+    ];
+    let mut program = Program::new(vec![cps_fn("f", &["__caps", "__k"], body)]);
+    inline_single_use_consts(&mut program);
+    let out = js(&program);
+    assert!(out.contains("return add(g(), h());"), "{out}");
+}
@@ -40,6 +40,9 @@ pub enum TypeExpr {
     Produce(Box<TypeExpr>),
     /// Thunked computation: `thunk T`
     Thunk(Box<TypeExpr>),
+    /// Computation with the caps it performs: the `T / { Yield[A] }` of
+    /// `thunk T / { Yield[A] }`
+    Comp { ty: Box<TypeExpr>, cap: Vec<CapEntry> },
     /// Capability type: `Add[Number]`, or bare `IO`
     Cap { name: String, type_args: Vec<TypeExpr> },
     /// Function type in value position: `fn(T, U): R` or `fn(T): R / { IO }`
@@ -74,6 +77,11 @@ impl TypeExpr {
             }
             TypeExpr::Produce(inner) => format!("produce {}", inner.display()),
             TypeExpr::Thunk(inner) => format!("thunk {}", inner.display()),
+            TypeExpr::Comp { ty, cap } => format!(
+                "{} / {{{}}}",
+                ty.display(),
+                cap.iter().map(|e| e.display()).collect::<Vec<_>>().join(", ")
+            ),
             TypeExpr::Cap { name, type_args } if !type_args.is_empty() => {
                 let args_str = type_args.iter().map(|a| a.display()).collect::<Vec<_>>().join(", ");
                 format!("{name}[{args_str}]")
@@ -99,6 +107,7 @@ impl TypeExpr {
             TypeExpr::Named(n) | TypeExpr::Var(n) => n,
             TypeExpr::App { head, .. } => head,
             TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => inner.head_name(),
+            TypeExpr::Comp { ty, .. } => ty.head_name(),
             TypeExpr::Cap { name, .. } => name,
             TypeExpr::Fn { ret, .. } => ret.head_name(),
             TypeExpr::Mu { body, .. } => body.head_name(),
@@ -113,6 +122,7 @@ impl TypeExpr {
                 head == target || args.iter().any(|a| a.references_name(target))
             }
             TypeExpr::Produce(inner) | TypeExpr::Thunk(inner) => inner.references_name(target),
+            TypeExpr::Comp { ty, .. } => ty.references_name(target),
             TypeExpr::Cap { name, type_args } => {
                 name == target || type_args.iter().any(|a| a.references_name(target))
             }
@@ -157,7 +167,11 @@ impl TypeExpr {
     pub fn cap_mangled_param(&self) -> String {
         match self {
             TypeExpr::Cap { name, type_args } if !type_args.is_empty() => {
-                let args = type_args.iter().map(|a| a.display()).collect::<Vec<_>>().join("_");
+                let args = type_args
+                    .iter()
+                    .map(|a| mangle_type_repr(&a.display()))
+                    .collect::<Vec<_>>()
+                    .join("_");
                 format!("__cap_{name}_{args}")
             }
             TypeExpr::Cap { name, .. } => format!("__cap_{name}"),
@@ -217,6 +231,15 @@ fn parse_type_expr_full(text: &str) -> TypeExpr {
         }
         // Only treat as keyword if followed by whitespace (already stripped) or known prefix
         if rest.starts_with(|c: char| c.is_uppercase() || c.is_lowercase()) {
+            // `thunk T / { caps }`; a `fn` type keeps its own cap row.
+            if !rest.starts_with("fn") {
+                if let Some((ty, cap)) = rest.split_once('/') {
+                    return TypeExpr::Thunk(Box::new(TypeExpr::Comp {
+                        ty: Box::new(parse_type_expr_full(ty)),
+                        cap: parse_cap_ref(cap),
+                    }));
+                }
+            }
             return TypeExpr::Thunk(Box::new(parse_type_expr_full(rest)));
         }
         // fallthrough: treat "thunk" as a type name part
@@ -332,11 +355,8 @@ pub fn parse_cap_ref(repr: &str) -> CapRef {
         return vec![];
     }
     let mut entries = Vec::new();
-    for part in s.split(',') {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
-        }
+    for part in split_type_args(s) {
+        let part = part.as_str();
         if part == ".." {
             entries.push(CapEntry::Infer);
         } else if let Some(var) = part.strip_prefix("..") {
@@ -348,6 +368,14 @@ pub fn parse_cap_ref(repr: &str) -> CapRef {
                     type_args: vec![parsed],
                 }));
             }
+        } else if let Some((name, ty)) = part.strip_suffix(']').and_then(|p| p.split_once('[')) {
+            // `Throw[String]`, as caps with `Self` are displayed
+            if let Some(parsed) = TypeExpr::parse(ty.trim()) {
+                entries.push(CapEntry::Cap(TypeExpr::Cap {
+                    name: name.trim().to_owned(),
+                    type_args: vec![parsed],
+                }));
+            }
         } else {
             entries.push(CapEntry::Cap(TypeExpr::Cap {
                 name: part.to_owned(),
@@ -358,6 +386,18 @@ pub fn parse_cap_ref(repr: &str) -> CapRef {
     entries
 }
 
+/// Identifier form of a type repr, for runtime cap names: `List[Number]`
+/// becomes `List_Number`, so `Eq[List[Number]]` is `__cap_Eq_List_Number`.
+pub fn mangle_type_repr(repr: &str) -> String {
+    repr.chars()
+        .filter_map(|c| match c {
+            '[' | ',' => Some('_'),
+            ']' | ' ' => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
 pub fn cap_ref_mangled_params(cap: &[CapEntry]) -> Vec<String> {
     cap.iter().filter_map(|e| e.cap_mangled_param()).collect()
 }
@@ -682,6 +722,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn type_expr_parse_thunk_with_caps() {
+        let ty = TypeExpr::parse("thunk Unit / { Yield[A] }").unwrap();
+        assert_eq!(
+            ty,
+            TypeExpr::Thunk(Box::new(TypeExpr::Comp {
+                ty: Box::new(TypeExpr::Named("Unit".into())),
+                cap: vec![CapEntry::Cap(TypeExpr::Cap {
+                    name: "Yield".into(),
+                    type_args: vec![TypeExpr::Named("A".into())],
+                })],
+            }))
+        );
+        assert_eq!(ty.display(), "thunk Unit / {Yield[A]}");
+        assert_eq!(TypeExpr::parse(&ty.display()), Some(ty));
+    }
+
     #[test]
     fn type_expr_display() {
         let ty = TypeExpr::App {
@@ -740,6 +797,16 @@ mod tests {
             parse_cap_ref("{ Add for String, IO }"),
             vec![typed_cap("Add", "String"), bare_cap("IO")]
         );
+        assert_eq!(
+            parse_cap_ref("{ Throw [ Map [ String , Number ] ], IO }"),
+            vec![
+                CapEntry::Cap(TypeExpr::Cap {
+                    name: "Throw".to_owned(),
+                    type_args: vec![TypeExpr::parse("Map[String, Number]").unwrap()],
+                }),
+                bare_cap("IO"),
+            ]
+        );
         assert_eq!(
             parse_cap_ref("{ Add for Number, Add for String, IO }"),
             vec![
@@ -769,6 +836,14 @@ mod tests {
     fn cap_entry_mangled_param() {
         assert_eq!(bare_cap("IO").cap_mangled_param(), Some("__cap_IO".into()));
         assert_eq!(typed_cap("Add", "Number").cap_mangled_param(), Some("__cap_Add_Number".into()));
+        let applied = CapEntry::Cap(TypeExpr::Cap {
+            name: "Eq".into(),
+            type_args: vec![TypeExpr::parse("Map[String, List[Number]]").unwrap()],
+        });
+        assert_eq!(
+            applied.cap_mangled_param(),
+            Some("__cap_Eq_Map_String_List_Number".into())
+        );
         assert_eq!(CapEntry::Infer.cap_mangled_param(), None);
         assert_eq!(CapEntry::Spread("c".into()).cap_mangled_param(), None);
     }